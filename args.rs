@@ -2,17 +2,173 @@ use path_clean::clean;
 use std::env::args;
 use std::{io, path::PathBuf};
 
+use protos_ts::{
+    Emit, EnumStyle, FieldCase, FileCase, ImportExtension, ImportStyle, Int64Type, Runtime,
+    WellKnownTypes,
+};
+
 #[derive(Debug)]
 pub(super) struct CliArguments {
     pub proto_folder_path: PathBuf,
+    /// Folders of `-I` / `--proto-path` options, searched in order for imported files.
+    pub include_paths: Vec<PathBuf>,
     pub out_folder_path: PathBuf,
+    pub single_file: bool,
+    /// Whether the whole output is a single `index.ts` with a namespace per package and message.
+    pub index_file: bool,
+    pub summary_json: bool,
+    pub emit_default_values: bool,
+    pub emit_types: bool,
+    pub emit_encode: bool,
+    pub emit_decode: bool,
+    pub emit_json: bool,
+    pub emit_guards: bool,
+    pub emit_equals: bool,
+    /// `--equals-presence`: missing fields are not equal to fields set to their defaults.
+    pub equals_presence: bool,
+    pub emit_enum_names: bool,
+    /// `--has-fields`: decoded messages tell whether their `optional` fields are set.
+    pub emit_has_fields: bool,
+    pub emit_manifest: bool,
+    /// Whether services also get the interface of the `@grpc/grpc-js` server handlers.
+    pub emit_grpc: bool,
+    /// `--runtime embedded`, or `--standalone-runtime`, emits `runtime.ts` instead of importing protobufjs.
+    pub runtime: Runtime,
+    pub well_known_types: WellKnownTypes,
+    pub import_extension: ImportExtension,
+    pub import_style: ImportStyle,
+    pub enum_style: EnumStyle,
+    pub field_case: FieldCase,
+    pub file_case: FileCase,
+    pub int64_type: Int64Type,
+    /// `--keep-unknown-fields`: unknown fields survive decoding and encoding again.
+    pub keep_unknown_fields: bool,
+    /// `--readonly-types`: properties of decoded messages are `readonly`.
+    pub readonly_types: bool,
+    /// Whether only `.d.ts` files with the types are generated.
+    pub declarations_only: bool,
+    pub emit: Emit,
+    pub prune: bool,
+    pub error_format: ErrorFormat,
+    /// File with the `[emit]` and `[renames]` tables.
+    pub config_path: Option<PathBuf>,
+    /// Whether the proto folder is watched and recompiled on changes after the first run.
+    pub watch: bool,
+    /// `--dry-run`: the tree of the output is printed and nothing is written.
+    pub dry_run: bool,
+    /// `--help`: the usage is printed instead of compiling.
+    pub help: bool,
+}
+
+/// Printed by `--help`.
+pub(super) const HELP: &str = "\
+Usage: protos-ts <proto folder or file> --out <folder> [options]
+
+Output:
+  --out <folder>               folder the TypeScript files are written to
+  -I, --proto-path <folder>    folder searched for imported files, can be repeated
+  --layout folder|file|index   a folder per message, a file per proto file or a single index.ts
+  --single-file                same as --layout file
+  --index-file                 same as --layout index
+  --emit ts|dts|both           .ts modules, .d.ts files or both
+  --declarations-only          .d.ts files with the types only
+  --prune                      delete the files in the output folder that are not generated anymore
+  --manifest                   write a manifest.ts with the fields and enum values of every package
+  --config <file>              TOML file with the [emit] and [renames] tables
+
+Generated code:
+  --no-types, --no-encode, --no-decode, --no-json
+                               skip types.ts, encode.ts, decode.ts or json.ts
+  --emit-default-values        toJSON writes the fields set to their default values
+  --guards                     emit type guards
+  --equals                     emit equality functions
+  --equals-presence            equality functions compare the presence of optional fields
+  --enum-names                 emit the names of enum values
+  --has-fields                 decoded messages have hasName properties for optional fields
+  --keep-unknown-fields        keep the unknown fields of decoded messages
+  --readonly-types             properties of decoded messages are readonly
+  --grpc                       also emit the @grpc/grpc-js server handler interfaces of services
+  --runtime protobufjs|embedded
+                               import protobufjs or emit a runtime.ts without dependencies
+  --standalone-runtime         same as --runtime embedded
+  --wkt raw|idiomatic          well-known types as other messages or as native types
+  --int64-type long|bigint     type of 64-bit integers
+
+Names:
+  --field-case camelCase|preserve|proto|snake_case
+                               properties of fields, camelCase by default: user_id is userId,
+                               or the json_name option of the field
+  --property-names json|proto  same as --field-case camelCase or --field-case proto
+  --file-case keep|kebab|snake|camel|pascal
+                               folders and files named after proto names
+  --enum-style enum|union|const-object
+  --import-style plain|type|inline
+  --import-ext none|js|ts      extension of import specifiers
+
+Running:
+  --watch                      recompile when the proto files change
+  --dry-run                    print the changes instead of writing them
+  --summary-json               print a JSON summary of the run to stdout
+  --error-format text|json     format of the errors printed to stderr
+  -h, --help                   print this help
+";
+
+/// How errors are printed to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ErrorFormat {
+    Text,
+    /// A JSON array of `{ "message", "file", "line", "column" }` objects.
+    Json,
+}
+
+impl std::fmt::Display for ErrorFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ErrorFormat::Text => write!(f, "text"),
+            ErrorFormat::Json => write!(f, "json"),
+        }
+    }
 }
 
 impl Default for CliArguments {
     fn default() -> Self {
         Self {
             proto_folder_path: PathBuf::from("."),
+            include_paths: Vec::new(),
             out_folder_path: PathBuf::from("./out"),
+            single_file: false,
+            index_file: false,
+            summary_json: false,
+            emit_default_values: false,
+            emit_types: true,
+            emit_encode: true,
+            emit_decode: true,
+            emit_json: true,
+            emit_guards: false,
+            emit_equals: false,
+            equals_presence: false,
+            emit_enum_names: false,
+            emit_has_fields: false,
+            emit_manifest: false,
+            emit_grpc: false,
+            runtime: Runtime::Protobufjs,
+            well_known_types: WellKnownTypes::Idiomatic,
+            import_extension: ImportExtension::None,
+            import_style: ImportStyle::Plain,
+            enum_style: EnumStyle::Enum,
+            field_case: FieldCase::CamelCase,
+            file_case: FileCase::Keep,
+            int64_type: Int64Type::Long,
+            keep_unknown_fields: false,
+            readonly_types: false,
+            declarations_only: false,
+            emit: Emit::Ts,
+            prune: false,
+            error_format: ErrorFormat::Text,
+            config_path: None,
+            watch: false,
+            dry_run: false,
+            help: false,
         }
     }
 }
@@ -21,15 +177,64 @@ impl std::fmt::Display for CliArguments {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "proto_folder_path: {:?}\nout_folder_path: {:?}",
-            self.proto_folder_path, self.out_folder_path
+            "proto_folder_path: {:?}\ninclude_paths: {:?}\nout_folder_path: {:?}\nsingle_file: {}\nindex_file: {}\nsummary_json: {}\nemit_default_values: {}\nemit_types: {}\nemit_encode: {}\nemit_decode: {}\nemit_json: {}\nemit_guards: {}\nemit_equals: {}\nequals_presence: {}\nemit_enum_names: {}\nemit_has_fields: {}\nemit_manifest: {}\nemit_grpc: {}\nruntime: {:?}\nwell_known_types: {:?}\nimport_extension: {:?}\nimport_style: {:?}\nenum_style: {:?}\nfield_case: {:?}\nfile_case: {:?}\nint64_type: {:?}\nkeep_unknown_fields: {}\nreadonly_types: {}\ndeclarations_only: {}\nemit: {:?}\nprune: {}\nerror_format: {}\nconfig_path: {:?}\nwatch: {}\ndry_run: {}\nhelp: {}",
+            self.proto_folder_path,
+            self.include_paths,
+            self.out_folder_path,
+            self.single_file,
+            self.index_file,
+            self.summary_json,
+            self.emit_default_values,
+            self.emit_types,
+            self.emit_encode,
+            self.emit_decode,
+            self.emit_json,
+            self.emit_guards,
+            self.emit_equals,
+            self.equals_presence,
+            self.emit_enum_names,
+            self.emit_has_fields,
+            self.emit_manifest,
+            self.emit_grpc,
+            self.runtime,
+            self.well_known_types,
+            self.import_extension,
+            self.import_style,
+            self.enum_style,
+            self.field_case,
+            self.file_case,
+            self.int64_type,
+            self.keep_unknown_fields,
+            self.readonly_types,
+            self.declarations_only,
+            self.emit,
+            self.prune,
+            self.error_format,
+            self.config_path,
+            self.watch,
+            self.dry_run,
+            self.help
         )
     }
 }
 
 enum ParseState {
     ProtoFolderPath,
+    IncludePath,
     OutFolderPath,
+    ErrorFormat,
+    ConfigPath,
+    WellKnownTypes,
+    ImportExtension,
+    ImportStyle,
+    EnumStyle,
+    FieldCase,
+    PropertyNames,
+    FileCase,
+    Int64Type,
+    Emit,
+    Layout,
+    Runtime,
 }
 impl Default for ParseState {
     fn default() -> Self {
@@ -39,24 +244,362 @@ impl Default for ParseState {
 use ParseState::*;
 
 /// It takes first argument as the relative or absolute path
-/// to the folder containing the proto files.
+/// to the folder containing the proto files, or to a single proto file.
 /// It returns absolute path to the folder.
 pub(crate) fn get_proto_folder_path() -> io::Result<CliArguments> {
     let mut res = CliArguments::default();
     let mut state = ParseState::default();
     for arg in args() {
+        if arg == "--help" || arg == "-h" {
+            res.help = true;
+            continue;
+        }
         if arg == "--out" {
             state = ParseState::OutFolderPath;
             continue;
         }
+        if arg == "-I" || arg == "--proto-path" {
+            state = ParseState::IncludePath;
+            continue;
+        }
+        if arg == "--single-file" {
+            res.single_file = true;
+            continue;
+        }
+        if arg == "--index-file" {
+            res.index_file = true;
+            continue;
+        }
+        if arg == "--summary-json" {
+            res.summary_json = true;
+            continue;
+        }
+        if arg == "--emit-default-values" {
+            res.emit_default_values = true;
+            continue;
+        }
+        if arg == "--no-types" {
+            res.emit_types = false;
+            continue;
+        }
+        if arg == "--no-encode" {
+            res.emit_encode = false;
+            continue;
+        }
+        if arg == "--no-decode" {
+            res.emit_decode = false;
+            continue;
+        }
+        if arg == "--no-json" {
+            res.emit_json = false;
+            continue;
+        }
+        if arg == "--guards" {
+            res.emit_guards = true;
+            continue;
+        }
+        if arg == "--equals" {
+            res.emit_equals = true;
+            continue;
+        }
+        if arg == "--equals-presence" {
+            res.equals_presence = true;
+            continue;
+        }
+        if arg == "--keep-unknown-fields" {
+            res.keep_unknown_fields = true;
+            continue;
+        }
+        if arg == "--readonly-types" {
+            res.readonly_types = true;
+            continue;
+        }
+        if arg == "--enum-names" {
+            res.emit_enum_names = true;
+            continue;
+        }
+        if arg == "--has-fields" {
+            res.emit_has_fields = true;
+            continue;
+        }
+        if arg == "--manifest" {
+            res.emit_manifest = true;
+            continue;
+        }
+        if arg == "--grpc" {
+            res.emit_grpc = true;
+            continue;
+        }
+        if arg == "--standalone-runtime" {
+            res.runtime = Runtime::Standalone;
+            continue;
+        }
+        if arg == "--watch" {
+            res.watch = true;
+            continue;
+        }
+        if arg == "--dry-run" {
+            res.dry_run = true;
+            continue;
+        }
+        if arg == "--declarations-only" {
+            res.declarations_only = true;
+            continue;
+        }
+        if arg == "--prune" {
+            res.prune = true;
+            continue;
+        }
+        if arg == "--error-format" {
+            state = ParseState::ErrorFormat;
+            continue;
+        }
+        if arg == "--wkt" {
+            state = ParseState::WellKnownTypes;
+            continue;
+        }
+        if arg == "--import-ext" {
+            state = ParseState::ImportExtension;
+            continue;
+        }
+        if arg == "--import-style" {
+            state = ParseState::ImportStyle;
+            continue;
+        }
+        if arg == "--enum-style" {
+            state = ParseState::EnumStyle;
+            continue;
+        }
+        if arg == "--field-case" {
+            state = ParseState::FieldCase;
+            continue;
+        }
+        if arg == "--property-names" {
+            state = ParseState::PropertyNames;
+            continue;
+        }
+        if arg == "--file-case" {
+            state = ParseState::FileCase;
+            continue;
+        }
+        if arg == "--int64-type" {
+            state = ParseState::Int64Type;
+            continue;
+        }
+        if arg == "--emit" {
+            state = ParseState::Emit;
+            continue;
+        }
+        if arg == "--runtime" {
+            state = ParseState::Runtime;
+            continue;
+        }
+        if arg == "--layout" {
+            state = ParseState::Layout;
+            continue;
+        }
+        if arg == "--config" {
+            state = ParseState::ConfigPath;
+            continue;
+        }
         match state {
             ProtoFolderPath => {
                 res.proto_folder_path = PathBuf::from(clean(&arg));
             }
+            IncludePath => {
+                res.include_paths.push(PathBuf::from(clean(&arg)));
+                state = ParseState::default();
+            }
             OutFolderPath => {
                 res.out_folder_path = PathBuf::from(clean(&arg));
                 state = ParseState::default();
             }
+            ParseState::ErrorFormat => {
+                res.error_format = match arg.as_str() {
+                    "text" => ErrorFormat::Text,
+                    "json" => ErrorFormat::Json,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unknown error format {}, expected text or json", arg),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ConfigPath => {
+                res.config_path = Some(PathBuf::from(clean(&arg)));
+                state = ParseState::default();
+            }
+            ParseState::WellKnownTypes => {
+                res.well_known_types = match arg.as_str() {
+                    "raw" => WellKnownTypes::Raw,
+                    "idiomatic" => WellKnownTypes::Idiomatic,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "Unknown well-known types mode {}, expected raw or idiomatic",
+                                arg
+                            ),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::ImportExtension => {
+                res.import_extension = match arg.as_str() {
+                    "js" => ImportExtension::Js,
+                    "ts" => ImportExtension::Ts,
+                    "none" => ImportExtension::None,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unknown import extension {}, expected js, ts or none", arg),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::ImportStyle => {
+                res.import_style = match arg.as_str() {
+                    "plain" => ImportStyle::Plain,
+                    "type" => ImportStyle::Type,
+                    "inline" => ImportStyle::Inline,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "Unknown import style {}, expected plain, type or inline",
+                                arg
+                            ),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::EnumStyle => {
+                res.enum_style = match arg.as_str() {
+                    "enum" => EnumStyle::Enum,
+                    "union" => EnumStyle::Union,
+                    "const-object" => EnumStyle::ConstObject,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "Unknown enum style {}, expected enum, union or const-object",
+                                arg
+                            ),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::FieldCase => {
+                res.field_case = match arg.as_str() {
+                    "preserve" => FieldCase::Preserve,
+                    "proto" => FieldCase::Proto,
+                    "camelCase" => FieldCase::CamelCase,
+                    "snake_case" => FieldCase::SnakeCase,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "Unknown field case {}, expected preserve, proto, camelCase or snake_case",
+                                arg
+                            ),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::PropertyNames => {
+                // `--property-names` names properties after the JSON names or the proto names of fields
+                res.field_case = match arg.as_str() {
+                    "json" => FieldCase::CamelCase,
+                    "proto" => FieldCase::Proto,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unknown property names {}, expected json or proto", arg),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::FileCase => {
+                res.file_case = match arg.as_str() {
+                    "keep" => FileCase::Keep,
+                    "kebab" => FileCase::Kebab,
+                    "snake" => FileCase::Snake,
+                    "camel" => FileCase::Camel,
+                    "pascal" => FileCase::Pascal,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!(
+                                "Unknown file case {}, expected keep, kebab, snake, camel, pascal",
+                                arg
+                            ),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::Int64Type => {
+                res.int64_type = match arg.as_str() {
+                    "long" => Int64Type::Long,
+                    "bigint" => Int64Type::BigInt,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unknown int64 type {}, expected long, bigint", arg),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::Emit => {
+                res.emit = match arg.as_str() {
+                    "ts" => Emit::Ts,
+                    "dts" => Emit::Dts,
+                    "both" => Emit::Both,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unknown emit mode {}, expected ts, dts or both", arg),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::Runtime => {
+                res.runtime = match arg.as_str() {
+                    "protobufjs" => Runtime::Protobufjs,
+                    "embedded" => Runtime::Standalone,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unknown runtime {}, expected protobufjs or embedded", arg),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
+            ParseState::Layout => {
+                (res.single_file, res.index_file) = match arg.as_str() {
+                    "folder" => (false, false),
+                    "file" => (true, false),
+                    "index" => (false, true),
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("Unknown layout {}, expected folder, file or index", arg),
+                        ))
+                    }
+                };
+                state = ParseState::default();
+            }
         }
     }
 
@@ -0,0 +1,79 @@
+//! Compares compilation of 50 generated proto files
+//! on a single thread and on the whole rayon thread pool.
+//!
+//! `cargo bench --features rayon`
+
+use std::{fmt::Write, fs, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use protos_ts::{compile_in_memory, CompileOptions};
+
+const FILES_COUNT: usize = 50;
+const MESSAGES_PER_FILE: usize = 20;
+
+/// Writes proto files with messages referencing each other into a temporary folder.
+fn write_proto_files() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("protos-ts-bench-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for file_index in 0..FILES_COUNT {
+        let mut content = String::new();
+        writeln!(content, "syntax = \"proto3\";").unwrap();
+        writeln!(content, "package bench.p{};", file_index).unwrap();
+        if file_index > 0 {
+            writeln!(content, "import \"f{}.proto\";", file_index - 1).unwrap();
+        }
+        for message_index in 0..MESSAGES_PER_FILE {
+            writeln!(content, "message M{} {{", message_index).unwrap();
+            writeln!(content, "  int64 id = 1;").unwrap();
+            writeln!(content, "  string name = 2;").unwrap();
+            writeln!(content, "  repeated double values = 3;").unwrap();
+            writeln!(content, "  map<string, int32> counts = 4;").unwrap();
+            writeln!(content, "  enum Kind {{ KIND_UNKNOWN = 0; KIND_A = 1; }}").unwrap();
+            writeln!(content, "  Kind kind = 5;").unwrap();
+            if message_index > 0 {
+                writeln!(content, "  M{} previous = 6;", message_index - 1).unwrap();
+            }
+            if file_index > 0 {
+                writeln!(
+                    content,
+                    "  bench.p{}.M{} imported = 7;",
+                    file_index - 1,
+                    message_index
+                )
+                .unwrap();
+            }
+            writeln!(content, "}}").unwrap();
+        }
+        fs::write(dir.join(format!("f{}.proto", file_index)), content).unwrap();
+    }
+    dir
+}
+
+fn bench_parallel(c: &mut Criterion) {
+    let dir = write_proto_files();
+    let options = CompileOptions {
+        proto_dirs: vec![dir.clone()],
+        out_dir: dir.join("out"),
+        ..Default::default()
+    };
+    let sequential = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("compile 50 files");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| sequential.install(|| compile_in_memory(&options).unwrap()))
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| compile_in_memory(&options).unwrap())
+    });
+    group.finish();
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+criterion_group!(benches, bench_parallel);
+criterion_main!(benches);
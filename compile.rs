@@ -0,0 +1,604 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::proto::{
+    compiler::ts::{
+        ast::Folder,
+        commit_folder::{commit_folder, pending_changes, render_folder, PendingChange},
+        options::{
+            Emit, EnumStyle, FieldCase, FileCase, ImportExtension, ImportStyle, Int64Type, Layout,
+            Options, Runtime, WellKnownTypes,
+        },
+        renames::Rename,
+        scope_to_folder::root_scope_to_folder,
+    },
+    error::ProtoError,
+    folder::{read_include_folders, read_proto_folder},
+    package::read_root_scope,
+    warning::Warning,
+};
+
+/// Options of a single compilation.
+///
+/// ```no_run
+/// let options = protos_ts::CompileOptions {
+///     proto_dirs: vec!["./proto".into()],
+///     out_dir: "./src/generated".into(),
+///     ..Default::default()
+/// };
+/// let report = protos_ts::compile(&options)?;
+/// println!("wrote {} files", report.files_written);
+/// # Ok::<(), protos_ts::ProtoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// Folders that are searched recursively for `.proto` files.
+    /// A path of a single `.proto` file compiles the file and the files it imports.
+    pub proto_dirs: Vec<PathBuf>,
+    /// Folders searched in order for imported files that `proto_dirs` do not have, like `protoc -I`.
+    /// Only the imported files of them are compiled.
+    pub include_dirs: Vec<PathBuf>,
+    /// Folder the TypeScript files are written to.
+    pub out_dir: PathBuf,
+    pub layout: Layout,
+    /// Whether `toJSON` keeps fields set to their default values.
+    pub emit_default_values: bool,
+    /// Whether `types.ts` is generated.
+    pub emit_types: bool,
+    /// Whether `encode.ts` is generated.
+    pub emit_encode: bool,
+    /// Whether `decode.ts` is generated.
+    pub emit_decode: bool,
+    /// Whether `json.ts` is generated.
+    pub emit_json: bool,
+    /// Whether `is.ts` with the type guard of the message is generated.
+    pub emit_guards: bool,
+    /// Whether `equals.ts` comparing two messages field by field is generated.
+    pub emit_equals: bool,
+    /// Whether `equals` tells missing fields from fields set to their defaults.
+    pub equals_presence: bool,
+    /// Whether TypeScript enums are followed by an object mapping wire numbers to the names of the members.
+    pub emit_enum_names: bool,
+    /// Whether decoded messages have a `hasName: boolean` property for every `optional` field `name`.
+    pub emit_has_fields: bool,
+    /// Whether `manifest.ts` is generated per package.
+    pub emit_manifest: bool,
+    /// Whether services also get the interface of the `@grpc/grpc-js` server handlers.
+    pub emit_grpc: bool,
+    pub runtime: Runtime,
+    /// How fields typed with `google.protobuf.Timestamp`, wrappers and other well-known messages are generated.
+    pub well_known_types: WellKnownTypes,
+    /// Replacement TypeScript names by fully qualified proto names
+    /// of messages and enums (`pkg.Message`) and fields (`pkg.Message.field`).
+    pub renames: HashMap<String, Rename>,
+    /// Whether files of `out_dir` that are not generated anymore are removed.
+    pub prune: bool,
+    /// Extension appended to relative import specifiers, `.js` for ES modules.
+    pub import_extension: ImportExtension,
+    /// Whether bindings used only as types are imported with `import type`.
+    pub import_style: ImportStyle,
+    /// Whether enums are TypeScript enums, unions of the names of the members or const objects.
+    pub enum_style: EnumStyle,
+    /// Whether properties of fields keep the proto names or are camelCased or snake_cased.
+    pub field_case: FieldCase,
+    /// Whether folders and files named after proto names keep the names or are kebab-, snake-, camel- or PascalCased.
+    pub file_case: FileCase,
+    /// Whether 64-bit integers are typed as `util.Long` or as `bigint`.
+    pub int64_type: Int64Type,
+    /// Whether decoded messages keep the fields unknown to the schema, and encoding writes them back.
+    pub keep_unknown_fields: bool,
+    /// Whether the properties of decoded messages are `readonly`, with readonly arrays and maps.
+    pub readonly_types: bool,
+    /// Whether only `.d.ts` files with the interfaces and the enums are generated.
+    pub declarations_only: bool,
+    /// Whether `.ts` modules, `.d.ts` declarations of them or both are written.
+    pub emit: Emit,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        let options = Options::default();
+        Self {
+            proto_dirs: vec![PathBuf::from(".")],
+            include_dirs: Vec::new(),
+            out_dir: PathBuf::from("./out"),
+            layout: options.layout,
+            emit_default_values: options.emit_default_values,
+            emit_types: options.emit_types,
+            emit_encode: options.emit_encode,
+            emit_decode: options.emit_decode,
+            emit_json: options.emit_json,
+            emit_guards: options.emit_guards,
+            emit_equals: options.emit_equals,
+            equals_presence: options.equals_presence,
+            emit_enum_names: options.emit_enum_names,
+            emit_has_fields: options.emit_has_fields,
+            emit_manifest: options.emit_manifest,
+            emit_grpc: options.emit_grpc,
+            runtime: options.runtime,
+            well_known_types: options.well_known_types,
+            renames: HashMap::new(),
+            prune: false,
+            import_extension: options.import_extension,
+            import_style: options.import_style,
+            enum_style: options.enum_style,
+            field_case: options.field_case,
+            file_case: options.file_case,
+            int64_type: options.int64_type,
+            keep_unknown_fields: options.keep_unknown_fields,
+            readonly_types: options.readonly_types,
+            declarations_only: options.declarations_only,
+            emit: options.emit,
+        }
+    }
+}
+
+impl From<&CompileOptions> for Options {
+    fn from(options: &CompileOptions) -> Self {
+        Self {
+            layout: options.layout,
+            emit_default_values: options.emit_default_values,
+            emit_types: options.emit_types,
+            emit_encode: options.emit_encode,
+            emit_decode: options.emit_decode,
+            emit_json: options.emit_json,
+            emit_guards: options.emit_guards,
+            emit_equals: options.emit_equals,
+            equals_presence: options.equals_presence,
+            emit_enum_names: options.emit_enum_names,
+            emit_has_fields: options.emit_has_fields,
+            emit_manifest: options.emit_manifest,
+            emit_grpc: options.emit_grpc,
+            runtime: options.runtime,
+            well_known_types: options.well_known_types,
+            renames: Arc::new(options.renames.clone()),
+            import_extension: options.import_extension,
+            import_style: options.import_style,
+            enum_style: options.enum_style,
+            field_case: options.field_case,
+            file_case: options.file_case,
+            int64_type: options.int64_type,
+            keep_unknown_fields: options.keep_unknown_fields,
+            readonly_types: options.readonly_types,
+            declarations_only: options.declarations_only,
+            emit: options.emit,
+        }
+    }
+}
+
+/// Stages of the compilation, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Stage {
+    #[default]
+    Read,
+    Parse,
+    Compile,
+    Write,
+}
+
+/// Time spent in every stage of the compilation.
+#[derive(Debug, Clone, Default)]
+pub struct StageDurations {
+    pub read: Duration,
+    pub parse: Duration,
+    pub compile: Duration,
+    pub write: Duration,
+}
+
+/// What a compilation did, filled in stage by stage.
+#[derive(Debug, Clone, Default)]
+pub struct CompileReport {
+    /// The last stage that was started. When the compilation fails,
+    /// it is the stage that failed.
+    pub stage: Stage,
+    pub files_read: usize,
+    pub files_parsed: usize,
+    pub files_generated: usize,
+    /// Files created or rewritten with a new content.
+    pub files_written: usize,
+    /// Files left untouched, because their content did not change.
+    pub files_unchanged: usize,
+    /// Files removed from `out_dir`, because they are not generated anymore.
+    pub files_pruned: usize,
+    pub warnings: Vec<Warning>,
+    pub durations: StageDurations,
+}
+
+/// What a dry run found, nothing of it is written.
+#[derive(Debug, Clone, Default)]
+pub struct DryRun {
+    /// Folders and files of the output, under `out_dir`.
+    pub tree: String,
+    /// Changes writing the output would make to the files of `out_dir`.
+    pub changes: Vec<PendingChange>,
+}
+
+/// Compiles the proto files of `options.proto_dirs` and writes
+/// the TypeScript files into `options.out_dir`.
+pub fn compile(options: &CompileOptions) -> Result<CompileReport, ProtoError> {
+    let mut report = CompileReport::default();
+    compile_with_report(options, &mut report)?;
+    Ok(report)
+}
+
+/// Same as `compile`, but the report is available even when the compilation fails.
+pub fn compile_with_report(
+    options: &CompileOptions,
+    report: &mut CompileReport,
+) -> Result<(), ProtoError> {
+    let folder = compile_folder(options, report)?;
+
+    report.stage = Stage::Write;
+    let start = Instant::now();
+    let stats = commit_folder(&options.out_dir, &folder, options.prune)?;
+    report.files_written = stats.written;
+    report.files_unchanged = stats.unchanged;
+    report.files_pruned = stats.pruned;
+    report.durations.write = start.elapsed();
+
+    Ok(())
+}
+
+/// Same as `compile_with_report`, but nothing is written into `options.out_dir`.
+/// Returns the tree of the folders and files the compilation would write
+/// and how they differ from the files of `out_dir`.
+pub fn compile_dry_run(
+    options: &CompileOptions,
+    report: &mut CompileReport,
+) -> Result<DryRun, ProtoError> {
+    let mut folder = compile_folder(options, report)?;
+    folder.name = options.out_dir.to_string_lossy().into();
+    Ok(DryRun {
+        tree: folder.display_tree(),
+        changes: pending_changes(&options.out_dir, &folder, options.prune),
+    })
+}
+
+/// Compiles the proto files of `options.proto_dirs` without touching `options.out_dir`.
+/// Returns the content of every file, with paths relative to `out_dir`.
+pub fn compile_in_memory(options: &CompileOptions) -> Result<Vec<(PathBuf, String)>, ProtoError> {
+    let folder = compile_folder(options, &mut CompileReport::default())?;
+    Ok(render_folder(&folder))
+}
+
+fn compile_folder(
+    options: &CompileOptions,
+    report: &mut CompileReport,
+) -> Result<Folder, ProtoError> {
+    report.stage = Stage::Read;
+    let start = Instant::now();
+    let mut proto_folders = Vec::new();
+    for proto_dir in &options.proto_dirs {
+        proto_folders.push(read_proto_folder(proto_dir.clone()).map_err(ProtoError::IOError)?);
+    }
+    let include_folders =
+        read_include_folders(&proto_folders, &options.include_dirs).map_err(ProtoError::IOError)?;
+    proto_folders.extend(include_folders);
+    let files_count = proto_folders.iter().map(|f| f.files.len()).sum();
+    report.files_read = files_count;
+    report.durations.read = start.elapsed();
+
+    report.stage = Stage::Parse;
+    let start = Instant::now();
+    let root_scope = read_root_scope(&proto_folders)?;
+    report.files_parsed = files_count;
+    report.durations.parse = start.elapsed();
+    for cycle in &root_scope.message_cycles {
+        report.warnings.push(Warning::CircularReferences {
+            messages: cycle
+                .iter()
+                .map(|&id| root_scope.get_full_name(id).unwrap().to_string())
+                .collect(),
+        });
+    }
+
+    report.stage = Stage::Compile;
+    let start = Instant::now();
+    let folder_name = options
+        .out_dir
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let folder = root_scope_to_folder(&root_scope, &options.into(), folder_name)?;
+    report.files_generated = folder.files_count();
+    report.durations.compile = start.elapsed();
+
+    Ok(folder)
+}
+
+#[cfg(test)]
+mod test_compile {
+    use std::{fs, time::SystemTime};
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "protos-ts-{}-{}-{}",
+            name,
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn it_compiles_in_memory_the_same_files_as_on_disk() {
+        let dir = temp_dir("compile");
+        let proto_dir = dir.join("proto");
+        fs::create_dir_all(proto_dir.join("pkg")).unwrap();
+        fs::write(
+            proto_dir.join("pkg/a.proto"),
+            "syntax = \"proto3\";\npackage pkg;\nmessage A { string s = 1; }\n",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            proto_dirs: vec![proto_dir],
+            out_dir: dir.join("nested/out"),
+            layout: Layout::File,
+            ..Default::default()
+        };
+
+        let files = compile_in_memory(&options).unwrap();
+        assert!(!options.out_dir.exists());
+        assert_eq!(
+            files.iter().map(|(path, _)| path).collect::<Vec<_>>(),
+            vec![&PathBuf::from("pkg/a.ts")]
+        );
+
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        let report = compile(&options).unwrap();
+        assert_eq!(report.stage, Stage::Write);
+        assert_eq!(report.files_read, 1);
+        assert_eq!(report.files_written, 1);
+        for (path, content) in files {
+            assert_eq!(
+                fs::read_to_string(options.out_dir.join(path)).unwrap(),
+                content
+            );
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_prints_the_tree_without_writing_in_dry_runs() {
+        let dir = temp_dir("dry-run");
+        let proto_dir = dir.join("proto");
+        fs::create_dir_all(proto_dir.join("pkg")).unwrap();
+        fs::write(
+            proto_dir.join("pkg/a.proto"),
+            "syntax = \"proto3\";\npackage pkg;\nmessage A { string s = 1; }\n",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            proto_dirs: vec![proto_dir],
+            out_dir: dir.join("out"),
+            layout: Layout::File,
+            ..Default::default()
+        };
+
+        let mut report = CompileReport::default();
+        let dry_run = compile_dry_run(&options, &mut report).unwrap();
+        assert_eq!(
+            dry_run.tree,
+            format!("{}\n└── pkg\n    └── a.ts\n", options.out_dir.display())
+        );
+        assert_eq!(
+            dry_run.changes,
+            vec![PendingChange::Create(PathBuf::from("pkg/a.ts"))]
+        );
+        assert_eq!(report.stage, Stage::Compile);
+        assert_eq!(report.files_generated, 1);
+        assert!(!options.out_dir.exists());
+
+        compile(&options).unwrap();
+        let dry_run = compile_dry_run(&options, &mut CompileReport::default()).unwrap();
+        assert_eq!(dry_run.changes, vec![]);
+
+        let a_path = options.out_dir.join("pkg/a.ts");
+        let content = fs::read_to_string(&a_path).unwrap();
+        fs::write(&a_path, content.replace("  s: string\n", "  s: number\n")).unwrap();
+        fs::write(options.out_dir.join("pkg/old.ts"), "").unwrap();
+        let changes = compile_dry_run(&options, &mut CompileReport::default())
+            .unwrap()
+            .changes;
+        assert_eq!(changes.len(), 1);
+        match &changes[0] {
+            PendingChange::Update { path, diff } => {
+                assert_eq!(path, &PathBuf::from("pkg/a.ts"));
+                assert!(diff.starts_with("--- a/pkg/a.ts\n+++ b/pkg/a.ts\n@@ "));
+                assert!(diff.contains("\n-  s: number\n+  s: string\n"));
+            }
+            change => panic!("{:?}", change),
+        }
+
+        let options = CompileOptions {
+            prune: true,
+            ..options
+        };
+        let changes = compile_dry_run(&options, &mut CompileReport::default())
+            .unwrap()
+            .changes;
+        assert_eq!(
+            changes[1..],
+            [PendingChange::Remove(PathBuf::from("pkg/old.ts"))]
+        );
+        assert_eq!(
+            fs::read_to_string(&a_path)
+                .unwrap()
+                .matches("s: number")
+                .count(),
+            1
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_writes_a_single_index_file() {
+        let dir = temp_dir("compile-index");
+        fs::create_dir_all(dir.join("proto/shop")).unwrap();
+        fs::write(
+            dir.join("proto/shop/order.proto"),
+            "syntax = \"proto3\";\npackage shop.orders;\nmessage Order { Status status = 1; message Line { string sku = 1; } }\nenum Status { NEW = 0; }\n",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            proto_dirs: vec![dir.join("proto")],
+            out_dir: dir.join("out"),
+            layout: Layout::Index,
+            ..Default::default()
+        };
+        let report = compile(&options).unwrap();
+        assert_eq!(report.files_written, 1);
+        let mut entries = fs::read_dir(&options.out_dir).unwrap();
+        assert!(entries.all(|entry| entry.unwrap().path().is_file()));
+        let index = fs::read_to_string(options.out_dir.join("index.ts")).unwrap();
+        assert!(index.contains("export namespace shop.orders {\n  export enum Status {\n"));
+        assert!(index.contains("export namespace shop.orders.Order {\n  export function create("));
+        assert!(index.contains("export namespace shop.orders.Order.Line {\n"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_writes_nothing_when_protos_did_not_change() {
+        let dir = temp_dir("compile-twice");
+        fs::write(
+            dir.join("a.proto"),
+            "syntax = \"proto3\";\npackage pkg;\nmessage A { string s = 1; }\n",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            proto_dirs: vec![dir.clone()],
+            out_dir: dir.join("out"),
+            ..Default::default()
+        };
+        let first = compile(&options).unwrap();
+        assert_eq!(first.files_written, first.files_generated);
+        assert!(options.out_dir.join(".protobufts-cache.json").is_file());
+
+        let second = compile(&options).unwrap();
+        assert_eq!(second.files_written, 0);
+        assert_eq!(second.files_unchanged, first.files_generated);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_reports_the_failed_stage() {
+        let dir = temp_dir("compile-error");
+        fs::write(
+            dir.join("a.proto"),
+            "message A { int32 a = 1; int32 b = 1; }",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            proto_dirs: vec![dir.clone()],
+            out_dir: dir.join("out"),
+            ..Default::default()
+        };
+        let mut report = CompileReport::default();
+        let err = compile_with_report(&options, &mut report).unwrap_err();
+        assert_eq!(err.code(), "duplicate_field_numbers");
+        assert_eq!(report.stage, Stage::Parse);
+        assert_eq!(report.files_read, 1);
+        assert!(!options.out_dir.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_reports_the_location_of_unresolved_names() {
+        let dir = temp_dir("compile-unresolved");
+        fs::write(
+            dir.join("a.proto"),
+            "syntax = \"proto3\";\nmessage A {\n  int32 a = 1;\n  Foo foo = 2;\n}\n",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            proto_dirs: vec![dir.clone()],
+            out_dir: dir.join("out"),
+            ..Default::default()
+        };
+        let err = compile(&options).unwrap_err();
+        let file = dir.canonicalize().unwrap().join("a.proto");
+        assert_eq!(err.code(), "unresolved_name");
+        assert_eq!(
+            err.location(),
+            Some(crate::SourceLocation {
+                file: file.clone(),
+                line: 4,
+                column: 3,
+            })
+        );
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "{}:4:3: Could not resolve name Foo\n  in Message A\n  in a.proto",
+                file.display()
+            )
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_warns_about_circular_references() {
+        let dir = temp_dir("compile-cycles");
+        fs::write(
+            dir.join("a.proto"),
+            "syntax = \"proto3\";\npackage pkg;\nmessage A { B b = 1; }\nmessage B { A a = 1; }\n",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            proto_dirs: vec![dir.clone()],
+            out_dir: dir.join("out"),
+            ..Default::default()
+        };
+        let report = compile(&options).unwrap();
+        assert_eq!(
+            report.warnings,
+            [Warning::CircularReferences {
+                messages: vec!["pkg.A".into(), "pkg.B".into()]
+            }]
+        );
+        assert_eq!(
+            report.warnings[0].to_string(),
+            "Messages pkg.A, pkg.B reference each other, imports between them are lazy"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_fails_on_files_importing_each_other() {
+        let dir = temp_dir("compile-import-cycles");
+        fs::create_dir_all(dir.join("pkg")).unwrap();
+        fs::write(
+            dir.join("pkg/a.proto"),
+            "syntax = \"proto3\";\npackage pkg;\nimport \"pkg/b.proto\";\nmessage A { B b = 1; }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("pkg/b.proto"),
+            "syntax = \"proto3\";\npackage pkg;\nimport \"pkg/a.proto\";\nmessage B { A a = 1; }\n",
+        )
+        .unwrap();
+        let options = CompileOptions {
+            proto_dirs: vec![dir.clone()],
+            out_dir: dir.join("out"),
+            ..Default::default()
+        };
+        let error = compile(&options).unwrap_err();
+        assert_eq!(error.code(), "import_cycle");
+        assert_eq!(
+            error.to_string(),
+            "Proto files import each other: pkg/a.proto -> pkg/b.proto -> pkg/a.proto, move the declarations they share to another file"
+        );
+        assert!(!dir.join("out").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
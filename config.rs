@@ -0,0 +1,140 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use protos_ts::Rename;
+use serde::Deserialize;
+
+/// Settings read from the file passed with `--config`.
+///
+/// ```toml
+/// [emit]
+/// json = false
+///
+/// [renames]
+/// "shop.Error" = "ShopError"
+/// "shop.Order.new" = { name = "isNew", json = false }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct Config {
+    /// Generated files, the `--no-*` flags turn off the ones that are on here.
+    #[serde(default)]
+    pub emit: EmitConfig,
+    /// Replacement TypeScript names by fully qualified proto names.
+    #[serde(default)]
+    pub renames: HashMap<String, RenameEntry>,
+}
+
+/// `[emit]` table, every file is generated unless it is set to `false`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(super) struct EmitConfig {
+    pub types: bool,
+    pub encode: bool,
+    pub decode: bool,
+    pub json: bool,
+}
+
+impl Default for EmitConfig {
+    fn default() -> Self {
+        Self {
+            types: true,
+            encode: true,
+            decode: true,
+            json: true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(super) enum RenameEntry {
+    Name(String),
+    Table {
+        name: String,
+        #[serde(default = "default_json")]
+        json: bool,
+    },
+}
+
+fn default_json() -> bool {
+    true
+}
+
+impl From<RenameEntry> for Rename {
+    fn from(entry: RenameEntry) -> Self {
+        match entry {
+            RenameEntry::Name(name) => Rename { name, json: true },
+            RenameEntry::Table { name, json } => Rename { name, json },
+        }
+    }
+}
+
+impl Config {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid config {}: {}", path.display(), e),
+            )
+        })
+    }
+
+    fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    pub fn renames(self) -> HashMap<String, Rename> {
+        self.renames
+            .into_iter()
+            .map(|(name, entry)| (name, entry.into()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_config {
+    use super::*;
+
+    #[test]
+    fn it_reads_renames() {
+        let config = Config::parse(
+            "[renames]\n\"shop.Error\" = \"ShopError\"\n\"shop.Order.new\" = { name = \"isNew\", json = false }\n",
+        )
+        .unwrap();
+        let renames = config.renames();
+        assert_eq!(renames["shop.Error"], Rename::from("ShopError"));
+        assert_eq!(
+            renames["shop.Order.new"],
+            Rename {
+                name: "isNew".into(),
+                json: false
+            }
+        );
+    }
+
+    #[test]
+    fn it_reads_emitted_files() {
+        let config = Config::parse("[emit]\nencode = false\njson = false\n").unwrap();
+        assert_eq!(
+            config.emit,
+            EmitConfig {
+                types: true,
+                encode: false,
+                decode: true,
+                json: false,
+            }
+        );
+        assert_eq!(Config::parse("").unwrap().emit, EmitConfig::default());
+        assert!(Config::parse("[emit]\nguards = false\n").is_err());
+        assert!(Config::parse("[emit]\ntypes = \"no\"\n").is_err());
+    }
+
+    #[test]
+    fn it_rejects_unknown_sections() {
+        assert!(Config::parse("[rename]\n\"a.B\" = \"C\"\n").is_err());
+        assert!(Config::parse("[emits]\njson = false\n").is_err());
+        assert!(Config::parse("[emit]\njson = false\n").is_ok());
+        assert!(Config::parse("").unwrap().renames().is_empty());
+    }
+}
@@ -0,0 +1,20 @@
+//! Compiles `.proto` files into TypeScript types, encoders and decoders.
+//!
+//! The `protos-ts` binary is a thin wrapper over this library,
+//! so the generator can be called from a `build.rs` as well.
+
+mod compile;
+mod proto;
+
+pub use compile::{
+    compile, compile_dry_run, compile_in_memory, compile_with_report, CompileOptions,
+    CompileReport, DryRun, Stage, StageDurations,
+};
+pub use proto::compiler::ts::commit_folder::PendingChange;
+pub use proto::compiler::ts::options::{
+    Emit, EnumStyle, FieldCase, FileCase, ImportExtension, ImportStyle, Int64Type, Layout, Runtime,
+    WellKnownTypes,
+};
+pub use proto::compiler::ts::renames::Rename;
+pub use proto::error::{ProtoError, SourceLocation};
+pub use proto::warning::Warning;
@@ -1,16 +1,20 @@
 mod args;
-mod proto;
+mod config;
+mod summary;
+mod watch;
 
-use std::process;
+use std::{process, time::Instant};
 
 use args::get_proto_folder_path;
 use args::CliArguments;
-use proto::compiler::ts::ast::Folder;
-use proto::compiler::ts::commit_folder::commit_folder;
-use proto::compiler::ts::scope_to_folder::root_scope_to_folder;
-use proto::folder::read_proto_folder;
-
-use proto::package::read_root_scope;
+use args::ErrorFormat;
+use config::Config;
+use protos_ts::{
+    compile_dry_run, compile_with_report, CompileOptions, CompileReport, DryRun, Emit, EnumStyle,
+    Layout, PendingChange, Stage,
+};
+use summary::{duration_ms, Summary};
+use watch::watch;
 
 fn main() -> () {
     let args = match get_proto_folder_path() {
@@ -21,50 +25,335 @@ fn main() -> () {
         Ok(r) => r,
     };
 
-    run(args);
-}
+    if args.help {
+        print!("{}", args::HELP);
+        return;
+    }
 
-fn run(args: CliArguments) {
-    let CliArguments {
-        proto_folder_path,
-        out_folder_path,
-    } = args;
+    let summary_json = args.summary_json;
+    let error_format = args.error_format;
+    let watch_mode = args.watch;
+    let dry_run = args.dry_run;
+    let out_folder_path = args.out_folder_path.clone();
 
-    let proto_folder = match read_proto_folder(proto_folder_path) {
+    let options = match compile_options(args) {
+        Ok(options) => options,
         Err(e) => {
             eprintln!("{}", e);
-            process::exit(2);
+            if summary_json {
+                println!("{}", Summary::new(&out_folder_path).to_json());
+            }
+            process::exit(1);
         }
-        Ok(r) => r,
     };
 
-    let root_scope = match read_root_scope(&proto_folder.files) {
-        Err(e) => {
+    let (exit_code, _) = run_with_summary(&options, error_format, summary_json, dry_run);
+
+    if watch_mode {
+        // errors are printed by every run, the watcher keeps going
+        let watched_paths = options
+            .proto_dirs
+            .iter()
+            .chain(&options.include_dirs)
+            .cloned()
+            .collect::<Vec<_>>();
+        let watched = watch(&watched_paths, &options.out_dir, || {
+            let started = Instant::now();
+            let (_, summary) = run_with_summary(&options, error_format, summary_json, false);
+            print_stats(
+                &format!(
+                    "recompiled in {:.0} ms, {} errors",
+                    duration_ms(started.elapsed()),
+                    summary.error_count()
+                ),
+                summary_json,
+            );
+        });
+        if let Err(e) = watched {
             eprintln!("{}", e);
-            process::exit(3);
+            process::exit(1);
         }
-        Ok(r) => r,
+    }
+    if exit_code != 0 {
+        process::exit(exit_code);
+    }
+}
+
+fn compile_options(args: CliArguments) -> std::io::Result<CompileOptions> {
+    let CliArguments {
+        proto_folder_path,
+        include_paths,
+        out_folder_path,
+        single_file,
+        index_file,
+        summary_json: _,
+        emit_default_values,
+        emit_types,
+        emit_encode,
+        emit_decode,
+        emit_json,
+        emit_guards,
+        emit_equals,
+        equals_presence,
+        emit_enum_names,
+        emit_has_fields,
+        emit_manifest,
+        emit_grpc,
+        runtime,
+        well_known_types,
+        import_extension,
+        import_style,
+        enum_style,
+        field_case,
+        file_case,
+        int64_type,
+        keep_unknown_fields,
+        readonly_types,
+        declarations_only,
+        emit,
+        prune,
+        error_format: _,
+        config_path,
+        watch,
+        dry_run,
+        help: _,
+    } = args;
+
+    let layout = if index_file {
+        Layout::Index
+    } else if single_file {
+        Layout::File
+    } else {
+        Layout::Folder
     };
+    if layout == Layout::Index && emit_manifest {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--manifest writes a manifest per package folder and cannot be used with --index-file",
+        ));
+    }
+    if dry_run && watch {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--dry-run writes nothing to watch and cannot be used with --watch",
+        ));
+    }
+    if declarations_only && emit_manifest {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--manifest emits code and cannot be used with --declarations-only",
+        ));
+    }
+    if declarations_only && enum_style == EnumStyle::ConstObject {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--enum-style const-object declares enums as values and cannot be used with --declarations-only",
+        ));
+    }
+
+    if declarations_only && emit != Emit::Ts {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--declarations-only writes .d.ts files already and cannot be used with --emit dts or both",
+        ));
+    }
+    if emit != Emit::Ts && enum_style == EnumStyle::ConstObject {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "--enum-style const-object declares enums as values and cannot be used with --emit dts or both",
+        ));
+    }
 
-    let root_file_name: String = out_folder_path
-        .file_name()
-        .map(|s| s.to_string_lossy())
-        .unwrap()
-        .into();
+    let config = config_path
+        .as_deref()
+        .map(Config::read)
+        .transpose()?
+        .unwrap_or_default();
+    let emitted = config.emit;
+    let renames = config.renames();
+
+    Ok(CompileOptions {
+        proto_dirs: vec![proto_folder_path],
+        include_dirs: include_paths,
+        out_dir: out_folder_path,
+        layout,
+        emit_default_values,
+        // a file is skipped when either the config or a `--no-*` flag turns it off
+        emit_types: emit_types && emitted.types,
+        emit_encode: emit_encode && emitted.encode,
+        emit_decode: emit_decode && emitted.decode,
+        emit_json: emit_json && emitted.json,
+        emit_guards,
+        emit_equals,
+        equals_presence,
+        emit_enum_names,
+        emit_has_fields,
+        emit_manifest,
+        emit_grpc,
+        runtime,
+        well_known_types,
+        renames,
+        prune,
+        import_extension,
+        import_style,
+        enum_style,
+        field_case,
+        file_case,
+        int64_type,
+        keep_unknown_fields,
+        readonly_types,
+        declarations_only,
+        emit,
+    })
+}
+
+/// Runs the compilation, prints the summary when it is requested
+/// and returns the exit code of the process with the summary of the run.
+fn run_with_summary(
+    options: &CompileOptions,
+    error_format: ErrorFormat,
+    summary_json: bool,
+    dry_run: bool,
+) -> (i32, Summary) {
+    let mut summary = Summary::new(&options.out_dir);
+    let exit_code = run(options, error_format, summary_json, dry_run, &mut summary);
+    if summary_json {
+        println!("{}", summary.to_json());
+    }
+    (exit_code, summary)
+}
 
-    let folder: Folder = match root_scope_to_folder(&root_scope, root_file_name) {
+/// Runs the compilation and returns the exit code of the process.
+/// A dry run prints the tree of the output and the changes instead of writing them,
+/// its exit code is 1 when the output is not up to date.
+fn run(
+    options: &CompileOptions,
+    error_format: ErrorFormat,
+    summary_json: bool,
+    dry_run: bool,
+    summary: &mut Summary,
+) -> i32 {
+    let mut report = CompileReport::default();
+    let res = if dry_run {
+        compile_dry_run(options, &mut report).map(Some)
+    } else {
+        compile_with_report(options, &mut report).map(|()| None)
+    };
+    summary.add_report(&report);
+    for warning in &report.warnings {
+        eprintln!("warning: {}", warning);
+        summary.add_warning(warning.code());
+    }
+    let dry_run = match res {
+        Ok(dry_run) => dry_run,
         Err(e) => {
-            eprintln!("{}", e);
-            process::exit(4);
+            match error_format {
+                ErrorFormat::Text => eprintln!("{}", e),
+                ErrorFormat::Json => eprintln!("{}", serde_json::to_string(&e.errors()).unwrap()),
+            }
+            for err in e.errors() {
+                summary.add_error(err.code());
+            }
+            return match report.stage {
+                Stage::Read => 2,
+                Stage::Parse => 3,
+                Stage::Compile | Stage::Write => 4,
+            };
         }
-        Ok(r) => r,
     };
+    summary.success = true;
+    if let Some(dry_run) = dry_run {
+        print_dry_run(&dry_run, &report, summary_json);
+        return if dry_run.changes.is_empty() { 0 } else { 1 };
+    }
 
-    match commit_folder(&folder) {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("{}", e);
-            process::exit(4);
+    let stats = format!(
+        "wrote {}, skipped {}, pruned {}",
+        report.files_written, report.files_unchanged, report.files_pruned
+    );
+    print_stats(&stats, summary_json);
+    0
+}
+
+/// Prints the tree of the output, then the files that would be created and removed
+/// and the diffs of the ones that would be rewritten.
+fn print_dry_run(dry_run: &DryRun, report: &CompileReport, summary_json: bool) {
+    print_stats(dry_run.tree.trim_end(), summary_json);
+    let (mut created, mut updated, mut removed) = (0, 0, 0);
+    for change in &dry_run.changes {
+        match change {
+            PendingChange::Create(path) => {
+                created += 1;
+                print_stats(&format!("new file: {}", path.display()), summary_json);
+            }
+            PendingChange::Update { path, diff } => {
+                updated += 1;
+                if diff.is_empty() {
+                    // only the line endings differ
+                    print_stats(&format!("changed file: {}", path.display()), summary_json);
+                } else {
+                    print_stats(diff.trim_end(), summary_json);
+                }
+            }
+            PendingChange::Remove(path) => {
+                removed += 1;
+                print_stats(&format!("deleted file: {}", path.display()), summary_json);
+            }
         }
     }
+    print_stats(
+        &format!(
+            "{} files, {} to create, {} to update, {} to delete, nothing written",
+            report.files_generated, created, updated, removed
+        ),
+        summary_json,
+    );
+}
+
+fn print_stats(stats: &str, summary_json: bool) {
+    // stdout is reserved for the summary when it is requested
+    if summary_json {
+        eprintln!("{}", stats);
+    } else {
+        println!("{}", stats);
+    }
+}
+
+#[cfg(test)]
+mod test_compile_options {
+    use std::{fs, time::SystemTime};
+
+    use protos_ts::FieldCase;
+
+    use super::*;
+
+    #[test]
+    fn it_merges_emitted_files_of_the_config_with_the_flags() {
+        let config_path = std::env::temp_dir().join(format!(
+            "protos-ts-config-{}-{}.toml",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::write(&config_path, "[emit]\nencode = false\n").unwrap();
+        let options = compile_options(CliArguments {
+            emit_json: false,
+            config_path: Some(config_path.clone()),
+            ..CliArguments::default()
+        })
+        .unwrap();
+        fs::remove_file(&config_path).unwrap();
+        assert!(options.emit_types);
+        assert!(!options.emit_encode);
+        assert!(options.emit_decode);
+        assert!(!options.emit_json);
+    }
+
+    #[test]
+    fn it_names_properties_in_camel_case_by_default() {
+        let options = compile_options(CliArguments::default()).unwrap();
+        assert_eq!(options.field_case, FieldCase::CamelCase);
+    }
 }
@@ -1,9 +1,11 @@
 pub(super) mod folder;
 pub(super) mod package;
 pub(super) mod error;
+pub(super) mod warning;
 pub(super) mod compiler;
 mod syntax;
 mod lexems;
 mod id_generator;
 mod proto_scope;
 mod protopath;
+mod validation;
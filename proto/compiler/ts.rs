@@ -1,6 +1,8 @@
 pub(crate) mod ast;
 pub(crate) mod commit_folder;
 mod constants;
+mod create_compiler;
+mod declaration_files;
 mod decode_compiler;
 mod encode_basic_repeated_type_field;
 mod encode_basic_type_field;
@@ -11,15 +13,41 @@ mod encode_map_field;
 mod encode_message_expr;
 mod ensure_import;
 mod enum_compiler;
+mod enum_values;
+mod equals_compiler;
+mod field_case;
+mod file_case;
 mod file_name_to_folder_name;
 mod file_to_folder;
+mod guard_compiler;
+mod files_to_index;
 mod get_relative_import;
+mod has_fields;
 mod has_property;
+mod import_extension;
+mod import_style;
+mod import_enum;
+mod int64_type;
 mod is_reserved;
 mod is_safe_id;
+mod json_compiler;
+mod layout;
+mod line_diff;
+mod manifest_compiler;
 mod message_name_to_encode_type_name;
+pub(crate) mod parallel_map;
+pub(crate) mod options;
 pub(crate) mod scope_to_folder;
+pub(crate) mod renames;
 mod render_file;
+mod runtime;
+mod safe_identifier;
+mod service_compiler;
+#[cfg(test)]
+mod test_utils;
 mod to_js_string;
 mod ts_path;
 mod types_compiler;
+mod unknown_fields;
+mod well_known_type;
+mod well_known_types;
@@ -1,7 +1,7 @@
 use std::{
     mem,
     ops::{Deref, DerefMut},
-    rc::Rc,
+    sync::Arc,
 };
 
 pub(crate) trait StatementList {
@@ -15,7 +15,7 @@ pub(crate) struct SourceFile {
 
 #[derive(Debug)]
 pub(crate) struct StringLiteral {
-    pub text: Rc<str>,
+    pub text: Arc<str>,
 }
 
 impl Deref for StringLiteral {
@@ -54,14 +54,14 @@ where
 }
 
 impl StringLiteral {
-    pub fn new(text: Rc<str>) -> Self {
+    pub fn new(text: Arc<str>) -> Self {
         Self { text }
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) struct Identifier {
-    pub text: Rc<str>,
+    pub text: Arc<str>,
 }
 
 impl Identifier {
@@ -90,36 +90,51 @@ where
 }
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct ImportSpecifier {
-    pub name: Rc<Identifier>,
-    pub property_name: Option<Rc<Identifier>>,
+    pub name: Arc<Identifier>,
+    pub property_name: Option<Arc<Identifier>>,
+    /// The binding is used only as a type: `import { type Name }`.
+    pub is_type_only: bool,
 }
 
 impl ImportSpecifier {
     #[allow(dead_code)]
-    pub fn new_full(name: Rc<Identifier>, property_name: Option<Rc<Identifier>>) -> Self {
+    pub fn new_full(name: Arc<Identifier>, property_name: Option<Arc<Identifier>>) -> Self {
         Self {
             name,
             property_name,
+            is_type_only: false,
         }
     }
-    pub fn new(name: Rc<Identifier>) -> Self {
+    pub fn new(name: Arc<Identifier>) -> Self {
         Self {
             name,
             property_name: None,
+            is_type_only: false,
         }
     }
+    /// Imported name of the specifier, then its local name.
+    pub fn sort_key(&self) -> (&str, &str) {
+        let imported = self.property_name.as_ref().unwrap_or(&self.name);
+        (&imported.text, &self.name.text)
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct ImportClause {
+    /// `import type { ... }`, erased from the emitted JavaScript.
+    pub is_type_only: bool,
     pub name: Option<Identifier>,
+    /// `import * as name`, the module object itself.
+    pub namespace_import: Option<Arc<Identifier>>,
     pub named_bindings: Option<Vec<ImportSpecifier>>,
 }
 
 impl From<Vec<ImportSpecifier>> for ImportClause {
     fn from(named_bindings: Vec<ImportSpecifier>) -> Self {
         Self {
+            is_type_only: false,
             name: None,
+            namespace_import: None,
             named_bindings: Some(named_bindings),
         }
     }
@@ -138,11 +153,30 @@ impl ImportDeclaration {
             string_literal: file_path,
         }
     }
+    pub fn import_namespace(name: Arc<Identifier>, file_path: StringLiteral) -> Self {
+        Self {
+            import_clause: Box::new(ImportClause {
+                is_type_only: false,
+                name: None,
+                namespace_import: Some(name),
+                named_bindings: None,
+            }),
+            string_literal: file_path,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum Modifier {
     Export,
+    /// `declare`, of the top-level statements of `.d.ts` files
+    Declare,
+    /// `static`, of class members
+    Static,
+    /// `private`, of class members
+    Private,
+    /// `readonly`, of class fields
+    Readonly,
 }
 
 #[derive(Debug)]
@@ -151,8 +185,8 @@ pub(crate) enum EnumValue {
     Number(NumericLiteral),
 }
 
-impl From<Rc<str>> for EnumValue {
-    fn from(text: Rc<str>) -> Self {
+impl From<Arc<str>> for EnumValue {
+    fn from(text: Arc<str>) -> Self {
         EnumValue::String(StringLiteral::new(text))
     }
 }
@@ -182,6 +216,8 @@ impl From<i64> for EnumValue {
 pub(crate) struct EnumMember {
     pub name: Identifier,
     pub value: Option<EnumValue>,
+    /// Rendered with the `/** @deprecated */` tag
+    pub deprecated: bool,
 }
 
 #[derive(Debug)]
@@ -189,6 +225,54 @@ pub(crate) struct EnumDeclaration {
     pub modifiers: Vec<Modifier>,
     pub name: Identifier,
     pub members: Vec<EnumMember>,
+    /// Rendered with the `/** @deprecated */` tag
+    pub deprecated: bool,
+}
+
+/// `T extends Constraint = Default` in the type parameters of a declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TypeParameter {
+    pub name: Identifier,
+    pub constraint: Option<Type>,
+    pub default: Option<Type>,
+}
+
+impl TypeParameter {
+    #[allow(dead_code)]
+    pub fn new(name: impl Into<Identifier>) -> Self {
+        Self {
+            name: name.into(),
+            constraint: None,
+            default: None,
+        }
+    }
+}
+
+/// `export type Color = "RED" | "GREEN"`
+#[derive(Debug)]
+pub(crate) struct TypeAliasDeclaration {
+    pub modifiers: Vec<Modifier>,
+    pub name: Identifier,
+    pub type_params: Vec<TypeParameter>,
+    pub alias_type: Type,
+    /// Rendered with the `/** @deprecated */` tag
+    pub deprecated: bool,
+}
+
+impl TypeAliasDeclaration {
+    pub fn new(name: Identifier, alias_type: Type) -> Self {
+        Self {
+            modifiers: vec![],
+            name,
+            type_params: vec![],
+            alias_type,
+            deprecated: false,
+        }
+    }
+    pub fn exported(mut self) -> Self {
+        self.modifiers.push(Modifier::Export);
+        self
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -216,10 +300,12 @@ impl UnionType {
             }
             _ => {
                 for x in self.types.iter() {
-                    if *x == t {
+                    if *x == t || t.widened().as_ref() == Some(x) {
                         return;
                     }
                 }
+                // `"a" | string` is `string`
+                self.types.retain(|x| x.widened().as_ref() != Some(&t));
                 self.types.push(t);
             }
         }
@@ -232,23 +318,95 @@ impl From<Vec<Type>> for UnionType {
     }
 }
 
+/// `A & B & C`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IntersectionType {
+    pub types: Vec<Type>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Type {
     Number,
+    /// `bigint`
+    BigInt,
     Null,
     Never,
     Boolean,
     String,
     UnionType(UnionType),
+    #[allow(dead_code)]
+    IntersectionType(IntersectionType),
     ArrayType(Box<Type>),
     Record(Box<Type>, Box<Type>),
-    TypeReference(Vec<Rc<Identifier>>),
+    TypeReference(Vec<Arc<Identifier>>),
+    Generic(Arc<Identifier>, Vec<Type>),
     Any,
+    Unknown,
+    /// `void`, the return type of functions returning nothing.
+    Void,
+    /// `undefined`, in unions of values that may be missing.
+    Undefined,
+    /// `value is T`, the return type of a type guard.
+    Predicate(Arc<Identifier>, Box<Type>),
+    LiteralType(LiteralValue),
+    /// `typeof value`
+    TypeQuery(Arc<Identifier>),
+    /// `keyof T`
+    KeyOf(Box<Type>),
+    /// `T[K]`
+    IndexedAccess(Box<Type>, Box<Type>),
+    /// Reference to a type parameter of the enclosing declaration: `T`
+    #[allow(dead_code)]
+    TypeVar(Identifier),
+    /// `[T, U]`, an array of a fixed length
+    #[allow(dead_code)]
+    TupleType(Vec<Type>),
+    /// `{ seconds: number; nanos: number }`, an object type written in place
+    Object(Vec<(Arc<str>, Type)>),
+    /// `{ [key: string]: T }`, an object with values of `T` under any keys
+    IndexSignature(Box<Type>),
+}
+
+/// Value of a literal type: `"PENDING"`, `1` or `true`
+#[derive(Debug, Clone)]
+pub(crate) enum LiteralValue {
+    StringLit(String),
+    #[allow(dead_code)]
+    NumberLit(f64),
+    #[allow(dead_code)]
+    BoolLit(bool),
+}
+
+/// Numbers are compared by their bits, so equal literal types are merged in unions.
+impl PartialEq for LiteralValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::StringLit(a), Self::StringLit(b)) => a == b,
+            (Self::NumberLit(a), Self::NumberLit(b)) => a.to_bits() == b.to_bits(),
+            (Self::BoolLit(a), Self::BoolLit(b)) => a == b,
+            _ => false,
+        }
+    }
 }
 
+impl Eq for LiteralValue {}
+
 impl Type {
     pub fn from_id(name: &str) -> Type {
-        return Type::TypeReference(vec![Rc::new(name.into())]);
+        return Type::TypeReference(vec![Arc::new(name.into())]);
+    }
+    pub fn generic(name: &str, type_arguments: Vec<Type>) -> Type {
+        Type::Generic(Arc::new(name.into()), type_arguments)
+    }
+    pub fn string_literal(value: &str) -> Type {
+        Type::LiteralType(LiteralValue::StringLit(value.to_string()))
+    }
+    /// `(typeof value)[keyof typeof value]`, the type of the values of the object.
+    pub fn value_of(id: Arc<Identifier>) -> Type {
+        Type::IndexedAccess(
+            Box::new(Type::TypeQuery(Arc::clone(&id))),
+            Box::new(Type::KeyOf(Box::new(Type::TypeQuery(id)))),
+        )
     }
 }
 
@@ -270,18 +428,44 @@ impl Type {
         match self {
             Type::ArrayType(_) => true,
             Type::UnionType(_) => true,
+            Type::IntersectionType(_) => true,
             Type::Number => false,
+            Type::BigInt => false,
             Type::Never => false,
             Type::Null => false,
             Type::Boolean => false,
             Type::String => false,
             Type::TypeReference(_) => false,
+            Type::Generic(_, _) => false,
             Type::Record(_, _) => false,
             Type::Any => false,
+            Type::Unknown => false,
+            Type::Void => false,
+            Type::Undefined => false,
+            Type::Predicate(_, _) => true,
+            Type::LiteralType(_) => false,
+            Type::TypeQuery(_) => true,
+            Type::KeyOf(_) => true,
+            Type::IndexedAccess(_, _) => false,
+            Type::TypeVar(_) => false,
+            // the brackets delimit the tuple already: `[K, V][]`, `[K, V] | null`
+            Type::TupleType(_) => false,
+            Type::Object(_) => false,
+            Type::IndexSignature(_) => false,
+        }
+    }
+
+    /// Primitive type of the values of a literal type, `string` for `"a"`.
+    fn widened(&self) -> Option<Type> {
+        match self {
+            Type::LiteralType(LiteralValue::StringLit(_)) => Some(Type::String),
+            Type::LiteralType(LiteralValue::NumberLit(_)) => Some(Type::Number),
+            Type::LiteralType(LiteralValue::BoolLit(_)) => Some(Type::Boolean),
+            _ => None,
         }
     }
 
-    pub fn reference(id: Rc<Identifier>) -> Self {
+    pub fn reference(id: Arc<Identifier>) -> Self {
         return Type::TypeReference(vec![id]);
     }
 
@@ -291,15 +475,37 @@ impl Type {
         res.push(another.clone());
         res.into()
     }
+
+    /// `a & b`, members of nested intersections become members of the result.
+    #[allow(dead_code)]
+    pub fn intersection(a: Type, b: Type) -> Self {
+        let mut types = Vec::new();
+        for t in [a, b] {
+            match t {
+                Type::IntersectionType(IntersectionType { types: nested }) => types.extend(nested),
+                t => types.push(t),
+            }
+        }
+        let mut res = Vec::with_capacity(types.len());
+        for t in types {
+            if !res.contains(&t) {
+                res.push(t);
+            }
+        }
+        if res.len() == 1 {
+            return res.pop().unwrap();
+        }
+        Type::IntersectionType(IntersectionType { types: res })
+    }
 }
 
 impl From<Identifier> for Type {
     fn from(identifier: Identifier) -> Self {
-        Rc::new(identifier).into()
+        Arc::new(identifier).into()
     }
 }
-impl From<Rc<Identifier>> for Type {
-    fn from(identifier: Rc<Identifier>) -> Self {
+impl From<Arc<Identifier>> for Type {
+    fn from(identifier: Arc<Identifier>) -> Self {
         Self::reference(identifier)
     }
 }
@@ -315,26 +521,51 @@ pub(crate) struct PropertySignature {
     pub name: Identifier,
     pub property_type: Type,
     pub optional: bool,
+    /// `readonly name: T`
+    pub readonly: bool,
+    /// Rendered with the `/** @deprecated */` tag
+    pub deprecated: bool,
 }
 
 impl PropertySignature {
-    pub fn new(name: Rc<str>, property_type: Type) -> Self {
+    pub fn new(name: Arc<str>, property_type: Type) -> Self {
         Self {
             name: name.into(),
             property_type,
             optional: false,
+            readonly: false,
+            deprecated: false,
         }
     }
-    pub fn new_optional(name: Rc<str>, property_type: Type) -> Self {
+    pub fn new_optional(name: Arc<str>, property_type: Type) -> Self {
         let mut res = Self::new(name, property_type);
         res.optional = true;
         return res;
     }
 }
 
+/// `send(request: Message): Promise<Ack>` of an interface.
+#[derive(Debug)]
+pub(crate) struct MethodSignature {
+    pub name: Identifier,
+    pub parameters: Vec<Parameter>,
+    pub return_type: Type,
+}
+
+impl From<&FunctionDeclaration> for MethodSignature {
+    fn from(function: &FunctionDeclaration) -> Self {
+        Self {
+            name: function.name.clone(),
+            parameters: function.parameters.clone(),
+            return_type: function.return_type.clone(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum InterfaceMember {
     PropertySignature(PropertySignature),
+    MethodSignature(MethodSignature),
 }
 
 impl From<PropertySignature> for InterfaceMember {
@@ -343,40 +574,54 @@ impl From<PropertySignature> for InterfaceMember {
     }
 }
 
+impl From<MethodSignature> for InterfaceMember {
+    fn from(method_signature: MethodSignature) -> Self {
+        Self::MethodSignature(method_signature)
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct InterfaceDeclaration {
     pub modifiers: Vec<Modifier>,
     pub name: Identifier,
+    pub type_params: Vec<TypeParameter>,
+    /// Interfaces after `extends`.
+    pub extends: Vec<Type>,
     pub members: Vec<InterfaceMember>,
+    /// Rendered with the `/** @deprecated */` tag
+    pub deprecated: bool,
 }
 
 impl InterfaceDeclaration {
-    pub fn new(name: Rc<str>) -> Self {
+    pub fn new(name: Arc<str>) -> Self {
         Self {
             modifiers: vec![],
             name: name.into(),
+            type_params: vec![],
+            extends: Vec::new(),
             members: Vec::new(),
+            deprecated: false,
         }
     }
-    pub fn new_exported(name: Rc<str>) -> Self {
+    pub fn new_exported(name: Arc<str>) -> Self {
         let mut r = Self::new(name);
         r.modifiers.push(Modifier::Export);
         r
     }
 }
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Parameter {
-    pub name: Rc<Identifier>,
-    pub parameter_type: Rc<Type>,
+    pub name: Arc<Identifier>,
+    pub parameter_type: Arc<Type>,
     pub optional: bool,
 }
 
 impl Parameter {
     pub fn new(name: &str, _type: Type) -> Self {
-        let id: Rc<Identifier> = Rc::new(name.into());
+        let id: Arc<Identifier> = Arc::new(name.into());
         Self {
             name: id,
-            parameter_type: Rc::new(_type),
+            parameter_type: Arc::new(_type),
             optional: false,
         }
     }
@@ -392,9 +637,11 @@ impl Parameter {
 pub(crate) struct FunctionDeclaration {
     pub modifiers: Vec<Modifier>,
     pub name: Identifier,
+    pub type_params: Vec<TypeParameter>,
     pub parameters: Vec<Parameter>,
     pub return_type: Type,
-    pub body: Block,
+    /// `None` for a signature without implementation, as in `.d.ts` files
+    pub body: Option<Block>,
 }
 
 impl FunctionDeclaration {
@@ -402,9 +649,10 @@ impl FunctionDeclaration {
         Self {
             modifiers: Vec::new(),
             name: name.into(),
+            type_params: Vec::new(),
             parameters: Vec::new(),
             return_type: Type::Never,
-            body: Block::new(),
+            body: Some(Block::new()),
         }
     }
     pub fn new_exported(name: &str) -> Self {
@@ -422,7 +670,93 @@ impl FunctionDeclaration {
 
 impl StatementList for FunctionDeclaration {
     fn push_statement(&mut self, statement: Statement) {
-        self.body.statements.push(statement.into());
+        self.body
+            .get_or_insert_with(Block::new)
+            .statements
+            .push(statement.into());
+    }
+}
+
+/// `private buf: number[] = []`, a field of a class.
+#[derive(Debug)]
+pub(crate) struct PropertyDeclaration {
+    pub modifiers: Vec<Modifier>,
+    pub name: Identifier,
+    pub property_type: Option<Type>,
+    pub initializer: Option<Expression>,
+}
+
+impl PropertyDeclaration {
+    pub fn new(name: &str, property_type: Option<Type>, initializer: Option<Expression>) -> Self {
+        Self {
+            modifiers: Vec::new(),
+            name: name.into(),
+            property_type,
+            initializer,
+        }
+    }
+}
+
+/// `class GreeterClient { ... }` with a constructor that declares `properties`
+/// as `private readonly` parameter properties.
+#[derive(Debug)]
+pub(crate) struct ClassDeclaration {
+    pub modifiers: Vec<Modifier>,
+    pub name: Identifier,
+    pub properties: Vec<Parameter>,
+    /// Fields declared in the body of the class, before the constructor.
+    pub fields: Vec<PropertyDeclaration>,
+    /// Parameters of the constructor after the parameter properties.
+    pub parameters: Vec<Parameter>,
+    /// Statements of the constructor, the constructor is omitted
+    /// when it has no parameters and no statements.
+    pub constructor: Block,
+    /// Interfaces of the `implements` clause.
+    pub implements: Vec<Type>,
+    /// Methods are rendered without the `function` keyword.
+    pub methods: Vec<FunctionDeclaration>,
+    /// Whether the class is declared without implementation, as in `.d.ts` files.
+    /// The properties are declared as fields then, since only an implemented constructor can declare them.
+    pub ambient: bool,
+}
+
+impl ClassDeclaration {
+    pub fn new_exported(name: Arc<str>) -> Self {
+        Self {
+            modifiers: vec![Modifier::Export],
+            name: name.into(),
+            properties: Vec::new(),
+            fields: Vec::new(),
+            parameters: Vec::new(),
+            constructor: Block::new(),
+            implements: Vec::new(),
+            methods: Vec::new(),
+            ambient: false,
+        }
+    }
+}
+
+/// `namespace shop.Order { ... }`, the name may be a dotted path of nested namespaces.
+#[derive(Debug)]
+pub(crate) struct NamespaceDeclaration {
+    pub modifiers: Vec<Modifier>,
+    pub name: Identifier,
+    pub statements: Vec<Statement>,
+}
+
+impl NamespaceDeclaration {
+    pub fn new_exported(name: Arc<str>) -> Self {
+        Self {
+            modifiers: vec![Modifier::Export],
+            name: name.into(),
+            statements: Vec::new(),
+        }
+    }
+}
+
+impl StatementList for NamespaceDeclaration {
+    fn push_statement(&mut self, statement: Statement) {
+        self.statements.push(statement);
     }
 }
 
@@ -431,17 +765,34 @@ pub(crate) enum BinaryOperator {
     LogicalOr,
     LogicalAnd,
     BinaryAnd,
+    BinaryOr,
+    BinaryXor,
     WeakNotEqual,
     LessThan,
+    GreaterThan,
+    GreaterThanOrEqual,
     InstanceOf,
     StrictEqual,
+    StrictNotEqual,
     Plus,
+    Minus,
+    Multiply,
+    Divide,
+    LeftShift,
+    RightShift,
     UnsignedRightShift,
     Assign,
+    PlusAssign,
+    MinusAssign,
+    BinaryOrAssign,
+    UnsignedRightShiftAssign,
+    In,
+    /// `??`, parenthesized by the callers when mixed with other operators.
+    NullishCoalescing,
 }
 
 impl BinaryOperator {
-    pub fn apply(self, left: Rc<Expression>, right: Rc<Expression>) -> Expression {
+    pub fn apply(self, left: Arc<Expression>, right: Arc<Expression>) -> Expression {
         let mut binary_expr = BinaryExpression::new(self);
 
         binary_expr.left(left);
@@ -449,6 +800,37 @@ impl BinaryOperator {
 
         Expression::BinaryExpression(binary_expr)
     }
+
+    /// How tightly the operator binds its operands, the operands of operators
+    /// with higher precedence are evaluated first: `a + b * c` is `a + (b * c)`.
+    /// Conditional expressions bind between assignments and `||`, at `2`.
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinaryOperator::Assign
+            | BinaryOperator::PlusAssign
+            | BinaryOperator::MinusAssign
+            | BinaryOperator::BinaryOrAssign
+            | BinaryOperator::UnsignedRightShiftAssign => 1,
+            BinaryOperator::LogicalOr | BinaryOperator::NullishCoalescing => 3,
+            BinaryOperator::LogicalAnd => 4,
+            BinaryOperator::BinaryOr => 5,
+            BinaryOperator::BinaryXor => 6,
+            BinaryOperator::BinaryAnd => 7,
+            BinaryOperator::WeakNotEqual
+            | BinaryOperator::StrictEqual
+            | BinaryOperator::StrictNotEqual => 8,
+            BinaryOperator::LessThan
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::GreaterThanOrEqual
+            | BinaryOperator::InstanceOf
+            | BinaryOperator::In => 9,
+            BinaryOperator::LeftShift
+            | BinaryOperator::RightShift
+            | BinaryOperator::UnsignedRightShift => 10,
+            BinaryOperator::Plus | BinaryOperator::Minus => 11,
+            BinaryOperator::Multiply | BinaryOperator::Divide => 12,
+        }
+    }
 }
 
 impl From<&BinaryOperator> for &str {
@@ -458,12 +840,28 @@ impl From<&BinaryOperator> for &str {
             BinaryOperator::LogicalAnd => "&&",
             BinaryOperator::WeakNotEqual => "!=",
             BinaryOperator::LessThan => "<",
+            BinaryOperator::GreaterThan => ">",
+            BinaryOperator::GreaterThanOrEqual => ">=",
             BinaryOperator::InstanceOf => "instanceof",
             BinaryOperator::Plus => "+",
+            BinaryOperator::Minus => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
             BinaryOperator::StrictEqual => "===",
+            BinaryOperator::StrictNotEqual => "!==",
+            BinaryOperator::LeftShift => "<<",
+            BinaryOperator::RightShift => ">>",
             BinaryOperator::UnsignedRightShift => ">>>",
             BinaryOperator::BinaryAnd => "&",
+            BinaryOperator::BinaryOr => "|",
+            BinaryOperator::BinaryXor => "^",
             BinaryOperator::Assign => "=",
+            BinaryOperator::PlusAssign => "+=",
+            BinaryOperator::MinusAssign => "-=",
+            BinaryOperator::BinaryOrAssign => "|=",
+            BinaryOperator::UnsignedRightShiftAssign => ">>>=",
+            BinaryOperator::In => "in",
+            BinaryOperator::NullishCoalescing => "??",
         }
     }
 }
@@ -476,42 +874,54 @@ impl From<BinaryOperator> for &str {
 #[derive(Debug, Clone)]
 pub(crate) struct BinaryExpression {
     pub operator: BinaryOperator,
-    pub left: Rc<Expression>,
-    pub right: Rc<Expression>,
+    pub left: Arc<Expression>,
+    pub right: Arc<Expression>,
 }
 
 impl BinaryExpression {
     pub fn new(operator: BinaryOperator) -> Self {
         Self {
             operator,
-            left: Rc::new(Expression::Undefined),
-            right: Rc::new(Expression::Undefined),
+            left: Arc::new(Expression::Undefined),
+            right: Arc::new(Expression::Undefined),
         }
     }
-    pub fn left(&mut self, expr: Rc<Expression>) -> &mut Self {
-        self.left = Rc::clone(&expr);
+    pub fn left(&mut self, expr: Arc<Expression>) -> &mut Self {
+        self.left = Arc::clone(&expr);
         self
     }
-    pub fn right(&mut self, expr: Rc<Expression>) -> &mut Self {
-        self.right = Rc::clone(&expr);
+    pub fn right(&mut self, expr: Arc<Expression>) -> &mut Self {
+        self.right = Arc::clone(&expr);
         self
     }
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct CallExpression {
-    pub expression: Rc<Expression>,
-    pub arguments: Vec<Rc<Expression>>,
+    pub expression: Arc<Expression>,
+    pub arguments: Vec<Arc<Expression>>,
 }
 #[derive(Debug, Clone)]
 pub(crate) struct PropertyAccessExpression {
-    pub expression: Rc<Expression>,
-    pub name: Rc<Identifier>,
+    pub expression: Arc<Expression>,
+    pub name: Arc<Identifier>,
+    /// Whether the property is accessed with `?.`
+    pub optional_chain: bool,
 }
 
 impl PropertyAccessExpression {
-    pub fn new(expression: Rc<Expression>, name: Rc<Identifier>) -> Self {
-        Self { expression, name }
+    pub fn new(expression: Arc<Expression>, name: Arc<Identifier>) -> Self {
+        Self {
+            expression,
+            name,
+            optional_chain: false,
+        }
+    }
+    pub fn new_optional_chain(expression: Arc<Expression>, name: Arc<Identifier>) -> Self {
+        Self {
+            optional_chain: true,
+            ..Self::new(expression, name)
+        }
     }
     pub fn requires_wrap_for_prop(&self) -> bool {
         match self.expression.deref() {
@@ -533,31 +943,44 @@ impl PropertyAccessExpression {
             Expression::PrefixUnaryExpression(_) => true,
             Expression::ConditionalExpression(_) => true,
             Expression::Typeof(_) => true,
+            Expression::AsExpression(_, _) => true,
+            Expression::SpreadElement(_) => unreachable!(),
         }
     }
 }
 #[derive(Debug)]
 pub(crate) enum ObjectLiteralMember {
-    PropertyAssignment(Rc<Identifier>, Rc<Expression>),
+    PropertyAssignment(Arc<Identifier>, Arc<Expression>),
+    SpreadAssignment(Arc<Expression>),
+    /// `name`, the value of the variable with the same name
+    #[allow(dead_code)]
+    ShorthandPropertyAssignment(Arc<Identifier>),
+}
+
+impl ObjectLiteralMember {
+    /// `name: value`
+    pub fn property(name: impl Into<Identifier>, value: impl Into<Arc<Expression>>) -> Arc<Self> {
+        Arc::new(ObjectLiteralMember::PropertyAssignment(
+            Arc::new(name.into()),
+            value.into(),
+        ))
+    }
 }
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub(crate) struct NewExpression {
-    pub expression: Rc<Expression>,
-    pub arguments: Vec<Rc<Expression>>,
+    pub expression: Arc<Expression>,
+    pub arguments: Vec<Arc<Expression>>,
 }
 
 impl NewExpression {
-    #[allow(dead_code)]
-    pub fn new(expression: Rc<Expression>) -> Self {
+    pub fn new(expression: Arc<Expression>) -> Self {
         Self {
             expression,
             arguments: Vec::new(),
         }
     }
-    #[allow(dead_code)]
-    pub fn add_argument(&mut self, argument: Rc<Expression>) -> &mut Self {
+    pub fn add_argument(&mut self, argument: Arc<Expression>) -> &mut Self {
         self.arguments.push(argument);
         self
     }
@@ -565,37 +988,41 @@ impl NewExpression {
 
 #[derive(Debug)]
 pub(crate) struct ElementAccessExpression {
-    pub expression: Rc<Expression>,
-    pub argument: Rc<Expression>,
+    pub expression: Arc<Expression>,
+    pub argument: Arc<Expression>,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum UnaryOperator {
     Increment,
+    Decrement,
     Not,
+    Minus,
 }
 
 impl From<&UnaryOperator> for &str {
     fn from(unary_operator: &UnaryOperator) -> Self {
         match unary_operator {
             UnaryOperator::Increment => "++",
+            UnaryOperator::Decrement => "--",
             UnaryOperator::Not => "!",
+            UnaryOperator::Minus => "-",
         }
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct ConditionalExpression {
-    pub condition: Rc<Expression>,
-    pub when_true: Rc<Expression>,
-    pub when_false: Rc<Expression>,
+    pub condition: Arc<Expression>,
+    pub when_true: Arc<Expression>,
+    pub when_false: Arc<Expression>,
 }
 
 impl ConditionalExpression {
     pub fn new(
-        condition: Rc<Expression>,
-        when_true: Rc<Expression>,
-        when_false: Rc<Expression>,
+        condition: Arc<Expression>,
+        when_true: Arc<Expression>,
+        when_false: Arc<Expression>,
     ) -> ConditionalExpression {
         return Self {
             condition,
@@ -608,21 +1035,21 @@ impl ConditionalExpression {
 #[derive(Debug)]
 pub(crate) struct PrefixUnaryExpression {
     pub operator: UnaryOperator,
-    pub operand: Rc<Expression>,
+    pub operand: Arc<Expression>,
 }
 
 impl PrefixUnaryExpression {
-    pub fn increment(operand: Rc<Identifier>) -> Self {
+    pub fn increment(operand: Arc<Identifier>) -> Self {
         Self {
             operator: UnaryOperator::Increment,
-            operand: Rc::new(operand.into()),
+            operand: Arc::new(operand.into()),
         }
     }
 }
 #[derive(Debug)]
 #[allow(dead_code)]
 pub(crate) enum Expression {
-    Identifier(Rc<Identifier>),
+    Identifier(Arc<Identifier>),
     Null,
     Undefined,
     False,
@@ -630,49 +1057,78 @@ pub(crate) enum Expression {
     BinaryExpression(BinaryExpression),
     CallExpression(CallExpression),
     PropertyAccessExpression(PropertyAccessExpression),
-    ParenthesizedExpression(Rc<Expression>),
-    ArrayLiteralExpression(Vec<Rc<Expression>>),
-    ObjectLiteralExpression(Vec<Rc<ObjectLiteralMember>>),
+    ParenthesizedExpression(Arc<Expression>),
+    ArrayLiteralExpression(Vec<Arc<Expression>>),
+    ObjectLiteralExpression(Vec<Arc<ObjectLiteralMember>>),
     NewExpression(NewExpression),
     NumericLiteral(f64),
     StringLiteral(StringLiteral),
     ElementAccessExpression(ElementAccessExpression),
     PrefixUnaryExpression(PrefixUnaryExpression),
     ConditionalExpression(ConditionalExpression),
-    Typeof(Rc<Expression>),
+    Typeof(Arc<Expression>),
+    /// `expression as T`
+    AsExpression(Arc<Expression>, Arc<Type>),
+    /// `...expression`, an element of an array literal or an argument of a call
+    SpreadElement(Arc<Expression>),
 }
 
 impl Expression {
+    /// `[a, b, ...c]`
+    pub fn array(elements: Vec<Arc<Expression>>) -> Self {
+        Expression::ArrayLiteralExpression(elements)
+    }
+    /// `...self`
+    #[allow(dead_code)]
+    pub fn into_spread(self) -> Self {
+        Expression::SpreadElement(Arc::new(self))
+    }
     pub fn conditional(
-        condition: Rc<Expression>,
-        when_true: Rc<Expression>,
-        when_false: Rc<Expression>,
+        condition: Arc<Expression>,
+        when_true: Arc<Expression>,
+        when_false: Arc<Expression>,
     ) -> Self {
         ConditionalExpression::new(condition, when_true, when_false).into()
     }
     pub fn into_return_statement(self) -> Statement {
         Statement::ReturnStatement(Some(self))
     }
+    pub fn into_throw_statement(self) -> Statement {
+        Statement::Throw(self)
+    }
+    /// `self as T`
+    pub fn into_as(self, t: Type) -> Self {
+        Expression::AsExpression(Arc::new(self), Arc::new(t))
+    }
+    /// `self as const`
+    pub fn into_as_const(self) -> Self {
+        self.into_as(Type::from_id("const"))
+    }
     pub fn into_prop(self, name: &str) -> Self {
         Expression::PropertyAccessExpression(PropertyAccessExpression::new(
-            Rc::new(self),
-            Rc::new(Identifier::new(name)),
+            Arc::new(self),
+            Arc::new(Identifier::new(name)),
         ))
     }
-    #[allow(dead_code)]
-    pub fn into_method_call(self, name: &str, args: Vec<Rc<Expression>>) -> Expression {
+    /// `self?.name`
+    pub fn into_optional_prop(self, name: &str) -> Self {
+        Expression::PropertyAccessExpression(PropertyAccessExpression::new_optional_chain(
+            Arc::new(self),
+            Arc::new(Identifier::new(name)),
+        ))
+    }
+    pub fn into_method_call(self, name: &str, args: Vec<Arc<Expression>>) -> Expression {
         self.into_prop(name).into_call(args)
     }
-    pub fn into_call(self, args: Vec<Rc<Expression>>) -> Expression {
+    pub fn into_call(self, args: Vec<Arc<Expression>>) -> Expression {
         Expression::CallExpression(CallExpression {
-            expression: Rc::new(self),
+            expression: Arc::new(self),
             arguments: args,
         })
     }
-    #[allow(dead_code)]
-    pub fn into_element(self, argument: Rc<Expression>) -> Expression {
+    pub fn into_element(self, argument: Arc<Expression>) -> Expression {
         Expression::ElementAccessExpression(ElementAccessExpression {
-            expression: Rc::new(self),
+            expression: Arc::new(self),
             argument,
         })
     }
@@ -701,8 +1157,8 @@ pub(crate) trait Prop {
 
 pub(crate) trait ExpressionChain {
     fn type_of(&self) -> Expression;
-    fn and(&self, other: Rc<Expression>) -> Expression;
-    fn or(&self, other: Rc<Expression>) -> Expression;
+    fn and(&self, other: Arc<Expression>) -> Expression;
+    fn or(&self, other: Arc<Expression>) -> Expression;
     fn not(&self) -> Expression;
 }
 
@@ -711,22 +1167,22 @@ pub(crate) trait WrapableExpr {
 }
 
 pub(crate) trait MethodCall {
-    fn method_call(&self, name: &str, args: Vec<Rc<Expression>>) -> Expression;
+    fn method_call(&self, name: &str, args: Vec<Arc<Expression>>) -> Expression;
 }
 
 pub(crate) trait MethodChain {
-    fn method_chain(&self, method_calls: Vec<(&str, Vec<Rc<Expression>>)>) -> Expression;
+    fn method_chain(&self, method_calls: Vec<(&str, Vec<Arc<Expression>>)>) -> Expression;
 }
 pub(crate) trait ElementAccess {
-    fn element(&self, argument: Rc<Expression>) -> Expression;
+    fn element(&self, argument: Arc<Expression>) -> Expression;
 }
 
 pub(crate) trait Call {
-    fn call(&self, args: Vec<Rc<Expression>>) -> Expression;
+    fn call(&self, args: Vec<Arc<Expression>>) -> Expression;
 }
 
 impl<T: MethodCall> MethodChain for T {
-    fn method_chain(&self, mut method_calls: Vec<(&str, Vec<Rc<Expression>>)>) -> Expression {
+    fn method_chain(&self, mut method_calls: Vec<(&str, Vec<Arc<Expression>>)>) -> Expression {
         if method_calls.is_empty() {
             unreachable!()
         }
@@ -738,68 +1194,68 @@ impl<T: MethodCall> MethodChain for T {
 
         while !method_calls.is_empty() {
             let (method, args) = method_calls.pop().unwrap();
-            current = Rc::new(current).method_call(method, args);
+            current = Arc::new(current).method_call(method, args);
         }
         current
     }
 }
 
-impl ElementAccess for Rc<Expression> {
-    fn element(&self, argument: Rc<Expression>) -> Expression {
+impl ElementAccess for Arc<Expression> {
+    fn element(&self, argument: Arc<Expression>) -> Expression {
         Expression::ElementAccessExpression(ElementAccessExpression {
-            expression: Rc::clone(self),
+            expression: Arc::clone(self),
             argument,
         })
     }
 }
 
-impl ExpressionChain for Rc<Expression> {
-    fn and(&self, other: Rc<Expression>) -> Expression {
-        BinaryOperator::LogicalAnd.apply(Rc::clone(&self), other)
+impl ExpressionChain for Arc<Expression> {
+    fn and(&self, other: Arc<Expression>) -> Expression {
+        BinaryOperator::LogicalAnd.apply(Arc::clone(&self), other)
     }
 
-    fn or(&self, other: Rc<Expression>) -> Expression {
-        BinaryOperator::LogicalOr.apply(Rc::clone(&self), other)
+    fn or(&self, other: Arc<Expression>) -> Expression {
+        BinaryOperator::LogicalOr.apply(Arc::clone(&self), other)
     }
     fn not(&self) -> Expression {
         Expression::PrefixUnaryExpression(PrefixUnaryExpression {
             operator: UnaryOperator::Not,
-            operand: Rc::clone(self),
+            operand: Arc::clone(self),
         })
     }
 
     fn type_of(&self) -> Expression {
-        Expression::Typeof(Rc::clone(self))
+        Expression::Typeof(Arc::clone(self))
     }
 }
 
-impl WrapableExpr for Rc<Expression> {
+impl WrapableExpr for Arc<Expression> {
     fn into_parentheses(&self) -> Expression {
-        Expression::ParenthesizedExpression(Rc::clone(self))
+        Expression::ParenthesizedExpression(Arc::clone(self))
     }
 }
 
-impl MethodCall for Rc<Expression> {
-    fn method_call(&self, name: &str, args: Vec<Rc<Expression>>) -> Expression {
-        Rc::new(self.prop(name)).call(args)
+impl MethodCall for Arc<Expression> {
+    fn method_call(&self, name: &str, args: Vec<Arc<Expression>>) -> Expression {
+        Arc::new(self.prop(name)).call(args)
     }
 }
 
-impl Call for Rc<Expression> {
-    fn call(&self, args: Vec<Rc<Expression>>) -> Expression {
+impl Call for Arc<Expression> {
+    fn call(&self, args: Vec<Arc<Expression>>) -> Expression {
         Expression::CallExpression(CallExpression {
-            expression: Rc::clone(self),
+            expression: Arc::clone(self),
             arguments: args,
         })
     }
 }
 
-impl Prop for Rc<Expression> {
+impl Prop for Arc<Expression> {
     fn prop(&self, name: &str) -> Expression {
-        Expression::PropertyAccessExpression(PropertyAccessExpression {
-            expression: Rc::clone(&self),
-            name: Rc::new(Identifier::new(name)),
-        })
+        Expression::PropertyAccessExpression(PropertyAccessExpression::new(
+            Arc::clone(&self),
+            Arc::new(Identifier::new(name)),
+        ))
     }
 }
 
@@ -826,7 +1282,7 @@ impl From<usize> for Expression {
 
 impl From<&str> for Expression {
     fn from(s: &str) -> Self {
-        Self::Identifier(Rc::new(Identifier::new(s)))
+        Self::Identifier(Arc::new(Identifier::new(s)))
     }
 }
 
@@ -842,8 +1298,8 @@ impl From<NewExpression> for Expression {
     }
 }
 
-impl From<Vec<Rc<Expression>>> for Expression {
-    fn from(expressions: Vec<Rc<Expression>>) -> Self {
+impl From<Vec<Arc<Expression>>> for Expression {
+    fn from(expressions: Vec<Arc<Expression>>) -> Self {
         Self::ArrayLiteralExpression(expressions)
     }
 }
@@ -854,8 +1310,8 @@ impl From<BinaryExpression> for Expression {
     }
 }
 
-impl From<Rc<Identifier>> for Expression {
-    fn from(identifier: Rc<Identifier>) -> Self {
+impl From<Arc<Identifier>> for Expression {
+    fn from(identifier: Arc<Identifier>) -> Self {
         Self::Identifier(identifier)
     }
 }
@@ -873,82 +1329,78 @@ impl From<PropertyAccessExpression> for Expression {
 
 impl From<Identifier> for Expression {
     fn from(identifier: Identifier) -> Self {
-        Self::Identifier(Rc::new(identifier))
+        Self::Identifier(Arc::new(identifier))
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) enum VariableKind {
     Let,
     Const,
+    #[allow(dead_code)]
+    Var,
 }
 
 #[derive(Debug)]
 pub(crate) struct VariableDeclaration {
-    pub name: Rc<Identifier>,
-    pub initializer: Rc<Expression>,
-    pub var_type: Option<Rc<Type>>,
+    pub name: Arc<Identifier>,
+    pub initializer: Option<Arc<Expression>>,
+    pub var_type: Option<Arc<Type>>,
 }
 
 #[derive(Debug)]
 pub(crate) struct VariableDeclarationList {
+    pub modifiers: Vec<Modifier>,
     pub kind: VariableKind,
     pub declarations: Vec<VariableDeclaration>,
 }
 
 impl VariableDeclarationList {
-    pub fn declare_const(name: Rc<Identifier>, initializer: Expression) -> Self {
+    /// Declares a single variable, `let y: string` when there is no initializer.
+    pub fn declare(
+        kind: VariableKind,
+        name: Arc<Identifier>,
+        var_type: Option<Arc<Type>>,
+        initializer: Option<Expression>,
+    ) -> Self {
         VariableDeclarationList {
-            kind: VariableKind::Const,
+            modifiers: Vec::new(),
+            kind,
             declarations: vec![VariableDeclaration {
                 name,
-                initializer: initializer.into(),
-                var_type: None,
+                initializer: initializer.map(Arc::new),
+                var_type,
             }],
         }
     }
-    pub fn declare_typed_const(name: Rc<Identifier>, t: Rc<Type>, initializer: Expression) -> Self {
-        VariableDeclarationList {
-            kind: VariableKind::Const,
-            declarations: vec![VariableDeclaration {
-                name,
-                initializer: initializer.into(),
-                var_type: Some(t),
-            }],
-        }
+    pub fn declare_const(name: Arc<Identifier>, initializer: Expression) -> Self {
+        Self::declare(VariableKind::Const, name, None, Some(initializer))
     }
-    pub fn declare_typed_let(name: Rc<Identifier>, t: Rc<Type>, initializer: Expression) -> Self {
-        VariableDeclarationList {
-            kind: VariableKind::Let,
-            declarations: vec![VariableDeclaration {
-                name,
-                initializer: initializer.into(),
-                var_type: Some(t),
-            }],
-        }
+    pub fn declare_typed_const(name: Arc<Identifier>, t: Arc<Type>, initializer: Expression) -> Self {
+        Self::declare(VariableKind::Const, name, Some(t), Some(initializer))
     }
-    pub fn declare_let(name: Rc<Identifier>, initializer: Expression) -> Self {
-        VariableDeclarationList {
-            kind: VariableKind::Let,
-            declarations: vec![VariableDeclaration {
-                name,
-                initializer: initializer.into(),
-                var_type: None,
-            }],
-        }
+    pub fn declare_typed_let(name: Arc<Identifier>, t: Arc<Type>, initializer: Expression) -> Self {
+        Self::declare(VariableKind::Let, name, Some(t), Some(initializer))
+    }
+    pub fn exported(mut self) -> Self {
+        self.modifiers.push(Modifier::Export);
+        self
+    }
+    pub fn declare_let(name: Arc<Identifier>, initializer: Expression) -> Self {
+        Self::declare(VariableKind::Let, name, None, Some(initializer))
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct IfStatement {
-    pub expression: Rc<Expression>,
-    pub then_statement: Rc<Statement>,
-    pub else_statement: Option<Rc<Statement>>,
+    pub expression: Arc<Expression>,
+    pub then_statement: Arc<Statement>,
+    pub else_statement: Option<Arc<Statement>>,
 }
 
 #[derive(Debug)]
 pub(crate) struct Block {
-    pub statements: Vec<Rc<Statement>>,
+    pub statements: Vec<Arc<Statement>>,
 }
 
 impl Block {
@@ -967,30 +1419,30 @@ impl StatementList for Block {
 
 #[derive(Debug)]
 pub(crate) struct ForStatement {
-    pub initializer: Rc<VariableDeclarationList>,
-    pub condition: Rc<Expression>,
-    pub incrementor: Rc<Expression>,
+    pub initializer: Arc<VariableDeclarationList>,
+    pub condition: Arc<Expression>,
+    pub incrementor: Arc<Expression>,
     pub statement: Box<Statement>,
 }
 
 impl ForStatement {
-    pub fn for_each(iter_var: Rc<Identifier>, arr_expr: Rc<Expression>) -> Self {
+    pub fn for_each(iter_var: Arc<Identifier>, arr_expr: Arc<Expression>) -> Self {
         Self {
-            initializer: VariableDeclarationList::declare_let(Rc::clone(&iter_var), 0f64.into())
+            initializer: VariableDeclarationList::declare_let(Arc::clone(&iter_var), 0f64.into())
                 .into(),
             condition: BinaryOperator::LessThan
                 .apply(
-                    Expression::Identifier(Rc::clone(&iter_var)).into(),
-                    Rc::new(Expression::PropertyAccessExpression(
-                        PropertyAccessExpression {
-                            expression: Rc::clone(&arr_expr),
-                            name: Rc::new("length".into()),
-                        },
+                    Expression::Identifier(Arc::clone(&iter_var)).into(),
+                    Arc::new(Expression::PropertyAccessExpression(
+                        PropertyAccessExpression::new(
+                            Arc::clone(&arr_expr),
+                            Arc::new("length".into()),
+                        ),
                     )),
                 )
                 .into(),
             incrementor: Expression::PrefixUnaryExpression(PrefixUnaryExpression::increment(
-                Rc::clone(&iter_var),
+                Arc::clone(&iter_var),
             ))
             .into(),
             statement: Default::default(),
@@ -1026,12 +1478,12 @@ impl StatementList for ForStatement {
 
 #[derive(Debug)]
 pub(crate) struct WhileStatement {
-    pub condition: Rc<Expression>,
+    pub condition: Arc<Expression>,
     pub statement: Box<Block>,
 }
 
 impl WhileStatement {
-    pub fn new(condition: Rc<Expression>) -> Self {
+    pub fn new(condition: Arc<Expression>) -> Self {
         Self {
             condition,
             statement: Box::new(Block::new()),
@@ -1045,13 +1497,37 @@ impl StatementList for WhileStatement {
     }
 }
 
+/// `for (const key in object) { ... }`
+#[derive(Debug)]
+pub(crate) struct ForInStatement {
+    pub initializer: Arc<Identifier>,
+    pub expression: Arc<Expression>,
+    pub statement: Box<Block>,
+}
+
+impl ForInStatement {
+    pub fn new(initializer: Arc<Identifier>, expression: Arc<Expression>) -> Self {
+        Self {
+            initializer,
+            expression,
+            statement: Box::new(Block::new()),
+        }
+    }
+}
+
+impl StatementList for ForInStatement {
+    fn push_statement(&mut self, stmt: Statement) {
+        self.statement.push_statement(stmt);
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct CaseClause {
-    pub expression: Rc<Expression>,
+    pub expression: Arc<Expression>,
     pub statements: Vec<Statement>,
 }
 impl CaseClause {
-    pub fn new(expr: Rc<Expression>) -> Self {
+    pub fn new(expr: Arc<Expression>) -> Self {
         Self {
             expression: expr,
             statements: vec![],
@@ -1081,13 +1557,13 @@ impl StatementList for DefaultClause {
 }
 #[derive(Debug)]
 pub(crate) struct SwitchStatement {
-    pub expression: Rc<Expression>,
+    pub expression: Arc<Expression>,
     pub cases: Vec<CaseClause>,
     pub default: Box<DefaultClause>,
 }
 
 impl SwitchStatement {
-    pub fn new(expression: Rc<Expression>, default: DefaultClause) -> Self {
+    pub fn new(expression: Arc<Expression>, default: DefaultClause) -> Self {
         SwitchStatement {
             expression,
             cases: vec![],
@@ -1105,15 +1581,22 @@ pub(crate) enum Statement {
     EnumDeclaration(Box<EnumDeclaration>),
     InterfaceDeclaration(Box<InterfaceDeclaration>),
     FunctionDeclaration(Box<FunctionDeclaration>),
+    TypeAliasDeclaration(Box<TypeAliasDeclaration>),
+    ClassDeclaration(Box<ClassDeclaration>),
+    NamespaceDeclaration(Box<NamespaceDeclaration>),
     ReturnStatement(Option<Expression>),
-    VariableStatement(Rc<VariableDeclarationList>),
+    Throw(Expression),
+    VariableStatement(Arc<VariableDeclarationList>),
     IfStatement(IfStatement),
     Block(Block),
-    Expression(Rc<Expression>),
-    For(Rc<ForStatement>),
-    While(Rc<WhileStatement>),
+    Expression(Arc<Expression>),
+    For(Arc<ForStatement>),
+    ForIn(Box<ForInStatement>),
+    While(Arc<WhileStatement>),
     Break,
     Switch(Box<SwitchStatement>),
+    /// Code that is rendered as is.
+    Verbatim(Arc<str>),
 }
 
 impl Default for Statement {
@@ -1130,30 +1613,36 @@ impl From<SwitchStatement> for Statement {
 
 impl From<WhileStatement> for Statement {
     fn from(wh: WhileStatement) -> Self {
-        Self::While(Rc::new(wh))
+        Self::While(Arc::new(wh))
     }
 }
 
-impl From<Rc<ForStatement>> for Statement {
-    fn from(for_stmt: Rc<ForStatement>) -> Self {
+impl From<Arc<ForStatement>> for Statement {
+    fn from(for_stmt: Arc<ForStatement>) -> Self {
         Self::For(for_stmt)
     }
 }
 
+impl From<ForInStatement> for Statement {
+    fn from(for_in: ForInStatement) -> Self {
+        Self::ForIn(Box::new(for_in))
+    }
+}
+
 impl From<ForStatement> for Statement {
     fn from(for_stmt: ForStatement) -> Self {
-        Self::For(Rc::new(for_stmt))
+        Self::For(Arc::new(for_stmt))
     }
 }
 
 impl From<Expression> for Statement {
     fn from(expression: Expression) -> Self {
-        Self::Expression(Rc::new(expression))
+        Self::Expression(Arc::new(expression))
     }
 }
 
-impl From<Rc<Expression>> for Statement {
-    fn from(expression: Rc<Expression>) -> Self {
+impl From<Arc<Expression>> for Statement {
+    fn from(expression: Arc<Expression>) -> Self {
         Self::Expression(expression)
     }
 }
@@ -1172,10 +1661,15 @@ impl From<Block> for Statement {
 
 impl From<VariableDeclarationList> for Statement {
     fn from(list: VariableDeclarationList) -> Self {
-        Self::VariableStatement(Rc::new(list))
+        Self::VariableStatement(Arc::new(list))
     }
 }
 
+impl From<TypeAliasDeclaration> for Statement {
+    fn from(type_alias: TypeAliasDeclaration) -> Self {
+        Statement::TypeAliasDeclaration(Box::new(type_alias))
+    }
+}
 impl From<EnumDeclaration> for Statement {
     fn from(enum_declaration: EnumDeclaration) -> Self {
         Statement::EnumDeclaration(Box::new(enum_declaration))
@@ -1196,17 +1690,30 @@ impl From<FunctionDeclaration> for Statement {
         Statement::FunctionDeclaration(Box::new(interface_declaration))
     }
 }
+impl From<ClassDeclaration> for Statement {
+    fn from(class_declaration: ClassDeclaration) -> Self {
+        Statement::ClassDeclaration(Box::new(class_declaration))
+    }
+}
+impl From<NamespaceDeclaration> for Statement {
+    fn from(namespace_declaration: NamespaceDeclaration) -> Self {
+        Statement::NamespaceDeclaration(Box::new(namespace_declaration))
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct File {
-    pub name: Rc<str>,
+    pub name: Arc<str>,
+    /// Whether the file is written as `.d.ts` instead of `.ts`.
+    pub declaration: bool,
     pub ast: Box<SourceFile>,
 }
 
 impl File {
-    pub fn new(name: Rc<str>) -> Self {
+    pub fn new(name: Arc<str>) -> Self {
         Self {
             name,
+            declaration: false,
             ast: Box::new(SourceFile {
                 statements: Vec::new(),
             }),
@@ -1226,6 +1733,15 @@ pub(crate) enum FolderEntry {
     Folder(Box<Folder>),
 }
 
+impl FolderEntry {
+    pub fn name(&self) -> &Arc<str> {
+        match self {
+            Self::File(file) => &file.name,
+            Self::Folder(folder) => &folder.name,
+        }
+    }
+}
+
 impl From<File> for FolderEntry {
     fn from(file: File) -> Self {
         Self::File(Box::new(file))
@@ -1239,12 +1755,12 @@ impl From<Folder> for FolderEntry {
 
 #[derive(Debug)]
 pub(crate) struct Folder {
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub entries: Vec<FolderEntry>,
 }
 
 impl Folder {
-    pub fn new(name: Rc<str>) -> Self {
+    pub fn new(name: Arc<str>) -> Self {
         Self {
             name,
             entries: Vec::new(),
@@ -1256,6 +1772,65 @@ impl Folder {
     pub fn push_folder(&mut self, folder: Folder) {
         self.entries.push(folder.into());
     }
+    /// File of this folder named `name`, without the extension.
+    #[allow(dead_code)]
+    pub fn find_file(&self, name: &str) -> Option<&File> {
+        self.entries.iter().find_map(|entry| match entry {
+            FolderEntry::File(file) if &*file.name == name => Some(file.as_ref()),
+            _ => None,
+        })
+    }
+    #[allow(dead_code)]
+    pub fn find_file_mut(&mut self, name: &str) -> Option<&mut File> {
+        self.entries.iter_mut().find_map(|entry| match entry {
+            FolderEntry::File(file) if &*file.name == name => Some(file.as_mut()),
+            _ => None,
+        })
+    }
+    /// Direct subfolder named `name`.
+    pub fn find_folder(&self, name: &str) -> Option<&Folder> {
+        self.entries.iter().find_map(|entry| match entry {
+            FolderEntry::Folder(folder) if &*folder.name == name => Some(folder.as_ref()),
+            _ => None,
+        })
+    }
+    pub fn find_folder_mut(&mut self, name: &str) -> Option<&mut Folder> {
+        self.entries.iter_mut().find_map(|entry| match entry {
+            FolderEntry::Folder(folder) if &*folder.name == name => Some(folder.as_mut()),
+            _ => None,
+        })
+    }
+    /// Nested subfolder at `path`, this folder itself for an empty path.
+    #[allow(dead_code)]
+    pub fn find_folder_by_path<S: AsRef<str>>(&self, path: &[S]) -> Option<&Folder> {
+        path.iter()
+            .try_fold(self, |folder, name| folder.find_folder(name.as_ref()))
+    }
+    #[allow(dead_code)]
+    pub fn find_folder_by_path_mut<S: AsRef<str>>(&mut self, path: &[S]) -> Option<&mut Folder> {
+        path.iter()
+            .try_fold(self, |folder, name| folder.find_folder_mut(name.as_ref()))
+    }
+    /// Number of files in the folder and all of its subfolders.
+    pub fn files_count(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| match entry {
+                FolderEntry::File(_) => 1,
+                FolderEntry::Folder(folder) => folder.files_count(),
+            })
+            .sum()
+    }
+    /// Sorts the entries of the folder and of all of its subfolders by name.
+    /// The sort is stable, a file keeps its place before a folder of the same name.
+    pub fn sort_entries(&mut self) {
+        self.entries.sort_by(|a, b| a.name().cmp(b.name()));
+        for entry in self.entries.iter_mut() {
+            if let FolderEntry::Folder(folder) = entry {
+                folder.sort_entries();
+            }
+        }
+    }
 }
 
 pub(crate) struct StatementPlaceholder<'parent, P, C>
@@ -1318,3 +1893,48 @@ impl<T: StatementList + Sized> StatementPlacer for T {
         }
     }
 }
+
+#[cfg(test)]
+mod test_folder {
+    use super::*;
+
+    fn tree() -> Folder {
+        let mut inner = Folder::new("inner".into());
+        inner.push_file(File::new("types".into()));
+        let mut outer = Folder::new("outer".into());
+        outer.push_file(File::new("inner".into()));
+        outer.push_folder(inner);
+        let mut root = Folder::new("root".into());
+        root.push_folder(outer);
+        root
+    }
+
+    #[test]
+    fn it_finds_files_and_folders_by_name() {
+        let root = tree();
+        let outer = root.find_folder("outer").unwrap();
+        assert_eq!(&*outer.find_file("inner").unwrap().name, "inner");
+        assert_eq!(&*outer.find_folder("inner").unwrap().name, "inner");
+        assert!(outer.find_file("types").is_none());
+        assert!(root.find_folder("inner").is_none());
+    }
+
+    #[test]
+    fn it_finds_nested_folders_by_path() {
+        let mut root = tree();
+        assert_eq!(
+            &*root.find_folder_by_path::<&str>(&[]).unwrap().name,
+            "root"
+        );
+        let inner = root.find_folder_by_path(&["outer", "inner"]).unwrap();
+        assert!(inner.find_file("types").is_some());
+        assert!(root.find_folder_by_path(&["outer", "missing"]).is_none());
+
+        root.find_folder_by_path_mut(&["outer", "inner"])
+            .unwrap()
+            .push_file(File::new("encode".into()));
+        let inner = root.find_folder_by_path(&["outer", "inner"]).unwrap();
+        assert!(inner.find_file("encode").is_some());
+        assert!(root.find_file_mut("missing").is_none());
+    }
+}
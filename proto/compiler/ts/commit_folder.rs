@@ -1,43 +1,767 @@
 use std::{
-    fs::{create_dir, remove_dir_all},
-    io::Write,
-    path::Path,
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs::{
+        create_dir, create_dir_all, metadata, read, read_dir, read_to_string, remove_dir_all,
+        remove_file,
+    },
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
-use super::super::super::error::ProtoError;
+use sha2::{Digest, Sha256};
 
-pub(crate) fn commit_folder(folder: &super::ast::Folder) -> Result<(), ProtoError> {
-    let folder_name = folder.name.to_string();
-    let destination_path = Path::new(&folder_name);
-    if destination_path.exists() {
-        remove_dir_all(&destination_path).map_err(ProtoError::IOError)?;
+use super::{
+    super::super::error::ProtoError,
+    ast::{File, Folder, FolderEntry},
+    constants::HASH_CACHE_FILE_NAME,
+    line_diff::unified_diff,
+    parallel_map::parallel_map,
+};
+
+/// Cache entry of every written file, by its path relative to the output folder.
+type HashCache = HashMap<PathBuf, CacheEntry>;
+
+/// SHA-256 of the content of a written file,
+/// with the length and the modification time the file had on the disk afterwards.
+/// A file whose length or modification time differs was edited and is compared with the disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CacheEntry {
+    hash: [u8; 32],
+    len: u64,
+    /// Nanoseconds since the Unix epoch.
+    modified: u64,
+}
+
+/// Numbers of files touched by `commit_folder`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CommitStats {
+    /// Files created or rewritten with a new content.
+    pub written: usize,
+    /// Files left untouched, because their content did not change.
+    pub unchanged: usize,
+    /// Files removed from the disk, because they are not generated anymore.
+    pub pruned: usize,
+}
+
+/// A change `commit_folder` would make to a file of the output folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingChange {
+    /// The file is not on the disk yet.
+    Create(PathBuf),
+    /// The file is on the disk with another content,
+    /// `diff` is the unified diff from it to the generated content.
+    Update { path: PathBuf, diff: String },
+    /// The file is not generated anymore and is removed with `prune`.
+    Remove(PathBuf),
+}
+
+/// Writes the content of the folder into `dist`.
+///
+/// Files whose content on the disk is the same are not rewritten,
+/// so their modification time is preserved and file watchers are not triggered.
+/// Hashes of the written contents are kept in `.protobufts-cache.json` inside of `dist`,
+/// a file whose hash, length and modification time did not change is skipped without reading it.
+/// With `prune`, files and folders that are not part of the `folder` are removed.
+/// A `dist` that does not exist yet is written with `Folder::write_to_disk`,
+/// there is nothing to compare or prune then.
+pub(crate) fn commit_folder(
+    dist: &Path,
+    folder: &Folder,
+    prune: bool,
+) -> Result<CommitStats, ProtoError> {
+    if dist.exists() {
+        write_folder(dist, folder, prune)
+    } else {
+        write_new_folder(dist, folder)
     }
-    create_dir(destination_path).map_err(ProtoError::IOError)?;
-    destination_path
-        .canonicalize()
-        .map_err(ProtoError::IOError)?;
-    write_folder(&destination_path, folder)
 }
 
-fn write_folder(dist: &Path, folder: &super::ast::Folder) -> Result<(), ProtoError> {
+impl Folder {
+    /// Writes every file of the folder under `root`, creating the missing directories.
+    /// Unlike `commit_folder`, files are always rewritten and no hashes are kept.
+    pub fn write_to_disk(&self, root: &Path) -> io::Result<()> {
+        for (path, content) in render_folder(self) {
+            let path = root.join(path);
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+        Ok(())
+    }
+
+    /// Folders and files of the folder, one per line, drawn the way `tree` draws them.
+    pub fn display_tree(&self) -> String {
+        let mut res = format!("{}\n", self.name);
+        push_tree_entries(self, "", &mut res);
+        res
+    }
+}
+
+fn push_tree_entries(folder: &Folder, indent: &str, res: &mut String) {
+    for (i, entry) in folder.entries.iter().enumerate() {
+        let is_last = i + 1 == folder.entries.len();
+        res.push_str(indent);
+        res.push_str(if is_last { "└── " } else { "├── " });
+        match entry {
+            FolderEntry::File(file) => res.push_str(&file_name(file)),
+            FolderEntry::Folder(subfolder) => res.push_str(&subfolder.name),
+        }
+        res.push('\n');
+        if let FolderEntry::Folder(subfolder) = entry {
+            let subfolder_indent = format!("{}{}", indent, if is_last { "    " } else { "│   " });
+            push_tree_entries(subfolder, &subfolder_indent, res);
+        }
+    }
+}
+
+/// Changes `commit_folder` would make to `dist`, found without writing anything.
+/// Every file is compared with the disk, the hashes of the previous run are not trusted.
+/// Paths are relative to `dist`.
+pub(crate) fn pending_changes(dist: &Path, folder: &Folder, prune: bool) -> Vec<PendingChange> {
+    let files = render_folder(folder);
+    let mut changes = parallel_map(&files, |(path, content)| {
+        pending_change(&dist.join(path), path, content)
+    })
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    if prune {
+        collect_removed_files(dist, Path::new(""), Some(folder), &mut changes);
+    }
+    changes
+}
+
+fn pending_change(out_file_path: &Path, path: &Path, content: &str) -> Option<PendingChange> {
+    match read(out_file_path) {
+        Ok(existing_content) if existing_content == content.as_bytes() => None,
+        Ok(existing_content) => Some(PendingChange::Update {
+            path: path.to_path_buf(),
+            diff: unified_diff(
+                &slash_path(path),
+                &String::from_utf8_lossy(&existing_content),
+                content,
+            ),
+        }),
+        Err(_) => Some(PendingChange::Create(path.to_path_buf())),
+    }
+}
+
+/// Files of `dist` inside of `path` that `prune_folder` removes:
+/// the ones that are not part of the `folder`, all of them when there is no such folder.
+fn collect_removed_files(
+    dist: &Path,
+    path: &Path,
+    folder: Option<&Folder>,
+    changes: &mut Vec<PendingChange>,
+) {
+    let Ok(entries) = read_dir(dist.join(path)) else {
+        return;
+    };
+    let mut entries = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| (entry.file_name(), entry.path().is_dir()))
+        .collect::<Vec<_>>();
+    entries.sort();
+    for (name, is_dir) in entries {
+        let name = name.to_string_lossy();
+        let entry_path = path.join(&*name);
+        if is_dir {
+            let subfolder = folder.and_then(|folder| folder.find_folder(&name));
+            // a folder in place of a generated file is replaced by the file
+            if subfolder.is_some() || !is_generated_file(folder, path, &name) {
+                collect_removed_files(dist, &entry_path, subfolder, changes);
+            }
+        } else if !is_generated_file(folder, path, &name) {
+            changes.push(PendingChange::Remove(entry_path));
+        }
+    }
+}
+
+/// Whether `name` is a file of the `folder` found at `path`,
+/// the hash cache is only kept at the root of the output folder.
+fn is_generated_file(folder: Option<&Folder>, path: &Path, name: &str) -> bool {
+    let Some(folder) = folder else {
+        return false;
+    };
+    (name == HASH_CACHE_FILE_NAME && path.as_os_str().is_empty())
+        || folder.entries.iter().any(|entry| match entry {
+            FolderEntry::File(file) => file_name(file) == name,
+            FolderEntry::Folder(_) => false,
+        })
+}
+
+/// `pkg/a.ts`, the path with `/` separators on every platform.
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Renders every file of the folder without touching the disk.
+/// Paths are relative to the folder itself.
+pub(crate) fn render_folder(folder: &Folder) -> Vec<(PathBuf, String)> {
+    let mut files = Vec::new();
+    collect_files(Path::new(""), folder, &mut files);
+    let contents = parallel_map(&files, |(_, file)| String::from(*file));
+    files
+        .into_iter()
+        .map(|(path, _)| path)
+        .zip(contents)
+        .collect()
+}
+
+fn collect_files<'folder>(
+    dist: &Path,
+    folder: &'folder Folder,
+    files: &mut Vec<(PathBuf, &'folder File)>,
+) {
     for entry in &folder.entries {
         match entry {
-            super::ast::FolderEntry::Folder(subfolder) => {
-                let destination_path = dist.join(&subfolder.name.to_string());
-                create_dir(&destination_path).map_err(ProtoError::IOError)?;
-                write_folder(&destination_path, subfolder)?;
+            FolderEntry::Folder(subfolder) => {
+                collect_files(&dist.join(subfolder.name.to_string()), subfolder, files)
             }
-            super::ast::FolderEntry::File(file) => {
-                let out_file_path = dist.join(format!("{}.ts", &file.name));
-                let mut out_file =
-                    std::fs::File::create(out_file_path).map_err(ProtoError::IOError)?;
-                let content: String = file.as_ref().into();
-                out_file
-                    .write_all(content.as_bytes())
-                    .map_err(ProtoError::IOError)?;
+            FolderEntry::File(file) => files.push((dist.join(file_name(file)), file)),
+        }
+    }
+}
+
+/// Creates the directory tree first, then renders and writes the files in parallel.
+fn write_folder(dist: &Path, folder: &Folder, prune: bool) -> Result<CommitStats, ProtoError> {
+    let mut stats = CommitStats::default();
+    create_folders(dist, folder, prune, true, &mut stats)?;
+
+    let files = render_folder(folder);
+    let cache_path = dist.join(HASH_CACHE_FILE_NAME);
+    let cache = read_hash_cache(&cache_path);
+    let mut new_cache = HashCache::new();
+    let mut errors = Vec::new();
+    for ((path, _), res) in files.iter().zip(parallel_map(&files, |(path, content)| {
+        write_file(&dist.join(path), path, content, &cache)
+    })) {
+        match res {
+            Ok((written, entry)) => {
+                if written {
+                    stats.written += 1;
+                } else {
+                    stats.unchanged += 1;
+                }
+                if let Some(entry) = entry {
+                    new_cache.insert(path.clone(), entry);
+                }
             }
+            Err(e) => errors.push(e),
         }
     }
+    if !errors.is_empty() {
+        return Err(ProtoError::from_errors(errors));
+    }
+    if new_cache != cache {
+        write_hash_cache(&cache_path, &new_cache)?;
+    }
 
+    Ok(stats)
+}
+
+/// Writes the folder into a `dist` that does not exist yet,
+/// the hash cache is built from the written files.
+fn write_new_folder(dist: &Path, folder: &Folder) -> Result<CommitStats, ProtoError> {
+    folder.write_to_disk(dist).map_err(ProtoError::IOError)?;
+
+    let mut files = Vec::new();
+    collect_files(Path::new(""), folder, &mut files);
+    let entries = parallel_map(&files, |(path, _)| {
+        let out_file_path = dist.join(path);
+        let content = read(&out_file_path)?;
+        Ok(cache_entry(&out_file_path, Sha256::digest(content).into()))
+    });
+    let mut cache = HashCache::new();
+    for ((path, _), entry) in files.iter().zip(entries) {
+        if let Some(entry) = entry.map_err(ProtoError::IOError)? {
+            cache.insert(path.clone(), entry);
+        }
+    }
+    write_hash_cache(&dist.join(HASH_CACHE_FILE_NAME), &cache)?;
+
+    Ok(CommitStats {
+        written: files.len(),
+        ..CommitStats::default()
+    })
+}
+
+/// Reads the cache entries of the previous run.
+/// A missing or malformed cache is treated as empty,
+/// the files are compared with the disk then.
+fn read_hash_cache(cache_path: &Path) -> HashCache {
+    let mut cache = HashCache::new();
+    let Ok(content) = read_to_string(cache_path) else {
+        return cache;
+    };
+    let Ok(serde_json::Value::Object(entries)) = serde_json::from_str(&content) else {
+        return cache;
+    };
+    for (path, entry) in entries {
+        if let Some(entry) = parse_cache_entry(&entry) {
+            cache.insert(PathBuf::from(path), entry);
+        }
+    }
+    cache
+}
+
+fn parse_cache_entry(entry: &serde_json::Value) -> Option<CacheEntry> {
+    Some(CacheEntry {
+        hash: parse_hash(entry.get("hash")?.as_str()?)?,
+        len: entry.get("len")?.as_u64()?,
+        modified: entry.get("modified")?.as_u64()?,
+    })
+}
+
+/// Writes the cache as a JSON object with `/` separated paths as keys
+/// and objects of the hex encoded hash, the length and the modification time as values.
+fn write_hash_cache(cache_path: &Path, cache: &HashCache) -> Result<(), ProtoError> {
+    let entries = cache
+        .iter()
+        .map(|(path, entry)| {
+            (
+                slash_path(path),
+                serde_json::json!({
+                    "hash": format_hash(&entry.hash),
+                    "len": entry.len,
+                    "modified": entry.modified,
+                }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+    let content = serde_json::to_string_pretty(&entries).unwrap();
+    std::fs::write(cache_path, content).map_err(ProtoError::IOError)
+}
+
+fn content_hash(content: &str) -> [u8; 32] {
+    Sha256::digest(content.as_bytes()).into()
+}
+
+/// The length and the modification time of the file on the disk,
+/// `None` when it is missing or the platform does not report the time.
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((metadata.len(), u64::try_from(modified.as_nanos()).ok()?))
+}
+
+/// The entry of the file as it is on the disk now,
+/// `None` when its length or modification time is unknown.
+fn cache_entry(path: &Path, hash: [u8; 32]) -> Option<CacheEntry> {
+    let (len, modified) = file_stamp(path)?;
+    Some(CacheEntry {
+        hash,
+        len,
+        modified,
+    })
+}
+
+fn format_hash(hash: &[u8; 32]) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn parse_hash(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+fn create_folders(
+    dist: &Path,
+    folder: &Folder,
+    prune: bool,
+    is_root: bool,
+    stats: &mut CommitStats,
+) -> Result<(), ProtoError> {
+    if !dist.is_dir() {
+        if dist.exists() {
+            remove_file(dist).map_err(ProtoError::IOError)?;
+            stats.pruned += 1;
+        }
+        create_dir(dist).map_err(ProtoError::IOError)?;
+    } else if prune {
+        stats.pruned += prune_folder(dist, folder, is_root)?;
+    }
+    for entry in &folder.entries {
+        match entry {
+            FolderEntry::Folder(subfolder) => {
+                let destination_path = dist.join(subfolder.name.to_string());
+                create_folders(&destination_path, subfolder, prune, false, stats)?;
+            }
+            FolderEntry::File(file) => {
+                let out_file_path = dist.join(file_name(file));
+                if out_file_path.is_dir() {
+                    stats.pruned += remove_folder(&out_file_path)?;
+                }
+            }
+        }
+    }
     Ok(())
 }
+
+fn file_name(file: &File) -> String {
+    if file.declaration {
+        format!("{}.d.ts", &file.name)
+    } else {
+        format!("{}.ts", &file.name)
+    }
+}
+
+/// Removes entries of `dist` that are not part of the `folder`
+/// and returns the number of removed files.
+/// The hash cache is only expected in the root of the output folder.
+fn prune_folder(dist: &Path, folder: &Folder, is_root: bool) -> Result<usize, ProtoError> {
+    let mut expected_folders = HashSet::new();
+    let mut expected_files = HashSet::new();
+    for entry in &folder.entries {
+        match entry {
+            FolderEntry::Folder(subfolder) => {
+                expected_folders.insert(OsString::from(subfolder.name.to_string()))
+            }
+            FolderEntry::File(file) => expected_files.insert(OsString::from(file_name(file))),
+        };
+    }
+    if is_root {
+        expected_files.insert(OsString::from(HASH_CACHE_FILE_NAME));
+    }
+
+    let mut pruned = 0;
+    for entry in read_dir(dist).map_err(ProtoError::IOError)? {
+        let entry = entry.map_err(ProtoError::IOError)?;
+        let path = entry.path();
+        let name = entry.file_name();
+        if path.is_dir() {
+            if !expected_folders.contains(&name) {
+                pruned += remove_folder(&path)?;
+            }
+        } else if !expected_files.contains(&name) {
+            remove_file(&path).map_err(ProtoError::IOError)?;
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+/// Removes the folder with all of its content and returns the number of removed files.
+fn remove_folder(path: &Path) -> Result<usize, ProtoError> {
+    let mut files_count = 0;
+    for entry in read_dir(path).map_err(ProtoError::IOError)? {
+        let entry_path = entry.map_err(ProtoError::IOError)?.path();
+        if entry_path.is_dir() {
+            files_count += remove_folder(&entry_path)?;
+        } else {
+            files_count += 1;
+        }
+    }
+    remove_dir_all(path).map_err(ProtoError::IOError)?;
+    Ok(files_count)
+}
+
+/// Writes the rendered file unless the cache has the same hash, length and modification time
+/// for it or the disk already has the same content.
+/// Returns whether the file was written and its new cache entry.
+/// `path` is the path of the file relative to the output folder, the key of its cache entry.
+fn write_file(
+    out_file_path: &Path,
+    path: &Path,
+    content: &str,
+    cache: &HashCache,
+) -> Result<(bool, Option<CacheEntry>), ProtoError> {
+    let hash = content_hash(content);
+    let cached = cache.get(path);
+    if let Some(cached) = cached.filter(|cached| cached.hash == hash) {
+        if file_stamp(out_file_path) == Some((cached.len, cached.modified)) {
+            return Ok((false, Some(*cached)));
+        }
+    }
+    if let Ok(existing_content) = read(out_file_path) {
+        if existing_content == content.as_bytes() {
+            return Ok((false, cache_entry(out_file_path, hash)));
+        }
+    }
+    let mut out_file = std::fs::File::create(out_file_path).map_err(ProtoError::IOError)?;
+    out_file
+        .write_all(content.as_bytes())
+        .map_err(ProtoError::IOError)?;
+    Ok((true, cache_entry(out_file_path, hash)))
+}
+
+#[cfg(test)]
+mod test_commit_folder {
+    use std::{fs, time::SystemTime};
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "protos-ts-{}-{}-{}",
+            name,
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sample_folder(with_b: bool) -> Folder {
+        let mut nested = Folder::new("nested".into());
+        nested.push_file(File::new("a".into()));
+        if with_b {
+            nested.push_file(File::new("b".into()));
+        }
+        let mut folder = Folder::new("out".into());
+        folder.push_folder(nested);
+        folder.push_file(File::new("index".into()));
+        folder
+    }
+
+    #[test]
+    fn it_skips_unchanged_files() {
+        let dir = temp_dir("unchanged");
+        let out = dir.join("out");
+        let first = write_folder(&out, &sample_folder(true), false).unwrap();
+        assert_eq!(
+            first,
+            CommitStats {
+                written: 3,
+                unchanged: 0,
+                pruned: 0
+            }
+        );
+        let mtime = fs::metadata(out.join("nested/a.ts"))
+            .unwrap()
+            .modified()
+            .unwrap();
+
+        let second = write_folder(&out, &sample_folder(true), false).unwrap();
+        assert_eq!(
+            second,
+            CommitStats {
+                written: 0,
+                unchanged: 3,
+                pruned: 0
+            }
+        );
+        assert_eq!(
+            fs::metadata(out.join("nested/a.ts"))
+                .unwrap()
+                .modified()
+                .unwrap(),
+            mtime
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_prunes_stale_files_only_when_asked() {
+        let dir = temp_dir("prune");
+        let out = dir.join("out");
+        write_folder(&out, &sample_folder(true), false).unwrap();
+        fs::create_dir(out.join("stale")).unwrap();
+        fs::write(out.join("stale/x.ts"), "").unwrap();
+
+        let kept = write_folder(&out, &sample_folder(false), false).unwrap();
+        assert_eq!(kept.pruned, 0);
+        assert!(out.join("nested/b.ts").exists());
+
+        let pruned = write_folder(&out, &sample_folder(false), true).unwrap();
+        assert_eq!(
+            pruned,
+            CommitStats {
+                written: 0,
+                unchanged: 2,
+                pruned: 2
+            }
+        );
+        assert!(!out.join("nested/b.ts").exists());
+        assert!(!out.join("stale").exists());
+        assert!(out.join("nested/a.ts").exists());
+        assert!(out.join(HASH_CACHE_FILE_NAME).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_skips_cached_files_unless_they_changed_on_disk() {
+        let dir = temp_dir("hash-cache");
+        let out = dir.join("out");
+        write_folder(&out, &sample_folder(true), false).unwrap();
+        let cache = read_hash_cache(&out.join(HASH_CACHE_FILE_NAME));
+        assert_eq!(cache.len(), 3);
+        let a_content = String::from(&File::new("a".into()));
+        let a_entry = cache[Path::new("nested/a.ts")];
+        assert_eq!(a_entry.hash, content_hash(&a_content));
+        assert_eq!(a_entry.len, a_content.len() as u64);
+        assert_eq!(
+            file_stamp(&out.join("nested/a.ts")),
+            Some((a_entry.len, a_entry.modified))
+        );
+
+        // the length of the edited file differs from the cached one, so it is restored
+        fs::write(out.join("nested/a.ts"), "edited").unwrap();
+        let second = write_folder(&out, &sample_folder(true), false).unwrap();
+        assert_eq!(second.written, 1);
+        assert_eq!(second.unchanged, 2);
+        assert_eq!(
+            fs::read_to_string(out.join("nested/a.ts")).unwrap(),
+            a_content
+        );
+        let cache = read_hash_cache(&out.join(HASH_CACHE_FILE_NAME));
+        assert_eq!(cache[Path::new("nested/a.ts")].hash, a_entry.hash);
+
+        fs::remove_file(out.join(HASH_CACHE_FILE_NAME)).unwrap();
+        let third = write_folder(&out, &sample_folder(true), false).unwrap();
+        assert_eq!(third.written, 0);
+        assert_eq!(third.unchanged, 3);
+        assert_eq!(read_hash_cache(&out.join(HASH_CACHE_FILE_NAME)), cache);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_writes_folders_to_disk() {
+        let dir = temp_dir("write-to-disk");
+        let out = dir.join("out");
+        sample_folder(true).write_to_disk(&out).unwrap();
+        assert_eq!(
+            fs::read_to_string(out.join("nested/b.ts")).unwrap(),
+            String::from(&File::new("b".into()))
+        );
+        assert!(out.join("nested/a.ts").exists());
+        assert!(out.join("index.ts").exists());
+        assert!(!out.join(HASH_CACHE_FILE_NAME).exists());
+
+        fs::write(out.join("index.ts"), "edited").unwrap();
+        sample_folder(false).write_to_disk(&out).unwrap();
+        assert_eq!(
+            fs::read_to_string(out.join("index.ts")).unwrap(),
+            String::from(&File::new("index".into()))
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_keeps_hashes_of_new_output_folders() {
+        let dir = temp_dir("new-folder");
+        let out = dir.join("out");
+        let first = commit_folder(&out, &sample_folder(true), true).unwrap();
+        assert_eq!(
+            first,
+            CommitStats {
+                written: 3,
+                unchanged: 0,
+                pruned: 0
+            }
+        );
+        let cache = read_hash_cache(&out.join(HASH_CACHE_FILE_NAME));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(
+            cache[Path::new("nested/b.ts")].hash,
+            content_hash(&String::from(&File::new("b".into())))
+        );
+
+        let second = commit_folder(&out, &sample_folder(true), true).unwrap();
+        assert_eq!(second.written, 0);
+        assert_eq!(second.unchanged, 3);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_expects_the_hash_cache_only_at_the_root() {
+        let dir = temp_dir("prune-nested-cache");
+        let out = dir.join("out");
+        commit_folder(&out, &sample_folder(true), true).unwrap();
+        fs::write(out.join("nested").join(HASH_CACHE_FILE_NAME), "{}").unwrap();
+
+        let changes = pending_changes(&out, &sample_folder(true), true);
+        assert_eq!(
+            changes,
+            vec![PendingChange::Remove(
+                Path::new("nested").join(HASH_CACHE_FILE_NAME)
+            )]
+        );
+        let stats = commit_folder(&out, &sample_folder(true), true).unwrap();
+        assert_eq!(stats.pruned, 1);
+        assert!(!out.join("nested").join(HASH_CACHE_FILE_NAME).exists());
+        assert!(out.join(HASH_CACHE_FILE_NAME).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_ignores_malformed_hash_caches() {
+        let dir = temp_dir("hash-cache-malformed");
+        fs::write(dir.join("cache.json"), "{\"a.ts\": \"abc\", \"b.ts\": 1").unwrap();
+        assert!(read_hash_cache(&dir.join("cache.json")).is_empty());
+        fs::write(dir.join("cache.json"), "{\"a.ts\": \"abc\"}").unwrap();
+        assert!(read_hash_cache(&dir.join("cache.json")).is_empty());
+        let hash = format_hash(&content_hash("export {}"));
+        fs::write(
+            dir.join("cache.json"),
+            format!("{{\"a.ts\": \"{hash}\", \"b.ts\": {{\"hash\": \"{hash}\", \"len\": 9}}}}"),
+        )
+        .unwrap();
+        assert!(read_hash_cache(&dir.join("cache.json")).is_empty());
+        assert!(read_hash_cache(&dir.join("missing.json")).is_empty());
+
+        let hash = content_hash("export {}");
+        assert_eq!(parse_hash(&format_hash(&hash)), Some(hash));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_renders_files_with_relative_paths() {
+        let rendered = render_folder(&sample_folder(true))
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rendered,
+            vec![
+                PathBuf::from("nested/a.ts"),
+                PathBuf::from("nested/b.ts"),
+                PathBuf::from("index.ts")
+            ]
+        );
+    }
+
+    #[test]
+    fn it_displays_the_tree_of_folders_and_files() {
+        let mut declaration = File::new("user".into());
+        declaration.declaration = true;
+        let mut types = Folder::new("types".into());
+        types.push_file(declaration);
+        let mut nested = Folder::new("nested".into());
+        nested.push_file(File::new("a".into()));
+        nested.push_folder(types);
+        nested.push_file(File::new("b".into()));
+        let mut folder = Folder::new("out".into());
+        folder.push_folder(nested);
+        folder.push_file(File::new("index".into()));
+        assert_eq!(
+            folder.display_tree(),
+            [
+                "out",
+                "├── nested",
+                "│   ├── a.ts",
+                "│   ├── types",
+                "│   │   └── user.d.ts",
+                "│   └── b.ts",
+                "└── index.ts",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
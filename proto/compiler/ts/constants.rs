@@ -1,6 +1,23 @@
 pub(super) const PROTOBUF_MODULE: &'static str = "protobufjs/minimal";
+pub(super) const GRPC_MODULE: &str = "@grpc/grpc-js";
 pub(super) const ENCODE_FUNCTION_NAME: &'static str = "encode";
 pub(super) const DECODE_FUNCTION_NAME: &'static str = "decode";
+pub(super) const ENCODE_DELIMITED_FUNCTION_NAME: &str = "encodeDelimited";
+pub(super) const DECODE_DELIMITED_FUNCTION_NAME: &str = "decodeDelimited";
+pub(super) const CREATE_FUNCTION_NAME: &'static str = "create";
+pub(super) const TO_JSON_FUNCTION_NAME: &'static str = "toJSON";
+pub(super) const FROM_JSON_FUNCTION_NAME: &'static str = "fromJSON";
+pub(super) const GUARD_FUNCTION_NAME: &str = "is";
+pub(super) const EQUALS_FUNCTION_NAME: &str = "equals";
+/// Property of decoded messages with the fields unknown to the schema, with `--keep-unknown-fields`.
+pub(super) const UNKNOWN_FIELDS_PROPERTY: &str = "$unknown";
+pub(super) const MANIFEST_FILE_NAME: &'static str = "manifest";
+pub(super) const COMPATIBILITY_FILE_NAME: &'static str = "compatibility";
+pub(super) const RUNTIME_FILE_NAME: &str = "runtime";
+pub(super) const WKT_FILE_NAME: &str = "wkt";
+pub(super) const TRANSPORT_FILE_NAME: &str = "transport";
+pub(super) const INDEX_FILE_NAME: &str = "index";
+pub(super) const HASH_CACHE_FILE_NAME: &str = ".protobufts-cache.json";
 
 // {
 //     long: {
@@ -0,0 +1,567 @@
+use std::sync::Arc;
+
+use crate::proto::{
+    error::ProtoError,
+    package::{self, MessageEntry},
+    proto_scope::{root_scope::RootScope, ProtoScope},
+};
+
+use super::{
+    ast::{self, Folder, MethodCall, ObjectLiteralMember, StatementList, Type},
+    constants::CREATE_FUNCTION_NAME,
+    ensure_import::ensure_import,
+    enum_values::enum_member,
+    has_fields::{has_field, has_field_name},
+    int64_type::is_bigint,
+    layout::{declaration_file_path, declaration_name, function_name, import_declaration},
+    options::Options,
+    renames::property_name,
+    runtime::runtime_module,
+    ts_path::TsPathComponent,
+};
+
+/// Emits `create.ts` with a `create(init?)` factory
+/// that returns a message with every field set to its proto3 default.
+/// Fields of `init` are spread over the defaults.
+pub(super) fn compile_create(
+    root: &RootScope,
+    options: &Options,
+    message_folder: &mut Folder,
+    message_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    let mut file = ast::File::new("create".into());
+
+    let init_parameter_id: Arc<ast::Identifier> = Arc::new("init".into());
+    let message_var_id: Arc<ast::Identifier> = Arc::new("message".into());
+
+    let message_id = message_scope.id().unwrap();
+    let message_type_id = import_declaration(
+        &mut file,
+        &declaration_file_path(root, options, message_id, "create"),
+        &declaration_file_path(root, options, message_id, "types"),
+        TsPathComponent::Interface(declaration_name(root, options, message_id)),
+        None,
+    );
+
+    let mut create_func = ast::FunctionDeclaration::new_exported(&function_name(
+        root,
+        options,
+        message_id,
+        CREATE_FUNCTION_NAME,
+    ));
+    create_func.add_param(ast::Parameter::new_optional(
+        &init_parameter_id,
+        Type::generic(
+            "Partial",
+            vec![Type::reference(Arc::clone(&message_type_id))],
+        ),
+    ));
+    create_func.returns(Type::reference(Arc::clone(&message_type_id)));
+
+    let mut members = get_default_members(root, options, message_scope, &mut file)?;
+    members.extend(has_field_members(
+        root,
+        options,
+        message_scope,
+        &init_parameter_id,
+    ));
+    members.push(Arc::new(ObjectLiteralMember::SpreadAssignment(Arc::new(
+        init_parameter_id.into(),
+    ))));
+
+    create_func.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Arc::clone(&message_var_id),
+            Type::Any.into(),
+            ast::Expression::ObjectLiteralExpression(members),
+        )
+        .into(),
+    );
+
+    create_func.push_statement(ast::Expression::from(message_var_id).into_return_statement());
+
+    file.push_statement(create_func.into());
+
+    message_folder.push_file(file);
+    Ok(())
+}
+
+/// Object literal members with defaults for every field of the message,
+/// the proto2 `default` option of a field replaces the proto3 default of its type.
+/// Oneof options and optional fields without the `default` option are left out,
+/// so none of them is considered set.
+fn get_default_members(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+) -> Result<Vec<Arc<ObjectLiteralMember>>, ProtoError> {
+    let message_declaration = match message_scope {
+        ProtoScope::Message(m) => m,
+        _ => unreachable!(),
+    };
+    let mut fields = message_declaration
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            MessageEntry::Field(f) if !f.optional || f.default_value().is_some() => Some(f),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    fields.sort_by_key(|f| f.tag);
+
+    let mut members = Vec::new();
+    for field in fields {
+        let default_value = match field.default_value() {
+            Some(value) => explicit_default_value(
+                root,
+                options,
+                message_scope,
+                file,
+                &field.field_type,
+                &value,
+            )
+            .ok_or_else(|| ProtoError::InvalidDefaultValue {
+                message_name: root
+                    .get_full_name(message_scope.id().unwrap())
+                    .map(|name| name.to_string())
+                    .unwrap_or_default(),
+                field_name: field.name.to_string(),
+                value: value.to_string(),
+            })?,
+            None => default_value(root, options, message_scope, file, &field.field_type),
+        };
+        members.push(ObjectLiteralMember::property(
+            property_name(root, options, message_scope.id().unwrap(), field),
+            default_value,
+        ));
+    }
+    Ok(members)
+}
+
+/// `hasAge: init?.age !== undefined` for the fields with `has` properties,
+/// the fields of `init` are the fields set before decoding.
+fn has_field_members(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    init_parameter_id: &Arc<ast::Identifier>,
+) -> Vec<Arc<ObjectLiteralMember>> {
+    let message_id = message_scope.id().unwrap();
+    let mut fields = message_scope
+        .get_message_declaration()
+        .map(|d| d.get_fields())
+        .unwrap_or_default();
+    fields.retain(|f| has_field(options, f));
+    fields
+        .into_iter()
+        .map(|field| {
+            let is_set = ast::BinaryOperator::StrictNotEqual.apply(
+                ast::Expression::from(Arc::clone(init_parameter_id))
+                    .into_optional_prop(&property_name(root, options, message_id, field))
+                    .into(),
+                ast::Expression::Undefined.into(),
+            );
+            ObjectLiteralMember::property(has_field_name(root, options, message_id, field), is_set)
+        })
+        .collect()
+}
+
+/// Value of the proto2 `default` option of a field,
+/// `None` if the value does not fit the type of the field.
+fn explicit_default_value(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    field_type: &package::Type,
+    value: &str,
+) -> Option<ast::Expression> {
+    match field_type {
+        package::Type::Enum(e_id) => {
+            let is_member = root
+                .get_declaration(*e_id)
+                .is_some_and(|scope| match &*scope {
+                    ProtoScope::Enum(e) => e.entries.iter().any(|entry| &*entry.name == value),
+                    _ => false,
+                });
+            if !is_member {
+                return None;
+            }
+            Some(enum_member(
+                root,
+                options,
+                message_scope,
+                file,
+                *e_id,
+                value,
+            ))
+        }
+        package::Type::Message(_) | package::Type::Repeated(_) | package::Type::Map(_, _) => None,
+        package::Type::Bool => match value {
+            "true" => Some(ast::Expression::True),
+            "false" => Some(ast::Expression::False),
+            _ => None,
+        },
+        package::Type::String => Some(ast::StringLiteral::new(value.into()).into()),
+        package::Type::Bytes => {
+            let mut bytes = ast::NewExpression::new(Arc::new("Uint8Array".into()));
+            bytes.add_argument(Arc::new(ast::Expression::ArrayLiteralExpression(
+                value
+                    .bytes()
+                    .map(|byte| Arc::new((byte as i32).into()))
+                    .collect(),
+            )));
+            Some(bytes.into())
+        }
+        package::Type::Double | package::Type::Float => {
+            value.parse::<f64>().ok().map(ast::Expression::from)
+        }
+        t if is_bigint(options, t) => {
+            value.parse::<i128>().ok()?;
+            Some(big_int(ast::StringLiteral::new(value.into()).into()))
+        }
+        t if t.long_wire_type().is_some() => {
+            let number = value.parse::<i128>().ok()?;
+            let runtime = runtime_module(root, options, message_scope.id().unwrap(), &file.name);
+            ensure_import(
+                file,
+                ast::ImportDeclaration::import(
+                    vec![ast::ImportSpecifier::new(Arc::new("util".into()))],
+                    runtime.as_ref().into(),
+                ),
+            );
+            let long_expr: Arc<ast::Expression> =
+                Arc::new(ast::Expression::from("util").into_prop("Long"));
+            let unsigned = match t {
+                package::Type::Uint64 | package::Type::Fixed64 => ast::Expression::True,
+                _ => ast::Expression::False,
+            };
+            Some(ast::Expression::conditional(
+                Arc::clone(&long_expr),
+                long_expr
+                    .method_call(
+                        "fromString",
+                        vec![
+                            Arc::new(ast::StringLiteral::new(value.into()).into()),
+                            unsigned.into(),
+                        ],
+                    )
+                    .into(),
+                Arc::new((number as f64).into()),
+            ))
+        }
+        _ => value.parse::<i64>().ok().map(ast::Expression::from),
+    }
+}
+
+/// proto3 default of a field of `field_type`, `undefined` for messages.
+pub(super) fn default_value(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    field_type: &package::Type,
+) -> ast::Expression {
+    match field_type {
+        package::Type::Enum(e_id) => {
+            let first_member = root.get_declaration(*e_id).and_then(|scope| match &*scope {
+                ProtoScope::Enum(e) => e
+                    .entries
+                    .iter()
+                    .find(|entry| entry.value == 0)
+                    .or_else(|| e.entries.first())
+                    .map(|entry| Arc::clone(&entry.name)),
+                _ => None,
+            });
+            match first_member {
+                Some(member_name) => {
+                    enum_member(root, options, message_scope, file, *e_id, &member_name)
+                }
+                None => 0f64.into(),
+            }
+        }
+        t if is_bigint(options, t) => big_int(0.into()),
+        t => {
+            let runtime = runtime_module(root, options, message_scope.id().unwrap(), &file.name);
+            basic_default_value(file, &runtime, t)
+        }
+    }
+}
+
+/// `BigInt(value)`
+fn big_int(value: ast::Expression) -> ast::Expression {
+    ast::Expression::from("BigInt").into_call(vec![value.into()])
+}
+
+/// Default value of a field of any type except enums.
+fn basic_default_value(
+    file: &mut ast::File,
+    runtime: &str,
+    field_type: &package::Type,
+) -> ast::Expression {
+    match field_type {
+        package::Type::Enum(_) => unreachable!(),
+        package::Type::Message(_) => ast::Expression::Undefined,
+        package::Type::Repeated(_) => ast::Expression::array(vec![]),
+        package::Type::Map(_, _) => ast::Expression::ObjectLiteralExpression(vec![]),
+        package::Type::Bytes => {
+            let mut empty_bytes = ast::NewExpression::new(Arc::new("Uint8Array".into()));
+            empty_bytes.add_argument(Arc::new(0.into()));
+            empty_bytes.into()
+        }
+        t if t.long_wire_type().is_some() => {
+            ensure_import(
+                file,
+                ast::ImportDeclaration::import(
+                    vec![ast::ImportSpecifier::new(Arc::new("util".into()))],
+                    runtime.into(),
+                ),
+            );
+            let long_expr: Arc<ast::Expression> =
+                Arc::new(ast::Expression::from("util").into_prop("Long"));
+            let unsigned = match t {
+                package::Type::Uint64 | package::Type::Fixed64 => ast::Expression::True,
+                _ => ast::Expression::False,
+            };
+            ast::Expression::conditional(
+                Arc::clone(&long_expr),
+                long_expr
+                    .method_call(
+                        "fromBits",
+                        vec![Arc::new(0.into()), Arc::new(0.into()), unsigned.into()],
+                    )
+                    .into(),
+                Arc::new(0.into()),
+            )
+        }
+        t => t.default_expression(),
+    }
+}
+
+#[cfg(test)]
+mod test_basic_default_value {
+    use super::*;
+    use crate::proto::compiler::ts::constants::PROTOBUF_MODULE;
+
+    fn render_default(field_type: package::Type) -> String {
+        let mut file = ast::File::new("create".into());
+        let expr = basic_default_value(&mut file, PROTOBUF_MODULE, &field_type);
+        String::from(&expr)
+    }
+
+    #[test]
+    fn it_returns_proto3_defaults_for_scalars() {
+        assert_eq!(render_default(package::Type::Bool), "false");
+        assert_eq!(render_default(package::Type::String), "\"\"");
+        assert_eq!(render_default(package::Type::Bytes), "new Uint8Array(0)");
+        for t in [
+            package::Type::Double,
+            package::Type::Float,
+            package::Type::Int32,
+            package::Type::Uint32,
+            package::Type::Sint32,
+            package::Type::Fixed32,
+            package::Type::Sfixed32,
+        ] {
+            assert_eq!(render_default(t), "0");
+        }
+    }
+
+    #[test]
+    fn it_returns_long_zero_for_64_bit_integers() {
+        for (t, unsigned) in [
+            (package::Type::Int64, false),
+            (package::Type::Sint64, false),
+            (package::Type::Sfixed64, false),
+            (package::Type::Uint64, true),
+            (package::Type::Fixed64, true),
+        ] {
+            let mut file = ast::File::new("create".into());
+            let expr = basic_default_value(&mut file, PROTOBUF_MODULE, &t);
+            assert_eq!(
+                String::from(&expr),
+                format!("util.Long ? util.Long.fromBits(0, 0, {}) : 0", unsigned)
+            );
+            assert_eq!(
+                String::from(&file),
+                "import { util } from \"protobufjs/minimal\"\n"
+            );
+        }
+    }
+
+    #[test]
+    fn it_returns_empty_containers() {
+        let element = Arc::new(package::Type::Int32);
+        assert_eq!(
+            render_default(package::Type::Repeated(Arc::clone(&element))),
+            "[]"
+        );
+        assert_eq!(
+            render_default(package::Type::Map(Arc::new(package::Type::String), element)),
+            "{}"
+        );
+        assert_eq!(render_default(package::Type::Message(1)), "undefined");
+    }
+}
+
+#[cfg(test)]
+mod test_proto2 {
+    use super::super::test_utils::{compile_sources, render_file_at};
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "legacy/user.proto",
+            r#"
+            syntax = "proto2";
+            package legacy;
+            enum Role {
+                GUEST = 1;
+                ADMIN = 2;
+            }
+            message User {
+                required string name = 1;
+                optional int32 age = 2 [default = 18];
+                optional Role role = 3 [default = ADMIN];
+                optional double score = 4 [default = -inf];
+                optional int64 balance = 5 [default = -100];
+                optional string nick = 6;
+                required Role main_role = 7;
+            }
+            "#,
+        ),
+        (
+            "app/account.proto",
+            r#"
+            syntax = "proto3";
+            package app;
+            import "legacy/user.proto";
+            message Account {
+                legacy.User owner = 1;
+                string id = 2;
+            }
+            "#,
+        ),
+    ];
+
+    #[test]
+    fn it_uses_default_options_in_create() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let create = render_file_at(&folder, &["legacy", "user", "User", "create"]);
+        assert!(create.contains(
+            "    name: \"\",\n    age: 18,\n    role: Role.ADMIN,\n    score: -Infinity,\n    balance: util.Long ? util.Long.fromString(\"-100\", false) : -100,\n    main_role: Role.GUEST,\n    ...init,\n"
+        ));
+    }
+
+    #[test]
+    fn it_checks_required_fields_in_decode() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let decode = render_file_at(&folder, &["legacy", "user", "User", "decode"]);
+        assert!(decode.contains("  let has1 = false\n  let has7 = false\n  while"));
+        assert!(decode.contains("message.name = r.string()\n        has1 = true\n        break;"));
+        assert!(decode.contains(
+            "  if (!has1)\n    throw new Error(\"missing required field \\\"name\\\"\")\n"
+        ));
+        assert!(decode.contains(
+            "  if (!has7)\n    throw new Error(\"missing required field \\\"main_role\\\"\")\n"
+        ));
+    }
+
+    #[test]
+    fn it_types_required_fields_as_present() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let types = render_file_at(&folder, &["legacy", "user", "User", "types"]);
+        assert!(types.contains("  name: string\n"));
+        assert!(types.contains("  main_role: Role\n"));
+        assert!(types.contains("  nick?: string\n"));
+    }
+
+    #[test]
+    fn it_decodes_absent_fields_to_default_options() {
+        let options = Options {
+            emit_guards: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let decode = render_file_at(&folder, &["legacy", "user", "User", "decode"]);
+        assert!(decode.contains("  const message: any = create()\n"));
+        let types = render_file_at(&folder, &["legacy", "user", "User", "types"]);
+        assert!(types.contains("  age: number\n  role: Role\n  score: number\n"));
+        assert!(types.contains("  nick?: string\n"));
+        let is = render_file_at(&folder, &["legacy", "user", "User", "is"]);
+        assert!(is.contains("  if (!(typeof v.age === \"number\"))\n"));
+        assert!(is.contains("  if (v.nick !== undefined && !(typeof v.nick === \"string\"))\n"));
+    }
+
+    #[test]
+    fn it_compiles_proto3_files_importing_proto2_files() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let decode = render_file_at(&folder, &["app", "account", "Account", "decode"]);
+        assert!(
+            decode.contains("import { decode as d2 } from \"../../../legacy/user/User/decode\"\n")
+        );
+        assert!(!decode.contains("has"));
+    }
+
+    #[test]
+    fn it_types_message_fields_as_undefined_until_decoded() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let create = render_file_at(&folder, &["app", "account", "Account", "create"]);
+        assert!(create.contains("    owner: undefined,\n"));
+        let types = render_file_at(&folder, &["app", "account", "Account", "types"]);
+        assert!(types.contains("  owner?: UserEncodeInput | null\n"));
+        assert!(types.contains("  owner: User | undefined\n"));
+    }
+
+    #[test]
+    fn it_rejects_default_values_of_other_types() {
+        let sources = &[(
+            "a.proto",
+            "syntax = \"proto2\";\nmessage A { optional int32 a = 1 [default = true]; }\n",
+        )];
+        let root = crate::proto::package::read_root_scope_from_sources(sources).unwrap();
+        match super::super::scope_to_folder::root_scope_to_folder(
+            &root,
+            &Options::default(),
+            "out".into(),
+        ) {
+            Err(ProtoError::InvalidDefaultValue {
+                message_name,
+                field_name,
+                value,
+            }) => {
+                assert_eq!(
+                    (message_name.as_str(), field_name.as_str(), value.as_str()),
+                    ("A", "a", "true")
+                );
+            }
+            res => panic!("expected invalid default value, got {:?}", res.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn it_reports_invalid_default_values_of_every_message() {
+        let sources = &[(
+            "a.proto",
+            "syntax = \"proto2\";\nmessage A {\n  optional int32 a = 1 [default = true];\n  message B { optional bool b = 1 [default = 1]; }\n}\nmessage C { optional uint32 c = 1 [default = false]; }\n",
+        )];
+        let root = crate::proto::package::read_root_scope_from_sources(sources).unwrap();
+        let error = super::super::scope_to_folder::root_scope_to_folder(
+            &root,
+            &Options::default(),
+            "out".into(),
+        )
+        .err()
+        .unwrap();
+        let fields = error
+            .errors()
+            .into_iter()
+            .map(|e| match e {
+                ProtoError::InvalidDefaultValue { field_name, .. } => field_name.as_str(),
+                e => panic!("expected invalid default value, got {:?}", e),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(fields, ["a", "b", "c"]);
+    }
+}
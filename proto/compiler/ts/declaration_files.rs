@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use super::ast::{
+    Folder, FolderEntry, Modifier, Statement, VariableDeclaration, VariableDeclarationList,
+};
+
+/// Turns every file of the folder into a `.d.ts` declaration of itself:
+/// functions and classes keep their signatures, constants their types
+/// and imports become `import type`, since a declaration file has no values to import.
+///
+/// Functions and constants that are not exported are left out, nothing can refer to them.
+/// Constants without a type annotation cannot be declared and are left out as well,
+/// the compilers annotate the exported ones when `Emit::Dts` is on.
+pub(super) fn into_declaration_files(folder: &mut Folder) {
+    for entry in folder.entries.iter_mut() {
+        match entry {
+            FolderEntry::File(file) => {
+                file.declaration = true;
+                let statements = std::mem::take(&mut file.ast.statements);
+                file.ast.statements = into_declarations(statements, true);
+            }
+            FolderEntry::Folder(folder) => into_declaration_files(folder),
+        }
+    }
+}
+
+/// Declarations of the `statements`, `top_level` ones are marked with `declare`,
+/// the ones of namespaces are ambient already.
+fn into_declarations(statements: Vec<Statement>, top_level: bool) -> Vec<Statement> {
+    let declare = |modifiers: &mut Vec<Modifier>| {
+        if top_level {
+            modifiers.push(Modifier::Declare);
+        }
+    };
+    let mut res = Vec::new();
+    for statement in statements {
+        match statement {
+            Statement::ImportDeclaration(mut import) => {
+                import.import_clause.is_type_only = true;
+                res.push(Statement::ImportDeclaration(import));
+            }
+            statement @ (Statement::EnumDeclaration(_)
+            | Statement::InterfaceDeclaration(_)
+            | Statement::TypeAliasDeclaration(_)) => res.push(statement),
+            Statement::FunctionDeclaration(mut function) => {
+                if !function.modifiers.contains(&Modifier::Export) {
+                    continue;
+                }
+                declare(&mut function.modifiers);
+                function.body = None;
+                res.push(Statement::FunctionDeclaration(function));
+            }
+            Statement::ClassDeclaration(mut class) => {
+                declare(&mut class.modifiers);
+                class.ambient = true;
+                for method in class.methods.iter_mut() {
+                    method.body = None;
+                }
+                res.push(Statement::ClassDeclaration(class));
+            }
+            Statement::NamespaceDeclaration(mut namespace) => {
+                declare(&mut namespace.modifiers);
+                namespace.statements = into_declarations(namespace.statements, false);
+                res.push(Statement::NamespaceDeclaration(namespace));
+            }
+            Statement::VariableStatement(list) => {
+                if !list.modifiers.contains(&Modifier::Export) {
+                    continue;
+                }
+                let declarations = list
+                    .declarations
+                    .iter()
+                    .filter(|declaration| declaration.var_type.is_some())
+                    .map(|declaration| VariableDeclaration {
+                        name: Arc::clone(&declaration.name),
+                        initializer: None,
+                        var_type: declaration.var_type.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                if declarations.is_empty() {
+                    continue;
+                }
+                let mut modifiers = list.modifiers.clone();
+                declare(&mut modifiers);
+                res.push(Statement::VariableStatement(Arc::new(
+                    VariableDeclarationList {
+                        modifiers,
+                        kind: list.kind,
+                        declarations,
+                    },
+                )));
+            }
+            // statements that run code have no declarations
+            _ => {}
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod test_declaration_files {
+    use super::super::{
+        options::{Emit, EnumStyle, Options},
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "chat/chat.proto",
+        r#"
+        syntax = "proto3";
+        package chat;
+        enum Mood { CALM = 0; ANGRY = 1; }
+        message Message { string text = 1; Mood mood = 2; }
+        service Chat { rpc Send(Message) returns (Message); }
+        "#,
+    )];
+
+    fn compile(emit: Emit) -> Folder {
+        let options = Options {
+            emit,
+            enum_style: EnumStyle::Union,
+            ..Options::default()
+        };
+        compile_sources(SOURCES, &options)
+    }
+
+    #[test]
+    fn it_declares_functions_by_their_signatures() {
+        let folder = compile(Emit::Dts);
+        let message = folder
+            .find_folder_by_path(&["chat", "chat", "Message"])
+            .unwrap();
+        assert!(message.find_file("encode").unwrap().declaration);
+        let decode = render_file_at(&folder, &["chat", "chat", "Message", "decode"]);
+        assert!(decode.contains("import type { Reader } from \"protobufjs/minimal\"\n"));
+        assert!(decode.contains(
+            "\nexport declare function decode(reader: Reader | Uint8Array, length?: number): Message\n"
+        ));
+    }
+
+    #[test]
+    fn it_declares_constants_by_their_types() {
+        let mood = render_file_at(&compile(Emit::Dts), &["chat", "chat", "Mood"]);
+        assert_eq!(
+            mood,
+            concat!(
+                "export type Mood = \"CALM\" | \"ANGRY\"\n",
+                "\n",
+                "export declare const Mood: Record<Mood, number>\n",
+                "\n",
+                "export declare const MoodNames: Record<number, Mood>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn it_declares_classes_without_implementation() {
+        let client = render_file_at(&compile(Emit::Dts), &["chat", "chat", "Chat"]);
+        assert!(client.contains(concat!(
+            "export interface IChatClient {\n",
+            "  send(request: MessageEncodeInput): Promise<Message>\n",
+            "}\n",
+            "\n",
+            "export declare class ChatClient implements IChatClient {\n",
+            "  private readonly transport\n",
+            "  constructor(transport: Transport)\n",
+            "\n",
+            "  send(request: MessageEncodeInput): Promise<Message>\n",
+            "}\n",
+        )));
+        // the transport the client is constructed with stays a module
+        let root_files = folder_file_names(&compile(Emit::Dts));
+        assert!(root_files.contains(&("transport".to_string(), false)));
+    }
+
+    #[test]
+    fn it_writes_declarations_next_to_the_modules() {
+        let folder = compile(Emit::Both);
+        let message = folder
+            .find_folder_by_path(&["chat", "chat", "Message"])
+            .unwrap();
+        let files = folder_file_names(message);
+        assert!(files.contains(&("types".to_string(), false)));
+        assert!(files.contains(&("types".to_string(), true)));
+        assert_eq!(files.len(), 10);
+
+        let ts = compile(Emit::Ts);
+        let mood = render_file_at(&ts, &["chat", "chat", "Mood"]);
+        assert!(mood.contains("export const Mood = {\n"));
+        assert_eq!(
+            render_file_at(&folder, &["chat", "chat", "Mood"]),
+            mood,
+            "the modules are the same as without declarations"
+        );
+    }
+
+    /// Names of the files of the folder with whether they are declarations.
+    fn folder_file_names(folder: &Folder) -> Vec<(String, bool)> {
+        folder
+            .entries
+            .iter()
+            .filter_map(|entry| match entry {
+                FolderEntry::File(file) => Some((file.name.to_string(), file.declaration)),
+                FolderEntry::Folder(_) => None,
+            })
+            .collect()
+    }
+}
@@ -1,7 +1,7 @@
-use std::{ops::Deref, rc::Rc};
+use std::{ops::Deref, sync::Arc};
 
 use crate::proto::{
-    compiler::ts::{ast::ElementAccess, ts_path::TsPath},
+    compiler::ts::ast::ElementAccess,
     error::ProtoError,
     package,
     proto_scope::{root_scope::RootScope, ProtoScope},
@@ -9,68 +9,84 @@ use crate::proto::{
 
 use super::{
     ast::{
-        self, BinaryOperator, Block, CaseClause, ExpressionChain, MethodCall, ObjectLiteralMember,
-        Prop, StatementList, StatementPlacer, VariableDeclarationList,
+        self, BinaryOperator, Block, CaseClause, ExpressionChain, MethodCall, Prop, StatementList,
+        StatementPlacer, VariableDeclarationList,
     },
-    constants::{DECODE_FUNCTION_NAME, PROTOBUF_MODULE},
+    constants::{CREATE_FUNCTION_NAME, DECODE_DELIMITED_FUNCTION_NAME, DECODE_FUNCTION_NAME},
     ensure_import::ensure_import,
-    get_relative_import::get_relative_import_string,
+    enum_values::{enum_values, lookup_value},
+    has_fields::{has_field, has_field_name, set_has_field},
+    int64_type::read_value,
+    layout::{
+        declaration_file_path, declaration_name, function_name, import_declaration,
+        import_function_lazily,
+    },
+    options::Options,
+    renames::property_name,
+    runtime::runtime_module,
+    ts_path::TsPathComponent,
+    unknown_fields::keep_unknown_field,
+    well_known_types::{import_well_known, mapped_well_known_type},
 };
 
 pub(super) fn compile_decode(
     root: &RootScope,
+    options: &Options,
     message_folder: &mut ast::Folder,
     message_scope: &ProtoScope,
 ) -> Result<(), ProtoError> {
     let mut file = super::ast::File::new("decode".into());
 
-    let reader_type_id: Rc<ast::Identifier> = ast::Identifier::from("Reader").into();
-    let util_id: Rc<ast::Identifier> = ast::Identifier::from("util").into();
-    let util_expr: Rc<ast::Expression> = ast::Expression::from(Rc::clone(&util_id)).into();
-    let message_type_id: Rc<ast::Identifier> = ast::Identifier::from(message_scope.name()).into();
-    let reader_parameter_id: Rc<ast::Identifier> = ast::Identifier::from("reader").into();
-    let length_parameter_id: Rc<ast::Identifier> = ast::Identifier::from("length").into();
-    let reader_var_id: Rc<ast::Identifier> = ast::Identifier::from("r").into();
-    let end_var_id: Rc<ast::Identifier> = ast::Identifier::from("end").into();
-    let tag_var_id: Rc<ast::Identifier> = ast::Identifier::from("tag").into();
-    let message_var_id: Rc<ast::Identifier> = ast::Identifier::from("message").into();
-    let arr_end_id: Rc<ast::Identifier> = ast::Identifier::from("arr_end").into();
-    let arr_end_expr: Rc<ast::Expression> = ast::Expression::from(Rc::clone(&arr_end_id)).into();
-    let map_entry_end_id: Rc<ast::Identifier> = ast::Identifier::from("pair_end").into();
-    let map_entry_end_expr: Rc<ast::Expression> =
-        ast::Expression::from(Rc::clone(&map_entry_end_id)).into();
-    let key_id: Rc<ast::Identifier> = ast::Identifier::from("k").into();
-    let key_expr: Rc<ast::Expression> = ast::Expression::from(Rc::clone(&key_id)).into();
-    let val_id: Rc<ast::Identifier> = ast::Identifier::from("v").into();
-    let val_expr: Rc<ast::Expression> = ast::Expression::from(Rc::clone(&val_id)).into();
-    let entry_tag_id: Rc<ast::Identifier> = ast::Identifier::from("t").into();
-    let entry_tag_expr: Rc<ast::Expression> =
-        ast::Expression::from(Rc::clone(&entry_tag_id)).into();
-
+    let reader_type_id: Arc<ast::Identifier> = ast::Identifier::from("Reader").into();
+    let util_id: Arc<ast::Identifier> = ast::Identifier::from("util").into();
+    let util_expr: Arc<ast::Expression> = ast::Expression::from(Arc::clone(&util_id)).into();
+    let reader_parameter_id: Arc<ast::Identifier> = ast::Identifier::from("reader").into();
+    let length_parameter_id: Arc<ast::Identifier> = ast::Identifier::from("length").into();
+    let reader_var_id: Arc<ast::Identifier> = ast::Identifier::from("r").into();
+    let end_var_id: Arc<ast::Identifier> = ast::Identifier::from("end").into();
+    let tag_var_id: Arc<ast::Identifier> = ast::Identifier::from("tag").into();
+    let message_var_id: Arc<ast::Identifier> = ast::Identifier::from("message").into();
+    let arr_end_id: Arc<ast::Identifier> = ast::Identifier::from("arr_end").into();
+    let arr_end_expr: Arc<ast::Expression> = ast::Expression::from(Arc::clone(&arr_end_id)).into();
+    let map_entry_end_id: Arc<ast::Identifier> = ast::Identifier::from("pair_end").into();
+    let map_entry_end_expr: Arc<ast::Expression> =
+        ast::Expression::from(Arc::clone(&map_entry_end_id)).into();
+    let key_id: Arc<ast::Identifier> = ast::Identifier::from("k").into();
+    let key_expr: Arc<ast::Expression> = ast::Expression::from(Arc::clone(&key_id)).into();
+    let val_id: Arc<ast::Identifier> = ast::Identifier::from("v").into();
+    let val_expr: Arc<ast::Expression> = ast::Expression::from(Arc::clone(&val_id)).into();
+    let entry_tag_id: Arc<ast::Identifier> = ast::Identifier::from("t").into();
+    let entry_tag_expr: Arc<ast::Expression> =
+        ast::Expression::from(Arc::clone(&entry_tag_id)).into();
+
+    let current_message_id = message_scope.id().unwrap();
+    let runtime = runtime_module(root, options, current_message_id, "decode");
     file.push_statement(ast::Statement::ImportDeclaration(
         ast::ImportDeclaration::import(
             vec![ast::ImportSpecifier {
-                name: Rc::clone(&reader_type_id),
+                name: Arc::clone(&reader_type_id),
                 property_name: None,
+                is_type_only: false,
             }],
-            PROTOBUF_MODULE.into(),
+            Arc::clone(&runtime).into(),
         )
         .into(),
     ));
-    file.push_statement(ast::Statement::ImportDeclaration(
-        ast::ImportDeclaration::import(
-            vec![ast::ImportSpecifier {
-                name: Rc::clone(&message_type_id),
-                property_name: None,
-            }],
-            "./types".into(),
-        )
-        .into(),
+    let message_type_id = import_declaration(
+        &mut file,
+        &declaration_file_path(root, options, current_message_id, "decode"),
+        &declaration_file_path(root, options, current_message_id, "types"),
+        TsPathComponent::Interface(declaration_name(root, options, current_message_id)),
+        None,
+    );
+
+    let mut decode_function_declaration = ast::FunctionDeclaration::new_exported(&function_name(
+        root,
+        options,
+        current_message_id,
+        DECODE_FUNCTION_NAME,
     ));
 
-    let mut decode_function_declaration =
-        ast::FunctionDeclaration::new_exported(DECODE_FUNCTION_NAME);
-
     decode_function_declaration.add_param(ast::Parameter::new(
         &reader_parameter_id,
         ast::Type::UnionType(ast::UnionType {
@@ -88,147 +104,199 @@ pub(super) fn compile_decode(
 
     decode_function_declaration.returns(ast::Type::from_id(&message_type_id));
 
-    let reader_parameter_expr = ast::Expression::Identifier(Rc::clone(&reader_parameter_id)).into();
-    let reader_type_expr: Rc<ast::Expression> =
-        ast::Expression::Identifier(Rc::clone(&reader_type_id)).into();
+    let reader_parameter_expr = ast::Expression::Identifier(Arc::clone(&reader_parameter_id)).into();
+    let reader_type_expr: Arc<ast::Expression> =
+        ast::Expression::Identifier(Arc::clone(&reader_type_id)).into();
     decode_function_declaration.push_statement(ast::Statement::VariableStatement(
         ast::VariableDeclarationList::declare_const(
-            Rc::clone(&reader_var_id),
+            Arc::clone(&reader_var_id),
             ast::Expression::conditional(
                 ast::BinaryOperator::InstanceOf
                     .apply(
-                        Rc::clone(&reader_parameter_expr),
-                        ast::Expression::Identifier(Rc::clone(&reader_type_id)).into(),
+                        Arc::clone(&reader_parameter_expr),
+                        ast::Expression::Identifier(Arc::clone(&reader_type_id)).into(),
                     )
                     .into(),
-                Rc::clone(&reader_parameter_expr),
+                Arc::clone(&reader_parameter_expr),
                 reader_type_expr
                     .prop("create")
-                    .into_call(vec![Rc::clone(&reader_parameter_expr)])
+                    .into_call(vec![Arc::clone(&reader_parameter_expr)])
                     .into(),
             ),
         )
         .into(),
     ));
 
-    let length_parameter_expr: Rc<ast::Expression> =
-        ast::Expression::Identifier(Rc::clone(&length_parameter_id)).into();
-    let reader_var_expr: Rc<ast::Expression> =
-        ast::Expression::Identifier(Rc::clone(&reader_var_id)).into();
-    let r_pos_expr: Rc<ast::Expression> = reader_var_expr.prop("pos").into();
+    let length_parameter_expr: Arc<ast::Expression> =
+        ast::Expression::Identifier(Arc::clone(&length_parameter_id)).into();
+    let reader_var_expr: Arc<ast::Expression> =
+        ast::Expression::Identifier(Arc::clone(&reader_var_id)).into();
+    let r_pos_expr: Arc<ast::Expression> = reader_var_expr.prop("pos").into();
     decode_function_declaration.push_statement(ast::Statement::VariableStatement(
         ast::VariableDeclarationList::declare_const(
-            Rc::clone(&end_var_id),
+            Arc::clone(&end_var_id),
             ast::Expression::conditional(
                 ast::BinaryOperator::StrictEqual
                     .apply(
-                        Rc::clone(&length_parameter_expr),
+                        Arc::clone(&length_parameter_expr),
                         ast::Expression::Undefined.into(),
                     )
                     .into(),
                 reader_var_expr.prop("len").into(),
                 ast::BinaryOperator::Plus
-                    .apply(Rc::clone(&r_pos_expr), Rc::clone(&length_parameter_expr))
+                    .apply(Arc::clone(&r_pos_expr), Arc::clone(&length_parameter_expr))
                     .into(),
             ),
         )
         .into(),
     ));
 
-    let default_message_value = get_default_message_value(message_scope);
+    let default_message_value =
+        import_create_func(root, options, message_scope, &mut file).into_call(vec![]);
 
     decode_function_declaration.push_statement(ast::Statement::VariableStatement(
         ast::VariableDeclarationList::declare_typed_const(
-            Rc::clone(&message_var_id),
+            Arc::clone(&message_var_id),
             ast::Type::Any.into(),
             default_message_value,
         )
         .into(),
     ));
 
+    // proto2 `required` fields have the defaults of `create` until they are read,
+    // so whether they were read is tracked separately
+    let required_fields = message_scope
+        .get_message_declaration()
+        .map(|d| d.get_fields())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|f| f.required)
+        .map(|f| {
+            let has_id = Arc::new(ast::Identifier::from(format!("has{}", f.tag).as_str()));
+            (f.tag, Arc::clone(&f.name), has_id)
+        })
+        .collect::<Vec<_>>();
+    for (_, _, has_id) in &required_fields {
+        decode_function_declaration.push_statement(
+            ast::VariableDeclarationList::declare_let(Arc::clone(has_id), ast::Expression::False)
+                .into(),
+        );
+    }
+
     {
         let mut while_loop = decode_function_declaration.place(ast::WhileStatement::new(
             BinaryOperator::LessThan
                 .apply(
                     reader_var_expr.prop("pos").into(),
-                    Rc::new(end_var_id.into()),
+                    Arc::new(end_var_id.into()),
                 )
                 .into(),
         ));
 
+        // with `keep_unknown_fields` the bytes of unknown fields are kept from the start of their tags
+        let start_var_id = Arc::new(ast::Identifier::from("start"));
+        if options.keep_unknown_fields {
+            while_loop.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    Arc::clone(&start_var_id),
+                    reader_var_expr.prop("pos"),
+                )
+                .into(),
+            );
+        }
         while_loop.push_statement(
             ast::VariableDeclarationList::declare_const(
-                Rc::clone(&tag_var_id),
+                Arc::clone(&tag_var_id),
                 reader_var_expr.method_call("uint32", vec![]),
             )
             .into(),
         );
 
-        let tag_var_expr = Rc::new(tag_var_id.into());
+        let tag_var_expr = Arc::new(tag_var_id.into());
 
         {
+            let mut default_statements = vec![reader_var_expr
+                .method_call(
+                    "skipType",
+                    vec![BinaryOperator::BinaryAnd
+                        .apply(Arc::clone(&tag_var_expr), Arc::new(7.into()))
+                        .into()],
+                )
+                .into()];
+            if options.keep_unknown_fields {
+                default_statements.extend(keep_unknown_field(
+                    &reader_var_expr,
+                    &Arc::new(Arc::clone(&message_var_id).into()),
+                    &tag_var_expr,
+                    &Arc::new(start_var_id.into()),
+                ));
+            }
+            default_statements.push(ast::Statement::Break);
             let mut switch_stmt = while_loop.place(ast::SwitchStatement::new(
                 BinaryOperator::UnsignedRightShift
-                    .apply(Rc::clone(&tag_var_expr), Rc::new(3.into()))
+                    .apply(Arc::clone(&tag_var_expr), Arc::new(3.into()))
                     .into(),
-                vec![
-                    reader_var_expr
-                        .method_call(
-                            "skipType",
-                            vec![BinaryOperator::BinaryAnd
-                                .apply(Rc::clone(&tag_var_expr), Rc::new(7.into()))
-                                .into()],
-                        )
-                        .into(),
-                    ast::Statement::Break,
-                ]
-                .into(),
+                default_statements.into(),
             ));
             let fields = message_scope
                 .get_message_declaration()
                 .map(|d| d.get_fields())
                 .unwrap_or_else(Vec::new);
+            // `util.longToHash` turns 64-bit map keys into strings
             if fields.iter().any(|f| match &f.field_type {
-                package::Type::Repeated(_) => true,
-                package::Type::Map(_, _) => true,
+                package::Type::Map(key, _) => key.long_wire_type().is_some(),
                 _ => false,
             }) {
                 let utils_import = ast::ImportDeclaration::import(
                     vec![ast::ImportSpecifier {
-                        name: Rc::clone(&util_id),
+                        name: Arc::clone(&util_id),
                         property_name: None,
+                        is_type_only: false,
                     }],
-                    PROTOBUF_MODULE.into(),
+                    Arc::clone(&runtime).into(),
                 );
 
                 ensure_import(&mut file, utils_import);
             }
             for field in fields {
-                let name = field.json_name();
+                let name = property_name(root, options, current_message_id, field);
                 let id = field.tag;
                 let field_type = match &field.field_type {
                     package::Type::Enum(_) => &package::Type::Int32,
                     t => t,
                 };
-                let field_value_ref: Rc<ast::Expression> =
-                    ast::Expression::from(Rc::clone(&message_var_id))
+                // decoded wire numbers are looked up in it for enums with non-numeric values
+                let enum_values = match &field.field_type {
+                    package::Type::Enum(e_id) => {
+                        enum_values(root, options, message_scope, &mut file, *e_id)
+                    }
+                    package::Type::Repeated(t) | package::Type::Map(_, t) => match t.deref() {
+                        package::Type::Enum(e_id) => {
+                            enum_values(root, options, message_scope, &mut file, *e_id)
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                let field_value_ref: Arc<ast::Expression> =
+                    ast::Expression::from(Arc::clone(&message_var_id))
                         .into_prop(&name)
                         .into();
-                let mut case_clause = ast::CaseClause::new(Rc::new(id.into()));
+                let mut case_clause = ast::CaseClause::new(Arc::new(id.into()));
 
                 match field_type {
                     package::Type::Enum(_) => unreachable!(),
                     package::Type::Message(m_id) => {
                         let decode_func_expr: ast::Expression =
-                            import_decode_func(&root, &message_scope, &mut file, *m_id);
+                            import_decode_func(root, options, message_scope, &mut file, *m_id);
 
                         case_clause.push_statement(
                             ast::BinaryOperator::Assign
                                 .apply(
-                                    Rc::clone(&field_value_ref),
+                                    Arc::clone(&field_value_ref),
                                     decode_func_expr
                                         .into_call(vec![
-                                            Rc::clone(&reader_var_expr),
+                                            Arc::clone(&reader_var_expr),
                                             reader_var_expr.method_call("uint32", vec![]).into(),
                                         ])
                                         .into(),
@@ -239,18 +307,18 @@ pub(super) fn compile_decode(
                     package::Type::Repeated(t) => {
                         let element_type = match t.deref() {
                             package::Type::Enum(_) => package::Type::Int32.into(),
-                            _ => Rc::clone(t),
+                            _ => Arc::clone(t),
                         };
-                        let reset_array_stmt = Rc::new(
+                        let reset_array_stmt = Arc::new(
                             ast::BinaryOperator::Assign
                                 .apply(
-                                    Rc::clone(&field_value_ref),
-                                    Rc::new(ast::Expression::ArrayLiteralExpression(vec![])),
+                                    Arc::clone(&field_value_ref),
+                                    Arc::new(ast::Expression::array(vec![])),
                                 )
                                 .into(),
                         );
 
-                        let is_empty_expr: Rc<ast::Expression> = field_value_ref
+                        let is_empty_expr: Arc<ast::Expression> = field_value_ref
                             .and(field_value_ref.prop("length").into())
                             .into_parentheses()
                             .not()
@@ -258,7 +326,7 @@ pub(super) fn compile_decode(
 
                         let reset_if = ast::IfStatement {
                             expression: is_empty_expr,
-                            then_statement: Rc::clone(&reset_array_stmt),
+                            then_statement: Arc::clone(&reset_array_stmt),
                             else_statement: None,
                         }
                         .into();
@@ -266,11 +334,18 @@ pub(super) fn compile_decode(
 
                         match element_type.packed_wire_type() {
                             Some(_) => {
-                                let parse_element_expr = Rc::new(field_value_ref.method_call(
+                                let parse_element_expr = Arc::new(field_value_ref.method_call(
                                     "push",
-                                    vec![reader_var_expr
-                                        .method_call(&element_type.to_string(), vec![])
-                                        .into()],
+                                    vec![lookup_value(
+                                        enum_values.as_ref(),
+                                        read_value(
+                                            options,
+                                            &element_type,
+                                            reader_var_expr
+                                                .method_call(&element_type.to_string(), vec![])
+                                                .into(),
+                                        ),
+                                    )],
                                 ));
 
                                 let mut packed_block = Block::new();
@@ -278,7 +353,7 @@ pub(super) fn compile_decode(
                                 packed_block.push_statement(
                                     ast::Statement::VariableStatement(
                                         VariableDeclarationList::declare_const(
-                                            Rc::clone(&arr_end_id),
+                                            Arc::clone(&arr_end_id),
                                             BinaryOperator::Plus.apply(
                                                 reader_var_expr
                                                     .method_call("uint32", vec![])
@@ -295,13 +370,13 @@ pub(super) fn compile_decode(
                                     BinaryOperator::LessThan
                                         .apply(
                                             reader_var_expr.prop("pos").into(),
-                                            Rc::clone(&arr_end_expr),
+                                            Arc::clone(&arr_end_expr),
                                         )
                                         .into(),
                                 );
 
                                 element_while.push_statement(ast::Statement::Expression(
-                                    Rc::clone(&parse_element_expr),
+                                    Arc::clone(&parse_element_expr),
                                 ));
 
                                 packed_block.push_statement(element_while.into());
@@ -312,17 +387,17 @@ pub(super) fn compile_decode(
                                             .apply(
                                                 BinaryOperator::BinaryAnd
                                                     .apply(
-                                                        Rc::clone(&tag_var_expr),
-                                                        Rc::new(7.into()),
+                                                        Arc::clone(&tag_var_expr),
+                                                        Arc::new(7.into()),
                                                     )
                                                     .into_parentheses()
                                                     .into(),
-                                                Rc::new(2.into()),
+                                                Arc::new(2.into()),
                                             )
                                             .into(),
-                                        then_statement: Rc::new(packed_block.into()),
+                                        then_statement: Arc::new(packed_block.into()),
                                         else_statement: Some(
-                                            ast::Statement::Expression(Rc::clone(
+                                            ast::Statement::Expression(Arc::clone(
                                                 &parse_element_expr,
                                             ))
                                             .into(),
@@ -336,14 +411,19 @@ pub(super) fn compile_decode(
                                 package::Type::Repeated(_) => unreachable!(),
                                 package::Type::Map(_, _) => unreachable!(),
                                 package::Type::Message(m) => {
-                                    let decode_func =
-                                        import_decode_func(&root, &message_scope, &mut file, *m);
+                                    let decode_func = import_decode_func(
+                                        root,
+                                        options,
+                                        message_scope,
+                                        &mut file,
+                                        *m,
+                                    );
                                     case_clause.push_statement(ast::Statement::from(
                                         field_value_ref.method_call(
                                             "push",
                                             vec![decode_func
                                                 .into_call(vec![
-                                                    Rc::clone(&reader_var_expr),
+                                                    Arc::clone(&reader_var_expr),
                                                     reader_var_expr
                                                         .method_call("uint32", vec![])
                                                         .into(),
@@ -367,34 +447,10 @@ pub(super) fn compile_decode(
                         }
                     }
                     package::Type::Map(kt, vt) => {
-                        case_clause.push_statement(
-                            ast::IfStatement {
-                                expression: BinaryOperator::StrictEqual
-                                    .apply(
-                                        Rc::clone(&field_value_ref),
-                                        util_expr.prop("emptyObject").into(),
-                                    )
-                                    .into(),
-                                then_statement: ast::Statement::Expression(
-                                    BinaryOperator::Assign
-                                        .apply(
-                                            Rc::clone(&field_value_ref),
-                                            Rc::new(ast::Expression::ObjectLiteralExpression(
-                                                vec![],
-                                            )),
-                                        )
-                                        .into(),
-                                )
-                                .into(),
-                                else_statement: None,
-                            }
-                            .into(),
-                        );
-
                         case_clause.push_statement(
                             ast::Statement::VariableStatement(
                                 VariableDeclarationList::declare_const(
-                                    Rc::clone(&map_entry_end_id),
+                                    Arc::clone(&map_entry_end_id),
                                     BinaryOperator::Plus.apply(
                                         reader_var_expr.method_call("uint32", vec![]).into(),
                                         reader_var_expr.prop("pos").into(),
@@ -407,13 +463,13 @@ pub(super) fn compile_decode(
 
                         let value_type = match vt.deref() {
                             package::Type::Enum(_) => package::Type::Int32.into(),
-                            _ => Rc::clone(vt),
+                            _ => Arc::clone(vt),
                         };
 
                         case_clause.push_statement(
                             ast::Statement::VariableStatement(
                                 VariableDeclarationList::declare_typed_let(
-                                    Rc::clone(&key_id),
+                                    Arc::clone(&key_id),
                                     ast::Type::Any.into(),
                                     kt.default_expression().into(),
                                 )
@@ -424,9 +480,14 @@ pub(super) fn compile_decode(
                         case_clause.push_statement(
                             ast::Statement::VariableStatement(
                                 VariableDeclarationList::declare_typed_let(
-                                    Rc::clone(&val_id),
+                                    Arc::clone(&val_id),
                                     ast::Type::Any.into(),
-                                    value_type.default_expression().into(),
+                                    match &enum_values {
+                                        Some(values) => {
+                                            values.element(value_type.default_expression().into())
+                                        }
+                                        None => value_type.default_expression(),
+                                    },
                                 )
                                 .into(),
                             )
@@ -438,14 +499,14 @@ pub(super) fn compile_decode(
                                 BinaryOperator::LessThan
                                     .apply(
                                         reader_var_expr.prop("pos").into(),
-                                        Rc::clone(&map_entry_end_expr),
+                                        Arc::clone(&map_entry_end_expr),
                                     )
                                     .into(),
                             ));
 
                             entry_while.push_statement(ast::Statement::VariableStatement(
                                 ast::VariableDeclarationList::declare_const(
-                                    Rc::clone(&entry_tag_id),
+                                    Arc::clone(&entry_tag_id),
                                     reader_var_expr.method_call("uint32", vec![]),
                                 )
                                 .into(),
@@ -454,7 +515,7 @@ pub(super) fn compile_decode(
                                 let mut entry_switch =
                                     entry_while.place(ast::SwitchStatement::new(
                                         BinaryOperator::UnsignedRightShift
-                                            .apply(Rc::clone(&entry_tag_expr), Rc::new(3.into()))
+                                            .apply(Arc::clone(&entry_tag_expr), Arc::new(3.into()))
                                             .into(),
                                         vec![
                                             reader_var_expr
@@ -462,8 +523,8 @@ pub(super) fn compile_decode(
                                                     "skipType",
                                                     vec![BinaryOperator::BinaryAnd
                                                         .apply(
-                                                            Rc::clone(&entry_tag_expr),
-                                                            Rc::new(7.into()),
+                                                            Arc::clone(&entry_tag_expr),
+                                                            Arc::new(7.into()),
                                                         )
                                                         .into()],
                                                 )
@@ -473,12 +534,12 @@ pub(super) fn compile_decode(
                                         .into(),
                                     ));
 
-                                let mut key_case = CaseClause::new(Rc::new(1.into()));
+                                let mut key_case = CaseClause::new(Arc::new(1.into()));
                                 let kt_string = kt.to_string();
                                 key_case.push_statement(ast::Statement::Expression(
                                     BinaryOperator::Assign
                                         .apply(
-                                            Rc::clone(&key_expr),
+                                            Arc::clone(&key_expr),
                                             reader_var_expr.method_call(&kt_string, vec![]).into(),
                                         )
                                         .into(),
@@ -486,21 +547,26 @@ pub(super) fn compile_decode(
                                 key_case.push_statement(ast::Statement::Break);
                                 entry_switch.add_case(key_case);
 
-                                let mut val_case = CaseClause::new(Rc::new(2.into()));
+                                let mut val_case = CaseClause::new(Arc::new(2.into()));
                                 match value_type.deref() {
                                     package::Type::Enum(_) => unreachable!(),
                                     package::Type::Repeated(_) => unreachable!(),
                                     package::Type::Map(_, _) => unreachable!(),
                                     package::Type::Message(m) => {
-                                        let decode_expr =
-                                            import_decode_func(root, message_scope, &mut file, *m);
+                                        let decode_expr = import_decode_func(
+                                            root,
+                                            options,
+                                            message_scope,
+                                            &mut file,
+                                            *m,
+                                        );
                                         val_case.push_statement(
                                             BinaryOperator::Assign
                                                 .apply(
-                                                    Rc::clone(&val_expr),
+                                                    Arc::clone(&val_expr),
                                                     decode_expr
                                                         .into_call(vec![
-                                                            Rc::clone(&reader_var_expr),
+                                                            Arc::clone(&reader_var_expr),
                                                             reader_var_expr
                                                                 .method_call("uint32", vec![])
                                                                 .into(),
@@ -516,10 +582,17 @@ pub(super) fn compile_decode(
                                         val_case.push_statement(ast::Statement::Expression(
                                             BinaryOperator::Assign
                                                 .apply(
-                                                    Rc::clone(&val_expr),
-                                                    reader_var_expr
-                                                        .method_call(&b_str, vec![])
-                                                        .into(),
+                                                    Arc::clone(&val_expr),
+                                                    lookup_value(
+                                                        enum_values.as_ref(),
+                                                        read_value(
+                                                            options,
+                                                            basic,
+                                                            reader_var_expr
+                                                                .method_call(&b_str, vec![])
+                                                                .into(),
+                                                        ),
+                                                    ),
                                                 )
                                                 .into(),
                                         ));
@@ -534,12 +607,12 @@ pub(super) fn compile_decode(
                             Some(_) => case_clause.push_statement(ast::Statement::Expression(
                                 BinaryOperator::Assign
                                     .apply(
-                                        Rc::clone(&key_expr),
+                                        Arc::clone(&key_expr),
                                         ast::Expression::conditional(
                                             BinaryOperator::StrictEqual
                                                 .apply(
                                                     key_expr.type_of().into(),
-                                                    Rc::new(
+                                                    Arc::new(
                                                         ast::StringLiteral::new("object".into())
                                                             .into(),
                                                     ),
@@ -548,10 +621,10 @@ pub(super) fn compile_decode(
                                             util_expr
                                                 .method_call(
                                                     "longToHash",
-                                                    vec![Rc::clone(&key_expr)],
+                                                    vec![Arc::clone(&key_expr)],
                                                 )
                                                 .into(),
-                                            Rc::clone(&key_expr),
+                                            Arc::clone(&key_expr),
                                         )
                                         .into(),
                                     )
@@ -562,8 +635,8 @@ pub(super) fn compile_decode(
                         case_clause.push_statement(ast::Statement::Expression(
                             BinaryOperator::Assign
                                 .apply(
-                                    field_value_ref.element(Rc::clone(&key_expr)).into(),
-                                    Rc::clone(&val_expr),
+                                    field_value_ref.element(Arc::clone(&key_expr)).into(),
+                                    Arc::clone(&val_expr),
                                 )
                                 .into(),
                         ));
@@ -571,13 +644,41 @@ pub(super) fn compile_decode(
                     basic => case_clause.push_statement(
                         ast::BinaryOperator::Assign
                             .apply(
-                                Rc::clone(&field_value_ref),
-                                Rc::new(reader_var_expr.method_call(&basic.to_string(), vec![])),
+                                Arc::clone(&field_value_ref),
+                                lookup_value(
+                                    enum_values.as_ref(),
+                                    read_value(
+                                        options,
+                                        basic,
+                                        Arc::new(
+                                            reader_var_expr.method_call(&basic.to_string(), vec![]),
+                                        ),
+                                    ),
+                                ),
                             )
                             .into(),
                     ),
                 }
 
+                if has_field(options, field) {
+                    let has_name = has_field_name(root, options, current_message_id, field);
+                    case_clause.push_statement(set_has_field(
+                        &Arc::new(Arc::clone(&message_var_id).into()),
+                        &has_name,
+                    ));
+                }
+
+                if let Some((_, _, has_id)) = required_fields.iter().find(|(tag, ..)| *tag == id) {
+                    case_clause.push_statement(
+                        BinaryOperator::Assign
+                            .apply(
+                                Arc::new(Arc::clone(has_id).into()),
+                                ast::Expression::True.into(),
+                            )
+                            .into(),
+                    );
+                }
+
                 case_clause.push_statement(ast::Statement::Break);
 
                 switch_stmt.add_case(case_clause);
@@ -585,6 +686,22 @@ pub(super) fn compile_decode(
         }
     }
 
+    for (_, field_name, has_id) in required_fields {
+        let mut error = ast::NewExpression::new(Arc::new("Error".into()));
+        error.add_argument(Arc::new(
+            ast::StringLiteral::new(format!("missing required field \"{}\"", field_name).into())
+                .into(),
+        ));
+        decode_function_declaration.push_statement(
+            ast::IfStatement {
+                expression: ast::Expression::from(has_id).not().into(),
+                then_statement: ast::Expression::from(error).into_throw_statement().into(),
+                else_statement: None,
+            }
+            .into(),
+        );
+    }
+
     decode_function_declaration
         .push_statement(ast::Expression::from(message_var_id).into_return_statement());
 
@@ -592,61 +709,155 @@ pub(super) fn compile_decode(
         decode_function_declaration.into(),
     ));
 
+    // decodeDelimited(reader) = decode(reader, reader.uint32())
+    let mut decode_delimited_declaration = ast::FunctionDeclaration::new_exported(&function_name(
+        root,
+        options,
+        current_message_id,
+        DECODE_DELIMITED_FUNCTION_NAME,
+    ));
+    decode_delimited_declaration.add_param(ast::Parameter::new(
+        &reader_parameter_id,
+        ast::Type::UnionType(ast::UnionType {
+            types: vec![
+                ast::Type::from_id(&reader_type_id),
+                ast::Type::from_id("Uint8Array"),
+            ],
+        }),
+    ));
+    decode_delimited_declaration.returns(ast::Type::from_id(&message_type_id));
+    decode_delimited_declaration.push_statement(ast::Statement::VariableStatement(
+        ast::VariableDeclarationList::declare_const(
+            Arc::clone(&reader_var_id),
+            ast::Expression::conditional(
+                ast::BinaryOperator::InstanceOf
+                    .apply(
+                        Arc::clone(&reader_parameter_expr),
+                        Arc::clone(&reader_type_expr),
+                    )
+                    .into(),
+                Arc::clone(&reader_parameter_expr),
+                reader_type_expr
+                    .prop("create")
+                    .into_call(vec![Arc::clone(&reader_parameter_expr)])
+                    .into(),
+            ),
+        )
+        .into(),
+    ));
+    decode_delimited_declaration.push_statement(
+        ast::Expression::from(ast::Identifier::new(&function_name(
+            root,
+            options,
+            current_message_id,
+            DECODE_FUNCTION_NAME,
+        )))
+        .into_call(vec![
+            Arc::clone(&reader_var_expr),
+            reader_var_expr.method_call("uint32", vec![]).into(),
+        ])
+        .into_return_statement(),
+    );
+    file.push_statement(ast::Statement::FunctionDeclaration(
+        decode_delimited_declaration.into(),
+    ));
+
     message_folder.push_file(file);
     Ok(())
 }
 
-fn get_default_message_value(message_scope: &ProtoScope) -> ast::Expression {
-    ast::Expression::ObjectLiteralExpression(
-        message_scope
-            .get_message_declaration()
-            .unwrap()
-            .get_fields()
-            .into_iter()
-            .map(|f| {
-                let n = f.json_name();
-                let default_value = f.field_type.default_expression();
-                ObjectLiteralMember::PropertyAssignment(Rc::new(n.into()), default_value.into())
-                    .into()
-            })
-            .collect(),
-    )
-}
-
 fn import_decode_func(
     root: &RootScope,
+    options: &Options,
     message_scope: &ProtoScope,
     file: &mut ast::File,
     m_id: usize,
 ) -> ast::Expression {
-    let message_decode_path = {
-        let message_declaration_path = root.get_declaration_path(m_id).unwrap();
-        let mut ts_path = TsPath::from(message_declaration_path);
-        ts_path.push_file("decode");
-        ts_path.push_function("decode");
-        ts_path
-    };
-    let current_file_path = {
-        let message_declaration_path = root
-            .get_declaration_path(message_scope.id().unwrap())
-            .unwrap();
-        let mut ts_path = TsPath::from(message_declaration_path);
-        ts_path.push_file("decode");
-        ts_path
-    };
-    match get_relative_import_string(&current_file_path, &message_decode_path) {
-        Some(import_string) => {
-            let imported_name = Rc::new(ast::Identifier::from(format!("d{}", m_id)));
-            let import_stmt = ast::ImportDeclaration::import(
-                vec![ast::ImportSpecifier {
-                    name: Rc::clone(&imported_name),
-                    property_name: Some(Rc::new(DECODE_FUNCTION_NAME.into())),
-                }],
-                import_string.into(),
-            );
-            ensure_import(file, import_stmt);
-            ast::Expression::from(imported_name)
+    let message_id = message_scope.id().unwrap();
+    if let Some(name) = mapped_well_known_type(root, options, m_id) {
+        return ast::Expression::from(import_well_known(
+            root,
+            options,
+            file,
+            message_id,
+            "decode",
+            TsPathComponent::Function(format!("{}{}", DECODE_FUNCTION_NAME, name).into()),
+            Some(format!("d{}", m_id).into()),
+        ));
+    }
+    if root.is_cyclic_reference(message_id, m_id) {
+        return import_function_lazily(
+            root,
+            options,
+            file,
+            message_id,
+            m_id,
+            "decode",
+            DECODE_FUNCTION_NAME,
+        );
+    }
+    let imported_name = import_declaration(
+        file,
+        &declaration_file_path(root, options, message_id, "decode"),
+        &declaration_file_path(root, options, m_id, "decode"),
+        TsPathComponent::Function(function_name(root, options, m_id, DECODE_FUNCTION_NAME)),
+        Some(format!("d{}", m_id).into()),
+    );
+    ast::Expression::from(imported_name)
+}
+
+/// Imports the `create` factory of the message, which provides
+/// the initial value of the decoded message.
+pub(super) fn import_create_func(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+) -> ast::Expression {
+    let message_id = message_scope.id().unwrap();
+    let imported_name = import_declaration(
+        file,
+        &declaration_file_path(root, options, message_id, &file.name),
+        &declaration_file_path(root, options, message_id, "create"),
+        TsPathComponent::Function(function_name(
+            root,
+            options,
+            message_id,
+            CREATE_FUNCTION_NAME,
+        )),
+        None,
+    );
+    ast::Expression::from(imported_name)
+}
+
+#[cfg(test)]
+mod test_decode_compiler {
+    use super::super::test_utils::{compile_sources, render_file_at};
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "app/user.proto",
+        r#"
+        syntax = "proto3";
+        package app;
+        message User {
+            string name = 1;
+            repeated int32 scores = 3;
+            oneof contact { string email = 7; string phone = 8; }
         }
-        None => DECODE_FUNCTION_NAME.into(),
+        "#,
+    )];
+
+    #[test]
+    fn it_dispatches_on_the_field_number() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let decode = render_file_at(&folder, &["app", "user", "User", "decode"]);
+        assert!(decode.contains("    switch (tag >>> 3) {\n"));
+        let cases = decode
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("case "))
+            .collect::<Vec<_>>();
+        assert_eq!(cases, ["1: {", "3: {", "7: {", "8: {"]);
+        assert!(decode.contains("      default:\n        r.skipType(tag & 7)\n        break;\n"));
     }
 }
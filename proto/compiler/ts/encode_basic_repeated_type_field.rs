@@ -1,22 +1,33 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::proto::{
     compiler::ts::ast::{ElementAccess, MethodCall, MethodChain, StatementList},
     package::{self},
 };
 
-use super::ast::{self, ForStatement, Prop};
+use super::{
+    ast::{self, ForStatement, Prop},
+    enum_values::lookup_value,
+    int64_type::write_value,
+    options::Options,
+};
 
+/// Writes the elements of the repeated scalar field as a single length-delimited record
+/// when the field is `packed`, and as a record per element otherwise.
+/// Elements are looked up in the `numbers` object, when there is one, before they are written.
 pub(super) fn encode_basic_repeated_type_field(
-    field_value: &Rc<ast::Expression>,
+    options: &Options,
+    field_value: &Arc<ast::Expression>,
     field_type: &package::Type,
     field_tag: i64,
-    writer_var: &Rc<ast::Identifier>,
+    packed: bool,
+    numbers: Option<&Arc<ast::Expression>>,
+    writer_var: &Arc<ast::Identifier>,
 ) -> ast::Statement {
     let field_exists_expression = ast::BinaryOperator::LogicalAnd
         .apply(
             ast::BinaryOperator::WeakNotEqual
-                .apply(Rc::clone(&field_value), Rc::new(ast::Expression::Null))
+                .apply(Arc::clone(&field_value), Arc::new(ast::Expression::Null))
                 .into(),
             (*field_value).prop("length").into(),
         )
@@ -28,8 +39,22 @@ pub(super) fn encode_basic_repeated_type_field(
         package::Type::Repeated(_) => unreachable!(),
         package::Type::Map(_, _) => unreachable!(),
         basic => match basic.packed_wire_type() {
-            Some(_) => encode_packed_elements(&field_value, basic, field_tag, &writer_var),
-            None => encode_non_packed_elements(&field_value, basic, field_tag, &writer_var),
+            Some(_) if packed => encode_packed_elements(
+                options,
+                &field_value,
+                basic,
+                field_tag,
+                numbers,
+                &writer_var,
+            ),
+            _ => encode_non_packed_elements(
+                options,
+                &field_value,
+                basic,
+                field_tag,
+                numbers,
+                &writer_var,
+            ),
         },
     };
 
@@ -41,10 +66,12 @@ pub(super) fn encode_basic_repeated_type_field(
 }
 
 fn encode_non_packed_elements(
-    field_value: &Rc<ast::Expression>,
+    options: &Options,
+    field_value: &Arc<ast::Expression>,
     element_type: &package::Type,
     field_tag: i64,
-    writer_var: &Rc<ast::Identifier>,
+    numbers: Option<&Arc<ast::Expression>>,
+    writer_var: &Arc<ast::Identifier>,
 ) -> ast::Statement {
     assert!(element_type.is_basic());
     let mut res = ast::Block::new();
@@ -53,27 +80,31 @@ fn encode_non_packed_elements(
 
     let field_prefix = field_tag << 3 | (wire_type as i64);
 
-    let writer_expr: Rc<ast::Expression> =
-        ast::Expression::Identifier(Rc::clone(writer_var)).into();
+    let writer_expr: Arc<ast::Expression> =
+        ast::Expression::Identifier(Arc::clone(writer_var)).into();
 
     let tag_encoding_expr = writer_expr.method_call(
         "uint32",
-        vec![Rc::new(ast::Expression::NumericLiteral(
+        vec![Arc::new(ast::Expression::NumericLiteral(
             field_prefix as f64,
         ))],
     );
 
-    let i_id = Rc::new(ast::Identifier::new("i"));
-    let i_id_expr = Rc::new(Rc::clone(&i_id).into());
+    let i_id = Arc::new(ast::Identifier::new("i"));
+    let i_id_expr = Arc::new(Arc::clone(&i_id).into());
 
-    let element_value_expr: Rc<ast::Expression> = field_value.element(i_id_expr).into();
+    let element_value_expr = write_value(
+        options,
+        element_type,
+        lookup_value(numbers, field_value.element(i_id_expr).into()),
+    );
 
     let type_str = element_type.to_string();
-    let encode_element_expr: Rc<ast::Expression> = Rc::new(tag_encoding_expr)
+    let encode_element_expr: Arc<ast::Expression> = Arc::new(tag_encoding_expr)
         .method_call(&type_str, vec![element_value_expr])
         .into();
 
-    let mut for_stmt = ForStatement::for_each(i_id, Rc::clone(&field_value));
+    let mut for_stmt = ForStatement::for_each(i_id, Arc::clone(&field_value));
     for_stmt.push_statement(ast::Statement::Expression(encode_element_expr));
 
     res.push_statement(ast::Statement::For(for_stmt.into()));
@@ -81,23 +112,25 @@ fn encode_non_packed_elements(
     ast::Statement::Block(res)
 }
 fn encode_packed_elements(
-    field_value: &Rc<ast::Expression>,
+    options: &Options,
+    field_value: &Arc<ast::Expression>,
     element_type: &package::Type,
     field_tag: i64,
-    writer_var: &Rc<ast::Identifier>,
+    numbers: Option<&Arc<ast::Expression>>,
+    writer_var: &Arc<ast::Identifier>,
 ) -> ast::Statement {
     assert!(element_type.is_basic());
     let mut res = ast::Block::new();
 
     let field_prefix = field_tag << 3 | 2;
 
-    let writer_expr: Rc<ast::Expression> =
-        ast::Expression::Identifier(Rc::clone(writer_var)).into();
+    let writer_expr: Arc<ast::Expression> =
+        ast::Expression::Identifier(Arc::clone(writer_var)).into();
 
     let fork_call = writer_expr.method_chain(vec![
         (
             "uint32",
-            vec![Rc::new(ast::Expression::NumericLiteral(
+            vec![Arc::new(ast::Expression::NumericLiteral(
                 field_prefix as f64,
             ))],
         ),
@@ -106,14 +139,18 @@ fn encode_packed_elements(
 
     res.push_statement(ast::Statement::Expression(fork_call.into()));
 
-    let i_id = Rc::new(ast::Identifier::new("i"));
-    let i_id_expr = Rc::new(ast::Expression::Identifier(Rc::clone(&i_id)));
-    let mut for_stmt = ForStatement::for_each(i_id, Rc::clone(&field_value));
+    let i_id = Arc::new(ast::Identifier::new("i"));
+    let i_id_expr = Arc::new(ast::Expression::Identifier(Arc::clone(&i_id)));
+    let mut for_stmt = ForStatement::for_each(i_id, Arc::clone(&field_value));
 
-    let element_value_expr: Rc<ast::Expression> = field_value.element(i_id_expr).into();
+    let element_value_expr = write_value(
+        options,
+        element_type,
+        lookup_value(numbers, field_value.element(i_id_expr).into()),
+    );
 
     let type_str = element_type.to_string();
-    let encode_element_expr: Rc<ast::Expression> = writer_expr
+    let encode_element_expr: Arc<ast::Expression> = writer_expr
         .method_call(&type_str, vec![element_value_expr])
         .into();
 
@@ -1,17 +1,22 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::proto::{
     compiler::ts::has_property::has_property,
     package::{self},
 };
 
-use super::ast::{self, Identifier, MethodCall};
+use super::{
+    ast::{self, Identifier, MethodCall},
+    int64_type::write_value,
+    options::Options,
+};
 
 pub(crate) fn encode_basic_type_field(
-    field_value: &Rc<ast::Expression>,
-    message_parameter_id: &Rc<Identifier>,
-    js_name_id: &Rc<Identifier>,
-    writer_var: &Rc<Identifier>,
+    options: &Options,
+    field_value: &Arc<ast::Expression>,
+    message_parameter_id: &Arc<Identifier>,
+    js_name_id: &Arc<Identifier>,
+    writer_var: &Arc<Identifier>,
     field_type: &package::Type,
     field_tag: i64,
 ) -> ast::Statement {
@@ -20,26 +25,28 @@ pub(crate) fn encode_basic_type_field(
     let field_exists_expression = ast::BinaryOperator::LogicalAnd
         .apply(
             ast::BinaryOperator::WeakNotEqual
-                .apply(Rc::clone(&field_value), ast::Expression::Null.into())
+                .apply(Arc::clone(&field_value), ast::Expression::Null.into())
                 .into(),
             has_property(
-                ast::Expression::from(Rc::clone(message_parameter_id)).into(),
-                Rc::clone(js_name_id),
+                ast::Expression::from(Arc::clone(message_parameter_id)).into(),
+                Arc::clone(js_name_id),
             )
             .into(),
         )
         .into();
-    let writer_var_expr = Rc::new(ast::Expression::Identifier(Rc::clone(writer_var)));
+    let writer_var_expr = Arc::new(ast::Expression::Identifier(Arc::clone(writer_var)));
     let tag_encoding_expr = writer_var_expr.method_call(
         "uint32",
-        vec![Rc::new(ast::Expression::NumericLiteral(
+        vec![Arc::new(ast::Expression::NumericLiteral(
             field_prefix as f64,
         ))],
     );
 
     let type_str = field_type.to_string();
-    let encode_field_stmt =
-        Rc::new(tag_encoding_expr).method_call(&type_str, vec![Rc::clone(&field_value)]);
+    let encode_field_stmt = Arc::new(tag_encoding_expr).method_call(
+        &type_str,
+        vec![write_value(options, field_type, Arc::clone(&field_value))],
+    );
     ast::Statement::IfStatement(ast::IfStatement {
         expression: field_exists_expression,
         then_statement: ast::Statement::from(ast::Block {
@@ -1,12 +1,12 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use super::ast::{self, MethodChain};
 
 pub(super) fn encode_call(
     encode_func_expr: ast::Expression,
-    writer_expr: Rc<ast::Expression>,
+    writer_expr: Arc<ast::Expression>,
     field_tag: i64,
-    field_value: Rc<ast::Expression>,
+    field_value: Arc<ast::Expression>,
 ) -> ast::Expression {
     encode_func_expr
         .into_call(vec![
@@ -15,7 +15,7 @@ pub(super) fn encode_call(
                 .method_chain(vec![
                     (
                         "uint32",
-                        vec![Rc::new(((field_tag << 3 | 2) as f64).into())],
+                        vec![Arc::new(((field_tag << 3 | 2) as f64).into())],
                     ),
                     ("fork", vec![]),
                 ])
@@ -1,4 +1,4 @@
-use std::{ops::Deref, rc::Rc};
+use std::{ops::Deref, sync::Arc};
 
 use crate::proto::{
     compiler::ts::{
@@ -11,72 +11,79 @@ use crate::proto::{
 };
 
 use super::{
-    ast::{self, ElementAccess, Folder, MethodCall, Prop, Type, StatementList},
-    constants::{ENCODE_FUNCTION_NAME, PROTOBUF_MODULE},
+    ast::{self, ElementAccess, Folder, MethodCall, Prop, StatementList, Type},
+    constants::{ENCODE_DELIMITED_FUNCTION_NAME, ENCODE_FUNCTION_NAME},
     encode_basic_type_field::encode_basic_type_field,
     encode_enum_field::encode_enum_field,
     encode_map_field::encode_map_field,
-    ensure_import::ensure_import,
+    enum_values::{enum_numbers, lookup_value},
     has_property::has_property,
-    message_name_to_encode_type_name::message_name_to_encode_type_name,
+    layout::{declaration_file_path, encode_type_name, function_name, import_declaration},
+    options::Options,
+    renames::property_name,
+    runtime::runtime_module,
+    ts_path::TsPathComponent,
+    unknown_fields::write_unknown_fields,
 };
 
 pub(super) fn compile_encode(
     root: &RootScope,
+    options: &Options,
     message_folder: &mut Folder,
     message_scope: &ProtoScope,
 ) -> Result<(), ProtoError> {
     let mut file = super::ast::File::new("encode".into());
 
-    let writer_type_id: Rc<ast::Identifier> = ast::Identifier::new("Writer").into();
+    let writer_type_id: Arc<ast::Identifier> = ast::Identifier::new("Writer").into();
+    let current_message_id = message_scope.id().unwrap();
 
     file.push_statement(
         ast::ImportDeclaration::import(
-            vec![ast::ImportSpecifier::new(Rc::clone(&writer_type_id))],
-            PROTOBUF_MODULE.into(),
+            vec![ast::ImportSpecifier::new(Arc::clone(&writer_type_id))],
+            runtime_module(root, options, current_message_id, "encode").into(),
         )
         .into(),
     );
 
-    let mut encode_func = ast::FunctionDeclaration::new_exported(ENCODE_FUNCTION_NAME);
-
-    let message_encode_input_type_id: Rc<ast::Identifier> = ast::Identifier::new(
-        &message_name_to_encode_type_name(message_scope.name().as_ref()),
-    )
-    .into();
+    let mut encode_func = ast::FunctionDeclaration::new_exported(&function_name(
+        root,
+        options,
+        current_message_id,
+        ENCODE_FUNCTION_NAME,
+    ));
 
-    let encode_type_import = ast::ImportDeclaration::import(
-        vec![ast::ImportSpecifier::new(Rc::clone(
-            &message_encode_input_type_id,
-        ))],
-        "./types".into(),
+    let message_encode_input_type_id = import_declaration(
+        &mut file,
+        &declaration_file_path(root, options, current_message_id, "encode"),
+        &declaration_file_path(root, options, current_message_id, "types"),
+        TsPathComponent::Interface(encode_type_name(root, options, current_message_id)),
+        None,
     );
-    ensure_import(&mut file, encode_type_import);
 
-    let message_parameter_id = Rc::new(ast::Identifier::new("message"));
-    let writer_parameter_id = Rc::new(ast::Identifier::new("writer"));
+    let message_parameter_id = Arc::new(ast::Identifier::new("message"));
+    let writer_parameter_id = Arc::new(ast::Identifier::new("writer"));
 
     encode_func.add_param(ast::Parameter::new(
         &message_parameter_id,
-        Type::reference(Rc::clone(&message_encode_input_type_id)),
+        Type::reference(Arc::clone(&message_encode_input_type_id)),
     ));
     encode_func.add_param(ast::Parameter::new_optional(
         &writer_parameter_id,
-        Type::reference(Rc::clone(&writer_type_id)),
+        Type::reference(Arc::clone(&writer_type_id)),
     ));
 
-    encode_func.returns(Type::reference(Rc::clone(&writer_type_id)).into());
+    encode_func.returns(Type::reference(Arc::clone(&writer_type_id)).into());
 
-    let writer_var = Rc::new(ast::Identifier { text: "w".into() });
-    let writer_var_expr = Rc::new(ast::Expression::Identifier(Rc::clone(&writer_var)));
+    let writer_var = Arc::new(ast::Identifier { text: "w".into() });
+    let writer_var_expr = Arc::new(ast::Expression::Identifier(Arc::clone(&writer_var)));
 
     encode_func.push_statement(
         ast::Statement::from(ast::VariableDeclarationList::declare_const(
-            Rc::clone(&writer_var),
+            Arc::clone(&writer_var),
             ast::BinaryOperator::LogicalOr
                 .apply(
-                    ast::Expression::from(Rc::clone(&writer_parameter_id)).into(),
-                    Rc::new(ast::Expression::from(Rc::clone(&writer_type_id)))
+                    ast::Expression::from(Arc::clone(&writer_parameter_id)).into(),
+                    Arc::new(ast::Expression::from(Arc::clone(&writer_type_id)))
                         .method_call("create", vec![])
                         .into(),
                 )
@@ -93,22 +100,22 @@ pub(super) fn compile_encode(
     let fields = message_declaration.get_fields();
 
     for field in fields {
-        let js_name = field.json_name();
-        let js_name_id: Rc<ast::Identifier> = ast::Identifier::new(&js_name).into();
-        let message_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&message_parameter_id).into());
-        let field_value = Rc::new(message_expr.prop(&js_name));
+        let js_name = property_name(root, options, current_message_id, field);
+        let js_name_id: Arc<ast::Identifier> = ast::Identifier::new(&js_name).into();
+        let message_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&message_parameter_id).into());
+        let field_value = Arc::new(message_expr.prop(&js_name));
         match &field.field_type {
-            package::Type::Enum(_) => {
-                encode_func.push_statement(
-                    encode_enum_field(
-                        &message_parameter_id,
-                        &writer_var,
-                        &js_name_id,
-                        field_value,
-                        field.tag,
-                    )
-                    .into(),
+            package::Type::Enum(e_id) => {
+                let numbers = enum_numbers(root, options, message_scope, &mut file, *e_id);
+                let encode_stmt = encode_enum_field(
+                    &message_parameter_id,
+                    &writer_var,
+                    &js_name_id,
+                    Arc::clone(&field_value),
+                    lookup_value(numbers.as_ref(), Arc::clone(&field_value)),
+                    field.tag,
                 );
+                encode_func.push_statement(with_presence(field, &field_value, encode_stmt));
             }
             package::Type::Message(m_id) => {
                 let message_id = *m_id;
@@ -116,57 +123,58 @@ pub(super) fn compile_encode(
                 let field_exists_expression = ast::BinaryOperator::LogicalAnd
                     .apply(
                         ast::BinaryOperator::WeakNotEqual
-                            .apply(Rc::clone(&field_value), ast::Expression::Null.into())
+                            .apply(Arc::clone(&field_value), ast::Expression::Null.into())
                             .into(),
                         has_property(
-                            ast::Expression::Identifier(Rc::clone(&message_parameter_id)).into(),
-                            Rc::clone(&js_name_id),
+                            ast::Expression::Identifier(Arc::clone(&message_parameter_id)).into(),
+                            Arc::clone(&js_name_id),
                         )
                         .into(),
                     )
                     .into();
                 let message_encode_expr =
-                    encode_message_expr(&root, &message_scope, &mut file, message_id);
+                    encode_message_expr(root, options, message_scope, &mut file, message_id);
                 let expr = encode_call(
                     message_encode_expr,
-                    Rc::clone(&writer_var_expr),
+                    Arc::clone(&writer_var_expr),
                     field.tag,
-                    field_value,
+                    Arc::clone(&field_value),
                 );
 
-                encode_func.push_statement(ast::Statement::IfStatement(ast::IfStatement {
+                let encode_stmt = ast::Statement::IfStatement(ast::IfStatement {
                     expression: field_exists_expression,
                     then_statement: ast::Statement::Block(ast::Block {
                         statements: vec![ast::Statement::Expression(expr.into()).into()],
                     })
                     .into(),
                     else_statement: None,
-                }));
+                });
+                encode_func.push_statement(with_presence(field, &field_value, encode_stmt));
             }
             package::Type::Repeated(element_type) => match element_type.deref() {
                 package::Type::Message(m_id) => {
                     let message_id = *m_id;
                     let message_encode_expr =
-                        encode_message_expr(&root, &message_scope, &mut file, message_id);
+                        encode_message_expr(root, options, message_scope, &mut file, message_id);
 
                     let array_is_not_empty = ast::BinaryOperator::LogicalAnd
                         .apply(
                             ast::BinaryOperator::WeakNotEqual
-                                .apply(Rc::clone(&field_value), ast::Expression::Null.into())
+                                .apply(Arc::clone(&field_value), ast::Expression::Null.into())
                                 .into(),
                             field_value.prop("length").into(),
                         )
                         .into();
 
                     let i_id = ast::Identifier::from("i").into();
-                    let i_id_expr = ast::Expression::from(Rc::clone(&i_id));
+                    let i_id_expr = ast::Expression::from(Arc::clone(&i_id));
 
                     let mut for_stmt =
-                        ast::ForStatement::for_each(Rc::clone(&i_id), Rc::clone(&field_value));
+                        ast::ForStatement::for_each(Arc::clone(&i_id), Arc::clone(&field_value));
 
                     let expr = encode_call(
                         message_encode_expr,
-                        Rc::clone(&writer_var_expr),
+                        Arc::clone(&writer_var_expr),
                         field.tag,
                         field_value.element(i_id_expr.into()).into(),
                     );
@@ -181,12 +189,16 @@ pub(super) fn compile_encode(
                 }
                 package::Type::Repeated(_) => unreachable!(),
                 package::Type::Map(_, _) => unreachable!(),
-                package::Type::Enum(_) => {
+                package::Type::Enum(e_id) => {
+                    let numbers = enum_numbers(root, options, message_scope, &mut file, *e_id);
                     encode_func.push_statement(
                         encode_basic_repeated_type_field(
+                            options,
                             &field_value,
                             &package::Type::Int32,
                             field.tag,
+                            field.packed,
+                            numbers.as_ref(),
                             &writer_var,
                         )
                         .into(),
@@ -197,9 +209,12 @@ pub(super) fn compile_encode(
 
                     encode_func.push_statement(
                         encode_basic_repeated_type_field(
+                            options,
                             &field_value,
                             basic,
                             field.tag,
+                            field.packed,
+                            None,
                             &writer_var,
                         )
                         .into(),
@@ -208,8 +223,9 @@ pub(super) fn compile_encode(
             },
             package::Type::Map(kt, vt) => encode_func.push_statement(
                 encode_map_field(
-                    &root,
-                    &message_scope,
+                    root,
+                    options,
+                    message_scope,
                     &mut file,
                     &message_parameter_id,
                     &writer_var,
@@ -224,20 +240,27 @@ pub(super) fn compile_encode(
             t => {
                 assert!(t.is_basic());
 
-                encode_func.push_statement(
-                    encode_basic_type_field(
-                        &field_value,
-                        &message_parameter_id,
-                        &js_name_id,
-                        &writer_var,
-                        t,
-                        field.tag,
-                    )
-                    .into(),
+                let encode_stmt = encode_basic_type_field(
+                    options,
+                    &field_value,
+                    &message_parameter_id,
+                    &js_name_id,
+                    &writer_var,
+                    t,
+                    field.tag,
                 );
+                encode_func.push_statement(with_presence(field, &field_value, encode_stmt));
             }
         }
     }
+    if options.keep_unknown_fields {
+        encode_func.push_statement(write_unknown_fields(
+            options,
+            &mut file,
+            &Arc::new(Arc::clone(&message_parameter_id).into()),
+            &writer_var_expr,
+        ));
+    }
 
     encode_func.push_statement(
         ast::Expression::from(writer_var)
@@ -247,7 +270,241 @@ pub(super) fn compile_encode(
 
     file.push_statement(encode_func.into());
 
+    // encodeDelimited(message, writer) = encode(message, writer.fork()).ldelim()
+    let encode_func_name = function_name(root, options, current_message_id, ENCODE_FUNCTION_NAME);
+    let mut encode_delimited_func = ast::FunctionDeclaration::new_exported(&function_name(
+        root,
+        options,
+        current_message_id,
+        ENCODE_DELIMITED_FUNCTION_NAME,
+    ));
+    encode_delimited_func.add_param(ast::Parameter::new(
+        &message_parameter_id,
+        Type::reference(Arc::clone(&message_encode_input_type_id)),
+    ));
+    encode_delimited_func.add_param(ast::Parameter::new_optional(
+        &writer_parameter_id,
+        Type::reference(Arc::clone(&writer_type_id)),
+    ));
+    encode_delimited_func.returns(Type::reference(Arc::clone(&writer_type_id)));
+    let forked_writer = ast::BinaryOperator::LogicalOr
+        .apply(
+            ast::Expression::from(Arc::clone(&writer_parameter_id)).into(),
+            Arc::new(ast::Expression::from(Arc::clone(&writer_type_id)))
+                .method_call("create", vec![])
+                .into(),
+        )
+        .into_prop("fork")
+        .into_call(vec![]);
+    encode_delimited_func.push_statement(
+        ast::Expression::from(ast::Identifier::new(&encode_func_name))
+            .into_call(vec![
+                Arc::new(Arc::clone(&message_parameter_id).into()),
+                forked_writer.into(),
+            ])
+            .into_prop("ldelim")
+            .into_call(vec![])
+            .into_return_statement(),
+    );
+    file.push_statement(encode_delimited_func.into());
+
     message_folder.push_file(file);
 
     Ok(())
 }
+
+/// Fields with explicit presence (proto3 `optional`) are written
+/// whenever they are not undefined, even if the value is the default one.
+fn with_presence(
+    field: &package::Field,
+    field_value: &Arc<ast::Expression>,
+    encode_stmt: ast::Statement,
+) -> ast::Statement {
+    if !field.optional {
+        return encode_stmt;
+    }
+    match encode_stmt {
+        ast::Statement::IfStatement(if_stmt) => ast::Statement::IfStatement(ast::IfStatement {
+            expression: ast::BinaryOperator::StrictNotEqual
+                .apply(Arc::clone(field_value), ast::Expression::Undefined.into())
+                .into(),
+            ..if_stmt
+        }),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test_optional_fields {
+    use super::super::{options::Options, test_utils::*};
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "opt.proto",
+        r#"
+        syntax = "proto3";
+        package opt;
+        enum Color {
+            RED = 0;
+            GREEN = 1;
+        }
+        message Point {
+            int32 x = 1;
+        }
+        message Opt {
+            optional Color color = 1;
+            optional Point point = 2;
+            optional string label = 3;
+            string plain = 4;
+        }
+        "#,
+    )];
+
+    #[test]
+    fn it_encodes_optional_fields_when_not_undefined() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let encode = render_file_at(&folder, &["opt", "opt", "Opt", "encode"]);
+        assert!(encode.contains(
+            "if (message.color !== undefined) {\n    w.uint32(8).int32(message.color)\n  }"
+        ));
+        assert!(encode.contains("if (message.point !== undefined) {\n    e"));
+        assert!(encode.contains(
+            "if (message.label !== undefined) {\n    w.uint32(26).string(message.label)\n  }"
+        ));
+        assert!(encode.contains(
+            "if (message.plain != null && Object.hasOwnProperty.call(message, \"plain\")) {"
+        ));
+    }
+
+    #[test]
+    fn it_types_optional_fields_as_possibly_undefined() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let types = render_file_at(&folder, &["opt", "opt", "Opt", "types"]);
+        assert!(types.contains("  color?: Color\n"));
+        assert!(types.contains("  point?: PointEncodeInput\n"));
+        assert!(types.contains("  point?: Point\n"));
+        assert!(types.contains("  label?: string\n"));
+        assert!(types.contains("  plain?: string | null\n"));
+        assert!(types.contains("  plain: string\n"));
+    }
+
+    #[test]
+    fn it_leaves_optional_fields_out_of_defaults() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let create = render_file_at(&folder, &["opt", "opt", "Opt", "create"]);
+        assert!(create.contains("  const message: any = {\n    plain: \"\",\n    ...init,\n  }"));
+    }
+}
+
+#[cfg(test)]
+mod test_packed_fields {
+    use super::super::{
+        options::{Layout, Options, Runtime},
+        test_utils::*,
+    };
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "p3.proto",
+            r#"
+            syntax = "proto3";
+            package p3;
+            enum Color { RED = 0; }
+            message List {
+                repeated int32 packed = 1;
+                repeated int32 unpacked = 2 [packed = false];
+                repeated Color colors = 3;
+                repeated string names = 4;
+            }
+            "#,
+        ),
+        (
+            "p2.proto",
+            r#"
+            syntax = "proto2";
+            package p2;
+            message List {
+                repeated int32 unpacked = 1;
+                repeated double packed = 2 [packed = true];
+            }
+            "#,
+        ),
+    ];
+
+    #[test]
+    fn it_packs_repeated_scalars_of_proto3_by_default() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let encode = render_file_at(&folder, &["p3", "p3", "List", "encode"]);
+        assert!(encode.contains(
+            "    w.uint32(10).fork()\n    for (let i = 0; i < message.packed.length; ++i)\n      w.int32(message.packed[i])\n    w.ldelim()\n"
+        ));
+        assert!(encode.contains(
+            "    for (let i = 0; i < message.unpacked.length; ++i)\n      w.uint32(16).int32(message.unpacked[i])\n"
+        ));
+        assert!(encode.contains("    w.uint32(26).fork()\n"));
+        assert!(encode.contains("      w.uint32(34).string(message.names[i])\n"));
+    }
+
+    #[test]
+    fn it_packs_repeated_scalars_of_proto2_only_when_requested() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let encode = render_file_at(&folder, &["p2", "p2", "List", "encode"]);
+        assert!(encode.contains("      w.uint32(8).int32(message.unpacked[i])\n"));
+        assert!(encode.contains(
+            "    w.uint32(18).fork()\n    for (let i = 0; i < message.packed.length; ++i)\n      w.double(message.packed[i])\n"
+        ));
+    }
+
+    #[test]
+    fn it_decodes_both_forms_regardless_of_the_option() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        for path in [
+            ["p3", "p3", "List", "decode"],
+            ["p2", "p2", "List", "decode"],
+        ] {
+            let decode = render_file_at(&folder, &path);
+            assert!(decode.contains("        if ((tag & 7) === 2) {\n"));
+            assert!(decode.contains("        } else message.unpacked.push(r.int32())\n"));
+        }
+    }
+
+    /// Runs `packed_test.js` with the generated module of `pk.Samples`.
+    /// Skipped when `node` is not installed.
+    #[test]
+    fn it_writes_the_bytes_protobufjs_writes() {
+        let sources = &[(
+            "pk/samples.proto",
+            r#"
+            syntax = "proto3";
+            package pk;
+            message Samples {
+                repeated int32 ints = 1;
+                repeated sint64 zigzags = 2;
+                repeated fixed32 fixeds = 3;
+                repeated int32 unpacked = 4 [packed = false];
+                repeated string names = 5;
+            }
+            "#,
+        )];
+        let options = Options {
+            layout: Layout::File,
+            runtime: Runtime::Standalone,
+            emit_json: false,
+            ..Options::default()
+        };
+        let folder = compile_sources(sources, &options);
+        run_node_script(
+            "the packed test vectors",
+            concat!(
+                include_str!("load_runtime.js"),
+                include_str!("packed_test.js")
+            ),
+            &[
+                ("PROTOS_TS_RUNTIME", render_file_at(&folder, &["runtime"])),
+                (
+                    "PROTOS_TS_MODULE",
+                    render_file_at(&folder, &["pk", "samples"]),
+                ),
+            ],
+        );
+    }
+}
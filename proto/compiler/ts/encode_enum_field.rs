@@ -1,14 +1,16 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use super::ast::{self, MethodChain};
 use super::has_property::has_property;
 use crate::proto::package;
 
+/// Writes the `wire_value` of the enum field, the number of the member `field_value` is.
 pub(super) fn encode_enum_field(
-    message_parameter_id: &Rc<ast::Identifier>,
-    writer_var: &Rc<ast::Identifier>,
-    js_name_id: &Rc<ast::Identifier>,
-    field_value: Rc<ast::Expression>,
+    message_parameter_id: &Arc<ast::Identifier>,
+    writer_var: &Arc<ast::Identifier>,
+    js_name_id: &Arc<ast::Identifier>,
+    field_value: Arc<ast::Expression>,
+    wire_value: Arc<ast::Expression>,
     field_tag: i64,
 ) -> ast::Statement {
     let wire_type = package::Type::Int32.get_basic_wire_type();
@@ -16,27 +18,27 @@ pub(super) fn encode_enum_field(
     let field_exists_expression = ast::BinaryOperator::LogicalAnd
         .apply(
             ast::BinaryOperator::WeakNotEqual
-                .apply(Rc::clone(&field_value), ast::Expression::Null.into())
+                .apply(Arc::clone(&field_value), ast::Expression::Null.into())
                 .into(),
             has_property(
-                ast::Expression::Identifier(Rc::clone(message_parameter_id)).into(),
-                Rc::clone(js_name_id),
+                ast::Expression::Identifier(Arc::clone(message_parameter_id)).into(),
+                Arc::clone(js_name_id),
             )
             .into(),
         )
         .into();
 
-    let writer_var_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&writer_var).into());
+    let writer_var_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&writer_var).into());
     let encode_field_stmt = ast::Statement::Expression(
         writer_var_expr
             .method_chain(vec![
                 (
                     "uint32",
-                    vec![Rc::new(ast::Expression::NumericLiteral(
+                    vec![Arc::new(ast::Expression::NumericLiteral(
                         field_prefix as f64,
                     ))],
                 ),
-                ("int32", vec![Rc::clone(&field_value)]),
+                ("int32", vec![wire_value]),
             ])
             .into(),
     );
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::proto::{
     compiler::ts::ast::ElementAccess,
@@ -10,17 +10,21 @@ use crate::proto::{
 use super::{
     ast::{self, MethodCall, MethodChain, StatementList},
     encode_message_expr::encode_message_expr,
+    enum_values::{enum_numbers, lookup_value},
     has_property::has_property,
+    int64_type::write_value,
+    options::Options,
 };
 
 pub(super) fn encode_map_field(
     root: &RootScope,
+    options: &Options,
     parent_message_scope: &ProtoScope,
     encode_file: &mut ast::File,
-    message_parameter_id: &Rc<ast::Identifier>,
-    writer_var: &Rc<ast::Identifier>,
-    js_name_id: &Rc<ast::Identifier>,
-    field_value: &Rc<ast::Expression>,
+    message_parameter_id: &Arc<ast::Identifier>,
+    writer_var: &Arc<ast::Identifier>,
+    js_name_id: &Arc<ast::Identifier>,
+    field_value: &Arc<ast::Expression>,
     field_tag: i64,
     key_type: &package::Type,
     value_type: &package::Type,
@@ -28,11 +32,11 @@ pub(super) fn encode_map_field(
     let field_exists_expression = ast::BinaryOperator::LogicalAnd
         .apply(
             ast::BinaryOperator::WeakNotEqual
-                .apply(Rc::clone(&field_value), ast::Expression::Null.into())
+                .apply(Arc::clone(&field_value), ast::Expression::Null.into())
                 .into(),
             has_property(
-                Rc::new(Rc::clone(message_parameter_id).into()),
-                Rc::clone(js_name_id),
+                Arc::new(Arc::clone(message_parameter_id).into()),
+                Arc::clone(js_name_id),
             )
             .into(),
         )
@@ -40,46 +44,46 @@ pub(super) fn encode_map_field(
 
     let mut then_block = ast::Block::new();
 
-    let i_id = Rc::new(ast::Identifier::from("i"));
-    let keys_id: Rc<ast::Identifier> = Rc::new(ast::Identifier::from("ks"));
+    let i_id = Arc::new(ast::Identifier::from("i"));
+    let keys_id: Arc<ast::Identifier> = Arc::new(ast::Identifier::from("ks"));
 
     then_block.push_statement(
         ast::VariableDeclarationList::declare_const(
-            Rc::clone(&keys_id),
-            object_keys(Rc::clone(field_value)),
+            Arc::clone(&keys_id),
+            object_keys(Arc::clone(field_value)),
         )
         .into(),
     );
 
-    let keys_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&keys_id).into());
-    let i_id_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&i_id).into());
+    let keys_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&keys_id).into());
+    let i_id_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&i_id).into());
 
-    let mut for_stmt = ast::ForStatement::for_each(Rc::clone(&i_id), Rc::clone(&keys_expr));
+    let mut for_stmt = ast::ForStatement::for_each(Arc::clone(&i_id), Arc::clone(&keys_expr));
 
-    let key_id = Rc::new(ast::Identifier::from("k"));
-    let key_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&key_id).into());
+    let key_id = Arc::new(ast::Identifier::from("k"));
+    let key_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&key_id).into());
     for_stmt.push_statement(
         ast::VariableDeclarationList::declare_const(
-            Rc::clone(&key_id),
-            keys_expr.element(Rc::clone(&i_id_expr)),
+            Arc::clone(&key_id),
+            keys_expr.element(Arc::clone(&i_id_expr)),
         )
         .into(),
     );
-    let value_id = Rc::new(ast::Identifier::from("v"));
-    let value_expr: Rc<ast::Expression> = Rc::new(Rc::clone(&value_id).into());
+    let value_id = Arc::new(ast::Identifier::from("v"));
+    let value_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&value_id).into());
     for_stmt.push_statement(
         ast::VariableDeclarationList::declare_const(
-            Rc::clone(&value_id),
-            field_value.element(Rc::clone(&key_expr)),
+            Arc::clone(&value_id),
+            field_value.element(Arc::clone(&key_expr)),
         )
         .into(),
     );
 
-    let writer_var_expr: Rc<ast::Expression> =
-        Rc::new(ast::Expression::Identifier(Rc::clone(writer_var)));
+    let writer_var_expr: Arc<ast::Expression> =
+        Arc::new(ast::Expression::Identifier(Arc::clone(writer_var)));
 
-    let encode_key_expr = Rc::new(encode_key(
-        Rc::clone(&writer_var_expr),
+    let encode_key_expr = Arc::new(encode_key(
+        Arc::clone(&writer_var_expr),
         field_tag,
         key_type,
         key_expr,
@@ -91,7 +95,7 @@ pub(super) fn encode_map_field(
 
         package::Type::Message(m_id) => {
             let encode_func_expr =
-                encode_message_expr(&root, &parent_message_scope, encode_file, *m_id);
+                encode_message_expr(root, options, parent_message_scope, encode_file, *m_id);
 
             for_stmt.push_statement(encode_key_expr.into());
 
@@ -100,7 +104,7 @@ pub(super) fn encode_map_field(
                     value_expr,
                     writer_var_expr
                         .method_chain(vec![
-                            ("uint32", vec![Rc::new(18f64.into())]),
+                            ("uint32", vec![Arc::new(18f64.into())]),
                             ("fork", vec![]),
                         ])
                         .into(),
@@ -112,12 +116,17 @@ pub(super) fn encode_map_field(
 
             for_stmt.push_statement(encode_value.into());
         }
-        package::Type::Enum(_) => {
-            let key_value_expr =
-                encode_basic_key_value(&package::Type::Int32, encode_key_expr, value_expr);
+        package::Type::Enum(e_id) => {
+            let numbers = enum_numbers(root, options, parent_message_scope, encode_file, *e_id);
+            let key_value_expr = encode_basic_key_value(
+                &package::Type::Int32,
+                encode_key_expr,
+                lookup_value(numbers.as_ref(), value_expr),
+            );
             for_stmt.push_statement(key_value_expr.into());
         }
         basic => {
+            let value_expr = write_value(options, basic, value_expr);
             for_stmt
                 .push_statement(encode_basic_key_value(basic, encode_key_expr, value_expr).into());
         }
@@ -127,7 +136,7 @@ pub(super) fn encode_map_field(
 
     let if_stmt = ast::Statement::IfStatement(ast::IfStatement {
         expression: field_exists_expression,
-        then_statement: Rc::new(ast::Statement::Block(then_block)),
+        then_statement: Arc::new(ast::Statement::Block(then_block)),
         else_statement: None,
     });
 
@@ -136,12 +145,12 @@ pub(super) fn encode_map_field(
 
 fn encode_basic_key_value(
     basic: &package::Type,
-    encode_key_expr: Rc<ast::Expression>,
-    value_expr: Rc<ast::Expression>,
+    encode_key_expr: Arc<ast::Expression>,
+    value_expr: Arc<ast::Expression>,
 ) -> ast::Expression {
     let wire_type = basic.get_basic_wire_type();
-    let wire_type_expr: Rc<ast::Expression> =
-        Rc::new(ast::Expression::from((16 | wire_type) as f64));
+    let wire_type_expr: Arc<ast::Expression> =
+        Arc::new(ast::Expression::from((16 | wire_type) as f64));
     let value_type_str = basic.to_string();
     encode_key_expr.method_chain(vec![
         ("uint32", vec![wire_type_expr]),
@@ -151,25 +160,25 @@ fn encode_basic_key_value(
 }
 
 fn encode_key(
-    writer_var_expr: Rc<ast::Expression>,
+    writer_var_expr: Arc<ast::Expression>,
     field_tag: i64,
     key_type: &package::Type,
-    key_expr: Rc<ast::Expression>,
+    key_expr: Arc<ast::Expression>,
 ) -> ast::Expression {
     let key_prefix = field_tag << 3 | 2;
     let map_key_wire = key_type.map_key_wire_type().unwrap();
     let map_key_wire_prefix = 8 | map_key_wire;
     let field_key_type_str = key_type.to_string();
     writer_var_expr.method_chain(vec![
-        ("uint32", vec![Rc::new((key_prefix as f64).into())]),
+        ("uint32", vec![Arc::new((key_prefix as f64).into())]),
         ("fork", vec![]),
-        ("uint32", vec![Rc::new((map_key_wire_prefix as f64).into())]),
+        ("uint32", vec![Arc::new((map_key_wire_prefix as f64).into())]),
         (&field_key_type_str, vec![key_expr]),
     ])
 }
 
-fn object_keys(obj_expr: Rc<ast::Expression>) -> ast::Expression {
+fn object_keys(obj_expr: Arc<ast::Expression>) -> ast::Expression {
     let object_id: ast::Identifier = "Object".into();
-    let object_expr = Rc::new(ast::Expression::Identifier(object_id.into()));
+    let object_expr = Arc::new(ast::Expression::Identifier(object_id.into()));
     object_expr.method_call("keys", vec![obj_expr])
 }
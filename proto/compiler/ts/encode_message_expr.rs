@@ -1,48 +1,57 @@
-use std::rc::Rc;
-
 use crate::proto::proto_scope::{root_scope::RootScope, ProtoScope};
 
 use super::{
-    ast::{self, File, ImportSpecifier},
+    ast::{self, File},
     constants::ENCODE_FUNCTION_NAME,
-    ensure_import::ensure_import,
-    get_relative_import::get_relative_import_string,
-    ts_path::{TsPath, TsPathComponent},
+    layout::{declaration_file_path, function_name, import_declaration, import_function_lazily},
+    options::Options,
+    ts_path::TsPathComponent,
+    well_known_types::{import_well_known, mapped_well_known_type},
 };
 
 pub(super) fn encode_message_expr(
     root: &RootScope,
+    options: &Options,
     parent_message_scope: &ProtoScope,
     encode_file: &mut File,
     field_message_id: usize,
 ) -> ast::Expression {
-    let encode_func_path = {
-        let mut res = TsPath::from(root.get_declaration_path(field_message_id).unwrap());
-        res.push(TsPathComponent::File("encode".into()));
-        res.push(TsPathComponent::Function("encode".into()));
-        res
-    };
-    let current_path = {
-        let mut res = TsPath::from(
-            root.get_declaration_path(parent_message_scope.id().unwrap())
-                .unwrap(),
+    let message_id = parent_message_scope.id().unwrap();
+    if let Some(name) = mapped_well_known_type(root, options, field_message_id) {
+        return ast::Expression::from(import_well_known(
+            root,
+            options,
+            encode_file,
+            message_id,
+            "encode",
+            TsPathComponent::Function(format!("{}{}", ENCODE_FUNCTION_NAME, name).into()),
+            Some(format!("e{}", field_message_id).into()),
+        ));
+    }
+    if root.is_cyclic_reference(message_id, field_message_id) {
+        return import_function_lazily(
+            root,
+            options,
+            encode_file,
+            message_id,
+            field_message_id,
+            "encode",
+            ENCODE_FUNCTION_NAME,
         );
-        res.push(TsPathComponent::File("encode".into()));
-        res
-    };
-    match get_relative_import_string(&current_path, &encode_func_path) {
-        Some(import_string) => {
-            let imported_name = Rc::new(ast::Identifier::from(format!("e{}", field_message_id)));
-            let import_stmt = ast::ImportDeclaration::import(
-                vec![ImportSpecifier {
-                    name: Rc::clone(&imported_name),
-                    property_name: Some(Rc::new(ENCODE_FUNCTION_NAME.into())),
-                }],
-                import_string.into(),
-            );
-            ensure_import(encode_file, import_stmt);
-            ast::Expression::from(imported_name)
-        }
-        None => ast::Expression::from(ENCODE_FUNCTION_NAME),
     }
+    let encode_file_path = declaration_file_path(root, options, field_message_id, "encode");
+    let current_path = declaration_file_path(root, options, message_id, "encode");
+    let imported_name = import_declaration(
+        encode_file,
+        &current_path,
+        &encode_file_path,
+        TsPathComponent::Function(function_name(
+            root,
+            options,
+            field_message_id,
+            ENCODE_FUNCTION_NAME,
+        )),
+        Some(format!("e{}", field_message_id).into()),
+    );
+    ast::Expression::from(imported_name)
 }
@@ -1,61 +1,177 @@
+use std::sync::Arc;
+
 use super::ast;
 
+/// Whether specifiers of `new_import` can be merged into `import`:
+/// both import from the same file in the same way.
+fn is_same_import(import: &ast::ImportDeclaration, new_import: &ast::ImportDeclaration) -> bool {
+    import.string_literal.text == new_import.string_literal.text
+        && import.import_clause.is_type_only == new_import.import_clause.is_type_only
+        && import.import_clause.namespace_import == new_import.import_clause.namespace_import
+}
+
+/// Imports the module `file_path` as a namespace, reusing the existing
+/// namespace import of the same module. Returns the name of the namespace.
+pub(super) fn ensure_namespace_import(
+    file: &mut ast::File,
+    file_path: &str,
+    name: Arc<ast::Identifier>,
+) -> Arc<ast::Identifier> {
+    for statement in file.ast.statements.iter() {
+        let import = match statement {
+            ast::Statement::ImportDeclaration(import) => import,
+            _ => break,
+        };
+        if let Some(namespace) = &import.import_clause.namespace_import {
+            if &*import.string_literal.text == file_path {
+                return Arc::clone(namespace);
+            }
+        }
+    }
+    ensure_import(
+        file,
+        ast::ImportDeclaration::import_namespace(Arc::clone(&name), file_path.into()),
+    );
+    name
+}
+
+/// Adds `new_import` to the imports at the top of `file`.
+///
+/// Specifiers of an existing import of the same module are merged into it,
+/// skipping the ones whose local name is already imported, otherwise the import is inserted in the order of module specifiers.
+/// Named bindings are kept sorted by the imported name,
+/// so the output does not depend on the order in which the imports are requested.
 pub(super) fn ensure_import(file: &mut ast::File, new_import: ast::ImportDeclaration) {
+    let mut insert_index = None;
     let mut import_statement_index = 0;
     let mut found_import_statement_to_the_same_file = false;
     while import_statement_index < file.ast.statements.len() {
         let statement = &mut file.ast.statements[import_statement_index];
         match statement {
             ast::Statement::ImportDeclaration(import) => {
-                if import.string_literal.text != new_import.string_literal.text {
-                    import_statement_index += 1;
-                    continue;
+                if is_same_import(import, &new_import) {
+                    found_import_statement_to_the_same_file = true;
+                    break;
                 }
-                found_import_statement_to_the_same_file = true;
-                break;
+                if insert_index.is_none()
+                    && import.string_literal.text > new_import.string_literal.text
+                {
+                    insert_index = Some(import_statement_index);
+                }
+                import_statement_index += 1;
             }
             _ => {
                 break;
             }
         }
     }
+    let mut new_import = new_import;
+    let specifiers = new_import.import_clause.named_bindings.take();
     if !found_import_statement_to_the_same_file {
-        file.ast
-            .statements
-            .insert(import_statement_index, new_import.into());
-        return;
+        let index = insert_index.unwrap_or(import_statement_index);
+        new_import.import_clause.named_bindings = specifiers.as_ref().map(|_| Vec::new());
+        file.ast.statements.insert(index, new_import.into());
+        import_statement_index = index;
     }
     let actual_import_declaration = match &mut file.ast.statements[import_statement_index] {
         ast::Statement::ImportDeclaration(imprt) => imprt,
         _ => unreachable!(),
     };
-    for specifier in new_import
-        .import_clause
-        .named_bindings
-        .into_iter()
-        .flatten()
-    {
+    for specifier in specifiers.into_iter().flatten() {
         ensure_import_specifier(&mut actual_import_declaration.import_clause, specifier);
     }
 }
 
 fn ensure_import_specifier(import_clause: &mut ast::ImportClause, specifier: ast::ImportSpecifier) {
-    let mut found_specifier = false;
-    for sp in import_clause.named_bindings.iter().flatten() {
-        if *sp == specifier {
-            found_specifier = true;
-            break;
+    let named_bindings = import_clause.named_bindings.get_or_insert_with(Vec::new);
+    // a local name can be bound only once per file, as a value when any of its uses is a value
+    if let Some(existing) = named_bindings
+        .iter_mut()
+        .find(|sp| sp.name.text == specifier.name.text)
+    {
+        existing.is_type_only &= specifier.is_type_only;
+        return;
+    }
+    let index = named_bindings
+        .iter()
+        .position(|sp| sp.sort_key() > specifier.sort_key())
+        .unwrap_or(named_bindings.len());
+    named_bindings.insert(index, specifier);
+}
+
+#[cfg(test)]
+mod test_ensure_import {
+    use super::*;
+
+    fn import(names: &[&str], module: &str) -> ast::ImportDeclaration {
+        ast::ImportDeclaration::import(
+            names
+                .iter()
+                .map(|name| ast::ImportSpecifier::new(ast::Identifier::new(*name).into()))
+                .collect(),
+            module.into(),
+        )
+    }
+
+    fn render_imports(imports: Vec<ast::ImportDeclaration>) -> String {
+        let mut file = ast::File::new("types".into());
+        for new_import in imports {
+            ensure_import(&mut file, new_import);
         }
+        String::from(&file)
     }
-    if found_specifier {
-        return;
+
+    #[test]
+    fn it_sorts_imports_regardless_of_the_order_of_requests() {
+        let expected = concat!(
+            "import { A, AEncodeInput } from \"../A/types\"\n",
+            "import { B } from \"./B/types\"\n",
+            "import { Reader, Writer } from \"protobufjs/minimal\"\n",
+        );
+        assert_eq!(
+            render_imports(vec![
+                import(&["Writer"], "protobufjs/minimal"),
+                import(&["B"], "./B/types"),
+                import(&["AEncodeInput", "A"], "../A/types"),
+                import(&["Reader", "Writer"], "protobufjs/minimal"),
+            ]),
+            expected
+        );
+        assert_eq!(
+            render_imports(vec![
+                import(&["A"], "../A/types"),
+                import(&["Reader"], "protobufjs/minimal"),
+                import(&["AEncodeInput"], "../A/types"),
+                import(&["B"], "./B/types"),
+                import(&["Writer"], "protobufjs/minimal"),
+            ]),
+            expected
+        );
+    }
+
+    #[test]
+    fn it_merges_imports_of_the_same_module() {
+        assert_eq!(
+            render_imports(vec![
+                import(&["Order"], "./types"),
+                import(&["OrderEncodeInput", "Order"], "./types"),
+                import(&["Order"], "./types"),
+            ]),
+            "import { Order, OrderEncodeInput } from \"./types\"\n"
+        );
     }
 
-    let mut named_bindings = import_clause.named_bindings.take();
-    if let Some(ref mut vec) = named_bindings {
-        vec.push(specifier);
-    } else {
-        named_bindings = Some(vec![specifier]);
+    #[test]
+    fn it_keeps_a_binding_used_as_a_value_and_as_a_type_a_value() {
+        for first_is_type in [true, false] {
+            let mut file = ast::File::new("order".into());
+            for is_type_only in [first_is_type, !first_is_type] {
+                let mut new_import = import(&["Line"], "./line");
+                new_import.import_clause.named_bindings.as_mut().unwrap()[0].is_type_only =
+                    is_type_only;
+                ensure_import(&mut file, new_import);
+            }
+            assert_eq!(String::from(&file), "import { Line } from \"./line\"\n");
+        }
     }
-    import_clause.named_bindings = named_bindings;
 }
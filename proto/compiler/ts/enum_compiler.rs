@@ -1,25 +1,299 @@
-use crate::proto::proto_scope::{ProtoScope};
+use std::sync::Arc;
 
-use super::ast::{self, Folder};
+use crate::proto::{
+    package::EnumEntry,
+    proto_scope::{root_scope::RootScope, ProtoScope},
+};
 
-pub(super) fn insert_enum_declaration(res: &mut Folder, enum_scope: &ProtoScope) {
-    let mut file = ast::File::new(enum_scope.name());
+use super::{
+    ast::{self, Folder, ObjectLiteralMember, StatementList, Type},
+    file_case::file_case_name,
+    layout::declaration_name,
+    options::{Emit, EnumStyle, Options},
+};
+
+pub(super) fn insert_enum_declaration(
+    root: &RootScope,
+    options: &Options,
+    res: &mut Folder,
+    enum_scope: &ProtoScope,
+) {
+    let mut file = ast::File::new(file_case_name(options.file_case, &enum_scope.name()));
     let enum_decl = match enum_scope {
         ProtoScope::Enum(e) => e,
         _ => unreachable!(),
     };
-    let enum_declaration = super::ast::EnumDeclaration {
-        modifiers: vec![ast::Modifier::Export],
-        name: enum_scope.name().into(),
-        members: enum_decl
-            .entries
-            .iter()
-            .map(|entry| super::ast::EnumMember {
-                name: entry.name.clone().into(),
-                value: Some(entry.value.into()),
-            })
-            .collect(),
-    };
-    file.ast.statements.push(enum_declaration.into());
+    let name = declaration_name(root, options, enum_scope.id().unwrap());
+    match options.enum_style {
+        EnumStyle::Enum => {
+            let enum_declaration = super::ast::EnumDeclaration {
+                modifiers: vec![ast::Modifier::Export],
+                name: Arc::clone(&name).into(),
+                members: enum_decl
+                    .entries
+                    .iter()
+                    .map(|entry| super::ast::EnumMember {
+                        name: entry.name.clone().into(),
+                        value: Some(entry.value.into()),
+                        deprecated: entry.deprecated,
+                    })
+                    .collect(),
+                deprecated: enum_decl.deprecated,
+            };
+            file.ast.statements.push(enum_declaration.into());
+            if options.emit_enum_names && !options.declarations_only {
+                let name_type = Type::KeyOf(Box::new(Type::TypeQuery(Arc::new(
+                    ast::Identifier::new(&name),
+                ))));
+                file.push_statement(names_declaration(&name, name_type, &enum_decl.entries));
+            }
+        }
+        EnumStyle::Union => {
+            let names = enum_decl
+                .entries
+                .iter()
+                .map(|entry| Type::string_literal(&entry.name))
+                .collect::<Vec<_>>();
+            let mut alias = ast::TypeAliasDeclaration::new(
+                ast::Identifier::new(&name),
+                ast::UnionType::from(names).into(),
+            )
+            .exported();
+            alias.deprecated = enum_decl.deprecated;
+            file.push_statement(alias.into());
+            if !options.declarations_only {
+                // the declaration of the object has no initializer to infer the type from
+                let numbers_type = (options.emit == Emit::Dts)
+                    .then(|| Type::Record(Box::new(Type::from_id(&name)), Box::new(Type::Number)));
+                file.push_statement(numbers_declaration(&name, numbers_type, &enum_decl.entries));
+                let name_type = Type::from_id(&name);
+                file.push_statement(names_declaration(&name, name_type, &enum_decl.entries));
+            }
+        }
+        EnumStyle::ConstObject => {
+            file.push_statement(numbers_declaration(&name, None, &enum_decl.entries));
+            let name_id = Arc::new(ast::Identifier::new(&name));
+            let mut alias = ast::TypeAliasDeclaration::new(
+                ast::Identifier::new(&name),
+                Type::value_of(Arc::clone(&name_id)),
+            )
+            .exported();
+            alias.deprecated = enum_decl.deprecated;
+            file.push_statement(alias.into());
+            let name_type = Type::KeyOf(Box::new(Type::TypeQuery(name_id)));
+            file.push_statement(names_declaration(&name, name_type, &enum_decl.entries));
+        }
+    }
     res.entries.push(file.into());
 }
+
+/// Name of the object mapping the wire numbers of the enum named `enum_name` to its members.
+pub(super) fn enum_names_name(enum_name: &str) -> Arc<str> {
+    format!("{}Names", enum_name).into()
+}
+
+/// `export const Color = { RED: 0, GREEN: 1 } as const`, annotated with `numbers_type` when given.
+fn numbers_declaration(
+    name: &str,
+    numbers_type: Option<Type>,
+    entries: &[EnumEntry],
+) -> ast::Statement {
+    let members = entries
+        .iter()
+        .map(|entry| ObjectLiteralMember::property(&entry.name, ast::Expression::from(entry.value)))
+        .collect();
+    let id = Arc::new(ast::Identifier::new(name));
+    let object = ast::Expression::ObjectLiteralExpression(members).into_as_const();
+    match numbers_type {
+        Some(t) => ast::VariableDeclarationList::declare_typed_const(id, Arc::new(t), object),
+        None => ast::VariableDeclarationList::declare_const(id, object),
+    }
+    .exported()
+    .into()
+}
+
+/// `export const ColorNames: Record<number, Color> = { 0: "RED", 1: "GREEN" }`,
+/// the first member wins for aliased numbers.
+fn names_declaration(name: &str, name_type: Type, entries: &[EnumEntry]) -> ast::Statement {
+    let mut numbers = Vec::new();
+    let mut members = Vec::new();
+    for entry in entries {
+        if numbers.contains(&entry.value) {
+            continue;
+        }
+        numbers.push(entry.value);
+        members.push(ObjectLiteralMember::property(
+            entry.value,
+            ast::Expression::from(ast::StringLiteral::from(Arc::clone(&entry.name))),
+        ));
+    }
+    ast::VariableDeclarationList::declare_typed_const(
+        Arc::new(ast::Identifier::new(&enum_names_name(name))),
+        Type::Record(Box::new(Type::Number), Box::new(name_type)).into(),
+        ast::Expression::ObjectLiteralExpression(members),
+    )
+    .exported()
+    .into()
+}
+
+#[cfg(test)]
+mod test_enum_compiler {
+    use super::super::test_utils::{compile_sources, render_file_at};
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "shop/status.proto",
+        r#"
+        syntax = "proto3";
+        package shop;
+        enum Status {
+            option allow_alias = true;
+            NEW = 0;
+            PAID = 1;
+            SETTLED = 1;
+            REFUNDED = 2;
+        }
+        "#,
+    )];
+
+    fn render_status(enum_style: EnumStyle) -> String {
+        let options = Options {
+            enum_style,
+            ..Options::default()
+        };
+        render_file_at(
+            &compile_sources(SOURCES, &options),
+            &["shop", "status", "Status"],
+        )
+    }
+
+    #[test]
+    fn it_declares_enums() {
+        assert_eq!(
+            render_status(EnumStyle::Enum),
+            "export enum Status {\n  NEW = 0,\n  PAID = 1,\n  SETTLED = 1,\n  REFUNDED = 2,\n}\n"
+        );
+    }
+
+    #[test]
+    fn it_maps_numbers_of_enums_to_the_first_aliased_name() {
+        let options = Options {
+            emit_enum_names: true,
+            ..Options::default()
+        };
+        let status = render_file_at(
+            &compile_sources(SOURCES, &options),
+            &["shop", "status", "Status"],
+        );
+        assert!(status.ends_with(
+            &[
+                "  REFUNDED = 2,",
+                "}",
+                "",
+                "export const StatusNames: Record<number, keyof typeof Status> = {",
+                "  \"0\": \"NEW\",",
+                "  \"1\": \"PAID\",",
+                "  \"2\": \"REFUNDED\",",
+                "}",
+                "",
+            ]
+            .join("\n")
+        ));
+
+        let options = Options {
+            declarations_only: true,
+            ..options
+        };
+        let status = render_file_at(
+            &compile_sources(SOURCES, &options),
+            &["shop", "status", "Status"],
+        );
+        assert!(!status.contains("StatusNames"));
+    }
+
+    #[test]
+    fn it_keeps_negative_values() {
+        let sources = &[(
+            "shop/sign.proto",
+            "syntax = \"proto3\";\npackage shop;\nenum Sign { ZERO = 0; NEGATIVE = -1; }\n",
+        )];
+        let render = |enum_style| {
+            let options = Options {
+                enum_style,
+                ..Options::default()
+            };
+            render_file_at(
+                &compile_sources(sources, &options),
+                &["shop", "sign", "Sign"],
+            )
+        };
+        assert_eq!(
+            render(EnumStyle::Enum),
+            "export enum Sign {\n  ZERO = 0,\n  NEGATIVE = -1,\n}\n"
+        );
+        assert!(render(EnumStyle::Union).contains("\n  NEGATIVE: -1,\n"));
+        assert!(render(EnumStyle::Union).contains("\n  \"-1\": \"NEGATIVE\",\n"));
+    }
+
+    #[test]
+    fn it_declares_unions_of_names() {
+        assert_eq!(
+            render_status(EnumStyle::Union),
+            [
+                "export type Status =",
+                "  | \"NEW\"",
+                "  | \"PAID\"",
+                "  | \"SETTLED\"",
+                "  | \"REFUNDED\"",
+                "",
+                "export const Status = {",
+                "  NEW: 0,",
+                "  PAID: 1,",
+                "  SETTLED: 1,",
+                "  REFUNDED: 2,",
+                "} as const",
+                "",
+                "export const StatusNames: Record<number, Status> = {",
+                "  \"0\": \"NEW\",",
+                "  \"1\": \"PAID\",",
+                "  \"2\": \"REFUNDED\",",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_tags_deprecated_enums_and_values() {
+        let sources = &[(
+            "shop/status.proto",
+            "syntax = \"proto3\";\npackage shop;\nenum Status {\n  option deprecated = true;\n  NEW = 0;\n  LEGACY = 1 [deprecated = true];\n}\n",
+        )];
+        let render = |enum_style| {
+            let options = Options {
+                enum_style,
+                ..Options::default()
+            };
+            render_file_at(
+                &compile_sources(sources, &options),
+                &["shop", "status", "Status"],
+            )
+        };
+        assert_eq!(
+            render(EnumStyle::Enum),
+            "/** @deprecated */\nexport enum Status {\n  NEW = 0,\n  /** @deprecated */\n  LEGACY = 1,\n}\n"
+        );
+        assert!(render(EnumStyle::Union).starts_with("/** @deprecated */\nexport type Status = "));
+    }
+
+    #[test]
+    fn it_declares_const_objects() {
+        let status = render_status(EnumStyle::ConstObject);
+        assert!(status.starts_with("export const Status = {\n  NEW: 0,\n"));
+        assert!(status.contains(
+            "\n\nexport type Status = (typeof Status)[keyof typeof Status]\n\n\
+             export const StatusNames: Record<number, keyof typeof Status> = {\n"
+        ));
+    }
+}
@@ -0,0 +1,301 @@
+use std::sync::Arc;
+
+use crate::proto::proto_scope::{root_scope::RootScope, ProtoScope};
+
+use super::{
+    ast::{self, ElementAccess, ExpressionChain, Type},
+    import_enum::{import_enum, import_enum_names},
+    options::{EnumStyle, Options},
+};
+
+/// Object translating values of the enum with `enum_id` into wire numbers,
+/// `None` when the values are the wire numbers already.
+pub(super) fn enum_numbers(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+) -> Option<Arc<ast::Expression>> {
+    match options.enum_style {
+        EnumStyle::Union => Some(Arc::new(
+            import_enum(root, options, message_scope, file, enum_id).into(),
+        )),
+        EnumStyle::Enum | EnumStyle::ConstObject => None,
+    }
+}
+
+/// Object translating wire numbers into values of the enum with `enum_id`,
+/// `None` when the values are the wire numbers.
+pub(super) fn enum_values(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+) -> Option<Arc<ast::Expression>> {
+    match options.enum_style {
+        EnumStyle::Union => Some(Arc::new(
+            import_enum_names(root, options, message_scope, file, enum_id).into(),
+        )),
+        EnumStyle::Enum | EnumStyle::ConstObject => None,
+    }
+}
+
+/// `value` looked up in the `lookup` object, `value` itself without the object.
+pub(super) fn lookup_value(
+    lookup: Option<&Arc<ast::Expression>>,
+    value: Arc<ast::Expression>,
+) -> Arc<ast::Expression> {
+    match lookup {
+        Some(lookup) => lookup.element(value).into(),
+        None => value,
+    }
+}
+
+/// The member named `member_name` as a value of a field.
+pub(super) fn enum_member(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+    member_name: &str,
+) -> ast::Expression {
+    match options.enum_style {
+        EnumStyle::Union => ast::StringLiteral::from(member_name).into(),
+        EnumStyle::Enum | EnumStyle::ConstObject => {
+            ast::Expression::from(import_enum(root, options, message_scope, file, enum_id))
+                .into_prop(member_name)
+        }
+    }
+}
+
+/// Name of the member `value` is, as the proto3 JSON mapping writes enums.
+/// Values unknown to the generated code have no name and are written as numbers.
+pub(super) fn enum_to_json(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+    value: Arc<ast::Expression>,
+) -> Arc<ast::Expression> {
+    match options.enum_style {
+        // the reverse mapping of a TypeScript enum names aliased numbers after the last member
+        EnumStyle::Enum if !options.emit_enum_names => {
+            let enum_expr: Arc<ast::Expression> =
+                Arc::new(import_enum(root, options, message_scope, file, enum_id).into());
+            name_or_number(enum_expr, value)
+        }
+        EnumStyle::Union => value,
+        EnumStyle::Enum | EnumStyle::ConstObject => {
+            let names_expr: Arc<ast::Expression> =
+                Arc::new(import_enum_names(root, options, message_scope, file, enum_id).into());
+            name_or_number(names_expr, value)
+        }
+    }
+}
+
+/// `Status[value] ?? value`
+fn name_or_number(
+    names_expr: Arc<ast::Expression>,
+    value: Arc<ast::Expression>,
+) -> Arc<ast::Expression> {
+    ast::BinaryOperator::NullishCoalescing
+        .apply(names_expr.element(Arc::clone(&value)).into(), value)
+        .into()
+}
+
+/// Value of the enum from a JSON `value`, which is either the name or the number of a member.
+pub(super) fn enum_from_json(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+    value: Arc<ast::Expression>,
+) -> Arc<ast::Expression> {
+    let type_is = |name: &str| -> Arc<ast::Expression> {
+        ast::BinaryOperator::StrictEqual
+            .apply(
+                value.type_of().into(),
+                Arc::new(ast::StringLiteral::from(name).into()),
+            )
+            .into()
+    };
+    let (condition, translated) = match options.enum_style {
+        EnumStyle::Enum => {
+            let enum_expr: Arc<ast::Expression> =
+                Arc::new(import_enum(root, options, message_scope, file, enum_id).into());
+            (type_is("string"), enum_expr.element(Arc::clone(&value)))
+        }
+        EnumStyle::Union => {
+            let names_expr: Arc<ast::Expression> =
+                Arc::new(import_enum_names(root, options, message_scope, file, enum_id).into());
+            (type_is("number"), names_expr.element(Arc::clone(&value)))
+        }
+        EnumStyle::ConstObject => {
+            let enum_id = import_enum(root, options, message_scope, file, enum_id);
+            let member_name = ast::Expression::AsExpression(
+                Arc::clone(&value),
+                Arc::new(Type::KeyOf(Box::new(Type::TypeQuery(Arc::clone(&enum_id))))),
+            );
+            let enum_expr: Arc<ast::Expression> = Arc::new(enum_id.into());
+            (type_is("string"), enum_expr.element(Arc::new(member_name)))
+        }
+    };
+    ast::Expression::conditional(condition, translated.into(), value).into()
+}
+
+/// Whether `value` is a value of the enum, the check of type guards.
+pub(super) fn is_enum_value(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+    value: Arc<ast::Expression>,
+) -> ast::Expression {
+    let type_is = |name: &str| -> Arc<ast::Expression> {
+        ast::BinaryOperator::StrictEqual
+            .apply(
+                value.type_of().into(),
+                Arc::new(ast::StringLiteral::from(name).into()),
+            )
+            .into()
+    };
+    match options.enum_style {
+        EnumStyle::Enum => {
+            let enum_expr: Arc<ast::Expression> =
+                Arc::new(import_enum(root, options, message_scope, file, enum_id).into());
+            type_is("number").and(
+                ast::BinaryOperator::In
+                    .apply(Arc::clone(&value), enum_expr)
+                    .into(),
+            )
+        }
+        EnumStyle::Union => {
+            let enum_expr: Arc<ast::Expression> =
+                Arc::new(import_enum(root, options, message_scope, file, enum_id).into());
+            type_is("string").and(own_property(enum_expr, value).into())
+        }
+        EnumStyle::ConstObject => {
+            let names_expr: Arc<ast::Expression> =
+                Arc::new(import_enum_names(root, options, message_scope, file, enum_id).into());
+            type_is("number").and(own_property(names_expr, value).into())
+        }
+    }
+}
+
+/// `Object.hasOwnProperty.call(object, key)`, unlike `in` it skips the prototype of the object.
+fn own_property(object: Arc<ast::Expression>, key: Arc<ast::Expression>) -> ast::Expression {
+    ast::Expression::from(ast::Identifier::new("Object"))
+        .into_prop("hasOwnProperty")
+        .into_prop("call")
+        .into_call(vec![object, key])
+}
+
+#[cfg(test)]
+mod test_enum_values {
+    use super::super::test_utils::{compile_sources, render_file_at};
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "shop/order.proto",
+            r#"
+            syntax = "proto3";
+            package shop;
+            import "shop/status.proto";
+            message Order {
+                Status status = 1;
+                repeated Status history = 2;
+                map<string, Status> by_item = 3;
+            }
+            "#,
+        ),
+        (
+            "shop/status.proto",
+            "syntax = \"proto3\";\npackage shop;\nenum Status { NEW = 0; PAID = 1; }\n",
+        ),
+    ];
+
+    fn render(enum_style: EnumStyle, file_name: &str) -> String {
+        let options = Options {
+            enum_style,
+            emit_guards: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        render_file_at(&folder, &["shop", "order", "Order", file_name])
+    }
+
+    #[test]
+    fn it_translates_names_of_union_enums_to_wire_numbers() {
+        let encode = render(EnumStyle::Union, "encode");
+        assert!(encode.contains("import { Status } from \"../../status/Status\"\n"));
+        assert!(encode.contains("w.uint32(8).int32(Status[message.status])\n"));
+        assert!(encode.contains("w.int32(Status[message.history[i]])\n"));
+        assert!(encode.contains(".uint32(16).int32(Status[v]).ldelim()\n"));
+
+        let decode = render(EnumStyle::Union, "decode");
+        assert!(decode.contains("import { StatusNames } from \"../../status/Status\"\n"));
+        assert!(decode.contains("message.status = StatusNames[r.int32()]\n"));
+        assert!(decode.contains("message.history.push(StatusNames[r.int32()])"));
+        assert!(decode.contains("let v: any = StatusNames[0]\n"));
+        assert!(decode.contains("v = StatusNames[r.int32()]\n"));
+
+        let create = render(EnumStyle::Union, "create");
+        assert!(create.contains("    status: \"NEW\",\n"));
+        assert!(!create.contains("/status/Status"));
+    }
+
+    #[test]
+    fn it_converts_union_enums_to_json_as_they_are() {
+        let json = render(EnumStyle::Union, "json");
+        assert!(json.contains("if (message.status != null && Status[message.status] !== 0) {\n"));
+        assert!(json.contains("    json.status = message.status\n"));
+        assert!(json.contains(
+            "message.status = typeof json.status === \"number\" ? StatusNames[json.status] : json.status\n"
+        ));
+
+        let is = render(EnumStyle::Union, "is");
+        assert!(is.contains(
+            "typeof v.status === \"string\" && Object.hasOwnProperty.call(Status, v.status)"
+        ));
+    }
+
+    #[test]
+    fn it_looks_up_names_of_enums_with_the_names_object() {
+        let json = render(EnumStyle::Enum, "json");
+        assert!(json.contains("    json.status = Status[message.status] ?? message.status\n"));
+
+        let options = Options {
+            emit_enum_names: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let json = render_file_at(&folder, &["shop", "order", "Order", "json"]);
+        assert!(json.contains("import { Status, StatusNames } from \"../../status/Status\"\n"));
+        assert!(json.contains("    json.status = StatusNames[message.status] ?? message.status\n"));
+    }
+
+    #[test]
+    fn it_looks_up_names_of_const_object_enums() {
+        let encode = render(EnumStyle::ConstObject, "encode");
+        assert!(encode.contains("w.uint32(8).int32(message.status)\n"));
+
+        let json = render(EnumStyle::ConstObject, "json");
+        assert!(json.contains("    json.status = StatusNames[message.status] ?? message.status\n"));
+        assert!(json.contains("? Status[json.status as keyof typeof Status]\n"));
+
+        let is = render(EnumStyle::ConstObject, "is");
+        assert!(is.contains(
+            "typeof v.status === \"number\" && Object.hasOwnProperty.call(StatusNames, v.status)"
+        ));
+
+        let create = render(EnumStyle::ConstObject, "create");
+        assert!(create.contains("    status: Status.NEW,\n"));
+    }
+}
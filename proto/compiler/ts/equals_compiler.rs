@@ -0,0 +1,517 @@
+use std::sync::Arc;
+
+use crate::proto::{
+    error::ProtoError,
+    package::{self, Field, MessageEntry},
+    proto_scope::{root_scope::RootScope, ProtoScope},
+};
+
+use super::{
+    ast::{self, ElementAccess, ExpressionChain, Folder, Prop, StatementList, Type, WrapableExpr},
+    constants::EQUALS_FUNCTION_NAME,
+    create_compiler::default_value,
+    decode_compiler::import_create_func,
+    layout::{
+        declaration_file_path, declaration_name, function_name, import_declaration,
+        import_function_lazily,
+    },
+    options::Options,
+    renames::property_name,
+    ts_path::TsPathComponent,
+    well_known_types::{import_well_known, mapped_well_known_type},
+};
+
+/// Which values besides the ones of the field type a property may hold.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Presence {
+    /// Only values of the field type.
+    Required,
+    /// `undefined` as well: `optional` fields and messages.
+    Optional,
+    /// `undefined` and `null`: options of a oneof.
+    Nullable,
+}
+
+/// Emits `equals.ts` with `equals(a, b)` comparing two messages field by field:
+/// scalars and enums with `===`, 64-bit integers by their decimal strings, bytes byte-wise,
+/// repeated fields element-wise, maps key-wise and nested messages by their own `equals`.
+///
+/// By default a missing message is equal to `create()` and a missing field to the proto3 default
+/// of its type. With `equals_presence` missing messages and fields are equal only to missing ones.
+/// Fields of well-known types mapped by `wkt.ts` are compared by the `equals` functions of it.
+pub(super) fn compile_equals(
+    root: &RootScope,
+    options: &Options,
+    message_folder: &mut Folder,
+    message_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    let mut file = ast::File::new(EQUALS_FUNCTION_NAME.into());
+
+    let message_id = message_scope.id().unwrap();
+    let message_type_id = import_declaration(
+        &mut file,
+        &declaration_file_path(root, options, message_id, EQUALS_FUNCTION_NAME),
+        &declaration_file_path(root, options, message_id, "types"),
+        TsPathComponent::Interface(declaration_name(root, options, message_id)),
+        None,
+    );
+    let message_type: Type =
+        ast::UnionType::from(vec![Type::reference(message_type_id), Type::Undefined]).into();
+
+    let a_id: Arc<ast::Identifier> = Arc::new("a".into());
+    let a_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&a_id).into());
+    let b_id: Arc<ast::Identifier> = Arc::new("b".into());
+    let b_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&b_id).into());
+
+    let mut func = ast::FunctionDeclaration::new_exported(&function_name(
+        root,
+        options,
+        message_id,
+        EQUALS_FUNCTION_NAME,
+    ));
+    func.add_param(ast::Parameter::new(&a_id, message_type.clone()));
+    func.add_param(ast::Parameter::new(&b_id, message_type));
+    func.returns(Type::Boolean);
+
+    func.push_statement(return_if(
+        ast::BinaryOperator::StrictEqual.apply(Arc::clone(&a_expr), Arc::clone(&b_expr)),
+        ast::Expression::True,
+    ));
+    if options.equals_presence {
+        func.push_statement(return_if(
+            Arc::new(is_undefined(&a_expr)).or(is_undefined(&b_expr).into()),
+            ast::Expression::False,
+        ));
+    } else {
+        let create: Arc<ast::Expression> =
+            import_create_func(root, options, message_scope, &mut file)
+                .into_call(vec![])
+                .into();
+        for message in [&a_expr, &b_expr] {
+            func.push_statement(
+                ast::BinaryOperator::Assign
+                    .apply(
+                        Arc::clone(message),
+                        ast::BinaryOperator::NullishCoalescing
+                            .apply(Arc::clone(message), Arc::clone(&create))
+                            .into(),
+                    )
+                    .into(),
+            );
+        }
+    }
+
+    let message_declaration = match message_scope {
+        ProtoScope::Message(m) => m,
+        _ => unreachable!(),
+    };
+    let mut fields: Vec<(&Field, Presence)> = Vec::new();
+    for entry in &message_declaration.entries {
+        match entry {
+            MessageEntry::Field(f) => {
+                let presence = match f.field_type {
+                    _ if f.may_be_unset() => Presence::Optional,
+                    package::Type::Message(_) => Presence::Optional,
+                    _ => Presence::Required,
+                };
+                fields.push((f, presence));
+            }
+            MessageEntry::OneOf(one_of) => {
+                fields.extend(one_of.options.iter().map(|o| (o, Presence::Nullable)));
+            }
+        }
+    }
+
+    let mut uses_bytes = false;
+    for (field, presence) in fields {
+        let name = property_name(root, options, message_id, field);
+        let left: Arc<ast::Expression> = Arc::new(a_expr.prop(&name));
+        let right: Arc<ast::Expression> = Arc::new(b_expr.prop(&name));
+        uses_bytes |= contains_bytes(&field.field_type);
+        match &field.field_type {
+            package::Type::Repeated(element_type) => {
+                func.push_statement(return_false_if(
+                    ast::BinaryOperator::StrictNotEqual
+                        .apply(left.prop("length").into(), right.prop("length").into()),
+                ));
+                let i_id: Arc<ast::Identifier> = Arc::new("i".into());
+                let i_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&i_id).into());
+                let mut for_stmt = ast::ForStatement::for_each(i_id, Arc::clone(&left));
+                for_stmt.push_statement(return_false_if(values_differ(
+                    root,
+                    options,
+                    message_scope,
+                    &mut file,
+                    element_type,
+                    left.element(Arc::clone(&i_expr)).into(),
+                    right.element(i_expr).into(),
+                )));
+                func.push_statement(for_stmt.into());
+            }
+            package::Type::Map(_, value_type) => {
+                let key_count = |map: &Arc<ast::Expression>| -> Arc<ast::Expression> {
+                    Arc::new(
+                        ast::Expression::from("Object")
+                            .into_prop("keys")
+                            .into_call(vec![Arc::clone(map)])
+                            .into_prop("length"),
+                    )
+                };
+                func.push_statement(return_false_if(
+                    ast::BinaryOperator::StrictNotEqual.apply(key_count(&left), key_count(&right)),
+                ));
+                let k_id: Arc<ast::Identifier> = Arc::new("k".into());
+                let k_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&k_id).into());
+                let is_missing = ast::BinaryOperator::In
+                    .apply(Arc::clone(&k_expr), Arc::clone(&right))
+                    .into_parentheses()
+                    .not();
+                let value_differs = values_differ(
+                    root,
+                    options,
+                    message_scope,
+                    &mut file,
+                    value_type,
+                    left.element(Arc::clone(&k_expr)).into(),
+                    right.element(k_expr).into(),
+                );
+                let mut for_in = ast::ForInStatement::new(k_id, Arc::clone(&left));
+                for_in.push_statement(return_false_if(
+                    Arc::new(is_missing).or(value_differs.into()),
+                ));
+                func.push_statement(for_in.into());
+            }
+            t => {
+                let (left, right) = match (presence, t) {
+                    (Presence::Required, _) => (left, right),
+                    // missing messages are handled by their own `equals`
+                    (Presence::Optional, package::Type::Message(_)) => (left, right),
+                    (Presence::Nullable, package::Type::Message(_)) => {
+                        (or_undefined(left), or_undefined(right))
+                    }
+                    (Presence::Optional, _) if options.equals_presence => (left, right),
+                    (Presence::Nullable, _) if options.equals_presence => {
+                        (or_undefined(left), or_undefined(right))
+                    }
+                    (_, t) => {
+                        let default =
+                            Arc::new(default_or_zero(root, options, message_scope, &mut file, t));
+                        (
+                            or_default(left, Arc::clone(&default)),
+                            or_default(right, default),
+                        )
+                    }
+                };
+                func.push_statement(return_false_if(values_differ(
+                    root,
+                    options,
+                    message_scope,
+                    &mut file,
+                    t,
+                    left,
+                    right,
+                )));
+            }
+        }
+    }
+
+    func.push_statement(ast::Expression::True.into_return_statement());
+    file.push_statement(func.into());
+    if uses_bytes {
+        file.push_statement(bytes_equal_function().into());
+    }
+    message_folder.push_file(file);
+    Ok(())
+}
+
+/// Expression that is true when the values of `value_type` differ.
+fn values_differ(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    value_type: &package::Type,
+    left: Arc<ast::Expression>,
+    right: Arc<ast::Expression>,
+) -> ast::Expression {
+    match value_type {
+        package::Type::Message(m_id) => import_equals(root, options, message_scope, file, *m_id)
+            .into_call(vec![left, right])
+            .not(),
+        package::Type::Repeated(_) => unreachable!(),
+        package::Type::Map(_, _) => unreachable!(),
+        package::Type::Bytes => ast::Expression::from(BYTES_EQUAL_FUNCTION_NAME)
+            .into_call(vec![left, right])
+            .not(),
+        // `util.Long` of protobufjs, numbers without long.js and bigints with the embedded runtime
+        t if t.long_wire_type().is_some() => {
+            let to_string = |value| ast::Expression::from("String").into_call(vec![value]);
+            ast::BinaryOperator::StrictNotEqual
+                .apply(to_string(left).into(), to_string(right).into())
+        }
+        _ => ast::BinaryOperator::StrictNotEqual.apply(operand(left), operand(right)),
+    }
+}
+
+fn import_equals(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    m_id: usize,
+) -> ast::Expression {
+    let message_id = message_scope.id().unwrap();
+    if let Some(name) = mapped_well_known_type(root, options, m_id) {
+        return ast::Expression::from(import_well_known(
+            root,
+            options,
+            file,
+            message_id,
+            EQUALS_FUNCTION_NAME,
+            TsPathComponent::Function(format!("{}{}", EQUALS_FUNCTION_NAME, name).into()),
+            Some(format!("{}{}", EQUALS_FUNCTION_NAME, m_id).into()),
+        ));
+    }
+    if root.is_cyclic_reference(message_id, m_id) {
+        return import_function_lazily(
+            root,
+            options,
+            file,
+            message_id,
+            m_id,
+            EQUALS_FUNCTION_NAME,
+            EQUALS_FUNCTION_NAME,
+        );
+    }
+    ast::Expression::from(import_declaration(
+        file,
+        &declaration_file_path(root, options, message_id, EQUALS_FUNCTION_NAME),
+        &declaration_file_path(root, options, m_id, EQUALS_FUNCTION_NAME),
+        TsPathComponent::Function(function_name(root, options, m_id, EQUALS_FUNCTION_NAME)),
+        Some(format!("{}{}", EQUALS_FUNCTION_NAME, m_id).into()),
+    ))
+}
+
+/// Default of a missing scalar or enum field, `0` for 64-bit integers,
+/// since they are compared by their decimal strings.
+fn default_or_zero(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    field_type: &package::Type,
+) -> ast::Expression {
+    match field_type {
+        t if t.long_wire_type().is_some() => 0.into(),
+        t => default_value(root, options, message_scope, file, t),
+    }
+}
+
+/// `value ?? default`
+fn or_default(value: Arc<ast::Expression>, default: Arc<ast::Expression>) -> Arc<ast::Expression> {
+    Arc::new(ast::BinaryOperator::NullishCoalescing.apply(value, default))
+}
+
+/// `value ?? undefined`, for `null` options of a oneof.
+fn or_undefined(value: Arc<ast::Expression>) -> Arc<ast::Expression> {
+    or_default(value, ast::Expression::Undefined.into())
+}
+
+/// Wraps `??` expressions in parentheses, so they can be compared.
+fn operand(value: Arc<ast::Expression>) -> Arc<ast::Expression> {
+    match &*value {
+        ast::Expression::BinaryExpression(_) => Arc::new(value.into_parentheses()),
+        _ => value,
+    }
+}
+
+fn is_undefined(value: &Arc<ast::Expression>) -> ast::Expression {
+    ast::BinaryOperator::StrictEqual.apply(Arc::clone(value), ast::Expression::Undefined.into())
+}
+
+fn contains_bytes(field_type: &package::Type) -> bool {
+    match field_type {
+        package::Type::Bytes => true,
+        package::Type::Repeated(t) => contains_bytes(t),
+        package::Type::Map(_, v) => contains_bytes(v),
+        _ => false,
+    }
+}
+
+const BYTES_EQUAL_FUNCTION_NAME: &str = "bytesEqual";
+
+/// `bytesEqual(a, b)`, comparing the bytes one by one, a missing value is equal only to a missing one.
+fn bytes_equal_function() -> ast::FunctionDeclaration {
+    let a_id: Arc<ast::Identifier> = Arc::new("a".into());
+    let a_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&a_id).into());
+    let b_id: Arc<ast::Identifier> = Arc::new("b".into());
+    let b_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&b_id).into());
+    let bytes_type: Type =
+        ast::UnionType::from(vec![Type::from_id("Uint8Array"), Type::Undefined]).into();
+
+    let mut func = ast::FunctionDeclaration::new(BYTES_EQUAL_FUNCTION_NAME);
+    func.add_param(ast::Parameter::new(&a_id, bytes_type.clone()));
+    func.add_param(ast::Parameter::new(&b_id, bytes_type));
+    func.returns(Type::Boolean);
+
+    func.push_statement(return_if(
+        Arc::new(is_undefined(&a_expr)).or(is_undefined(&b_expr).into()),
+        ast::BinaryOperator::StrictEqual.apply(Arc::clone(&a_expr), Arc::clone(&b_expr)),
+    ));
+    func.push_statement(return_false_if(
+        ast::BinaryOperator::StrictNotEqual
+            .apply(a_expr.prop("length").into(), b_expr.prop("length").into()),
+    ));
+    let i_id: Arc<ast::Identifier> = Arc::new("i".into());
+    let i_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&i_id).into());
+    let mut for_stmt = ast::ForStatement::for_each(i_id, Arc::clone(&a_expr));
+    for_stmt.push_statement(return_false_if(ast::BinaryOperator::StrictNotEqual.apply(
+        a_expr.element(Arc::clone(&i_expr)).into(),
+        b_expr.element(i_expr).into(),
+    )));
+    func.push_statement(for_stmt.into());
+    func.push_statement(ast::Expression::True.into_return_statement());
+    func
+}
+
+fn return_if(condition: ast::Expression, value: ast::Expression) -> ast::Statement {
+    ast::IfStatement {
+        expression: condition.into(),
+        then_statement: Arc::new(value.into_return_statement()),
+        else_statement: None,
+    }
+    .into()
+}
+
+fn return_false_if(condition: ast::Expression) -> ast::Statement {
+    return_if(condition, ast::Expression::False)
+}
+
+#[cfg(test)]
+mod test_equals_compiler {
+    use super::super::{
+        options::{Layout, Options},
+        test_utils::{compile_sources, render_file_at},
+    };
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "app/user.proto",
+        r#"
+        syntax = "proto3";
+        package app;
+        import "google/protobuf/timestamp.proto";
+        message User {
+            string name = 1;
+            optional int32 age = 2;
+            repeated int64 scores = 3;
+            map<string, Address> addresses = 4;
+            Address home = 5;
+            bytes avatar = 6;
+            oneof contact { string email = 7; Address post = 8; }
+            google.protobuf.Timestamp seen = 9;
+            Role role = 10;
+            message Address { string city = 1; }
+            enum Role { ROLE_UNKNOWN = 0; ROLE_ADMIN = 1; }
+        }
+        "#,
+    )];
+
+    fn equals_options(layout: Layout) -> Options {
+        Options {
+            layout,
+            emit_equals: true,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn it_compares_every_field_of_the_message() {
+        let folder = compile_sources(SOURCES, &equals_options(Layout::Folder));
+        let equals = render_file_at(&folder, &["app", "user", "User", "equals"]);
+        assert!(equals.starts_with(concat!(
+            "import { equalsTimestamp as equals4 } from \"../../../wkt\"\n",
+            "import { equals as equals1 } from \"./Address/equals\"\n",
+            "import { create } from \"./create\"\n",
+            "import { User } from \"./types\"\n",
+            "\n",
+            "export function equals(a: User | undefined, b: User | undefined): boolean {\n",
+            "  if (a === b)\n    return true\n",
+            "  a = a ?? create()\n",
+            "  b = b ?? create()\n",
+        )));
+        for check in [
+            "  if (a.name !== b.name)\n    return false\n",
+            "  if ((a.age ?? 0) !== (b.age ?? 0))\n",
+            "  if (a.scores.length !== b.scores.length)\n",
+            "  for (let i = 0; i < a.scores.length; ++i)\n    if (String(a.scores[i]) !== String(b.scores[i]))\n",
+            "  if (Object.keys(a.addresses).length !== Object.keys(b.addresses).length)\n",
+            "  for (const k in a.addresses) {\n    if (!(k in b.addresses) || !equals1(a.addresses[k], b.addresses[k]))\n",
+            "  if (!equals1(a.home, b.home))\n",
+            "  if (!bytesEqual(a.avatar, b.avatar))\n",
+            "  if ((a.email ?? \"\") !== (b.email ?? \"\"))\n",
+            "  if (!equals1(a.post ?? undefined, b.post ?? undefined))\n",
+            "  if (!equals4(a.seen, b.seen))\n",
+            "  if (a.role !== b.role)\n",
+            "  return true\n}\n\nfunction bytesEqual(a: Uint8Array | undefined, b: Uint8Array | undefined): boolean {\n",
+        ] {
+            assert!(
+                equals.contains(check),
+                "{} is not found in\n{}",
+                check,
+                equals
+            );
+        }
+    }
+
+    #[test]
+    fn it_tells_missing_fields_from_defaults_with_presence() {
+        let options = Options {
+            equals_presence: true,
+            ..equals_options(Layout::Folder)
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let equals = render_file_at(&folder, &["app", "user", "User", "equals"]);
+        assert!(!equals.contains("create"));
+        for check in [
+            "  if (a === undefined || b === undefined)\n    return false\n",
+            "  if (a.age !== b.age)\n",
+            "  if ((a.email ?? undefined) !== (b.email ?? undefined))\n",
+            "  if (!equals1(a.post ?? undefined, b.post ?? undefined))\n",
+        ] {
+            assert!(
+                equals.contains(check),
+                "{} is not found in\n{}",
+                check,
+                equals
+            );
+        }
+    }
+
+    #[test]
+    fn it_is_not_generated_by_default() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let user = folder
+            .find_folder_by_path(&["app", "user", "User"])
+            .unwrap();
+        assert!(user.find_file("equals").is_none());
+    }
+
+    #[test]
+    fn it_names_equals_by_the_layout() {
+        let folder = compile_sources(SOURCES, &equals_options(Layout::File));
+        let user = render_file_at(&folder, &["app", "user"]);
+        assert!(user.contains(
+            "export function equalsUser(a: User | undefined, b: User | undefined): boolean {\n"
+        ));
+        assert!(user.contains("  a = a ?? createUser()\n"));
+        assert!(user.contains("  if (!equalsUser_Address(a.home, b.home))\n"));
+        assert_eq!(user.matches("function bytesEqual(").count(), 1);
+
+        let folder = compile_sources(SOURCES, &equals_options(Layout::Index));
+        let index = render_file_at(&folder, &["index"]);
+        assert!(index.contains(
+            "  export function equals(a: app.User | undefined, b: app.User | undefined): boolean {\n"
+        ));
+        assert!(index.contains("    if (!app.User.Address.equals(a.home, b.home))\n"));
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use crate::proto::{
+    error::ProtoError,
+    package::Field,
+    proto_scope::{root_scope::RootScope, ProtoScope},
+};
+
+use super::{
+    has_fields::{has_field, has_field_name},
+    options::{FieldCase, Options},
+    renames::property_name,
+};
+
+/// Name of the property of `field` before renames, in the casing of `field_case`.
+pub(super) fn field_case_name(field_case: FieldCase, field: &Field) -> Arc<str> {
+    match field_case {
+        FieldCase::Preserve => field.json_name(),
+        FieldCase::Proto => Arc::clone(&field.name),
+        FieldCase::CamelCase => match field.json_name_option() {
+            Some(json_name) => json_name,
+            None => to_lower_camel_case(&field.name).into(),
+        },
+        FieldCase::SnakeCase => to_snake_case(&field.name).into(),
+    }
+}
+
+/// `user_id` -> `userId`, the way `protoc` derives the JSON names of fields:
+/// underscores are dropped and the letters after them are capitalized.
+pub(super) fn to_lower_camel_case(name: &str) -> String {
+    let mut res = String::with_capacity(name.len());
+    let mut capitalize_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            res.extend(c.to_uppercase());
+            capitalize_next = false;
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+/// `userId` -> `user_id`, names in snake_case already are kept.
+fn to_snake_case(name: &str) -> String {
+    let mut res = String::with_capacity(name.len() + 4);
+    let mut previous_is_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() {
+            if previous_is_lower {
+                res.push('_');
+            }
+            res.extend(c.to_lowercase());
+            previous_is_lower = false;
+        } else {
+            res.push(c);
+            previous_is_lower = c.is_lowercase() || c.is_ascii_digit();
+        }
+    }
+    res
+}
+
+/// Checks that the fields of every message keep unique property names in the case of the options,
+/// `foo_bar` and `fooBar` are both `fooBar` in camelCase.
+/// Collisions of renamed fields are reported by `validate_renames`.
+pub(super) fn validate_property_names(
+    root: &RootScope,
+    options: &Options,
+) -> Result<(), ProtoError> {
+    let mut decl_ids = root.types.keys().copied().collect::<Vec<_>>();
+    decl_ids.sort_unstable();
+    let mut errors = Vec::new();
+    for decl_id in decl_ids {
+        let scope = root.get_declaration(decl_id).unwrap();
+        let message = match &*scope {
+            ProtoScope::Message(m) => m,
+            _ => continue,
+        };
+        let full_name = root.get_full_name(decl_id).unwrap();
+        let is_renamed = |field: &Field| {
+            options
+                .renames
+                .contains_key(&format!("{}.{}", full_name, field.name))
+        };
+        let mut properties: Vec<(&Field, Arc<str>)> = Vec::new();
+        for field in message.get_fields() {
+            let property = property_name(root, options, decl_id, field);
+            let other = properties
+                .iter()
+                .find(|(other, p)| *p == property && !is_renamed(other));
+            if let Some((other, _)) = other.filter(|_| !is_renamed(field)) {
+                errors.push(ProtoError::PropertyNameCollision {
+                    message: full_name.to_string(),
+                    first: other.name.to_string(),
+                    second: field.name.to_string(),
+                    property: property.to_string(),
+                });
+            }
+            properties.push((field, property));
+        }
+        // `hasName` properties of `optional` fields are named after their fields
+        for field in message.get_fields() {
+            if !has_field(options, field) {
+                continue;
+            }
+            let property = has_field_name(root, options, decl_id, field);
+            if let Some((other, _)) = properties.iter().find(|(_, p)| *p == property) {
+                errors.push(ProtoError::PropertyNameCollision {
+                    message: full_name.to_string(),
+                    first: other.name.to_string(),
+                    second: field.name.to_string(),
+                    property: property.to_string(),
+                });
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProtoError::from_errors(errors))
+    }
+}
+
+#[cfg(test)]
+mod test_field_case {
+    use super::super::{
+        options::Options,
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+    use crate::proto::package::read_root_scope_from_sources;
+
+    #[test]
+    fn it_converts_names_between_cases() {
+        for (name, camel, snake) in [
+            ("user_id", "userId", "user_id"),
+            ("userId", "userId", "user_id"),
+            ("UserId", "UserId", "user_id"),
+            ("address_line_2", "addressLine2", "address_line_2"),
+            ("line2Id", "line2Id", "line2_id"),
+            ("id", "id", "id"),
+        ] {
+            assert_eq!(to_lower_camel_case(name), camel, "{}", name);
+            assert_eq!(to_snake_case(name), snake, "{}", name);
+        }
+    }
+
+    #[test]
+    fn it_names_properties_of_every_file_by_the_case() {
+        let sources = &[(
+            "shop/user.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage User { string user_id = 1; int32 loginCount = 2; string nick_name = 3 [json_name = \"nick\"]; }\n",
+        )];
+        let render = |field_case, file_name| {
+            let options = Options {
+                field_case,
+                ..Options::default()
+            };
+            let folder = compile_sources(sources, &options);
+            render_file_at(&folder, &["shop", "user", "User", file_name])
+        };
+
+        let types = render(FieldCase::Preserve, "types");
+        assert!(types.contains("  user_id: string\n  loginCount: number\n  nick: string\n"));
+
+        let types = render(FieldCase::CamelCase, "types");
+        assert!(types.contains("  userId?: string | null\n"));
+        assert!(types.contains("  userId: string\n  loginCount: number\n  nick: string\n"));
+        let encode = render(FieldCase::CamelCase, "encode");
+        assert!(encode.contains("w.uint32(10).string(message.userId)\n"));
+        let decode = render(FieldCase::CamelCase, "decode");
+        assert!(decode.contains("message.userId = r.string()\n"));
+
+        let types = render(FieldCase::SnakeCase, "types");
+        assert!(types.contains("  user_id: string\n  login_count: number\n  nick_name: string\n"));
+        let encode = render(FieldCase::SnakeCase, "encode");
+        assert!(encode.contains("w.uint32(16).int32(message.login_count)\n"));
+
+        let types = render(FieldCase::Proto, "types");
+        assert!(types.contains("  user_id: string\n  loginCount: number\n  nick_name: string\n"));
+        // JSON keys follow `json_name` whatever the properties are named
+        let json = render(FieldCase::Proto, "json");
+        assert!(json.contains("    json.nick = message.nick_name\n"));
+        assert!(json.contains("    message.nick_name = String(json.nick ?? json.nick_name)\n"));
+    }
+
+    #[test]
+    fn it_reports_fields_with_the_same_property_name() {
+        let sources = &[(
+            "shop/user.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage User { string foo_bar = 1; string fooBar = 2; string baz = 3 [json_name = \"foo_bar\"]; }\n",
+        )];
+        let validate = |field_case| {
+            let root = read_root_scope_from_sources(sources).unwrap();
+            let options = Options {
+                field_case,
+                ..Options::default()
+            };
+            validate_property_names(&root, &options)
+        };
+
+        let error = validate(FieldCase::CamelCase).unwrap_err();
+        assert_eq!(error.code(), "property_name_collision");
+        assert_eq!(
+            error.to_string(),
+            "Fields \"foo_bar\" and \"fooBar\" of shop.User are both named \"fooBar\", rename one of them or choose another field case"
+        );
+        let error = validate(FieldCase::Preserve).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Fields \"foo_bar\" and \"baz\" of shop.User are both named \"foo_bar\""));
+        assert!(validate(FieldCase::Proto).is_ok());
+    }
+}
@@ -0,0 +1,251 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::proto::error::ProtoError;
+
+use super::{
+    ast::{Folder, FolderEntry},
+    options::FileCase,
+};
+
+/// Name of the folder or the file generated for a package, a proto file
+/// (without `.proto`), a message, an enum or a service named `name`.
+pub(super) fn file_case_name(file_case: FileCase, name: &str) -> Arc<str> {
+    let words = words(name);
+    match file_case {
+        FileCase::Keep => name.into(),
+        FileCase::Kebab => lower_words(&words).join("-").into(),
+        FileCase::Snake => lower_words(&words).join("_").into(),
+        FileCase::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| match i {
+                0 => word.to_lowercase(),
+                _ => capitalize(word),
+            })
+            .collect::<String>()
+            .into(),
+        FileCase::Pascal => words
+            .iter()
+            .map(|word| capitalize(word))
+            .collect::<String>()
+            .into(),
+    }
+}
+
+/// `OrderLine`, `order_line` and `order-line` are `["Order", "Line"]` or `["order", "line"]`:
+/// words are separated by `_` and `-`, and start at an uppercase letter after a lowercase one
+/// or a digit, and at the last uppercase letter of an acronym followed by a lowercase one (`HTTPServer`).
+fn words(name: &str) -> Vec<&str> {
+    let chars = name.char_indices().collect::<Vec<_>>();
+    let mut res = Vec::new();
+    let mut start = 0;
+    for (i, &(pos, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if start < pos {
+                res.push(&name[start..pos]);
+            }
+            start = pos + c.len_utf8();
+            continue;
+        }
+        if !c.is_uppercase() || i == 0 || start == pos {
+            continue;
+        }
+        let previous = chars[i - 1].1;
+        let next_is_lower = chars
+            .get(i + 1)
+            .is_some_and(|(_, next)| next.is_lowercase());
+        if previous.is_lowercase()
+            || previous.is_ascii_digit()
+            || (previous.is_uppercase() && next_is_lower)
+        {
+            res.push(&name[start..pos]);
+            start = pos;
+        }
+    }
+    if start < name.len() {
+        res.push(&name[start..]);
+    }
+    res
+}
+
+fn lower_words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_lowercase()).collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Checks that no two entries of the folder are written to the same path
+/// on case-insensitive file systems, like the folders of messages `Foo` and `foo`,
+/// or of `OrderLine` and `Order_Line` with `FileCase::Kebab`.
+pub(super) fn validate_file_names(folder: &Folder) -> Result<(), ProtoError> {
+    let mut errors = Vec::new();
+    validate_entries(folder, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProtoError::from_errors(errors))
+    }
+}
+
+/// Validates the entries of `folder` located at `path` and of its subfolders.
+fn validate_entries(folder: &Folder, path: &str, errors: &mut Vec<ProtoError>) {
+    let mut names: HashMap<String, String> = HashMap::new();
+    for entry in folder.entries.iter() {
+        let name = entry_file_name(entry);
+        match names.get(&name.to_lowercase()) {
+            Some(first) => errors.push(ProtoError::FileNameCollision {
+                first: format!("{}{}", path, first),
+                second: format!("{}{}", path, name),
+            }),
+            None => {
+                names.insert(name.to_lowercase(), name);
+                if let FolderEntry::Folder(subfolder) = entry {
+                    let subfolder_path = format!("{}{}/", path, subfolder.name);
+                    validate_entries(subfolder, &subfolder_path, errors);
+                }
+            }
+        }
+    }
+}
+
+fn entry_file_name(entry: &FolderEntry) -> String {
+    match entry {
+        FolderEntry::Folder(folder) => folder.name.to_string(),
+        FolderEntry::File(file) if file.declaration => format!("{}.d.ts", file.name),
+        FolderEntry::File(file) => format!("{}.ts", file.name),
+    }
+}
+
+#[cfg(test)]
+mod test_file_case {
+    use super::super::{
+        options::{Layout, Options},
+        scope_to_folder::root_scope_to_folder,
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+    use crate::proto::package::read_root_scope_from_sources;
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "shop/order_line.proto",
+            r#"
+            syntax = "proto3";
+            package shop_api;
+            import "shop/line_item.proto";
+            enum LineStatus { NEW = 0; }
+            message OrderLine { LineItem item = 1; LineStatus status = 2; }
+            service OrderService { rpc Get(OrderLine) returns (LineItem); }
+            "#,
+        ),
+        (
+            "shop/line_item.proto",
+            "syntax = \"proto3\";\npackage shop_api;\nmessage LineItem { string sku = 1; }\n",
+        ),
+    ];
+
+    fn file_case_options(layout: Layout, file_case: FileCase) -> Options {
+        Options {
+            layout,
+            file_case,
+            ..Options::default()
+        }
+    }
+
+    fn compile_error(sources: &[(&str, &str)], options: &Options) -> ProtoError {
+        let root = read_root_scope_from_sources(sources).unwrap();
+        root_scope_to_folder(&root, options, "out".into()).unwrap_err()
+    }
+
+    #[test]
+    fn it_converts_names_between_cases() {
+        // name, kebab, snake, camel, pascal
+        for row in [
+            "order_line order-line order_line orderLine OrderLine",
+            "OrderLine order-line order_line orderLine OrderLine",
+            "orderLine order-line order_line orderLine OrderLine",
+            "order-line order-line order_line orderLine OrderLine",
+            "HTTPServer http-server http_server httpServer HttpServer",
+            "v2Order v2-order v2_order v2Order V2Order",
+            "shop shop shop shop Shop",
+        ] {
+            let names = row.split(' ').collect::<Vec<_>>();
+            let name = names[0];
+            assert_eq!(&*file_case_name(FileCase::Keep, name), name);
+            assert_eq!(
+                &*file_case_name(FileCase::Kebab, name),
+                names[1],
+                "{}",
+                name
+            );
+            assert_eq!(
+                &*file_case_name(FileCase::Snake, name),
+                names[2],
+                "{}",
+                name
+            );
+            assert_eq!(
+                &*file_case_name(FileCase::Camel, name),
+                names[3],
+                "{}",
+                name
+            );
+            assert_eq!(
+                &*file_case_name(FileCase::Pascal, name),
+                names[4],
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn it_names_folders_and_imports_by_the_case() {
+        let folder = compile_sources(SOURCES, &file_case_options(Layout::Folder, FileCase::Kebab));
+        let types = render_file_at(&folder, &["shop-api", "order-line", "order-line", "types"]);
+        assert!(types.contains("from \"../../line-item/line-item/types\"\n"));
+        assert!(types.contains("import { LineStatus } from \"../line-status\"\n"));
+        let service = render_file_at(&folder, &["shop-api", "order-line", "order-service"]);
+        assert!(service.contains("from \"./order-line/encode\"\n"));
+
+        let folder = compile_sources(SOURCES, &file_case_options(Layout::File, FileCase::Pascal));
+        let order_line = render_file_at(&folder, &["ShopApi", "OrderLine"]);
+        assert!(order_line.contains("from \"./LineItem\"\n"));
+
+        let folder = compile_sources(SOURCES, &Options::default());
+        render_file_at(&folder, &["shop_api", "order_line", "OrderLine", "types"]);
+    }
+
+    #[test]
+    fn it_reports_names_that_collide_on_case_insensitive_file_systems() {
+        let sources = &[(
+            "shop/order.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage OrderLine { string sku = 1; }\nmessage Order_Line { string sku = 1; }\n",
+        )];
+        compile_sources(sources, &Options::default());
+        let error = compile_error(sources, &file_case_options(Layout::Folder, FileCase::Kebab));
+        assert_eq!(error.code(), "file_name_collision");
+        assert_eq!(
+            error.to_string(),
+            "\"shop/order/order-line\" and \"shop/order/order-line\" are the same path on case-insensitive file systems, rename one of the declarations or choose another file case"
+        );
+
+        let sources = &[(
+            "shop/order.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage Item { string sku = 1; }\nmessage item { string sku = 1; }\n",
+        )];
+        let error = compile_error(sources, &Options::default());
+        assert!(error
+            .to_string()
+            .starts_with("\"shop/order/Item\" and \"shop/order/item\" are the same path"));
+    }
+}
@@ -1,9 +1,66 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
-pub(crate) fn file_name_to_folder_name(file_name: &str) -> Rc<str> {
-    if file_name.ends_with(".proto") {
-        file_name[..file_name.len() - 6].into()
-    } else {
-        Rc::from(file_name)
+const PROTO_EXTENSION: &str = ".proto";
+
+/// Name of the folder of the proto file `file_name`: the name without the trailing `.proto`,
+/// in any case. Other dots are kept, `my.service.proto` is `my.service`.
+pub(crate) fn file_name_to_folder_name(file_name: &str) -> Arc<str> {
+    let stem_len = file_name.len().saturating_sub(PROTO_EXTENSION.len());
+    match file_name.get(stem_len..) {
+        Some(extension) if stem_len > 0 && extension.eq_ignore_ascii_case(PROTO_EXTENSION) => {
+            file_name[..stem_len].into()
+        }
+        _ => Arc::from(file_name),
+    }
+}
+
+#[cfg(test)]
+mod test_file_name_to_folder_name {
+    use super::super::{
+        options::Options,
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+
+    #[test]
+    fn it_strips_the_proto_extension() {
+        assert_eq!(&*file_name_to_folder_name("foo.proto"), "foo");
+        assert_eq!(&*file_name_to_folder_name("FOO.PROTO"), "FOO");
+        assert_eq!(&*file_name_to_folder_name("foo.Proto"), "foo");
+    }
+
+    #[test]
+    fn it_keeps_the_other_dots() {
+        assert_eq!(&*file_name_to_folder_name("foo.bar.proto"), "foo.bar");
+        assert_eq!(&*file_name_to_folder_name("my.service.proto"), "my.service");
+        assert_eq!(&*file_name_to_folder_name("foo.proto.bak"), "foo.proto.bak");
+    }
+
+    #[test]
+    fn it_keeps_names_without_the_proto_extension() {
+        assert_eq!(&*file_name_to_folder_name("foo"), "foo");
+        assert_eq!(&*file_name_to_folder_name("fooproto"), "fooproto");
+        assert_eq!(&*file_name_to_folder_name(".proto"), ".proto");
+        assert_eq!(&*file_name_to_folder_name("ééééa"), "ééééa");
+    }
+
+    #[test]
+    fn it_imports_from_the_folders_of_dotted_file_names() {
+        let sources = &[
+            (
+                "shop/order.api.proto",
+                "syntax = \"proto3\";\npackage shop;\nimport \"shop/line.v1.PROTO\";\nmessage Order { Line line = 1; }\n",
+            ),
+            (
+                "shop/line.v1.PROTO",
+                "syntax = \"proto3\";\npackage shop;\nmessage Line { string sku = 1; }\n",
+            ),
+        ];
+        let folder = compile_sources(sources, &Options::default());
+        let types = render_file_at(&folder, &["shop", "order.api", "Order", "types"]);
+        assert!(
+            types.contains("import { Line, LineEncodeInput } from \"../../line.v1/Line/types\"\n")
+        );
+        render_file_at(&folder, &["shop", "line.v1", "Line", "types"]);
     }
 }
@@ -1,77 +1,279 @@
-use std::ops::Deref;
+use std::{collections::HashSet, ops::Deref};
 
 use super::{
-    ast::Folder, decode_compiler::compile_decode, encode_compiler::compile_encode,
-    enum_compiler::insert_enum_declaration, file_name_to_folder_name::file_name_to_folder_name,
+    ast::{self, Folder, FolderEntry},
+    create_compiler::compile_create,
+    decode_compiler::compile_decode,
+    encode_compiler::compile_encode,
+    ensure_import::ensure_import,
+    enum_compiler::insert_enum_declaration,
+    equals_compiler::compile_equals,
+    file_case::file_case_name,
+    file_name_to_folder_name::file_name_to_folder_name,
+    guard_compiler::compile_guard,
+    json_compiler::compile_json,
+    options::Options,
+    service_compiler::insert_services,
     types_compiler::insert_message_types,
+    well_known_types::mapped_well_known_type,
 };
 use crate::proto::{
     error::ProtoError,
     proto_scope::{root_scope::RootScope, traits::ChildrenScopes, ProtoScope},
 };
 
+/// Compiles the messages, enums and services of the proto file into a folder.
+/// A message that fails to compile does not stop the others,
+/// errors of all of them are reported together.
 pub(super) fn file_to_folder(
     root: &RootScope,
+    options: &Options,
     file_scope: &ProtoScope,
 ) -> Result<Folder, ProtoError> {
-    let folder_name = file_name_to_folder_name(&file_scope.name());
+    let folder_name = file_case_name(
+        options.file_case,
+        &file_name_to_folder_name(&file_scope.name()),
+    );
     let mut res = Folder::new(folder_name);
+    let mut errors = Vec::new();
     for declaration in file_scope.children().iter() {
         match declaration.deref() {
             ProtoScope::Root(_) => unreachable!(),
             ProtoScope::Package(_) => unreachable!(),
             ProtoScope::File(_) => unreachable!(),
-            e @ ProtoScope::Enum(_) => insert_enum_declaration(&mut res, e),
+            e @ ProtoScope::Enum(_) => insert_enum_declaration(root, options, &mut res, e),
+            // mapped well-known messages are written by `wkt.ts`
+            ProtoScope::Message(m) if mapped_well_known_type(root, options, m.id).is_some() => {}
             m @ ProtoScope::Message(_) => {
-                insert_message_declaration(&root, &mut res, m)?;
+                if let Err(e) = insert_message_declaration(root, options, &mut res, m) {
+                    errors.push(e);
+                }
             }
         };
     }
+    if !errors.is_empty() {
+        return Err(ProtoError::from_errors(errors));
+    }
+    if let ProtoScope::File(file) = file_scope {
+        insert_services(root, options, &mut res, file);
+    }
+    Ok(res)
+}
+
+/// Compiles the proto file into a single `.ts` file
+/// containing all of its messages and enums.
+pub(super) fn file_to_file(
+    root: &RootScope,
+    options: &Options,
+    file_scope: &ProtoScope,
+) -> Result<ast::File, ProtoError> {
+    let folder = file_to_folder(root, options, file_scope)?;
+    let mut res = ast::File::new(folder.name.clone());
+    flatten_folder(&mut res, folder, &mut HashSet::new());
     Ok(res)
 }
 
+fn flatten_folder(res: &mut ast::File, folder: Folder, keys: &mut HashSet<String>) {
+    for entry in folder.entries {
+        match entry {
+            FolderEntry::File(file) => merge_file(res, *file, keys),
+            FolderEntry::Folder(folder) => flatten_folder(res, *folder, keys),
+        }
+    }
+}
+
+/// Appends statements of `file` to `res`, merging imports.
+/// Imports and declarations whose keys are already in `keys`
+/// (like shared helper functions) are skipped.
+fn merge_file(res: &mut ast::File, file: ast::File, keys: &mut HashSet<String>) {
+    for statement in file.ast.statements {
+        if let Some(key) = statement_key(&statement) {
+            if !keys.insert(key) {
+                continue;
+            }
+        }
+        match statement {
+            ast::Statement::ImportDeclaration(import) => ensure_import(res, *import),
+            statement => res.ast.statements.push(statement),
+        }
+    }
+}
+
+/// Key `merge_file` skips duplicated statements by:
+/// the rendered import, or the kind and the name of the declaration.
+/// Other statements have no key and are always appended.
+fn statement_key(statement: &ast::Statement) -> Option<String> {
+    use ast::Statement::*;
+    match statement {
+        ImportDeclaration(_) => Some(String::from(statement)),
+        EnumDeclaration(d) => Some(format!("enum {}", d.name.text)),
+        InterfaceDeclaration(d) => Some(format!("interface {}", d.name.text)),
+        FunctionDeclaration(d) => Some(format!("function {}", d.name.text)),
+        TypeAliasDeclaration(d) => Some(format!("type {}", d.name.text)),
+        ClassDeclaration(d) => Some(format!("class {}", d.name.text)),
+        NamespaceDeclaration(d) => Some(format!("namespace {}", d.name.text)),
+        VariableStatement(list) => Some(format!(
+            "var {}",
+            list.declarations
+                .iter()
+                .map(|d| d.name.text.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        _ => None,
+    }
+}
+
 fn insert_message_declaration(
     root: &RootScope,
+    options: &Options,
     message_parent_folder: &mut Folder,
     message_scope: &ProtoScope,
 ) -> Result<(), ProtoError> {
-    let message_name = message_scope.name();
+    let message_name = file_case_name(options.file_case, &message_scope.name());
     let mut message_folder = Folder::new(message_name);
+    let mut errors = Vec::new();
     if !message_scope
         .get_message_declaration()
         .map(|d| d.entries.is_empty())
         .unwrap_or(false)
     {
-        insert_message_types(&root, &mut message_folder, &message_scope)?;
-        compile_encode(&root, &mut message_folder, &message_scope)?;
-        compile_decode(&root, &mut message_folder, &message_scope)?;
+        if options.emit_types {
+            if let Err(e) = insert_message_types(root, options, &mut message_folder, message_scope)
+            {
+                errors.push(e);
+            }
+        }
+        if !options.declarations_only {
+            if let Err(e) =
+                insert_message_functions(root, options, &mut message_folder, message_scope)
+            {
+                errors.push(e);
+            }
+        }
+    }
+    // nested messages are compiled even if their parent failed
+    if let Err(e) = insert_children(root, options, &mut message_folder, message_scope) {
+        errors.push(e);
+    }
+    if !errors.is_empty() {
+        return Err(ProtoError::from_errors(errors));
     }
-    insert_children(&root, &mut message_folder, &message_scope)?;
     message_parent_folder.entries.push(message_folder.into());
 
     Ok(())
 }
 
+fn insert_message_functions(
+    root: &RootScope,
+    options: &Options,
+    message_folder: &mut Folder,
+    message_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    compile_create(root, options, message_folder, message_scope)?;
+    if options.emit_encode {
+        compile_encode(root, options, message_folder, message_scope)?;
+    }
+    if options.emit_decode {
+        compile_decode(root, options, message_folder, message_scope)?;
+    }
+    if options.emit_json {
+        compile_json(root, options, message_folder, message_scope)?;
+    }
+    if options.emit_guards {
+        compile_guard(root, options, message_folder, message_scope)?;
+    }
+    if options.emit_equals {
+        compile_equals(root, options, message_folder, message_scope)?;
+    }
+    Ok(())
+}
+
 fn insert_children(
     root: &RootScope,
+    options: &Options,
     message_folder: &mut Folder,
     message_scope: &ProtoScope,
 ) -> Result<(), ProtoError> {
-    let message_declaration = match message_scope.deref() {
+    let message_declaration = match message_scope {
         ProtoScope::Message(m) => m,
         _ => unreachable!(),
     };
 
+    let mut errors = Vec::new();
     for child_scope in message_declaration.children().iter() {
         match child_scope.deref() {
             ProtoScope::Root(_) => unreachable!(),
             ProtoScope::Package(_) => unreachable!(),
             ProtoScope::File(_) => unreachable!(),
-            e @ ProtoScope::Enum(_) => insert_enum_declaration(message_folder, e),
+            e @ ProtoScope::Enum(_) => insert_enum_declaration(root, options, message_folder, e),
             m @ ProtoScope::Message(_) => {
-                insert_message_declaration(&root, message_folder, m)?;
+                if let Err(e) = insert_message_declaration(root, options, message_folder, m) {
+                    errors.push(e);
+                }
             }
         }
     }
+    if !errors.is_empty() {
+        return Err(ProtoError::from_errors(errors));
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod test_emit_options {
+    use super::super::test_utils::compile_sources;
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "pkg.proto",
+        "syntax = \"proto3\";\npackage pkg;\nmessage Msg { string s = 1; }\n",
+    )];
+
+    fn message_file_names(options: &Options) -> Vec<String> {
+        let folder = compile_sources(SOURCES, options);
+        folder
+            .find_folder_by_path(&["pkg", "pkg", "Msg"])
+            .unwrap()
+            .entries
+            .iter()
+            .filter_map(|entry| match entry {
+                FolderEntry::File(f) => Some(f.name.to_string()),
+                FolderEntry::Folder(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_emits_all_files_by_default() {
+        assert_eq!(
+            message_file_names(&Options::default()),
+            ["create", "decode", "encode", "json", "types"]
+        );
+    }
+
+    #[test]
+    fn it_skips_disabled_files() {
+        let options = Options {
+            emit_encode: false,
+            emit_json: false,
+            ..Options::default()
+        };
+        assert_eq!(message_file_names(&options), ["create", "decode", "types"]);
+        let options = Options {
+            emit_types: false,
+            emit_decode: false,
+            ..Options::default()
+        };
+        assert_eq!(message_file_names(&options), ["create", "encode", "json"]);
+    }
+
+    #[test]
+    fn it_emits_only_types_of_declarations() {
+        let options = Options {
+            declarations_only: true,
+            ..Options::default()
+        };
+        assert_eq!(message_file_names(&options), ["types"]);
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::HashSet, sync::Arc};
+
+use super::{
+    ast::{self, FolderEntry, StatementList},
+    constants::INDEX_FILE_NAME,
+    ensure_import::ensure_import,
+};
+
+/// Statement of `index.ts` with the namespace it is declared in.
+type NamespacedStatement = (Option<Arc<str>>, ast::Statement);
+
+/// Merges the files compiled with the index layout into a single `index.ts`.
+///
+/// Declarations of the index layout are named by their qualified names (`shop.Order.encode`),
+/// each of them is declared by the last part of the name inside of the exported namespace
+/// named by the rest of it. Enums are hoisted above the messages,
+/// imports of all files are merged at the top and helpers shared by the files
+/// (like `bytesFromBase64`) are emitted once at the bottom.
+pub(super) fn files_to_index(entries: Vec<FolderEntry>) -> ast::File {
+    let mut files = Vec::new();
+    collect_files(entries, &mut files);
+
+    let mut index = ast::File::new(INDEX_FILE_NAME.into());
+    let mut enums = Vec::new();
+    let mut declarations = Vec::new();
+    let mut helpers = Vec::new();
+    for file in files {
+        for statement in file.ast.statements {
+            match statement {
+                ast::Statement::ImportDeclaration(import) => ensure_import(&mut index, *import),
+                ast::Statement::EnumDeclaration(mut declaration) => {
+                    let namespace = split_qualified_name(&mut declaration.name);
+                    enums.push((namespace, ast::Statement::EnumDeclaration(declaration)));
+                }
+                // enums declared as unions or const objects
+                ast::Statement::TypeAliasDeclaration(mut declaration) => {
+                    let namespace = split_qualified_name(&mut declaration.name);
+                    enums.push((namespace, ast::Statement::TypeAliasDeclaration(declaration)));
+                }
+                ast::Statement::VariableStatement(mut list) if !list.modifiers.is_empty() => {
+                    let declaration = &mut Arc::get_mut(&mut list).unwrap().declarations[0];
+                    let mut name = ast::Identifier::clone(&declaration.name);
+                    let namespace = split_qualified_name(&mut name);
+                    declaration.name = Arc::new(name);
+                    enums.push((namespace, ast::Statement::VariableStatement(list)));
+                }
+                ast::Statement::InterfaceDeclaration(mut declaration) => {
+                    let namespace = split_qualified_name(&mut declaration.name);
+                    declarations
+                        .push((namespace, ast::Statement::InterfaceDeclaration(declaration)));
+                }
+                ast::Statement::ClassDeclaration(mut declaration) => {
+                    let namespace = split_qualified_name(&mut declaration.name);
+                    declarations.push((namespace, ast::Statement::ClassDeclaration(declaration)));
+                }
+                ast::Statement::FunctionDeclaration(mut declaration) => {
+                    // functions of messages are always qualified, the rest are helpers
+                    match split_qualified_name(&mut declaration.name) {
+                        Some(namespace) => declarations.push((
+                            Some(namespace),
+                            ast::Statement::FunctionDeclaration(declaration),
+                        )),
+                        None => helpers.push(ast::Statement::FunctionDeclaration(declaration)),
+                    }
+                }
+                statement => helpers.push(statement),
+            }
+        }
+    }
+    push_namespaces(&mut index, enums);
+    push_namespaces(&mut index, declarations);
+    let mut rendered_helpers = HashSet::new();
+    for helper in helpers {
+        if rendered_helpers.insert(String::from(&helper)) {
+            index.push_statement(helper);
+        }
+    }
+    index
+}
+
+fn collect_files(entries: Vec<FolderEntry>, files: &mut Vec<ast::File>) {
+    for entry in entries {
+        match entry {
+            FolderEntry::File(file) => files.push(*file),
+            FolderEntry::Folder(folder) => collect_files(folder.entries, files),
+        }
+    }
+}
+
+/// Replaces the qualified `name` with its last part and returns the rest of it,
+/// `None` for declarations outside of any package and message.
+fn split_qualified_name(name: &mut ast::Identifier) -> Option<Arc<str>> {
+    let (namespace, local_name) = name.text.rsplit_once('.')?;
+    let (namespace, local_name): (Arc<str>, Arc<str>) = (namespace.into(), local_name.into());
+    name.text = local_name;
+    Some(namespace)
+}
+
+/// Appends `statements` to `index`, consecutive statements of the same namespace
+/// share a single namespace block.
+fn push_namespaces(index: &mut ast::File, statements: Vec<NamespacedStatement>) {
+    let mut block: Option<ast::NamespaceDeclaration> = None;
+    for (namespace, statement) in statements {
+        let is_same_block = matches!(
+            (&block, &namespace),
+            (Some(block), Some(namespace)) if block.name.text == *namespace
+        );
+        if !is_same_block {
+            if let Some(block) = block.take() {
+                index.push_statement(block.into());
+            }
+            block = namespace.map(ast::NamespaceDeclaration::new_exported);
+        }
+        match block.as_mut() {
+            Some(block) => block.push_statement(statement),
+            None => index.push_statement(statement),
+        }
+    }
+    if let Some(block) = block {
+        index.push_statement(block.into());
+    }
+}
+
+#[cfg(test)]
+mod test_files_to_index {
+    use super::super::{
+        ast::FolderEntry,
+        options::{EnumStyle, Layout, Options},
+        test_utils::{compile_sources, render_file_at},
+    };
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "shop/order.proto",
+            r#"
+            syntax = "proto3";
+            package shop;
+            import "common/money.proto";
+            message Order {
+                message Line {
+                    string sku = 1;
+                    common.Money price = 2;
+                }
+                enum Kind { KIND_UNKNOWN = 0; KIND_GIFT = 1; }
+                repeated Line lines = 1;
+                Status status = 2;
+                Kind kind = 3;
+                bytes note = 4;
+            }
+            enum Status { NEW = 0; PAID = 1; }
+            "#,
+        ),
+        (
+            "common/money.proto",
+            r#"
+            syntax = "proto3";
+            package common;
+            message Money { int64 units = 1; bytes blob = 2; }
+            "#,
+        ),
+    ];
+
+    fn index_options() -> Options {
+        Options {
+            layout: Layout::Index,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn it_emits_a_single_file_without_folders() {
+        let folder = compile_sources(SOURCES, &index_options());
+        let names = folder
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                FolderEntry::File(file) => file.name.to_string(),
+                FolderEntry::Folder(folder) => panic!("unexpected folder {}", folder.name),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["index"]);
+    }
+
+    #[test]
+    fn it_declares_messages_inside_of_namespaces() {
+        let index = render_file_at(&compile_sources(SOURCES, &index_options()), &["index"]);
+        let position = |code: &str| {
+            index
+                .find(code)
+                .unwrap_or_else(|| panic!("{} is not found in\n{}", code, index))
+        };
+
+        // enums are hoisted above every message
+        let enums = [
+            "export namespace shop.Order {\n  export enum Kind {\n",
+            "export namespace shop {\n  export enum Status {\n",
+        ];
+        for declaration in [
+            "export namespace common {\n  export interface MoneyEncodeInput {\n",
+            "  export interface Money {\n",
+            "export namespace common.Money {\n  export function create(",
+            "export namespace shop {\n  export interface OrderEncodeInput {\n",
+            "  export interface Order {\n",
+            "export namespace shop.Order {\n  export function create(",
+            "  export function encode(message: shop.OrderEncodeInput, writer?: Writer): Writer {\n",
+            "  export function decode(reader: Reader | Uint8Array, length?: number): shop.Order {\n",
+            "  export function toJSON(message: shop.Order): any {\n",
+            "  export function fromJSON(json: any): shop.Order {\n",
+            "  export interface Line {\n",
+            "export namespace shop.Order.Line {\n  export function create(",
+        ] {
+            assert!(enums.iter().all(|e| position(e) < position(declaration)));
+        }
+
+        // references are qualified instead of imported
+        assert!(
+            index.starts_with("import { Reader, Writer, util } from \"protobufjs/minimal\"\n\n")
+        );
+        assert_eq!(index.matches("import ").count(), 1);
+        assert!(index.contains("    price: common.Money | undefined\n"));
+        assert!(index.contains("    status: shop.Status\n"));
+        assert!(index.contains("    kind: shop.Order.Kind\n"));
+        assert!(index
+            .contains("shop.Order.Line.encode(message.lines[i], w.uint32(10).fork()).ldelim()"));
+        assert!(index.contains("const message: any = shop.Order.create()"));
+
+        // shared helpers are declared once at the top level
+        assert_eq!(index.matches("function bytesFromBase64(").count(), 1);
+        assert!(
+            position("\nfunction bytesFromBase64(")
+                > position("export namespace shop.Order.Line {")
+        );
+    }
+
+    #[test]
+    fn it_declares_enum_objects_inside_of_namespaces() {
+        let options = Options {
+            enum_style: EnumStyle::Union,
+            ..index_options()
+        };
+        let index = render_file_at(&compile_sources(SOURCES, &options), &["index"]);
+        assert!(index.contains(
+            "export namespace shop {\n  export type Status = \"NEW\" | \"PAID\"\n\n  \
+             export const Status = {\n"
+        ));
+        assert!(index.contains("  export const StatusNames: Record<number, shop.Status> = {\n"));
+        assert!(index.contains("w.uint32(16).int32(shop.Status[message.status])"));
+    }
+}
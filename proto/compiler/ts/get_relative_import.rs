@@ -61,7 +61,9 @@ pub(super) fn get_relative_import(
     let import_string = get_relative_import_string(from, to);
     import_string.map(|import_string| ast::ImportDeclaration {
         import_clause: ast::ImportClause {
+            is_type_only: false,
             name: None,
+            namespace_import: None,
             named_bindings: Some(vec![ast::ImportSpecifier::new(
                 ast::Identifier::new(&imported_name).into(),
             )]),
@@ -70,3 +72,309 @@ pub(super) fn get_relative_import(
         string_literal: import_string.into(),
     })
 }
+
+#[cfg(test)]
+mod test_get_relative_import {
+    use std::{
+        collections::HashSet,
+        path::{Component, Path, PathBuf},
+    };
+
+    use super::super::{
+        commit_folder::render_folder,
+        options::{Layout, Options},
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+
+    /// Builds a path of folders followed by a file and an enum declaration
+    /// when `declaration` is given.
+    fn path(folders: &[&str], file: &str, declaration: Option<&str>) -> Vec<TsPathComponent> {
+        let mut res = folders
+            .iter()
+            .map(|folder| TsPathComponent::Folder((*folder).into()))
+            .collect::<Vec<_>>();
+        res.push(TsPathComponent::File(file.into()));
+        if let Some(declaration) = declaration {
+            res.push(TsPathComponent::Enum(declaration.into()));
+        }
+        res
+    }
+
+    fn import_string(from: Vec<TsPathComponent>, to: Vec<TsPathComponent>) -> Option<String> {
+        get_relative_import_string(&from, &to)
+    }
+
+    #[test]
+    fn it_imports_enum_nested_in_the_importing_message() {
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "Order"], "types", None),
+                path(&["pkg", "file", "Order"], "Status", Some("Status")),
+            ),
+            Some("./Status".into())
+        );
+    }
+
+    #[test]
+    fn it_imports_enum_nested_in_a_child_message() {
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "Order"], "types", None),
+                path(&["pkg", "file", "Order", "Item"], "Kind", Some("Kind")),
+            ),
+            Some("./Item/Kind".into())
+        );
+    }
+
+    #[test]
+    fn it_imports_enum_of_the_parent_message() {
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "Order", "Item"], "types", None),
+                path(&["pkg", "file", "Order"], "Status", Some("Status")),
+            ),
+            Some("../Status".into())
+        );
+    }
+
+    #[test]
+    fn it_imports_enum_of_a_sibling_message() {
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "Order", "Item"], "types", None),
+                path(
+                    &["pkg", "file", "Order", "Payment"],
+                    "Method",
+                    Some("Method")
+                ),
+            ),
+            Some("../Payment/Method".into())
+        );
+    }
+
+    #[test]
+    fn it_imports_enum_of_another_file() {
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "Order"], "types", None),
+                path(&["pkg", "common", "Money"], "Currency", Some("Currency")),
+            ),
+            Some("../../common/Money/Currency".into())
+        );
+    }
+
+    #[test]
+    fn it_imports_top_level_enum_from_a_deeply_nested_message() {
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "A", "B", "C"], "types", None),
+                path(&["pkg", "file"], "Status", Some("Status")),
+            ),
+            Some("../../../Status".into())
+        );
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "A", "B", "C"], "types", None),
+                path(&["pkg", "file", "A"], "Kind", Some("Kind")),
+            ),
+            Some("../../Kind".into())
+        );
+    }
+
+    #[test]
+    fn it_imports_enum_of_another_package_from_a_deeply_nested_message() {
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "A", "B", "C"], "types", None),
+                path(&["common", "money"], "Currency", Some("Currency")),
+            ),
+            Some("../../../../../common/money/Currency".into())
+        );
+        assert_eq!(
+            import_string(
+                path(&["pkg", "sub", "file", "A"], "types", None),
+                path(&["pkg", "other", "B"], "Kind", Some("Kind")),
+            ),
+            Some("../../../other/B/Kind".into())
+        );
+    }
+
+    #[test]
+    fn it_imports_enum_nested_deeper_than_the_importing_message() {
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "A"], "types", None),
+                path(&["pkg", "file", "A", "B", "C"], "Kind", Some("Kind")),
+            ),
+            Some("./B/C/Kind".into())
+        );
+        assert_eq!(
+            import_string(
+                path(&["pkg", "file", "A", "B"], "types", None),
+                path(&["pkg", "file", "D", "E", "F"], "Kind", Some("Kind")),
+            ),
+            Some("../../D/E/F/Kind".into())
+        );
+    }
+
+    #[test]
+    fn it_does_not_import_from_the_same_file() {
+        let types = path(&["pkg", "file", "Order"], "types", None);
+        let mut interface = types.clone();
+        interface.push(TsPathComponent::Interface("Order".into()));
+        assert_eq!(import_string(types, interface), None);
+    }
+
+    #[test]
+    fn it_resolves_imports_of_nested_enums_in_generated_files() {
+        let folder = compile_sources(
+            &[(
+                "shop/order.proto",
+                r#"
+                syntax = "proto3";
+                package shop;
+                message Order {
+                    enum Status { STATUS_UNKNOWN = 0; }
+                    message Item {
+                        enum Kind { KIND_UNKNOWN = 0; }
+                        Kind kind = 1;
+                        Status status = 2;
+                    }
+                    Status status = 1;
+                    Item.Kind kind = 2;
+                }
+                "#,
+            )],
+            &Options::default(),
+        );
+        let order = render_file_at(&folder, &["shop", "order", "Order", "types"]);
+        assert!(order.contains("import { Status } from \"./Status\"\n"));
+        assert!(order.contains("import { Kind } from \"./Item/Kind\"\n"));
+        let item = render_file_at(&folder, &["shop", "order", "Order", "Item", "types"]);
+        assert!(item.contains("import { Kind } from \"./Kind\"\n"));
+        assert!(item.contains("import { Status } from \"../Status\"\n"));
+    }
+
+    #[test]
+    fn it_resolves_imports_of_deeply_nested_messages_in_generated_files() {
+        let folder = compile_sources(
+            &[(
+                "shop/order.proto",
+                r#"
+                syntax = "proto3";
+                package shop;
+                enum Status { STATUS_UNKNOWN = 0; }
+                message A {
+                    enum Kind { KIND_UNKNOWN = 0; }
+                    message B {
+                        message C {
+                            Status status = 1;
+                            A.Kind kind = 2;
+                            Line line = 3;
+                        }
+                    }
+                }
+                message Line { string sku = 1; }
+                "#,
+            )],
+            &Options::default(),
+        );
+        let c = render_file_at(&folder, &["shop", "order", "A", "B", "C", "types"]);
+        assert!(c.contains("import { Status } from \"../../../Status\"\n"));
+        assert!(c.contains("import { Kind } from \"../../Kind\"\n"));
+        assert!(c.contains("from \"../../../Line/types\"\n"));
+        // the imported files are where the imports point to
+        render_file_at(&folder, &["shop", "order", "Status"]);
+        render_file_at(&folder, &["shop", "order", "A", "Kind"]);
+        render_file_at(&folder, &["shop", "order", "Line", "types"]);
+    }
+
+    /// Relative import specifiers of every rendered file that do not point to a generated file,
+    /// as `(importing file, specifier)`.
+    fn unresolved_imports(files: &[(PathBuf, String)]) -> Vec<(PathBuf, String)> {
+        let generated = files
+            .iter()
+            .map(|(path, _)| path.with_extension(""))
+            .collect::<HashSet<_>>();
+        let mut res = Vec::new();
+        for (path, content) in files {
+            for specifier in content.split("from \"").skip(1) {
+                let specifier = &specifier[..specifier.find('"').unwrap()];
+                if !specifier.starts_with('.') {
+                    continue;
+                }
+                let mut target = path.parent().unwrap().to_path_buf();
+                for component in Path::new(specifier).components() {
+                    match component {
+                        Component::ParentDir => assert!(target.pop(), "{}", specifier),
+                        Component::Normal(name) => target.push(name),
+                        _ => {}
+                    }
+                }
+                if !generated.contains(&target) {
+                    res.push((path.clone(), specifier.to_string()));
+                }
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn it_imports_enums_of_three_levels_of_nested_messages_from_generated_files() {
+        let sources = &[
+            (
+                "shop/order.proto",
+                r#"
+                syntax = "proto3";
+                package shop;
+                message Outer {
+                    message Inner {
+                        message Deep {
+                            enum Status { STATUS_UNKNOWN = 0; }
+                            Status status = 1;
+                        }
+                        Deep.Status status = 1;
+                        Deep deep = 2;
+                    }
+                    Inner.Deep.Status status = 1;
+                    Inner inner = 2;
+                }
+                message Sibling { Outer.Inner.Deep.Status status = 1; }
+                "#,
+            ),
+            (
+                "shop/user.proto",
+                r#"
+                syntax = "proto3";
+                package shop;
+                import "shop/order.proto";
+                message User {
+                    Outer.Inner.Deep.Status status = 1;
+                    message Nested { Outer.Inner.Deep.Status status = 1; }
+                }
+                "#,
+            ),
+        ];
+        for layout in [Layout::Folder, Layout::File, Layout::Index] {
+            let options = Options {
+                layout,
+                emit_guards: true,
+                emit_equals: true,
+                ..Options::default()
+            };
+            let files = render_folder(&compile_sources(sources, &options));
+            assert_eq!(unresolved_imports(&files), vec![], "{:?}", layout);
+        }
+
+        let folder = compile_sources(sources, &Options::default());
+        let user = render_file_at(&folder, &["shop", "user", "User", "Nested", "types"]);
+        assert!(
+            user.contains("import { Status } from \"../../../order/Outer/Inner/Deep/Status\"\n")
+        );
+        render_file_at(
+            &folder,
+            &["shop", "order", "Outer", "Inner", "Deep", "Status"],
+        );
+    }
+}
@@ -0,0 +1,65 @@
+import type { Reader, Writer, util } from "protobufjs/minimal"
+
+export enum Status {
+  UNKNOWN = 0,
+  PAID = 1,
+  SHIPPED = 2,
+}
+
+export interface OrderEncodeInput {
+  id?: string | null
+  status?: Status | null
+  lines?: (Order_LineEncodeInput[]) | null
+  labels?: Record<string, string> | null
+  note?: string
+  card?: string | null
+  credits?: util.Long | number | null
+}
+
+export interface Order {
+  id: string
+  status: Status
+  lines: Order_Line[]
+  labels: Record<string, string>
+  note?: string
+  card?: string | null
+  credits?: util.Long | null
+}
+
+export declare function createOrder(init?: Partial<Order>): Order
+
+export declare function encodeOrder(message: OrderEncodeInput, writer?: Writer): Writer
+
+export declare function encodeDelimitedOrder(message: OrderEncodeInput, writer?: Writer): Writer
+
+export declare function decodeOrder(reader: Reader | Uint8Array, length?: number): Order
+
+export declare function decodeDelimitedOrder(reader: Reader | Uint8Array): Order
+
+export declare function toJSONOrder(message: Order): any
+
+export declare function fromJSONOrder(json: any): Order
+
+export interface Order_LineEncodeInput {
+  sku?: string | null
+  quantity?: number | null
+}
+
+export interface Order_Line {
+  sku: string
+  quantity: number
+}
+
+export declare function createOrder_Line(init?: Partial<Order_Line>): Order_Line
+
+export declare function encodeOrder_Line(message: Order_LineEncodeInput, writer?: Writer): Writer
+
+export declare function encodeDelimitedOrder_Line(message: Order_LineEncodeInput, writer?: Writer): Writer
+
+export declare function decodeOrder_Line(reader: Reader | Uint8Array, length?: number): Order_Line
+
+export declare function decodeDelimitedOrder_Line(reader: Reader | Uint8Array): Order_Line
+
+export declare function toJSONOrder_Line(message: Order_Line): any
+
+export declare function fromJSONOrder_Line(json: any): Order_Line
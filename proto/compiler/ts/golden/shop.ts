@@ -0,0 +1,296 @@
+import { Reader, Writer, util } from "protobufjs/minimal"
+
+export enum Status {
+  UNKNOWN = 0,
+  PAID = 1,
+  SHIPPED = 2,
+}
+
+export interface OrderEncodeInput {
+  id?: string | null
+  status?: Status | null
+  lines?: (Order_LineEncodeInput[]) | null
+  labels?: Record<string, string> | null
+  note?: string
+  card?: string | null
+  credits?: util.Long | number | null
+}
+
+export interface Order {
+  id: string
+  status: Status
+  lines: Order_Line[]
+  labels: Record<string, string>
+  note?: string
+  card?: string | null
+  credits?: util.Long | null
+}
+
+export function createOrder(init?: Partial<Order>): Order {
+  const message: any = {
+    id: "",
+    status: Status.UNKNOWN,
+    lines: [],
+    labels: {},
+    ...init,
+  }
+  return message
+}
+
+export function encodeOrder(message: OrderEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  if (message.id != null && Object.hasOwnProperty.call(message, "id")) {
+    w.uint32(10).string(message.id)
+  }
+  if (message.status != null && Object.hasOwnProperty.call(message, "status")) {
+    w.uint32(16).int32(message.status)
+  }
+  if (message.lines != null && message.lines.length)
+    for (let i = 0; i < message.lines.length; ++i)
+      encodeOrder_Line(message.lines[i], w.uint32(26).fork()).ldelim()
+  if (message.labels != null && Object.hasOwnProperty.call(message, "labels")) {
+    const ks = Object.keys(message.labels)
+    for (let i = 0; i < ks.length; ++i) {
+      const k = ks[i]
+      const v = message.labels[k]
+      w.uint32(34).fork().uint32(10).string(k).uint32(18).string(v).ldelim()
+    }
+  }
+  if (message.note !== undefined) {
+    w.uint32(42).string(message.note)
+  }
+  if (message.card != null && Object.hasOwnProperty.call(message, "card")) {
+    w.uint32(50).string(message.card)
+  }
+  if (message.credits != null && Object.hasOwnProperty.call(message, "credits")) {
+    w.uint32(56).int64(message.credits)
+  }
+  return w
+}
+
+export function encodeDelimitedOrder(message: OrderEncodeInput, writer?: Writer): Writer {
+  return encodeOrder(message, (writer || Writer.create()).fork()).ldelim()
+}
+
+export function decodeOrder(reader: Reader | Uint8Array, length?: number): Order {
+  const r = reader instanceof Reader ? reader : Reader.create(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  const message: any = createOrder()
+  while (r.pos < end) {
+    const tag = r.uint32()
+    switch (tag >>> 3) {
+      case 1: {
+        message.id = r.string()
+        break;
+      }
+      case 2: {
+        message.status = r.int32()
+        break;
+      }
+      case 3: {
+        if (!(message.lines && message.lines.length))
+          message.lines = []
+        message.lines.push(decodeOrder_Line(r, r.uint32()))
+        break;
+      }
+      case 4: {
+        const pair_end = r.uint32() + r.pos
+        let k: any = ""
+        let v: any = ""
+        while (r.pos < pair_end) {
+          const t = r.uint32()
+          switch (t >>> 3) {
+            case 1: {
+              k = r.string()
+              break;
+            }
+            case 2: {
+              v = r.string()
+              break;
+            }
+            default:
+              r.skipType(t & 7)
+              break;
+          }
+        }
+        message.labels[k] = v
+        break;
+      }
+      case 5: {
+        message.note = r.string()
+        break;
+      }
+      case 6: {
+        message.card = r.string()
+        break;
+      }
+      case 7: {
+        message.credits = r.int64()
+        break;
+      }
+      default:
+        r.skipType(tag & 7)
+        break;
+    }
+  }
+  return message
+}
+
+export function decodeDelimitedOrder(reader: Reader | Uint8Array): Order {
+  const r = reader instanceof Reader ? reader : Reader.create(reader)
+  return decodeOrder(r, r.uint32())
+}
+
+export function toJSONOrder(message: Order): any {
+  const json: any = {}
+  if (message.id != null && message.id !== "") {
+    json.id = message.id
+  }
+  if (message.status != null && message.status !== 0) {
+    json.status = Status[message.status] ?? message.status
+  }
+  if (message.lines != null && message.lines.length > 0) {
+    const a: any[] = []
+    for (let i = 0; i < message.lines.length; ++i)
+      a.push(toJSONOrder_Line(message.lines[i]))
+    json.lines = a
+  }
+  if (message.labels != null && Object.keys(message.labels).length > 0) {
+    const o: any = {}
+    const ks = Object.keys(message.labels)
+    for (let i = 0; i < ks.length; ++i) {
+      const k = ks[i]
+      o[k] = message.labels[k]
+    }
+    json.labels = o
+  }
+  if (message.note != null) {
+    json.note = message.note
+  }
+  if (message.card != null) {
+    json.card = message.card
+  }
+  if (message.credits != null) {
+    json.credits = String(message.credits)
+  }
+  return json
+}
+
+export function fromJSONOrder(json: any): Order {
+  const message: any = createOrder()
+  if (json.id != null) {
+    message.id = String(json.id)
+  }
+  if (json.status != null) {
+    message.status = typeof json.status === "string" ? Status[json.status] : json.status
+  }
+  if (json.lines != null) {
+    const a: any[] = []
+    for (let i = 0; i < json.lines.length; ++i)
+      a.push(fromJSONOrder_Line(json.lines[i]))
+    message.lines = a
+  }
+  if (json.labels != null) {
+    const o: any = {}
+    const ks = Object.keys(json.labels)
+    for (let i = 0; i < ks.length; ++i) {
+      const k = ks[i]
+      o[k] = String(json.labels[k])
+    }
+    message.labels = o
+  }
+  if (json.note != null) {
+    message.note = String(json.note)
+  }
+  if (json.card != null) {
+    message.card = String(json.card)
+  }
+  if (json.credits != null) {
+    message.credits = util.Long ? util.Long.fromValue(json.credits) : Number(json.credits)
+  }
+  return message
+}
+
+export interface Order_LineEncodeInput {
+  sku?: string | null
+  quantity?: number | null
+}
+
+export interface Order_Line {
+  sku: string
+  quantity: number
+}
+
+export function createOrder_Line(init?: Partial<Order_Line>): Order_Line {
+  const message: any = {
+    sku: "",
+    quantity: 0,
+    ...init,
+  }
+  return message
+}
+
+export function encodeOrder_Line(message: Order_LineEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  if (message.sku != null && Object.hasOwnProperty.call(message, "sku")) {
+    w.uint32(10).string(message.sku)
+  }
+  if (message.quantity != null && Object.hasOwnProperty.call(message, "quantity")) {
+    w.uint32(16).uint32(message.quantity)
+  }
+  return w
+}
+
+export function encodeDelimitedOrder_Line(message: Order_LineEncodeInput, writer?: Writer): Writer {
+  return encodeOrder_Line(message, (writer || Writer.create()).fork()).ldelim()
+}
+
+export function decodeOrder_Line(reader: Reader | Uint8Array, length?: number): Order_Line {
+  const r = reader instanceof Reader ? reader : Reader.create(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  const message: any = createOrder_Line()
+  while (r.pos < end) {
+    const tag = r.uint32()
+    switch (tag >>> 3) {
+      case 1: {
+        message.sku = r.string()
+        break;
+      }
+      case 2: {
+        message.quantity = r.uint32()
+        break;
+      }
+      default:
+        r.skipType(tag & 7)
+        break;
+    }
+  }
+  return message
+}
+
+export function decodeDelimitedOrder_Line(reader: Reader | Uint8Array): Order_Line {
+  const r = reader instanceof Reader ? reader : Reader.create(reader)
+  return decodeOrder_Line(r, r.uint32())
+}
+
+export function toJSONOrder_Line(message: Order_Line): any {
+  const json: any = {}
+  if (message.sku != null && message.sku !== "") {
+    json.sku = message.sku
+  }
+  if (message.quantity != null && message.quantity !== 0) {
+    json.quantity = message.quantity
+  }
+  return json
+}
+
+export function fromJSONOrder_Line(json: any): Order_Line {
+  const message: any = createOrder_Line()
+  if (json.sku != null) {
+    message.sku = String(json.sku)
+  }
+  if (json.quantity != null) {
+    message.quantity = Number(json.quantity)
+  }
+  return message
+}
@@ -0,0 +1,376 @@
+use std::sync::Arc;
+
+use crate::proto::{
+    error::ProtoError,
+    package::{self, Field, MessageEntry},
+    proto_scope::{root_scope::RootScope, ProtoScope},
+};
+
+use super::{
+    ast::{self, ElementAccess, ExpressionChain, Folder, Prop, StatementList, Type},
+    constants::GUARD_FUNCTION_NAME,
+    enum_values::is_enum_value,
+    has_fields::{has_field, has_field_name},
+    int64_type::is_bigint,
+    layout::{
+        declaration_file_path, declaration_name, guard_function_name, import_declaration,
+        import_named_function_lazily,
+    },
+    options::{Options, Runtime},
+    renames::property_name,
+    ts_path::TsPathComponent,
+    well_known_types::mapped_well_known_type,
+};
+
+/// Which values besides the ones of the field type a property accepts.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Presence {
+    /// Only values of the field type.
+    Required,
+    /// `undefined` as well: `optional` fields and messages, which are not set by `create`.
+    Optional,
+    /// `undefined` and `null`: options of a oneof.
+    Nullable,
+}
+
+/// Emits `is.ts` with the `isX(value: unknown): value is X` type guard of the message.
+///
+/// Properties of the decoded message are checked structurally: scalars by `typeof`,
+/// bytes as `Uint8Array`, enums by the membership in the generated enum,
+/// repeated fields and maps element-wise and nested messages by their own guards.
+/// Fields of well-known types mapped to idiomatic types are not checked.
+pub(super) fn compile_guard(
+    root: &RootScope,
+    options: &Options,
+    message_folder: &mut Folder,
+    message_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    let mut file = ast::File::new(GUARD_FUNCTION_NAME.into());
+
+    let message_id = message_scope.id().unwrap();
+    let message_type_id = import_declaration(
+        &mut file,
+        &declaration_file_path(root, options, message_id, GUARD_FUNCTION_NAME),
+        &declaration_file_path(root, options, message_id, "types"),
+        TsPathComponent::Interface(declaration_name(root, options, message_id)),
+        None,
+    );
+
+    let value_id: Arc<ast::Identifier> = Arc::new("value".into());
+    let value_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&value_id).into());
+    let v_id: Arc<ast::Identifier> = Arc::new("v".into());
+    let v_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&v_id).into());
+
+    let mut func =
+        ast::FunctionDeclaration::new_exported(&guard_function_name(root, options, message_id));
+    func.add_param(ast::Parameter::new(&value_id, Type::Unknown));
+    func.returns(Type::Predicate(
+        Arc::clone(&value_id),
+        Box::new(Type::reference(message_type_id)),
+    ));
+    func.push_statement(return_false_if(is_not_object(&value_expr)));
+    func.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Arc::clone(&v_id),
+            Type::Any.into(),
+            ast::Expression::from(value_id),
+        )
+        .into(),
+    );
+
+    let message_declaration = match message_scope {
+        ProtoScope::Message(m) => m,
+        _ => unreachable!(),
+    };
+    let mut fields: Vec<(&Field, Presence)> = Vec::new();
+    for entry in &message_declaration.entries {
+        match entry {
+            MessageEntry::Field(f) => {
+                let presence = match f.field_type {
+                    _ if f.may_be_unset() => Presence::Optional,
+                    package::Type::Message(_) => Presence::Optional,
+                    _ => Presence::Required,
+                };
+                fields.push((f, presence));
+            }
+            MessageEntry::OneOf(one_of) => {
+                fields.extend(one_of.options.iter().map(|o| (o, Presence::Nullable)));
+            }
+        }
+    }
+
+    for (field, presence) in fields {
+        let property: Arc<ast::Expression> =
+            Arc::new(v_expr.prop(&property_name(root, options, message_id, field)));
+        if has_field(options, field) {
+            let has_property: Arc<ast::Expression> =
+                Arc::new(v_expr.prop(&has_field_name(root, options, message_id, field)));
+            func.push_statement(return_false_if(ast::BinaryOperator::StrictNotEqual.apply(
+                has_property.type_of().into(),
+                Arc::new(ast::StringLiteral::from("boolean").into()),
+            )));
+        }
+        let mut check = |t: &package::Type, value: Arc<ast::Expression>| {
+            value_check(root, options, message_scope, &mut file, t, value)
+        };
+        match &field.field_type {
+            package::Type::Repeated(element_type) => {
+                func.push_statement(return_false_if(
+                    ast::Expression::from("Array")
+                        .into_prop("isArray")
+                        .into_call(vec![Arc::clone(&property)])
+                        .not(),
+                ));
+                let i_id: Arc<ast::Identifier> = Arc::new("i".into());
+                let element: Arc<ast::Expression> =
+                    Arc::new(property.element(Arc::new(Arc::clone(&i_id).into())));
+                if let Some(element_check) = check(element_type, element) {
+                    let mut for_stmt = ast::ForStatement::for_each(i_id, Arc::clone(&property));
+                    for_stmt.push_statement(return_false_if(negate(element_check)));
+                    func.push_statement(for_stmt.into());
+                }
+            }
+            package::Type::Map(_, value_type) => {
+                func.push_statement(return_false_if(is_not_object(&property)));
+                let k_id: Arc<ast::Identifier> = Arc::new("k".into());
+                let value: Arc<ast::Expression> =
+                    Arc::new(property.element(Arc::new(Arc::clone(&k_id).into())));
+                if let Some(value_check) = check(value_type, value) {
+                    let mut for_in = ast::ForInStatement::new(k_id, Arc::clone(&property));
+                    for_in.push_statement(return_false_if(negate(value_check)));
+                    func.push_statement(for_in.into());
+                }
+            }
+            t => {
+                let value_check = match check(t, Arc::clone(&property)) {
+                    Some(value_check) => value_check,
+                    None => continue,
+                };
+                let condition = match presence {
+                    Presence::Required => negate(value_check),
+                    Presence::Optional => Arc::new(
+                        ast::BinaryOperator::StrictNotEqual
+                            .apply(Arc::clone(&property), ast::Expression::Undefined.into()),
+                    )
+                    .and(negate(value_check).into()),
+                    Presence::Nullable => Arc::new(
+                        ast::BinaryOperator::WeakNotEqual
+                            .apply(Arc::clone(&property), ast::Expression::Null.into()),
+                    )
+                    .and(negate(value_check).into()),
+                };
+                func.push_statement(return_false_if(condition));
+            }
+        }
+    }
+
+    func.push_statement(ast::Expression::True.into_return_statement());
+    file.push_statement(func.into());
+    message_folder.push_file(file);
+    Ok(())
+}
+
+/// Expression that is true when `value` is a valid value of the scalar, enum or message type,
+/// `None` when any value is accepted.
+fn value_check(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    value_type: &package::Type,
+    value: Arc<ast::Expression>,
+) -> Option<ast::Expression> {
+    let type_of = |name: &str| {
+        ast::BinaryOperator::StrictEqual.apply(
+            value.type_of().into(),
+            Arc::new(ast::StringLiteral::from(name).into()),
+        )
+    };
+    let check = match value_type {
+        package::Type::Enum(e_id) => {
+            is_enum_value(root, options, message_scope, file, *e_id, value)
+        }
+        package::Type::Message(m_id) => {
+            if mapped_well_known_type(root, options, *m_id).is_some() {
+                return None;
+            }
+            import_guard(root, options, message_scope, file, *m_id).into_call(vec![value])
+        }
+        package::Type::Repeated(_) => unreachable!(),
+        package::Type::Map(_, _) => unreachable!(),
+        package::Type::Bool => type_of("boolean"),
+        package::Type::String => type_of("string"),
+        package::Type::Bytes => ast::BinaryOperator::InstanceOf.apply(
+            Arc::clone(&value),
+            Arc::new(ast::Expression::from("Uint8Array")),
+        ),
+        t if is_bigint(options, t) => type_of("bigint"),
+        // `util.Long` of protobufjs, numbers without long.js and bigints with the embedded runtime
+        t if t.long_wire_type().is_some() => match options.runtime {
+            Runtime::Protobufjs => {
+                let is_object = Arc::new(type_of("object")).and(
+                    ast::BinaryOperator::StrictNotEqual
+                        .apply(Arc::clone(&value), ast::Expression::Null.into())
+                        .into(),
+                );
+                Arc::new(type_of("number")).or(is_object.into())
+            }
+            Runtime::Standalone => type_of("bigint"),
+        },
+        _ => type_of("number"),
+    };
+    Some(check)
+}
+
+fn import_guard(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    m_id: usize,
+) -> ast::Expression {
+    let message_id = message_scope.id().unwrap();
+    let guard_name = guard_function_name(root, options, m_id);
+    if root.is_cyclic_reference(message_id, m_id) {
+        return import_named_function_lazily(
+            root,
+            options,
+            file,
+            message_id,
+            m_id,
+            GUARD_FUNCTION_NAME,
+            guard_name,
+        );
+    }
+    ast::Expression::from(import_declaration(
+        file,
+        &declaration_file_path(root, options, message_id, GUARD_FUNCTION_NAME),
+        &declaration_file_path(root, options, m_id, GUARD_FUNCTION_NAME),
+        TsPathComponent::Function(guard_name),
+        None,
+    ))
+}
+
+/// `typeof value !== "object" || value === null`
+fn is_not_object(value: &Arc<ast::Expression>) -> ast::Expression {
+    let is_not_object: Arc<ast::Expression> = Arc::new(ast::BinaryOperator::StrictNotEqual.apply(
+        value.type_of().into(),
+        Arc::new(ast::StringLiteral::from("object").into()),
+    ));
+    is_not_object.or(ast::BinaryOperator::StrictEqual
+        .apply(Arc::clone(value), ast::Expression::Null.into())
+        .into())
+}
+
+/// `!isX(value)` for calls, `!(check)` for the rest of the checks.
+fn negate(check: ast::Expression) -> ast::Expression {
+    match check {
+        ast::Expression::CallExpression(_) => check.not(),
+        check => check.into_parentheses().not(),
+    }
+}
+
+fn return_false_if(condition: ast::Expression) -> ast::Statement {
+    ast::IfStatement {
+        expression: condition.into(),
+        then_statement: Arc::new(ast::Expression::False.into_return_statement()),
+        else_statement: None,
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod test_guard_compiler {
+    use super::super::{
+        options::{Layout, Options},
+        test_utils::{compile_sources, render_file_at},
+    };
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "app/user.proto",
+            r#"
+            syntax = "proto3";
+            package app;
+            import "app/role.proto";
+            message User {
+                string name = 1;
+                optional int32 age = 2;
+                repeated Role roles = 3;
+                map<string, Address> addresses = 4;
+                Address home = 5;
+                bytes avatar = 6;
+                oneof contact { string email = 7; Address post = 8; }
+                message Address { string city = 1; }
+            }
+            "#,
+        ),
+        (
+            "app/role.proto",
+            r#"
+            syntax = "proto3";
+            package app;
+            enum Role { ROLE_UNKNOWN = 0; ROLE_ADMIN = 1; }
+            "#,
+        ),
+    ];
+
+    fn guard_options(layout: Layout) -> Options {
+        Options {
+            layout,
+            emit_guards: true,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn it_checks_every_field_of_the_message() {
+        let folder = compile_sources(SOURCES, &guard_options(Layout::Folder));
+        let guard = render_file_at(&folder, &["app", "user", "User", "is"]);
+        assert!(guard.contains("import { Role } from \"../../role/Role\"\n"));
+        assert!(guard.contains("import { isAddress } from \"./Address/is\"\n"));
+        assert!(guard.contains("export function isUser(value: unknown): value is User {\n"));
+        for check in [
+            "  if (typeof value !== \"object\" || value === null)\n    return false\n",
+            "  if (!(typeof v.name === \"string\"))\n",
+            "  if (v.age !== undefined && !(typeof v.age === \"number\"))\n",
+            "  if (!Array.isArray(v.roles))\n",
+            "    if (!(typeof v.roles[i] === \"number\" && v.roles[i] in Role))\n",
+            "  for (const k in v.addresses) {\n    if (!isAddress(v.addresses[k]))\n",
+            "  if (v.home !== undefined && !isAddress(v.home))\n",
+            "  if (!(v.avatar instanceof Uint8Array))\n",
+            "  if (v.email != null && !(typeof v.email === \"string\"))\n",
+            "  if (v.post != null && !isAddress(v.post))\n",
+        ] {
+            assert!(
+                guard.contains(check),
+                "{} is not found in\n{}",
+                check,
+                guard
+            );
+        }
+        assert!(guard.ends_with("  return true\n}\n"));
+    }
+
+    #[test]
+    fn it_is_not_generated_by_default() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let user = folder
+            .find_folder_by_path(&["app", "user", "User"])
+            .unwrap();
+        assert!(user.find_file("is").is_none());
+    }
+
+    #[test]
+    fn it_names_the_guard_by_the_layout() {
+        let folder = compile_sources(SOURCES, &guard_options(Layout::File));
+        let user = render_file_at(&folder, &["app", "user"]);
+        assert!(user.contains("export function isUser(value: unknown): value is User {\n"));
+        assert!(user.contains("  if (v.home !== undefined && !isUser_Address(v.home))\n"));
+
+        let folder = compile_sources(SOURCES, &guard_options(Layout::Index));
+        let index = render_file_at(&folder, &["index"]);
+        assert!(index.contains("  export function is(value: unknown): value is app.User {\n"));
+        assert!(index.contains("    if (v.home !== undefined && !app.User.Address.is(v.home))\n"));
+    }
+}
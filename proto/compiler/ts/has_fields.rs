@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use crate::proto::{package::Field, proto_scope::root_scope::RootScope};
+
+use super::{
+    ast::{self, Prop},
+    options::Options,
+    renames::property_name,
+};
+
+/// Whether the decoded message has a `hasName` property for `field`:
+/// fields declared `optional`, in proto3 or in proto2, with `--has-fields`.
+pub(super) fn has_field(options: &Options, field: &Field) -> bool {
+    options.emit_has_fields && field.optional
+}
+
+/// `hasAge` for the property `age`, `hasUser_id` for `user_id`.
+pub(super) fn has_field_name(
+    root: &RootScope,
+    options: &Options,
+    message_id: usize,
+    field: &Field,
+) -> Arc<str> {
+    let property = property_name(root, options, message_id, field);
+    let mut chars = property.chars();
+    let mut res = String::from("has");
+    if let Some(first) = chars.next() {
+        res.extend(first.to_uppercase());
+        res.push_str(chars.as_str());
+    }
+    res.into()
+}
+
+/// `message.hasAge = true`, after the field is read by `decode` or `fromJSON`.
+pub(super) fn set_has_field(message: &Arc<ast::Expression>, name: &str) -> ast::Statement {
+    ast::BinaryOperator::Assign
+        .apply(message.prop(name).into(), ast::Expression::True.into())
+        .into()
+}
+
+#[cfg(test)]
+mod test_has_fields {
+    use super::super::{
+        scope_to_folder::root_scope_to_folder,
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+    use crate::proto::package::read_root_scope_from_sources;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "shop/user.proto",
+        r#"
+        syntax = "proto3";
+        package shop;
+        message User {
+            string name = 1;
+            optional int32 age = 2;
+            optional string nick_name = 3;
+        }
+        "#,
+    )];
+
+    fn has_fields_options() -> Options {
+        Options {
+            emit_has_fields: true,
+            emit_guards: true,
+            ..Options::default()
+        }
+    }
+
+    fn render(options: &Options, file_name: &str) -> String {
+        let folder = compile_sources(SOURCES, options);
+        render_file_at(&folder, &["shop", "user", "User", file_name])
+    }
+
+    #[test]
+    fn it_declares_has_fields_of_optional_fields() {
+        let options = has_fields_options();
+        let types = render(&options, "types");
+        assert!(types.contains(
+            "export interface User {\n  name: string\n  age?: number\n  hasAge: boolean\n  nick_name?: string\n  hasNick_name: boolean\n}\n"
+        ));
+        assert!(types.contains("export interface UserEncodeInput {\n  name?: string | null\n  age?: number\n  nick_name?: string\n}\n"));
+
+        let decode = render(&options, "decode");
+        assert!(decode.contains(
+            "        message.age = r.int32()\n        message.hasAge = true\n        break;\n"
+        ));
+
+        let create = render(&options, "create");
+        assert!(create.contains("    hasAge: init?.age !== undefined,\n    hasNick_name: init?.nick_name !== undefined,\n    ...init,\n"));
+
+        let json = render(&options, "json");
+        assert!(json.contains("    message.age = Number(json.age)\n    message.hasAge = true\n"));
+        assert!(!json.contains("json.hasAge"));
+
+        let is = render(&options, "is");
+        assert!(is.contains("  if (typeof v.hasAge !== \"boolean\")\n    return false\n"));
+    }
+
+    #[test]
+    fn it_declares_has_fields_of_proto2_optional_fields() {
+        let sources = &[(
+            "shop/user.proto",
+            "syntax = \"proto2\";\npackage shop;\nmessage User { required string name = 1; optional int32 age = 2 [default = 18]; }\n",
+        )];
+        let folder = compile_sources(sources, &has_fields_options());
+        let types = render_file_at(&folder, &["shop", "user", "User", "types"]);
+        assert!(types.contains("  age: number\n  hasAge: boolean\n"));
+        assert!(!types.contains("hasName"));
+    }
+
+    #[test]
+    fn it_skips_has_fields_by_default() {
+        let options = Options {
+            emit_guards: true,
+            ..Options::default()
+        };
+        for file_name in ["types", "decode", "create", "json", "is"] {
+            assert!(
+                !render(&options, file_name).contains("hasAge"),
+                "{}",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn it_reports_fields_named_as_has_fields() {
+        let sources = &[(
+            "shop/user.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage User { optional int32 age = 1; bool hasAge = 2; }\n",
+        )];
+        let root = read_root_scope_from_sources(sources).unwrap();
+        let error = root_scope_to_folder(&root, &has_fields_options(), "out".into()).unwrap_err();
+        assert_eq!(error.code(), "property_name_collision");
+        assert_eq!(
+            error.to_string(),
+            "Fields \"hasAge\" and \"age\" of shop.User are both named \"hasAge\", rename one of them or choose another field case"
+        );
+        assert!(root_scope_to_folder(&root, &Options::default(), "out".into()).is_ok());
+    }
+}
@@ -1,17 +1,17 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use super::ast;
 pub(super) fn has_property(
-    obj_expr: Rc<ast::Expression>,
-    id: Rc<ast::Identifier>,
+    obj_expr: Arc<ast::Expression>,
+    id: Arc<ast::Identifier>,
 ) -> ast::Expression {
     ast::Expression::from(ast::Identifier::new("Object"))
         .into_prop("hasOwnProperty")
         .into_prop("call")
         .into_call(vec![
-            Rc::clone(&obj_expr),
-            Rc::new(ast::Expression::StringLiteral(ast::StringLiteral {
-                text: Rc::clone(&id.text),
+            Arc::clone(&obj_expr),
+            Arc::new(ast::Expression::StringLiteral(ast::StringLiteral {
+                text: Arc::clone(&id.text),
             })),
         ])
 }
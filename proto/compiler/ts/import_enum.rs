@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use crate::proto::proto_scope::{root_scope::RootScope, ProtoScope};
+
+use super::{
+    ast,
+    enum_compiler::enum_names_name,
+    layout::{declaration_file_path, declaration_name, import_declaration},
+    options::Options,
+    ts_path::TsPathComponent,
+};
+
+/// Imports the enum with `enum_id` into `file` of the `message_scope` folder
+/// and returns the identifier it is available under.
+pub(super) fn import_enum(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+) -> Arc<ast::Identifier> {
+    let enum_name = declaration_name(root, options, enum_id);
+    let enum_file_path = declaration_file_path(root, options, enum_id, &enum_name);
+    let current_file_path =
+        declaration_file_path(root, options, message_scope.id().unwrap(), &file.name);
+    import_declaration(
+        file,
+        &current_file_path,
+        &enum_file_path,
+        TsPathComponent::Enum(enum_name),
+        None,
+    )
+}
+
+/// Imports the object mapping wire numbers to the members of the enum with `enum_id`,
+/// emitted next to the enum by the union and const object enum styles and by `--enum-names`.
+pub(super) fn import_enum_names(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    enum_id: usize,
+) -> Arc<ast::Identifier> {
+    let enum_name = declaration_name(root, options, enum_id);
+    let enum_file_path = declaration_file_path(root, options, enum_id, &enum_name);
+    let current_file_path =
+        declaration_file_path(root, options, message_scope.id().unwrap(), &file.name);
+    import_declaration(
+        file,
+        &current_file_path,
+        &enum_file_path,
+        TsPathComponent::Enum(enum_names_name(&enum_name)),
+        None,
+    )
+}
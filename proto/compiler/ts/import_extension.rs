@@ -0,0 +1,98 @@
+use super::{
+    ast::{Folder, FolderEntry, Statement},
+    options::ImportExtension,
+};
+
+/// Appends the extension to every relative import specifier of the generated files:
+/// `"./types"` becomes `"./types.js"`, as ES modules resolved by Node16 require.
+///
+/// Bare specifiers, like `protobufjs/minimal`, are left as they are.
+/// Relative imports always point to files, no folder of the output has an `index.ts`.
+pub(super) fn add_import_extensions(folder: &mut Folder, extension: ImportExtension) {
+    match extension {
+        ImportExtension::None => {}
+        ImportExtension::Js => add_extension(folder, ".js"),
+        ImportExtension::Ts => add_extension(folder, ".ts"),
+    }
+}
+
+fn add_extension(folder: &mut Folder, extension: &str) {
+    for entry in folder.entries.iter_mut() {
+        match entry {
+            FolderEntry::File(file) => {
+                for statement in file.ast.statements.iter_mut() {
+                    let import = match statement {
+                        Statement::ImportDeclaration(import) => import,
+                        _ => continue,
+                    };
+                    let specifier = &import.string_literal.text;
+                    if specifier.starts_with("./") || specifier.starts_with("../") {
+                        import.string_literal.text = format!("{}{}", specifier, extension).into();
+                    }
+                }
+            }
+            FolderEntry::Folder(folder) => add_extension(folder, extension),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_import_extension {
+    use super::super::{
+        options::{Options, Runtime},
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "app/user.proto",
+            "syntax = \"proto3\";\npackage app;\nimport \"app/role.proto\";\nmessage User { Role role = 1; Address address = 2; message Address { string city = 1; } }\n",
+        ),
+        (
+            "app/role.proto",
+            "syntax = \"proto3\";\npackage app;\nenum Role { ROLE_UNKNOWN = 0; }\n",
+        ),
+    ];
+
+    fn options(import_extension: ImportExtension) -> Options {
+        Options {
+            import_extension,
+            runtime: Runtime::Standalone,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn it_appends_the_extension_to_relative_imports() {
+        let folder = compile_sources(SOURCES, &options(ImportExtension::Js));
+        let types = render_file_at(&folder, &["app", "user", "User", "types"]);
+        assert!(types.contains("import { Role } from \"../../role/Role.js\"\n"));
+        assert!(types.contains(" from \"./Address/types.js\"\n"));
+        let encode = render_file_at(&folder, &["app", "user", "User", "encode"]);
+        assert!(encode.contains(" from \"../../../runtime.js\"\n"));
+
+        let folder = compile_sources(SOURCES, &options(ImportExtension::Ts));
+        let types = render_file_at(&folder, &["app", "user", "User", "types"]);
+        assert!(types.contains("import { Role } from \"../../role/Role.ts\"\n"));
+    }
+
+    #[test]
+    fn it_keeps_bare_specifiers() {
+        let options = Options {
+            import_extension: ImportExtension::Js,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let encode = render_file_at(&folder, &["app", "user", "User", "encode"]);
+        assert!(encode.contains("import { Writer } from \"protobufjs/minimal\"\n"));
+        assert!(!encode.contains("minimal.js"));
+    }
+
+    #[test]
+    fn it_keeps_specifiers_without_extension_by_default() {
+        let folder = compile_sources(SOURCES, &options(ImportExtension::None));
+        let types = render_file_at(&folder, &["app", "user", "User", "types"]);
+        assert!(types.contains("import { Role } from \"../../role/Role\"\n"));
+    }
+}
@@ -0,0 +1,217 @@
+use super::{
+    ast::{File, Folder, FolderEntry, ImportClause, ImportDeclaration, Statement},
+    ensure_import::ensure_import,
+    options::ImportStyle,
+};
+
+/// Writes the imports of bindings used only as types, marked by the compilers,
+/// as `--import-style` asks for, in every file of the folder.
+///
+/// `ImportStyle::Plain` imports them as values, the same as the rest of the bindings.
+/// Otherwise imports of types only become `import type`, and the types of imports
+/// that also have values are split into an `import type` or get the inline `type` modifier.
+pub(super) fn apply_import_style(folder: &mut Folder, import_style: ImportStyle) {
+    for entry in folder.entries.iter_mut() {
+        match entry {
+            FolderEntry::File(file) => apply_to_file(file, import_style),
+            FolderEntry::Folder(folder) => apply_import_style(folder, import_style),
+        }
+    }
+}
+
+fn apply_to_file(file: &mut File, import_style: ImportStyle) {
+    let import_count = file
+        .ast
+        .statements
+        .iter()
+        .take_while(|statement| matches!(statement, Statement::ImportDeclaration(_)))
+        .count();
+    let imports = file
+        .ast
+        .statements
+        .drain(..import_count)
+        .map(|statement| match statement {
+            Statement::ImportDeclaration(import) => *import,
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    // imports are added again, so the `import type` split off merges with the one of the same module
+    for import in imports {
+        for styled_import in style_import(import, import_style) {
+            ensure_import(file, styled_import);
+        }
+    }
+}
+
+fn style_import(
+    mut import: ImportDeclaration,
+    import_style: ImportStyle,
+) -> Vec<ImportDeclaration> {
+    let clause = &mut import.import_clause;
+    let bindings = match &mut clause.named_bindings {
+        Some(bindings) if !bindings.is_empty() => bindings,
+        _ => return vec![import],
+    };
+    let all_types = bindings.iter().all(|specifier| specifier.is_type_only);
+    let any_type = bindings.iter().any(|specifier| specifier.is_type_only);
+    if import_style == ImportStyle::Plain
+        || clause.is_type_only
+        || (all_types && clause.name.is_none())
+    {
+        clause.is_type_only |= import_style != ImportStyle::Plain;
+        clear_type_modifiers(clause);
+        return vec![import];
+    }
+    if import_style == ImportStyle::Inline || !any_type {
+        return vec![import];
+    }
+    let (types, values) = bindings
+        .drain(..)
+        .partition::<Vec<_>, _>(|specifier| specifier.is_type_only);
+    *bindings = values;
+    let mut type_import =
+        ImportDeclaration::import(types, import.string_literal.text.as_ref().into());
+    type_import.import_clause.is_type_only = true;
+    clear_type_modifiers(&mut type_import.import_clause);
+    vec![import, type_import]
+}
+
+fn clear_type_modifiers(clause: &mut ImportClause) {
+    for specifier in clause.named_bindings.iter_mut().flatten() {
+        specifier.is_type_only = false;
+    }
+}
+
+#[cfg(test)]
+mod test_import_style {
+    use super::super::{
+        ast::{Identifier, ImportSpecifier, StatementList},
+        options::{Layout, Options},
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "shop/order.proto",
+            r#"
+            syntax = "proto3";
+            package shop;
+            import "shop/line.proto";
+            enum Status { NEW = 0; PAID = 1; }
+            message Order {
+                repeated Line lines = 1;
+                Status status = 2;
+            }
+            "#,
+        ),
+        (
+            "shop/line.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage Line { string sku = 1; }\n",
+        ),
+    ];
+
+    fn options(import_style: ImportStyle) -> Options {
+        Options {
+            import_style,
+            ..Options::default()
+        }
+    }
+
+    fn render(options: &Options, file_name: &str) -> String {
+        let folder = compile_sources(SOURCES, options);
+        render_file_at(&folder, &["shop", "order", "Order", file_name])
+    }
+
+    /// `encode.ts` importing the type `User` and the function `encodeUser` from `./User`.
+    fn mixed_import_folder() -> Folder {
+        let mut import = ImportDeclaration::import(
+            ["User", "encodeUser"]
+                .into_iter()
+                .map(|name| ImportSpecifier::new(Identifier::new(name).into()))
+                .collect(),
+            "./User".into(),
+        );
+        import.import_clause.named_bindings.as_mut().unwrap()[0].is_type_only = true;
+        let mut file = File::new("encode".into());
+        file.push_statement(import.into());
+        let mut folder = Folder::new("out".into());
+        folder.push_file(file);
+        folder
+    }
+
+    #[test]
+    fn it_imports_types_as_values_by_default() {
+        let options = options(ImportStyle::Plain);
+        let types = render(&options, "types");
+        assert!(types.contains("import { Line, LineEncodeInput } from \"../../line/Line/types\"\n"));
+        assert!(types.contains("import { Status } from \"../Status\"\n"));
+        let encode = render(&options, "encode");
+        assert!(encode.contains("import { OrderEncodeInput } from \"./types\"\n"));
+        assert!(!encode.contains("type "));
+
+        let mut folder = mixed_import_folder();
+        apply_import_style(&mut folder, ImportStyle::Plain);
+        assert_eq!(
+            render_file_at(&folder, &["encode"]),
+            "import { User, encodeUser } from \"./User\"\n"
+        );
+    }
+
+    #[test]
+    fn it_imports_types_with_import_type() {
+        let options = options(ImportStyle::Type);
+        let types = render(&options, "types");
+        assert!(types
+            .contains("import type { Line, LineEncodeInput } from \"../../line/Line/types\"\n"));
+        assert!(types.contains("import type { Status } from \"../Status\"\n"));
+
+        let encode = render(&options, "encode");
+        assert!(encode.contains("import type { OrderEncodeInput } from \"./types\"\n"));
+        assert!(encode.contains("import { Writer } from \"protobufjs/minimal\"\n"));
+
+        let decode = render(&options, "decode");
+        assert!(decode.contains("import type { Order } from \"./types\"\n"));
+        assert!(decode.contains("import { Reader } from \"protobufjs/minimal\"\n"));
+    }
+
+    #[test]
+    fn it_splits_imports_of_types_and_values_of_the_same_module() {
+        let options = Options {
+            layout: Layout::File,
+            ..options(ImportStyle::Type)
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let order = render_file_at(&folder, &["shop", "order"]);
+        assert!(order.contains("import type { Line, LineEncodeInput } from \"./line\"\n"));
+        assert!(order.starts_with("import { decodeLine as "));
+
+        let mut folder = mixed_import_folder();
+        apply_import_style(&mut folder, ImportStyle::Type);
+        assert_eq!(
+            render_file_at(&folder, &["encode"]),
+            "import { encodeUser } from \"./User\"\nimport type { User } from \"./User\"\n"
+        );
+    }
+
+    #[test]
+    fn it_imports_types_with_inline_modifiers() {
+        let file_options = Options {
+            layout: Layout::File,
+            ..options(ImportStyle::Inline)
+        };
+        let folder = compile_sources(SOURCES, &file_options);
+        let order = render_file_at(&folder, &["shop", "order"]);
+        assert!(order.starts_with("import { type Line, type LineEncodeInput, decodeLine as "));
+
+        let types = render(&options(ImportStyle::Inline), "types");
+        assert!(types.contains("import type { Status } from \"../Status\"\n"));
+
+        let mut folder = mixed_import_folder();
+        apply_import_style(&mut folder, ImportStyle::Inline);
+        assert_eq!(
+            render_file_at(&folder, &["encode"]),
+            "import { type User, encodeUser } from \"./User\"\n"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use crate::proto::package;
+
+use super::{
+    ast::{self, MethodCall},
+    options::{Int64Type, Options, Runtime},
+};
+
+/// Whether values of `field_type` are bigints: 64-bit integers with `Int64Type::BigInt`.
+pub(super) fn is_bigint(options: &Options, field_type: &package::Type) -> bool {
+    options.int64_type == Int64Type::BigInt && field_type.long_wire_type().is_some()
+}
+
+/// `BigInt(read.toString())`, the `Long` of long.js or the number protobufjs reads as a bigint.
+/// The standalone runtime reads bigints already, and other values are kept as they are read.
+pub(super) fn read_value(
+    options: &Options,
+    field_type: &package::Type,
+    read: Arc<ast::Expression>,
+) -> Arc<ast::Expression> {
+    if !is_bigint(options, field_type) || options.runtime == Runtime::Standalone {
+        return read;
+    }
+    ast::Expression::from("BigInt")
+        .into_call(vec![read.method_call("toString", vec![]).into()])
+        .into()
+}
+
+/// `String(value)`, protobufjs writes 64-bit integers from decimal strings without losing precision,
+/// but not from bigints. The standalone runtime writes bigints as they are.
+pub(super) fn write_value(
+    options: &Options,
+    field_type: &package::Type,
+    value: Arc<ast::Expression>,
+) -> Arc<ast::Expression> {
+    if !is_bigint(options, field_type) || options.runtime == Runtime::Standalone {
+        return value;
+    }
+    ast::Expression::from("String")
+        .into_call(vec![value])
+        .into()
+}
+
+#[cfg(test)]
+mod test_int64_type {
+    use super::super::test_utils::{compile_sources, render_file_at};
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "shop/order.proto",
+        r#"
+        syntax = "proto3";
+        package shop;
+        message Order {
+            int64 id = 1;
+            repeated sint64 deltas = 2;
+            map<string, uint64> totals = 3;
+            optional sfixed64 discount = 4;
+            int32 count = 5;
+        }
+        "#,
+    )];
+
+    fn render(options: &Options, file_name: &str) -> String {
+        let folder = compile_sources(SOURCES, options);
+        render_file_at(&folder, &["shop", "order", "Order", file_name])
+    }
+
+    fn bigint_options() -> Options {
+        Options {
+            int64_type: Int64Type::BigInt,
+            emit_guards: true,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn it_types_64_bit_integers_by_the_option() {
+        let types = render(&Options::default(), "types");
+        assert!(types.contains("  id?: util.Long | number | null\n"));
+        assert!(types.contains("  id: util.Long\n"));
+
+        let types = render(&bigint_options(), "types");
+        assert!(types.contains("  id?: bigint | null\n"));
+        assert!(types.contains("  deltas?: (bigint[]) | null\n"));
+        assert!(types.contains("  totals?: Record<string, bigint> | null\n"));
+        assert!(types.contains("  id: bigint\n"));
+        assert!(types.contains("  deltas: bigint[]\n"));
+        assert!(types.contains("  totals: Record<string, bigint>\n"));
+        assert!(types.contains("  discount?: bigint\n"));
+        assert!(types.contains("  count: number\n"));
+        assert!(!types.contains("util"));
+    }
+
+    #[test]
+    fn it_converts_bigints_from_and_to_protobufjs_longs() {
+        let options = bigint_options();
+        let encode = render(&options, "encode");
+        assert!(encode.contains("w.uint32(8).int64(String(message.id))\n"));
+        assert!(encode.contains("w.uint32(18).fork()"));
+        assert!(encode.contains("w.sint64(String(message.deltas[i]))\n"));
+        assert!(encode.contains(".uint32(16).uint64(String(v))"));
+        assert!(encode.contains("w.uint32(40).int32(message.count)\n"));
+
+        let decode = render(&options, "decode");
+        assert!(decode.contains("message.id = BigInt(r.int64().toString())\n"));
+        assert!(decode.contains("message.deltas.push(BigInt(r.sint64().toString()))\n"));
+        assert!(decode.contains("v = BigInt(r.uint64().toString())\n"));
+        assert!(decode.contains("message.count = r.int32()\n"));
+
+        let create = render(&options, "create");
+        assert!(create.contains("    id: BigInt(0),\n"));
+
+        let json = render(&options, "json");
+        assert!(json.contains("BigInt(json.id)"));
+
+        let is = render(&options, "is");
+        assert!(is.contains("  if (!(typeof v.id === \"bigint\"))\n"));
+    }
+
+    #[test]
+    fn it_keeps_bigints_of_the_standalone_runtime() {
+        let options = Options {
+            runtime: Runtime::Standalone,
+            ..bigint_options()
+        };
+        let encode = render(&options, "encode");
+        assert!(encode.contains("w.uint32(8).int64(message.id)\n"));
+        let decode = render(&options, "decode");
+        assert!(decode.contains("message.id = r.int64()\n"));
+    }
+}
@@ -0,0 +1,720 @@
+use std::sync::Arc;
+
+use crate::proto::{
+    error::ProtoError,
+    package::{self, Field, MessageEntry},
+    proto_scope::{root_scope::RootScope, ProtoScope},
+};
+
+use super::{
+    ast::{self, ElementAccess, ExpressionChain, Folder, MethodCall, Prop, StatementList, Type},
+    constants::{FROM_JSON_FUNCTION_NAME, TO_JSON_FUNCTION_NAME},
+    decode_compiler::import_create_func,
+    ensure_import::ensure_import,
+    enum_values::{enum_from_json, enum_numbers, enum_to_json, lookup_value},
+    has_fields::{has_field, has_field_name, set_has_field},
+    int64_type::is_bigint,
+    layout::{
+        declaration_file_path, declaration_name, function_name, import_declaration,
+        import_function_lazily,
+    },
+    options::Options,
+    renames::{json_key, property_name},
+    runtime::runtime_module,
+    ts_path::TsPathComponent,
+    well_known_types::{import_well_known, mapped_well_known_type},
+};
+
+/// Emits `json.ts` with `toJSON` and `fromJSON` functions
+/// implementing the canonical proto3 JSON mapping:
+/// enums are serialized by name, 64-bit integers as strings
+/// and bytes as base64. Fields set to their default values are omitted
+/// unless `emit_default_values` is enabled.
+pub(super) fn compile_json(
+    root: &RootScope,
+    options: &Options,
+    message_folder: &mut Folder,
+    message_scope: &ProtoScope,
+) -> Result<(), ProtoError> {
+    let mut file = ast::File::new("json".into());
+
+    let message_id = message_scope.id().unwrap();
+    let message_type_id = import_declaration(
+        &mut file,
+        &declaration_file_path(root, options, message_id, "json"),
+        &declaration_file_path(root, options, message_id, "types"),
+        TsPathComponent::Interface(declaration_name(root, options, message_id)),
+        None,
+    );
+
+    let fields = message_scope
+        .get_message_declaration()
+        .map(|d| d.get_fields())
+        .unwrap_or_default();
+
+    if fields.iter().any(|f| requires_util(options, &f.field_type)) {
+        ensure_import(
+            &mut file,
+            ast::ImportDeclaration::import(
+                vec![ast::ImportSpecifier::new(Arc::new("util".into()))],
+                runtime_module(root, options, message_id, "json").into(),
+            ),
+        );
+    }
+
+    let to_json_func = compile_to_json(
+        root,
+        options,
+        message_scope,
+        &mut file,
+        &message_type_id,
+        &fields,
+    );
+    let from_json_func = compile_from_json(
+        root,
+        options,
+        message_scope,
+        &mut file,
+        &message_type_id,
+        &fields,
+    );
+
+    file.push_statement(to_json_func.into());
+    file.push_statement(from_json_func.into());
+
+    if fields.iter().any(|f| contains_bytes(&f.field_type)) {
+        file.push_statement(bytes_from_base64_function().into());
+    }
+
+    message_folder.push_file(file);
+    Ok(())
+}
+
+fn compile_to_json(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    message_type_id: &Arc<ast::Identifier>,
+    fields: &[&Field],
+) -> ast::FunctionDeclaration {
+    let message_parameter_id: Arc<ast::Identifier> = Arc::new("message".into());
+    let json_var_id: Arc<ast::Identifier> = Arc::new("json".into());
+    let message_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&message_parameter_id).into());
+    let json_var_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&json_var_id).into());
+
+    let mut func = ast::FunctionDeclaration::new_exported(&function_name(
+        root,
+        options,
+        message_scope.id().unwrap(),
+        TO_JSON_FUNCTION_NAME,
+    ));
+    func.add_param(ast::Parameter::new(
+        &message_parameter_id,
+        Type::reference(Arc::clone(message_type_id)),
+    ));
+    func.returns(Type::Any);
+
+    func.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Arc::clone(&json_var_id),
+            Type::Any.into(),
+            ast::Expression::ObjectLiteralExpression(vec![]),
+        )
+        .into(),
+    );
+
+    let message_id = message_scope.id().unwrap();
+    for field in fields {
+        let field_value: Arc<ast::Expression> = Arc::new(
+            message_expr.prop(&property_name(root, options, message_id, field)),
+        );
+        let json_value: Arc<ast::Expression> =
+            Arc::new(json_var_expr.prop(&json_key(root, options, message_id, field)));
+        let block = convert_field(&field.field_type, &field_value, &json_value, &mut |t, v| {
+            to_json_value(root, options, message_scope, file, t, v)
+        });
+        let mut condition = is_present(&field_value);
+        if !options.emit_default_values && !has_presence(message_scope, field) {
+            // values of union enums are compared by their wire numbers
+            let wire_value = match &field.field_type {
+                package::Type::Enum(e_id) => lookup_value(
+                    enum_numbers(root, options, message_scope, file, *e_id).as_ref(),
+                    Arc::clone(&field_value),
+                ),
+                _ => Arc::clone(&field_value),
+            };
+            if let Some(not_default) = is_not_default(&field.field_type, &wire_value) {
+                condition = Arc::new(condition).and(not_default.into());
+            }
+        }
+        func.push_statement(if_statement(condition, block));
+    }
+
+    func.push_statement(ast::Expression::from(json_var_id).into_return_statement());
+    func
+}
+
+fn compile_from_json(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    message_type_id: &Arc<ast::Identifier>,
+    fields: &[&Field],
+) -> ast::FunctionDeclaration {
+    let json_parameter_id: Arc<ast::Identifier> = Arc::new("json".into());
+    let message_var_id: Arc<ast::Identifier> = Arc::new("message".into());
+    let json_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&json_parameter_id).into());
+    let message_var_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&message_var_id).into());
+
+    let mut func = ast::FunctionDeclaration::new_exported(&function_name(
+        root,
+        options,
+        message_scope.id().unwrap(),
+        FROM_JSON_FUNCTION_NAME,
+    ));
+    func.add_param(ast::Parameter::new(&json_parameter_id, Type::Any));
+    func.returns(Type::reference(Arc::clone(message_type_id)));
+
+    func.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Arc::clone(&message_var_id),
+            Type::Any.into(),
+            import_create_func(root, options, message_scope, file).into_call(vec![]),
+        )
+        .into(),
+    );
+
+    let message_id = message_scope.id().unwrap();
+    for field in fields {
+        let json_value = json_field_value(
+            &json_expr,
+            &json_key(root, options, message_id, field),
+            field,
+        );
+        let field_value: Arc<ast::Expression> = Arc::new(
+            message_var_expr.prop(&property_name(root, options, message_id, field)),
+        );
+        let mut block = convert_field(&field.field_type, &json_value, &field_value, &mut |t, v| {
+            from_json_value(root, options, message_scope, file, t, v)
+        });
+        if has_field(options, field) {
+            let has_name = has_field_name(root, options, message_id, field);
+            block.push_statement(set_has_field(&message_var_expr, &has_name));
+        }
+        func.push_statement(if_statement(is_present(&json_value), block));
+    }
+
+    func.push_statement(ast::Expression::from(message_var_id).into_return_statement());
+    func
+}
+
+/// `json.userId ?? json.user_id`: parsers of the proto3 JSON mapping accept
+/// the original name of the field as well as its JSON key.
+fn json_field_value(
+    json_expr: &Arc<ast::Expression>,
+    key: &str,
+    field: &Field,
+) -> Arc<ast::Expression> {
+    let value: Arc<ast::Expression> = Arc::new(json_expr.prop(key));
+    if *field.name == *key {
+        return value;
+    }
+    Arc::new(
+        ast::BinaryOperator::NullishCoalescing.apply(value, json_expr.prop(&field.name).into()),
+    )
+}
+
+/// Builds statements that convert `source` into `destination`,
+/// using `convert` for every scalar, enum or message value.
+/// Repeated fields are converted element-wise and maps value-wise.
+fn convert_field(
+    field_type: &package::Type,
+    source: &Arc<ast::Expression>,
+    destination: &Arc<ast::Expression>,
+    convert: &mut dyn FnMut(&package::Type, Arc<ast::Expression>) -> Arc<ast::Expression>,
+) -> ast::Block {
+    let mut block = ast::Block::new();
+    match field_type {
+        package::Type::Repeated(element_type) => {
+            let arr_id: Arc<ast::Identifier> = Arc::new("a".into());
+            let arr_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&arr_id).into());
+            let i_id: Arc<ast::Identifier> = Arc::new("i".into());
+            let i_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&i_id).into());
+            block.push_statement(
+                ast::VariableDeclarationList::declare_typed_const(
+                    Arc::clone(&arr_id),
+                    Type::array(Type::Any).into(),
+                    ast::Expression::ArrayLiteralExpression(vec![]),
+                )
+                .into(),
+            );
+            let mut for_stmt = ast::ForStatement::for_each(Arc::clone(&i_id), Arc::clone(source));
+            let element = convert(element_type, source.element(i_expr).into());
+            for_stmt.push_statement(arr_expr.method_call("push", vec![element]).into());
+            block.push_statement(for_stmt.into());
+            block.push_statement(
+                ast::BinaryOperator::Assign
+                    .apply(Arc::clone(destination), arr_expr)
+                    .into(),
+            );
+        }
+        package::Type::Map(_, value_type) => {
+            let obj_id: Arc<ast::Identifier> = Arc::new("o".into());
+            let obj_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&obj_id).into());
+            let keys_id: Arc<ast::Identifier> = Arc::new("ks".into());
+            let keys_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&keys_id).into());
+            let i_id: Arc<ast::Identifier> = Arc::new("i".into());
+            let i_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&i_id).into());
+            let key_id: Arc<ast::Identifier> = Arc::new("k".into());
+            let key_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&key_id).into());
+            block.push_statement(
+                ast::VariableDeclarationList::declare_typed_const(
+                    Arc::clone(&obj_id),
+                    Type::Any.into(),
+                    ast::Expression::ObjectLiteralExpression(vec![]),
+                )
+                .into(),
+            );
+            block.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    Arc::clone(&keys_id),
+                    ast::Expression::from("Object")
+                        .into_prop("keys")
+                        .into_call(vec![Arc::clone(source)]),
+                )
+                .into(),
+            );
+            let mut for_stmt = ast::ForStatement::for_each(Arc::clone(&i_id), Arc::clone(&keys_expr));
+            for_stmt.push_statement(
+                ast::VariableDeclarationList::declare_const(
+                    Arc::clone(&key_id),
+                    keys_expr.element(i_expr),
+                )
+                .into(),
+            );
+            let value = convert(value_type, source.element(Arc::clone(&key_expr)).into());
+            for_stmt.push_statement(
+                ast::BinaryOperator::Assign
+                    .apply(obj_expr.element(key_expr).into(), value)
+                    .into(),
+            );
+            block.push_statement(for_stmt.into());
+            block.push_statement(
+                ast::BinaryOperator::Assign
+                    .apply(Arc::clone(destination), obj_expr)
+                    .into(),
+            );
+        }
+        t => {
+            let value = convert(t, Arc::clone(source));
+            block.push_statement(
+                ast::BinaryOperator::Assign
+                    .apply(Arc::clone(destination), value)
+                    .into(),
+            );
+        }
+    }
+    block
+}
+
+fn if_statement(condition: ast::Expression, block: ast::Block) -> ast::Statement {
+    ast::IfStatement {
+        expression: condition.into(),
+        then_statement: Arc::new(block.into()),
+        else_statement: None,
+    }
+    .into()
+}
+
+fn is_present(value: &Arc<ast::Expression>) -> ast::Expression {
+    let value = match &**value {
+        ast::Expression::BinaryExpression(_) => {
+            ast::Expression::ParenthesizedExpression(Arc::clone(value)).into()
+        }
+        _ => Arc::clone(value),
+    };
+    ast::BinaryOperator::WeakNotEqual.apply(value, ast::Expression::Null.into())
+}
+
+/// Fields with explicit presence (`optional` and oneof fields)
+/// are serialized whenever they are set, even to a default value.
+fn has_presence(message_scope: &ProtoScope, field: &Field) -> bool {
+    field.optional
+        || message_scope
+            .get_message_declaration()
+            .map(|d| {
+                d.entries.iter().any(|entry| match entry {
+                    MessageEntry::OneOf(one_of) => {
+                        one_of.options.iter().any(|o| o.tag == field.tag)
+                    }
+                    MessageEntry::Field(_) => false,
+                })
+            })
+            .unwrap_or(false)
+}
+
+/// Checks that the value differs from the proto3 default of its type.
+/// Returns `None` for messages, which have no default value.
+fn is_not_default(
+    field_type: &package::Type,
+    value: &Arc<ast::Expression>,
+) -> Option<ast::Expression> {
+    let not_empty = |length: ast::Expression| {
+        ast::BinaryOperator::GreaterThan.apply(length.into(), Arc::new(0.into()))
+    };
+    let not_equal = |default_value: ast::Expression| {
+        ast::BinaryOperator::StrictNotEqual.apply(Arc::clone(value), default_value.into())
+    };
+    match field_type {
+        package::Type::Message(_) => None,
+        package::Type::Repeated(_) | package::Type::Bytes => Some(not_empty(value.prop("length"))),
+        package::Type::Map(_, _) => Some(not_empty(
+            ast::Expression::from("Object")
+                .into_prop("keys")
+                .into_call(vec![Arc::clone(value)])
+                .into_prop("length"),
+        )),
+        package::Type::Bool => Some(not_equal(ast::Expression::False)),
+        package::Type::String => Some(not_equal(ast::StringLiteral::from("").into())),
+        t if t.long_wire_type().is_some() => Some(
+            ast::BinaryOperator::StrictNotEqual.apply(
+                ast::Expression::from("String")
+                    .into_call(vec![Arc::clone(value)])
+                    .into(),
+                Arc::new(ast::StringLiteral::from("0").into()),
+            ),
+        ),
+        _ => Some(not_equal(0.into())),
+    }
+}
+
+fn to_json_value(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    value_type: &package::Type,
+    value: Arc<ast::Expression>,
+) -> Arc<ast::Expression> {
+    match value_type {
+        package::Type::Enum(e_id) => enum_to_json(root, options, message_scope, file, *e_id, value),
+        package::Type::Message(m_id) => import_json_func(
+            root,
+            options,
+            message_scope,
+            file,
+            *m_id,
+            TO_JSON_FUNCTION_NAME,
+        )
+        .into_call(vec![value])
+        .into(),
+        package::Type::Repeated(_) => unreachable!(),
+        package::Type::Map(_, _) => unreachable!(),
+        package::Type::Bytes => ast::Expression::from("util")
+            .into_prop("base64")
+            .into_prop("encode")
+            .into_call(vec![
+                Arc::clone(&value),
+                Arc::new(0.into()),
+                Arc::new(value.prop("length")),
+            ])
+            .into(),
+        t if t.long_wire_type().is_some() => ast::Expression::from("String")
+            .into_call(vec![value])
+            .into(),
+        _ => value,
+    }
+}
+
+fn from_json_value(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    value_type: &package::Type,
+    value: Arc<ast::Expression>,
+) -> Arc<ast::Expression> {
+    match value_type {
+        package::Type::Enum(e_id) => {
+            enum_from_json(root, options, message_scope, file, *e_id, value)
+        }
+        package::Type::Message(m_id) => import_json_func(
+            root,
+            options,
+            message_scope,
+            file,
+            *m_id,
+            FROM_JSON_FUNCTION_NAME,
+        )
+        .into_call(vec![value])
+        .into(),
+        package::Type::Repeated(_) => unreachable!(),
+        package::Type::Map(_, _) => unreachable!(),
+        package::Type::Bytes => ast::Expression::from(BYTES_FROM_BASE64_FUNCTION_NAME)
+            .into_call(vec![value])
+            .into(),
+        package::Type::Bool => ast::Expression::from("Boolean")
+            .into_call(vec![value])
+            .into(),
+        package::Type::String => ast::Expression::from("String")
+            .into_call(vec![value])
+            .into(),
+        t if is_bigint(options, t) => ast::Expression::from("BigInt")
+            .into_call(vec![value])
+            .into(),
+        t if t.long_wire_type().is_some() => {
+            let long_expr: Arc<ast::Expression> =
+                Arc::new(ast::Expression::from("util").into_prop("Long"));
+            ast::Expression::conditional(
+                Arc::clone(&long_expr),
+                long_expr
+                    .method_call("fromValue", vec![Arc::clone(&value)])
+                    .into(),
+                ast::Expression::from("Number")
+                    .into_call(vec![value])
+                    .into(),
+            )
+            .into()
+        }
+        _ => ast::Expression::from("Number")
+            .into_call(vec![value])
+            .into(),
+    }
+}
+
+const BYTES_FROM_BASE64_FUNCTION_NAME: &str = "bytesFromBase64";
+
+fn bytes_from_base64_function() -> ast::FunctionDeclaration {
+    let str_id: Arc<ast::Identifier> = Arc::new("s".into());
+    let str_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&str_id).into());
+    let buf_id: Arc<ast::Identifier> = Arc::new("buf".into());
+    let base64_expr: Arc<ast::Expression> =
+        Arc::new(ast::Expression::from("util").into_prop("base64"));
+
+    let mut func = ast::FunctionDeclaration::new(BYTES_FROM_BASE64_FUNCTION_NAME);
+    func.add_param(ast::Parameter::new(&str_id, Type::String));
+    func.returns(Type::from_id("Uint8Array"));
+
+    let mut new_buffer = ast::NewExpression::new(Arc::new("Uint8Array".into()));
+    new_buffer.add_argument(
+        base64_expr
+            .method_call("length", vec![Arc::clone(&str_expr)])
+            .into(),
+    );
+    func.push_statement(
+        ast::VariableDeclarationList::declare_const(Arc::clone(&buf_id), new_buffer.into()).into(),
+    );
+    func.push_statement(
+        base64_expr
+            .method_call(
+                "decode",
+                vec![
+                    str_expr,
+                    Arc::new(Arc::clone(&buf_id).into()),
+                    Arc::new(0.into()),
+                ],
+            )
+            .into(),
+    );
+    func.push_statement(ast::Expression::from(buf_id).into_return_statement());
+    func
+}
+
+/// Whether the conversion of the field uses `util` of the runtime:
+/// bytes are base64 encoded with it and 64-bit integers that are not bigints are read with `util.Long`.
+/// Repeated fields and maps need it for their elements and values only.
+fn requires_util(options: &Options, field_type: &package::Type) -> bool {
+    match field_type {
+        package::Type::Repeated(t) => requires_util(options, t),
+        package::Type::Map(_, v) => requires_util(options, v),
+        package::Type::Bytes => true,
+        t => t.long_wire_type().is_some() && !is_bigint(options, t),
+    }
+}
+
+fn contains_bytes(field_type: &package::Type) -> bool {
+    match field_type {
+        package::Type::Bytes => true,
+        package::Type::Repeated(t) => contains_bytes(t),
+        package::Type::Map(_, v) => contains_bytes(v),
+        _ => false,
+    }
+}
+
+fn import_json_func(
+    root: &RootScope,
+    options: &Options,
+    message_scope: &ProtoScope,
+    file: &mut ast::File,
+    m_id: usize,
+    json_function_name: &str,
+) -> ast::Expression {
+    let message_id = message_scope.id().unwrap();
+    if let Some(name) = mapped_well_known_type(root, options, m_id) {
+        return ast::Expression::from(import_well_known(
+            root,
+            options,
+            file,
+            message_id,
+            "json",
+            TsPathComponent::Function(format!("{}{}", json_function_name, name).into()),
+            Some(format!("{}{}", json_function_name, m_id).into()),
+        ));
+    }
+    if root.is_cyclic_reference(message_id, m_id) {
+        return import_function_lazily(
+            root,
+            options,
+            file,
+            message_id,
+            m_id,
+            "json",
+            json_function_name,
+        );
+    }
+    let imported_name = import_declaration(
+        file,
+        &declaration_file_path(root, options, message_id, "json"),
+        &declaration_file_path(root, options, m_id, "json"),
+        TsPathComponent::Function(function_name(root, options, m_id, json_function_name)),
+        Some(format!("{}{}", json_function_name, m_id).into()),
+    );
+    ast::Expression::from(imported_name)
+}
+
+#[cfg(test)]
+mod test_default_values {
+    use super::super::test_utils::{compile_sources, render_file_at};
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "defaults.proto",
+        r#"
+        syntax = "proto3";
+        package defaults;
+        message Defaults {
+            string name = 1;
+            int64 id = 2;
+            repeated int32 nums = 3;
+            optional int32 count = 4;
+            oneof value {
+                bool flag = 5;
+            }
+        }
+        "#,
+    )];
+
+    #[test]
+    fn it_omits_default_values() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let json = render_file_at(&folder, &["defaults", "defaults", "Defaults", "json"]);
+        assert!(json.contains("if (message.name != null && message.name !== \"\") {"));
+        assert!(json.contains("if (message.id != null && String(message.id) !== \"0\") {"));
+        assert!(json.contains("if (message.nums != null && message.nums.length > 0) {"));
+        assert!(json.contains("if (message.count != null) {"));
+        assert!(json.contains("if (message.flag != null) {"));
+    }
+
+    #[test]
+    fn it_emits_default_values_when_asked() {
+        let options = Options {
+            emit_default_values: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let json = render_file_at(&folder, &["defaults", "defaults", "Defaults", "json"]);
+        assert!(json.contains("if (message.name != null) {"));
+        assert!(json.contains("if (message.id != null) {"));
+        assert!(json.contains("if (message.nums != null) {"));
+    }
+}
+
+#[cfg(test)]
+mod test_json_keys {
+    use super::super::{
+        options::Layout,
+        test_utils::{compile_sources, render_file_at, run_node_script},
+    };
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "shop/user.proto",
+        r#"
+        syntax = "proto3";
+        package shop;
+        message User {
+            string user_id = 1;
+            int32 zip_code = 2 [json_name = "zip"];
+            string name = 3;
+            repeated string tag_names = 4;
+            map<string, int32> item_counts = 5;
+            Status account_status = 6;
+        }
+        enum Status {
+            STATUS_UNKNOWN = 0;
+            STATUS_ACTIVE = 1;
+        }
+        "#,
+    )];
+
+    #[test]
+    fn it_writes_lower_camel_case_keys() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let json = render_file_at(&folder, &["shop", "user", "User", "json"]);
+        assert!(json.contains("    json.userId = message.user_id\n"));
+        assert!(json.contains("    json.zip = message.zip\n"));
+        assert!(json.contains("    json.name = message.name\n"));
+        assert!(!json.contains("json.user_id ="));
+    }
+
+    #[test]
+    fn it_reads_json_keys_and_proto_names() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let json = render_file_at(&folder, &["shop", "user", "User", "json"]);
+        assert!(json.contains(
+            "  if ((json.userId ?? json.user_id) != null) {\n    message.user_id = String(json.userId ?? json.user_id)\n"
+        ));
+        assert!(json.contains("    message.zip = Number(json.zip ?? json.zip_code)\n"));
+        assert!(json.contains("  if (json.name != null) {\n    message.name = String(json.name)\n"));
+        assert!(json.contains("      a.push(String((json.tagNames ?? json.tag_names)[i]))\n"));
+        assert!(json.contains("      o[k] = Number((json.itemCounts ?? json.item_counts)[k])\n"));
+    }
+
+    #[test]
+    fn it_imports_util_only_for_bytes_and_64_bit_values() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let json = render_file_at(&folder, &["shop", "user", "User", "json"]);
+        assert!(!json.contains("util"));
+
+        let sources = &[(
+            "shop/blob.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage Blob { repeated bytes parts = 1; }\nmessage Totals { map<int64, int64> totals = 1; }\nmessage Ids { map<int64, string> names = 1; }\n",
+        )];
+        let folder = compile_sources(sources, &Options::default());
+        let at = |message| render_file_at(&folder, &["shop", "blob", message, "json"]);
+        assert!(at("Blob").contains("import { util } from "));
+        assert!(at("Totals").contains("import { util } from "));
+        assert!(!at("Ids").contains("util"));
+    }
+
+    #[test]
+    fn it_parses_json_keys_and_proto_names() {
+        let options = Options {
+            layout: Layout::File,
+            emit_encode: false,
+            emit_decode: false,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        run_node_script(
+            "the parsing of JSON keys",
+            include_str!("json_keys_test.js"),
+            &[(
+                "PROTOS_TS_MODULE",
+                render_file_at(&folder, &["shop", "user"]),
+            )],
+        );
+    }
+}
@@ -0,0 +1,62 @@
+// Checks that `fromJSON` reads the lowerCamelCase JSON keys written by `toJSON`
+// as well as the original names of the fields, as the proto3 JSON mapping requires.
+// PROTOS_TS_MODULE is the generated single-file module of `shop.User`.
+const assert = require("assert")
+
+function loadModule(source) {
+  const code = source
+    .replace(/^import .*\n/gm, "")
+    .replace(/^export interface \w+ \{\n[^}]*\}\n/gm, "")
+    .replace(/^export enum (\w+) \{\n([^}]*)\}\n/gm, (_, name, members) => {
+      const values = members
+        .trim()
+        .split("\n")
+        .map((member) => member.match(/(\w+) = (-?\d+)/))
+        .map(([, key, value]) => `${name}[${name}["${key}"] = ${value}] = "${key}"`)
+      return [`const ${name} = {}`, ...values, ""].join("\n")
+    })
+    .replace(/^export /gm, "")
+    .replace(/^function (\w+)\((.*)\): .* \{$/gm, (_, name, params) => {
+      const names = params.split(", ").map((param) => param.replace(/\??: .*/, ""))
+      return `function ${name}(${names.join(", ")}) {`
+    })
+    .replace(/: any(\[\])? =/g, " =")
+  return new Function(code + "\nreturn { toJSONUser, fromJSONUser }")()
+}
+
+const { toJSONUser, fromJSONUser } = loadModule(process.env.PROTOS_TS_MODULE)
+
+const user = {
+  user_id: "u1",
+  zip: 12345,
+  name: "Ann",
+  tag_names: ["a", "b"],
+  item_counts: { x: 2 },
+  account_status: 1,
+}
+const json = toJSONUser(user)
+assert.deepStrictEqual(json, {
+  userId: "u1",
+  zip: 12345,
+  name: "Ann",
+  tagNames: ["a", "b"],
+  itemCounts: { x: 2 },
+  accountStatus: "STATUS_ACTIVE",
+})
+assert.deepStrictEqual(fromJSONUser(json), user)
+
+const protoNames = {
+  user_id: "u1",
+  zip_code: 12345,
+  name: "Ann",
+  tag_names: ["a", "b"],
+  item_counts: { x: 2 },
+  account_status: "STATUS_ACTIVE",
+}
+assert.deepStrictEqual(fromJSONUser(protoNames), user)
+// enum values unknown to the generated code are written and read as numbers
+const unknownStatus = toJSONUser({ ...user, account_status: 7 })
+assert.strictEqual(unknownStatus.accountStatus, 7)
+assert.deepStrictEqual(fromJSONUser(unknownStatus), { ...user, account_status: 7 })
+// the JSON key wins when both names are present
+assert.strictEqual(fromJSONUser({ userId: "a", user_id: "b" }).user_id, "a")
@@ -0,0 +1,630 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::proto::{
+    error::ProtoError,
+    proto_scope::{file::FileScope, root_scope::RootScope},
+    protopath::PathComponent,
+};
+
+use super::{
+    ast,
+    constants::{GUARD_FUNCTION_NAME, INDEX_FILE_NAME},
+    ensure_import::{ensure_import, ensure_namespace_import},
+    file_case::file_case_name,
+    file_name_to_folder_name::file_name_to_folder_name,
+    get_relative_import::{get_relative_import, get_relative_import_string},
+    message_name_to_encode_type_name::message_name_to_encode_type_name,
+    options::{Layout, Options},
+    renames::declaration_rename,
+    safe_identifier::safe_identifier,
+    ts_path::{TsPath, TsPathComponent},
+};
+
+/// Path of the file that contains the `file_name` part
+/// ("types", "encode", "decode", ...) of the declaration.
+///
+/// In the folder layout it is a file in the folder of the message
+/// (or the enum's own file), in the file layout it is the file
+/// generated for the whole proto file, in the index layout it is `index.ts`.
+pub(super) fn declaration_file_path(
+    root: &RootScope,
+    options: &Options,
+    decl_id: usize,
+    file_name: &str,
+) -> TsPath {
+    let proto_path = root.get_declaration_path(decl_id).unwrap();
+    match options.layout {
+        Layout::Folder => {
+            let is_enum = matches!(proto_path.path.last(), Some(PathComponent::Enum(_)));
+            let mut res = TsPath::from(proto_path).with_file_case(options.file_case);
+            if !is_enum {
+                res.push_file(file_name);
+            }
+            res
+        }
+        Layout::File => {
+            let mut res = TsPath::default();
+            for component in proto_path.path.iter() {
+                match component {
+                    PathComponent::Package(name) => {
+                        res.push(TsPathComponent::Folder(file_case_name(
+                            options.file_case,
+                            name,
+                        )));
+                    }
+                    PathComponent::File(name) => {
+                        res.push(TsPathComponent::File(file_case_name(
+                            options.file_case,
+                            &file_name_to_folder_name(name),
+                        )));
+                        break;
+                    }
+                    PathComponent::Message(_) => unreachable!(),
+                    PathComponent::Enum(_) => unreachable!(),
+                }
+            }
+            res
+        }
+        Layout::Index => {
+            let mut res = TsPath::default();
+            res.push_file(INDEX_FILE_NAME);
+            res
+        }
+    }
+}
+
+/// Name the message interface or the enum is exported under.
+///
+/// Nested declarations share a single file in the file layout,
+/// so their names are prefixed with names of the parent messages: `Parent_Child`.
+/// In the index layout the name is qualified with the package and the parent messages,
+/// `shop.Order.Line`, and `files_to_index` declares it inside of the `shop.Order` namespace.
+/// A rename of the declaration replaces the name as a whole,
+/// except for the namespaces of the index layout.
+///
+/// Proto names that are reserved words or would shadow the identifiers
+/// the generated code refers to (`Writer`, `Object`, ...) are suffixed with `_`.
+pub(super) fn declaration_name(root: &RootScope, options: &Options, decl_id: usize) -> Arc<str> {
+    let rename = declaration_rename(root, options, decl_id);
+    match options.layout {
+        Layout::Folder => {
+            rename.unwrap_or_else(|| safe_identifier(&root.get_declaration_name(decl_id).unwrap()))
+        }
+        Layout::File => rename.unwrap_or_else(|| {
+            let proto_path = root.get_declaration_path(decl_id).unwrap();
+            let name = proto_path
+                .path
+                .iter()
+                .filter_map(|component| match component {
+                    PathComponent::Message(name) | PathComponent::Enum(name) => {
+                        Some(name.to_string())
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("_");
+            safe_identifier(&name)
+        }),
+        Layout::Index => {
+            let proto_path = root.get_declaration_path(decl_id).unwrap();
+            let mut names = proto_path
+                .path
+                .iter()
+                .filter_map(|component| match component {
+                    PathComponent::File(_) => None,
+                    PathComponent::Package(name) => Some(
+                        name.split('.')
+                            .map(|part| safe_identifier(part).to_string())
+                            .collect::<Vec<_>>()
+                            .join(".")
+                            .into(),
+                    ),
+                    PathComponent::Message(name) | PathComponent::Enum(name) => {
+                        Some(safe_identifier(name))
+                    }
+                })
+                .collect::<Vec<_>>();
+            if let Some(name) = rename {
+                names.pop();
+                names.push(name);
+            }
+            names.join(".").into()
+        }
+    }
+}
+
+/// Checks that the declarations of every proto file keep unique names in the file layout,
+/// a nested `Parent.Child` and a top-level `Parent_Child` are both `Parent_Child`.
+/// Collisions of renamed declarations are reported by `validate_renames`.
+pub(super) fn validate_declaration_names(
+    root: &RootScope,
+    options: &Options,
+) -> Result<(), ProtoError> {
+    if options.layout != Layout::File {
+        return Ok(());
+    }
+    let mut decl_ids = root.types.keys().copied().collect::<Vec<_>>();
+    decl_ids.sort_unstable();
+    let mut errors = Vec::new();
+    // full proto names by the path of the file and the exported name
+    let mut exports: HashMap<(String, Arc<str>), Arc<str>> = HashMap::new();
+    for decl_id in decl_ids {
+        let full_name = root.get_full_name(decl_id).unwrap();
+        if options.renames.contains_key(&*full_name) {
+            continue;
+        }
+        let file_path = root
+            .get_declaration_path(decl_id)
+            .unwrap()
+            .path
+            .iter()
+            .take_while(|c| matches!(c, PathComponent::Package(_) | PathComponent::File(_)))
+            .map(String::from)
+            .collect::<Vec<_>>()
+            .join("/");
+        let name = declaration_name(root, options, decl_id);
+        match exports.get(&(file_path.clone(), Arc::clone(&name))) {
+            Some(first) => errors.push(ProtoError::DeclarationNameCollision {
+                file: file_path,
+                first: first.to_string(),
+                second: full_name.to_string(),
+                name: name.to_string(),
+            }),
+            None => {
+                exports.insert((file_path, name), full_name);
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProtoError::from_errors(errors))
+    }
+}
+
+/// Name of the `XEncodeInput` interface of the message.
+pub(super) fn encode_type_name(root: &RootScope, options: &Options, decl_id: usize) -> Arc<str> {
+    message_name_to_encode_type_name(&declaration_name(root, options, decl_id)).into()
+}
+
+/// Name of the message function (`encode`, `decode`, ...).
+///
+/// In the folder layout every message has its own files
+/// so the function name is used as is, in the file layout it is suffixed
+/// with the message name: `encodeUser`, in the index layout it is a member
+/// of the message namespace: `auth.User.encode`.
+pub(super) fn function_name(
+    root: &RootScope,
+    options: &Options,
+    decl_id: usize,
+    function_name: &str,
+) -> Arc<str> {
+    match options.layout {
+        Layout::Folder => function_name.into(),
+        Layout::File => format!(
+            "{}{}",
+            function_name,
+            declaration_name(root, options, decl_id)
+        )
+        .into(),
+        Layout::Index => format!(
+            "{}.{}",
+            declaration_name(root, options, decl_id),
+            function_name
+        )
+        .into(),
+    }
+}
+
+/// Name of the type guard of the message: `isUser`,
+/// in the index layout a member of the message namespace: `auth.User.is`.
+pub(super) fn guard_function_name(root: &RootScope, options: &Options, decl_id: usize) -> Arc<str> {
+    match options.layout {
+        Layout::Folder => format!(
+            "{}{}",
+            GUARD_FUNCTION_NAME,
+            declaration_name(root, options, decl_id)
+        )
+        .into(),
+        Layout::File | Layout::Index => function_name(root, options, decl_id, GUARD_FUNCTION_NAME),
+    }
+}
+
+/// Path of the file with the client of the service `service_name` declared in `file_scope`.
+///
+/// In the folder layout it is a file next to the message folders of the proto file,
+/// in the file layout it is the file generated for the proto file,
+/// in the index layout it is `index.ts`.
+pub(super) fn service_file_path(
+    options: &Options,
+    file_scope: &FileScope,
+    service_name: &str,
+) -> TsPath {
+    let mut res = TsPath::default();
+    if options.layout == Layout::Index {
+        res.push_file(INDEX_FILE_NAME);
+        return res;
+    }
+    for package in file_scope
+        .package
+        .split('.')
+        .filter(|name| !name.is_empty())
+    {
+        res.push(TsPathComponent::Folder(file_case_name(
+            options.file_case,
+            package,
+        )));
+    }
+    let file_name = file_case_name(
+        options.file_case,
+        &file_name_to_folder_name(&file_scope.name),
+    );
+    match options.layout {
+        Layout::Folder => {
+            res.push(TsPathComponent::Folder(file_name));
+            res.push_file(&file_case_name(options.file_case, service_name));
+        }
+        Layout::File | Layout::Index => res.push(TsPathComponent::File(file_name)),
+    }
+    res
+}
+
+/// Name of the client class of the service: `GreeterClient`,
+/// qualified with the package in the index layout: `shop.GreeterClient`.
+pub(super) fn service_client_name(
+    options: &Options,
+    file_scope: &FileScope,
+    service_name: &str,
+) -> Arc<str> {
+    qualified_service_name(options, file_scope, format!("{}Client", service_name))
+}
+
+/// Name of the interface with the methods of the client class of the service: `IGreeterClient`,
+/// qualified with the package in the index layout: `shop.IGreeterClient`.
+pub(super) fn service_interface_name(
+    options: &Options,
+    file_scope: &FileScope,
+    service_name: &str,
+) -> Arc<str> {
+    qualified_service_name(options, file_scope, format!("I{}Client", service_name))
+}
+
+/// Name of the interface of the `@grpc/grpc-js` handlers of the service: `GreeterServer`,
+/// qualified with the package in the index layout: `shop.GreeterServer`.
+pub(super) fn service_server_name(
+    options: &Options,
+    file_scope: &FileScope,
+    service_name: &str,
+) -> Arc<str> {
+    qualified_service_name(options, file_scope, format!("{}Server", service_name))
+}
+
+fn qualified_service_name(options: &Options, file_scope: &FileScope, name: String) -> Arc<str> {
+    match options.layout {
+        Layout::Index if !file_scope.package.is_empty() => {
+            format!("{}.{}", file_scope.package, name).into()
+        }
+        _ => name.into(),
+    }
+}
+
+/// Makes `declaration` exported from `target_file` available inside of `file`,
+/// located at `current_file`, and returns the local identifier for it.
+///
+/// No import is added when both paths point to the same file.
+/// Interfaces are imported as bindings used only as types.
+pub(super) fn import_declaration(
+    file: &mut ast::File,
+    current_file: &TsPath,
+    target_file: &TsPath,
+    declaration: TsPathComponent,
+    alias: Option<Arc<str>>,
+) -> Arc<ast::Identifier> {
+    let name: Arc<str> = String::from(&declaration).into();
+    let is_type_only = matches!(declaration, TsPathComponent::Interface(_));
+    let mut target_path = target_file.clone();
+    target_path.push(declaration);
+    let alias = match alias {
+        Some(alias) => alias,
+        None => {
+            if let Some(mut import) = get_relative_import(current_file, &target_path) {
+                for specifier in import.import_clause.named_bindings.iter_mut().flatten() {
+                    specifier.is_type_only = is_type_only;
+                }
+                ensure_import(file, import);
+            }
+            return Arc::new(ast::Identifier { text: name });
+        }
+    };
+    match get_relative_import_string(current_file, &target_path) {
+        Some(import_string) => {
+            let imported_name = Arc::new(ast::Identifier { text: alias });
+            ensure_import(
+                file,
+                ast::ImportDeclaration::import(
+                    vec![ast::ImportSpecifier {
+                        is_type_only,
+                        ..ast::ImportSpecifier::new_full(
+                            Arc::clone(&imported_name),
+                            Some(Arc::new(ast::Identifier { text: name })),
+                        )
+                    }],
+                    import_string.into(),
+                ),
+            );
+            imported_name
+        }
+        None => Arc::new(ast::Identifier { text: name }),
+    }
+}
+
+/// Same as `import_declaration` without an alias, but the import is `import type`:
+/// the declaration is used only as a type and the import is erased from JavaScript.
+pub(super) fn import_type_declaration(
+    file: &mut ast::File,
+    current_file: &TsPath,
+    target_file: &TsPath,
+    declaration: TsPathComponent,
+) -> Arc<ast::Identifier> {
+    let name: Arc<str> = String::from(&declaration).into();
+    let mut target_path = target_file.clone();
+    target_path.push(declaration);
+    if let Some(mut import) = get_relative_import(current_file, &target_path) {
+        import.import_clause.is_type_only = true;
+        ensure_import(file, import);
+    }
+    Arc::new(ast::Identifier { text: name })
+}
+
+/// Imports the function ("encode", "decode", ...) of the message `message_id`
+/// into `file` of the message `current_id`, when the messages reference each other in a cycle.
+///
+/// The module is imported as a namespace and the function is looked up when it is called
+/// (`m2.encode`), so the result does not depend on the order the modules of the cycle
+/// are evaluated in.
+pub(super) fn import_function_lazily(
+    root: &RootScope,
+    options: &Options,
+    file: &mut ast::File,
+    current_id: usize,
+    message_id: usize,
+    file_name: &str,
+    message_function_name: &str,
+) -> ast::Expression {
+    let function = function_name(root, options, message_id, message_function_name);
+    import_named_function_lazily(
+        root, options, file, current_id, message_id, file_name, function,
+    )
+}
+
+/// Same as `import_function_lazily` for a function whose name is already resolved.
+pub(super) fn import_named_function_lazily(
+    root: &RootScope,
+    options: &Options,
+    file: &mut ast::File,
+    current_id: usize,
+    message_id: usize,
+    file_name: &str,
+    function: Arc<str>,
+) -> ast::Expression {
+    let current_file = declaration_file_path(root, options, current_id, file_name);
+    let mut target_path = declaration_file_path(root, options, message_id, file_name);
+    target_path.push(TsPathComponent::Function(Arc::clone(&function)));
+    match get_relative_import_string(&current_file, &target_path) {
+        Some(import_string) => {
+            let namespace = ensure_namespace_import(
+                file,
+                &import_string,
+                Arc::new(format!("m{}", message_id).into()),
+            );
+            ast::Expression::from(namespace).into_prop(&function)
+        }
+        None => ast::Expression::from(Arc::new(ast::Identifier { text: function })),
+    }
+}
+
+#[cfg(test)]
+mod test_import_declaration {
+    use super::*;
+
+    fn file_path(components: &[&str]) -> TsPath {
+        let mut res = TsPath::default();
+        for (i, component) in components.iter().enumerate() {
+            if i + 1 == components.len() {
+                res.push_file(component);
+            } else {
+                res.push(TsPathComponent::Folder((*component).into()));
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn it_uses_local_name_in_the_same_file() {
+        let mut file = ast::File::new("types".into());
+        let path = file_path(&["common", "types"]);
+        let id = import_declaration(
+            &mut file,
+            &path,
+            &path,
+            TsPathComponent::Function("encodePoint".into()),
+            Some("e2".into()),
+        );
+        assert_eq!(&*id.text, "encodePoint");
+        assert!(file.ast.statements.is_empty());
+    }
+
+    #[test]
+    fn it_imports_from_another_file() {
+        let mut file = ast::File::new("main".into());
+        let id = import_declaration(
+            &mut file,
+            &file_path(&["app", "main"]),
+            &file_path(&["common", "types"]),
+            TsPathComponent::Function("encodePoint".into()),
+            Some("e2".into()),
+        );
+        assert_eq!(&*id.text, "e2");
+        assert_eq!(
+            String::from(&file),
+            "import { encodePoint as e2 } from \"../common/types\"\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_declaration_names {
+    use super::super::renames::Rename;
+    use super::*;
+    use crate::proto::package::read_root_scope_from_sources;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "shop/order.proto",
+        "syntax = \"proto3\";\npackage shop;\nmessage Order { message Line { string sku = 1; } }\nmessage Order_Line { string sku = 1; }\n",
+    )];
+
+    #[test]
+    fn it_reports_nested_declarations_named_as_top_level_ones() {
+        let root = read_root_scope_from_sources(SOURCES).unwrap();
+        let options = Options {
+            layout: Layout::File,
+            ..Options::default()
+        };
+        let error = validate_declaration_names(&root, &options).unwrap_err();
+        assert_eq!(error.code(), "declaration_name_collision");
+        assert_eq!(
+            error.to_string(),
+            "shop.Order.Line and shop.Order_Line are both exported as \"Order_Line\" from shop/order.proto, rename one of them or choose another layout"
+        );
+
+        let renamed = Options {
+            renames: Arc::new(HashMap::from([(
+                "shop.Order_Line".to_string(),
+                Rename::from("OrderLine"),
+            )])),
+            ..options
+        };
+        assert!(validate_declaration_names(&root, &renamed).is_ok());
+        assert!(validate_declaration_names(&root, &Options::default()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod test_cyclic_imports {
+    use super::super::{
+        options::Runtime,
+        test_utils::{compile_sources, render_file_at, run_node_script},
+    };
+    use super::*;
+    use crate::proto::package::read_root_scope_from_sources;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "a.proto",
+        "syntax = \"proto3\";\npackage pkg;\nmessage A { B b = 1; }\nmessage B { repeated A a = 1; C c = 2; }\nmessage C { C next = 1; }\n",
+    )];
+
+    #[test]
+    fn it_imports_messages_of_a_cycle_lazily() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let at = |path: &[&str]| render_file_at(&folder, path);
+
+        assert!(at(&["pkg", "a", "A", "types"])
+            .starts_with("import type { B, BEncodeInput } from \"../B/types\"\n"));
+        let encode = at(&["pkg", "a", "A", "encode"]);
+        assert!(encode.contains("import * as m2 from \"../B/encode\"\n"));
+        assert!(encode.contains("    m2.encode(message.b, w.uint32(10).fork()).ldelim()\n"));
+        let decode = at(&["pkg", "a", "A", "decode"]);
+        assert!(decode.contains("import * as m2 from \"../B/decode\"\n"));
+        assert!(decode.contains("message.b = m2.decode(r, r.uint32())"));
+        let json = at(&["pkg", "a", "A", "json"]);
+        assert!(json.contains("import * as m2 from \"../B/json\"\n"));
+        assert!(json.contains("json.b = m2.toJSON(message.b)"));
+        assert!(json.contains("message.b = m2.fromJSON(json.b)"));
+
+        // C is not a part of the cycle
+        let b_types = at(&["pkg", "a", "B", "types"]);
+        assert!(b_types.contains("import type { A, AEncodeInput } from \"../A/types\"\n"));
+        assert!(b_types.contains("import { C, CEncodeInput } from \"../C/types\"\n"));
+        assert!(at(&["pkg", "a", "B", "encode"])
+            .contains("import { encode as e3 } from \"../C/encode\"\n"));
+    }
+
+    #[test]
+    fn it_rejects_files_importing_each_other() {
+        let sources = [
+            (
+                "a.proto",
+                "syntax = \"proto3\";\npackage pkg;\nimport \"b.proto\";\nmessage A { B b = 1; }\n",
+            ),
+            (
+                "b.proto",
+                "syntax = \"proto3\";\npackage pkg;\nimport \"a.proto\";\nmessage B { A a = 1; }\n",
+            ),
+        ];
+        let error = read_root_scope_from_sources(&sources).unwrap_err();
+        assert_eq!(error.code(), "import_cycle");
+    }
+
+    const RECURSIVE_SOURCES: &[(&str, &str)] = &[(
+        "pkg/tree.proto",
+        r#"
+        syntax = "proto3";
+        package pkg;
+        message TreeNode {
+            string name = 1;
+            repeated TreeNode children = 2;
+        }
+        message A { B b = 1; int32 n = 2; }
+        message B { A a = 1; }
+        "#,
+    )];
+
+    #[test]
+    fn it_compiles_self_referential_messages() {
+        let folder = compile_sources(RECURSIVE_SOURCES, &Options::default());
+        let at = |path: &[&str]| render_file_at(&folder, path);
+        let types = at(&["pkg", "tree", "TreeNode", "types"]);
+        assert!(types.starts_with("export interface TreeNodeEncodeInput {\n"));
+        assert!(types.contains("  children?: (TreeNodeEncodeInput[]) | null\n"));
+        assert!(types.contains("  children: TreeNode[]\n"));
+        assert!(at(&["pkg", "tree", "TreeNode", "encode"])
+            .contains("      encode(message.children[i], w.uint32(18).fork()).ldelim()\n"));
+        assert!(at(&["pkg", "tree", "TreeNode", "decode"])
+            .contains("        message.children.push(decode(r, r.uint32()))\n"));
+
+        for layout in [Layout::File, Layout::Index] {
+            let options = Options {
+                layout,
+                emit_guards: true,
+                emit_equals: true,
+                ..Options::default()
+            };
+            compile_sources(RECURSIVE_SOURCES, &options);
+        }
+    }
+
+    #[test]
+    fn it_encodes_and_decodes_recursive_messages() {
+        let options = Options {
+            layout: Layout::File,
+            runtime: Runtime::Standalone,
+            emit_json: false,
+            ..Options::default()
+        };
+        let folder = compile_sources(RECURSIVE_SOURCES, &options);
+        run_node_script(
+            "the round trip of recursive messages",
+            concat!(
+                include_str!("load_runtime.js"),
+                include_str!("recursive_messages_test.js")
+            ),
+            &[
+                ("PROTOS_TS_RUNTIME", render_file_at(&folder, &["runtime"])),
+                (
+                    "PROTOS_TS_MODULE",
+                    render_file_at(&folder, &["pkg", "tree"]),
+                ),
+            ],
+        );
+    }
+}
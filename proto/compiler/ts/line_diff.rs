@@ -0,0 +1,231 @@
+/// Lines of context kept around the changed lines of a hunk.
+const CONTEXT_LINES: usize = 3;
+
+/// Longest middles of the two contents, after the common first and last lines,
+/// compared line by line. Larger ones are shown as removed and added whole.
+const MAX_COMPARED_LINES: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Keep,
+    Remove,
+    Add,
+}
+
+/// Unified diff of the lines of `old` and `new` with the path of the file in the headers,
+/// `git diff` style. Empty when the lines are the same.
+pub(super) fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+    let edits = edits(&old_lines, &new_lines);
+    if edits.iter().all(|&edit| edit == Edit::Keep) {
+        return String::new();
+    }
+    let mut res = format!("--- a/{}\n+++ b/{}\n", path, path);
+    // line indices of both contents before every edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_index, mut new_index) = (0, 0);
+    for edit in &edits {
+        positions.push((old_index, new_index));
+        match edit {
+            Edit::Keep => {
+                old_index += 1;
+                new_index += 1;
+            }
+            Edit::Remove => old_index += 1,
+            Edit::Add => new_index += 1,
+        }
+    }
+    positions.push((old_index, new_index));
+
+    for (start, end) in hunks(&edits) {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        res.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for (i, edit) in edits[start..end].iter().enumerate() {
+            let (old_index, new_index) = positions[start + i];
+            match edit {
+                Edit::Keep => res.push_str(&format!(" {}\n", old_lines[old_index])),
+                Edit::Remove => res.push_str(&format!("-{}\n", old_lines[old_index])),
+                Edit::Add => res.push_str(&format!("+{}\n", new_lines[new_index])),
+            }
+        }
+    }
+    res
+}
+
+/// `start,count` of a hunk, 1-based. An empty range starts at the line before it.
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// Ranges of `edits` shown as hunks: the changed lines with their context,
+/// hunks whose contexts overlap are joined.
+fn hunks(edits: &[Edit]) -> Vec<(usize, usize)> {
+    let mut res: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in edits
+        .iter()
+        .enumerate()
+        .filter(|(_, &edit)| edit != Edit::Keep)
+    {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + 1 + CONTEXT_LINES).min(edits.len());
+        match res.last_mut() {
+            Some(last) if last.1 >= start => last.1 = end,
+            _ => res.push((start, end)),
+        }
+    }
+    res
+}
+
+/// Edits turning `old` into `new`, keeping the longest common subsequence of lines.
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut res = vec![Edit::Keep; prefix];
+    if old_middle.len() * new_middle.len() > MAX_COMPARED_LINES {
+        res.extend(std::iter::repeat_n(Edit::Remove, old_middle.len()));
+        res.extend(std::iter::repeat_n(Edit::Add, new_middle.len()));
+    } else {
+        res.extend(common_subsequence_edits(old_middle, new_middle));
+    }
+    res.extend(std::iter::repeat_n(Edit::Keep, suffix));
+    res
+}
+
+fn common_subsequence_edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let width = new.len() + 1;
+    // lengths[i * width + j] is the length of the common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let mut res = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            res.push(Edit::Keep);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            res.push(Edit::Remove);
+            i += 1;
+        } else {
+            res.push(Edit::Add);
+            j += 1;
+        }
+    }
+    res.extend(std::iter::repeat_n(Edit::Remove, old.len() - i));
+    res.extend(std::iter::repeat_n(Edit::Add, new.len() - j));
+    res
+}
+
+#[cfg(test)]
+mod test_line_diff {
+    use super::*;
+
+    fn numbered_lines(range: std::ops::Range<usize>) -> String {
+        range.map(|i| format!("line {}\n", i)).collect()
+    }
+
+    #[test]
+    fn it_shows_changed_lines_with_their_context() {
+        let old = numbered_lines(1..11);
+        let new = old.replace("line 5\n", "line five\n");
+        assert_eq!(
+            unified_diff("pkg/a.ts", &old, &new),
+            [
+                "--- a/pkg/a.ts",
+                "+++ b/pkg/a.ts",
+                "@@ -2,7 +2,7 @@",
+                " line 2",
+                " line 3",
+                " line 4",
+                "-line 5",
+                "+line five",
+                " line 6",
+                " line 7",
+                " line 8",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_joins_hunks_with_overlapping_contexts() {
+        let old = numbered_lines(1..21);
+        let new = old
+            .replace("line 2\n", "")
+            .replace("line 7\n", "line 7\nline 7.5\n")
+            .replace("line 18\n", "line eighteen\n");
+        assert_eq!(
+            unified_diff("a.ts", &old, &new),
+            [
+                "--- a/a.ts",
+                "+++ b/a.ts",
+                "@@ -1,10 +1,10 @@",
+                " line 1",
+                "-line 2",
+                " line 3",
+                " line 4",
+                " line 5",
+                " line 6",
+                " line 7",
+                "+line 7.5",
+                " line 8",
+                " line 9",
+                " line 10",
+                "@@ -15,6 +15,6 @@",
+                " line 15",
+                " line 16",
+                " line 17",
+                "-line 18",
+                "+line eighteen",
+                " line 19",
+                " line 20",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_diffs_empty_contents() {
+        assert_eq!(unified_diff("a.ts", "a\nb\n", "a\nb\n"), "");
+        assert_eq!(
+            unified_diff("a.ts", "", "a\n"),
+            "--- a/a.ts\n+++ b/a.ts\n@@ -0,0 +1 @@\n+a\n"
+        );
+        assert_eq!(
+            unified_diff("a.ts", "a\nb\n", ""),
+            "--- a/a.ts\n+++ b/a.ts\n@@ -1,2 +0,0 @@\n-a\n-b\n"
+        );
+    }
+}
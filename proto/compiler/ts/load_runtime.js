@@ -0,0 +1,16 @@
+// Evaluates `Writer` and `Reader` of the rendered runtime.ts as plain JavaScript.
+// Prepended to the node scripts of the tests.
+function loadRuntime(source) {
+  const util = source.indexOf("export namespace util")
+  const code = source
+    .slice(0, util)
+    .concat(source.slice(source.indexOf("\n}\n", util) + 3))
+    .replace(/\b(private readonly|private|readonly) /g, "")
+    .replace(/export /g, "")
+    .replace(/\): [\w\[\], ]+ \{$/gm, ") {")
+    .replace(/(\w+): (bigint \| number \| string|Array<number\[\]>|number\[\]|number|boolean|string|Uint8Array|unknown)(?![\w\[])/g, "$1")
+  return new Function(code + "\nreturn { Writer, Reader }")()
+}
+
+const hex = (bytes) => Buffer.from(bytes).toString("hex")
+const bytes = (s) => Uint8Array.from(Buffer.from(s, "hex"))
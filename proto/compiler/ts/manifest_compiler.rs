@@ -0,0 +1,460 @@
+use std::{ops::Deref, sync::Arc};
+
+use crate::proto::proto_scope::{traits::ChildrenScopes, ProtoScope};
+
+use super::{
+    ast::{
+        self, ElementAccess, ExpressionChain, MethodCall, ObjectLiteralMember, Prop, StatementList,
+        Type,
+    },
+    constants::{COMPATIBILITY_FILE_NAME, MANIFEST_FILE_NAME},
+    layout::import_declaration,
+    ts_path::{TsPath, TsPathComponent},
+};
+
+const PACKAGE_MANIFEST_TYPE_NAME: &str = "PackageManifest";
+const MANIFEST_DRIFT_TYPE_NAME: &str = "ManifestDrift";
+const COMPARE_MANIFESTS_FUNCTION_NAME: &str = "compareManifests";
+
+/// Emits `manifest.ts` of a package with the field numbers of every message
+/// and the values of every enum declared in `file_scopes`,
+/// and an `isCompatibleWith` function comparing it with a remote manifest.
+pub(super) fn compile_manifest(
+    package_path: &[Arc<str>],
+    file_scopes: &[&ProtoScope],
+) -> ast::File {
+    let mut file = ast::File::new(MANIFEST_FILE_NAME.into());
+
+    let mut manifest_path = TsPath::default();
+    for name in package_path {
+        manifest_path.push(TsPathComponent::Folder(Arc::clone(name)));
+    }
+    manifest_path.push_file(MANIFEST_FILE_NAME);
+    let mut compatibility_path = TsPath::default();
+    compatibility_path.push_file(COMPATIBILITY_FILE_NAME);
+    let import_from_compatibility = |file: &mut ast::File, declaration: TsPathComponent| {
+        import_declaration(file, &manifest_path, &compatibility_path, declaration, None)
+    };
+    let manifest_type_id = import_from_compatibility(
+        &mut file,
+        TsPathComponent::Interface(PACKAGE_MANIFEST_TYPE_NAME.into()),
+    );
+    let drift_type_id = import_from_compatibility(
+        &mut file,
+        TsPathComponent::Interface(MANIFEST_DRIFT_TYPE_NAME.into()),
+    );
+    let compare_func_id = import_from_compatibility(
+        &mut file,
+        TsPathComponent::Function(COMPARE_MANIFESTS_FUNCTION_NAME.into()),
+    );
+
+    let mut messages = Vec::new();
+    let mut enums = Vec::new();
+    let mut source_paths = Vec::new();
+    for file_scope in file_scopes {
+        collect_declarations(package_path, file_scope, &mut messages, &mut enums);
+        if let ProtoScope::File(f) = file_scope {
+            source_paths.push(Arc::new(
+                ast::StringLiteral::from(Arc::clone(&f.source_path)).into(),
+            ));
+        }
+    }
+
+    let manifest_id: Arc<ast::Identifier> = Arc::new("manifest".into());
+    let package_name: Arc<str> = package_path.join(".").into();
+    file.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Arc::clone(&manifest_id),
+            Type::reference(Arc::clone(&manifest_type_id)).into(),
+            ast::Expression::ObjectLiteralExpression(vec![
+                ObjectLiteralMember::property(
+                    "package",
+                    ast::Expression::from(ast::StringLiteral::from(package_name)),
+                ),
+                ObjectLiteralMember::property(
+                    "files",
+                    ast::Expression::ArrayLiteralExpression(source_paths),
+                ),
+                ObjectLiteralMember::property("messages", numbers_record(messages)),
+                ObjectLiteralMember::property("enums", numbers_record(enums)),
+            ]),
+        )
+        .exported()
+        .into(),
+    );
+
+    let remote_id: Arc<ast::Identifier> = Arc::new("remote".into());
+    let mut is_compatible_func = ast::FunctionDeclaration::new_exported("isCompatibleWith");
+    is_compatible_func.add_param(ast::Parameter::new(
+        &remote_id,
+        Type::reference(Arc::clone(&manifest_type_id)),
+    ));
+    is_compatible_func.returns(Type::reference(drift_type_id));
+    is_compatible_func.push_statement(
+        ast::Expression::from(compare_func_id)
+            .into_call(vec![
+                Arc::new(manifest_id.into()),
+                Arc::new(remote_id.into()),
+            ])
+            .into_return_statement(),
+    );
+    file.push_statement(is_compatible_func.into());
+
+    file
+}
+
+/// Collects `(full name, numbers)` of messages and enums declared in the scope and its children.
+fn collect_declarations(
+    parent_path: &[Arc<str>],
+    scope: &ProtoScope,
+    messages: &mut Vec<(Arc<str>, Vec<i64>)>,
+    enums: &mut Vec<(Arc<str>, Vec<i64>)>,
+) {
+    for child in scope.children().iter() {
+        let mut path = parent_path.to_vec();
+        path.push(child.name());
+        let full_name: Arc<str> = path.join(".").into();
+        match child.deref() {
+            ProtoScope::Message(m) => {
+                let mut tags = m.get_fields().iter().map(|f| f.tag).collect::<Vec<_>>();
+                tags.sort_unstable();
+                messages.push((full_name, tags));
+                collect_declarations(&path, child, messages, enums);
+            }
+            ProtoScope::Enum(e) => {
+                let mut values = e.entries.iter().map(|e| e.value).collect::<Vec<_>>();
+                values.sort_unstable();
+                values.dedup();
+                enums.push((full_name, values));
+            }
+            ProtoScope::Root(_) => unreachable!(),
+            ProtoScope::Package(_) => unreachable!(),
+            ProtoScope::File(_) => unreachable!(),
+        }
+    }
+}
+
+fn numbers_record(entries: Vec<(Arc<str>, Vec<i64>)>) -> ast::Expression {
+    ast::Expression::ObjectLiteralExpression(
+        entries
+            .into_iter()
+            .map(|(name, numbers)| {
+                ObjectLiteralMember::property(
+                    name,
+                    ast::Expression::ArrayLiteralExpression(
+                        numbers
+                            .into_iter()
+                            .map(|n| Arc::new((n as f64).into()))
+                            .collect(),
+                    ),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Emits `compatibility.ts` shared by all package manifests.
+/// It declares the manifest types and the comparison of a local manifest with a remote one.
+pub(super) fn compile_compatibility_module() -> ast::File {
+    let mut file = ast::File::new(COMPATIBILITY_FILE_NAME.into());
+    let numbers_record_type =
+        || Type::Record(Type::String.into(), Type::array(Type::Number).into());
+
+    let mut manifest_interface =
+        ast::InterfaceDeclaration::new_exported(PACKAGE_MANIFEST_TYPE_NAME.into());
+    manifest_interface
+        .members
+        .push(ast::PropertySignature::new("package".into(), Type::String).into());
+    manifest_interface
+        .members
+        .push(ast::PropertySignature::new("files".into(), Type::array(Type::String)).into());
+    manifest_interface
+        .members
+        .push(ast::PropertySignature::new("messages".into(), numbers_record_type()).into());
+    manifest_interface
+        .members
+        .push(ast::PropertySignature::new("enums".into(), numbers_record_type()).into());
+    file.push_statement(manifest_interface.into());
+
+    let mut drift_interface =
+        ast::InterfaceDeclaration::new_exported(MANIFEST_DRIFT_TYPE_NAME.into());
+    drift_interface.members.push(
+        ast::PropertySignature::new("missingFields".into(), Type::array(Type::String)).into(),
+    );
+    drift_interface
+        .members
+        .push(ast::PropertySignature::new("unknownEnums".into(), Type::array(Type::String)).into());
+    file.push_statement(drift_interface.into());
+
+    let local_id: Arc<ast::Identifier> = Arc::new("local".into());
+    let remote_id: Arc<ast::Identifier> = Arc::new("remote".into());
+    let local_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&local_id).into());
+    let remote_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&remote_id).into());
+
+    let mut compare_func = ast::FunctionDeclaration::new_exported(COMPARE_MANIFESTS_FUNCTION_NAME);
+    compare_func.add_param(ast::Parameter::new(
+        &local_id,
+        Type::from_id(PACKAGE_MANIFEST_TYPE_NAME),
+    ));
+    compare_func.add_param(ast::Parameter::new(
+        &remote_id,
+        Type::from_id(PACKAGE_MANIFEST_TYPE_NAME),
+    ));
+    compare_func.returns(Type::from_id(MANIFEST_DRIFT_TYPE_NAME));
+    let unknown_numbers = |prop: &str| {
+        Arc::new(
+            ast::Expression::from(UNKNOWN_NUMBERS_FUNCTION_NAME).into_call(vec![
+                Arc::new(local_expr.prop(prop)),
+                Arc::new(remote_expr.prop(prop)),
+            ]),
+        )
+    };
+    compare_func.push_statement(
+        ast::Expression::ObjectLiteralExpression(vec![
+            ObjectLiteralMember::property("missingFields", unknown_numbers("messages")),
+            ObjectLiteralMember::property("unknownEnums", unknown_numbers("enums")),
+        ])
+        .into_return_statement(),
+    );
+    file.push_statement(compare_func.into());
+    file.push_statement(unknown_numbers_function(numbers_record_type).into());
+
+    file
+}
+
+const UNKNOWN_NUMBERS_FUNCTION_NAME: &str = "unknownNumbers";
+
+/// `unknownNumbers(local, remote)` returns `name.number` of every number
+/// listed in the `remote` record, but missing in the `local` one.
+fn unknown_numbers_function(numbers_record_type: impl Fn() -> Type) -> ast::FunctionDeclaration {
+    let local_id: Arc<ast::Identifier> = Arc::new("local".into());
+    let remote_id: Arc<ast::Identifier> = Arc::new("remote".into());
+    let res_id: Arc<ast::Identifier> = Arc::new("res".into());
+    let names_id: Arc<ast::Identifier> = Arc::new("names".into());
+    let name_id: Arc<ast::Identifier> = Arc::new("name".into());
+    let known_id: Arc<ast::Identifier> = Arc::new("known".into());
+    let numbers_id: Arc<ast::Identifier> = Arc::new("numbers".into());
+    let i_id: Arc<ast::Identifier> = Arc::new("i".into());
+    let j_id: Arc<ast::Identifier> = Arc::new("j".into());
+    let local_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&local_id).into());
+    let remote_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&remote_id).into());
+    let res_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&res_id).into());
+    let names_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&names_id).into());
+    let name_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&name_id).into());
+    let known_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&known_id).into());
+    let numbers_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&numbers_id).into());
+    let number_expr: Arc<ast::Expression> =
+        Arc::new(numbers_expr.element(Arc::new(Arc::clone(&j_id).into())));
+
+    let mut func = ast::FunctionDeclaration::new(UNKNOWN_NUMBERS_FUNCTION_NAME);
+    func.add_param(ast::Parameter::new(&local_id, numbers_record_type()));
+    func.add_param(ast::Parameter::new(&remote_id, numbers_record_type()));
+    func.returns(Type::array(Type::String));
+
+    func.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Arc::clone(&res_id),
+            Type::array(Type::String).into(),
+            ast::Expression::array(vec![]),
+        )
+        .into(),
+    );
+    func.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Arc::clone(&names_id),
+            ast::Expression::from("Object")
+                .into_prop("keys")
+                .into_call(vec![Arc::clone(&remote_expr)]),
+        )
+        .into(),
+    );
+
+    let mut names_loop = ast::ForStatement::for_each(Arc::clone(&i_id), Arc::clone(&names_expr));
+    names_loop.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Arc::clone(&name_id),
+            names_expr.element(Arc::new(i_id.into())),
+        )
+        .into(),
+    );
+    names_loop.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Arc::clone(&known_id),
+            Arc::new(local_expr.element(Arc::clone(&name_expr)))
+                .or(Arc::new(ast::Expression::array(vec![]))),
+        )
+        .into(),
+    );
+    names_loop.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Arc::clone(&numbers_id),
+            remote_expr.element(Arc::clone(&name_expr)),
+        )
+        .into(),
+    );
+
+    let mut numbers_loop =
+        ast::ForStatement::for_each(Arc::clone(&j_id), Arc::clone(&numbers_expr));
+    let mut push_unknown = ast::Block::new();
+    push_unknown.push_statement(
+        res_expr
+            .method_call(
+                "push",
+                vec![Arc::new(
+                    ast::BinaryOperator::Plus.apply(
+                        Arc::new(
+                            ast::BinaryOperator::Plus
+                                .apply(name_expr, Arc::new(ast::StringLiteral::from(".").into())),
+                        ),
+                        Arc::clone(&number_expr),
+                    ),
+                )],
+            )
+            .into(),
+    );
+    numbers_loop.push_statement(
+        ast::IfStatement {
+            expression: ast::BinaryOperator::LessThan
+                .apply(
+                    known_expr.method_call("indexOf", vec![number_expr]).into(),
+                    Arc::new(0.into()),
+                )
+                .into(),
+            then_statement: Arc::new(push_unknown.into()),
+            else_statement: None,
+        }
+        .into(),
+    );
+    names_loop.push_statement(numbers_loop.into());
+    func.push_statement(names_loop.into());
+
+    func.push_statement(ast::Expression::from(res_id).into_return_statement());
+    func
+}
+
+#[cfg(test)]
+mod test_manifest {
+    use super::super::{
+        options::Options,
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+    use crate::proto::package::read_root_scope_from_sources;
+
+    const V1: &[(&str, &str)] = &[(
+        "shop/shop.proto",
+        r#"
+        syntax = "proto3";
+        package shop;
+        enum Status { UNKNOWN = 0; PAID = 1; }
+        message Order {
+            string id = 1;
+            Status status = 2;
+            message Item { string sku = 1; }
+        }
+        "#,
+    )];
+
+    const V2: &[(&str, &str)] = &[(
+        "shop/shop.proto",
+        r#"
+        syntax = "proto3";
+        package shop;
+        enum Status { UNKNOWN = 0; PAID = 1; SHIPPED = 2; }
+        message Order {
+            string id = 1;
+            Status status = 2;
+            oneof discount { int32 percent = 3; }
+            message Item { string sku = 1; int32 count = 2; }
+        }
+        message Refund { string order_id = 1; }
+        "#,
+    )];
+
+    type Numbers = Vec<(Arc<str>, Vec<i64>)>;
+
+    fn collect(sources: &[(&str, &str)]) -> (Numbers, Numbers) {
+        let root = read_root_scope_from_sources(sources).unwrap();
+        let package = root.children[0].clone();
+        let file_scope = package.children()[0].clone();
+        let mut messages = Vec::new();
+        let mut enums = Vec::new();
+        collect_declarations(&[package.name()], &file_scope, &mut messages, &mut enums);
+        (messages, enums)
+    }
+
+    /// Mirrors `unknownNumbers` of the generated `compatibility.ts`.
+    fn unknown_numbers(local: &Numbers, remote: &Numbers) -> Vec<String> {
+        let mut res = Vec::new();
+        for (name, numbers) in remote {
+            let known = local
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, known)| known.clone())
+                .unwrap_or_default();
+            for number in numbers {
+                if !known.contains(number) {
+                    res.push(format!("{}.{}", name, number));
+                }
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn it_reports_drift_between_schema_versions() {
+        let (local_messages, local_enums) = collect(V1);
+        let (remote_messages, remote_enums) = collect(V2);
+        assert_eq!(
+            unknown_numbers(&local_messages, &remote_messages),
+            ["shop.Order.3", "shop.Order.Item.2", "shop.Refund.1"]
+        );
+        assert_eq!(
+            unknown_numbers(&local_enums, &remote_enums),
+            ["shop.Status.2"]
+        );
+        assert!(unknown_numbers(&remote_messages, &local_messages).is_empty());
+        assert!(unknown_numbers(&remote_enums, &local_enums).is_empty());
+    }
+
+    #[test]
+    fn it_renders_package_manifest() {
+        let options = Options {
+            emit_manifest: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(V2, &options);
+        assert_eq!(
+            render_file_at(&folder, &["shop", "manifest"]),
+            r#"import { ManifestDrift, PackageManifest, compareManifests } from "../compatibility"
+
+export const manifest: PackageManifest = {
+  package: "shop",
+  files: ["shop/shop.proto"],
+  messages: {
+    "shop.Order": [1, 2, 3],
+    "shop.Order.Item": [1, 2],
+    "shop.Refund": [1],
+  },
+  enums: {
+    "shop.Status": [0, 1, 2],
+  },
+}
+
+export function isCompatibleWith(remote: PackageManifest): ManifestDrift {
+  return compareManifests(manifest, remote)
+}
+"#
+        );
+        let compatibility = render_file_at(&folder, &["compatibility"]);
+        assert!(compatibility.contains(
+            "export function compareManifests(local: PackageManifest, remote: PackageManifest): ManifestDrift {"
+        ));
+    }
+
+    #[test]
+    fn it_skips_manifests_by_default() {
+        let folder = compile_sources(V1, &Options::default());
+        assert_eq!(folder.entries.len(), 1);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::renames::Rename;
+
+/// Options that change the shape of the generated TypeScript code.
+#[derive(Debug, Clone)]
+pub(crate) struct Options {
+    pub layout: Layout,
+    /// Whether `toJSON` keeps fields set to their default values.
+    /// By default they are omitted, as the proto3 JSON mapping recommends.
+    pub emit_default_values: bool,
+    /// Whether `types.ts` is generated.
+    pub emit_types: bool,
+    /// Whether `encode.ts` is generated.
+    pub emit_encode: bool,
+    /// Whether `decode.ts` is generated.
+    pub emit_decode: bool,
+    /// Whether `json.ts` is generated.
+    pub emit_json: bool,
+    /// Whether `is.ts` with the type guard of the message is generated.
+    pub emit_guards: bool,
+    /// Whether `equals.ts` with the field by field comparison of two messages is generated.
+    pub emit_equals: bool,
+    /// Whether `equals` tells missing fields from fields set to their defaults,
+    /// by default a missing field is equal to the default of its type.
+    pub equals_presence: bool,
+    /// Whether `enum` style enums are followed by the object mapping wire numbers to names,
+    /// the other styles always have it.
+    pub emit_enum_names: bool,
+    /// Whether the interfaces of decoded messages have a `hasName: boolean` property
+    /// for every field `name` with explicit presence, set by `decode` and `fromJSON`.
+    pub emit_has_fields: bool,
+    /// Whether `manifest.ts` is generated per package,
+    /// along with the shared `compatibility.ts` module.
+    pub emit_manifest: bool,
+    /// Whether the files of services also declare the interface of the `@grpc/grpc-js` handlers
+    /// a server implements, next to the client.
+    pub emit_grpc: bool,
+    /// Module the generated code reads and writes the wire format with.
+    pub runtime: Runtime,
+    /// How fields typed with well-known messages of `google.protobuf` are generated.
+    pub well_known_types: WellKnownTypes,
+    /// Replacement names by fully qualified proto names of messages, enums and fields.
+    pub renames: Arc<HashMap<String, Rename>>,
+    /// Extension of the files in relative import specifiers.
+    pub import_extension: ImportExtension,
+    /// How imports of bindings used only as types are written.
+    pub import_style: ImportStyle,
+    /// How proto enums are declared.
+    pub enum_style: EnumStyle,
+    /// Casing of the properties of fields in the interfaces and the functions of messages.
+    pub field_case: FieldCase,
+    /// Casing of the folders and the files named after packages, proto files, messages, enums and services.
+    pub file_case: FileCase,
+    /// Whether 64-bit integers are `util.Long` values of the runtime or native bigints.
+    pub int64_type: Int64Type,
+    /// Whether `decode` keeps the fields it does not know in the `$unknown` property of the message,
+    /// for `encode` to write them back after the known ones.
+    pub keep_unknown_fields: bool,
+    /// Whether the interfaces of decoded messages have `readonly` properties,
+    /// `ReadonlyArray`s for repeated fields and `Readonly<Record>`s for maps.
+    pub readonly_types: bool,
+    /// Whether only the types and the enums are generated, into `.d.ts` files.
+    /// Unions skip the objects of the wire numbers, since a declaration file has no values.
+    pub declarations_only: bool,
+    /// Whether `.ts` modules, `.d.ts` declarations of them or both are written.
+    pub emit: Emit,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            layout: Layout::Folder,
+            emit_default_values: false,
+            emit_types: true,
+            emit_encode: true,
+            emit_decode: true,
+            emit_json: true,
+            emit_guards: false,
+            emit_equals: false,
+            equals_presence: false,
+            emit_enum_names: false,
+            emit_has_fields: false,
+            emit_manifest: false,
+            emit_grpc: false,
+            runtime: Runtime::Protobufjs,
+            well_known_types: WellKnownTypes::Idiomatic,
+            renames: Arc::default(),
+            import_extension: ImportExtension::None,
+            import_style: ImportStyle::Plain,
+            enum_style: EnumStyle::Enum,
+            field_case: FieldCase::Preserve,
+            file_case: FileCase::Keep,
+            int64_type: Int64Type::Long,
+            keep_unknown_fields: false,
+            readonly_types: false,
+            declarations_only: false,
+            emit: Emit::Ts,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Folder per proto file and per message,
+    /// with `types.ts`, `encode.ts`, `decode.ts`, ... files inside.
+    Folder,
+    /// Single `.ts` file per proto file.
+    File,
+    /// Single `index.ts` for the whole tree, with a `namespace` per package and message:
+    /// `shop.Order` is the interface and `shop.Order.encode` is its function.
+    /// Manifests are not emitted.
+    Index,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runtime {
+    /// `Writer`, `Reader` and `util` are imported from `protobufjs/minimal`.
+    Protobufjs,
+    /// `runtime.ts` without dependencies is emitted into the root of the output
+    /// and the generated code imports `Writer`, `Reader` and `util` from it.
+    /// 64-bit integers are bigints, `--runtime embedded` on the command line.
+    Standalone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WellKnownTypes {
+    /// Well-known messages are generated and used as any other message.
+    Raw,
+    /// `Timestamp` is a `Date`, wrappers are their values or `undefined`,
+    /// `Struct`, `Value` and `ListValue` are plain JSON values, `Duration` is `{ seconds, nanos }`.
+    /// They are imported from `wkt.ts`, emitted into the root of the output.
+    Idiomatic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportExtension {
+    /// `import { User } from "./types"`, resolved by bundlers and `moduleResolution: node`.
+    None,
+    /// `import { User } from "./types.js"`, required by ES modules with `moduleResolution: node16`.
+    Js,
+    /// `import { User } from "./types.ts"`, for Deno and `allowImportingTsExtensions`.
+    Ts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportStyle {
+    /// `import { User, encodeUser } from "./User"`, types and values are imported alike.
+    Plain,
+    /// `import type { User } from "./User"` and `import { encodeUser } from "./User"`,
+    /// as `verbatimModuleSyntax` requires.
+    Type,
+    /// `import { type User, encodeUser } from "./User"`, `import type` when every binding is a type.
+    Inline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnumStyle {
+    /// `export enum Color { RED = 0, GREEN = 1 }`
+    Enum,
+    /// `export type Color = "RED" | "GREEN"`, values of fields are the names of the members.
+    /// The `Color` object maps the names to the wire numbers and `ColorNames` maps them back.
+    Union,
+    /// `export const Color = { RED: 0, GREEN: 1 } as const` with the `Color` type of its values,
+    /// values of fields are the wire numbers. `ColorNames` maps the numbers to the names.
+    ConstObject,
+}
+
+/// Casing of the TypeScript properties of fields, renames take precedence over it.
+/// Keys of the JSON representation do not depend on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldCase {
+    /// Names as they are declared, or their `json_name` options.
+    Preserve,
+    /// Names as they are declared, `json_name` options are ignored.
+    Proto,
+    /// `user_id` becomes `userId`, as `protoc` derives JSON names, unless the field has a `json_name` option.
+    CamelCase,
+    /// `userId` becomes `user_id`, `json_name` options are ignored.
+    SnakeCase,
+}
+
+/// Casing of the output paths named after proto names, `order_line.proto` with `message OrderLine`:
+/// the fixed names of the generated files (`types`, `encode`, `runtime`, ...) do not depend on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCase {
+    /// `order_line/OrderLine/types.ts`, names as they are declared.
+    Keep,
+    /// `order-line/order-line/types.ts`
+    Kebab,
+    /// `order_line/order_line/types.ts`
+    Snake,
+    /// `orderLine/orderLine/types.ts`
+    Camel,
+    /// `OrderLine/OrderLine/types.ts`
+    Pascal,
+}
+
+/// TypeScript type of `int64`, `uint64`, `sint64`, `fixed64` and `sfixed64` fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Int64Type {
+    /// `util.Long` when decoded, `util.Long | number` when encoded:
+    /// `Long` of long.js with protobufjs, a number without it and a bigint with the standalone runtime.
+    Long,
+    /// `bigint`, converted from and to the 64-bit integers of protobufjs by the generated code.
+    BigInt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emit {
+    /// `.ts` modules with the implementation.
+    Ts,
+    /// `.d.ts` files only: functions are declared by their signatures, constants by their types
+    /// and imports are `import type`, for a runtime that is published separately.
+    /// The shared `runtime.ts`, `wkt.ts` and `transport.ts` modules stay `.ts`.
+    Dts,
+    /// `.ts` modules with a `.d.ts` declaration file next to each of them.
+    Both,
+}
@@ -0,0 +1,64 @@
+// Checks the wire format of repeated scalar fields and of length-delimited messages
+// written and read by the generated code.
+// PROTOS_TS_RUNTIME is the source of runtime.ts and PROTOS_TS_MODULE is the generated
+// single-file module of `pk.Samples`, `loadRuntime`, `hex` and `bytes` come from load_runtime.js.
+// The expected bytes are the ones protobufjs writes for the same values.
+const assert = require("assert")
+
+function loadModule(source, runtime) {
+  const code = source
+    .replace(/^import .*\n/gm, "")
+    .replace(/^export interface \w+ \{\n[^}]*\}\n/gm, "")
+    .replace(/^export /gm, "")
+    .replace(/^function (\w+)\((.*)\): .* \{$/gm, (_, name, params) => {
+      const names = params.split(", ").map((param) => param.replace(/\??: .*/, ""))
+      return `function ${name}(${names.join(", ")}) {`
+    })
+    .replace(/: any\b/g, "")
+  return new Function(
+    "Writer",
+    "Reader",
+    code + "\nreturn { encodeSamples, decodeSamples, encodeDelimitedSamples, decodeDelimitedSamples }",
+  )(
+    runtime.Writer,
+    runtime.Reader,
+  )
+}
+
+const runtime = loadRuntime(process.env.PROTOS_TS_RUNTIME)
+const { encodeSamples, decodeSamples, encodeDelimitedSamples, decodeDelimitedSamples } = loadModule(
+  process.env.PROTOS_TS_MODULE,
+  runtime,
+)
+
+// [field, values, hex]: `values` of the field encode to `hex` and `hex` decodes to `values`.
+const FIELDS = [
+  ["ints", [1, 150, -1], "0a0d019601ffffffffffffffffff01"],
+  ["zigzags", [BigInt(-1), BigInt(1), BigInt(2 ** 53 - 1)], "120a0102feffffffffffff1f"],
+  ["fixeds", [1, 4294967295], "1a0801000000ffffffff"],
+  // [packed = false]
+  ["unpacked", [1, 2], "20012002"],
+  // strings are never packed
+  ["names", ["a", "b"], "2a01612a0162"],
+]
+
+for (const [field, values, encoded] of FIELDS) {
+  assert.strictEqual(hex(encodeSamples({ [field]: values }).finish()), encoded, `encode ${field}`)
+  assert.deepStrictEqual(decodeSamples(bytes(encoded))[field], values, `decode ${field}`)
+}
+
+// Readers accept both encodings of a field, whatever the writer would choose.
+assert.deepStrictEqual(decodeSamples(bytes("0801089601")).ints, [1, 150])
+assert.deepStrictEqual(decodeSamples(bytes("1d010000001d02000000")).fixeds, [1, 2])
+assert.deepStrictEqual(decodeSamples(bytes("22020102")).unpacked, [1, 2])
+// packed and unpacked records of the same field are concatenated
+assert.deepStrictEqual(decodeSamples(bytes("0a0201020803")).ints, [1, 2, 3])
+
+// Delimited messages are prefixed with their length, so several of them can share a stream.
+const stream = encodeDelimitedSamples({ ints: [1] })
+encodeDelimitedSamples({ unpacked: [1, 2] }, stream)
+assert.strictEqual(hex(stream.finish()), "030a01010420012002")
+const r = runtime.Reader.create(bytes("030a01010420012002"))
+assert.deepStrictEqual(decodeDelimitedSamples(r).ints, [1])
+assert.deepStrictEqual(decodeDelimitedSamples(r).unpacked, [1, 2])
+assert.strictEqual(r.pos, r.len)
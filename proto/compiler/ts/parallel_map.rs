@@ -0,0 +1,81 @@
+#[cfg(not(feature = "rayon"))]
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// Applies `f` to every item on the rayon thread pool.
+/// Results are returned in the order of `items`.
+#[cfg(feature = "rayon")]
+pub(crate) fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+    items.par_iter().map(&f).collect()
+}
+
+/// Applies `f` to every item on a pool of scoped threads.
+/// Results are returned in the order of `items`,
+/// so the output does not depend on the scheduling.
+#[cfg(not(feature = "rayon"))]
+pub(crate) fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads_count = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(items.len());
+    if threads_count <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next_index = AtomicUsize::new(0);
+    let mut indexed_results = thread::scope(|scope| {
+        let workers = (0..threads_count)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next_index.fetch_add(1, Ordering::Relaxed);
+                        match items.get(index) {
+                            Some(item) => results.push((index, f(item))),
+                            None => break,
+                        }
+                    }
+                    results
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    indexed_results.sort_by_key(|(index, _)| *index);
+    indexed_results.into_iter().map(|(_, r)| r).collect()
+}
+
+#[cfg(test)]
+mod test_parallel_map {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_order_of_items() {
+        let items = (0..1000).collect::<Vec<usize>>();
+        let squares = parallel_map(&items, |x| x * x);
+        assert_eq!(squares, items.iter().map(|x| x * x).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_handles_empty_input() {
+        let items: Vec<usize> = vec![];
+        assert!(parallel_map(&items, |x| *x).is_empty());
+    }
+}
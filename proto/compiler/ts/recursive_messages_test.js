@@ -0,0 +1,44 @@
+// Checks that messages referencing themselves or each other are encoded and decoded at any depth.
+// PROTOS_TS_RUNTIME is the source of runtime.ts and PROTOS_TS_MODULE is the generated
+// single-file module of `pkg.TreeNode`, referencing itself, and of `pkg.A` and `pkg.B`, referencing each other.
+const assert = require("assert")
+
+function loadModule(source, runtime) {
+  const code = source
+    .replace(/^import .*\n/gm, "")
+    .replace(/^export interface \w+ \{\n[^}]*\}\n/gm, "")
+    .replace(/^export /gm, "")
+    .replace(/^function (\w+)\((.*)\): .* \{$/gm, (_, name, params) => {
+      const names = params.split(", ").map((param) => param.replace(/\??: .*/, ""))
+      return `function ${name}(${names.join(", ")}) {`
+    })
+    .replace(/: any\b/g, "")
+  return new Function(
+    "Writer",
+    "Reader",
+    "util",
+    code + "\nreturn { encodeTreeNode, decodeTreeNode, encodeA, decodeA, decodeB }",
+  )(runtime.Writer, runtime.Reader, {})
+}
+
+const runtime = loadRuntime(process.env.PROTOS_TS_RUNTIME)
+const { encodeTreeNode, decodeTreeNode, encodeA, decodeA, decodeB } = loadModule(
+  process.env.PROTOS_TS_MODULE,
+  runtime,
+)
+
+const tree = {
+  name: "root",
+  children: [
+    { name: "a", children: [{ name: "a1", children: [{ name: "a11", children: [] }] }] },
+    { name: "b", children: [] },
+  ],
+}
+assert.deepStrictEqual(decodeTreeNode(encodeTreeNode(tree).finish()), tree)
+assert.deepStrictEqual(decodeTreeNode(encodeTreeNode({}).finish()), { name: "", children: [] })
+
+const a = { b: { a: { b: { a: { b: undefined, n: 3 } }, n: 2 } }, n: 1 }
+const encoded = encodeA(a).finish()
+assert.deepStrictEqual(decodeA(encoded), a)
+// the field of `A` holding `B` is a `B` with the rest of the chain
+assert.deepStrictEqual(decodeB(encoded.slice(2, 2 + encoded[1])), a.b)
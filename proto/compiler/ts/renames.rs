@@ -0,0 +1,334 @@
+use std::{collections::HashMap, ops::Deref, sync::Arc};
+
+use crate::proto::{
+    error::ProtoError,
+    package::Field,
+    proto_scope::{root_scope::RootScope, ProtoScope},
+    protopath::PathComponent,
+};
+
+use super::{
+    field_case::{field_case_name, to_lower_camel_case},
+    is_reserved::is_reserved,
+    is_safe_id::is_safe_id,
+    layout::declaration_name,
+    options::Options,
+};
+
+/// Replacement of the TypeScript name of a message, an enum or a field,
+/// for names that collide with something on the TypeScript side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub name: String,
+    /// Whether JSON keys of a renamed field use the new name as well.
+    /// Has no effect on messages and enums.
+    pub json: bool,
+}
+
+impl From<&str> for Rename {
+    fn from(name: &str) -> Self {
+        Rename {
+            name: name.to_string(),
+            json: true,
+        }
+    }
+}
+
+fn field_full_name(root: &RootScope, message_id: usize, field: &Field) -> Option<String> {
+    let message_name = root.get_full_name(message_id)?;
+    Some(format!("{}.{}", message_name, field.name))
+}
+
+fn field_rename<'options>(
+    root: &RootScope,
+    options: &'options Options,
+    message_id: usize,
+    field: &Field,
+) -> Option<&'options Rename> {
+    if options.renames.is_empty() {
+        return None;
+    }
+    options
+        .renames
+        .get(&field_full_name(root, message_id, field)?)
+}
+
+/// Name of the property of the field in the message interfaces,
+/// used by every generated function that reads or writes the message.
+pub(super) fn property_name(
+    root: &RootScope,
+    options: &Options,
+    message_id: usize,
+    field: &Field,
+) -> Arc<str> {
+    match field_rename(root, options, message_id, field) {
+        Some(rename) => rename.name.as_str().into(),
+        None => field_case_name(options.field_case, field),
+    }
+}
+
+/// Key of the field in the JSON representation of the message: its `json_name` option,
+/// otherwise the lowerCamelCase name the proto3 JSON mapping requires, `user_id` is `userId`.
+pub(super) fn json_key(
+    root: &RootScope,
+    options: &Options,
+    message_id: usize,
+    field: &Field,
+) -> Arc<str> {
+    match field_rename(root, options, message_id, field) {
+        Some(rename) if rename.json => rename.name.as_str().into(),
+        _ => field
+            .json_name_option()
+            .unwrap_or_else(|| to_lower_camel_case(&field.name).into()),
+    }
+}
+
+/// Renamed name of the message or the enum, if there is a rename for it.
+pub(super) fn declaration_rename(
+    root: &RootScope,
+    options: &Options,
+    decl_id: usize,
+) -> Option<Arc<str>> {
+    if options.renames.is_empty() {
+        return None;
+    }
+    let full_name = root.get_full_name(decl_id)?;
+    options
+        .renames
+        .get(full_name.deref())
+        .map(|rename| rename.name.as_str().into())
+}
+
+fn invalid_rename(name: &str, reason: String) -> ProtoError {
+    ProtoError::InvalidRename {
+        name: name.to_string(),
+        reason,
+    }
+}
+
+/// Full proto name and TypeScript name of a declaration.
+type Export = (Arc<str>, Arc<str>);
+
+/// Checks that every rename refers to a declaration or a field,
+/// is a valid identifier and does not introduce a collision:
+/// properties of a message and declarations of a proto file must keep unique names.
+pub(super) fn validate_renames(root: &RootScope, options: &Options) -> Result<(), ProtoError> {
+    if options.renames.is_empty() {
+        return Ok(());
+    }
+    let mut errors = Vec::new();
+    let mut declaration_names = Vec::new();
+    let mut field_names = Vec::new();
+    let mut decl_ids = root.types.keys().copied().collect::<Vec<_>>();
+    decl_ids.sort_unstable();
+
+    // names exported from the same proto file, by the path of the file
+    let mut file_exports: HashMap<String, Vec<Export>> = HashMap::new();
+    for &decl_id in &decl_ids {
+        let full_name = root.get_full_name(decl_id).unwrap();
+        let file_path = root
+            .get_declaration_path(decl_id)
+            .unwrap()
+            .path
+            .iter()
+            .take_while(|c| matches!(c, PathComponent::Package(_) | PathComponent::File(_)))
+            .map(String::from)
+            .collect::<Vec<_>>()
+            .join("/");
+        file_exports.entry(file_path).or_default().push((
+            Arc::clone(&full_name),
+            declaration_name(root, options, decl_id),
+        ));
+        declaration_names.push(full_name.to_string());
+
+        let scope = root.get_declaration(decl_id).unwrap();
+        let message = match scope.deref() {
+            ProtoScope::Message(m) => m,
+            _ => continue,
+        };
+        let mut properties: Vec<(&Field, Arc<str>)> = Vec::new();
+        for field in message.get_fields() {
+            field_names.push(format!("{}.{}", full_name, field.name));
+            let property = property_name(root, options, decl_id, field);
+            if let Some((other, _)) = properties.iter().find(|(_, p)| *p == property) {
+                let renamed = [*other, field]
+                    .into_iter()
+                    .map(|f| format!("{}.{}", full_name, f.name))
+                    .find(|name| options.renames.contains_key(name));
+                if let Some(renamed) = renamed {
+                    errors.push(invalid_rename(
+                        &renamed,
+                        format!(
+                            "fields \"{}\" and \"{}\" of {} are both named \"{}\"",
+                            other.name, field.name, full_name, property
+                        ),
+                    ));
+                }
+            }
+            properties.push((field, property));
+        }
+    }
+
+    let mut file_paths = file_exports.keys().collect::<Vec<_>>();
+    file_paths.sort();
+    for file_path in file_paths {
+        let exports = &file_exports[file_path];
+        for (ind, (full_name, ts_name)) in exports.iter().enumerate() {
+            for (other_full_name, other_ts_name) in &exports[..ind] {
+                if ts_name != other_ts_name {
+                    continue;
+                }
+                let renamed = [other_full_name, full_name]
+                    .into_iter()
+                    .find(|name| options.renames.contains_key(&name[..]));
+                if let Some(renamed) = renamed {
+                    errors.push(invalid_rename(
+                        renamed,
+                        format!(
+                            "{} and {} are both exported as \"{}\" from {}",
+                            other_full_name, full_name, ts_name, file_path
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut renamed = options.renames.keys().collect::<Vec<_>>();
+    renamed.sort();
+    for name in renamed {
+        let rename = &options.renames[name];
+        let is_declaration = declaration_names.contains(name);
+        if !is_declaration && !field_names.contains(name) {
+            errors.push(invalid_rename(
+                name,
+                "there is no message, enum or field with this name".to_string(),
+            ));
+            continue;
+        }
+        let is_identifier = is_safe_id(&rename.name)
+            && !rename.name.is_empty()
+            && !rename.name.starts_with(|c: char| c.is_ascii_digit());
+        if !is_identifier || (is_declaration && is_reserved(&rename.name)) {
+            errors.push(invalid_rename(
+                name,
+                format!("\"{}\" is not a valid identifier", rename.name),
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProtoError::from_errors(errors))
+    }
+}
+
+#[cfg(test)]
+mod test_renames {
+    use super::*;
+    use crate::proto::{
+        compiler::ts::test_utils::{compile_sources, render_file_at},
+        package::read_root_scope_from_sources,
+    };
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "shop/order.proto",
+        "syntax = \"proto3\";\npackage shop;\nmessage Error { string text = 1; }\nmessage Order { bool new = 1; Error error = 2; }\n",
+    )];
+
+    fn options(renames: &[(&str, Rename)]) -> Options {
+        let renames = renames
+            .iter()
+            .map(|(name, rename)| (name.to_string(), rename.clone()))
+            .collect::<HashMap<_, _>>();
+        Options {
+            renames: Arc::new(renames),
+            ..Options::default()
+        }
+    }
+
+    fn rename_errors(renames: &[(&str, Rename)]) -> Vec<String> {
+        let root = read_root_scope_from_sources(SOURCES).unwrap();
+        let error = validate_renames(&root, &options(renames)).unwrap_err();
+        error.errors().iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn it_uses_renamed_names_in_every_file() {
+        let options = options(&[
+            ("shop.Error", Rename::from("ShopError")),
+            (
+                "shop.Order.new",
+                Rename {
+                    name: "isNew".into(),
+                    json: false,
+                },
+            ),
+        ]);
+        let folder = compile_sources(SOURCES, &options);
+        let at = |name: &str| render_file_at(&folder, &["shop", "order", "Order", name]);
+
+        let types = at("types");
+        assert!(types
+            .starts_with("import { ShopError, ShopErrorEncodeInput } from \"../Error/types\"\n"));
+        assert!(types.contains("  isNew?: boolean | null\n  error?: ShopErrorEncodeInput | null\n"));
+        assert!(types.contains("  isNew: boolean\n  error: ShopError | undefined\n"));
+        assert!(at("encode").contains("w.uint32(8).bool(message.isNew)"));
+        assert!(at("decode").contains("message.isNew = r.bool()"));
+        assert!(at("create").contains("isNew: false"));
+        let json = at("json");
+        assert!(json.contains("json[\"new\"] = message.isNew"));
+        assert!(json.contains("message.isNew = Boolean(json[\"new\"])"));
+        assert!(!json.contains("json.isNew"));
+
+        let error_types = render_file_at(&folder, &["shop", "order", "Error", "types"]);
+        assert!(error_types.contains("export interface ShopError {"));
+        assert!(error_types.contains("export interface ShopErrorEncodeInput {"));
+    }
+
+    #[test]
+    fn it_renames_json_keys_by_default() {
+        let options = options(&[("shop.Order.new", Rename::from("isNew"))]);
+        let folder = compile_sources(SOURCES, &options);
+        let json = render_file_at(&folder, &["shop", "order", "Order", "json"]);
+        assert!(json.contains("json.isNew = message.isNew"));
+        assert!(json.contains("message.isNew = Boolean(json.isNew ?? json[\"new\"])"));
+    }
+
+    #[test]
+    fn it_rejects_unknown_names() {
+        assert_eq!(
+            rename_errors(&[("shop.Order.old", Rename::from("isOld"))]),
+            ["Invalid rename of \"shop.Order.old\": there is no message, enum or field with this name"]
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_identifiers() {
+        assert_eq!(
+            rename_errors(&[
+                ("shop.Error", Rename::from("class")),
+                ("shop.Order.new", Rename::from("is-new")),
+            ]),
+            [
+                "Invalid rename of \"shop.Error\": \"class\" is not a valid identifier",
+                "Invalid rename of \"shop.Order.new\": \"is-new\" is not a valid identifier",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rejects_renames_introducing_collisions() {
+        assert_eq!(
+            rename_errors(&[
+                ("shop.Error", Rename::from("Order")),
+                ("shop.Order.new", Rename::from("error")),
+            ]),
+            [
+                "Invalid rename of \"shop.Order.new\": fields \"new\" and \"error\" of shop.Order are both named \"error\"",
+                "Invalid rename of \"shop.Error\": shop.Error and shop.Order are both exported as \"Order\" from shop/order.proto",
+            ]
+        );
+    }
+}
@@ -1,27 +1,237 @@
-use std::{ops::Deref, rc::Rc};
+use std::{
+    fmt::{self, Write},
+    ops::Deref,
+    sync::Arc,
+};
 
-use super::{ast::*, is_reserved::is_reserved, is_safe_id::is_safe_id, to_js_string::to_js_string};
+use super::{
+    ast::*,
+    is_reserved::is_reserved,
+    is_safe_id::is_safe_id,
+    to_js_string::{to_js_string, to_property_name},
+};
 
-impl From<&ImportDeclaration> for String {
-    fn from(import_declaration: &ImportDeclaration) -> Self {
+/// Unions with more members are written one member per line
+/// when they are the type of a property.
+const MAX_INLINE_UNION_MEMBERS: usize = 3;
+
+/// Wraps a `std::fmt::Write` implementor and starts every non-empty line
+/// with two spaces per level of indentation.
+pub(super) struct IndentWriter<W> {
+    inner: W,
+    level: usize,
+    at_line_start: bool,
+}
+
+impl<W: Write> IndentWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            level: 0,
+            at_line_start: true,
+        }
+    }
+
+    /// Indents the lines written after this call one level deeper.
+    pub fn indent(&mut self) {
+        self.level += 1;
+    }
+
+    /// Reverts the last `indent`.
+    pub fn dedent(&mut self) {
+        debug_assert!(self.level > 0, "dedent without indent");
+        self.level -= 1;
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for IndentWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.at_line_start && line != "\n" {
+                for _ in 0..self.level {
+                    self.inner.write_str("  ")?;
+                }
+            }
+            self.inner.write_str(line)?;
+            self.at_line_start = line.ends_with('\n');
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_indent_writer {
+    use super::*;
+
+    #[test]
+    fn it_indents_every_non_empty_line() {
+        let mut w = IndentWriter::new(String::new());
+        write!(w, "{{\n").unwrap();
+        w.indent();
+        write!(w, "a\n\nb").unwrap();
+        write!(w, "c\n").unwrap();
+        w.dedent();
+        write!(w, "}}").unwrap();
+        assert_eq!(w.into_inner(), "{\n  a\n\n  bc\n}");
+    }
+}
+
+/// Node of the AST that can be written as TypeScript code.
+trait Render {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result;
+}
+
+fn render_to_string<T: Render + ?Sized>(node: &T) -> String {
+    let mut w = IndentWriter::new(String::new());
+    node.render(&mut w)
+        .expect("writing to a String does not fail");
+    w.into_inner()
+}
+
+macro_rules! impl_string_from_render {
+    ($($node:ty),* $(,)?) => {
+        $(
+            impl From<&$node> for String {
+                fn from(node: &$node) -> Self {
+                    render_to_string(node)
+                }
+            }
+        )*
+    };
+}
+
+impl_string_from_render!(
+    ImportDeclaration,
+    EnumDeclaration,
+    Type,
+    InterfaceDeclaration,
+    FunctionDeclaration,
+    ClassDeclaration,
+    NamespaceDeclaration,
+    PropertyAccessExpression,
+    BinaryExpression,
+    CallExpression,
+    NewExpression,
+    ElementAccessExpression,
+    PrefixUnaryExpression,
+    ConditionalExpression,
+    Expression,
+    VariableDeclarationList,
+    IfStatement,
+    Block,
+    WhileStatement,
+    ForStatement,
+    ForInStatement,
+    CaseClause,
+    DefaultClause,
+    SwitchStatement,
+    Statement,
+    File,
+);
+
+fn render_modifiers<W: Write>(modifiers: &[Modifier], w: &mut IndentWriter<W>) -> fmt::Result {
+    for modifier in modifiers {
+        match modifier {
+            Modifier::Export => w.write_str("export ")?,
+            Modifier::Declare => w.write_str("declare ")?,
+            Modifier::Static => w.write_str("static ")?,
+            Modifier::Private => w.write_str("private ")?,
+            Modifier::Readonly => w.write_str("readonly ")?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes the `/** @deprecated */` tag on its own line, editors strike out what follows it.
+fn render_deprecated<W: Write>(deprecated: bool, w: &mut IndentWriter<W>) -> fmt::Result {
+    if deprecated {
+        w.write_str("/** @deprecated */\n")?;
+    }
+    Ok(())
+}
+
+/// Writes `items` separated by `separator`.
+fn render_list<W: Write, T: Render>(
+    items: impl IntoIterator<Item = T>,
+    separator: &str,
+    w: &mut IndentWriter<W>,
+) -> fmt::Result {
+    for (ind, item) in items.into_iter().enumerate() {
+        if ind > 0 {
+            w.write_str(separator)?;
+        }
+        item.render(w)?;
+    }
+    Ok(())
+}
+
+/// Writes the operand of a prefix operator, `typeof`, `as` or an element access,
+/// binary and conditional expressions are parenthesized as they bind looser than these operators.
+fn render_operand<W: Write>(expr: &Expression, w: &mut IndentWriter<W>) -> fmt::Result {
+    if let Expression::BinaryExpression(_) | Expression::ConditionalExpression(_) = expr {
+        w.write_str("(")?;
+        expr.render(w)?;
+        return w.write_str(")");
+    }
+    expr.render(w)
+}
+
+impl<T: Render + ?Sized> Render for &T {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        (**self).render(w)
+    }
+}
+
+impl<T: Render + ?Sized> Render for Arc<T> {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        self.deref().render(w)
+    }
+}
+
+impl Render for ImportDeclaration {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
         let mut imports = Vec::new();
-        if let Some(name) = &import_declaration.import_clause.name {
-            imports.push(name.text.clone());
+        if let Some(name) = &self.import_clause.name {
+            imports.push(name.text.to_string());
         }
-        if let Some(bindings) = &import_declaration.import_clause.named_bindings {
+        if let Some(name) = &self.import_clause.namespace_import {
+            imports.push(format!("* as {}", name.text));
+        }
+        if let Some(bindings) = &self.import_clause.named_bindings {
+            let mut bindings: Vec<&ImportSpecifier> = bindings.iter().collect();
+            bindings.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
             let pairs: Vec<String> = bindings
                 .iter()
-                .map(|e| match &e.property_name {
-                    Some(property_name) => format!("{} as {}", property_name.text, e.name.text),
-                    None => e.name.text.to_string(),
+                .map(|e| {
+                    let modifier = if e.is_type_only && !self.import_clause.is_type_only {
+                        "type "
+                    } else {
+                        ""
+                    };
+                    match &e.property_name {
+                        Some(property_name) => {
+                            format!("{}{} as {}", modifier, property_name.text, e.name.text)
+                        }
+                        None => format!("{}{}", modifier, e.name.text),
+                    }
                 })
                 .collect();
-            imports.push(format!("{{ {} }}", pairs.join(", ")).into());
+            imports.push(format!("{{ {} }}", pairs.join(", ")));
         }
-        format!(
-            "import {} from \"{}\"",
+        write!(
+            w,
+            "import {}{} from {}",
+            if self.import_clause.is_type_only {
+                "type "
+            } else {
+                ""
+            },
             imports.join(", "),
-            import_declaration.string_literal.text
+            to_js_string(&self.string_literal.text)
         )
     }
 }
@@ -33,7 +243,9 @@ mod test_import_declaration {
     fn it_works() {
         let decl = Statement::ImportDeclaration(Box::new(ImportDeclaration {
             import_clause: Box::new(ImportClause {
+                is_type_only: false,
                 name: Some(Identifier::new("google".into())),
+                namespace_import: None,
                 named_bindings: Some(vec![ImportSpecifier::new_full(
                     Identifier::new("wrong").into(),
                     Some(Identifier::new("right").into()),
@@ -47,47 +259,92 @@ mod test_import_declaration {
             "import google, { right as wrong } from \"google/proto\"".to_string()
         );
     }
+
+    #[test]
+    fn it_renders_type_only_and_namespace_imports() {
+        let mut decl = ImportDeclaration::import(
+            vec![ImportSpecifier::new(Identifier::new("User").into())],
+            StringLiteral::new("../User/types".into()),
+        );
+        decl.import_clause.is_type_only = true;
+        assert_eq!(
+            String::from(&decl),
+            "import type { User } from \"../User/types\""
+        );
+        let decl = ImportDeclaration::import_namespace(
+            Identifier::new("e1").into(),
+            StringLiteral::new("../User/encode".into()),
+        );
+        assert_eq!(
+            String::from(&decl),
+            "import * as e1 from \"../User/encode\""
+        );
+    }
+
+    #[test]
+    fn it_renders_imports_of_a_file_sorted() {
+        let import = |names: &[&str], path: &str| {
+            Statement::ImportDeclaration(Box::new(ImportDeclaration::import(
+                names
+                    .iter()
+                    .map(|name| ImportSpecifier::new(Identifier::new(name).into()))
+                    .collect(),
+                StringLiteral::new(path.into()),
+            )))
+        };
+        let mut file = File::new("encode".into());
+        file.push_statement(import(&["Writer", "Reader"], "protobufjs/minimal"));
+        file.push_statement(import(&["User"], "../User/types"));
+        file.push_statement(Statement::Verbatim("const b = 1".into()));
+        file.push_statement(Statement::Verbatim("const a = 0".into()));
+        file.push_statement(import(&["Role"], "../Role"));
+        assert_eq!(
+            String::from(&file),
+            [
+                "import { Role } from \"../Role\"",
+                "import { User } from \"../User/types\"",
+                "import { Reader, Writer } from \"protobufjs/minimal\"",
+                "",
+                "const b = 1",
+                "const a = 0\n",
+            ]
+            .join("\n")
+        );
+    }
 }
 
-impl From<&EnumDeclaration> for String {
-    fn from(enum_declaration: &EnumDeclaration) -> Self {
-        let mut res = String::new();
+impl Render for EnumDeclaration {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
         let EnumDeclaration {
             modifiers,
             name,
             members,
-        } = enum_declaration;
-        for modifier in modifiers {
-            match modifier {
-                Modifier::Export => res.push_str("export "),
-            }
-        }
-        res.push_str("enum ");
-        res.push_str(&name.text);
-        if members.len() <= 0 {
-            res.push_str("{}");
-            return res;
+            deprecated,
+        } = self;
+        render_deprecated(*deprecated, w)?;
+        render_modifiers(modifiers, w)?;
+        write!(w, "enum {}", name.text)?;
+        if members.is_empty() {
+            return w.write_str("{}");
         }
-        res.push_str(" {\n");
+        w.write_str(" {\n")?;
+        w.indent();
         for member in members {
-            res.push_str("  ");
-            res.push_str(&member.name.text);
+            render_deprecated(member.deprecated, w)?;
+            w.write_str(&member.name.text)?;
             if let Some(value) = &member.value {
-                res.push_str(" = ");
+                w.write_str(" = ")?;
                 match value {
                     EnumValue::String(string_literal) => {
-                        res.push_str("\"");
-                        res.push_str(&string_literal.text);
-                        res.push_str("\"");
+                        w.write_str(&to_js_string(&string_literal.text))?
                     }
-                    EnumValue::Number(numeric_literal) => res.push_str(&numeric_literal.text),
+                    EnumValue::Number(numeric_literal) => w.write_str(&numeric_literal.text)?,
                 }
             }
-            res.push_str(",\n");
+            w.write_str(",\n")?;
         }
-        res.push_str("}");
-
-        res
+        w.dedent();
+        w.write_str("}")
     }
 }
 
@@ -103,16 +360,20 @@ mod test_enum_declaration {
                 EnumMember {
                     name: "A".into(),
                     value: Some(EnumValue::String("A".into())),
+                    deprecated: false,
                 },
                 EnumMember {
                     name: "B".into(),
                     value: None,
+                    deprecated: false,
                 },
                 EnumMember {
                     name: "C".into(),
                     value: Some(1.into()),
+                    deprecated: false,
                 },
             ],
+            deprecated: false,
         };
         let rendered: String = (&decl).into();
         assert_eq!(
@@ -120,47 +381,256 @@ mod test_enum_declaration {
             "export enum MyEnum {\n  A = \"A\",\n  B,\n  C = 1,\n}".to_string()
         );
     }
+
+    #[test]
+    fn it_renders_deprecated_tags() {
+        let decl = EnumDeclaration {
+            modifiers: vec![Modifier::Export],
+            name: "Status".into(),
+            members: vec![
+                EnumMember {
+                    name: "NEW".into(),
+                    value: Some(0.into()),
+                    deprecated: false,
+                },
+                EnumMember {
+                    name: "LEGACY".into(),
+                    value: Some(1.into()),
+                    deprecated: true,
+                },
+            ],
+            deprecated: true,
+        };
+        assert_eq!(
+            String::from(&decl),
+            "/** @deprecated */\nexport enum Status {\n  NEW = 0,\n  /** @deprecated */\n  LEGACY = 1,\n}"
+        );
+    }
 }
 
-impl From<&Type> for String {
-    fn from(type_: &Type) -> Self {
-        match type_ {
-            Type::Any => "any".into(),
-            Type::Boolean => "boolean".into(),
-            Type::Number => "number".into(),
-            Type::String => "string".into(),
-            Type::Null => "null".into(),
-            Type::Never => "never".into(),
+/// Member of a union or an intersection,
+/// wrapped in parentheses when it is a union, an intersection or an array itself.
+struct UnionMember<'a>(&'a Type);
+
+impl Render for UnionMember<'_> {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        if self.0.requires_wrap_for_nesting() {
+            w.write_str("(")?;
+            self.0.render(w)?;
+            w.write_str(")")
+        } else {
+            self.0.render(w)
+        }
+    }
+}
+
+impl Render for Type {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        match self {
+            Type::Any => w.write_str("any"),
+            Type::Unknown => w.write_str("unknown"),
+            Type::Void => w.write_str("void"),
+            Type::Undefined => w.write_str("undefined"),
+            Type::Boolean => w.write_str("boolean"),
+            Type::Number => w.write_str("number"),
+            Type::BigInt => w.write_str("bigint"),
+            Type::String => w.write_str("string"),
+            Type::Null => w.write_str("null"),
+            Type::Never => w.write_str("never"),
             Type::UnionType(UnionType { types }) => {
-                let type_str: Vec<String> = types
-                    .iter()
-                    .map(|t| {
-                        let str: String = t.into();
-                        if t.requires_wrap_for_nesting() {
-                            format!("({})", str)
-                        } else {
-                            str
-                        }
-                    })
-                    .collect();
-                type_str.join(" | ")
+                render_list(types.iter().map(UnionMember), " | ", w)
+            }
+            Type::IntersectionType(IntersectionType { types }) => {
+                render_list(types.iter().map(UnionMember), " & ", w)
             }
             Type::ArrayType(element) => {
                 if element.requires_wrap_for_nesting() {
-                    format!("Array<{}>", element)
+                    w.write_str("Array<")?;
+                    element.render(w)?;
+                    w.write_str(">")
                 } else {
-                    format!("{}[]", element)
+                    element.render(w)?;
+                    w.write_str("[]")
                 }
             }
             Type::Record(key, value) => {
-                format!("Record<{}, {}>", key, value)
+                w.write_str("Record<")?;
+                key.render(w)?;
+                w.write_str(", ")?;
+                value.render(w)?;
+                w.write_str(">")
             }
-            Type::TypeReference(ids) => ids
-                .iter()
-                .map(|id| id.text.to_string())
-                .collect::<Vec<_>>()
-                .join("."),
+            Type::TypeReference(ids) => {
+                for (ind, id) in ids.iter().enumerate() {
+                    if ind > 0 {
+                        w.write_str(".")?;
+                    }
+                    w.write_str(&id.text)?;
+                }
+                Ok(())
+            }
+            Type::Generic(id, type_arguments) => {
+                write!(w, "{}<", id.text)?;
+                render_list(type_arguments.iter(), ", ", w)?;
+                w.write_str(">")
+            }
+            Type::Predicate(parameter, t) => {
+                write!(w, "{} is ", parameter.text)?;
+                t.render(w)
+            }
+            Type::LiteralType(LiteralValue::StringLit(value)) => w.write_str(&to_js_string(value)),
+            Type::LiteralType(LiteralValue::NumberLit(value)) => write!(w, "{}", value),
+            Type::LiteralType(LiteralValue::BoolLit(value)) => write!(w, "{}", value),
+            Type::TupleType(types) => {
+                w.write_str("[")?;
+                render_list(types.iter(), ", ", w)?;
+                w.write_str("]")
+            }
+            Type::Object(members) => {
+                w.write_str("{ ")?;
+                for (ind, (name, t)) in members.iter().enumerate() {
+                    if ind > 0 {
+                        w.write_str("; ")?;
+                    }
+                    write!(w, "{}: ", name)?;
+                    t.render(w)?;
+                }
+                w.write_str(" }")
+            }
+            Type::IndexSignature(value) => {
+                w.write_str("{ [key: string]: ")?;
+                value.render(w)?;
+                w.write_str(" }")
+            }
+            Type::TypeQuery(id) => write!(w, "typeof {}", id.text),
+            Type::TypeVar(id) => w.write_str(&id.text),
+            Type::KeyOf(t) => {
+                w.write_str("keyof ")?;
+                t.render(w)
+            }
+            Type::IndexedAccess(object, index) => {
+                UnionMember(object).render(w)?;
+                w.write_str("[")?;
+                index.render(w)?;
+                w.write_str("]")
+            }
+        }
+    }
+}
+
+/// Writes the type of a property after its colon.
+/// Long unions start on the next line, one member per line:
+///
+/// ```ts
+/// value?:
+///   | number
+///   | string
+///   | boolean
+///   | null
+/// ```
+fn render_property_type<W: Write>(property_type: &Type, w: &mut IndentWriter<W>) -> fmt::Result {
+    match property_type {
+        Type::UnionType(UnionType { types }) if types.len() > MAX_INLINE_UNION_MEMBERS => {
+            w.indent();
+            for t in types {
+                w.write_str("\n| ")?;
+                UnionMember(t).render(w)?;
+            }
+            w.dedent();
+            Ok(())
         }
+        t => {
+            w.write_str(" ")?;
+            t.render(w)
+        }
+    }
+}
+
+impl Render for TypeParameter {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str(&self.name.text)?;
+        if let Some(constraint) = &self.constraint {
+            w.write_str(" extends ")?;
+            constraint.render(w)?;
+        }
+        if let Some(default) = &self.default {
+            w.write_str(" = ")?;
+            default.render(w)?;
+        }
+        Ok(())
+    }
+}
+
+/// `<A, B extends C = D>`, nothing for a declaration without type parameters
+fn render_type_params<W: Write>(
+    type_params: &[TypeParameter],
+    w: &mut IndentWriter<W>,
+) -> fmt::Result {
+    if type_params.is_empty() {
+        return Ok(());
+    }
+    w.write_str("<")?;
+    render_list(type_params.iter(), ", ", w)?;
+    w.write_str(">")
+}
+
+impl Render for TypeAliasDeclaration {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        render_deprecated(self.deprecated, w)?;
+        render_modifiers(&self.modifiers, w)?;
+        write!(w, "type {}", self.name.text)?;
+        render_type_params(&self.type_params, w)?;
+        w.write_str(" =")?;
+        render_property_type(&self.alias_type, w)
+    }
+}
+
+#[cfg(test)]
+mod test_type_alias_declaration {
+    use super::*;
+
+    #[test]
+    fn it_renders_an_exported_alias_of_a_union() {
+        let status = TypeAliasDeclaration::new(
+            Identifier::new("Status"),
+            UnionType::from(vec![
+                Type::string_literal("ok"),
+                Type::string_literal("error"),
+            ])
+            .into(),
+        )
+        .exported();
+        assert_eq!(
+            String::from(&Statement::from(status)),
+            "export type Status = \"ok\" | \"error\""
+        );
+    }
+
+    #[test]
+    fn it_renders_type_parameters() {
+        let mut maybe = TypeAliasDeclaration::new(
+            Identifier::new("Maybe"),
+            Type::TypeVar("T".into()).or(&Type::Null),
+        );
+        maybe.type_params.push(TypeParameter::new("T"));
+        assert_eq!(
+            String::from(&Statement::from(maybe)),
+            "type Maybe<T> = T | null"
+        );
+
+        let mut page = TypeAliasDeclaration::new(
+            Identifier::new("Page"),
+            Type::array(Type::TypeVar("T".into())),
+        );
+        page.type_params.push(TypeParameter {
+            constraint: Some(Type::from_id("Message")),
+            default: Some(Type::from_id("Message")),
+            ..TypeParameter::new("T")
+        });
+        assert_eq!(
+            String::from(&Statement::from(page)),
+            "type Page<T extends Message = Message> = T[]"
+        );
     }
 }
 
@@ -192,6 +662,87 @@ mod test_type {
         let rendered: String = (&type_).into();
         assert_eq!(rendered, "boolean[]");
     }
+    #[test]
+    fn it_renders_generic() {
+        let type_ = Type::generic("Partial", vec![Type::from_id("Message")]);
+        let rendered: String = (&type_).into();
+        assert_eq!(rendered, "Partial<Message>");
+    }
+    #[test]
+    fn it_renders_literal_types() {
+        let type_ = Type::string_literal("PENDING").or(&Type::string_literal("DONE"));
+        assert_eq!(String::from(&type_), "\"PENDING\" | \"DONE\"");
+        let type_ = Type::LiteralType(LiteralValue::NumberLit(-1.0))
+            .or(&Type::LiteralType(LiteralValue::NumberLit(2.5)))
+            .or(&Type::LiteralType(LiteralValue::BoolLit(true)));
+        assert_eq!(String::from(&type_), "-1 | 2.5 | true");
+    }
+    #[test]
+    fn it_deduplicates_literal_types_of_unions() {
+        let type_ = Type::string_literal("DONE")
+            .or(&Type::string_literal("PENDING"))
+            .or(&Type::string_literal("DONE"));
+        assert_eq!(String::from(&type_), "\"DONE\" | \"PENDING\"");
+        let type_ = Type::string_literal("DONE")
+            .or(&Type::Null)
+            .or(&Type::String)
+            .or(&Type::string_literal("PENDING"));
+        assert_eq!(String::from(&type_), "null | string");
+        let type_ = Type::LiteralType(LiteralValue::NumberLit(1.0))
+            .or(&Type::LiteralType(LiteralValue::NumberLit(1.0)));
+        assert_eq!(String::from(&type_), "1");
+    }
+    #[test]
+    fn it_renders_tuples() {
+        let type_ = Type::TupleType(vec![Type::String, Type::Number]);
+        assert_eq!(String::from(&type_), "[string, number]");
+        let type_ = Type::array(Type::TupleType(vec![
+            Type::String,
+            Type::String.or(&Type::Null),
+        ]));
+        assert_eq!(String::from(&type_), "[string, string | null][]");
+        let type_ = Type::TupleType(vec![Type::String, Type::Number]).or(&Type::Null);
+        assert_eq!(String::from(&type_), "[string, number] | null");
+    }
+    #[test]
+    fn it_renders_object_types() {
+        let type_ = Type::Object(vec![
+            ("seconds".into(), Type::Number),
+            ("nanos".into(), Type::Number),
+        ]);
+        assert_eq!(String::from(&type_), "{ seconds: number; nanos: number }");
+        let type_ = Type::from_id("Date").or(&type_);
+        assert_eq!(
+            String::from(&type_),
+            "Date | { seconds: number; nanos: number }"
+        );
+        let type_ = Type::IndexSignature(Box::new(Type::Unknown));
+        assert_eq!(String::from(&type_), "{ [key: string]: unknown }");
+    }
+    #[test]
+    fn it_renders_intersections() {
+        let type_ = Type::intersection(Type::from_id("Base"), Type::from_id("Extension"));
+        assert_eq!(String::from(&type_), "Base & Extension");
+        assert_eq!(String::from(&Type::array(type_)), "Array<Base & Extension>");
+    }
+    #[test]
+    fn it_flattens_nested_intersections() {
+        let type_ = Type::intersection(
+            Type::intersection(Type::from_id("A"), Type::from_id("B")),
+            Type::from_id("C"),
+        );
+        assert_eq!(
+            type_,
+            Type::IntersectionType(IntersectionType {
+                types: vec![Type::from_id("A"), Type::from_id("B"), Type::from_id("C")]
+            })
+        );
+        assert_eq!(String::from(&type_), "A & B & C");
+        let type_ = Type::intersection(Type::from_id("A").or(&Type::Null), Type::from_id("B"));
+        assert_eq!(String::from(&type_), "(A | null) & B");
+        let type_ = Type::from_id("A").or(&type_);
+        assert_eq!(String::from(&type_), "A | ((A | null) & B)");
+    }
 }
 
 impl std::fmt::Display for Type {
@@ -201,44 +752,55 @@ impl std::fmt::Display for Type {
     }
 }
 
-impl From<&InterfaceDeclaration> for String {
-    fn from(interface_declaration: &InterfaceDeclaration) -> Self {
-        let mut res = String::new();
+impl Render for InterfaceDeclaration {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
         let InterfaceDeclaration {
             modifiers,
             name,
+            type_params,
+            extends,
             members,
-        } = interface_declaration;
-        for modifier in modifiers {
-            match modifier {
-                Modifier::Export => res.push_str("export "),
-            }
+            deprecated,
+        } = self;
+        render_deprecated(*deprecated, w)?;
+        render_modifiers(modifiers, w)?;
+        write!(w, "interface {}", name.text)?;
+        render_type_params(type_params, w)?;
+        if !extends.is_empty() {
+            w.write_str(" extends ")?;
+            render_list(extends.iter(), ", ", w)?;
         }
-        res.push_str("interface ");
-        res.push_str(&name.text);
-        if members.len() <= 0 {
-            res.push_str("{}");
-            return res;
+        if members.is_empty() {
+            return w.write_str("{}");
         }
-        res.push_str(" {\n");
+        w.write_str(" {\n")?;
+        w.indent();
         for member in members {
             match member {
                 InterfaceMember::PropertySignature(prop) => {
-                    res.push_str("  ");
-                    res.push_str(&prop.name.text);
+                    render_deprecated(prop.deprecated, w)?;
+                    if prop.readonly {
+                        w.write_str("readonly ")?;
+                    }
+                    w.write_str(&to_property_name(&prop.name.text))?;
                     if prop.optional {
-                        res.push_str("?");
+                        w.write_str("?")?;
                     }
-                    res.push_str(": ");
-                    let type_str: String = (&prop.property_type).into();
-                    res.push_str(type_str.as_str());
-                    res.push_str("\n");
+                    w.write_str(":")?;
+                    render_property_type(&prop.property_type, w)?;
+                    w.write_str("\n")?;
+                }
+                InterfaceMember::MethodSignature(method) => {
+                    write!(w, "{}(", method.name.text)?;
+                    render_list(method.parameters.iter(), ", ", w)?;
+                    w.write_str("): ")?;
+                    method.return_type.render(w)?;
+                    w.write_str("\n")?;
                 }
             }
         }
-        res.push_str("}");
-
-        res
+        w.dedent();
+        w.write_str("}")
     }
 }
 
@@ -250,11 +812,14 @@ mod test_interface_declaration {
         let decl = InterfaceDeclaration {
             modifiers: vec![Modifier::Export],
             name: "MyInterface".into(),
+            type_params: vec![],
+            extends: vec![],
             members: vec![
                 PropertySignature::new("A".into(), Type::Boolean).into(),
                 PropertySignature::new_optional("B".into(), Type::Number).into(),
                 PropertySignature::new("C".into(), Type::String).into(),
             ],
+            deprecated: false,
         };
         let rendered: String = (&decl).into();
         assert_eq!(
@@ -263,467 +828,1157 @@ mod test_interface_declaration {
                 .to_string()
         );
     }
+
+    #[test]
+    fn it_breaks_long_unions_across_lines() {
+        let decl = InterfaceDeclaration {
+            modifiers: vec![],
+            name: "Value".into(),
+            type_params: vec![],
+            extends: vec![],
+            members: vec![
+                PropertySignature::new_optional(
+                    "kind".into(),
+                    Type::UnionType(UnionType {
+                        types: vec![
+                            Type::Number,
+                            Type::String,
+                            Type::array(Type::Boolean),
+                            Type::Null,
+                        ],
+                    }),
+                )
+                .into(),
+                PropertySignature::new(
+                    "short".into(),
+                    Type::UnionType(UnionType {
+                        types: vec![Type::Number, Type::String, Type::Null],
+                    }),
+                )
+                .into(),
+            ],
+            deprecated: false,
+        };
+        let rendered: String = (&decl).into();
+        assert_eq!(
+            rendered,
+            "interface Value {\n  kind?:\n    | number\n    | string\n    | (boolean[])\n    | null\n  short: number | string | null\n}"
+        );
+    }
+
+    #[test]
+    fn it_renders_deprecated_tags() {
+        let mut decl = InterfaceDeclaration::new_exported("User".into());
+        decl.deprecated = true;
+        decl.members
+            .push(PropertySignature::new("name".into(), Type::String).into());
+        let mut login = PropertySignature::new("login".into(), Type::String);
+        login.deprecated = true;
+        decl.members.push(login.into());
+        assert_eq!(
+            String::from(&decl),
+            "/** @deprecated */\nexport interface User {\n  name: string\n  /** @deprecated */\n  login: string\n}"
+        );
+    }
+
+    #[test]
+    fn it_renders_a_type_parameter() {
+        let t = Identifier::new("T");
+        let mut decl = InterfaceDeclaration::new_exported("Wrapper".into());
+        decl.type_params.push(TypeParameter::new(t.clone()));
+        decl.members
+            .push(PropertySignature::new("value".into(), Type::TypeVar(t)).into());
+        assert_eq!(
+            String::from(&decl),
+            "export interface Wrapper<T> {\n  value: T\n}"
+        );
+    }
+}
+
+impl Render for Parameter {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str(&self.name.text)?;
+        if self.optional {
+            w.write_str("?")?;
+        }
+        w.write_str(": ")?;
+        self.parameter_type.render(w)
+    }
+}
+
+#[cfg(test)]
+mod test_function_declaration {
+    use super::*;
+
+    #[test]
+    fn it_renders_a_constrained_type_parameter_with_a_default() {
+        let t = Identifier::new("T");
+        let mut decl = FunctionDeclaration::new_exported("first");
+        decl.type_params.push(TypeParameter {
+            constraint: Some(Type::from_id("Message")),
+            default: Some(Type::Unknown),
+            ..TypeParameter::new(t.clone())
+        });
+        decl.add_param(Parameter::new(
+            "items",
+            Type::array(Type::TypeVar(t.clone())),
+        ));
+        decl.returns(Type::TypeVar(t).or(&Type::Null));
+        assert_eq!(
+            String::from(&decl),
+            "export function first<T extends Message = unknown>(items: T[]): T | null {}"
+        );
+    }
 }
 
-impl From<&FunctionDeclaration> for String {
-    fn from(f: &FunctionDeclaration) -> Self {
-        let mut res = String::new();
+impl Render for FunctionDeclaration {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
         let FunctionDeclaration {
             modifiers,
             name,
+            type_params,
             parameters,
             body,
             return_type,
-            ..
-        } = f;
+        } = self;
 
-        for modifier in modifiers {
-            match modifier {
-                Modifier::Export => res.push_str("export "),
+        render_modifiers(modifiers, w)?;
+        write!(w, "function {}", name.text)?;
+        render_type_params(type_params, w)?;
+        w.write_str("(")?;
+        render_list(parameters.iter(), ", ", w)?;
+        w.write_str("): ")?;
+        return_type.render(w)?;
+        match body {
+            None => Ok(()),
+            Some(body) if body.statements.is_empty() => w.write_str(" {}"),
+            Some(body) => {
+                w.write_str(" ")?;
+                body.render(w)
             }
         }
-        res.push_str("function ");
-        res.push_str(&name.text);
-        res.push_str("(");
-        for (ind, param) in parameters.iter().enumerate() {
-            if ind > 0 {
-                res.push_str(", ");
+    }
+}
+
+impl Render for ClassDeclaration {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        render_modifiers(&self.modifiers, w)?;
+        write!(w, "class {}", self.name.text)?;
+        if !self.implements.is_empty() {
+            w.write_str(" implements ")?;
+            render_list(self.implements.iter(), ", ", w)?;
+        }
+        w.write_str(" {\n")?;
+        w.indent();
+        if self.ambient {
+            for property in &self.properties {
+                writeln!(w, "private readonly {}", property.name.text)?;
             }
-            res.push_str(&param.name.text);
-            if param.optional {
-                res.push_str("?");
+        }
+        for field in &self.fields {
+            render_modifiers(&field.modifiers, w)?;
+            w.write_str(&field.name.text)?;
+            if let Some(t) = &field.property_type {
+                w.write_str(": ")?;
+                t.render(w)?;
+            }
+            match &field.initializer {
+                Some(initializer) if !self.ambient => {
+                    w.write_str(" = ")?;
+                    initializer.render(w)?;
+                }
+                _ => {}
+            }
+            w.write_str("\n")?;
+        }
+        let has_constructor = !self.properties.is_empty()
+            || !self.parameters.is_empty()
+            || !self.constructor.statements.is_empty();
+        if has_constructor {
+            if !self.fields.is_empty() {
+                w.write_str("\n")?;
+            }
+            w.write_str("constructor(")?;
+            for (ind, property) in self.properties.iter().enumerate() {
+                if ind > 0 {
+                    w.write_str(", ")?;
+                }
+                if !self.ambient {
+                    w.write_str("private readonly ")?;
+                }
+                property.render(w)?;
+            }
+            if !self.properties.is_empty() && !self.parameters.is_empty() {
+                w.write_str(", ")?;
+            }
+            render_list(self.parameters.iter(), ", ", w)?;
+            if self.ambient {
+                w.write_str(")\n")?;
+            } else if self.constructor.statements.is_empty() {
+                w.write_str(") {}\n")?;
+            } else {
+                w.write_str(") ")?;
+                self.constructor.render(w)?;
+                w.write_str("\n")?;
             }
-            res.push_str(": ");
-            let type_str: String = param.parameter_type.deref().into();
-            res.push_str(type_str.as_str());
         }
-        res.push_str(")");
-        res.push_str(": ");
-        let type_str: String = return_type.into();
-        res.push_str(type_str.as_str());
-        if body.statements.len() <= 0 {
-            res.push_str(" {}");
-            return res;
+        for (ind, method) in self.methods.iter().enumerate() {
+            if ind > 0 || has_constructor || !self.fields.is_empty() {
+                w.write_str("\n")?;
+            }
+            render_modifiers(&method.modifiers, w)?;
+            write!(w, "{}(", method.name.text)?;
+            render_list(method.parameters.iter(), ", ", w)?;
+            w.write_str("): ")?;
+            method.return_type.render(w)?;
+            if let Some(body) = &method.body {
+                w.write_str(" ")?;
+                body.render(w)?;
+            }
+            w.write_str("\n")?;
         }
-
-        res.push(' ');
-        let block_str: String = body.into();
-        res.push_str(&block_str);
-        res
+        w.dedent();
+        w.write_str("}")
     }
 }
 
-impl From<&PropertyAccessExpression> for String {
-    fn from(decl: &PropertyAccessExpression) -> Self {
-        let mut res = String::new();
-        let wrapped = decl.requires_wrap_for_prop();
-        if wrapped {
-            res.push('(');
-        }
-        let obj_str: String = decl.expression.deref().into();
-        res.push_str(&obj_str);
-        if wrapped {
-            res.push(')');
+impl Render for PropertyAccessExpression {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        if self.requires_wrap_for_prop() {
+            w.write_str("(")?;
+            self.expression.render(w)?;
+            w.write_str(")")?;
+        } else {
+            self.expression.render(w)?;
         }
 
-        if !is_safe_id(&decl.name.text) || is_reserved(&decl.name.text) {
-            res.push('[');
-            let prop_str = to_js_string(&decl.name.text);
-            res.push_str(&prop_str);
-            res.push(']');
-            return res;
+        if self.optional_chain {
+            w.write_str("?.")?;
         }
-        res.push('.');
-        res.push_str(&decl.name.text);
-
-        res
+        if !is_safe_id(&self.name.text) || is_reserved(&self.name.text) {
+            return write!(w, "[{}]", to_js_string(&self.name.text));
+        }
+        if self.optional_chain {
+            return w.write_str(&self.name.text);
+        }
+        write!(w, ".{}", self.name.text)
     }
 }
 
-impl From<&BinaryExpression> for String {
-    fn from(expr: &BinaryExpression) -> Self {
+impl Render for BinaryExpression {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
         let BinaryExpression {
             left,
             right,
             operator,
-        } = expr;
-        let mut res = String::new();
+        } = self;
 
         let left_str: String = left.deref().into();
-        let right_str: String = right.deref().into();
         assert!(!left_str.contains('\n'));
-        assert!(!right_str.contains('\n'));
 
-        res.push_str(&left_str);
-        res.push(' ');
-        res.push_str(operator.into());
-        res.push(' ');
-        res.push_str(&right_str);
+        let operator: &str = operator.into();
+        write!(w, "{} {} ", left_str, operator)?;
+        // long conditional expressions continue on the next lines
+        right.render(w)
+    }
+}
 
-        res
+impl Render for CallExpression {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        self.expression.render(w)?;
+        w.write_str("(")?;
+        render_list(self.arguments.iter(), ", ", w)?;
+        w.write_str(")")
     }
 }
-impl From<&CallExpression> for String {
-    fn from(call_expr: &CallExpression) -> Self {
-        let mut res = String::new();
-        let callee_str: String = call_expr.expression.deref().into();
-        res.push_str(&callee_str);
-        res.push('(');
-        for (ind, arg) in call_expr.arguments.iter().enumerate() {
-            if ind > 0 {
-                res.push_str(", ");
-            }
-            let arg_str: String = arg.deref().into();
-            res.push_str(&arg_str);
-        }
-        res.push(')');
-        res
+
+impl Render for NewExpression {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str("new ")?;
+        self.expression.render(w)?;
+        w.write_str("(")?;
+        render_list(self.arguments.iter(), ", ", w)?;
+        w.write_str(")")
     }
 }
-impl From<&ElementAccessExpression> for String {
-    fn from(expr: &ElementAccessExpression) -> Self {
-        let mut res = String::new();
-        let obj_str: String = expr.expression.deref().into();
-        res.push_str(&obj_str);
-        res.push('[');
-        let prop_str: String = expr.argument.deref().into();
-        res.push_str(&prop_str);
-        res.push(']');
-        res
+
+impl Render for ElementAccessExpression {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        render_operand(&self.expression, w)?;
+        w.write_str("[")?;
+        self.argument.render(w)?;
+        w.write_str("]")
     }
 }
 
-impl From<&PrefixUnaryExpression> for String {
-    fn from(unary_expr: &PrefixUnaryExpression) -> Self {
-        let mut res = String::new();
-        res.push_str((&unary_expr.operator).into());
-        let expr_str: String = unary_expr.operand.deref().into();
-        res.push_str(&expr_str);
-        res
+impl Render for PrefixUnaryExpression {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        let operator: &str = (&self.operator).into();
+        w.write_str(operator)?;
+        render_operand(&self.operand, w)
     }
 }
-impl From<&ConditionalExpression> for String {
-    fn from(cond: &ConditionalExpression) -> Self {
-        let cond_str: String = cond.condition.deref().into();
-        let when_true_str: String = cond.when_true.deref().into();
-        let when_false_str: String = cond.when_false.deref().into();
+
+impl Render for ConditionalExpression {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        let cond_str: String = self.condition.deref().into();
+        let when_true_str: String = self.when_true.deref().into();
+        let when_false_str: String = self.when_false.deref().into();
         let full_len = cond_str.len() + when_true_str.len() + when_false_str.len() + 6;
-        let mut res: String = String::new();
         if full_len <= 80 {
-            res.push_str(&cond_str);
-            res.push_str(" ? ");
-            res.push_str(&when_true_str);
-            res.push_str(" : ");
-            res.push_str(&when_false_str);
-        } else {
-            res.push_str(&cond_str);
-            res.push_str("\n  ? ");
-            res.push_str(&when_true_str);
-            res.push_str("\n  : ");
-            res.push_str(&when_false_str);
+            return write!(w, "{} ? {} : {}", cond_str, when_true_str, when_false_str);
         }
-
-        res
+        w.write_str(&cond_str)?;
+        w.indent();
+        write!(w, "\n? {}\n: {}", when_true_str, when_false_str)?;
+        w.dedent();
+        Ok(())
     }
 }
-impl From<&Expression> for String {
-    fn from(expr: &Expression) -> Self {
-        match expr {
-            Expression::Identifier(id) => id.text.to_string(),
-            Expression::Null => "null".to_string(),
-            Expression::Undefined => "undefined".to_string(),
-            Expression::False => "false".to_string(),
-            Expression::True => "true".to_string(),
-            Expression::BinaryExpression(expr) => expr.into(),
-            Expression::CallExpression(call_exrp) => call_exrp.deref().into(),
-            Expression::PropertyAccessExpression(proeprty_access_expr) => {
-                proeprty_access_expr.into()
+
+impl Render for Expression {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        match self {
+            Expression::Identifier(id) => w.write_str(&id.text),
+            Expression::Null => w.write_str("null"),
+            Expression::Undefined => w.write_str("undefined"),
+            Expression::False => w.write_str("false"),
+            Expression::True => w.write_str("true"),
+            Expression::BinaryExpression(expr) => expr.render(w),
+            Expression::CallExpression(call_expr) => call_expr.render(w),
+            Expression::PropertyAccessExpression(property_access_expr) => {
+                property_access_expr.render(w)
             }
             Expression::ParenthesizedExpression(expr) => {
-                let expr_str: String = expr.deref().into();
-                format!("({})", expr_str)
+                w.write_str("(")?;
+                expr.render(w)?;
+                w.write_str(")")
             }
             Expression::ArrayLiteralExpression(exprs) => {
-                match exprs[..] {
-                    [] => "[]".into(),
-                    _ => unreachable!()
-                }
-            },
-            Expression::ObjectLiteralExpression(props) => object_literal_to_string(props),
-            Expression::NewExpression(_) => todo!(),
-            Expression::NumericLiteral(f64) => f64.to_string(),
-            Expression::StringLiteral(str) => to_js_string(str),
+                w.write_str("[")?;
+                render_list(exprs.iter(), ", ", w)?;
+                w.write_str("]")
+            }
+            Expression::ObjectLiteralExpression(props) => render_object_literal(props, w),
+            Expression::NewExpression(new_expr) => new_expr.render(w),
+            Expression::NumericLiteral(f64) if f64.is_nan() => w.write_str("NaN"),
+            Expression::NumericLiteral(f64) if *f64 == f64::INFINITY => w.write_str("Infinity"),
+            Expression::NumericLiteral(f64) if *f64 == f64::NEG_INFINITY => {
+                w.write_str("-Infinity")
+            }
+            Expression::NumericLiteral(f64) => write!(w, "{}", f64),
+            Expression::StringLiteral(str) => w.write_str(&to_js_string(str)),
             Expression::ElementAccessExpression(element_access_expr) => {
-                element_access_expr.deref().into()
+                element_access_expr.render(w)
             }
-            Expression::PrefixUnaryExpression(unary_expr) => unary_expr.deref().into(),
-            Expression::ConditionalExpression(cond) => cond.deref().into(),
+            Expression::PrefixUnaryExpression(unary_expr) => unary_expr.render(w),
+            Expression::ConditionalExpression(cond) => cond.render(w),
             Expression::Typeof(expr) => {
-                let mut res = String::new();
-                res.push_str("typeof ");
-                let inner_str: String = expr.deref().into();
-                res.push_str(&inner_str);
-                res
-            },
-            
-        }
-    }
-}
-
-fn object_literal_to_string(props: &[Rc<ObjectLiteralMember>]) -> String {
-    match props {
-        [] => "{}".into(),
-        props => {
-            let mut res = String::new();
-            res.push('{');
-            res.push('\n');
-            for p in props {
-                match &**p {
-                    ObjectLiteralMember::PropertyAssignment(prop, value) => {
-                        res.push(' ');
-                        res.push(' ');
-                        res.push_str(&prop.text);
-                        res.push(':');
-                        res.push(' ');
-                        let value_str: String = value.deref().into();
-                        res.push_str(&value_str);
-                        res.push(',');
-                        res.push('\n');
-                    }
-                }
+                w.write_str("typeof ")?;
+                render_operand(expr, w)
+            }
+            Expression::AsExpression(expr, t) => {
+                render_operand(expr, w)?;
+                w.write_str(" as ")?;
+                t.render(w)
+            }
+            Expression::SpreadElement(expr) => {
+                w.write_str("...")?;
+                expr.render(w)
+            }
+        }
+    }
+}
+
+fn render_object_literal<W: Write>(
+    props: &[Arc<ObjectLiteralMember>],
+    w: &mut IndentWriter<W>,
+) -> fmt::Result {
+    if props.is_empty() {
+        return w.write_str("{}");
+    }
+    w.write_str("{\n")?;
+    w.indent();
+    for p in props {
+        match &**p {
+            ObjectLiteralMember::PropertyAssignment(prop, value) => {
+                write!(w, "{}: ", to_property_name(&prop.text))?;
+                value.render(w)?;
+            }
+            ObjectLiteralMember::SpreadAssignment(value) => {
+                w.write_str("...")?;
+                value.render(w)?;
+            }
+            ObjectLiteralMember::ShorthandPropertyAssignment(name) => {
+                w.write_str(&name.text)?;
             }
-            res.push('}');
-            res
         }
+        w.write_str(",\n")?;
+    }
+    w.dedent();
+    w.write_str("}")
+}
+
+#[cfg(test)]
+mod test_array_literal {
+    use super::*;
+
+    #[test]
+    fn it_renders_empty_arrays() {
+        assert_eq!(String::from(&Expression::array(vec![])), "[]");
+    }
+
+    #[test]
+    fn it_renders_literal_elements() {
+        let expr = Expression::array(vec![
+            Arc::new(1f64.into()),
+            Arc::new(StringLiteral::from("a").into()),
+            Arc::new(Expression::Null),
+        ]);
+        assert_eq!(String::from(&expr), "[1, \"a\", null]");
+    }
+
+    #[test]
+    fn it_renders_spread_elements() {
+        let items = Expression::from(Identifier::new("message")).into_prop("items");
+        let expr = Expression::array(vec![
+            Arc::new(items.into_spread()),
+            Arc::new(Identifier::new("item").into()),
+        ]);
+        assert_eq!(String::from(&expr), "[...message.items, item]");
+    }
+}
+
+#[cfg(test)]
+mod test_object_literal {
+    use super::*;
+    #[test]
+    fn it_renders_spread_assignment() {
+        let expr = Expression::ObjectLiteralExpression(vec![
+            Arc::new(ObjectLiteralMember::PropertyAssignment(
+                Arc::new("x".into()),
+                Arc::new(0f64.into()),
+            )),
+            Arc::new(ObjectLiteralMember::SpreadAssignment(Arc::new(
+                "init".into(),
+            ))),
+        ]);
+        let rendered: String = (&expr).into();
+        assert_eq!(rendered, "{\n  x: 0,\n  ...init,\n}");
+    }
+
+    #[test]
+    fn it_renders_empty_objects_inline() {
+        let expr = Expression::ObjectLiteralExpression(vec![]);
+        assert_eq!(String::from(&expr), "{}");
+    }
+
+    #[test]
+    fn it_renders_properties_and_shorthand_properties() {
+        let expr = Expression::ObjectLiteralExpression(vec![
+            ObjectLiteralMember::property("sku", Expression::from(StringLiteral::from(""))),
+            ObjectLiteralMember::property("quantity", Expression::from(0f64)),
+            Arc::new(ObjectLiteralMember::ShorthandPropertyAssignment(Arc::new(
+                "labels".into(),
+            ))),
+        ]);
+        assert_eq!(
+            String::from(&expr),
+            "{\n  sku: \"\",\n  quantity: 0,\n  labels,\n}"
+        );
+    }
+
+    #[test]
+    fn it_quotes_keys_that_are_not_identifiers() {
+        let expr = Expression::ObjectLiteralExpression(vec![
+            ObjectLiteralMember::property("a-b", Expression::from(1f64)),
+            ObjectLiteralMember::property("1", Expression::from(2f64)),
+            ObjectLiteralMember::property("$ok_", Expression::from(3f64)),
+        ]);
+        assert_eq!(
+            String::from(&expr),
+            "{\n  \"a-b\": 1,\n  \"1\": 2,\n  $ok_: 3,\n}"
+        );
+    }
+
+    #[test]
+    fn it_indents_nested_literals_inside_of_calls() {
+        let nested = Expression::ObjectLiteralExpression(vec![
+            ObjectLiteralMember::property("messages", Expression::ObjectLiteralExpression(vec![])),
+            ObjectLiteralMember::property(
+                "enums",
+                Expression::ObjectLiteralExpression(vec![ObjectLiteralMember::property(
+                    "Role",
+                    Expression::from(0f64),
+                )]),
+            ),
+        ]);
+        let call = Expression::from(Identifier::new("compare")).into_call(vec![
+            Arc::new(Identifier::new("local").into()),
+            Arc::new(nested),
+        ]);
+        assert_eq!(
+            String::from(&call),
+            "compare(local, {\n  messages: {},\n  enums: {\n    Role: 0,\n  },\n})"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_binary_expression {
+    use super::*;
+
+    fn id(name: &str) -> Arc<Expression> {
+        Arc::new(Identifier::new(name).into())
+    }
+
+    #[test]
+    fn it_renders_parenthesized_operands() {
+        let field_number = BinaryOperator::UnsignedRightShift
+            .apply(id("tag"), Arc::new(3f64.into()))
+            .into_parentheses();
+        let is_first =
+            BinaryOperator::StrictEqual.apply(field_number.into(), Arc::new(1f64.into()));
+        assert_eq!(String::from(&is_first), "(tag >>> 3) === 1");
+    }
+
+    #[test]
+    fn it_renders_arithmetic_and_bitwise_operators() {
+        for (operator, rendered) in [
+            (BinaryOperator::Plus, "a + b"),
+            (BinaryOperator::Minus, "a - b"),
+            (BinaryOperator::Multiply, "a * b"),
+            (BinaryOperator::Divide, "a / b"),
+            (BinaryOperator::BinaryAnd, "a & b"),
+            (BinaryOperator::BinaryOr, "a | b"),
+            (BinaryOperator::GreaterThan, "a > b"),
+            (BinaryOperator::StrictNotEqual, "a !== b"),
+        ] {
+            assert_eq!(String::from(&operator.apply(id("a"), id("b"))), rendered);
+        }
+    }
+
+    #[test]
+    fn it_renders_assignment_statements() {
+        let assignment: Statement = BinaryOperator::Assign
+            .apply(
+                Arc::new(id("result").prop("x")),
+                Arc::new(Expression::from("reader").into_method_call("int32", vec![])),
+            )
+            .into();
+        assert_eq!(String::from(&assignment), "result.x = reader.int32()");
+    }
+
+    #[test]
+    fn it_continues_long_conditional_operands_on_the_next_lines() {
+        let value: Arc<Expression> = Arc::new(id("json").prop("order_status"));
+        let condition = BinaryOperator::StrictEqual.apply(
+            value.type_of().into(),
+            Arc::new(StringLiteral::from("string").into()),
+        );
+        let status = id("Status");
+        let conditional = Expression::conditional(
+            condition.into(),
+            status.element(Arc::clone(&value)).into(),
+            value,
+        );
+        let mut block = Block::new();
+        block.push_statement(
+            BinaryOperator::Assign
+                .apply(
+                    Arc::new(id("message").prop("order_status")),
+                    Arc::new(conditional),
+                )
+                .into(),
+        );
+        assert_eq!(
+            String::from(&Statement::from(block)),
+            [
+                "{",
+                "  message.order_status = typeof json.order_status === \"string\"",
+                "    ? Status[json.order_status]",
+                "    : json.order_status",
+                "}",
+            ]
+            .join("\n")
+        );
     }
 }
 
-impl From<&VariableDeclarationList> for String {
-    fn from(vars: &VariableDeclarationList) -> Self {
-        assert!(!vars.declarations.is_empty());
-        let mut res = String::new();
-        match vars.kind {
-            VariableKind::Let => res.push_str("let "),
-            VariableKind::Const => res.push_str("const "),
+#[cfg(test)]
+mod test_call_expression {
+    use super::*;
+
+    #[test]
+    fn it_renders_chained_member_calls() {
+        let call = Expression::from("a")
+            .into_prop("b")
+            .into_method_call("c", vec![Arc::new(1f64.into()), Arc::new(2f64.into())]);
+        assert_eq!(String::from(&call), "a.b.c(1, 2)");
+
+        let chained = call.into_method_call("d", vec![Arc::new(StringLiteral::from("x").into())]);
+        assert_eq!(String::from(&chained), "a.b.c(1, 2).d(\"x\")");
+    }
+
+    #[test]
+    fn it_renders_optional_chains() {
+        let expr = Expression::from("a")
+            .into_optional_prop("b")
+            .into_method_call("c", vec![]);
+        assert_eq!(String::from(&expr), "a?.b.c()");
+
+        let reserved = Expression::from("a").into_optional_prop("default");
+        assert_eq!(String::from(&reserved), "a?.[\"default\"]");
+    }
+
+    #[test]
+    fn it_wraps_objects_of_member_access() {
+        let sum: Arc<Expression> = BinaryOperator::Plus
+            .apply(Arc::new("a".into()), Arc::new(1f64.into()))
+            .into();
+        assert_eq!(String::from(&sum.prop("toString")), "(a + 1).toString");
+        let number: Arc<Expression> = Arc::new(1f64.into());
+        assert_eq!(String::from(&number.prop("toFixed")), "(1).toFixed");
+    }
+}
+
+impl Render for VariableDeclarationList {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        assert!(!self.declarations.is_empty());
+        render_modifiers(&self.modifiers, w)?;
+        match self.kind {
+            VariableKind::Let => w.write_str("let ")?,
+            VariableKind::Const => w.write_str("const ")?,
+            VariableKind::Var => w.write_str("var ")?,
         }
-        for (ind, var) in vars.declarations.iter().enumerate() {
+        for (ind, var) in self.declarations.iter().enumerate() {
+            if ind == 1 {
+                w.indent();
+            }
             if ind > 0 {
-                res.push_str(",\n  ");
+                w.write_str(",\n")?;
             }
-            res.push_str(&var.name.text);
+            w.write_str(&var.name.text)?;
             if let Some(t) = &var.var_type {
-                let type_str: String = t.deref().into();
-                res.push_str(": ");
-                res.push_str(&type_str);
+                w.write_str(": ")?;
+                t.render(w)?;
+            }
+            if let Some(initializer) = &var.initializer {
+                w.write_str(" = ")?;
+                initializer.render(w)?;
             }
-            res.push_str(" = ");
-
-            let expr_str: String = var.initializer.deref().into();
-            res.push_str(&expr_str);
         }
-        res
+        if self.declarations.len() > 1 {
+            w.dedent();
+        }
+        Ok(())
     }
 }
 
-impl From<&IfStatement> for String {
-    fn from(expr: &IfStatement) -> Self {
-        let mut res = String::new();
-        res.push_str("if (");
-        let test_expr_str: String = expr.expression.deref().into();
-        res.push_str(&test_expr_str);
-        res.push(')');
-        match *expr.then_statement {
+/// Writes the statement on the next line, one level deeper,
+/// for the bodies of `if` and `for` that are not blocks.
+fn render_nested_statement<W: Write>(
+    statement: &Statement,
+    w: &mut IndentWriter<W>,
+) -> fmt::Result {
+    w.write_str("\n")?;
+    w.indent();
+    statement.render(w)?;
+    w.dedent();
+    Ok(())
+}
+
+impl Render for IfStatement {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str("if (")?;
+        self.expression.render(w)?;
+        w.write_str(")")?;
+        match *self.then_statement {
             Statement::Empty => unreachable!(),
             Statement::Block(_) => {
-                res.push(' ');
-                let then_expr_str: String = expr.then_statement.deref().into();
-                res.push_str(&then_expr_str);
+                w.write_str(" ")?;
+                self.then_statement.render(w)?;
             }
             _ => {
-                res.push_str("\n");
-                tab_lines(&mut res, expr.then_statement.deref().into());
+                render_nested_statement(&self.then_statement, w)?;
+                if self.else_statement.is_some() {
+                    w.write_str("\n")?;
+                }
             }
         }
-        if let Some(else_statement) = &expr.else_statement {
-            res.push_str(" else ");
-            let else_expr_str: String = else_statement.deref().into();
-            res.push_str(&else_expr_str);
+        if let Some(else_statement) = &self.else_statement {
+            w.write_str(if matches!(*self.then_statement, Statement::Block(_)) {
+                " else "
+            } else {
+                "else "
+            })?;
+            else_statement.render(w)?;
         }
-        return res;
+        Ok(())
     }
 }
 
-impl From<&Block> for String {
-    fn from(block: &Block) -> Self {
-        let mut res = String::new();
-        res.push_str("{\n");
-        for s in block.statements.iter() {
-            tab_lines(&mut res, s.deref().into());
+impl Render for Block {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str("{\n")?;
+        w.indent();
+        for s in self.statements.iter() {
+            s.render(w)?;
+            w.write_str("\n")?;
         }
-        res.push_str("}");
-        res
+        w.dedent();
+        w.write_str("}")
     }
 }
 
-impl From<&WhileStatement> for String {
-    fn from(whl: &WhileStatement) -> Self {
-        let mut res = String::new();
+#[cfg(test)]
+mod test_variable_statement {
+    use super::*;
+
+    #[test]
+    fn it_renders_declarations() {
+        let x = VariableDeclarationList::declare_const(Identifier::new("x").into(), 0f64.into());
+        assert_eq!(String::from(&x), "const x = 0");
+        let y = VariableDeclarationList::declare(
+            VariableKind::Let,
+            Identifier::new("y").into(),
+            Some(Type::String.into()),
+            None,
+        );
+        assert_eq!(String::from(&y), "let y: string");
+        let z = VariableDeclarationList::declare(
+            VariableKind::Var,
+            Identifier::new("z").into(),
+            None,
+            Some(1f64.into()),
+        );
+        assert_eq!(String::from(&z), "var z = 1");
+    }
 
-        res.push_str("while (");
-        let cond_str: String = whl.condition.deref().into();
-        res.push_str(&cond_str);
-        res.push_str(") ");
-        let block_str: String = whl.statement.deref().into();
-        res.push_str(&block_str);
+    #[test]
+    fn it_renders_nested_blocks() {
+        let mut inner = Block::new();
+        inner.push_statement(
+            VariableDeclarationList::declare_let(Identifier::new("y").into(), 1f64.into()).into(),
+        );
+        let mut outer = Block::new();
+        outer.push_statement(
+            VariableDeclarationList::declare_const(Identifier::new("x").into(), 0f64.into()).into(),
+        );
+        outer.push_statement(inner.into());
+        assert_eq!(
+            String::from(&outer),
+            "{\n  const x = 0\n  {\n    let y = 1\n  }\n}"
+        );
+    }
+}
 
-        res
+impl Render for WhileStatement {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str("while (")?;
+        self.condition.render(w)?;
+        w.write_str(") ")?;
+        self.statement.render(w)
     }
 }
-impl From<&ForStatement> for String {
-    fn from(for_stmt: &ForStatement) -> Self {
+
+impl Render for ForStatement {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
         let ForStatement {
             initializer,
             condition,
             incrementor,
             statement,
-        } = for_stmt;
-        let mut res = String::new();
-
-        res.push_str("for (");
-        let init_str: String = initializer.deref().into();
-        res.push_str(&init_str);
-        res.push(';');
-        res.push(' ');
-        let condition_str: String = condition.deref().into();
-        res.push_str(&condition_str);
-        res.push(';');
-        res.push(' ');
-        let incrementor_str: String = incrementor.deref().into();
-        res.push_str(&incrementor_str);
-        res.push(')');
+        } = self;
+
+        w.write_str("for (")?;
+        initializer.render(w)?;
+        w.write_str("; ")?;
+        condition.render(w)?;
+        w.write_str("; ")?;
+        incrementor.render(w)?;
+        w.write_str(")")?;
         match statement.deref() {
-            Statement::Empty => {
-                res.push(';');
-                return res;
-            }
+            Statement::Empty => w.write_str(";"),
             Statement::Block(_) => {
-                res.push(' ');
-            }
-            _ => {
-                res.push('\n');
-                res.push(' ');
-                res.push(' ');
+                w.write_str(" ")?;
+                statement.render(w)
             }
+            _ => render_nested_statement(statement, w),
         }
-        let stmt_str: String = statement.deref().into();
+    }
+}
+
+impl Render for ForInStatement {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        write!(w, "for (const {} in ", self.initializer.text)?;
+        self.expression.render(w)?;
+        w.write_str(") ")?;
+        self.statement.render(w)
+    }
+}
+
+#[cfg(test)]
+mod test_control_flow {
+    use super::*;
+
+    fn id(name: &str) -> Arc<Expression> {
+        Arc::new(Identifier::new(name).into())
+    }
+
+    fn tag_is_zero() -> Arc<Expression> {
+        BinaryOperator::StrictEqual
+            .apply(id("tag"), Arc::new(0f64.into()))
+            .into()
+    }
 
-        res.push_str(&stmt_str);
+    #[test]
+    fn it_renders_if_statements() {
+        let if_break = IfStatement {
+            expression: tag_is_zero(),
+            then_statement: Statement::Break.into(),
+            else_statement: None,
+        };
+        assert_eq!(String::from(&if_break), "if (tag === 0)\n  break;");
 
-        res
+        let mut then_block = Block::new();
+        then_block.push_statement(Statement::Break);
+        let if_else = IfStatement {
+            expression: tag_is_zero(),
+            then_statement: Statement::Block(then_block).into(),
+            else_statement: Some(
+                Statement::ReturnStatement(Some(Identifier::new("tag").into())).into(),
+            ),
+        };
+        assert_eq!(
+            String::from(&if_else),
+            "if (tag === 0) {\n  break;\n} else return tag"
+        );
+    }
+
+    #[test]
+    fn it_renders_while_statements() {
+        let mut while_stmt =
+            WhileStatement::new(BinaryOperator::LessThan.apply(id("pos"), id("end")).into());
+        while_stmt.push_statement(Statement::Break);
+        assert_eq!(
+            String::from(&while_stmt),
+            "while (pos < end) {\n  break;\n}"
+        );
+    }
+
+    #[test]
+    fn it_renders_for_statements() {
+        let i = Arc::new(Identifier::new("i"));
+        let empty = ForStatement::for_each(Arc::clone(&i), id("items"));
+        assert_eq!(
+            String::from(&empty),
+            "for (let i = 0; i < items.length; ++i);"
+        );
+
+        let mut for_stmt = ForStatement::for_each(i, id("items"));
+        for_stmt.push_statement(Statement::Break);
+        assert_eq!(
+            String::from(&for_stmt),
+            "for (let i = 0; i < items.length; ++i)\n  break;"
+        );
+        for_stmt.push_statement(Statement::ReturnStatement(None));
+        assert_eq!(
+            String::from(&for_stmt),
+            "for (let i = 0; i < items.length; ++i) {\n  break;\n  return\n}"
+        );
+    }
+
+    #[test]
+    fn it_renders_for_in_statements() {
+        let mut for_in = ForInStatement::new(Identifier::new("k").into(), id("labels"));
+        for_in.push_statement(
+            Expression::CallExpression(CallExpression {
+                expression: id("use"),
+                arguments: vec![id("k")],
+            })
+            .into(),
+        );
+        assert_eq!(
+            String::from(&Statement::from(for_in)),
+            "for (const k in labels) {\n  use(k)\n}"
+        );
     }
 }
-impl From<&CaseClause> for String {
-    fn from(c: &CaseClause) -> Self {
-        let mut res = String::new();
-        res.push_str("case ");
-        let expr_str: String = c.expression.deref().into();
-        res.push_str(&expr_str);
-        res.push_str(": {\n");
-        for s in &c.statements {
-            tab_lines(&mut res, s.into());
+
+impl Render for CaseClause {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str("case ")?;
+        self.expression.render(w)?;
+        w.write_str(": {\n")?;
+        w.indent();
+        for s in &self.statements {
+            s.render(w)?;
+            w.write_str("\n")?;
         }
-        res.push('}');
-        res
+        w.dedent();
+        w.write_str("}")
     }
 }
-impl From<&DefaultClause> for String {
-    fn from(d: &DefaultClause) -> Self {
-        let mut res = String::new();
-        res.push_str("default:\n");
-        for s in &d.statements {
-            tab_lines(&mut res, s.into());
+
+impl Render for DefaultClause {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str("default:\n")?;
+        w.indent();
+        for s in &self.statements {
+            s.render(w)?;
+            w.write_str("\n")?;
         }
-        res
+        w.dedent();
+        Ok(())
     }
 }
 
-fn tab_lines(dst: &mut String, src: String) {
-    for line in src.lines() {
-        dst.push(' ');
-        dst.push(' ');
-        dst.push_str(&line);
-        dst.push('\n');
+impl Render for SwitchStatement {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        w.write_str("switch (")?;
+        self.expression.render(w)?;
+        w.write_str(") {\n")?;
+        w.indent();
+        for case in &self.cases {
+            case.render(w)?;
+            w.write_str("\n")?;
+        }
+        self.default.render(w)?;
+        w.dedent();
+        w.write_str("}")
     }
 }
 
-impl From<&SwitchStatement> for String {
-    fn from(s: &SwitchStatement) -> Self {
-        let mut res = String::new();
-        res.push_str("switch (");
-        let expr_str: String = s.expression.deref().into();
-        res.push_str(&expr_str);
-        res.push_str(") {\n");
-        for case in &s.cases {
-            tab_lines(&mut res, case.into());
+#[cfg(test)]
+mod test_switch_statement {
+    use super::*;
+
+    fn id(name: &str) -> Arc<Expression> {
+        Arc::new(Identifier::new(name).into())
+    }
+
+    #[test]
+    fn it_renders_cases_and_default() {
+        let mut switch = SwitchStatement::new(
+            BinaryOperator::UnsignedRightShift
+                .apply(id("tag"), Arc::new(3f64.into()))
+                .into(),
+            vec![
+                id("r").method_call("skipType", vec![id("tag")]).into(),
+                Statement::Break,
+            ]
+            .into(),
+        );
+        for (number, method) in [(1f64, "string"), (2f64, "int32")] {
+            let mut case = CaseClause::new(Arc::new(number.into()));
+            case.push_statement(
+                BinaryOperator::Assign
+                    .apply(
+                        Arc::new(Expression::from(Identifier::new("message")).into_prop(method)),
+                        id("r").method_call(method, vec![]).into(),
+                    )
+                    .into(),
+            );
+            case.push_statement(Statement::Break);
+            switch.add_case(case);
         }
-        tab_lines(&mut res, s.default.deref().into());
-        res.push('}');
+        assert_eq!(
+            String::from(&Statement::from(switch)),
+            [
+                "switch (tag >>> 3) {",
+                "  case 1: {",
+                "    message.string = r.string()",
+                "    break;",
+                "  }",
+                "  case 2: {",
+                "    message.int32 = r.int32()",
+                "    break;",
+                "  }",
+                "  default:",
+                "    r.skipType(tag)",
+                "    break;",
+                "}",
+            ]
+            .join("\n")
+        );
+    }
 
-        res
+    #[test]
+    fn it_renders_a_switch_with_only_default() {
+        let switch = SwitchStatement::new(id("kind"), vec![Statement::Break].into());
+        assert_eq!(
+            String::from(&Statement::from(switch)),
+            "switch (kind) {\n  default:\n    break;\n}"
+        );
     }
 }
-impl From<&Statement> for String {
-    fn from(statement: &Statement) -> Self {
-        match statement {
-            Statement::ImportDeclaration(import_declaration) => (import_declaration.deref()).into(),
-            Statement::EnumDeclaration(enum_declaration) => (enum_declaration.deref()).into(),
+
+impl Render for Statement {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        match self {
+            Statement::ImportDeclaration(import_declaration) => import_declaration.render(w),
+            Statement::EnumDeclaration(enum_declaration) => enum_declaration.render(w),
             Statement::InterfaceDeclaration(interface_declaration) => {
-                (interface_declaration.deref()).into()
+                interface_declaration.render(w)
             }
-            Statement::FunctionDeclaration(func_decl) => func_decl.deref().into(),
+            Statement::FunctionDeclaration(func_decl) => func_decl.render(w),
+            Statement::TypeAliasDeclaration(type_alias) => type_alias.render(w),
+            Statement::ClassDeclaration(class_decl) => class_decl.render(w),
+            Statement::NamespaceDeclaration(namespace) => namespace.render(w),
             Statement::ReturnStatement(Some(expression)) => {
-                let mut res = String::new();
-                res.push_str("return ");
-                let expr_str: String = expression.into();
-                res.push_str(expr_str.as_str());
-                res
-            }
-            &Statement::ReturnStatement(None) => "return".to_string(),
-            Statement::VariableStatement(var_decl) => var_decl.deref().into(),
-            Statement::IfStatement(if_stmt) => if_stmt.deref().into(),
-            Statement::Block(block) => block.deref().into(),
-            Statement::Expression(expr) => expr.deref().into(),
-            Statement::Empty => ";".into(),
-            Statement::For(for_stmt) => for_stmt.deref().into(),
-            Statement::While(whl) => whl.deref().into(),
-            Statement::Break => "break;".into(),
-            Statement::Switch(s) => s.deref().into(),
-        }
-    }
-}
-
-impl From<&File> for String {
-    fn from(file: &File) -> Self {
-        let mut res = String::new();
-        let mut last_statement: Option<&Statement> = None;
-        for statement in &file.ast.statements {
-            // Addition of vertical space between declarations
-            match (statement, last_statement) {
-                (_, None) => {}
-                (Statement::EnumDeclaration(_), _) => res.push_str("\n"),
-                (Statement::InterfaceDeclaration(_), _) => res.push_str("\n"),
-                (Statement::ImportDeclaration(_), Some(Statement::ImportDeclaration(_))) => {}
-                (Statement::ImportDeclaration(_), _) => res.push_str("\n"),
-                (Statement::FunctionDeclaration(_), _) => res.push_str("\n"),
-                (_, Some(Statement::ReturnStatement(_))) => res.push_str("\n"),
-                (&Statement::ReturnStatement(_), _) => {}
-                _ => {}
+                w.write_str("return ")?;
+                expression.render(w)
+            }
+            Statement::ReturnStatement(None) => w.write_str("return"),
+            Statement::Throw(expression) => {
+                w.write_str("throw ")?;
+                expression.render(w)
             }
-            let statement_string: String = statement.into();
-            res.push_str(&statement_string);
-            res.push('\n');
-            last_statement = Some(statement)
+            Statement::VariableStatement(var_decl) => var_decl.render(w),
+            Statement::IfStatement(if_stmt) => if_stmt.render(w),
+            Statement::Block(block) => block.render(w),
+            Statement::Expression(expr) => expr.render(w),
+            Statement::Empty => w.write_str(";"),
+            Statement::For(for_stmt) => for_stmt.render(w),
+            Statement::ForIn(for_in) => for_in.render(w),
+            Statement::While(whl) => whl.render(w),
+            Statement::Break => w.write_str("break;"),
+            Statement::Switch(s) => s.render(w),
+            Statement::Verbatim(code) => w.write_str(code.trim_end()),
+        }
+    }
+}
+
+impl Render for NamespaceDeclaration {
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        render_modifiers(&self.modifiers, w)?;
+        write!(w, "namespace {}", self.name.text)?;
+        w.write_str(" {\n")?;
+        w.indent();
+        render_statements(&self.statements, w)?;
+        w.dedent();
+        w.write_str("}")
+    }
+}
+
+/// Renders top-level statements of a file or a namespace, a statement per line.
+fn render_statements<'a, W: Write>(
+    statements: impl IntoIterator<Item = &'a Statement>,
+    w: &mut IndentWriter<W>,
+) -> fmt::Result {
+    let mut last_statement: Option<&Statement> = None;
+    for statement in statements {
+        // Addition of vertical space between declarations
+        let blank_line = match (statement, last_statement) {
+            (_, None) => false,
+            (Statement::EnumDeclaration(_), _) => true,
+            (Statement::InterfaceDeclaration(_), _) => true,
+            (Statement::NamespaceDeclaration(_), _) => true,
+            (Statement::ClassDeclaration(_), _) => true,
+            (Statement::TypeAliasDeclaration(_), _) => true,
+            (Statement::VariableStatement(list), _) if !list.modifiers.is_empty() => true,
+            (Statement::ImportDeclaration(_), Some(Statement::ImportDeclaration(_))) => false,
+            (Statement::ImportDeclaration(_), _) => true,
+            (_, Some(Statement::ImportDeclaration(_))) => true,
+            (Statement::FunctionDeclaration(_), _) => true,
+            (_, Some(Statement::ReturnStatement(_))) => true,
+            _ => false,
+        };
+        if blank_line {
+            w.write_str("\n")?;
         }
-        res
+        statement.render(w)?;
+        w.write_str("\n")?;
+        last_statement = Some(statement)
+    }
+    Ok(())
+}
+
+impl Render for File {
+    /// Imports are rendered first, sorted by their module specifiers,
+    /// so the output does not depend on the order they were added in.
+    /// The rest of the statements keep their order.
+    fn render<W: Write>(&self, w: &mut IndentWriter<W>) -> fmt::Result {
+        let mut imports: Vec<&Statement> = self
+            .ast
+            .statements
+            .iter()
+            .filter(|statement| matches!(statement, Statement::ImportDeclaration(_)))
+            .collect();
+        imports.sort_by_key(|statement| match statement {
+            Statement::ImportDeclaration(import) => &import.string_literal.text,
+            _ => unreachable!(),
+        });
+        let rest = self
+            .ast
+            .statements
+            .iter()
+            .filter(|statement| !matches!(statement, Statement::ImportDeclaration(_)));
+        render_statements(imports.into_iter().chain(rest), w)
+    }
+}
+
+#[cfg(test)]
+mod test_golden {
+    use crate::proto::compiler::ts::{
+        ast::{Folder, FolderEntry},
+        options::{Emit, Layout, Options},
+        test_utils::{compile_sources, render_file_at},
+    };
+
+    fn compile_shop(layout: Layout) -> Folder {
+        compile_shop_with(Options {
+            layout,
+            ..Options::default()
+        })
+    }
+
+    fn compile_shop_with(options: Options) -> Folder {
+        compile_sources(
+            &[("shop/order.proto", include_str!("golden/shop.proto"))],
+            &options,
+        )
+    }
+
+    /// Every file of the folder, preceded by a `// path/to/file.ts` line
+    fn render_tree(folder: &Folder, path: &str, res: &mut String) {
+        for entry in &folder.entries {
+            match entry {
+                FolderEntry::File(file) => {
+                    res.push_str(&format!("// {}{}.ts\n", path, file.name));
+                    res.push_str(&String::from(file.as_ref()));
+                }
+                FolderEntry::Folder(subfolder) => {
+                    render_tree(subfolder, &format!("{}{}/", path, subfolder.name), res)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_renders_the_golden_file() {
+        let folder = compile_shop(Layout::File);
+        let rendered = render_file_at(&folder, &["shop", "order"]);
+        assert_eq!(rendered, include_str!("golden/shop.ts"));
+    }
+
+    #[test]
+    fn it_renders_the_golden_folder() {
+        let folder = compile_shop(Layout::Folder);
+        let mut rendered = String::new();
+        render_tree(&folder, "", &mut rendered);
+        assert_eq!(rendered, include_str!("golden/shop_folder.ts"));
+    }
+
+    #[test]
+    fn it_renders_the_golden_declaration_file() {
+        let folder = compile_shop_with(Options {
+            layout: Layout::File,
+            emit: Emit::Dts,
+            ..Options::default()
+        });
+        let rendered = render_file_at(&folder, &["shop", "order"]);
+        assert_eq!(rendered, include_str!("golden/shop.d.ts"));
     }
 }
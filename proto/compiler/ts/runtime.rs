@@ -0,0 +1,1744 @@
+use std::sync::Arc;
+
+use crate::proto::proto_scope::root_scope::RootScope;
+
+use super::{
+    ast::{
+        self, BinaryOperator, Expression, ExpressionChain, Modifier, Statement, StatementList, Type,
+    },
+    constants::{PROTOBUF_MODULE, RUNTIME_FILE_NAME},
+    get_relative_import::get_relative_import_string,
+    layout::declaration_file_path,
+    options::{Options, Runtime},
+    ts_path::{TsPath, TsPathComponent},
+};
+
+/// Module `Writer`, `Reader` and `util` are imported from
+/// inside of the `file_name` part of the declaration.
+///
+/// With the standalone runtime it is the path of `runtime.ts`
+/// relative to the importing file.
+pub(super) fn runtime_module(
+    root: &RootScope,
+    options: &Options,
+    decl_id: usize,
+    file_name: &str,
+) -> Arc<str> {
+    match options.runtime {
+        Runtime::Protobufjs => PROTOBUF_MODULE.into(),
+        Runtime::Standalone => {
+            let current_file = declaration_file_path(root, options, decl_id, file_name);
+            relative_runtime_module(&current_file)
+        }
+    }
+}
+
+/// Same as `runtime_module`, but for a file that is not a part of any declaration.
+pub(super) fn runtime_module_at(options: &Options, current_file: &TsPath) -> Arc<str> {
+    match options.runtime {
+        Runtime::Protobufjs => PROTOBUF_MODULE.into(),
+        Runtime::Standalone => relative_runtime_module(current_file),
+    }
+}
+
+fn relative_runtime_module(current_file: &TsPath) -> Arc<str> {
+    let mut runtime_path = TsPath::default();
+    runtime_path.push_file(RUNTIME_FILE_NAME);
+    runtime_path.push(TsPathComponent::Function("util".into()));
+    get_relative_import_string(current_file, &runtime_path)
+        .unwrap()
+        .into()
+}
+
+/// Emits `runtime.ts`: the minimal varint reader and writer
+/// used by the generated code instead of protobufjs.
+/// 64-bit integers are read as bigints and written from bigints, numbers or decimal strings.
+pub(super) fn compile_runtime_module() -> ast::File {
+    let mut file = ast::File::new(RUNTIME_FILE_NAME.into());
+    file.push_statement(declare_const(
+        "BITS_32",
+        call(id("BigInt"), vec![num(32.0)]),
+    ));
+    file.push_statement(declare_const(
+        "MASK_32",
+        call(id("BigInt"), vec![num(0xffffffffu32 as f64)]),
+    ));
+    file.push_statement(util_namespace().into());
+    file.push_statement(to_big_int_function().into());
+    file.push_statement(split_function().into());
+    file.push_statement(join_function().into());
+    file.push_statement(long_from_string_function().into());
+    file.push_statement(writer_class().into());
+    file.push_statement(reader_class().into());
+    file
+}
+
+/// `util` of protobufjs, as much of it as the generated code uses.
+fn util_namespace() -> ast::NamespaceDeclaration {
+    let mut util = ast::NamespaceDeclaration::new_exported("util".into());
+    util.push_statement(
+        ast::TypeAliasDeclaration::new("Long".into(), Type::BigInt)
+            .exported()
+            .into(),
+    );
+
+    // the methods of `Long` take the parameters of the functions they are
+    let mut long_constructor = ast::InterfaceDeclaration::new_exported("LongConstructor".into());
+    for (name, function) in [
+        ("fromBits", join_function()),
+        ("fromValue", to_big_int_function()),
+        ("fromString", long_from_string_function()),
+    ] {
+        long_constructor.members.push(
+            ast::MethodSignature {
+                name: name.into(),
+                parameters: function.parameters,
+                return_type: Type::from_id("Long"),
+            }
+            .into(),
+        );
+    }
+    util.push_statement(long_constructor.into());
+
+    // creates the bigints 64-bit integers are read as, in place of long.js
+    util.push_statement(
+        ast::VariableDeclarationList::declare_typed_const(
+            Arc::new("Long".into()),
+            Arc::new(Type::from_id("LongConstructor")),
+            object_literal(&[
+                ("fromBits", JOIN_FUNCTION_NAME),
+                ("fromValue", TO_BIG_INT_FUNCTION_NAME),
+                ("fromString", LONG_FROM_STRING_FUNCTION_NAME),
+            ]),
+        )
+        .exported()
+        .into(),
+    );
+    util.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Arc::new("emptyObject".into()),
+            method_call(id("Object"), "freeze", vec![object_literal(&[])]),
+        )
+        .exported()
+        .into(),
+    );
+    let mut long_to_hash = function(
+        "longToHash",
+        vec![ast::Parameter::new("value", Type::Unknown)],
+        Type::String,
+        vec![call(id("String"), vec![id("value")]).into_return_statement()],
+    );
+    long_to_hash.modifiers.push(Modifier::Export);
+    util.push_statement(long_to_hash.into());
+
+    util.push_statement(declare_const(
+        "ALPHABET",
+        string("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/"),
+    ));
+    util.push_statement(base64_length_function().into());
+    util.push_statement(base64_encode_function().into());
+    util.push_statement(base64_decode_function().into());
+    util.push_statement(
+        ast::VariableDeclarationList::declare_const(
+            Arc::new("base64".into()),
+            object_literal(&[
+                ("length", "base64Length"),
+                ("encode", "base64Encode"),
+                ("decode", "base64Decode"),
+            ]),
+        )
+        .exported()
+        .into(),
+    );
+    util
+}
+
+/// Number of bytes the base64 string decodes into.
+fn base64_length_function() -> ast::FunctionDeclaration {
+    let padded = binary(
+        BinaryOperator::StrictEqual,
+        method_call(
+            id("s"),
+            "charAt",
+            vec![binary(BinaryOperator::Minus, id("n"), num(1.0))],
+        ),
+        string("="),
+    );
+    let mut trim_padding = ast::WhileStatement::new(Arc::new(binary(
+        BinaryOperator::LogicalAnd,
+        binary(BinaryOperator::GreaterThan, id("n"), num(0.0)),
+        padded,
+    )));
+    trim_padding.push_statement(prefix(ast::UnaryOperator::Decrement, id("n")).into());
+    function(
+        "base64Length",
+        vec![ast::Parameter::new("s", Type::String)],
+        Type::Number,
+        vec![
+            declare_let("n", id("s").into_prop("length")),
+            trim_padding.into(),
+            call(
+                id("Math").into_prop("floor"),
+                vec![binary(
+                    BinaryOperator::Divide,
+                    binary(BinaryOperator::Multiply, id("n"), num(3.0)),
+                    num(4.0),
+                )],
+            )
+            .into_return_statement(),
+        ],
+    )
+}
+
+/// Writes the bytes of `buf` from `start` to `end` as a base64 string.
+fn base64_encode_function() -> ast::FunctionDeclaration {
+    let char_at = |index: Expression| method_call(id("ALPHABET"), "charAt", vec![index]);
+    // `i + offset < end ? then : otherwise`, for the bytes after the end of the buffer
+    let if_in_bounds = |offset: f64, then: Expression, otherwise: Expression| {
+        conditional(
+            binary(
+                BinaryOperator::LessThan,
+                binary(BinaryOperator::Plus, id("i"), num(offset)),
+                id("end"),
+            ),
+            then,
+            otherwise,
+        )
+    };
+    let byte_at = |offset: f64| {
+        if_in_bounds(
+            offset,
+            element(
+                id("buf"),
+                binary(BinaryOperator::Plus, id("i"), num(offset)),
+            ),
+            num(0.0),
+        )
+    };
+    let append = |value: Expression| -> Statement {
+        binary(BinaryOperator::PlusAssign, id("s"), value).into()
+    };
+    let loop_statement = for_loop(
+        "i",
+        id("start"),
+        binary(BinaryOperator::LessThan, id("i"), id("end")),
+        binary(BinaryOperator::PlusAssign, id("i"), num(3.0)),
+        vec![
+            declare_const("b0", element(id("buf"), id("i"))),
+            declare_const("b1", byte_at(1.0)),
+            declare_const("b2", byte_at(2.0)),
+            append(binary(
+                BinaryOperator::Plus,
+                char_at(binary(BinaryOperator::RightShift, id("b0"), num(2.0))),
+                char_at(binary(
+                    BinaryOperator::BinaryOr,
+                    binary(
+                        BinaryOperator::LeftShift,
+                        binary(BinaryOperator::BinaryAnd, id("b0"), num(3.0)),
+                        num(4.0),
+                    ),
+                    binary(BinaryOperator::RightShift, id("b1"), num(4.0)),
+                )),
+            )),
+            append(if_in_bounds(
+                1.0,
+                char_at(binary(
+                    BinaryOperator::BinaryOr,
+                    binary(
+                        BinaryOperator::LeftShift,
+                        binary(BinaryOperator::BinaryAnd, id("b1"), num(15.0)),
+                        num(2.0),
+                    ),
+                    binary(BinaryOperator::RightShift, id("b2"), num(6.0)),
+                )),
+                string("="),
+            )),
+            append(if_in_bounds(
+                2.0,
+                char_at(binary(BinaryOperator::BinaryAnd, id("b2"), num(63.0))),
+                string("="),
+            )),
+        ],
+    );
+    function(
+        "base64Encode",
+        vec![
+            ast::Parameter::new("buf", Type::from_id("Uint8Array")),
+            ast::Parameter::new("start", Type::Number),
+            ast::Parameter::new("end", Type::Number),
+        ],
+        Type::String,
+        vec![
+            declare_let("s", string("")),
+            loop_statement.into(),
+            id("s").into_return_statement(),
+        ],
+    )
+}
+
+/// Decodes the base64 string into `buf` from `offset`, returns the number of the bytes written.
+/// The URL-safe alphabet is accepted too.
+fn base64_decode_function() -> ast::FunctionDeclaration {
+    let c_is = |character: &str| binary(BinaryOperator::StrictEqual, id("c"), string(character));
+    let mut loop_statement = ast::ForStatement::for_each(Arc::new("i".into()), Arc::new(id("s")));
+    loop_statement.push_statement(declare_const(
+        "c",
+        method_call(id("s"), "charAt", vec![id("i")]),
+    ));
+    loop_statement.push_statement(if_then(c_is("="), Statement::Break));
+    loop_statement.push_statement(declare_const(
+        "index",
+        method_call(
+            id("ALPHABET"),
+            "indexOf",
+            vec![conditional(
+                c_is("-"),
+                string("+"),
+                conditional(c_is("_"), string("/"), id("c")),
+            )],
+        ),
+    ));
+    loop_statement.push_statement(if_then(
+        binary(BinaryOperator::LessThan, id("index"), num(0.0)),
+        throw_error("Error", string("invalid base64 string")),
+    ));
+    loop_statement.push_statement(
+        binary(
+            BinaryOperator::Assign,
+            id("value"),
+            binary(
+                BinaryOperator::BinaryAnd,
+                binary(
+                    BinaryOperator::BinaryOr,
+                    binary(BinaryOperator::LeftShift, id("value"), num(6.0)),
+                    id("index"),
+                ),
+                num(0xffffff as f64),
+            ),
+        )
+        .into(),
+    );
+    loop_statement.push_statement(binary(BinaryOperator::PlusAssign, id("bits"), num(6.0)).into());
+    loop_statement.push_statement(if_then(
+        binary(BinaryOperator::GreaterThanOrEqual, id("bits"), num(8.0)),
+        block(vec![
+            binary(BinaryOperator::MinusAssign, id("bits"), num(8.0)).into(),
+            binary(
+                BinaryOperator::Assign,
+                element(id("buf"), id("offset")),
+                binary(
+                    BinaryOperator::BinaryAnd,
+                    binary(BinaryOperator::RightShift, id("value"), id("bits")),
+                    num(255.0),
+                ),
+            )
+            .into(),
+            prefix(ast::UnaryOperator::Increment, id("offset")).into(),
+        ]),
+    ));
+    function(
+        "base64Decode",
+        vec![
+            ast::Parameter::new("s", Type::String),
+            ast::Parameter::new("buf", Type::from_id("Uint8Array")),
+            ast::Parameter::new("offset", Type::Number),
+        ],
+        Type::Number,
+        vec![
+            declare_const("start", id("offset")),
+            declare_let("bits", num(0.0)),
+            declare_let("value", num(0.0)),
+            loop_statement.into(),
+            binary(BinaryOperator::Minus, id("offset"), id("start")).into_return_statement(),
+        ],
+    )
+}
+
+const TO_BIG_INT_FUNCTION_NAME: &str = "toBigInt";
+const JOIN_FUNCTION_NAME: &str = "join";
+const LONG_FROM_STRING_FUNCTION_NAME: &str = "longFromString";
+
+/// `function toBigInt(value: unknown): bigint`, numbers are truncated as protobufjs does,
+/// strings are decimal, like the keys of 64-bit maps.
+fn to_big_int_function() -> ast::FunctionDeclaration {
+    let is_type = |type_name: &str| {
+        binary(
+            BinaryOperator::StrictEqual,
+            Arc::new(id("value")).type_of(),
+            string(type_name),
+        )
+    };
+    function(
+        TO_BIG_INT_FUNCTION_NAME,
+        vec![ast::Parameter::new("value", Type::Unknown)],
+        Type::BigInt,
+        vec![
+            if_then(is_type("bigint"), id("value").into_return_statement()),
+            if_then(
+                is_type("number"),
+                conditional(
+                    method_call(id("Number"), "isFinite", vec![id("value")]),
+                    call(
+                        id("BigInt"),
+                        vec![method_call(id("Math"), "trunc", vec![id("value")])],
+                    ),
+                    call(id("BigInt"), vec![num(0.0)]),
+                )
+                .into_return_statement(),
+            ),
+            call(id("BigInt"), vec![call(id("String"), vec![id("value")])]).into_return_statement(),
+        ],
+    )
+}
+
+/// `function split(value: bigint | number | string): [number, number]`,
+/// the low and high 32 bits of the two's complement of an integer.
+fn split_function() -> ast::FunctionDeclaration {
+    function(
+        "split",
+        vec![ast::Parameter::new("value", int64_value_type())],
+        Type::TupleType(vec![Type::Number, Type::Number]),
+        vec![
+            declare_const(
+                "bits",
+                method_call(
+                    id("BigInt"),
+                    "asUintN",
+                    vec![
+                        num(64.0),
+                        call(id(TO_BIG_INT_FUNCTION_NAME), vec![id("value")]),
+                    ],
+                ),
+            ),
+            Expression::array(vec![
+                Arc::new(call(
+                    id("Number"),
+                    vec![binary(BinaryOperator::BinaryAnd, id("bits"), id("MASK_32"))],
+                )),
+                Arc::new(call(
+                    id("Number"),
+                    vec![binary(
+                        BinaryOperator::RightShift,
+                        id("bits"),
+                        id("BITS_32"),
+                    )],
+                )),
+            ])
+            .into_return_statement(),
+        ],
+    )
+}
+
+/// `function join(lo: number, hi: number, unsigned: boolean): bigint`, the reverse of `split`.
+fn join_function() -> ast::FunctionDeclaration {
+    let to_unsigned = |name: &str| {
+        call(
+            id("BigInt"),
+            vec![binary(
+                BinaryOperator::UnsignedRightShift,
+                id(name),
+                num(0.0),
+            )],
+        )
+    };
+    function(
+        JOIN_FUNCTION_NAME,
+        vec![
+            ast::Parameter::new("lo", Type::Number),
+            ast::Parameter::new("hi", Type::Number),
+            ast::Parameter::new("unsigned", Type::Boolean),
+        ],
+        Type::BigInt,
+        vec![
+            declare_const(
+                "bits",
+                binary(
+                    BinaryOperator::BinaryOr,
+                    binary(BinaryOperator::LeftShift, to_unsigned("hi"), id("BITS_32")),
+                    to_unsigned("lo"),
+                ),
+            ),
+            signed_unless_unsigned(),
+        ],
+    )
+}
+
+/// `function longFromString(value: string, unsigned: boolean): bigint`
+fn long_from_string_function() -> ast::FunctionDeclaration {
+    function(
+        LONG_FROM_STRING_FUNCTION_NAME,
+        vec![
+            ast::Parameter::new("value", Type::String),
+            ast::Parameter::new("unsigned", Type::Boolean),
+        ],
+        Type::BigInt,
+        vec![
+            declare_const(
+                "bits",
+                method_call(
+                    id("BigInt"),
+                    "asUintN",
+                    vec![num(64.0), call(id("BigInt"), vec![id("value")])],
+                ),
+            ),
+            signed_unless_unsigned(),
+        ],
+    )
+}
+
+/// `return unsigned ? bits : BigInt.asIntN(64, bits)`
+fn signed_unless_unsigned() -> Statement {
+    conditional(
+        id("unsigned"),
+        id("bits"),
+        method_call(id("BigInt"), "asIntN", vec![num(64.0), id("bits")]),
+    )
+    .into_return_statement()
+}
+
+/// `bigint | number | string`, the 64-bit integers `Writer` accepts.
+fn int64_value_type() -> Type {
+    ast::UnionType::from(vec![Type::BigInt, Type::Number, Type::String]).into()
+}
+
+/// `Writer` of protobufjs, the bytes are collected into an array of numbers
+/// and the length delimited parts into the arrays of `forks`.
+fn writer_class() -> ast::ClassDeclaration {
+    let writer_type = || Type::from_id("Writer");
+    let value = |value_type: Type| vec![ast::Parameter::new("value", value_type)];
+    let push_byte = |byte: Expression| -> Statement {
+        method_call(this_prop("buf"), "push", vec![byte]).into()
+    };
+    let return_this = || id("this").into_return_statement();
+    let return_self_call = |method: &str, args: Vec<Expression>| {
+        method_call(id("this"), method, args).into_return_statement()
+    };
+    // `const bits = split(value)`
+    let split_value = || declare_const("bits", call(id("split"), vec![id("value")]));
+    let bits = |index: f64| element(id("bits"), num(index));
+
+    let mut writer = ast::ClassDeclaration::new_exported("Writer".into());
+    let mut buf = ast::PropertyDeclaration::new(
+        "buf",
+        Some(Type::array(Type::Number)),
+        Some(Expression::array(vec![])),
+    );
+    buf.modifiers.push(Modifier::Private);
+    writer.fields.push(buf);
+    let mut forks = ast::PropertyDeclaration::new(
+        "forks",
+        Some(Type::array(Type::array(Type::Number))),
+        Some(Expression::array(vec![])),
+    );
+    forks
+        .modifiers
+        .extend([Modifier::Private, Modifier::Readonly]);
+    writer.fields.push(forks);
+
+    let mut create = function(
+        "create",
+        vec![],
+        writer_type(),
+        vec![new_instance("Writer", vec![]).into_return_statement()],
+    );
+    create.modifiers.push(Modifier::Static);
+    writer.methods.push(create);
+
+    let mut write_varint = ast::WhileStatement::new(Arc::new(binary(
+        BinaryOperator::GreaterThan,
+        id("value"),
+        num(127.0),
+    )));
+    write_varint.push_statement(push_byte(binary(
+        BinaryOperator::BinaryOr,
+        binary(BinaryOperator::BinaryAnd, id("value"), num(127.0)),
+        num(128.0),
+    )));
+    write_varint.push_statement(
+        binary(
+            BinaryOperator::UnsignedRightShiftAssign,
+            id("value"),
+            num(7.0),
+        )
+        .into(),
+    );
+    writer.methods.push(function(
+        "uint32",
+        value(Type::Number),
+        writer_type(),
+        vec![
+            binary(
+                BinaryOperator::UnsignedRightShiftAssign,
+                id("value"),
+                num(0.0),
+            )
+            .into(),
+            write_varint.into(),
+            push_byte(id("value")),
+            return_this(),
+        ],
+    ));
+    writer.methods.push(function(
+        "int32",
+        value(Type::Number),
+        writer_type(),
+        vec![conditional(
+            binary(BinaryOperator::LessThan, id("value"), num(0.0)),
+            method_call(
+                id("this"),
+                "varint64",
+                vec![
+                    binary(BinaryOperator::UnsignedRightShift, id("value"), num(0.0)),
+                    num(0xffffffffu32 as f64),
+                ],
+            ),
+            method_call(id("this"), "uint32", vec![id("value")]),
+        )
+        .into_return_statement()],
+    ));
+    writer.methods.push(function(
+        "sint32",
+        value(Type::Number),
+        writer_type(),
+        vec![return_self_call(
+            "uint32",
+            vec![binary(
+                BinaryOperator::BinaryXor,
+                binary(BinaryOperator::LeftShift, id("value"), num(1.0)),
+                binary(BinaryOperator::RightShift, id("value"), num(31.0)),
+            )],
+        )],
+    ));
+    let byte_of = |shift: f64| {
+        binary(
+            BinaryOperator::BinaryAnd,
+            binary(BinaryOperator::UnsignedRightShift, id("value"), num(shift)),
+            num(255.0),
+        )
+    };
+    writer.methods.push(function(
+        "fixed32",
+        value(Type::Number),
+        writer_type(),
+        vec![
+            binary(
+                BinaryOperator::UnsignedRightShiftAssign,
+                id("value"),
+                num(0.0),
+            )
+            .into(),
+            method_call(
+                this_prop("buf"),
+                "push",
+                vec![
+                    binary(BinaryOperator::BinaryAnd, id("value"), num(255.0)),
+                    byte_of(8.0),
+                    byte_of(16.0),
+                    binary(BinaryOperator::UnsignedRightShift, id("value"), num(24.0)),
+                ],
+            )
+            .into(),
+            return_this(),
+        ],
+    ));
+    writer.methods.push(function(
+        "sfixed32",
+        value(Type::Number),
+        writer_type(),
+        vec![return_self_call("fixed32", vec![id("value")])],
+    ));
+    writer.methods.push(function(
+        "int64",
+        value(int64_value_type()),
+        writer_type(),
+        vec![
+            split_value(),
+            return_self_call("varint64", vec![bits(0.0), bits(1.0)]),
+        ],
+    ));
+    writer.methods.push(function(
+        "uint64",
+        value(int64_value_type()),
+        writer_type(),
+        vec![return_self_call("int64", vec![id("value")])],
+    ));
+    let zigzag = |bits: Expression| {
+        binary(
+            BinaryOperator::UnsignedRightShift,
+            binary(BinaryOperator::BinaryXor, bits, id("sign")),
+            num(0.0),
+        )
+    };
+    writer.methods.push(function(
+        "sint64",
+        value(int64_value_type()),
+        writer_type(),
+        vec![
+            split_value(),
+            declare_const("lo", bits(0.0)),
+            declare_const("hi", bits(1.0)),
+            declare_const(
+                "sign",
+                binary(BinaryOperator::RightShift, id("hi"), num(31.0)),
+            ),
+            return_self_call(
+                "varint64",
+                vec![
+                    zigzag(binary(BinaryOperator::LeftShift, id("lo"), num(1.0))),
+                    zigzag(binary(
+                        BinaryOperator::BinaryOr,
+                        binary(BinaryOperator::LeftShift, id("hi"), num(1.0)),
+                        binary(BinaryOperator::UnsignedRightShift, id("lo"), num(31.0)),
+                    )),
+                ],
+            ),
+        ],
+    ));
+    writer.methods.push(function(
+        "fixed64",
+        value(int64_value_type()),
+        writer_type(),
+        vec![
+            split_value(),
+            method_call(
+                method_call(id("this"), "fixed32", vec![bits(0.0)]),
+                "fixed32",
+                vec![bits(1.0)],
+            )
+            .into_return_statement(),
+        ],
+    ));
+    writer.methods.push(function(
+        "sfixed64",
+        value(int64_value_type()),
+        writer_type(),
+        vec![return_self_call("fixed64", vec![id("value")])],
+    ));
+    for (method, size, setter) in [("float", 4.0, "setFloat32"), ("double", 8.0, "setFloat64")] {
+        writer.methods.push(function(
+            method,
+            value(Type::Number),
+            writer_type(),
+            vec![
+                declare_const("bytes", new_instance("Uint8Array", vec![num(size)])),
+                method_call(
+                    new_instance("DataView", vec![id("bytes").into_prop("buffer")]),
+                    setter,
+                    vec![num(0.0), id("value"), Expression::True],
+                )
+                .into(),
+                return_self_call("raw", vec![id("bytes")]),
+            ],
+        ));
+    }
+    writer.methods.push(function(
+        "bool",
+        value(Type::Boolean),
+        writer_type(),
+        vec![return_self_call(
+            "uint32",
+            vec![conditional(id("value"), num(1.0), num(0.0))],
+        )],
+    ));
+    writer.methods.push(function(
+        "string",
+        value(Type::String),
+        writer_type(),
+        vec![return_self_call(
+            "bytes",
+            vec![method_call(
+                new_instance("TextEncoder", vec![]),
+                "encode",
+                vec![id("value")],
+            )],
+        )],
+    ));
+    writer.methods.push(function(
+        "bytes",
+        value(Type::from_id("Uint8Array")),
+        writer_type(),
+        vec![method_call(
+            method_call(id("this"), "uint32", vec![id("value").into_prop("length")]),
+            "raw",
+            vec![id("value")],
+        )
+        .into_return_statement()],
+    ));
+    // starts a length delimited part, finished by `ldelim`
+    writer.methods.push(function(
+        "fork",
+        vec![],
+        writer_type(),
+        vec![
+            method_call(this_prop("forks"), "push", vec![this_prop("buf")]).into(),
+            binary(
+                BinaryOperator::Assign,
+                this_prop("buf"),
+                Expression::array(vec![]),
+            )
+            .into(),
+            return_this(),
+        ],
+    ));
+    writer.methods.push(function(
+        "ldelim",
+        vec![],
+        writer_type(),
+        vec![
+            declare_const("part", this_prop("buf")),
+            declare_const("parent", method_call(this_prop("forks"), "pop", vec![])),
+            if_then(
+                id("parent").not(),
+                throw_error("Error", string("ldelim without fork")),
+            ),
+            binary(BinaryOperator::Assign, this_prop("buf"), id("parent")).into(),
+            method_call(id("this"), "uint32", vec![id("part").into_prop("length")]).into(),
+            copy_bytes("part"),
+            return_this(),
+        ],
+    ));
+    writer.methods.push(function(
+        "finish",
+        vec![],
+        Type::from_id("Uint8Array"),
+        vec![method_call(id("Uint8Array"), "from", vec![this_prop("buf")]).into_return_statement()],
+    ));
+    // writes the bytes as they are, like the unknown fields kept by `decode`
+    writer.methods.push(function(
+        "raw",
+        vec![ast::Parameter::new("bytes", Type::from_id("Uint8Array"))],
+        writer_type(),
+        vec![copy_bytes("bytes"), return_this()],
+    ));
+
+    let mut write_varint = ast::WhileStatement::new(Arc::new(binary(
+        BinaryOperator::LogicalOr,
+        binary(BinaryOperator::GreaterThan, id("hi"), num(0.0)),
+        binary(BinaryOperator::GreaterThan, id("lo"), num(127.0)),
+    )));
+    write_varint.push_statement(push_byte(binary(
+        BinaryOperator::BinaryOr,
+        binary(BinaryOperator::BinaryAnd, id("lo"), num(127.0)),
+        num(128.0),
+    )));
+    write_varint.push_statement(
+        binary(
+            BinaryOperator::Assign,
+            id("lo"),
+            binary(
+                BinaryOperator::UnsignedRightShift,
+                binary(
+                    BinaryOperator::BinaryOr,
+                    binary(BinaryOperator::UnsignedRightShift, id("lo"), num(7.0)),
+                    binary(BinaryOperator::LeftShift, id("hi"), num(25.0)),
+                ),
+                num(0.0),
+            ),
+        )
+        .into(),
+    );
+    write_varint.push_statement(
+        binary(BinaryOperator::UnsignedRightShiftAssign, id("hi"), num(7.0)).into(),
+    );
+    let mut varint64 = function(
+        "varint64",
+        vec![
+            ast::Parameter::new("lo", Type::Number),
+            ast::Parameter::new("hi", Type::Number),
+        ],
+        writer_type(),
+        vec![write_varint.into(), push_byte(id("lo")), return_this()],
+    );
+    varint64.modifiers.push(Modifier::Private);
+    writer.methods.push(varint64);
+    writer
+}
+
+/// `for (let i = 0; i < bytes.length; ++i) this.buf.push(bytes[i])`
+fn copy_bytes(bytes: &str) -> Statement {
+    let mut copy = ast::ForStatement::for_each(Arc::new("i".into()), Arc::new(id(bytes)));
+    copy.push_statement(
+        method_call(this_prop("buf"), "push", vec![element(id(bytes), id("i"))]).into(),
+    );
+    copy.into()
+}
+
+/// `Reader` of protobufjs, reads from `pos` until `len`,
+/// reading past the end throws a `RangeError`.
+fn reader_class() -> ast::ClassDeclaration {
+    let reader_type = || Type::from_id("Reader");
+    let advance = |length: Expression| -> Statement {
+        binary(BinaryOperator::PlusAssign, this_prop("pos"), length).into()
+    };
+    let ensure = |length: Expression| -> Statement {
+        method_call(id("this"), "ensure", vec![length]).into()
+    };
+    // `const bits = this.varint64()`
+    let read_varint = || declare_const("bits", method_call(id("this"), "varint64", vec![]));
+    let bits = |index: f64| element(id("bits"), num(index));
+    let join = |lo: Expression, hi: Expression, unsigned: bool| {
+        call(
+            id(JOIN_FUNCTION_NAME),
+            vec![
+                lo,
+                hi,
+                if unsigned {
+                    Expression::True
+                } else {
+                    Expression::False
+                },
+            ],
+        )
+    };
+
+    let mut reader = ast::ClassDeclaration::new_exported("Reader".into());
+    reader
+        .fields
+        .push(ast::PropertyDeclaration::new("pos", None, Some(num(0.0))));
+    for (name, field_type) in [("buf", Type::from_id("Uint8Array")), ("len", Type::Number)] {
+        let mut field = ast::PropertyDeclaration::new(name, Some(field_type), None);
+        field.modifiers.push(Modifier::Readonly);
+        reader.fields.push(field);
+    }
+    reader
+        .parameters
+        .push(ast::Parameter::new("buf", Type::from_id("Uint8Array")));
+    reader
+        .constructor
+        .push_statement(binary(BinaryOperator::Assign, this_prop("buf"), id("buf")).into());
+    reader.constructor.push_statement(
+        binary(
+            BinaryOperator::Assign,
+            this_prop("len"),
+            id("buf").into_prop("length"),
+        )
+        .into(),
+    );
+
+    let mut create = function(
+        "create",
+        vec![ast::Parameter::new("buf", Type::from_id("Uint8Array"))],
+        reader_type(),
+        vec![new_instance("Reader", vec![id("buf")]).into_return_statement()],
+    );
+    create.modifiers.push(Modifier::Static);
+    reader.methods.push(create);
+
+    reader.methods.push(function(
+        "uint32",
+        vec![],
+        Type::Number,
+        vec![
+            element(method_call(id("this"), "varint64", vec![]), num(0.0)).into_return_statement(),
+        ],
+    ));
+    // `return this.uint32() | 0`
+    let signed = |method: &str, unsigned: &str| {
+        function(
+            method,
+            vec![],
+            Type::Number,
+            vec![binary(
+                BinaryOperator::BinaryOr,
+                method_call(id("this"), unsigned, vec![]),
+                num(0.0),
+            )
+            .into_return_statement()],
+        )
+    };
+    reader.methods.push(signed("int32", "uint32"));
+    reader.methods.push(function(
+        "sint32",
+        vec![],
+        Type::Number,
+        vec![
+            declare_const("value", method_call(id("this"), "uint32", vec![])),
+            binary(
+                BinaryOperator::BinaryXor,
+                binary(BinaryOperator::UnsignedRightShift, id("value"), num(1.0)),
+                prefix(
+                    ast::UnaryOperator::Minus,
+                    binary(BinaryOperator::BinaryAnd, id("value"), num(1.0)),
+                ),
+            )
+            .into_return_statement(),
+        ],
+    ));
+    let byte_at = |offset: f64, shift: f64| {
+        let byte = element(
+            id("b"),
+            if offset == 0.0 {
+                id("p")
+            } else {
+                binary(BinaryOperator::Plus, id("p"), num(offset))
+            },
+        );
+        if shift == 0.0 {
+            byte
+        } else {
+            binary(BinaryOperator::LeftShift, byte, num(shift))
+        }
+    };
+    reader.methods.push(function(
+        "fixed32",
+        vec![],
+        Type::Number,
+        vec![
+            ensure(num(4.0)),
+            declare_const("b", this_prop("buf")),
+            declare_const("p", this_prop("pos")),
+            advance(num(4.0)),
+            binary(
+                BinaryOperator::UnsignedRightShift,
+                binary(
+                    BinaryOperator::BinaryOr,
+                    binary(
+                        BinaryOperator::BinaryOr,
+                        binary(
+                            BinaryOperator::BinaryOr,
+                            byte_at(0.0, 0.0),
+                            byte_at(1.0, 8.0),
+                        ),
+                        byte_at(2.0, 16.0),
+                    ),
+                    byte_at(3.0, 24.0),
+                ),
+                num(0.0),
+            )
+            .into_return_statement(),
+        ],
+    ));
+    reader.methods.push(signed("sfixed32", "fixed32"));
+    for (method, unsigned) in [("int64", false), ("uint64", true)] {
+        reader.methods.push(function(
+            method,
+            vec![],
+            Type::BigInt,
+            vec![
+                read_varint(),
+                join(bits(0.0), bits(1.0), unsigned).into_return_statement(),
+            ],
+        ));
+    }
+    reader.methods.push(function(
+        "sint64",
+        vec![],
+        Type::BigInt,
+        vec![
+            read_varint(),
+            declare_const("lo", bits(0.0)),
+            declare_const("hi", bits(1.0)),
+            declare_const(
+                "sign",
+                prefix(
+                    ast::UnaryOperator::Minus,
+                    binary(BinaryOperator::BinaryAnd, id("lo"), num(1.0)),
+                ),
+            ),
+            join(
+                binary(
+                    BinaryOperator::BinaryXor,
+                    binary(
+                        BinaryOperator::BinaryOr,
+                        binary(BinaryOperator::UnsignedRightShift, id("lo"), num(1.0)),
+                        binary(BinaryOperator::LeftShift, id("hi"), num(31.0)),
+                    ),
+                    id("sign"),
+                ),
+                binary(
+                    BinaryOperator::BinaryXor,
+                    binary(BinaryOperator::UnsignedRightShift, id("hi"), num(1.0)),
+                    id("sign"),
+                ),
+                false,
+            )
+            .into_return_statement(),
+        ],
+    ));
+    for (method, unsigned) in [("fixed64", true), ("sfixed64", false)] {
+        reader.methods.push(function(
+            method,
+            vec![],
+            Type::BigInt,
+            vec![
+                declare_const("lo", method_call(id("this"), "fixed32", vec![])),
+                join(
+                    id("lo"),
+                    method_call(id("this"), "fixed32", vec![]),
+                    unsigned,
+                )
+                .into_return_statement(),
+            ],
+        ));
+    }
+    for (method, size, getter) in [("float", 4.0, "getFloat32"), ("double", 8.0, "getFloat64")] {
+        reader.methods.push(function(
+            method,
+            vec![],
+            Type::Number,
+            vec![
+                ensure(num(size)),
+                declare_const(
+                    "value",
+                    method_call(
+                        method_call(id("this"), "view", vec![]),
+                        getter,
+                        vec![this_prop("pos"), Expression::True],
+                    ),
+                ),
+                advance(num(size)),
+                id("value").into_return_statement(),
+            ],
+        ));
+    }
+    reader.methods.push(function(
+        "bool",
+        vec![],
+        Type::Boolean,
+        vec![binary(
+            BinaryOperator::StrictNotEqual,
+            method_call(id("this"), "uint32", vec![]),
+            num(0.0),
+        )
+        .into_return_statement()],
+    ));
+    reader.methods.push(function(
+        "string",
+        vec![],
+        Type::String,
+        vec![method_call(
+            new_instance("TextDecoder", vec![]),
+            "decode",
+            vec![method_call(id("this"), "bytes", vec![])],
+        )
+        .into_return_statement()],
+    ));
+    reader.methods.push(function(
+        "bytes",
+        vec![],
+        Type::from_id("Uint8Array"),
+        vec![
+            declare_const("length", method_call(id("this"), "uint32", vec![])),
+            ensure(id("length")),
+            declare_const(
+                "value",
+                method_call(
+                    this_prop("buf"),
+                    "slice",
+                    vec![
+                        this_prop("pos"),
+                        binary(BinaryOperator::Plus, this_prop("pos"), id("length")),
+                    ],
+                ),
+            ),
+            advance(id("length")),
+            id("value").into_return_statement(),
+        ],
+    ));
+    reader.methods.push(function(
+        "skipType",
+        vec![ast::Parameter::new("wireType", Type::Number)],
+        reader_type(),
+        vec![
+            skip_type_switch().into(),
+            id("this").into_return_statement(),
+        ],
+    ));
+    let mut skip = function(
+        "skip",
+        vec![ast::Parameter::new("length", Type::Number)],
+        Type::Void,
+        vec![ensure(id("length")), advance(id("length"))],
+    );
+    skip.modifiers.push(Modifier::Private);
+    reader.methods.push(skip);
+    let mut ensure_method = function(
+        "ensure",
+        vec![ast::Parameter::new("length", Type::Number)],
+        Type::Void,
+        vec![if_then(
+            binary(
+                BinaryOperator::GreaterThan,
+                binary(BinaryOperator::Plus, this_prop("pos"), id("length")),
+                this_prop("len"),
+            ),
+            throw_error(
+                "RangeError",
+                concat(vec![
+                    string("index out of range: "),
+                    this_prop("pos"),
+                    string(" + "),
+                    id("length"),
+                    string(" > "),
+                    this_prop("len"),
+                ]),
+            ),
+        )],
+    );
+    ensure_method.modifiers.push(Modifier::Private);
+    reader.methods.push(ensure_method);
+    let mut view = function(
+        "view",
+        vec![],
+        Type::from_id("DataView"),
+        vec![new_instance(
+            "DataView",
+            vec![
+                this_prop("buf").into_prop("buffer"),
+                this_prop("buf").into_prop("byteOffset"),
+                this_prop("buf").into_prop("byteLength"),
+            ],
+        )
+        .into_return_statement()],
+    );
+    view.modifiers.push(Modifier::Private);
+    reader.methods.push(view);
+    reader.methods.push(read_varint_method());
+    reader
+}
+
+/// `switch (wireType)` of `skipType`, groups are skipped until their end tag.
+fn skip_type_switch() -> ast::SwitchStatement {
+    let self_call = |method: &str, args: Vec<Expression>| -> Statement {
+        method_call(id("this"), method, args).into()
+    };
+    let mut switch = ast::SwitchStatement::new(
+        Arc::new(id("wireType")),
+        vec![throw_error(
+            "Error",
+            concat(vec![
+                string("invalid wire type "),
+                id("wireType"),
+                string(" at offset "),
+                this_prop("pos"),
+            ]),
+        )]
+        .into(),
+    );
+    let mut skip_group = ast::WhileStatement::new(Arc::new(binary(
+        BinaryOperator::StrictNotEqual,
+        binary(
+            BinaryOperator::Assign,
+            id("wireType"),
+            binary(
+                BinaryOperator::BinaryAnd,
+                method_call(id("this"), "uint32", vec![]),
+                num(7.0),
+            ),
+        ),
+        num(4.0),
+    )));
+    skip_group.push_statement(self_call("skipType", vec![id("wireType")]));
+    let cases: [(f64, Statement); 5] = [
+        (0.0, self_call("varint64", vec![])),
+        (1.0, self_call("skip", vec![num(8.0)])),
+        (
+            2.0,
+            self_call("skip", vec![method_call(id("this"), "uint32", vec![])]),
+        ),
+        (3.0, skip_group.into()),
+        (5.0, self_call("skip", vec![num(4.0)])),
+    ];
+    for (wire_type, statement) in cases {
+        let mut case = ast::CaseClause::new(Arc::new(num(wire_type)));
+        case.push_statement(statement);
+        case.push_statement(Statement::Break);
+        switch.add_case(case);
+    }
+    switch
+}
+
+/// `private varint64(): [number, number]`, reads a varint of at most 10 bytes
+/// as low and high 32 bits. Negative int32 values are sign-extended to 10 bytes
+/// by the writers, the low 32 bits of them are the value itself.
+fn read_varint_method() -> ast::FunctionDeclaration {
+    let or_assign = |name: &str, value: Expression| -> Statement {
+        binary(BinaryOperator::BinaryOrAssign, id(name), value).into()
+    };
+    let mut read_byte = for_loop(
+        "shift",
+        num(0.0),
+        binary(BinaryOperator::LessThan, id("shift"), num(70.0)),
+        binary(BinaryOperator::PlusAssign, id("shift"), num(7.0)),
+        vec![
+            if_then(
+                binary(
+                    BinaryOperator::GreaterThanOrEqual,
+                    this_prop("pos"),
+                    this_prop("len"),
+                ),
+                throw_error(
+                    "RangeError",
+                    concat(vec![string("truncated varint at offset "), id("start")]),
+                ),
+            ),
+            declare_const("bits", element(this_prop("buf"), this_prop("pos"))),
+            prefix(ast::UnaryOperator::Increment, this_prop("pos")).into(),
+            declare_const(
+                "value",
+                binary(BinaryOperator::BinaryAnd, id("bits"), num(127.0)),
+            ),
+        ],
+    );
+    let mut low_bits = ast::Block::new();
+    low_bits.push_statement(or_assign(
+        "lo",
+        binary(BinaryOperator::LeftShift, id("value"), id("shift")),
+    ));
+    let mut middle_bits = ast::Block::new();
+    middle_bits.push_statement(or_assign(
+        "lo",
+        binary(BinaryOperator::LeftShift, id("value"), num(28.0)),
+    ));
+    middle_bits.push_statement(or_assign(
+        "hi",
+        binary(BinaryOperator::UnsignedRightShift, id("value"), num(4.0)),
+    ));
+    let mut high_bits = ast::Block::new();
+    high_bits.push_statement(or_assign(
+        "hi",
+        binary(
+            BinaryOperator::LeftShift,
+            id("value"),
+            binary(BinaryOperator::Minus, id("shift"), num(32.0)),
+        ),
+    ));
+    read_byte.push_statement(
+        ast::IfStatement {
+            expression: Arc::new(binary(BinaryOperator::LessThan, id("shift"), num(28.0))),
+            then_statement: Arc::new(low_bits.into()),
+            else_statement: Some(Arc::new(
+                ast::IfStatement {
+                    expression: Arc::new(binary(
+                        BinaryOperator::StrictEqual,
+                        id("shift"),
+                        num(28.0),
+                    )),
+                    then_statement: Arc::new(middle_bits.into()),
+                    else_statement: Some(Arc::new(high_bits.into())),
+                }
+                .into(),
+            )),
+        }
+        .into(),
+    );
+    read_byte.push_statement(if_then(
+        binary(BinaryOperator::BinaryAnd, id("bits"), num(128.0)).not(),
+        Expression::array(vec![
+            Arc::new(binary(
+                BinaryOperator::UnsignedRightShift,
+                id("lo"),
+                num(0.0),
+            )),
+            Arc::new(binary(
+                BinaryOperator::UnsignedRightShift,
+                id("hi"),
+                num(0.0),
+            )),
+        ])
+        .into_return_statement(),
+    ));
+    let mut method = function(
+        "varint64",
+        vec![],
+        Type::TupleType(vec![Type::Number, Type::Number]),
+        vec![
+            declare_const("start", this_prop("pos")),
+            declare_let("lo", num(0.0)),
+            declare_let("hi", num(0.0)),
+            read_byte.into(),
+            throw_error(
+                "Error",
+                concat(vec![
+                    string("varint at offset "),
+                    id("start"),
+                    string(" is longer than 10 bytes"),
+                ]),
+            ),
+        ],
+    );
+    method.modifiers.push(Modifier::Private);
+    method
+}
+
+fn id(name: &str) -> Expression {
+    Expression::from(name)
+}
+
+fn num(value: f64) -> Expression {
+    value.into()
+}
+
+fn string(text: &str) -> Expression {
+    ast::StringLiteral::new(text.into()).into()
+}
+
+/// `this.name`
+fn this_prop(name: &str) -> Expression {
+    id("this").into_prop(name)
+}
+
+/// `left operator right`, the operands are parenthesized when they bind looser than `operator`,
+/// and when they are operands of a bitwise operator with another operator,
+/// so that the code reads without the precedence table.
+fn binary(operator: BinaryOperator, left: Expression, right: Expression) -> Expression {
+    fn precedence(expr: &Expression) -> u8 {
+        match expr {
+            Expression::BinaryExpression(binary) => binary.operator.precedence(),
+            Expression::ConditionalExpression(_) => 2,
+            _ => u8::MAX,
+        }
+    }
+    fn is_bitwise(operator: BinaryOperator) -> bool {
+        matches!(
+            operator,
+            BinaryOperator::BinaryAnd
+                | BinaryOperator::BinaryOr
+                | BinaryOperator::BinaryXor
+                | BinaryOperator::LeftShift
+                | BinaryOperator::RightShift
+                | BinaryOperator::UnsignedRightShift
+        )
+    }
+    let group = |operand: Expression, is_right: bool| {
+        let mixed_bitwise = match &operand {
+            Expression::BinaryExpression(binary) => {
+                binary.operator != operator && is_bitwise(operator)
+            }
+            _ => false,
+        };
+        let operand_precedence = precedence(&operand);
+        if operand_precedence < operator.precedence()
+            || (is_right && operand_precedence == operator.precedence())
+            || mixed_bitwise
+        {
+            operand.into_parentheses()
+        } else {
+            operand
+        }
+    };
+    operator.apply(Arc::new(group(left, false)), Arc::new(group(right, true)))
+}
+
+/// `a + b + c`
+fn concat(parts: Vec<Expression>) -> Expression {
+    parts
+        .into_iter()
+        .reduce(|left, right| binary(BinaryOperator::Plus, left, right))
+        .unwrap()
+}
+
+fn prefix(operator: ast::UnaryOperator, operand: Expression) -> Expression {
+    Expression::PrefixUnaryExpression(ast::PrefixUnaryExpression {
+        operator,
+        operand: Arc::new(operand),
+    })
+}
+
+fn conditional(condition: Expression, when_true: Expression, when_false: Expression) -> Expression {
+    Expression::conditional(
+        Arc::new(condition),
+        Arc::new(when_true),
+        Arc::new(when_false),
+    )
+}
+
+fn call(callee: Expression, args: Vec<Expression>) -> Expression {
+    callee.into_call(args.into_iter().map(Arc::new).collect())
+}
+
+fn method_call(object: Expression, name: &str, args: Vec<Expression>) -> Expression {
+    object.into_method_call(name, args.into_iter().map(Arc::new).collect())
+}
+
+fn new_instance(class: &str, args: Vec<Expression>) -> Expression {
+    let mut new_expression = ast::NewExpression::new(Arc::new(id(class)));
+    for arg in args {
+        new_expression.add_argument(Arc::new(arg));
+    }
+    new_expression.into()
+}
+
+fn element(object: Expression, index: Expression) -> Expression {
+    object.into_element(Arc::new(index))
+}
+
+/// `{ name: value }` of identifiers
+fn object_literal(members: &[(&str, &str)]) -> Expression {
+    Expression::ObjectLiteralExpression(
+        members
+            .iter()
+            .map(|(name, value)| ast::ObjectLiteralMember::property(*name, id(value)))
+            .collect(),
+    )
+}
+
+fn declare_const(name: &str, initializer: Expression) -> Statement {
+    ast::VariableDeclarationList::declare_const(Arc::new(name.into()), initializer).into()
+}
+
+fn declare_let(name: &str, initializer: Expression) -> Statement {
+    ast::VariableDeclarationList::declare_let(Arc::new(name.into()), initializer).into()
+}
+
+fn if_then(condition: Expression, then: Statement) -> Statement {
+    ast::IfStatement {
+        expression: Arc::new(condition),
+        then_statement: Arc::new(then),
+        else_statement: None,
+    }
+    .into()
+}
+
+fn block(statements: Vec<Statement>) -> Statement {
+    let mut block = ast::Block::new();
+    for statement in statements {
+        block.push_statement(statement);
+    }
+    block.into()
+}
+
+/// `throw new Error(message)`
+fn throw_error(class: &str, message: Expression) -> Statement {
+    new_instance(class, vec![message]).into_throw_statement()
+}
+
+/// `for (let name = initializer; condition; incrementor) { statements }`
+fn for_loop(
+    name: &str,
+    initializer: Expression,
+    condition: Expression,
+    incrementor: Expression,
+    statements: Vec<Statement>,
+) -> ast::ForStatement {
+    let mut for_statement = ast::ForStatement {
+        initializer: ast::VariableDeclarationList::declare_let(Arc::new(name.into()), initializer)
+            .into(),
+        condition: Arc::new(condition),
+        incrementor: Arc::new(incrementor),
+        statement: Box::new(ast::Block::new().into()),
+    };
+    for statement in statements {
+        for_statement.push_statement(statement);
+    }
+    for_statement
+}
+
+/// Function, or method of a class, with a body.
+fn function(
+    name: &str,
+    parameters: Vec<ast::Parameter>,
+    return_type: Type,
+    statements: Vec<Statement>,
+) -> ast::FunctionDeclaration {
+    let mut func = ast::FunctionDeclaration::new(name);
+    func.parameters = parameters;
+    func.returns(return_type);
+    for statement in statements {
+        func.push_statement(statement);
+    }
+    func
+}
+
+#[cfg(test)]
+mod test_runtime_module {
+    use super::*;
+    use crate::proto::compiler::ts::test_utils::{
+        compile_sources, render_file_at, run_node_script,
+    };
+
+    #[test]
+    fn it_imports_runtime_relative_to_the_root() {
+        let mut root_file = TsPath::default();
+        root_file.push_file("types");
+        assert_eq!(&*relative_runtime_module(&root_file), "./runtime");
+
+        let mut nested_file = TsPath::default();
+        nested_file.push(TsPathComponent::Folder("app".into()));
+        nested_file.push(TsPathComponent::Folder("User".into()));
+        nested_file.push_file("encode");
+        assert_eq!(&*relative_runtime_module(&nested_file), "../../runtime");
+    }
+
+    #[test]
+    fn it_imports_standalone_runtime_instead_of_protobufjs() {
+        let sources = &[(
+            "shop/order.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage Order { int64 id = 1; map<string, int32> counts = 2; }\n",
+        )];
+        let options = Options {
+            runtime: Runtime::Standalone,
+            ..Options::default()
+        };
+        let folder = compile_sources(sources, &options);
+        let encode = render_file_at(&folder, &["shop", "order", "Order", "encode"]);
+        assert!(encode.starts_with("import { Writer } from \"../../../runtime\"\n"));
+        let decode = render_file_at(&folder, &["shop", "order", "Order", "decode"]);
+        assert!(decode.starts_with("import { Reader } from \"../../../runtime\"\n"));
+        let types = render_file_at(&folder, &["shop", "order", "Order", "types"]);
+        assert!(types.starts_with("import { util } from \"../../../runtime\"\n"));
+        let runtime = render_file_at(&folder, &["runtime"]);
+        assert!(runtime.contains("\nexport class Writer {\n"));
+        assert!(runtime.contains("\nexport class Reader {\n"));
+        assert!(runtime.contains("\nexport namespace util {\n"));
+
+        let folder = compile_sources(sources, &Options::default());
+        let encode = render_file_at(&folder, &["shop", "order", "Order", "encode"]);
+        assert!(encode.contains("import { Writer } from \"protobufjs/minimal\"\n"));
+        assert!(folder.find_file(RUNTIME_FILE_NAME).is_none());
+    }
+
+    #[test]
+    fn it_checks_64_bit_integers_as_bigints_with_the_standalone_runtime() {
+        let sources = &[(
+            "shop/order.proto",
+            "syntax = \"proto3\";\npackage shop;\nmessage Order { int64 id = 1; }\n",
+        )];
+        let options = Options {
+            runtime: Runtime::Standalone,
+            emit_guards: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(sources, &options);
+        let is = render_file_at(&folder, &["shop", "order", "Order", "is"]);
+        assert!(is.contains("  if (!(typeof v.id === \"bigint\"))\n"));
+        let runtime = render_file_at(&folder, &["runtime"]);
+        assert!(runtime.contains("  export type Long = bigint\n"));
+    }
+
+    #[test]
+    fn it_declares_the_runtime_without_verbatim_code() {
+        let file = compile_runtime_module();
+        let names: Vec<&str> = file
+            .ast
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::VariableStatement(list) => &*list.declarations[0].name.text,
+                Statement::NamespaceDeclaration(namespace) => &namespace.name.text,
+                Statement::FunctionDeclaration(function) => &function.name.text,
+                Statement::ClassDeclaration(class) => &class.name.text,
+                statement => panic!("unexpected statement {:?}", statement),
+            })
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "BITS_32",
+                "MASK_32",
+                "util",
+                "toBigInt",
+                "split",
+                "join",
+                "longFromString",
+                "Writer",
+                "Reader"
+            ]
+        );
+    }
+
+    /// Names of the methods of the class, `static` and `private` ones with their modifiers.
+    fn method_names(file: &ast::File, class_name: &str) -> Vec<String> {
+        let class = file
+            .ast
+            .statements
+            .iter()
+            .find_map(|statement| match statement {
+                Statement::ClassDeclaration(class) if &*class.name.text == class_name => {
+                    Some(class)
+                }
+                _ => None,
+            })
+            .unwrap();
+        class
+            .methods
+            .iter()
+            .map(|method| {
+                let modifiers: String = method
+                    .modifiers
+                    .iter()
+                    .map(|modifier| match modifier {
+                        Modifier::Static => "static ",
+                        Modifier::Private => "private ",
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                format!("{}{}", modifiers, method.name.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_declares_writer_and_reader_methods_for_every_scalar_type() {
+        let file = compile_runtime_module();
+        let scalars = [
+            "uint32", "int32", "sint32", "fixed32", "sfixed32", "int64", "uint64", "sint64",
+            "fixed64", "sfixed64", "float", "double", "bool", "string", "bytes",
+        ];
+        let mut expected = vec!["static create"];
+        expected.extend(scalars);
+        expected.extend(["fork", "ldelim", "finish", "raw", "private varint64"]);
+        assert_eq!(method_names(&file, "Writer"), expected);
+
+        let mut expected = vec!["static create"];
+        expected.extend(scalars);
+        expected.extend([
+            "skipType",
+            "private skip",
+            "private ensure",
+            "private view",
+            "private varint64",
+        ]);
+        assert_eq!(method_names(&file, "Reader"), expected);
+    }
+
+    #[test]
+    fn it_renders_fields_and_the_constructor_of_reader() {
+        let runtime = String::from(&compile_runtime_module());
+        assert!(runtime.contains(concat!(
+            "export class Reader {\n",
+            "  pos = 0\n",
+            "  readonly buf: Uint8Array\n",
+            "  readonly len: number\n",
+            "\n",
+            "  constructor(buf: Uint8Array) {\n",
+            "    this.buf = buf\n",
+            "    this.len = buf.length\n",
+            "  }\n",
+            "\n",
+            "  static create(buf: Uint8Array): Reader {\n",
+        )));
+        assert!(runtime.contains(concat!(
+            "export class Writer {\n",
+            "  private buf: number[] = []\n",
+            "  private readonly forks: Array<number[]> = []\n",
+            "\n",
+            "  static create(): Writer {\n",
+        )));
+    }
+
+    #[test]
+    fn it_parenthesizes_mixed_operators() {
+        let runtime = String::from(&compile_runtime_module());
+        assert!(runtime.contains("    return (value >>> 1) ^ -(value & 1)\n"));
+        assert!(runtime.contains(
+            "    return (b[p] | (b[p + 1] << 8) | (b[p + 2] << 16) | (b[p + 3] << 24)) >>> 0\n"
+        ));
+        assert!(runtime.contains("        while ((wireType = this.uint32() & 7) !== 4) {\n"));
+        assert!(runtime.contains("        hi |= value << (shift - 32)\n"));
+    }
+
+    /// Runs `runtime_test.js` with the wire format test vectors.
+    /// Skipped when `node` is not installed.
+    #[test]
+    fn it_passes_runtime_test_vectors() {
+        run_node_script(
+            "the runtime test vectors",
+            concat!(
+                include_str!("load_runtime.js"),
+                include_str!("runtime_test.js")
+            ),
+            &[("PROTOS_TS_RUNTIME", String::from(&compile_runtime_module()))],
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// Checks `Writer` and `Reader` of runtime.ts against wire format test vectors.
+// The source of runtime.ts is passed in the PROTOS_TS_RUNTIME environment variable,
+// `loadRuntime`, `hex` and `bytes` come from load_runtime.js.
+// When protobufjs can be resolved, the same vectors are checked against it.
+const assert = require("assert")
+
+const MAX_UINT64 = BigInt.asUintN(64, BigInt(-1))
+const MAX_SAFE = BigInt(2 ** 53 - 1)
+
+// [type, hex, value]: `value` encodes to `hex` and `hex` decodes to `value`.
+const ROUND_TRIP = [
+  ["uint32", "00", 0],
+  ["uint32", "9601", 150],
+  ["uint32", "ffffffff0f", 4294967295],
+  ["int32", "ffffffffffffffffff01", -1],
+  ["int32", "80808080f8ffffffff01", -2147483648],
+  ["int32", "ffffffff07", 2147483647],
+  ["sint32", "01", -1],
+  ["sint32", "02", 1],
+  ["sint32", "feffffff0f", 2147483647],
+  ["sint32", "ffffffff0f", -2147483648],
+  ["int64", "ffffffffffffffffff01", BigInt(-1)],
+  ["int64", "ffffffffffffff0f", MAX_SAFE],
+  ["int64", "ffffffffffffffff7f", BigInt("9223372036854775807")],
+  ["int64", "80808080808080808001", BigInt("-9223372036854775808")],
+  ["uint64", "ffffffffffffff0f", MAX_SAFE],
+  ["sint64", "01", BigInt(-1)],
+  ["sint64", "feffffffffffff1f", MAX_SAFE],
+  ["fixed32", "ffffffff", 4294967295],
+  ["sfixed32", "ffffffff", -1],
+  ["fixed64", "ffffffffffff1f00", MAX_SAFE],
+  ["sfixed64", "ffffffffffffffff", BigInt(-1)],
+  ["bool", "01", true],
+  ["string", "03616263", "abc"],
+]
+
+// [type, hex, value]: `hex` decodes to `value`, but is not the canonical encoding of it.
+const DECODE_ONLY = [
+  // max uint64, exact since 64-bit integers are bigints
+  ["uint64", "ffffffffffffffffff01", MAX_UINT64],
+  // uint32 keeps the low 32 bits of a sign-extended value
+  ["uint32", "ffffffffffffffffff01", 4294967295],
+  // non-canonical 10-byte encoding of 1
+  ["int32", "81808080808080808000", 1],
+  ["bool", "0a", true],
+]
+
+// [method, hex, strict]: reading fails.
+// `strict` vectors are rejected by runtime.ts only: the `uint32` of protobufjs
+// skips 5 bytes of a long varint without looking at them.
+const MALFORMED = [
+  // truncated varints
+  ["uint32", "", false],
+  ["uint32", "ff", false],
+  ["int64", "ffffffffff", false],
+  // 11 bytes
+  ["uint32", "ffffffffffffffffffff01", true],
+  ["int64", "ffffffffffffffffffff01", false],
+  ["string", "05616263", false],
+  ["fixed32", "ffff", false],
+]
+
+// 64-bit values of protobufjs are `Long` objects when it finds long.js and numbers otherwise,
+// they are compared with the vectors as numbers.
+const toNumber = (value) => (value !== null && typeof value === "object" ? value.toNumber() : Number(value))
+
+function check(name, { Writer, Reader }, strict) {
+  for (const [type, encoded, value] of ROUND_TRIP) {
+    if (!strict && typeof value === "bigint" && value !== BigInt(Number(value))) continue
+    const input = !strict && typeof value === "bigint" ? Number(value) : value
+    assert.strictEqual(hex(Writer.create()[type](input).finish()), encoded, `${name}: encode ${type} ${value}`)
+  }
+  for (const [type, encoded, value] of ROUND_TRIP.concat(DECODE_ONLY)) {
+    const reader = Reader.create(bytes(encoded))
+    const decoded = reader[type]()
+    const loose = !strict && typeof value === "bigint"
+    const actual = loose || typeof value === "number" ? toNumber(decoded) : decoded
+    assert.strictEqual(actual, loose ? Number(value) : value, `${name}: decode ${type} ${encoded}`)
+    assert.strictEqual(reader.pos, reader.len, `${name}: position after ${type} ${encoded}`)
+  }
+  for (const [type, encoded, strictOnly] of MALFORMED) {
+    if (strictOnly && !strict) continue
+    assert.throws(() => Reader.create(bytes(encoded))[type](), `${name}: ${type} ${encoded}`)
+  }
+  if (strict) {
+    assert.throws(() => Reader.create(bytes("ff")).uint32(), /^RangeError: truncated varint at offset 0$/)
+    assert.throws(
+      () => Reader.create(bytes("ffffffffffffffffffff01")).int32(),
+      /^Error: varint at offset 0 is longer than 10 bytes$/,
+    )
+    assert.throws(() => Reader.create(bytes("0affffffffffffffffffff01")).skipType(0).skipType(0), /longer than 10 bytes/)
+  }
+}
+
+check("runtime.ts", loadRuntime(process.env.PROTOS_TS_RUNTIME), true)
+
+let protobufjs = null
+try {
+  protobufjs = require("protobufjs/minimal")
+} catch (e) {}
+if (protobufjs) {
+  check("protobufjs", protobufjs, false)
+}
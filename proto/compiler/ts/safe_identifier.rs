@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use super::is_reserved::is_reserved;
+
+/// Identifiers the generated code refers to: the exports of the runtime module
+/// and the globals of JavaScript and TypeScript.
+/// A declaration with one of these names would shadow them.
+const REFERENCED_NAMES: &[&str] = &[
+    "Writer",
+    "Reader",
+    "util",
+    "Array",
+    "AsyncIterable",
+    "Boolean",
+    "Date",
+    "Error",
+    "JSON",
+    "Math",
+    "Number",
+    "Object",
+    "Partial",
+    "Promise",
+    "Record",
+    "String",
+    "Uint8Array",
+];
+
+/// `name` with a trailing `_` when it cannot be declared as is:
+/// `delete` becomes `delete_` and a message named `Writer` is declared as `Writer_`.
+pub(super) fn safe_identifier(name: &str) -> Arc<str> {
+    if is_reserved(name) || REFERENCED_NAMES.contains(&name) {
+        format!("{}_", name).into()
+    } else {
+        name.into()
+    }
+}
+
+#[cfg(test)]
+mod test_safe_identifier {
+    use super::super::{
+        options::{Layout, Options},
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "default/delete.proto",
+        r#"
+        syntax = "proto3";
+        package default;
+        message Object {
+            enum typeof { var = 0; let = 1; }
+            string delete = 1;
+            typeof enum = 2;
+            repeated string class = 3;
+            Writer writer = 4;
+            oneof switch { string case = 5; int64 new = 6; }
+        }
+        message Writer { Reader reader = 1; }
+        message Reader { string util = 1; }
+        "#,
+    )];
+
+    #[test]
+    fn it_suffixes_reserved_and_referenced_names() {
+        assert_eq!(&*safe_identifier("delete"), "delete_");
+        assert_eq!(&*safe_identifier("typeof"), "typeof_");
+        assert_eq!(&*safe_identifier("Writer"), "Writer_");
+        assert_eq!(&*safe_identifier("Object"), "Object_");
+        assert_eq!(&*safe_identifier("Order"), "Order");
+        assert_eq!(&*safe_identifier("writer"), "writer");
+    }
+
+    #[test]
+    fn it_declares_keywords_and_runtime_names_with_a_suffix() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let types = render_file_at(&folder, &["default", "delete", "Object", "types"]);
+        assert!(types.contains("import { Writer_, Writer_EncodeInput } from \"../Writer/types\"\n"));
+        assert!(types.contains("import { typeof_ } from \"./typeof\"\n"));
+        assert!(types.contains("export interface Object_ {\n  delete: string\n  enum: typeof_\n"));
+        let enum_file = render_file_at(&folder, &["default", "delete", "Object", "typeof"]);
+        assert!(enum_file.starts_with("export enum typeof_ {\n  var = 0,\n  let = 1,\n}"));
+
+        let decode = render_file_at(&folder, &["default", "delete", "Reader", "decode"]);
+        assert!(decode.contains("import { Reader_ } from \"./types\"\n"));
+        assert!(decode.contains("import { Reader } from \"protobufjs/minimal\"\n"));
+        assert!(decode.contains(
+            "export function decode(reader: Reader | Uint8Array, length?: number): Reader_ {\n"
+        ));
+        let encode = render_file_at(&folder, &["default", "delete", "Object", "encode"]);
+        assert!(encode.contains("    w.uint32(10).string(message[\"delete\"])\n"));
+    }
+
+    #[test]
+    fn it_suffixes_joined_names_of_the_file_layout() {
+        let options = Options {
+            layout: Layout::File,
+            ..Options::default()
+        };
+        let file = render_file_at(&compile_sources(SOURCES, &options), &["default", "delete"]);
+        assert!(file.contains("export interface Object_ {\n"));
+        assert!(file.contains("export enum Object_typeof {\n"));
+        assert!(file.contains("export function encodeWriter_(message: Writer_EncodeInput, writer?: Writer): Writer {\n"));
+    }
+}
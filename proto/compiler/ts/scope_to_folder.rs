@@ -1,40 +1,419 @@
-use std::ops::Deref;
+use std::{ops::Deref, sync::Arc};
 
-use super::{ast::*, file_to_folder::file_to_folder};
+use super::{
+    ast::*,
+    declaration_files::into_declaration_files,
+    field_case::validate_property_names,
+    file_case::{file_case_name, validate_file_names},
+    file_to_folder::{file_to_file, file_to_folder},
+    files_to_index::files_to_index,
+    import_extension::add_import_extensions,
+    import_style::apply_import_style,
+    layout::validate_declaration_names,
+    manifest_compiler::{compile_compatibility_module, compile_manifest},
+    options::{Emit, Layout, Options, Runtime},
+    parallel_map::parallel_map,
+    renames::validate_renames,
+    runtime::compile_runtime_module,
+    service_compiler::{compile_transport_module, uses_transport},
+    unknown_fields::validate_unknown_fields_property,
+    well_known_types::{compile_wkt_module, is_mapped_well_known_file, uses_well_known_types},
+};
 use crate::proto::{
     error::ProtoError,
     proto_scope::{root_scope::RootScope, traits::ChildrenScopes, ProtoScope},
 };
 
-fn scope_to_folder(root: &RootScope, scope: &ProtoScope) -> Result<Folder, ProtoError> {
-    let mut folder = Folder::new(scope.name());
-    for child in scope.children().iter() {
-        let child_folder: Folder = match child.deref() {
-            ProtoScope::Root(_) => unreachable!(),
-            p @ ProtoScope::Package(_) => scope_to_folder(root, p)?,
-            f @ ProtoScope::File(_) => file_to_folder(root, f)?,
-            ProtoScope::Enum(_) => unreachable!(),
-            ProtoScope::Message(_) => unreachable!(),
-        };
-        folder.push_folder(child_folder);
+/// Compiles every proto file of the tree.
+///
+/// Files are compiled in parallel, since the output of a file
+/// depends only on the shared `RootScope`. The results are then
+/// placed into package folders in the order of the scope tree,
+/// so the output is the same as the one of a serial compilation.
+/// Entries of every folder are sorted by name.
+/// Errors of all files are reported together.
+///
+/// With `Emit::Both` the files are compiled twice, the second time into declarations.
+pub(crate) fn root_scope_to_folder(
+    root: &RootScope,
+    options: &Options,
+    folder_name: String,
+) -> Result<Folder, ProtoError> {
+    validate_renames(root, options)?;
+    validate_declaration_names(root, options)?;
+    validate_property_names(root, options)?;
+    validate_unknown_fields_property(root, options)?;
+
+    let mut file_scopes = Vec::new();
+    collect_file_scopes(root, options, &root.children, &mut file_scopes);
+
+    let mut folder = match options.emit {
+        Emit::Ts => compile_files(root, options, &file_scopes, folder_name)?,
+        Emit::Dts => {
+            let mut folder = compile_files(root, options, &file_scopes, folder_name)?;
+            into_declaration_files(&mut folder);
+            folder
+        }
+        Emit::Both => {
+            let mut folder = compile_files(root, options, &file_scopes, folder_name.clone())?;
+            let declaration_options = Options {
+                emit: Emit::Dts,
+                ..options.clone()
+            };
+            let mut declarations =
+                compile_files(root, &declaration_options, &file_scopes, folder_name)?;
+            into_declaration_files(&mut declarations);
+            merge_folders(&mut folder, declarations);
+            folder
+        }
+    };
+    if options.runtime == Runtime::Standalone {
+        folder.push_file(compile_runtime_module());
+    }
+    if uses_well_known_types(root, options) {
+        folder.push_file(compile_wkt_module(options));
+    }
+    if uses_transport(options, &file_scopes) {
+        folder.push_file(compile_transport_module());
     }
+    validate_file_names(&folder)?;
+    apply_import_style(&mut folder, options.import_style);
+    add_import_extensions(&mut folder, options.import_extension);
+    folder.sort_entries();
     Ok(folder)
 }
 
-pub(crate) fn root_scope_to_folder(
+/// Compiles the proto files into the folder, without the shared modules.
+fn compile_files(
     root: &RootScope,
+    options: &Options,
+    file_scopes: &[&ProtoScope],
     folder_name: String,
 ) -> Result<Folder, ProtoError> {
+    let mut compiled_files = Vec::new();
+    let mut errors = Vec::new();
+    for res in parallel_map(file_scopes, |file_scope| {
+        compile_file(root, options, file_scope)
+    }) {
+        match res {
+            Ok(entry) => compiled_files.push(entry),
+            Err(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ProtoError::from_errors(errors));
+    }
+
     let mut folder = Folder::new(folder_name.into());
-    for child in root.children.iter() {
-        let child_folder = match child.deref() {
+    if options.layout == Layout::Index {
+        folder.push_file(files_to_index(compiled_files));
+    } else {
+        insert_children(
+            root,
+            options,
+            &mut folder,
+            &[],
+            &root.children,
+            &mut compiled_files.into_iter(),
+        );
+        if options.emit_manifest {
+            folder.push_file(compile_compatibility_module());
+        }
+    }
+    if options.declarations_only {
+        mark_declaration_files(&mut folder);
+    }
+    Ok(folder)
+}
+
+/// Moves the entries of `from` into the folder of the same path of `into`.
+fn merge_folders(into: &mut Folder, from: Folder) {
+    for entry in from.entries {
+        match entry {
+            FolderEntry::Folder(folder) => {
+                let existing = into.entries.iter_mut().find_map(|entry| match entry {
+                    FolderEntry::Folder(existing) if existing.name == folder.name => Some(existing),
+                    _ => None,
+                });
+                match existing {
+                    Some(existing) => merge_folders(existing, *folder),
+                    None => into.entries.push(FolderEntry::Folder(folder)),
+                }
+            }
+            file @ FolderEntry::File(_) => into.entries.push(file),
+        }
+    }
+}
+
+/// Makes every file of the folder a `.d.ts` file.
+/// The runtime and the well-known types that the declarations refer to stay `.ts` modules.
+fn mark_declaration_files(folder: &mut Folder) {
+    for entry in folder.entries.iter_mut() {
+        match entry {
+            FolderEntry::File(file) => file.declaration = true,
+            FolderEntry::Folder(folder) => mark_declaration_files(folder),
+        }
+    }
+}
+
+/// Collects the proto files to compile,
+/// the ones `wkt.ts` replaces in the idiomatic well-known types mode are skipped.
+fn collect_file_scopes<'scope>(
+    root: &RootScope,
+    options: &Options,
+    children: &'scope [Arc<ProtoScope>],
+    file_scopes: &mut Vec<&'scope ProtoScope>,
+) {
+    for child in children.iter() {
+        match child.deref() {
             ProtoScope::Root(_) => unreachable!(),
-            package_child @ ProtoScope::Package(_) => scope_to_folder(root, package_child)?,
-            file_scope @ ProtoScope::File(_) => file_to_folder(root, file_scope)?,
-            ProtoScope::Enum(_) => todo!(),
-            ProtoScope::Message(_) => todo!(),
+            p @ ProtoScope::Package(_) => {
+                collect_file_scopes(root, options, p.children(), file_scopes)
+            }
+            f @ ProtoScope::File(_) if is_mapped_well_known_file(root, options, f) => {}
+            f @ ProtoScope::File(_) => file_scopes.push(f),
+            ProtoScope::Enum(_) => unreachable!(),
+            ProtoScope::Message(_) => unreachable!(),
+        }
+    }
+}
+
+fn compile_file(
+    root: &RootScope,
+    options: &Options,
+    file_scope: &ProtoScope,
+) -> Result<FolderEntry, ProtoError> {
+    match options.layout {
+        Layout::Folder => file_to_folder(root, options, file_scope).map(FolderEntry::from),
+        Layout::File | Layout::Index => {
+            file_to_file(root, options, file_scope).map(FolderEntry::from)
+        }
+    }
+}
+
+/// Places compiled files into the folder, creating a subfolder per package.
+/// `compiled_files` must follow the order of `collect_file_scopes`.
+/// Packages without compiled files get no folder.
+fn insert_children(
+    root: &RootScope,
+    options: &Options,
+    folder: &mut Folder,
+    package_path: &[Arc<str>],
+    children: &[Arc<ProtoScope>],
+    compiled_files: &mut impl Iterator<Item = FolderEntry>,
+) {
+    let mut file_scopes = Vec::new();
+    for child in children.iter() {
+        match child.deref() {
+            ProtoScope::Root(_) => unreachable!(),
+            p @ ProtoScope::Package(_) => {
+                let name = file_case_name(options.file_case, &p.name());
+                if folder.find_folder(&name).is_none() {
+                    folder.push_folder(Folder::new(Arc::clone(&name)));
+                }
+                let package_folder = folder.find_folder_mut(&name).unwrap();
+                let mut path = package_path.to_vec();
+                path.push(Arc::clone(&name));
+                insert_children(
+                    root,
+                    options,
+                    package_folder,
+                    &path,
+                    p.children(),
+                    compiled_files,
+                );
+                if package_folder.entries.is_empty() {
+                    folder
+                        .entries
+                        .retain(|entry| !matches!(entry, FolderEntry::Folder(f) if f.name == name));
+                }
+            }
+            f @ ProtoScope::File(_) if is_mapped_well_known_file(root, options, f) => {}
+            f @ ProtoScope::File(_) => {
+                folder.entries.push(compiled_files.next().unwrap());
+                file_scopes.push(f);
+            }
+            ProtoScope::Enum(_) => unreachable!(),
+            ProtoScope::Message(_) => unreachable!(),
+        }
+    }
+    if options.emit_manifest && !file_scopes.is_empty() {
+        folder.push_file(compile_manifest(package_path, &file_scopes));
+    }
+}
+
+#[cfg(test)]
+mod test_root_scope_to_folder {
+    use super::super::{
+        options::EnumStyle,
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "pkg/a/a.proto",
+            "syntax = \"proto3\";\npackage pkg.a;\nimport \"pkg/b/b.proto\";\nmessage A { pkg.b.B b = 1; }\n",
+        ),
+        (
+            "pkg/b/b.proto",
+            "syntax = \"proto3\";\npackage pkg.b;\nmessage B { string s = 1; }\n",
+        ),
+        (
+            "pkg/a/c.proto",
+            "syntax = \"proto3\";\npackage pkg.a;\nenum C { X = 0; }\n",
+        ),
+    ];
+
+    fn render_folder(folder: &Folder, res: &mut Vec<String>) {
+        for entry in &folder.entries {
+            match entry {
+                FolderEntry::File(file) => res.push(String::from(file.as_ref())),
+                FolderEntry::Folder(subfolder) => {
+                    res.push(subfolder.name.to_string());
+                    render_folder(subfolder, res)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_matches_serial_compilation() {
+        let options = Options::default();
+        let root = crate::proto::package::read_root_scope_from_sources(SOURCES).unwrap();
+        let mut file_scopes = Vec::new();
+        collect_file_scopes(&root, &options, &root.children, &mut file_scopes);
+        let serial = file_scopes
+            .iter()
+            .map(|f| compile_file(&root, &options, f).unwrap())
+            .collect::<Vec<_>>();
+        let mut expected = Folder::new("out".into());
+        insert_children(
+            &root,
+            &options,
+            &mut expected,
+            &[],
+            &root.children,
+            &mut serial.into_iter(),
+        );
+        apply_import_style(&mut expected, options.import_style);
+        expected.sort_entries();
+
+        let mut expected_rendered = Vec::new();
+        render_folder(&expected, &mut expected_rendered);
+        let mut rendered = Vec::new();
+        render_folder(&compile_sources(SOURCES, &options), &mut rendered);
+        assert_eq!(rendered, expected_rendered);
+    }
+
+    #[test]
+    fn it_renders_the_same_output_on_every_run() {
+        let options = Options::default();
+        let mut first = Vec::new();
+        render_folder(&compile_sources(SOURCES, &options), &mut first);
+        for _ in 0..3 {
+            let mut next = Vec::new();
+            render_folder(&compile_sources(SOURCES, &options), &mut next);
+            assert_eq!(next, first);
+        }
+    }
+
+    #[test]
+    fn it_emits_declaration_files() {
+        let options = Options {
+            layout: Layout::File,
+            declarations_only: true,
+            enum_style: EnumStyle::Union,
+            runtime: Runtime::Standalone,
+            ..Options::default()
         };
-        folder.push_folder(child_folder);
+        let folder = compile_sources(SOURCES, &options);
+        let mut file_count = 0;
+        let mut files = vec![&folder];
+        while let Some(folder) = files.pop() {
+            for entry in &folder.entries {
+                match entry {
+                    FolderEntry::File(file) => {
+                        assert_eq!(file.declaration, &*file.name != "runtime");
+                        file_count += 1;
+                    }
+                    FolderEntry::Folder(subfolder) => files.push(subfolder),
+                }
+            }
+        }
+        assert_eq!(file_count, 4);
+        assert_eq!(
+            render_file_at(&folder, &["pkg", "a", "c"]),
+            "export type C = \"X\"\n"
+        );
+        let a = render_file_at(&folder, &["pkg", "a", "a"]);
+        assert!(a.contains("export interface A {\n"));
+        assert!(!a.contains("function"));
+    }
+
+    /// `acme.common` is declared by files of two unrelated folders,
+    /// and `price.proto` imports `money.proto` by its location.
+    const SPLIT_PACKAGE: &[(&str, &str)] = &[
+        (
+            "billing/money.proto",
+            "syntax = \"proto3\";\npackage acme.common;\nmessage Money { int64 units = 1; }\n",
+        ),
+        (
+            "catalog/shared/price.proto",
+            "syntax = \"proto3\";\npackage acme.common;\nimport \"billing/money.proto\";\nmessage Price { Money amount = 1; }\n",
+        ),
+    ];
+
+    #[test]
+    fn it_merges_files_of_a_package_from_different_folders() {
+        let options = Options {
+            layout: Layout::File,
+            emit_manifest: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(SPLIT_PACKAGE, &options);
+        let acme = match &folder.entries[..] {
+            [FolderEntry::Folder(acme), FolderEntry::File(_)] => acme,
+            _ => panic!("unexpected layout"),
+        };
+        let common = match &acme.entries[..] {
+            [FolderEntry::Folder(common)] => common,
+            _ => panic!("unexpected layout"),
+        };
+        let names = common
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                FolderEntry::File(file) => file.name.to_string(),
+                FolderEntry::Folder(folder) => folder.name.to_string(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["manifest", "money", "price"]);
+
+        let price = render_file_at(&folder, &["acme", "common", "price"]);
+        assert!(price.starts_with("import { Money, MoneyEncodeInput, "));
+        assert!(price.contains(" } from \"./money\"\n"));
+        let manifest = render_file_at(&folder, &["acme", "common", "manifest"]);
+        assert!(manifest
+            .contains("  files: [\"billing/money.proto\", \"catalog/shared/price.proto\"],\n"));
+    }
+
+    #[test]
+    fn it_reports_files_with_the_same_name_in_a_package() {
+        let sources = &[
+            (
+                "a/common.proto",
+                "syntax = \"proto3\";\npackage acme;\nmessage A { int32 x = 1; }\n",
+            ),
+            (
+                "b/common.proto",
+                "syntax = \"proto3\";\npackage acme;\nmessage B { int32 x = 1; }\n",
+            ),
+        ];
+        let err = crate::proto::package::read_root_scope_from_sources(sources).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Files a/common.proto and b/common.proto declare the same package acme and have the same name"
+        );
     }
-    Ok(folder)
 }
@@ -0,0 +1,529 @@
+use std::sync::Arc;
+
+use crate::proto::{
+    package::{Method, MethodKind, Service},
+    proto_scope::{file::FileScope, root_scope::RootScope, ProtoScope},
+};
+
+use super::{
+    ast::{self, Call, Folder, StatementList},
+    constants::{DECODE_FUNCTION_NAME, ENCODE_FUNCTION_NAME, GRPC_MODULE, TRANSPORT_FILE_NAME},
+    ensure_import::ensure_import,
+    file_case::file_case_name,
+    layout::{
+        declaration_file_path, declaration_name, encode_type_name, function_name,
+        import_declaration, service_client_name, service_file_path, service_interface_name,
+        service_server_name,
+    },
+    options::Options,
+    ts_path::{TsPath, TsPathComponent},
+    well_known_types::{mapped_well_known_type, wkt_file_path},
+};
+
+const TRANSPORT_SOURCE: &str = include_str!("transport.ts");
+
+/// Whether the clients of services are emitted:
+/// they need the types, the encode and the decode functions of the messages.
+pub(super) fn emits_services(options: &Options) -> bool {
+    options.emit_types && options.emit_encode && options.emit_decode && !options.declarations_only
+}
+
+/// Whether any of the compiled files declares a service, so `transport.ts` is needed.
+pub(super) fn uses_transport(options: &Options, file_scopes: &[&ProtoScope]) -> bool {
+    emits_services(options)
+        && file_scopes
+            .iter()
+            .any(|file| matches!(file, ProtoScope::File(file) if !file.services.is_empty()))
+}
+
+/// Emits `transport.ts`: the `Transport` interface the clients send requests with
+/// and the helpers that encode the requests and decode the responses of every kind of method.
+pub(super) fn compile_transport_module() -> ast::File {
+    let mut file = ast::File::new(TRANSPORT_FILE_NAME.into());
+    file.push_statement(ast::Statement::Verbatim(TRANSPORT_SOURCE.into()));
+    file
+}
+
+/// Adds a file with the client interface and the client class of every service of `file_scope`
+/// to the folder of the file.
+///
+/// A client takes a `Transport` and has a method per rpc, lowerCamelCased,
+/// that encodes the request with the generated `encode` function of the request message
+/// and decodes the response with the `decode` function of the response message.
+/// The interface declares the same methods, for mocks and other implementations of the client.
+/// With `emit_grpc` the file also declares the interface of the handlers of a `@grpc/grpc-js` server.
+pub(super) fn insert_services(
+    root: &RootScope,
+    options: &Options,
+    file_folder: &mut Folder,
+    file_scope: &FileScope,
+) {
+    if !emits_services(options) {
+        return;
+    }
+    for service in &file_scope.services {
+        let mut file = ast::File::new(file_case_name(options.file_case, &service.name));
+        let current_file = service_file_path(options, file_scope, &service.name);
+        let client = compile_client(root, options, &mut file, &current_file, file_scope, service);
+        emit_client_interface(&mut file, &client, options, file_scope, service);
+        file.push_statement(client.into());
+        if options.emit_grpc {
+            emit_server_interface(root, options, &mut file, &current_file, file_scope, service);
+        }
+        file_folder.push_file(file);
+    }
+}
+
+fn compile_client(
+    root: &RootScope,
+    options: &Options,
+    file: &mut ast::File,
+    current_file: &TsPath,
+    file_scope: &FileScope,
+    service: &Service,
+) -> ast::ClassDeclaration {
+    let mut transport_path = TsPath::default();
+    transport_path.push_file(TRANSPORT_FILE_NAME);
+    let transport_type = import_declaration(
+        file,
+        current_file,
+        &transport_path,
+        TsPathComponent::Interface("Transport".into()),
+        None,
+    );
+
+    let mut client = ast::ClassDeclaration::new_exported(service_client_name(
+        options,
+        file_scope,
+        &service.name,
+    ));
+    client
+        .properties
+        .push(ast::Parameter::new("transport", transport_type.into()));
+
+    let full_service_name = match &*file_scope.package {
+        "" => service.name.to_string(),
+        package => format!("{}.{}", package, service.name),
+    };
+    for method in &service.methods {
+        let call_helper = import_declaration(
+            file,
+            current_file,
+            &transport_path,
+            TsPathComponent::Function(call_helper_name(method.kind()).into()),
+            None,
+        );
+        let imports = MessageImports {
+            root,
+            options,
+            current_file,
+        };
+        let input_type = imports.encode_type(file, method.input_id);
+        let output_type = imports.message_type(file, method.output_id);
+        let encode = imports.function(file, method.input_id, ENCODE_FUNCTION_NAME);
+        let decode = imports.function(file, method.output_id, DECODE_FUNCTION_NAME);
+
+        let (parameter_name, parameter_type, return_type) = match method.kind() {
+            MethodKind::Unary => (
+                "request",
+                input_type,
+                ast::Type::generic("Promise", vec![output_type]),
+            ),
+            MethodKind::ServerStreaming => (
+                "request",
+                input_type,
+                ast::Type::generic("AsyncIterable", vec![output_type]),
+            ),
+            MethodKind::ClientStreaming => (
+                "requests",
+                ast::Type::generic("AsyncIterable", vec![input_type]),
+                ast::Type::generic("Promise", vec![output_type]),
+            ),
+            MethodKind::BidiStreaming => (
+                "requests",
+                ast::Type::generic("AsyncIterable", vec![input_type]),
+                ast::Type::generic("AsyncIterable", vec![output_type]),
+            ),
+        };
+
+        let mut method_declaration = ast::FunctionDeclaration::new(&method_name(method));
+        method_declaration.add_param(ast::Parameter::new(parameter_name, parameter_type));
+        method_declaration.returns(return_type);
+        let call = Arc::new(ast::Expression::from(call_helper)).call(vec![
+            Arc::new(ast::Expression::from("this").into_prop("transport")),
+            Arc::new(
+                ast::StringLiteral::new(format!("/{}/{}", full_service_name, method.name).into())
+                    .into(),
+            ),
+            Arc::new(ast::Expression::from(parameter_name)),
+            Arc::new(ast::Expression::from(encode)),
+            Arc::new(ast::Expression::from(decode)),
+        ]);
+        method_declaration.push_statement(ast::Statement::ReturnStatement(Some(call)));
+        client.methods.push(method_declaration);
+    }
+    let interface_name = service_interface_name(options, file_scope, &service.name);
+    client.implements.push(ast::Type::from_id(&interface_name));
+    client
+}
+
+/// Pushes `export interface IGreeterClient` with the signatures of the methods of the `client` class.
+fn emit_client_interface(
+    file: &mut ast::File,
+    client: &ast::ClassDeclaration,
+    options: &Options,
+    file_scope: &FileScope,
+    service: &Service,
+) {
+    let mut interface = ast::InterfaceDeclaration::new_exported(service_interface_name(
+        options,
+        file_scope,
+        &service.name,
+    ));
+    interface.members = client
+        .methods
+        .iter()
+        .map(|method| ast::MethodSignature::from(method).into())
+        .collect();
+    file.push_statement(interface.into());
+}
+
+/// Pushes `export interface GreeterServer extends UntypedServiceImplementation`
+/// with a `@grpc/grpc-js` handler per rpc, so it can be passed to `Server.addService`.
+/// Handlers receive the decoded requests and respond with the encode inputs of the responses:
+///
+/// - unary: `sayHello(call: ServerUnaryCall<Req, Res>, callback: sendUnaryData<Res>): void`
+/// - server streaming: `(call: ServerWritableStream<Req, Res>): void`
+/// - client streaming: `(call: ServerReadableStream<Req, Res>, callback: sendUnaryData<Res>): void`
+/// - bidi streaming: `(call: ServerDuplexStream<Req, Res>): void`
+fn emit_server_interface(
+    root: &RootScope,
+    options: &Options,
+    file: &mut ast::File,
+    current_file: &TsPath,
+    file_scope: &FileScope,
+    service: &Service,
+) {
+    let mut interface = ast::InterfaceDeclaration::new_exported(service_server_name(
+        options,
+        file_scope,
+        &service.name,
+    ));
+    interface
+        .extends
+        .push(grpc_import(file, "UntypedServiceImplementation").into());
+    let imports = MessageImports {
+        root,
+        options,
+        current_file,
+    };
+    for method in &service.methods {
+        let request_type = imports.message_type(file, method.input_id);
+        let response_type = imports.encode_type(file, method.output_id);
+        let call_type = match method.kind() {
+            MethodKind::Unary => "ServerUnaryCall",
+            MethodKind::ServerStreaming => "ServerWritableStream",
+            MethodKind::ClientStreaming => "ServerReadableStream",
+            MethodKind::BidiStreaming => "ServerDuplexStream",
+        };
+        let call_type = grpc_import(file, call_type);
+        let mut parameters = vec![ast::Parameter::new(
+            "call",
+            ast::Type::Generic(call_type, vec![request_type, response_type.clone()]),
+        )];
+        if matches!(
+            method.kind(),
+            MethodKind::Unary | MethodKind::ClientStreaming
+        ) {
+            let callback_type = grpc_import(file, "sendUnaryData");
+            parameters.push(ast::Parameter::new(
+                "callback",
+                ast::Type::Generic(callback_type, vec![response_type]),
+            ));
+        }
+        interface.members.push(
+            ast::MethodSignature {
+                name: method_name(method).as_str().into(),
+                parameters,
+                return_type: ast::Type::Void,
+            }
+            .into(),
+        );
+    }
+    file.push_statement(interface.into());
+}
+
+/// Imports the type named `name` from `@grpc/grpc-js`.
+fn grpc_import(file: &mut ast::File, name: &str) -> Arc<ast::Identifier> {
+    let id: Arc<ast::Identifier> = Arc::new(name.into());
+    ensure_import(
+        file,
+        ast::ImportDeclaration::import(
+            vec![ast::ImportSpecifier {
+                is_type_only: true,
+                ..ast::ImportSpecifier::new(Arc::clone(&id))
+            }],
+            ast::StringLiteral::new(GRPC_MODULE.into()),
+        ),
+    );
+    id
+}
+
+fn call_helper_name(kind: MethodKind) -> &'static str {
+    match kind {
+        MethodKind::Unary => "unaryCall",
+        MethodKind::ServerStreaming => "serverStreamingCall",
+        MethodKind::ClientStreaming => "clientStreamingCall",
+        MethodKind::BidiStreaming => "bidiStreamingCall",
+    }
+}
+
+/// `SayHello` -> `sayHello`
+fn method_name(method: &Method) -> String {
+    let mut chars = method.name.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Imports the declarations of request and response messages into a client file.
+struct MessageImports<'a> {
+    root: &'a RootScope,
+    options: &'a Options,
+    current_file: &'a TsPath,
+}
+
+impl MessageImports<'_> {
+    fn encode_type(&self, file: &mut ast::File, message_id: usize) -> ast::Type {
+        let (target_file, name) = match mapped_well_known_type(self.root, self.options, message_id)
+        {
+            Some(mapped) => (wkt_file_path(), format!("{}EncodeInput", mapped).into()),
+            None => (
+                declaration_file_path(self.root, self.options, message_id, "types"),
+                encode_type_name(self.root, self.options, message_id),
+            ),
+        };
+        import_declaration(
+            file,
+            self.current_file,
+            &target_file,
+            TsPathComponent::Interface(name),
+            None,
+        )
+        .into()
+    }
+
+    fn message_type(&self, file: &mut ast::File, message_id: usize) -> ast::Type {
+        let (target_file, name) = match mapped_well_known_type(self.root, self.options, message_id)
+        {
+            Some(mapped) => (wkt_file_path(), mapped.into()),
+            None => (
+                declaration_file_path(self.root, self.options, message_id, "types"),
+                declaration_name(self.root, self.options, message_id),
+            ),
+        };
+        import_declaration(
+            file,
+            self.current_file,
+            &target_file,
+            TsPathComponent::Interface(name),
+            None,
+        )
+        .into()
+    }
+
+    /// Imports the `encode` or the `decode` function of the message
+    /// under the alias `e{id}` or `d{id}`, since functions of every message share their names.
+    fn function(
+        &self,
+        file: &mut ast::File,
+        message_id: usize,
+        message_function_name: &str,
+    ) -> Arc<ast::Identifier> {
+        let (target_file, name): (TsPath, Arc<str>) =
+            match mapped_well_known_type(self.root, self.options, message_id) {
+                Some(mapped) => (
+                    wkt_file_path(),
+                    format!("{}{}", message_function_name, mapped).into(),
+                ),
+                None => (
+                    declaration_file_path(
+                        self.root,
+                        self.options,
+                        message_id,
+                        message_function_name,
+                    ),
+                    function_name(self.root, self.options, message_id, message_function_name),
+                ),
+            };
+        import_declaration(
+            file,
+            self.current_file,
+            &target_file,
+            TsPathComponent::Function(name),
+            Some(format!("{}{}", &message_function_name[..1], message_id).into()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_service_compiler {
+    use super::*;
+    use crate::proto::compiler::ts::{
+        options::Layout,
+        test_utils::{compile_sources, render_file_at},
+    };
+
+    const SOURCES: &[(&str, &str)] = &[
+        (
+            "chat/chat.proto",
+            r#"
+            syntax = "proto3";
+            package chat;
+            import "chat/message.proto";
+            import "google/protobuf/timestamp.proto";
+            service Chat {
+                rpc Send(Message) returns (Ack);
+                rpc Watch(google.protobuf.Timestamp) returns (stream Message);
+                rpc Upload(stream Message) returns (Ack);
+                rpc Talk(stream Message) returns (stream Message);
+            }
+            message Ack { bool ok = 1; }
+            "#,
+        ),
+        (
+            "chat/message.proto",
+            r#"
+            syntax = "proto3";
+            package chat;
+            message Message { string text = 1; }
+            "#,
+        ),
+    ];
+
+    #[test]
+    fn it_emits_a_client_per_service() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let client = render_file_at(&folder, &["chat", "chat", "Chat"]);
+        assert_eq!(
+            client,
+            concat!(
+                "import { Transport, bidiStreamingCall, clientStreamingCall, serverStreamingCall, unaryCall } from \"../../transport\"\n",
+                "import { TimestampEncodeInput, encodeTimestamp as e2 } from \"../../wkt\"\n",
+                "import { decode as d3 } from \"../message/Message/decode\"\n",
+                "import { encode as e3 } from \"../message/Message/encode\"\n",
+                "import { Message, MessageEncodeInput } from \"../message/Message/types\"\n",
+                "import { decode as d1 } from \"./Ack/decode\"\n",
+                "import { Ack } from \"./Ack/types\"\n",
+                "\n",
+                "export interface IChatClient {\n",
+                "  send(request: MessageEncodeInput): Promise<Ack>\n",
+                "  watch(request: TimestampEncodeInput): AsyncIterable<Message>\n",
+                "  upload(requests: AsyncIterable<MessageEncodeInput>): Promise<Ack>\n",
+                "  talk(requests: AsyncIterable<MessageEncodeInput>): AsyncIterable<Message>\n",
+                "}\n",
+                "\n",
+                "export class ChatClient implements IChatClient {\n",
+                "  constructor(private readonly transport: Transport) {}\n",
+                "\n",
+                "  send(request: MessageEncodeInput): Promise<Ack> {\n",
+                "    return unaryCall(this.transport, \"/chat.Chat/Send\", request, e3, d1)\n",
+                "  }\n",
+                "\n",
+                "  watch(request: TimestampEncodeInput): AsyncIterable<Message> {\n",
+                "    return serverStreamingCall(this.transport, \"/chat.Chat/Watch\", request, e2, d3)\n",
+                "  }\n",
+                "\n",
+                "  upload(requests: AsyncIterable<MessageEncodeInput>): Promise<Ack> {\n",
+                "    return clientStreamingCall(this.transport, \"/chat.Chat/Upload\", requests, e3, d1)\n",
+                "  }\n",
+                "\n",
+                "  talk(requests: AsyncIterable<MessageEncodeInput>): AsyncIterable<Message> {\n",
+                "    return bidiStreamingCall(this.transport, \"/chat.Chat/Talk\", requests, e3, d3)\n",
+                "  }\n",
+                "}\n",
+            )
+        );
+        assert_eq!(
+            render_file_at(&folder, &["transport"]),
+            format!("{}\n", TRANSPORT_SOURCE.trim_end())
+        );
+    }
+
+    #[test]
+    fn it_appends_clients_to_the_file_of_the_proto_file() {
+        let options = Options {
+            layout: Layout::File,
+            ..Options::default()
+        };
+        let chat = render_file_at(&compile_sources(SOURCES, &options), &["chat", "chat"]);
+        assert!(chat.contains(
+            "import { Transport, bidiStreamingCall, clientStreamingCall, serverStreamingCall, unaryCall } from \"../transport\"\n"
+        ));
+        assert!(chat.contains("export class ChatClient implements IChatClient {\n"));
+        assert!(chat.contains(
+            "    return unaryCall(this.transport, \"/chat.Chat/Send\", request, e3, decodeAck)\n"
+        ));
+    }
+
+    #[test]
+    fn it_declares_clients_inside_of_package_namespaces() {
+        let options = Options {
+            layout: Layout::Index,
+            ..Options::default()
+        };
+        let index = render_file_at(&compile_sources(SOURCES, &options), &["index"]);
+        assert!(index.contains(
+            "export namespace chat {\n  export interface IChatClient {\n    send(request: chat.MessageEncodeInput): Promise<chat.Ack>\n"
+        ));
+        assert!(index.contains("  export class ChatClient implements chat.IChatClient {\n"));
+        assert!(index.contains(
+            "    send(request: chat.MessageEncodeInput): Promise<chat.Ack> {\n      return unaryCall(this.transport, \"/chat.Chat/Send\", request, chat.Message.encode, chat.Ack.decode)\n"
+        ));
+    }
+
+    #[test]
+    fn it_declares_grpc_server_handlers_of_every_kind_of_method() {
+        let options = Options {
+            emit_grpc: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let chat = render_file_at(&folder, &["chat", "chat", "Chat"]);
+        assert!(chat.contains(
+            "import { ServerDuplexStream, ServerReadableStream, ServerUnaryCall, ServerWritableStream, UntypedServiceImplementation, sendUnaryData } from \"@grpc/grpc-js\"\n"
+        ));
+        assert!(chat.ends_with(concat!(
+            "export interface ChatServer extends UntypedServiceImplementation {\n",
+            "  send(call: ServerUnaryCall<Message, AckEncodeInput>, callback: sendUnaryData<AckEncodeInput>): void\n",
+            "  watch(call: ServerWritableStream<Timestamp, MessageEncodeInput>): void\n",
+            "  upload(call: ServerReadableStream<Message, AckEncodeInput>, callback: sendUnaryData<AckEncodeInput>): void\n",
+            "  talk(call: ServerDuplexStream<Message, MessageEncodeInput>): void\n",
+            "}\n",
+        )));
+
+        let chat = render_file_at(
+            &compile_sources(SOURCES, &Options::default()),
+            &["chat", "chat", "Chat"],
+        );
+        assert!(!chat.contains("@grpc/grpc-js"));
+        assert!(!chat.contains("ChatServer"));
+    }
+
+    #[test]
+    fn it_skips_clients_without_encode_and_decode() {
+        let options = Options {
+            emit_decode: false,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        assert!(folder.find_file(TRANSPORT_FILE_NAME).is_none());
+        let options = Options {
+            layout: Layout::File,
+            ..options
+        };
+        let chat = render_file_at(&compile_sources(SOURCES, &options), &["chat", "chat"]);
+        assert!(!chat.contains("ChatClient"));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::proto::package::read_root_scope_from_sources;
+
+use super::{ast::Folder, options::Options, scope_to_folder::root_scope_to_folder};
+
+/// Compiles `(file_path, content)` proto sources into the `out` folder.
+pub(super) fn compile_sources(sources: &[(&str, &str)], options: &Options) -> Folder {
+    let root = read_root_scope_from_sources(sources).unwrap();
+    root_scope_to_folder(&root, options, "out".into()).unwrap()
+}
+
+/// Renders the file located at `path` inside of the `folder`.
+/// The last component of the path is the file name without the extension.
+pub(super) fn render_file_at(folder: &Folder, path: &[&str]) -> String {
+    let (name, folder_path) = path.split_last().unwrap();
+    let file = folder
+        .find_folder_by_path(folder_path)
+        .and_then(|parent| parent.find_file(name))
+        .unwrap_or_else(|| panic!("{} is not found in {}", path.join("/"), folder.name));
+    String::from(file)
+}
+
+/// Runs the node `script` with the generated `files` in the environment variables
+/// the `*_test.js` scripts read them from, `PROTOS_TS_RUNTIME` or `PROTOS_TS_MODULE`.
+/// Skipped when `node` is not installed, `name` describes what is skipped in the message.
+/// Fails with the stderr of the script when it throws.
+pub(super) fn run_node_script(name: &str, script: &str, files: &[(&str, String)]) {
+    let output = match std::process::Command::new("node")
+        .arg("-e")
+        .arg(script)
+        .envs(files.iter().map(|(variable, content)| (variable, content)))
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("node is not found, skipping {}", name);
+            return;
+        }
+        Err(e) => panic!("{}", e),
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -1,14 +1,166 @@
+use super::is_safe_id::is_safe_id;
+
+/// Renders `text` as a double quoted JS string literal.
 pub(super) fn to_js_string(text: &str) -> String {
-    let mut res = String::new();
+    let mut res = String::with_capacity(text.len() + 2);
+    res.push('"');
+    res.push_str(&escape_js_string(text));
     res.push('"');
+    res
+}
+
+/// Escapes `text` so it can be placed between two double quotes.
+///
+/// Besides the quote itself and backslashes, control characters and
+/// line terminators (including U+2028 and U+2029) are escaped,
+/// so the literal always stays on a single line.
+fn escape_js_string(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
     for char in text.chars() {
         match char {
-            '\"' => res.push_str(r#"\""#),
             '\\' => res.push_str(r"\\"),
-            _ => res.push(char),
+            '"' => res.push_str(r#"\""#),
+            '\n' => res.push_str(r"\n"),
+            '\r' => res.push_str(r"\r"),
+            '\t' => res.push_str(r"\t"),
+            '\u{8}' => res.push_str(r"\b"),
+            '\u{b}' => res.push_str(r"\v"),
+            '\u{c}' => res.push_str(r"\f"),
+            '\u{2028}' => res.push_str(r"\u2028"),
+            '\u{2029}' => res.push_str(r"\u2029"),
+            c if c.is_ascii_control() => res.push_str(&format!("\\x{:02x}", c as u32)),
+            c => res.push(c),
         }
-
     }
-    res.push('"');
     res
-}
\ No newline at end of file
+}
+
+/// Renders a property name of an object literal or an interface:
+/// safe identifiers are kept as is, everything else is quoted.
+pub(super) fn to_property_name(name: &str) -> String {
+    let starts_with_digit = name.chars().next().is_none_or(|c| c.is_ascii_digit());
+    if is_safe_id(name) && !starts_with_digit {
+        return name.to_string();
+    }
+    to_js_string(name)
+}
+
+#[cfg(test)]
+mod test_to_js_string {
+    use super::*;
+
+    /// Parses a JS string literal following the `StringLiteral` and
+    /// `NoSubstitutionTemplate` grammar and returns its value.
+    fn parse_js_string(literal: &str) -> Result<String, String> {
+        let mut chars = literal.chars();
+        let quote = chars.next().ok_or("empty literal")?;
+        if !matches!(quote, '"' | '\'' | '`') {
+            return Err(format!("unexpected opening quote {:?}", quote));
+        }
+        let mut res = String::new();
+        loop {
+            let char = chars.next().ok_or("unterminated literal")?;
+            match char {
+                c if c == quote => break,
+                '\\' => {
+                    let escaped = chars.next().ok_or("unterminated escape")?;
+                    match escaped {
+                        'n' => res.push('\n'),
+                        'r' => res.push('\r'),
+                        't' => res.push('\t'),
+                        'b' => res.push('\u{8}'),
+                        'v' => res.push('\u{b}'),
+                        'f' => res.push('\u{c}'),
+                        '0' => res.push('\0'),
+                        'x' => {
+                            let hex: String = chars.by_ref().take(2).collect();
+                            let code = u32::from_str_radix(&hex, 16).map_err(|e| e.to_string())?;
+                            res.push(char::from_u32(code).ok_or("invalid \\x escape")?);
+                        }
+                        'u' => {
+                            let hex: String = chars.by_ref().take(4).collect();
+                            let code = u32::from_str_radix(&hex, 16).map_err(|e| e.to_string())?;
+                            res.push(char::from_u32(code).ok_or("invalid \\u escape")?);
+                        }
+                        '1'..='9' => return Err("octal escapes are not allowed".into()),
+                        '\n' | '\r' | '\u{2028}' | '\u{2029}' => {}
+                        c => res.push(c),
+                    }
+                }
+                '$' if quote == '`' && chars.clone().next() == Some('{') => {
+                    return Err("unescaped substitution in template literal".into())
+                }
+                '\n' | '\r' if quote != '`' => return Err("unescaped line terminator".into()),
+                c => res.push(c),
+            }
+        }
+        match chars.next() {
+            None => Ok(res),
+            Some(c) => Err(format!("unexpected {:?} after the closing quote", c)),
+        }
+    }
+
+    /// Deterministic xorshift generator, so failures are reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn string(&mut self) -> String {
+            const INTERESTING: &[char] = &[
+                '"', '\'', '`', '\\', '$', '{', '}', '\n', '\r', '\t', '\0', '\u{8}', '\u{b}',
+                '\u{c}', '\u{1b}', '\u{7f}', '\u{2028}', '\u{2029}', 'a', '1', ' ', '/', 'é', '💡',
+            ];
+            let len = (self.next() % 16) as usize;
+            (0..len)
+                .map(|_| match self.next() % 4 {
+                    0 => char::from_u32((self.next() % 0x3000) as u32).unwrap_or('?'),
+                    _ => INTERESTING[(self.next() % INTERESTING.len() as u64) as usize],
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn it_escapes_special_characters() {
+        assert_eq!(to_js_string("it's"), "\"it's\"");
+        assert_eq!(to_js_string("a\"b"), r#""a\"b""#);
+        assert_eq!(to_js_string(r"C:\dir"), r#""C:\\dir""#);
+        assert_eq!(to_js_string("a\nb\u{2028}"), r#""a\nb\u2028""#);
+        assert_eq!(to_js_string("\0\u{1b}"), r#""\x00\x1b""#);
+        assert_eq!(to_js_string("`${a}`"), "\"`${a}`\"");
+    }
+
+    #[test]
+    fn it_round_trips_arbitrary_strings() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..2000 {
+            let text = rng.string();
+            let literal = to_js_string(&text);
+            assert!(
+                !literal.contains(['\n', '\r', '\u{2028}', '\u{2029}']),
+                "{:?} is not single line",
+                literal
+            );
+            assert_eq!(
+                parse_js_string(&literal).as_deref(),
+                Ok(text.as_str()),
+                "{:?}",
+                literal
+            );
+        }
+    }
+
+    #[test]
+    fn it_quotes_unsafe_property_names() {
+        assert_eq!(to_property_name("fooBar"), "fooBar");
+        assert_eq!(to_property_name("foo-bar"), "\"foo-bar\"");
+        assert_eq!(to_property_name("1st"), "\"1st\"");
+        assert_eq!(to_property_name(""), "\"\"");
+    }
+}
@@ -0,0 +1,62 @@
+// Transport the generated service clients send their requests with.
+// Requests and responses are passed as encoded messages, so any gRPC or gRPC-web library can implement it.
+
+export interface Transport {
+  unary(method: string, request: Uint8Array): Promise<Uint8Array>
+  serverStreaming(method: string, request: Uint8Array): AsyncIterable<Uint8Array>
+  clientStreaming(method: string, requests: AsyncIterable<Uint8Array>): Promise<Uint8Array>
+  bidiStreaming(method: string, requests: AsyncIterable<Uint8Array>): AsyncIterable<Uint8Array>
+}
+
+export type Encode<T> = (message: T) => { finish(): Uint8Array }
+export type Decode<T> = (bytes: Uint8Array) => T
+
+async function* encodeAll<T>(messages: AsyncIterable<T>, encode: Encode<T>): AsyncIterable<Uint8Array> {
+  for await (const message of messages) {
+    yield encode(message).finish()
+  }
+}
+
+export async function unaryCall<I, O>(
+  transport: Transport,
+  method: string,
+  request: I,
+  encode: Encode<I>,
+  decode: Decode<O>,
+): Promise<O> {
+  return decode(await transport.unary(method, encode(request).finish()))
+}
+
+export async function* serverStreamingCall<I, O>(
+  transport: Transport,
+  method: string,
+  request: I,
+  encode: Encode<I>,
+  decode: Decode<O>,
+): AsyncIterable<O> {
+  for await (const response of transport.serverStreaming(method, encode(request).finish())) {
+    yield decode(response)
+  }
+}
+
+export async function clientStreamingCall<I, O>(
+  transport: Transport,
+  method: string,
+  requests: AsyncIterable<I>,
+  encode: Encode<I>,
+  decode: Decode<O>,
+): Promise<O> {
+  return decode(await transport.clientStreaming(method, encodeAll(requests, encode)))
+}
+
+export async function* bidiStreamingCall<I, O>(
+  transport: Transport,
+  method: string,
+  requests: AsyncIterable<I>,
+  encode: Encode<I>,
+  decode: Decode<O>,
+): AsyncIterable<O> {
+  for await (const response of transport.bidiStreaming(method, encodeAll(requests, encode))) {
+    yield decode(response)
+  }
+}
@@ -1,15 +1,18 @@
-use std::{ops::Deref, rc::Rc};
+use std::{ops::Deref, sync::Arc};
 
-use super::file_name_to_folder_name::file_name_to_folder_name;
+use super::{
+    file_case::file_case_name, file_name_to_folder_name::file_name_to_folder_name,
+    options::FileCase,
+};
 use crate::proto::protopath::{PathComponent, ProtoPath};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(super) enum TsPathComponent {
-    Folder(Rc<str>),
-    File(Rc<str>),
-    Enum(Rc<str>),
-    Interface(Rc<str>),
-    Function(Rc<str>),
+    Folder(Arc<str>),
+    File(Arc<str>),
+    Enum(Arc<str>),
+    Interface(Arc<str>),
+    Function(Arc<str>),
 }
 
 impl From<&TsPathComponent> for String {
@@ -42,7 +45,7 @@ impl TsPathComponent {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct TsPath {
     path: Vec<TsPathComponent>,
 }
@@ -59,10 +62,24 @@ impl TsPath {
         self.path.push(item);
     }
     pub fn push_file(&mut self, file_name: &str) {
-        self.push(TsPathComponent::File(Rc::from(file_name)))
+        self.push(TsPathComponent::File(Arc::from(file_name)))
     }
-    pub fn push_function(&mut self, file_name: &str) {
-        self.push(TsPathComponent::Function(Rc::from(file_name)))
+    /// The path with folders and files named in `file_case`, as `scope_to_folder` names them.
+    pub fn with_file_case(self, file_case: FileCase) -> Self {
+        let path = self
+            .path
+            .into_iter()
+            .map(|component| match component {
+                TsPathComponent::Folder(name) => {
+                    TsPathComponent::Folder(file_case_name(file_case, &name))
+                }
+                TsPathComponent::File(name) => {
+                    TsPathComponent::File(file_case_name(file_case, &name))
+                }
+                declaration => declaration,
+            })
+            .collect();
+        TsPath { path }
     }
 }
 
@@ -74,18 +91,19 @@ impl Default for TsPath {
 
 impl std::fmt::Display for TsPath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.path.is_empty() {
-            return Ok(());
-        }
-        for (prev, cur) in self.path.iter().zip(self.path[1..].iter()) {
-            match (prev, cur) {
-                (TsPathComponent::Folder(prev), _) => write!(f, "{}/", prev)?,
-                (TsPathComponent::File(prev), _) => write!(f, "{}::", prev)?,
-                (_, _) => unreachable!(),
+        let mut prev: Option<&TsPathComponent> = None;
+        for component in self.path.iter() {
+            if let Some(prev) = prev {
+                f.write_str(match prev {
+                    TsPathComponent::Folder(_) => "/",
+                    TsPathComponent::File(_) => "::",
+                    _ => ".",
+                })?;
             }
+            f.write_str(&String::from(component))?;
+            prev = Some(component);
         }
-        let str: String = self.path.last().unwrap().into();
-        f.write_str(&str)
+        Ok(())
     }
 }
 
@@ -99,20 +117,73 @@ impl From<ProtoPath> for TsPath {
         for p in path.iter() {
             match p {
                 PathComponent::Package(s) => {
-                    res.path.push(TsPathComponent::Folder(Rc::clone(&s)));
+                    res.path.push(TsPathComponent::Folder(Arc::clone(&s)));
                 }
                 PathComponent::File(s) => {
                     res.path
                         .push(TsPathComponent::Folder(file_name_to_folder_name(s)));
                 }
                 PathComponent::Message(s) => {
-                    res.path.push(TsPathComponent::Folder(Rc::clone(&s)));
+                    res.path.push(TsPathComponent::Folder(Arc::clone(&s)));
                 }
                 PathComponent::Enum(s) => {
-                    res.path.push(TsPathComponent::File(Rc::clone(&s)));
+                    res.path.push(TsPathComponent::File(Arc::clone(&s)));
                 }
             }
         }
         res
     }
 }
+
+#[cfg(test)]
+mod test_ts_path {
+    use super::*;
+
+    fn path(components: Vec<TsPathComponent>) -> TsPath {
+        TsPath { path: components }
+    }
+
+    #[test]
+    fn it_writes_every_component() {
+        let function = path(vec![
+            TsPathComponent::Folder("common".into()),
+            TsPathComponent::Folder("types".into()),
+            TsPathComponent::File("User".into()),
+            TsPathComponent::Function("encodeUser".into()),
+        ]);
+        assert_eq!(function.to_string(), "common/types/User::encodeUser");
+
+        let enum_path = path(vec![
+            TsPathComponent::Folder("common".into()),
+            TsPathComponent::File("Role".into()),
+            TsPathComponent::Enum("Role".into()),
+        ]);
+        assert_eq!(enum_path.to_string(), "common/Role::Role");
+    }
+
+    #[test]
+    fn it_does_not_panic_on_nested_declarations() {
+        let member = path(vec![
+            TsPathComponent::File("types".into()),
+            TsPathComponent::Interface("User".into()),
+            TsPathComponent::Enum("Role".into()),
+        ]);
+        assert_eq!(member.to_string(), "types::User.Role");
+        assert_eq!(TsPath::default().to_string(), "");
+    }
+
+    #[test]
+    fn it_converts_proto_paths() {
+        let mut proto_path = ProtoPath::new();
+        proto_path.push(PathComponent::Package("common".into()));
+        proto_path.push(PathComponent::File("types.proto".into()));
+        proto_path.push(PathComponent::Enum("Role".into()));
+        assert_eq!(TsPath::from(proto_path).to_string(), "common/types/Role");
+
+        let mut proto_path = ProtoPath::new();
+        proto_path.push(PathComponent::File("types.proto".into()));
+        proto_path.push(PathComponent::Message("User".into()));
+        proto_path.push(PathComponent::Message("Address".into()));
+        assert_eq!(TsPath::from(proto_path).to_string(), "types/User/Address");
+    }
+}
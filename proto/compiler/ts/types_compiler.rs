@@ -1,4 +1,4 @@
-use std::{ops::Deref, rc::Rc};
+use std::{ops::Deref, sync::Arc};
 
 use crate::proto::{
     compiler::ts::ast::{self, Type},
@@ -9,22 +9,41 @@ use crate::proto::{
 
 use super::{
     ast::Folder,
-    constants::PROTOBUF_MODULE,
+    constants::UNKNOWN_FIELDS_PROPERTY,
     ensure_import::ensure_import,
-    get_relative_import::get_relative_import,
-    message_name_to_encode_type_name::message_name_to_encode_type_name,
-    ts_path::{TsPath, TsPathComponent},
+    has_fields::{has_field, has_field_name},
+    import_enum::import_enum,
+    int64_type::is_bigint,
+    layout::{
+        declaration_file_path, declaration_name, encode_type_name, import_declaration,
+        import_type_declaration,
+    },
+    options::{Options, WellKnownTypes},
+    renames::property_name,
+    runtime::runtime_module,
+    ts_path::TsPathComponent,
+    unknown_fields::unknown_field_type,
+    well_known_type::{map_well_known_type, TypeUsage},
 };
 
 pub(super) fn insert_message_types(
     root: &RootScope,
+    options: &Options,
     message_folder: &mut Folder,
     message_scope: &ProtoScope,
 ) -> Result<(), ProtoError> {
     let mut file = super::ast::File::new("types".into());
 
-    insert_encoded_input_interface(&root, &mut file, &message_scope)?;
-    insert_decode_result_interface(&root, &mut file, &message_scope)?;
+    insert_encoded_input_interface(root, options, &mut file, message_scope)?;
+    insert_decode_result_interface(root, options, &mut file, message_scope)?;
+    // enums and `util` are referenced only in the types of the interfaces
+    for statement in file.ast.statements.iter_mut() {
+        if let ast::Statement::ImportDeclaration(import) = statement {
+            for specifier in import.import_clause.named_bindings.iter_mut().flatten() {
+                specifier.is_type_only = true;
+            }
+        }
+    }
 
     message_folder.push_file(file);
 
@@ -33,44 +52,70 @@ pub(super) fn insert_message_types(
 
 fn insert_encoded_input_interface(
     root: &RootScope,
+    options: &Options,
     types_file: &mut ast::File,
     message_scope: &ProtoScope,
 ) -> Result<(), ProtoError> {
-    let message_name = message_scope.name();
-    let mut interface = ast::InterfaceDeclaration::new_exported(
-        message_name_to_encode_type_name(&message_name).into(),
-    );
+    let mut interface = ast::InterfaceDeclaration::new_exported(encode_type_name(
+        root,
+        options,
+        message_scope.id().unwrap(),
+    ));
     let message_declaration = match message_scope {
         ProtoScope::Message(m) => m,
         _ => unreachable!(),
     };
+    interface.deprecated = message_declaration.deprecated;
     for entry in &message_declaration.entries {
         match entry {
             MessageEntry::Field(f) => {
-                let property_type =
-                    import_encoding_input_type(&root, &message_scope, types_file, &f.field_type)?
-                        .or(&Type::Null);
-                interface.members.push(
-                    ast::PropertySignature::new_optional(f.json_name(), property_type).into(),
+                let property_type = import_encoding_input_type(
+                    root,
+                    options,
+                    message_scope,
+                    types_file,
+                    &f.field_type,
+                )?;
+                // Fields with explicit presence are encoded whenever they are not undefined
+                let property_type = if f.optional {
+                    property_type
+                } else {
+                    property_type.or(&Type::Null)
+                };
+                let mut property = ast::PropertySignature::new_optional(
+                    property_name(root, options, message_scope.id().unwrap(), f),
+                    property_type,
                 );
+                property.deprecated = f.options.deprecated;
+                interface.members.push(property.into());
             }
             MessageEntry::OneOf(one_of) => {
                 for option in &one_of.options {
                     let property_type = import_encoding_input_type(
-                        &root,
-                        &message_scope,
+                        root,
+                        options,
+                        message_scope,
                         types_file,
                         &option.field_type,
                     )?
                     .or(&Type::Null);
-                    interface.members.push(
-                        ast::PropertySignature::new_optional(option.json_name(), property_type)
-                            .into(),
+                    let mut property = ast::PropertySignature::new_optional(
+                        property_name(root, options, message_scope.id().unwrap(), option),
+                        property_type,
                     );
+                    property.deprecated = option.options.deprecated;
+                    interface.members.push(property.into());
                 }
             }
         }
     }
+    if options.keep_unknown_fields {
+        let property = ast::PropertySignature::new_optional(
+            UNKNOWN_FIELDS_PROPERTY.into(),
+            repeated_type(options, unknown_field_type()).or(&Type::Null),
+        );
+        interface.members.push(property.into());
+    }
 
     types_file.ast.statements.push(interface.into());
     Ok(())
@@ -78,41 +123,93 @@ fn insert_encoded_input_interface(
 
 fn insert_decode_result_interface(
     root: &RootScope,
+    options: &Options,
     types_file: &mut ast::File,
     message_scope: &ProtoScope,
 ) -> Result<(), ProtoError> {
-    let mut interface = ast::InterfaceDeclaration::new_exported(message_scope.name().into());
+    let mut interface = ast::InterfaceDeclaration::new_exported(declaration_name(
+        root,
+        options,
+        message_scope.id().unwrap(),
+    ));
     let message_declaration = match message_scope {
         ProtoScope::Message(m) => m,
         _ => unreachable!(),
     };
+    interface.deprecated = message_declaration.deprecated;
     for entry in &message_declaration.entries {
         use crate::proto::package::MessageEntry::*;
         match entry {
             Field(f) => {
-                let property_type =
-                    import_decode_result_type(&root, &message_scope, types_file, &f.field_type)?;
-                interface
-                    .members
-                    .push(ast::PropertySignature::new(f.json_name(), property_type).into())
+                let mut property_type = import_decode_result_type(
+                    root,
+                    options,
+                    message_scope,
+                    types_file,
+                    &f.field_type,
+                )?;
+                // `create` leaves message fields undefined until they are decoded,
+                // `unknown` of `google.protobuf.Value` includes `undefined` already
+                if matches!(f.field_type, package::Type::Message(_))
+                    && !f.may_be_unset()
+                    && property_type != Type::Unknown
+                {
+                    property_type = property_type.or(&Type::Undefined);
+                }
+                let mut property = if f.may_be_unset() {
+                    ast::PropertySignature::new_optional(
+                        property_name(root, options, message_scope.id().unwrap(), f),
+                        property_type,
+                    )
+                } else {
+                    ast::PropertySignature::new(
+                        property_name(root, options, message_scope.id().unwrap(), f),
+                        property_type,
+                    )
+                };
+                property.deprecated = f.options.deprecated;
+                interface.members.push(property.into());
+                if has_field(options, f) {
+                    let name = has_field_name(root, options, message_scope.id().unwrap(), f);
+                    interface
+                        .members
+                        .push(ast::PropertySignature::new(name, Type::Boolean).into());
+                }
             }
             OneOf(one_of) => {
                 for option in &one_of.options {
                     let property_type = import_decode_result_type(
-                        &root,
-                        &message_scope,
+                        root,
+                        options,
+                        message_scope,
                         types_file,
                         &option.field_type,
                     )?
                     .or(&Type::Null);
-                    interface.members.push(
-                        ast::PropertySignature::new_optional(option.json_name(), property_type)
-                            .into(),
+                    let mut property = ast::PropertySignature::new_optional(
+                        property_name(root, options, message_scope.id().unwrap(), option),
+                        property_type,
                     );
+                    property.deprecated = option.options.deprecated;
+                    interface.members.push(property.into());
                 }
             }
         }
     }
+    if options.keep_unknown_fields {
+        let property = ast::PropertySignature::new_optional(
+            UNKNOWN_FIELDS_PROPERTY.into(),
+            repeated_type(options, unknown_field_type()),
+        );
+        interface.members.push(property.into());
+    }
+    if options.readonly_types {
+        for member in interface.members.iter_mut() {
+            if let ast::InterfaceMember::PropertySignature(property) = member {
+                property.readonly = true;
+            }
+        }
+    }
 
     types_file.ast.statements.push(interface.into());
     Ok(())
@@ -120,19 +217,24 @@ fn insert_decode_result_interface(
 
 fn import_encoding_input_type(
     root: &RootScope,
+    options: &Options,
     message_scope: &ProtoScope,
     types_file: &mut ast::File,
     field_type: &package::Type,
 ) -> Result<Type, ProtoError> {
+    if let Some(mapped) = well_known_field_type(root, options, field_type, TypeUsage::Encode) {
+        return Ok(mapped);
+    }
     match field_type {
-        package::Type::Enum(e_id) => import_enum_type(root, message_scope, types_file, *e_id),
+        package::Type::Enum(e_id) => {
+            import_enum_type(root, options, message_scope, types_file, *e_id)
+        }
         package::Type::Message(m_id) => {
             let imported_message_id = *m_id;
-            let imported_name = Rc::from(message_name_to_encode_type_name(
-                &root.get_declaration_name(imported_message_id).unwrap(),
-            ));
+            let imported_name = encode_type_name(root, options, imported_message_id);
             import_message_type(
                 root,
+                options,
                 message_scope,
                 types_file,
                 imported_message_id,
@@ -141,14 +243,16 @@ fn import_encoding_input_type(
         }
         package::Type::Repeated(field_type) => {
             let element_type =
-                import_encoding_input_type(root, message_scope, types_file, field_type)?;
-            return Ok(Type::array(element_type));
+                import_encoding_input_type(root, options, message_scope, types_file, field_type)?;
+            Ok(repeated_type(options, element_type))
         }
         package::Type::Map(key, value) => {
             let key_type = resolve_key_type(key);
-            let value_type = import_encoding_input_type(root, message_scope, types_file, value)?;
-            return Ok(Type::Record(Box::new(key_type), Box::new(value_type)));
+            let value_type =
+                import_encoding_input_type(root, options, message_scope, types_file, value)?;
+            Ok(Type::Record(Box::new(key_type), Box::new(value_type)))
         }
+        t if is_bigint(options, t) => Ok(Type::BigInt),
         package::Type::Bool => Ok(Type::Boolean),
         package::Type::Bytes => Ok(Type::reference(ast::Identifier::new("Uint8Array").into())),
         package::Type::Double => Ok(Type::Number),
@@ -160,15 +264,15 @@ fn import_encoding_input_type(
         | package::Type::Sfixed64
         | package::Type::Sint64
         | package::Type::Uint64 => {
-            let util_id: Rc<ast::Identifier> = Rc::new("util".into());
+            let util_id: Arc<ast::Identifier> = Arc::new("util".into());
             let util_import = ast::ImportDeclaration::import(
-                vec![ast::ImportSpecifier::new(Rc::clone(&util_id))],
-                PROTOBUF_MODULE.into(),
+                vec![ast::ImportSpecifier::new(Arc::clone(&util_id))],
+                runtime_module(root, options, message_scope.id().unwrap(), &types_file.name).into(),
             );
             ensure_import(types_file, util_import);
             Ok(Type::TypeReference(vec![
-                Rc::clone(&util_id),
-                Rc::new(ast::Identifier::new("Long")),
+                Arc::clone(&util_id),
+                Arc::new(ast::Identifier::new("Long")),
             ])
             .or(&Type::Number))
         }
@@ -179,7 +283,17 @@ fn import_encoding_input_type(
     }
 }
 
-fn resolve_key_type(key: &Rc<package::Type>) -> Type {
+/// `T[]`, or `ReadonlyArray<T>` with readonly types. Encoding accepts readonly arrays as well,
+/// so readonly decoded messages can be encoded again.
+fn repeated_type(options: &Options, element_type: Type) -> Type {
+    if options.readonly_types {
+        Type::generic("ReadonlyArray", vec![element_type])
+    } else {
+        Type::array(element_type)
+    }
+}
+
+fn resolve_key_type(key: &Arc<package::Type>) -> Type {
     match key.deref() {
         package::Type::Message(_) => unreachable!(),
         package::Type::Repeated(_) => unreachable!(),
@@ -191,17 +305,31 @@ fn resolve_key_type(key: &Rc<package::Type>) -> Type {
 
 fn import_decode_result_type(
     root: &RootScope,
+    options: &Options,
     message_scope: &ProtoScope,
     types_file: &mut ast::File,
     field_type: &package::Type,
 ) -> Result<Type, ProtoError> {
+    if let Some(mapped) = well_known_field_type(root, options, field_type, TypeUsage::Decode) {
+        return Ok(mapped);
+    }
     match field_type {
-        package::Type::Enum(e_id) => import_enum_type(root, message_scope, types_file, *e_id),
+        package::Type::Enum(e_id) => {
+            import_enum_type(root, options, message_scope, types_file, *e_id)
+        }
         package::Type::Message(m_id) => {
             let message_id = *m_id;
-            let imported_name = root.get_declaration_name(message_id).unwrap();
-            import_message_type(root, message_scope, types_file, message_id, imported_name)
+            let imported_name = declaration_name(root, options, message_id);
+            import_message_type(
+                root,
+                options,
+                message_scope,
+                types_file,
+                message_id,
+                imported_name,
+            )
         }
+        t if is_bigint(options, t) => Ok(Type::BigInt),
         package::Type::Bool => Ok(Type::Boolean),
         package::Type::Bytes => Ok(Type::reference(ast::Identifier::new("Uint8Array").into())),
         package::Type::Double => Ok(Type::Number),
@@ -213,15 +341,15 @@ fn import_decode_result_type(
         | package::Type::Sfixed64
         | package::Type::Sint64
         | package::Type::Uint64 => {
-            let util_id: Rc<ast::Identifier> = Rc::new("util".into());
+            let util_id: Arc<ast::Identifier> = Arc::new("util".into());
             let util_import = ast::ImportDeclaration::import(
-                vec![ast::ImportSpecifier::new(Rc::clone(&util_id))],
-                PROTOBUF_MODULE.into(),
+                vec![ast::ImportSpecifier::new(Arc::clone(&util_id))],
+                runtime_module(root, options, message_scope.id().unwrap(), &types_file.name).into(),
             );
             ensure_import(types_file, util_import);
             Ok(Type::TypeReference(vec![
-                Rc::clone(&util_id),
-                Rc::new(ast::Identifier::new("Long")),
+                Arc::clone(&util_id),
+                Arc::new(ast::Identifier::new("Long")),
             ]))
         }
         package::Type::Sfixed32 => Ok(Type::Number),
@@ -231,81 +359,186 @@ fn import_decode_result_type(
 
         package::Type::Repeated(field_type) => {
             let element_type =
-                import_decode_result_type(root, message_scope, types_file, field_type)?;
-            return Ok(Type::array(element_type));
+                import_decode_result_type(root, options, message_scope, types_file, field_type)?;
+            Ok(repeated_type(options, element_type))
         }
         package::Type::Map(key, value) => {
             let key_type = resolve_key_type(key);
-            let value_type = import_decode_result_type(root, message_scope, types_file, value)?;
-            return Ok(Type::Record(Box::new(key_type), Box::new(value_type)));
+            let value_type =
+                import_decode_result_type(root, options, message_scope, types_file, value)?;
+            let record_type = Type::Record(Box::new(key_type), Box::new(value_type));
+            if options.readonly_types {
+                Ok(Type::generic("Readonly", vec![record_type]))
+            } else {
+                Ok(record_type)
+            }
         }
     }
 }
 
 fn import_enum_type(
     root: &RootScope,
+    options: &Options,
     message_scope: &ProtoScope,
     types_file: &mut ast::File,
     enum_declaration_id: usize,
 ) -> Result<Type, ProtoError> {
-    let enum_name = root.get_declaration_name(enum_declaration_id).unwrap();
-    let enum_ts_path = {
-        let enum_proto_path = root.get_declaration_path(enum_declaration_id).unwrap();
-        let mut res = TsPath::from(enum_proto_path);
-        res.push(TsPathComponent::Enum(Rc::clone(&enum_name)));
-        res
-    };
-    let types_file_path = {
-        let message_id = message_scope.id().unwrap();
-        let declaration_proto_path = root.get_declaration_path(message_id).unwrap();
-        let mut res = TsPath::from(declaration_proto_path);
-        res.push(TsPathComponent::File("types".into()));
-        res
-    };
+    let enum_id = import_enum(
+        root,
+        options,
+        message_scope,
+        types_file,
+        enum_declaration_id,
+    );
+    Ok(Type::reference(enum_id))
+}
 
-    match get_relative_import(&types_file_path, &enum_ts_path) {
-        Some(import_declaration) => {
-            ensure_import(types_file, import_declaration);
-        }
-        _ => {}
+/// Type written in place of a field of a well-known message, see `map_well_known_type`.
+fn well_known_field_type(
+    root: &RootScope,
+    options: &Options,
+    field_type: &package::Type,
+    usage: TypeUsage,
+) -> Option<Type> {
+    let package::Type::Message(message_id) = field_type else {
+        return None;
+    };
+    if options.well_known_types != WellKnownTypes::Idiomatic {
+        return None;
     }
-
-    return Ok(Type::reference(Rc::new(enum_name.into())));
+    map_well_known_type(&root.get_full_name(*message_id)?, usage)
 }
 
 fn import_message_type(
     root: &RootScope,
+    options: &Options,
     message_scope: &ProtoScope,
     types_file: &mut ast::File,
     imported_message_id: usize,
-    imported_name: Rc<str>,
+    imported_name: Arc<str>,
 ) -> Result<Type, ProtoError> {
-    let requested_ts_path = {
-        let mut res = TsPath::from(root.get_declaration_path(imported_message_id).unwrap());
-        res.push(TsPathComponent::File("types".into()));
-        res.push(TsPathComponent::Interface(Rc::clone(&imported_name)));
-        res
-    };
-    let current_file_path = {
-        let current_message_path = root
-            .get_declaration_path(message_scope.id().unwrap())
-            .unwrap();
-        let mut res = TsPath::from(current_message_path);
-        res.push(TsPathComponent::File("types".into()));
-        res
+    let requested_file_path =
+        declaration_file_path(root, options, imported_message_id, &types_file.name);
+    let message_id = message_scope.id().unwrap();
+    let current_file_path = declaration_file_path(root, options, message_id, &types_file.name);
+    let imported_id = if root.is_cyclic_reference(message_id, imported_message_id) {
+        import_type_declaration(
+            types_file,
+            &current_file_path,
+            &requested_file_path,
+            TsPathComponent::Interface(imported_name),
+        )
+    } else {
+        import_declaration(
+            types_file,
+            &current_file_path,
+            &requested_file_path,
+            TsPathComponent::Interface(imported_name),
+            None,
+        )
     };
 
-    match get_relative_import(&current_file_path, &requested_ts_path) {
-        Some(import_declaration) => {
-            ensure_import(types_file, import_declaration);
+    Ok(Type::reference(imported_id))
+}
+
+#[cfg(test)]
+mod test_deprecated {
+    use super::super::test_utils::{compile_sources, render_file_at};
+    use super::*;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "shop/order.proto",
+        r#"
+        syntax = "proto3";
+        package shop;
+        message Order {
+            option deprecated = true;
+            string id = 1;
+            string note = 2 [deprecated = true];
+            oneof payment {
+                string card = 3 [deprecated = true];
+            }
         }
-        _ => {}
+        "#,
+    )];
+
+    #[test]
+    fn it_tags_deprecated_messages_and_fields() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let types = render_file_at(&folder, &["shop", "order", "Order", "types"]);
+        assert!(types.contains(
+            "/** @deprecated */\nexport interface OrderEncodeInput {\n  id?: string | null\n  /** @deprecated */\n  note?: string | null\n  /** @deprecated */\n  card?: string | null\n}\n"
+        ));
+        assert!(types.contains(
+            "/** @deprecated */\nexport interface Order {\n  id: string\n  /** @deprecated */\n  note: string\n"
+        ));
     }
+}
+
+#[cfg(test)]
+mod test_readonly_types {
+    use super::super::test_utils::{compile_sources, render_file_at};
+    use super::*;
 
-    return Ok(Type::reference(
-        ast::Identifier {
-            text: imported_name,
+    const SOURCES: &[(&str, &str)] = &[(
+        "shop/order.proto",
+        r#"
+        syntax = "proto3";
+        package shop;
+        message Line {
+            string sku = 1;
         }
-        .into(),
-    ));
+        message Order {
+            string id = 1;
+            repeated Line lines = 2;
+            repeated string tags = 3;
+            map<string, Line> lines_by_sku = 4;
+            optional int32 discount = 5;
+            oneof payment {
+                string card = 6;
+            }
+        }
+        "#,
+    )];
+
+    #[test]
+    fn it_declares_readonly_decoded_messages() {
+        let options = Options {
+            readonly_types: true,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        assert_eq!(
+            render_file_at(&folder, &["shop", "order", "Order", "types"]),
+            [
+                "import { Line, LineEncodeInput } from \"../Line/types\"",
+                "",
+                "export interface OrderEncodeInput {",
+                "  id?: string | null",
+                "  lines?: ReadonlyArray<LineEncodeInput> | null",
+                "  tags?: ReadonlyArray<string> | null",
+                "  lines_by_sku?: Record<string, LineEncodeInput> | null",
+                "  discount?: number",
+                "  card?: string | null",
+                "}",
+                "",
+                "export interface Order {",
+                "  readonly id: string",
+                "  readonly lines: ReadonlyArray<Line>",
+                "  readonly tags: ReadonlyArray<string>",
+                "  readonly lines_by_sku: Readonly<Record<string, Line>>",
+                "  readonly discount?: number",
+                "  readonly card?: string | null",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+
+        let folder = compile_sources(SOURCES, &Options::default());
+        let types = render_file_at(&folder, &["shop", "order", "Order", "types"]);
+        assert!(types.contains("  lines: Line[]\n"));
+        assert!(!types.contains("readonly"));
+        assert!(!types.contains("Readonly"));
+    }
 }
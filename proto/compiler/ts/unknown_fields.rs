@@ -0,0 +1,300 @@
+use std::sync::Arc;
+
+use crate::proto::{
+    error::ProtoError,
+    proto_scope::{root_scope::RootScope, ProtoScope},
+};
+
+use super::{
+    ast::{self, ElementAccess, ExpressionChain, MethodCall, Prop, StatementList, Type},
+    constants::UNKNOWN_FIELDS_PROPERTY,
+    options::{Options, Runtime},
+    renames::property_name,
+};
+
+const WRITE_RAW_FUNCTION_NAME: &str = "writeRaw";
+
+/// `[number, Uint8Array]`, the number of an unknown field and its bytes as they were read, the tag included.
+pub(super) fn unknown_field_type() -> Type {
+    Type::TupleType(vec![Type::Number, Type::from_id("Uint8Array")])
+}
+
+/// Statements of the `default` clause of `decode` after the unknown field is skipped,
+/// keeping its bytes from `start` to the position of the reader in `message.$unknown`.
+pub(super) fn keep_unknown_field(
+    reader: &Arc<ast::Expression>,
+    message: &Arc<ast::Expression>,
+    tag: &Arc<ast::Expression>,
+    start: &Arc<ast::Expression>,
+) -> Vec<ast::Statement> {
+    let unknown: Arc<ast::Expression> = message.prop(UNKNOWN_FIELDS_PROPERTY).into();
+    let field_bytes = reader
+        .prop("buf")
+        .into_prop("slice")
+        .into_call(vec![Arc::clone(start), reader.prop("pos").into()]);
+    let field = ast::Expression::array(vec![
+        ast::BinaryOperator::UnsignedRightShift
+            .apply(Arc::clone(tag), Arc::new(3.into()))
+            .into(),
+        field_bytes.into(),
+    ]);
+    vec![
+        ast::IfStatement {
+            expression: unknown.not().into(),
+            then_statement: Arc::new(
+                ast::BinaryOperator::Assign
+                    .apply(Arc::clone(&unknown), ast::Expression::array(vec![]).into())
+                    .into(),
+            ),
+            else_statement: None,
+        }
+        .into(),
+        unknown.method_call("push", vec![field.into()]).into(),
+    ]
+}
+
+/// Writes the fields of `message.$unknown` back as they were read, after the known fields.
+///
+/// protobufjs has no public method writing bytes without their length,
+/// so `writeRaw` is pushed to its list of operations the way its own methods do.
+pub(super) fn write_unknown_fields(
+    options: &Options,
+    file: &mut ast::File,
+    message: &Arc<ast::Expression>,
+    writer: &Arc<ast::Expression>,
+) -> ast::Statement {
+    let unknown: Arc<ast::Expression> = message.prop(UNKNOWN_FIELDS_PROPERTY).into();
+    let i_id: Arc<ast::Identifier> = Arc::new("i".into());
+    let i_expr: Arc<ast::Expression> = Arc::new(Arc::clone(&i_id).into());
+    let field_bytes: Arc<ast::Expression> = unknown
+        .element(i_expr)
+        .into_element(Arc::new(1.into()))
+        .into();
+    let write = match options.runtime {
+        Runtime::Standalone => writer.method_call("raw", vec![field_bytes]),
+        Runtime::Protobufjs => {
+            file.push_statement(write_raw_function().into());
+            let any_writer = ast::Expression::AsExpression(Arc::clone(writer), Arc::new(Type::Any));
+            Arc::new(any_writer.into_parentheses()).method_call(
+                "_push",
+                vec![
+                    Arc::new(ast::Expression::from(WRITE_RAW_FUNCTION_NAME)),
+                    field_bytes.prop("length").into(),
+                    field_bytes,
+                ],
+            )
+        }
+    };
+    let mut for_stmt = ast::ForStatement::for_each(i_id, Arc::clone(&unknown));
+    for_stmt.push_statement(write.into());
+    ast::IfStatement {
+        expression: ast::BinaryOperator::WeakNotEqual
+            .apply(unknown, ast::Expression::Null.into())
+            .into(),
+        then_statement: Arc::new(for_stmt.into()),
+        else_statement: None,
+    }
+    .into()
+}
+
+/// `function writeRaw(value: Uint8Array, buf: Uint8Array, pos: number): void`,
+/// copying the bytes into the buffer of a protobufjs `Writer` when it is finished.
+fn write_raw_function() -> ast::FunctionDeclaration {
+    let value_id: Arc<ast::Identifier> = Arc::new("value".into());
+    let buf_id: Arc<ast::Identifier> = Arc::new("buf".into());
+    let pos_id: Arc<ast::Identifier> = Arc::new("pos".into());
+
+    let mut func = ast::FunctionDeclaration::new(WRITE_RAW_FUNCTION_NAME);
+    func.add_param(ast::Parameter::new(&value_id, Type::from_id("Uint8Array")));
+    func.add_param(ast::Parameter::new(&buf_id, Type::from_id("Uint8Array")));
+    func.add_param(ast::Parameter::new(&pos_id, Type::Number));
+    func.returns(Type::Void);
+    func.push_statement(
+        ast::Expression::from(buf_id)
+            .into_prop("set")
+            .into_call(vec![Arc::new(value_id.into()), Arc::new(pos_id.into())])
+            .into(),
+    );
+    func
+}
+
+/// Checks that no field of a message is named as the property unknown fields are kept in,
+/// by its `json_name` option or a rename.
+pub(super) fn validate_unknown_fields_property(
+    root: &RootScope,
+    options: &Options,
+) -> Result<(), ProtoError> {
+    if !options.keep_unknown_fields {
+        return Ok(());
+    }
+    let mut decl_ids = root.types.keys().copied().collect::<Vec<_>>();
+    decl_ids.sort_unstable();
+    let mut errors = Vec::new();
+    for decl_id in decl_ids {
+        let scope = root.get_declaration(decl_id).unwrap();
+        let message = match &*scope {
+            ProtoScope::Message(m) => m,
+            _ => continue,
+        };
+        for field in message.get_fields() {
+            if &*property_name(root, options, decl_id, field) == UNKNOWN_FIELDS_PROPERTY {
+                errors.push(ProtoError::UnknownFieldsCollision {
+                    message: root.get_full_name(decl_id).unwrap().to_string(),
+                    field: field.name.to_string(),
+                    property: UNKNOWN_FIELDS_PROPERTY.to_string(),
+                });
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProtoError::from_errors(errors))
+    }
+}
+
+#[cfg(test)]
+mod test_unknown_fields {
+    use super::super::{
+        options::Layout,
+        renames::Rename,
+        scope_to_folder::root_scope_to_folder,
+        test_utils::{compile_sources, render_file_at, run_node_script},
+    };
+    use super::*;
+    use crate::proto::package::read_root_scope_from_sources;
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "rt/messages.proto",
+        r#"
+        syntax = "proto3";
+        package rt;
+        message Base {
+            int32 id = 1;
+            string name = 2;
+        }
+        message Extended {
+            int32 id = 1;
+            string name = 2;
+            sint64 delta = 3;
+            repeated string tags = 4;
+            fixed32 flags = 5;
+            double score = 6;
+            Base child = 7;
+        }
+        "#,
+    )];
+
+    fn keep_options(runtime: Runtime) -> Options {
+        Options {
+            keep_unknown_fields: true,
+            runtime,
+            ..Options::default()
+        }
+    }
+
+    fn render(options: &Options, file_name: &str) -> String {
+        let folder = compile_sources(SOURCES, options);
+        render_file_at(&folder, &["rt", "messages", "Base", file_name])
+    }
+
+    #[test]
+    fn it_keeps_unknown_fields_in_the_decoded_messages() {
+        let options = keep_options(Runtime::Protobufjs);
+        let types = render(&options, "types");
+        assert!(types.contains("  $unknown?: ([number, Uint8Array][]) | null\n"));
+        assert!(types.contains("  $unknown?: [number, Uint8Array][]\n"));
+
+        let decode = render(&options, "decode");
+        assert!(decode.contains("    const start = r.pos\n    const tag = r.uint32()\n"));
+        assert!(decode.contains(
+            &[
+                "      default:",
+                "        r.skipType(tag & 7)",
+                "        if (!message.$unknown)",
+                "          message.$unknown = []",
+                "        message.$unknown.push([tag >>> 3, r.buf.slice(start, r.pos)])",
+                "        break;",
+            ]
+            .join("\n")
+        ));
+
+        let encode = render(&options, "encode");
+        assert!(encode.contains(
+            &[
+                "  if (message.$unknown != null)",
+                "    for (let i = 0; i < message.$unknown.length; ++i)",
+                "      (w as any)._push(writeRaw, message.$unknown[i][1].length, message.$unknown[i][1])",
+                "  return w",
+            ]
+            .join("\n")
+        ));
+        assert!(encode.contains(
+            "function writeRaw(value: Uint8Array, buf: Uint8Array, pos: number): void {\n  buf.set(value, pos)\n}\n"
+        ));
+
+        let encode = render(&keep_options(Runtime::Standalone), "encode");
+        assert!(encode.contains("      w.raw(message.$unknown[i][1])\n"));
+        assert!(!encode.contains("writeRaw"));
+    }
+
+    #[test]
+    fn it_skips_unknown_fields_by_default() {
+        let options = Options::default();
+        assert!(!render(&options, "types").contains("$unknown"));
+        let decode = render(&options, "decode");
+        assert!(!decode.contains("$unknown"));
+        assert!(!decode.contains("const start"));
+        assert!(!render(&options, "encode").contains("$unknown"));
+    }
+
+    #[test]
+    fn it_reports_fields_renamed_to_the_unknown_fields_property() {
+        let mut renames = std::collections::HashMap::new();
+        renames.insert(
+            "rt.Base.name".to_string(),
+            Rename::from(UNKNOWN_FIELDS_PROPERTY),
+        );
+        let options = Options {
+            renames: Arc::new(renames),
+            ..keep_options(Runtime::Protobufjs)
+        };
+        let root = read_root_scope_from_sources(SOURCES).unwrap();
+        let error = root_scope_to_folder(&root, &options, "out".into()).unwrap_err();
+        assert_eq!(error.code(), "unknown_fields_collision");
+        assert_eq!(
+            error.to_string(),
+            "Field \"name\" of rt.Base is named \"$unknown\", the property unknown fields are kept in, rename the field"
+        );
+
+        let options = Options {
+            keep_unknown_fields: false,
+            ..options
+        };
+        assert!(root_scope_to_folder(&root, &options, "out".into()).is_ok());
+    }
+
+    #[test]
+    fn it_encodes_the_bytes_it_decoded() {
+        let options = Options {
+            layout: Layout::File,
+            emit_json: false,
+            ..keep_options(Runtime::Standalone)
+        };
+        let folder = compile_sources(SOURCES, &options);
+        run_node_script(
+            "the round trip of unknown fields",
+            concat!(
+                include_str!("load_runtime.js"),
+                include_str!("unknown_fields_test.js")
+            ),
+            &[
+                ("PROTOS_TS_RUNTIME", render_file_at(&folder, &["runtime"])),
+                (
+                    "PROTOS_TS_MODULE",
+                    render_file_at(&folder, &["rt", "messages"]),
+                ),
+            ],
+        );
+    }
+}
@@ -0,0 +1,72 @@
+// Checks that the fields a message does not know survive decoding and encoding it again.
+// PROTOS_TS_RUNTIME is the source of runtime.ts and PROTOS_TS_MODULE is the generated
+// single-file module of `rt.Base` and `rt.Extended`, compiled with `--keep-unknown-fields`.
+// `Extended` is `Base` with more fields, as a newer version of the schema would be.
+const assert = require("assert")
+
+function loadModule(source, runtime) {
+  const code = source
+    .replace(/^import .*\n/gm, "")
+    .replace(/^export interface \w+ \{\n[^}]*\}\n/gm, "")
+    .replace(/^export /gm, "")
+    .replace(/^function (\w+)\((.*)\): .* \{$/gm, (_, name, params) => {
+      const names = params.split(", ").map((param) => param.replace(/\??: .*/, ""))
+      return `function ${name}(${names.join(", ")}) {`
+    })
+    .replace(/: any\b/g, "")
+  return new Function(
+    "Writer",
+    "Reader",
+    "util",
+    code +
+      "\nreturn { encodeBase, decodeBase, encodeDelimitedBase, encodeExtended, decodeExtended, encodeDelimitedExtended }",
+  )(runtime.Writer, runtime.Reader, {})
+}
+
+const runtime = loadRuntime(process.env.PROTOS_TS_RUNTIME)
+const { encodeBase, decodeBase, encodeDelimitedBase, encodeExtended, decodeExtended, encodeDelimitedExtended } =
+  loadModule(process.env.PROTOS_TS_MODULE, runtime)
+
+const extended = {
+  id: 150,
+  name: "a",
+  delta: BigInt(-2),
+  tags: ["x", "y"],
+  flags: 7,
+  score: 1.5,
+  child: { id: 1, name: "c" },
+}
+const encoded = hex(encodeExtended(extended).finish())
+
+// The fields of `Base` are read, the others are kept with their tags.
+const base = decodeBase(bytes(encoded))
+assert.strictEqual(base.id, 150)
+assert.strictEqual(base.name, "a")
+assert.deepStrictEqual(
+  base.$unknown.map(([field, value]) => [field, hex(value)]),
+  [
+    [3, "1803"],
+    [4, "220178"],
+    [4, "220179"],
+    [5, "2d07000000"],
+    [6, "31000000000000f83f"],
+    [7, "3a050801120163"],
+  ],
+)
+
+// Writing the message again gives the bytes it was read from.
+assert.strictEqual(hex(encodeBase(base).finish()), encoded)
+assert.strictEqual(hex(encodeDelimitedBase(base).finish()), hex(encodeDelimitedExtended(extended).finish()))
+const decoded = decodeExtended(bytes(hex(encodeBase(base).finish())))
+assert.deepStrictEqual(decoded.tags, ["x", "y"])
+assert.strictEqual(decoded.delta, BigInt(-2))
+assert.deepStrictEqual(decoded.child, { id: 1, name: "c" })
+assert.strictEqual(decoded.$unknown, undefined)
+
+// Unknown fields of nested messages are kept by the nested messages.
+const nested = decodeExtended(encodeExtended({ child: base }).finish())
+assert.strictEqual(hex(encodeBase(nested.child).finish()), encoded)
+assert.strictEqual(nested.$unknown, undefined)
+
+// Messages without unknown fields have no `$unknown` property.
+assert.strictEqual(decodeBase(encodeBase({ id: 1 }).finish()).$unknown, undefined)
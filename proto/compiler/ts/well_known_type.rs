@@ -0,0 +1,178 @@
+use super::ast::Type;
+
+/// Whether a type is the one `encode` accepts or the one `decode` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TypeUsage {
+    Encode,
+    Decode,
+}
+
+/// TypeScript type written in place of the well-known message at `proto_path`,
+/// the one `wkt.ts` reads and writes it as. `None` for other messages.
+pub(super) fn map_well_known_type(proto_path: &str, usage: TypeUsage) -> Option<Type> {
+    let name = proto_path.strip_prefix("google.protobuf.")?;
+    let mapped = match (name, usage) {
+        ("Timestamp", TypeUsage::Decode) => Type::from_id("Date"),
+        ("Timestamp", TypeUsage::Encode) => Type::from_id("Date").or(&seconds_and_nanos()),
+        ("Duration", TypeUsage::Decode) => seconds_and_nanos(),
+        // a number of seconds with the fraction in nanoseconds is encoded as well
+        ("Duration", TypeUsage::Encode) => seconds_and_nanos().or(&Type::Number),
+        ("Any", _) => Type::Object(vec![
+            ("typeUrl".into(), Type::String),
+            ("value".into(), Type::from_id("Uint8Array")),
+        ]),
+        ("Struct", _) => Type::IndexSignature(Box::new(Type::Unknown)),
+        ("Value", _) => Type::Unknown,
+        ("ListValue", _) => Type::array(Type::Unknown),
+        ("FieldMask", _) => Type::Object(vec![("paths".into(), Type::array(Type::String))]),
+        (
+            "DoubleValue" | "FloatValue" | "Int64Value" | "UInt64Value" | "Int32Value"
+            | "UInt32Value",
+            _,
+        ) => nullable(Type::Number),
+        ("BoolValue", _) => nullable(Type::Boolean),
+        ("StringValue", _) => nullable(Type::String),
+        ("BytesValue", _) => nullable(Type::from_id("Uint8Array")),
+        _ => return None,
+    };
+    Some(mapped)
+}
+
+/// `{ seconds: number; nanos: number }`
+fn seconds_and_nanos() -> Type {
+    Type::Object(vec![
+        ("seconds".into(), Type::Number),
+        ("nanos".into(), Type::Number),
+    ])
+}
+
+/// `T | null`, the value of a wrapper message, `null` when the wrapper is missing.
+fn nullable(value_type: Type) -> Type {
+    value_type.or(&Type::Null)
+}
+
+#[cfg(test)]
+mod test_well_known_type {
+    use super::super::{
+        options::Options,
+        test_utils::{compile_sources, render_file_at},
+    };
+    use super::*;
+
+    #[test]
+    fn it_maps_timestamps_to_dates() {
+        let decoded = map_well_known_type("google.protobuf.Timestamp", TypeUsage::Decode);
+        assert_eq!(decoded.as_ref().map(String::from).as_deref(), Some("Date"));
+        let encoded = map_well_known_type("google.protobuf.Timestamp", TypeUsage::Encode);
+        assert_eq!(
+            encoded.as_ref().map(String::from).as_deref(),
+            Some("Date | { seconds: number; nanos: number }")
+        );
+        assert!(map_well_known_type("app.Timestamp", TypeUsage::Decode).is_none());
+    }
+
+    #[test]
+    fn it_types_timestamp_fields() {
+        let sources = &[(
+            "app/event.proto",
+            r#"
+            syntax = "proto3";
+            package app;
+            import "google/protobuf/timestamp.proto";
+            message Event {
+                google.protobuf.Timestamp at = 1;
+                repeated google.protobuf.Timestamp history = 2;
+            }
+            "#,
+        )];
+        let folder = compile_sources(sources, &Options::default());
+        let types = render_file_at(&folder, &["app", "event", "Event", "types"]);
+        assert!(types.contains("  at?: Date | { seconds: number; nanos: number } | null\n"));
+        assert!(types
+            .contains("  history?: (Array<Date | { seconds: number; nanos: number }>) | null\n"));
+        assert!(types.contains("  at: Date | undefined\n"));
+        assert!(types.contains("  history: Date[]\n"));
+        assert!(!types.contains("wkt"));
+    }
+
+    #[test]
+    fn it_types_every_well_known_type() {
+        let sources = &[(
+            "app/all.proto",
+            r#"
+            syntax = "proto3";
+            package app;
+            import "google/protobuf/any.proto";
+            import "google/protobuf/duration.proto";
+            import "google/protobuf/field_mask.proto";
+            import "google/protobuf/struct.proto";
+            import "google/protobuf/timestamp.proto";
+            import "google/protobuf/wrappers.proto";
+            message All {
+                google.protobuf.Timestamp timestamp = 1;
+                google.protobuf.Duration duration = 2;
+                google.protobuf.Any any = 3;
+                google.protobuf.Struct struct = 4;
+                google.protobuf.Value value = 5;
+                google.protobuf.ListValue list = 6;
+                google.protobuf.FieldMask mask = 7;
+                google.protobuf.DoubleValue double = 8;
+                google.protobuf.FloatValue float = 9;
+                google.protobuf.Int64Value int64 = 10;
+                google.protobuf.UInt64Value uint64 = 11;
+                google.protobuf.Int32Value int32 = 12;
+                google.protobuf.UInt32Value uint32 = 13;
+                google.protobuf.BoolValue bool = 14;
+                google.protobuf.StringValue string = 15;
+                google.protobuf.BytesValue bytes = 16;
+            }
+            "#,
+        )];
+        let folder = compile_sources(sources, &Options::default());
+        let types = render_file_at(&folder, &["app", "all", "All", "types"]);
+        assert_eq!(
+            types,
+            [
+                "export interface AllEncodeInput {",
+                "  timestamp?: Date | { seconds: number; nanos: number } | null",
+                "  duration?: { seconds: number; nanos: number } | number | null",
+                "  any?: { typeUrl: string; value: Uint8Array } | null",
+                "  struct?: { [key: string]: unknown } | null",
+                "  value?: unknown | null",
+                "  list?: (unknown[]) | null",
+                "  mask?: { paths: string[] } | null",
+                "  double?: number | null",
+                "  float?: number | null",
+                "  int64?: number | null",
+                "  uint64?: number | null",
+                "  int32?: number | null",
+                "  uint32?: number | null",
+                "  bool?: boolean | null",
+                "  string?: string | null",
+                "  bytes?: Uint8Array | null",
+                "}",
+                "",
+                "export interface All {",
+                "  timestamp: Date | undefined",
+                "  duration: { seconds: number; nanos: number } | undefined",
+                "  any: { typeUrl: string; value: Uint8Array } | undefined",
+                "  struct: { [key: string]: unknown } | undefined",
+                "  value: unknown",
+                "  list: (unknown[]) | undefined",
+                "  mask: { paths: string[] } | undefined",
+                "  double: number | null | undefined",
+                "  float: number | null | undefined",
+                "  int64: number | null | undefined",
+                "  uint64: number | null | undefined",
+                "  int32: number | null | undefined",
+                "  uint32: number | null | undefined",
+                "  bool: boolean | null | undefined",
+                "  string: string | null | undefined",
+                "  bytes: Uint8Array | null | undefined",
+                "}",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use std::{ops::Deref, sync::Arc};
+
+use crate::proto::proto_scope::{root_scope::RootScope, traits::ChildrenScopes, ProtoScope};
+
+use super::{
+    ast::{self, StatementList},
+    constants::WKT_FILE_NAME,
+    ensure_import::ensure_import,
+    layout::{declaration_file_path, import_declaration},
+    options::{Options, WellKnownTypes},
+    runtime::runtime_module_at,
+    ts_path::{TsPath, TsPathComponent},
+};
+
+const WKT_SOURCE: &str = include_str!("wkt.ts");
+
+/// Messages of `google.protobuf` that `wkt.ts` provides plain TypeScript values for.
+const MAPPED_MESSAGES: &[&str] = &[
+    "Timestamp",
+    "Duration",
+    "DoubleValue",
+    "FloatValue",
+    "Int64Value",
+    "UInt64Value",
+    "Int32Value",
+    "UInt32Value",
+    "BoolValue",
+    "StringValue",
+    "BytesValue",
+    "Struct",
+    "Value",
+    "ListValue",
+    "Any",
+    "FieldMask",
+];
+
+/// Name of the well-known message `message_id` when it is mapped by `wkt.ts`,
+/// `None` for other messages and with `WellKnownTypes::Raw`.
+pub(super) fn mapped_well_known_type(
+    root: &RootScope,
+    options: &Options,
+    message_id: usize,
+) -> Option<&'static str> {
+    if options.well_known_types != WellKnownTypes::Idiomatic {
+        return None;
+    }
+    let full_name = root.get_full_name(message_id)?;
+    let name = full_name.strip_prefix("google.protobuf.")?;
+    MAPPED_MESSAGES
+        .iter()
+        .copied()
+        .find(|mapped| *mapped == name)
+}
+
+/// Whether every declaration of the proto file is a message mapped by `wkt.ts`,
+/// such files (like `google/protobuf/timestamp.proto`) are not generated.
+pub(super) fn is_mapped_well_known_file(
+    root: &RootScope,
+    options: &Options,
+    file_scope: &ProtoScope,
+) -> bool {
+    let declarations = file_scope.children();
+    !declarations.is_empty()
+        && declarations.iter().all(|declaration| {
+            matches!(declaration.deref(), ProtoScope::Message(m)
+                if mapped_well_known_type(root, options, m.id).is_some())
+        })
+}
+
+/// Imports `declaration` of `wkt.ts` into the `file_name` part of the declaration `current_id`.
+///
+/// Every export of `wkt.ts` is named the way the file layout names
+/// the parts of a message: `Timestamp`, `TimestampEncodeInput`, `encodeTimestamp`, `toJSONTimestamp`.
+pub(super) fn import_well_known(
+    root: &RootScope,
+    options: &Options,
+    file: &mut ast::File,
+    current_id: usize,
+    file_name: &str,
+    declaration: TsPathComponent,
+    alias: Option<Arc<str>>,
+) -> Arc<ast::Identifier> {
+    let current_file = declaration_file_path(root, options, current_id, file_name);
+    import_declaration(file, &current_file, &wkt_file_path(), declaration, alias)
+}
+
+pub(super) fn wkt_file_path() -> TsPath {
+    let mut path = TsPath::default();
+    path.push_file(WKT_FILE_NAME);
+    path
+}
+
+/// Whether `wkt.ts` is imported by any of the generated files.
+pub(super) fn uses_well_known_types(root: &RootScope, options: &Options) -> bool {
+    root.types
+        .keys()
+        .any(|&id| mapped_well_known_type(root, options, id).is_some())
+}
+
+/// Emits `wkt.ts` with the mapped well-known types,
+/// it reads and writes them with the same runtime as the rest of the generated code.
+pub(super) fn compile_wkt_module(options: &Options) -> ast::File {
+    let mut file = ast::File::new(WKT_FILE_NAME.into());
+    ensure_import(
+        &mut file,
+        ast::ImportDeclaration::import(
+            ["Reader", "Writer", "util"]
+                .into_iter()
+                .map(|name| ast::ImportSpecifier::new(Arc::new(ast::Identifier::new(name))))
+                .collect(),
+            runtime_module_at(options, &wkt_file_path()).into(),
+        ),
+    );
+    file.push_statement(ast::Statement::Verbatim(WKT_SOURCE.into()));
+    file
+}
+
+#[cfg(test)]
+mod test_well_known_types {
+    use super::*;
+    use crate::proto::compiler::ts::{
+        options::{Layout, Runtime},
+        test_utils::{compile_sources, render_file_at},
+    };
+
+    const SOURCES: &[(&str, &str)] = &[(
+        "app/event.proto",
+        r#"
+        syntax = "proto3";
+        package app;
+        import "google/protobuf/timestamp.proto";
+        import "google/protobuf/wrappers.proto";
+        message Event {
+            google.protobuf.Timestamp at = 1;
+            google.protobuf.StringValue title = 2;
+        }
+        "#,
+    )];
+
+    #[test]
+    fn it_imports_mapped_types_from_wkt_module() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        // types are written in place, only the functions are imported
+        let types = render_file_at(&folder, &["app", "event", "Event", "types"]);
+        assert!(!types.contains("wkt"));
+        let encode = render_file_at(&folder, &["app", "event", "Event", "encode"]);
+        assert!(encode.contains(
+            "import { encodeStringValue as e10, encodeTimestamp as e2 } from \"../../../wkt\"\n"
+        ));
+        assert!(encode.contains("e2(message.at, w.uint32(10).fork()).ldelim()"));
+        let decode = render_file_at(&folder, &["app", "event", "Event", "decode"]);
+        assert!(decode.contains(
+            "import { decodeStringValue as d10, decodeTimestamp as d2 } from \"../../../wkt\"\n"
+        ));
+        let json = render_file_at(&folder, &["app", "event", "Event", "json"]);
+        assert!(json.contains("toJSONTimestamp as toJSON2"));
+        assert!(json.contains("fromJSONStringValue as fromJSON10"));
+
+        let wkt = render_file_at(&folder, &["wkt"]);
+        assert!(wkt.starts_with("import { Reader, Writer, util } from \"protobufjs/minimal\"\n"));
+        assert!(wkt.ends_with(WKT_SOURCE));
+    }
+
+    #[test]
+    fn it_types_timestamps_as_dates() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        let types = render_file_at(&folder, &["app", "event", "Event", "types"]);
+        assert!(types.contains("  at?: Date | { seconds: number; nanos: number } | null\n"));
+        assert!(types.contains("  at: Date | undefined\n"));
+        let wkt = render_file_at(&folder, &["wkt"]);
+        assert!(wkt.contains("export type Timestamp = Date\n"));
+        assert!(wkt.contains("export type TimestampEncodeInput = Date | SecondsAndNanos\n"));
+    }
+
+    #[test]
+    fn it_maps_every_well_known_type() {
+        let sources = &[(
+            "app/all.proto",
+            r#"
+            syntax = "proto3";
+            package app;
+            import "google/protobuf/any.proto";
+            import "google/protobuf/duration.proto";
+            import "google/protobuf/field_mask.proto";
+            import "google/protobuf/struct.proto";
+            import "google/protobuf/timestamp.proto";
+            import "google/protobuf/wrappers.proto";
+            message All {
+                google.protobuf.Timestamp timestamp = 1;
+                google.protobuf.Duration duration = 2;
+                google.protobuf.Any any = 3;
+                google.protobuf.Struct struct = 4;
+                google.protobuf.Value value = 5;
+                google.protobuf.ListValue list = 6;
+                google.protobuf.FieldMask mask = 7;
+                google.protobuf.DoubleValue double = 8;
+                google.protobuf.FloatValue float = 9;
+                google.protobuf.Int64Value int64 = 10;
+                google.protobuf.UInt64Value uint64 = 11;
+                google.protobuf.Int32Value int32 = 12;
+                google.protobuf.UInt32Value uint32 = 13;
+                google.protobuf.BoolValue bool = 14;
+                google.protobuf.StringValue string = 15;
+                google.protobuf.BytesValue bytes = 16;
+            }
+            "#,
+        )];
+        let folder = compile_sources(sources, &Options::default());
+        let types = render_file_at(&folder, &["app", "all", "All", "types"]);
+        let names = [
+            "Timestamp",
+            "Duration",
+            "Any",
+            "Struct",
+            "Value",
+            "ListValue",
+            "FieldMask",
+            "DoubleValue",
+            "FloatValue",
+            "Int64Value",
+            "UInt64Value",
+            "Int32Value",
+            "UInt32Value",
+            "BoolValue",
+            "StringValue",
+            "BytesValue",
+        ];
+        // the types are written in place, see `map_well_known_type`
+        assert!(!types.contains("google/protobuf"));
+        assert!(!types.contains("wkt"));
+
+        let wkt = render_file_at(&folder, &["wkt"]);
+        for declaration in [
+            "export type Timestamp = Date\n",
+            "export interface Duration {\n  seconds: number\n  nanos: number\n}\n",
+            "export interface Any {\n  typeUrl: string\n  value: Uint8Array\n}\n",
+            "export type Struct = { [key: string]: unknown }\n",
+            "export type Value = unknown\n",
+            "export type ListValue = unknown[]\n",
+            "export interface FieldMask {\n  paths: string[]\n}\n",
+            "export type Int64Value = number | null\n",
+            "export type BoolValue = boolean | null\n",
+            "export type BytesValue = Uint8Array | null\n",
+        ] {
+            assert!(wkt.contains(declaration), "{}", declaration);
+        }
+        for name in names {
+            for function in ["encode", "decode", "toJSON", "fromJSON", "equals"] {
+                let export = format!("{}{}", function, name);
+                assert!(wkt.contains(&export), "{}", export);
+            }
+        }
+    }
+
+    #[test]
+    fn it_imports_runtime_into_wkt_module() {
+        let options = Options {
+            layout: Layout::File,
+            runtime: Runtime::Standalone,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let event = render_file_at(&folder, &["app", "event"]);
+        assert!(event.contains("encodeTimestamp as e2"));
+        assert!(event.contains("from \"../wkt\"\n"));
+        let wkt = render_file_at(&folder, &["wkt"]);
+        assert!(wkt.starts_with("import { Reader, Writer, util } from \"./runtime\"\n"));
+    }
+
+    #[test]
+    fn it_uses_generated_messages_when_raw() {
+        let options = Options {
+            well_known_types: WellKnownTypes::Raw,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        let encode = render_file_at(&folder, &["app", "event", "Event", "encode"]);
+        assert!(encode.contains(
+            "import { encode as e2 } from \"../../../google/protobuf/timestamp/Timestamp/encode\"\n"
+        ));
+        assert!(folder.find_file(WKT_FILE_NAME).is_none());
+    }
+
+    #[test]
+    fn it_skips_generated_well_known_messages() {
+        let folder = compile_sources(SOURCES, &Options::default());
+        assert!(folder.find_folder("google").is_none());
+
+        let options = Options {
+            layout: Layout::File,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        assert!(folder.find_folder("google").is_none());
+
+        let options = Options {
+            well_known_types: WellKnownTypes::Raw,
+            ..Options::default()
+        };
+        let folder = compile_sources(SOURCES, &options);
+        assert!(folder
+            .find_folder_by_path(&["google", "protobuf", "timestamp", "Timestamp"])
+            .is_some());
+        assert!(folder
+            .find_folder_by_path(&["google", "protobuf", "wrappers"])
+            .is_some());
+    }
+
+    #[test]
+    fn it_keeps_unmapped_declarations_of_well_known_files() {
+        let sources = &[(
+            "app/doc.proto",
+            r#"
+            syntax = "proto3";
+            package app;
+            import "google/protobuf/struct.proto";
+            message Doc {
+                google.protobuf.Struct data = 1;
+                google.protobuf.NullValue nothing = 2;
+            }
+            "#,
+        )];
+        let folder = compile_sources(sources, &Options::default());
+        let structs = folder
+            .find_folder_by_path(&["google", "protobuf", "struct"])
+            .unwrap();
+        assert!(structs.find_file("NullValue").is_some());
+        assert!(structs.find_folder("Struct").is_none());
+        assert!(structs.find_folder("Value").is_none());
+        assert!(structs.find_folder("ListValue").is_none());
+    }
+
+    #[test]
+    fn it_does_not_emit_wkt_module_without_well_known_types() {
+        let sources = &[(
+            "a.proto",
+            "syntax = \"proto3\";\nmessage A { string s = 1; }\n",
+        )];
+        let folder = compile_sources(sources, &Options::default());
+        assert!(folder.find_file(WKT_FILE_NAME).is_none());
+    }
+}
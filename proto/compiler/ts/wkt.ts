@@ -0,0 +1,517 @@
+// Well-known types of google/protobuf mapped to plain TypeScript values.
+// They keep the wire format of the messages they replace and the proto3 JSON mapping of them.
+
+/** A number, a `Long` of protobufjs or a bigint of the embedded runtime as a number. */
+function toNumber(value: unknown): number {
+  if (typeof value === "object" && value !== null) return (value as { toNumber(): number }).toNumber()
+  return Number(value)
+}
+
+function toReader(reader: Reader | Uint8Array): Reader {
+  return reader instanceof Reader ? reader : Reader.create(reader)
+}
+
+/** Seconds and nanoseconds of a `Timestamp` or a `Duration` as they are on the wire. */
+export interface SecondsAndNanos {
+  seconds?: number | null
+  nanos?: number | null
+}
+
+export type Timestamp = Date
+/** A `Date`, or the seconds and nanoseconds since the epoch for a precision finer than milliseconds. */
+export type TimestampEncodeInput = Date | SecondsAndNanos
+
+export function encodeTimestamp(value: TimestampEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  const millis = value instanceof Date ? value.getTime() : 0
+  const seconds = value instanceof Date ? Math.floor(millis / 1000) : value.seconds || 0
+  const nanos = value instanceof Date ? (millis - seconds * 1000) * 1000000 : value.nanos || 0
+  if (seconds !== 0) w.uint32(8).int64(seconds)
+  if (nanos !== 0) w.uint32(16).int32(nanos)
+  return w
+}
+
+export function decodeTimestamp(reader: Reader | Uint8Array, length?: number): Timestamp {
+  const r = toReader(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  let seconds = 0
+  let nanos = 0
+  while (r.pos < end) {
+    const tag = r.uint32()
+    switch (tag >>> 3) {
+      case 1:
+        seconds = toNumber(r.int64())
+        break
+      case 2:
+        nanos = r.int32()
+        break
+      default:
+        r.skipType(tag & 7)
+        break
+    }
+  }
+  return new Date(seconds * 1000 + Math.floor(nanos / 1000000))
+}
+
+export function toJSONTimestamp(value: Timestamp): string {
+  return value.toISOString()
+}
+
+export function fromJSONTimestamp(json: any): Timestamp {
+  return new Date(json)
+}
+
+export interface Duration {
+  seconds: number
+  nanos: number
+}
+
+/** The seconds and nanoseconds, or a number of seconds with the fraction in nanoseconds. */
+export type DurationEncodeInput = SecondsAndNanos | number
+
+export function encodeDuration(value: DurationEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  let seconds = typeof value === "number" ? Math.trunc(value) : value.seconds || 0
+  let nanos = typeof value === "number" ? Math.round((value - seconds) * 1000000000) : value.nanos || 0
+  if (Math.abs(nanos) === 1000000000) {
+    // the fraction is rounded up to a whole second, like the one of 0.9999999999
+    seconds += Math.sign(nanos)
+    nanos = 0
+  }
+  if (seconds) w.uint32(8).int64(seconds)
+  if (nanos) w.uint32(16).int32(nanos)
+  return w
+}
+
+export function decodeDuration(reader: Reader | Uint8Array, length?: number): Duration {
+  const r = toReader(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  const value: Duration = { seconds: 0, nanos: 0 }
+  while (r.pos < end) {
+    const tag = r.uint32()
+    switch (tag >>> 3) {
+      case 1:
+        value.seconds = toNumber(r.int64())
+        break
+      case 2:
+        value.nanos = r.int32()
+        break
+      default:
+        r.skipType(tag & 7)
+        break
+    }
+  }
+  return value
+}
+
+/** `"1.5s"`, `"-0.000000001s"` */
+export function toJSONDuration(value: Duration): string {
+  const sign = value.seconds < 0 || value.nanos < 0 ? "-" : ""
+  const fraction = String(Math.abs(value.nanos)).padStart(9, "0").replace(/0+$/, "")
+  return sign + Math.abs(value.seconds) + (fraction ? "." + fraction : "") + "s"
+}
+
+export function fromJSONDuration(json: any): Duration {
+  const match = /^(-)?(\d+)(?:\.(\d{1,9}))?s$/.exec(String(json))
+  if (!match) throw new Error("invalid duration " + json)
+  const sign = match[1] ? -1 : 1
+  return {
+    seconds: sign * Number(match[2]),
+    nanos: sign * Number((match[3] || "").padEnd(9, "0")),
+  }
+}
+
+/** Encoders and decoders of a wrapper message with the single `value` field, `null` when it is missing. */
+function wrapper<T>(
+  tag: number,
+  isDefault: (value: T) => boolean,
+  write: (w: Writer, value: T) => Writer,
+  read: (r: Reader) => T,
+) {
+  return {
+    encode(value: T | null | undefined, writer?: Writer): Writer {
+      const w = writer || Writer.create()
+      if (value != null && !isDefault(value)) write(w.uint32(tag), value)
+      return w
+    },
+    decode(reader: Reader | Uint8Array, length?: number): T | null {
+      const r = toReader(reader)
+      const end = length === undefined ? r.len : r.pos + length
+      let value: T | null = null
+      while (r.pos < end) {
+        const tag = r.uint32()
+        if (tag >>> 3 === 1) value = read(r)
+        else r.skipType(tag & 7)
+      }
+      return value
+    },
+  }
+}
+
+const isZero = (value: number) => value === 0
+
+const doubleValue = wrapper<number>(9, isZero, (w, v) => w.double(v), (r) => r.double())
+export type DoubleValue = number | null
+export type DoubleValueEncodeInput = number | null
+export const encodeDoubleValue = doubleValue.encode
+export const decodeDoubleValue = doubleValue.decode
+
+const floatValue = wrapper<number>(13, isZero, (w, v) => w.float(v), (r) => r.float())
+export type FloatValue = number | null
+export type FloatValueEncodeInput = number | null
+export const encodeFloatValue = floatValue.encode
+export const decodeFloatValue = floatValue.decode
+
+const int64Value = wrapper<number>(8, isZero, (w, v) => w.int64(v), (r) => toNumber(r.int64()))
+export type Int64Value = number | null
+export type Int64ValueEncodeInput = number | null
+export const encodeInt64Value = int64Value.encode
+export const decodeInt64Value = int64Value.decode
+
+const uint64Value = wrapper<number>(8, isZero, (w, v) => w.uint64(v), (r) => toNumber(r.uint64()))
+export type UInt64Value = number | null
+export type UInt64ValueEncodeInput = number | null
+export const encodeUInt64Value = uint64Value.encode
+export const decodeUInt64Value = uint64Value.decode
+
+const int32Value = wrapper<number>(8, isZero, (w, v) => w.int32(v), (r) => r.int32())
+export type Int32Value = number | null
+export type Int32ValueEncodeInput = number | null
+export const encodeInt32Value = int32Value.encode
+export const decodeInt32Value = int32Value.decode
+
+const uint32Value = wrapper<number>(8, isZero, (w, v) => w.uint32(v), (r) => r.uint32())
+export type UInt32Value = number | null
+export type UInt32ValueEncodeInput = number | null
+export const encodeUInt32Value = uint32Value.encode
+export const decodeUInt32Value = uint32Value.decode
+
+const boolValue = wrapper<boolean>(8, (v) => !v, (w, v) => w.bool(v), (r) => r.bool())
+export type BoolValue = boolean | null
+export type BoolValueEncodeInput = boolean | null
+export const encodeBoolValue = boolValue.encode
+export const decodeBoolValue = boolValue.decode
+
+const stringValue = wrapper<string>(10, (v) => v === "", (w, v) => w.string(v), (r) => r.string())
+export type StringValue = string | null
+export type StringValueEncodeInput = string | null
+export const encodeStringValue = stringValue.encode
+export const decodeStringValue = stringValue.decode
+
+const bytesValue = wrapper<Uint8Array>(10, (v) => v.length === 0, (w, v) => w.bytes(v), (r) => r.bytes())
+export type BytesValue = Uint8Array | null
+export type BytesValueEncodeInput = Uint8Array | null
+export const encodeBytesValue = bytesValue.encode
+export const decodeBytesValue = bytesValue.decode
+
+// Wrappers are written as their values in JSON, 64-bit integers as strings.
+const same = (value: any) => value
+export const toJSONDoubleValue = same
+export const fromJSONDoubleValue = (json: any): DoubleValue => Number(json)
+export const toJSONFloatValue = same
+export const fromJSONFloatValue = (json: any): FloatValue => Number(json)
+export const toJSONInt64Value = (value: Int64Value): string => String(value)
+export const fromJSONInt64Value = (json: any): Int64Value => Number(json)
+export const toJSONUInt64Value = (value: UInt64Value): string => String(value)
+export const fromJSONUInt64Value = (json: any): UInt64Value => Number(json)
+export const toJSONInt32Value = same
+export const fromJSONInt32Value = (json: any): Int32Value => Number(json)
+export const toJSONUInt32Value = same
+export const fromJSONUInt32Value = (json: any): UInt32Value => Number(json)
+export const toJSONBoolValue = same
+export const fromJSONBoolValue = (json: any): BoolValue => Boolean(json)
+export const toJSONStringValue = same
+export const fromJSONStringValue = (json: any): StringValue => String(json)
+
+export function toJSONBytesValue(value: BytesValue): string | null {
+  return value && util.base64.encode(value, 0, value.length)
+}
+
+export function fromJSONBytesValue(json: any): BytesValue {
+  const value = new Uint8Array(util.base64.length(json))
+  util.base64.decode(json, value, 0)
+  return value
+}
+
+// Values of any type, only `null`, numbers, strings, booleans, arrays and objects of them are encoded.
+export type Struct = { [key: string]: unknown }
+export type StructEncodeInput = Struct
+export type Value = unknown
+export type ValueEncodeInput = Value
+export type ListValue = unknown[]
+export type ListValueEncodeInput = ListValue
+
+export function encodeStruct(value: StructEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  for (const key of Object.keys(value)) {
+    w.uint32(10).fork().uint32(10).string(key)
+    encodeValue(value[key], w.uint32(18).fork()).ldelim().ldelim()
+  }
+  return w
+}
+
+export function decodeStruct(reader: Reader | Uint8Array, length?: number): Struct {
+  const r = toReader(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  const value: Struct = {}
+  while (r.pos < end) {
+    const tag = r.uint32()
+    if (tag >>> 3 !== 1) {
+      r.skipType(tag & 7)
+      continue
+    }
+    const entryEnd = r.uint32() + r.pos
+    let key = ""
+    let entryValue: Value = null
+    while (r.pos < entryEnd) {
+      const entryTag = r.uint32()
+      switch (entryTag >>> 3) {
+        case 1:
+          key = r.string()
+          break
+        case 2:
+          entryValue = decodeValue(r, r.uint32())
+          break
+        default:
+          r.skipType(entryTag & 7)
+          break
+      }
+    }
+    value[key] = entryValue
+  }
+  return value
+}
+
+export function encodeValue(value: ValueEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  if (value === null || value === undefined) w.uint32(8).int32(0)
+  else if (typeof value === "number") w.uint32(17).double(value)
+  else if (typeof value === "string") w.uint32(26).string(value)
+  else if (typeof value === "boolean") w.uint32(32).bool(value)
+  else if (Array.isArray(value)) encodeListValue(value, w.uint32(50).fork()).ldelim()
+  else encodeStruct(value as Struct, w.uint32(42).fork()).ldelim()
+  return w
+}
+
+export function decodeValue(reader: Reader | Uint8Array, length?: number): Value {
+  const r = toReader(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  let value: Value = null
+  while (r.pos < end) {
+    const tag = r.uint32()
+    switch (tag >>> 3) {
+      case 1:
+        r.int32()
+        value = null
+        break
+      case 2:
+        value = r.double()
+        break
+      case 3:
+        value = r.string()
+        break
+      case 4:
+        value = r.bool()
+        break
+      case 5:
+        value = decodeStruct(r, r.uint32())
+        break
+      case 6:
+        value = decodeListValue(r, r.uint32())
+        break
+      default:
+        r.skipType(tag & 7)
+        break
+    }
+  }
+  return value
+}
+
+export function encodeListValue(value: ListValueEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  for (let i = 0; i < value.length; ++i) encodeValue(value[i], w.uint32(10).fork()).ldelim()
+  return w
+}
+
+export function decodeListValue(reader: Reader | Uint8Array, length?: number): ListValue {
+  const r = toReader(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  const value: ListValue = []
+  while (r.pos < end) {
+    const tag = r.uint32()
+    if (tag >>> 3 === 1) value.push(decodeValue(r, r.uint32()))
+    else r.skipType(tag & 7)
+  }
+  return value
+}
+
+// Struct, Value and ListValue are the JSON values themselves.
+export const toJSONStruct = same
+export const fromJSONStruct = (json: any): Struct => json
+export const toJSONValue = same
+export const fromJSONValue = (json: any): Value => json
+export const toJSONListValue = same
+export const fromJSONListValue = (json: any): ListValue => json
+
+export interface Any {
+  typeUrl: string
+  value: Uint8Array
+}
+
+export interface AnyEncodeInput {
+  typeUrl?: string | null
+  value?: Uint8Array | null
+}
+
+export function encodeAny(value: AnyEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  if (value.typeUrl) w.uint32(10).string(value.typeUrl)
+  if (value.value && value.value.length) w.uint32(18).bytes(value.value)
+  return w
+}
+
+export function decodeAny(reader: Reader | Uint8Array, length?: number): Any {
+  const r = toReader(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  const value: Any = { typeUrl: "", value: new Uint8Array(0) }
+  while (r.pos < end) {
+    const tag = r.uint32()
+    switch (tag >>> 3) {
+      case 1:
+        value.typeUrl = r.string()
+        break
+      case 2:
+        value.value = r.bytes()
+        break
+      default:
+        r.skipType(tag & 7)
+        break
+    }
+  }
+  return value
+}
+
+// Without a registry of the packed types their fields are not known,
+// so the JSON of `Any` keeps the encoded message in base64 next to `@type`.
+export function toJSONAny(value: Any): { "@type": string; value: string } {
+  return { "@type": value.typeUrl, value: util.base64.encode(value.value, 0, value.value.length) }
+}
+
+export function fromJSONAny(json: any): Any {
+  const value = new Uint8Array(util.base64.length(json.value || ""))
+  util.base64.decode(json.value || "", value, 0)
+  return { typeUrl: String(json["@type"] || ""), value }
+}
+
+export interface FieldMask {
+  paths: string[]
+}
+
+export interface FieldMaskEncodeInput {
+  paths?: string[] | null
+}
+
+export function encodeFieldMask(value: FieldMaskEncodeInput, writer?: Writer): Writer {
+  const w = writer || Writer.create()
+  const paths = value.paths || []
+  for (let i = 0; i < paths.length; ++i) w.uint32(10).string(paths[i])
+  return w
+}
+
+export function decodeFieldMask(reader: Reader | Uint8Array, length?: number): FieldMask {
+  const r = toReader(reader)
+  const end = length === undefined ? r.len : r.pos + length
+  const value: FieldMask = { paths: [] }
+  while (r.pos < end) {
+    const tag = r.uint32()
+    if (tag >>> 3 === 1) value.paths.push(r.string())
+    else r.skipType(tag & 7)
+  }
+  return value
+}
+
+/** `{ paths: ["user_id", "address.zip_code"] }` is `"userId,address.zipCode"` */
+export function toJSONFieldMask(value: FieldMask): string {
+  return value.paths.map((path) => path.replace(/_([a-z0-9])/g, (_, c: string) => c.toUpperCase())).join(",")
+}
+
+export function fromJSONFieldMask(json: any): FieldMask {
+  const paths = String(json).split(",").filter((path) => path !== "")
+  return { paths: paths.map((path) => path.replace(/[A-Z]/g, (c) => "_" + c.toLowerCase())) }
+}
+
+// Equality of the mapped values, used by the generated `equals` functions of messages.
+// A missing value is equal only to a missing value.
+
+function equalsBytes(a: Uint8Array | null | undefined, b: Uint8Array | null | undefined): boolean {
+  if (a == null || b == null) return a === b
+  if (a.length !== b.length) return false
+  for (let i = 0; i < a.length; i++) {
+    if (a[i] !== b[i]) return false
+  }
+  return true
+}
+
+const strictEquals = (a: unknown, b: unknown): boolean => a === b
+
+export function equalsTimestamp(a: Timestamp | undefined, b: Timestamp | undefined): boolean {
+  if (a === undefined || b === undefined) return a === b
+  return a.getTime() === b.getTime()
+}
+
+export function equalsDuration(a: Duration | undefined, b: Duration | undefined): boolean {
+  if (a === undefined || b === undefined) return a === b
+  return a.seconds === b.seconds && a.nanos === b.nanos
+}
+
+export const equalsDoubleValue = strictEquals
+export const equalsFloatValue = strictEquals
+export const equalsInt64Value = strictEquals
+export const equalsUInt64Value = strictEquals
+export const equalsInt32Value = strictEquals
+export const equalsUInt32Value = strictEquals
+export const equalsBoolValue = strictEquals
+export const equalsStringValue = strictEquals
+export const equalsBytesValue = equalsBytes
+
+export function equalsStruct(a: Struct | undefined, b: Struct | undefined): boolean {
+  if (a === undefined || b === undefined) return a === b
+  const keys = Object.keys(a)
+  if (keys.length !== Object.keys(b).length) return false
+  for (const key of keys) {
+    if (!(key in b) || !equalsValue(a[key], b[key])) return false
+  }
+  return true
+}
+
+export function equalsValue(a: Value, b: Value): boolean {
+  if (a === b) return true
+  if (typeof a !== "object" || typeof b !== "object" || a === null || b === null) return false
+  if (Array.isArray(a) || Array.isArray(b)) {
+    return Array.isArray(a) && Array.isArray(b) && equalsListValue(a, b)
+  }
+  return equalsStruct(a as Struct, b as Struct)
+}
+
+export function equalsListValue(a: ListValue | undefined, b: ListValue | undefined): boolean {
+  if (a === undefined || b === undefined) return a === b
+  if (a.length !== b.length) return false
+  for (let i = 0; i < a.length; i++) {
+    if (!equalsValue(a[i], b[i])) return false
+  }
+  return true
+}
+
+export function equalsAny(a: Any | undefined, b: Any | undefined): boolean {
+  if (a === undefined || b === undefined) return a === b
+  return a.typeUrl === b.typeUrl && equalsBytes(a.value, b.value)
+}
+
+export function equalsFieldMask(a: FieldMask | undefined, b: FieldMask | undefined): boolean {
+  if (a === undefined || b === undefined) return a === b
+  if (a.paths.length !== b.paths.length) return false
+  for (let i = 0; i < a.paths.length; i++) {
+    if (a.paths[i] !== b.paths[i]) return false
+  }
+  return true
+}
@@ -1,12 +1,31 @@
 use std::{
     fmt::{Display, Formatter},
     io,
+    path::PathBuf,
 };
 
-use super::lexems::{self};
+use super::{
+    lexems::{self},
+    validation::{MAX_FIELD_NUMBER, MIN_FIELD_NUMBER, RESERVED_FIELD_NUMBERS},
+};
+
+/// Position in a proto file an error refers to,
+/// `line` and `column` start from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
 
 #[derive(Debug)]
-pub(crate) enum ProtoError {
+pub enum ProtoError {
     Default(String),
     CannotOpenFile(io::Error),
     IOError(io::Error),
@@ -29,12 +48,289 @@ pub(crate) enum ProtoError {
         column: usize,
         message: String,
     },
+    InvalidFieldNumber {
+        file_path: String,
+        line: usize,
+        column: usize,
+        field_name: String,
+        number: i64,
+    },
+    FieldNumberOutOfRange {
+        message_name: String,
+        field_name: String,
+        number: i64,
+    },
+    /// Field numbers used by more than one field of a message,
+    /// with the names of these fields.
+    DuplicateFieldNumbers {
+        message_name: String,
+        duplicates: Vec<(i64, Vec<String>)>,
+    },
+    /// Values shared by more than one member of an enum without `option allow_alias = true;`,
+    /// with the names of these members.
+    DuplicateEnumValues {
+        enum_name: String,
+        duplicates: Vec<(i64, Vec<String>)>,
+    },
+    /// Enum of a proto3 file whose first member is not `0`, the default value of its fields.
+    /// `first_value` is `None` for an enum without members.
+    MissingZeroEnumValue {
+        enum_name: String,
+        first_value: Option<(String, i64)>,
+    },
+    /// proto2 group, which is not supported.
+    UnsupportedGroup {
+        file_path: String,
+        line: usize,
+        column: usize,
+        field_name: String,
+    },
+    /// proto2 `default` option of a field that does not fit the type of the field.
+    InvalidDefaultValue {
+        message_name: String,
+        field_name: String,
+        value: String,
+    },
+    /// Field uses a number of a `reserved` statement of its message.
+    ReservedFieldNumber {
+        message_name: String,
+        field_name: String,
+        number: i64,
+    },
+    /// Field uses a name of a `reserved` statement of its message.
+    ReservedFieldName {
+        message_name: String,
+        field_name: String,
+    },
+    /// Type of a field refers to a name that is not declared
+    /// in the scope of the field or in the imported files.
+    UnresolvedName {
+        file_path: String,
+        line: usize,
+        column: usize,
+        name: String,
+        /// Scopes the name was looked up in, innermost first.
+        scopes: Vec<String>,
+    },
+    /// `import` statement refers to a file that was not read.
+    UnresolvedImport {
+        file_path: String,
+        line: usize,
+        column: usize,
+        import_path: String,
+    },
+    /// Request or response of a service method that is not a message.
+    InvalidMethodType {
+        file_path: String,
+        line: usize,
+        column: usize,
+        /// `Service.Method`
+        method_name: String,
+        type_name: String,
+    },
+    /// Rename of a message, an enum or a field that cannot be applied.
+    InvalidRename {
+        name: String,
+        reason: String,
+    },
+    /// Two generated files or folders that are the same path on case-insensitive file systems,
+    /// paths are relative to the output folder.
+    FileNameCollision {
+        first: String,
+        second: String,
+    },
+    /// Two fields of a message with the same property name,
+    /// like `foo_bar` and `fooBar` once they are camelCased.
+    PropertyNameCollision {
+        message: String,
+        first: String,
+        second: String,
+        property: String,
+    },
+    /// Two declarations of a proto file exported under the same name in the file layout,
+    /// like a nested `Parent.Child` and a top-level `Parent_Child`.
+    DeclarationNameCollision {
+        file: String,
+        first: String,
+        second: String,
+        name: String,
+    },
+    /// A field named as the property unknown fields are kept in,
+    /// by its `json_name` option or a rename.
+    UnknownFieldsCollision {
+        message: String,
+        field: String,
+        property: String,
+    },
+    /// Proto files importing each other, `files` is the chain of imports
+    /// from a file back to itself: `a.proto -> b.proto -> a.proto`.
+    ImportCycle {
+        files: Vec<String>,
+    },
+    /// Errors collected from independent units of work,
+    /// like files compiled in parallel.
+    Multiple(Vec<ProtoError>),
 }
 
 impl ProtoError {
     pub fn new(value: &str) -> Self {
         ProtoError::Default(value.to_string())
     }
+
+    /// Combines collected errors, a single error is returned as is.
+    /// Nested `Multiple` errors are flattened.
+    pub fn from_errors(errors: Vec<ProtoError>) -> Self {
+        let mut flat = Vec::with_capacity(errors.len());
+        for error in errors {
+            match error {
+                ProtoError::Multiple(nested) => flat.extend(nested),
+                error => flat.push(error),
+            }
+        }
+        if flat.len() == 1 {
+            return flat.pop().unwrap();
+        }
+        ProtoError::Multiple(flat)
+    }
+
+    /// Every error that is not a `Multiple`, in the order they were collected.
+    pub fn errors(&self) -> Vec<&ProtoError> {
+        match self {
+            ProtoError::Multiple(errors) => errors.iter().flat_map(|e| e.errors()).collect(),
+            e => vec![e],
+        }
+    }
+
+    /// Stable identifier of the error kind, used in machine-readable output.
+    pub fn code(&self) -> &'static str {
+        use ProtoError::*;
+        match self {
+            Default(_) => "default",
+            CannotOpenFile(_) => "cannot_open_file",
+            IOError(_) => "io_error",
+            UnknownCharacter { .. } => "unknown_character",
+            InvalidIntLiteral { .. } => "invalid_int_literal",
+            SyntaxError { .. } => "syntax_error",
+            InvalidFieldNumber { .. } => "invalid_field_number",
+            FieldNumberOutOfRange { .. } => "field_number_out_of_range",
+            DuplicateFieldNumbers { .. } => "duplicate_field_numbers",
+            DuplicateEnumValues { .. } => "duplicate_enum_values",
+            MissingZeroEnumValue { .. } => "missing_zero_enum_value",
+            UnsupportedGroup { .. } => "unsupported_group",
+            InvalidDefaultValue { .. } => "invalid_default_value",
+            ReservedFieldNumber { .. } => "reserved_field_number",
+            ReservedFieldName { .. } => "reserved_field_name",
+            UnresolvedName { .. } => "unresolved_name",
+            UnresolvedImport { .. } => "unresolved_import",
+            InvalidMethodType { .. } => "invalid_method_type",
+            InvalidRename { .. } => "invalid_rename",
+            FileNameCollision { .. } => "file_name_collision",
+            PropertyNameCollision { .. } => "property_name_collision",
+            DeclarationNameCollision { .. } => "declaration_name_collision",
+            UnknownFieldsCollision { .. } => "unknown_fields_collision",
+            ImportCycle { .. } => "import_cycle",
+            Multiple(_) => "multiple",
+        }
+    }
+
+    /// Position in a proto file the error was found at,
+    /// `None` for errors that are not caused by a specific place of a file.
+    pub fn location(&self) -> Option<SourceLocation> {
+        use ProtoError::*;
+        let (file_path, line, column) = match self {
+            UnknownCharacter {
+                file_path,
+                line,
+                column,
+                ..
+            }
+            | SyntaxError {
+                file_path,
+                line,
+                column,
+                ..
+            }
+            | InvalidFieldNumber {
+                file_path,
+                line,
+                column,
+                ..
+            }
+            | UnsupportedGroup {
+                file_path,
+                line,
+                column,
+                ..
+            }
+            | UnresolvedName {
+                file_path,
+                line,
+                column,
+                ..
+            }
+            | UnresolvedImport {
+                file_path,
+                line,
+                column,
+                ..
+            }
+            | InvalidMethodType {
+                file_path,
+                line,
+                column,
+                ..
+            } => (file_path, *line, *column),
+            InvalidIntLiteral {
+                file_path,
+                line,
+                start_column,
+                ..
+            } => (file_path, *line, *start_column),
+            _ => return None,
+        };
+        Some(SourceLocation {
+            file: PathBuf::from(file_path),
+            line,
+            column,
+        })
+    }
+
+    /// Text of the error without the location it starts with.
+    pub fn message(&self) -> String {
+        let text = self.to_string();
+        match self.location() {
+            Some(location) => match text.strip_prefix(&format!("{}: ", location)) {
+                Some(message) => message.to_string(),
+                None => text,
+            },
+            None => text,
+        }
+    }
+}
+
+/// Serialized as `{ "message", "file", "line", "column" }`,
+/// where the location fields are `null` for errors without a location.
+/// `Multiple` is serialized as an array of its errors.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ProtoError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        if let ProtoError::Multiple(_) = self {
+            return serializer.collect_seq(self.errors());
+        }
+        let location = self.location();
+        let mut state = serializer.serialize_struct("ProtoError", 4)?;
+        state.serialize_field("message", &self.message())?;
+        state.serialize_field(
+            "file",
+            &location
+                .as_ref()
+                .map(|l| l.file.to_string_lossy().to_string()),
+        )?;
+        state.serialize_field("line", &location.as_ref().map(|l| l.line))?;
+        state.serialize_field("column", &location.as_ref().map(|l| l.column))?;
+        state.end()
+    }
 }
 
 impl Display for ProtoError {
@@ -78,6 +374,220 @@ impl Display for ProtoError {
                     file_path, line, start_column, end_column
                 )
             }
+            InvalidFieldNumber {
+                file_path,
+                line,
+                column,
+                field_name,
+                number,
+            } => write!(
+                f,
+                "{}:{}:{}: Invalid field number {} of \"{}\": field numbers must be in range {}..={} excluding the reserved range {}..={}",
+                file_path,
+                line,
+                column,
+                number,
+                field_name,
+                MIN_FIELD_NUMBER,
+                MAX_FIELD_NUMBER,
+                RESERVED_FIELD_NUMBERS.start(),
+                RESERVED_FIELD_NUMBERS.end()
+            ),
+            FieldNumberOutOfRange {
+                message_name,
+                field_name,
+                number,
+            } => write!(
+                f,
+                "Invalid field number {} of \"{}.{}\": field numbers must be in range {}..={} excluding the reserved range {}..={}",
+                number,
+                message_name,
+                field_name,
+                MIN_FIELD_NUMBER,
+                MAX_FIELD_NUMBER,
+                RESERVED_FIELD_NUMBERS.start(),
+                RESERVED_FIELD_NUMBERS.end()
+            ),
+            DuplicateFieldNumbers {
+                message_name,
+                duplicates,
+            } => {
+                write!(f, "Duplicate field numbers in message \"{}\": ", message_name)?;
+                for (ind, (number, field_names)) in duplicates.iter().enumerate() {
+                    if ind > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} ({})", number, field_names.join(", "))?;
+                }
+                Ok(())
+            }
+            DuplicateEnumValues {
+                enum_name,
+                duplicates,
+            } => {
+                write!(
+                    f,
+                    "Duplicate values in enum \"{}\" without option allow_alias = true: ",
+                    enum_name
+                )?;
+                for (ind, (value, names)) in duplicates.iter().enumerate() {
+                    if ind > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} ({})", value, names.join(", "))?;
+                }
+                Ok(())
+            }
+            MissingZeroEnumValue {
+                enum_name,
+                first_value: Some((name, value)),
+            } => write!(
+                f,
+                "The first value of enum \"{}\" must be zero in proto3, found {} = {}",
+                enum_name, name, value
+            ),
+            MissingZeroEnumValue {
+                enum_name,
+                first_value: None,
+            } => write!(
+                f,
+                "Enum \"{}\" must have a zero value in proto3, it has no values",
+                enum_name
+            ),
+            UnsupportedGroup {
+                file_path,
+                line,
+                column,
+                field_name,
+            } => write!(
+                f,
+                "{}:{}:{}: Group \"{}\" is not supported, declare a nested message and a field of its type instead",
+                file_path, line, column, field_name
+            ),
+            InvalidDefaultValue {
+                message_name,
+                field_name,
+                value,
+            } => write!(
+                f,
+                "Invalid default value {} of field \"{}.{}\"",
+                value, message_name, field_name
+            ),
+            ReservedFieldNumber {
+                message_name,
+                field_name,
+                number,
+            } => write!(
+                f,
+                "Field \"{}.{}\" uses the reserved field number {}",
+                message_name, field_name, number
+            ),
+            ReservedFieldName {
+                message_name,
+                field_name,
+            } => write!(
+                f,
+                "Field \"{}.{}\" uses the reserved field name",
+                message_name, field_name
+            ),
+            UnresolvedName {
+                file_path,
+                line,
+                column,
+                name,
+                scopes,
+            } => {
+                write!(
+                    f,
+                    "{}:{}:{}: Could not resolve name {}",
+                    file_path, line, column, name
+                )?;
+                for scope in scopes {
+                    write!(f, "\n  in {}", scope)?;
+                }
+                Ok(())
+            }
+            UnresolvedImport {
+                file_path,
+                line,
+                column,
+                import_path,
+            } => write!(
+                f,
+                "{}:{}:{}: Could not resolve import \"{}\"",
+                file_path, line, column, import_path
+            ),
+            InvalidMethodType {
+                file_path,
+                line,
+                column,
+                method_name,
+                type_name,
+            } => write!(
+                f,
+                "{}:{}:{}: Type {} of method {} is not a message",
+                file_path, line, column, type_name, method_name
+            ),
+            InvalidRename { name, reason } => {
+                write!(f, "Invalid rename of \"{}\": {}", name, reason)
+            }
+            FileNameCollision { first, second } => write!(
+                f,
+                "\"{}\" and \"{}\" are the same path on case-insensitive file systems, rename one of the declarations or choose another file case",
+                first, second
+            ),
+            PropertyNameCollision {
+                message,
+                first,
+                second,
+                property,
+            } => write!(
+                f,
+                "Fields \"{}\" and \"{}\" of {} are both named \"{}\", rename one of them or choose another field case",
+                first, second, message, property
+            ),
+            DeclarationNameCollision {
+                file,
+                first,
+                second,
+                name,
+            } => write!(
+                f,
+                "{} and {} are both exported as \"{}\" from {}, rename one of them or choose another layout",
+                first, second, name, file
+            ),
+            UnknownFieldsCollision {
+                message,
+                field,
+                property,
+            } => write!(
+                f,
+                "Field \"{}\" of {} is named \"{}\", the property unknown fields are kept in, rename the field",
+                field, message, property
+            ),
+            ImportCycle { files } => write!(
+                f,
+                "Proto files import each other: {}, move the declarations they share to another file",
+                files.join(" -> ")
+            ),
+            Multiple(errors) => {
+                for (ind, err) in errors.iter().enumerate() {
+                    if ind > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProtoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProtoError::CannotOpenFile(err) | ProtoError::IOError(err) => Some(err),
+            _ => None,
         }
     }
 }
@@ -99,3 +609,54 @@ pub(super) fn syntax_error<T: Into<String>>(
         message: format!("{}, but {} occurred", message.into(), lexem.lexem).into(),
     }
 }
+
+#[cfg(test)]
+mod test_send {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn it_can_be_sent_between_threads() {
+        // errors of files parsed and compiled in parallel are collected on the main thread
+        assert_send_sync::<ProtoError>();
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod test_serialize {
+    use super::*;
+
+    #[test]
+    fn it_serializes_message_and_location() {
+        let err = ProtoError::from_errors(vec![
+            ProtoError::UnresolvedName {
+                file_path: "schema/user.proto".into(),
+                line: 42,
+                column: 17,
+                name: "Address".into(),
+                scopes: vec!["Message User".into()],
+            },
+            ProtoError::from_errors(vec![
+                ProtoError::new("Cannot resolve empty full path"),
+                ProtoError::SyntaxError {
+                    file_path: "a.proto".into(),
+                    line: 1,
+                    column: 2,
+                    message: "Expected lexem".into(),
+                },
+            ]),
+        ]);
+        assert_eq!(err.errors().len(), 3);
+        assert_eq!(
+            serde_json::to_string(&err).unwrap(),
+            concat!(
+                "[",
+                r#"{"message":"Could not resolve name Address\n  in Message User","file":"schema/user.proto","line":42,"column":17},"#,
+                r#"{"message":"Cannot resolve empty full path","file":null,"line":null,"column":null},"#,
+                r#"{"message":"SyntaxError: Expected lexem","file":"a.proto","line":1,"column":2}"#,
+                "]"
+            )
+        );
+    }
+}
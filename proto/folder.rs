@@ -1,4 +1,13 @@
-use std::{io, path::PathBuf};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    compiler::ts::parallel_map::parallel_map,
+    lexems::{read_lexems, Lexem},
+};
 
 #[derive(Debug)]
 pub(crate) struct ProtoFolder {
@@ -16,8 +25,27 @@ impl std::fmt::Display for ProtoFolder {
     }
 }
 
-/// Recursively goes through the folder and collects all .proto files
+impl ProtoFolder {
+    /// Path of the file relative to the folder, with `/` separators,
+    /// as it is written in `import` statements.
+    pub fn source_path(&self, file: &Path) -> String {
+        let relative = file.strip_prefix(&self.path).unwrap_or(file);
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// Recursively goes through the folder and collects all .proto files.
+///
+/// When `folder_path` is a single .proto file, its parent is the folder
+/// and only the file with the files it imports, transitively, is collected.
 pub(crate) fn read_proto_folder(folder_path: PathBuf) -> io::Result<ProtoFolder> {
+    if folder_path.is_file() {
+        return read_proto_file_with_imports(folder_path);
+    }
     let mut folders = vec![folder_path.clone()];
 
     let mut all_proto_file_paths: Vec<PathBuf> = Vec::new();
@@ -39,3 +67,211 @@ pub(crate) fn read_proto_folder(folder_path: PathBuf) -> io::Result<ProtoFolder>
         path: folder_path,
     })
 }
+
+/// Imports are resolved relative to the parent folder of the file.
+/// Imports that are not found on the disk, like the well-known types,
+/// are left to the compiler.
+///
+/// Files found at the same depth of imports are read in parallel.
+fn read_proto_file_with_imports(file_path: PathBuf) -> io::Result<ProtoFolder> {
+    let folder_path = file_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let mut files = vec![file_path];
+    let mut read_count = 0;
+    while read_count < files.len() {
+        let imports = read_files_imports(&files[read_count..])?;
+        read_count = files.len();
+        for import in imports.into_iter().flatten() {
+            let import_path = folder_path.join(import);
+            if import_path.is_file() && !files.contains(&import_path) {
+                files.push(import_path);
+            }
+        }
+    }
+    Ok(ProtoFolder {
+        files,
+        path: folder_path,
+    })
+}
+
+/// Collects the files imported by the files of `folders` that none of them has,
+/// from the first of the `include_dirs` containing the import path, as `protoc -I` does.
+/// Imports of the collected files are followed as well,
+/// files found at the same depth of imports are read in parallel.
+///
+/// Every include folder with collected files is returned as a folder of its own,
+/// so the source path of a file is the path it is imported by.
+pub(crate) fn read_include_folders(
+    folders: &[ProtoFolder],
+    include_dirs: &[PathBuf],
+) -> io::Result<Vec<ProtoFolder>> {
+    let mut include_folders = include_dirs
+        .iter()
+        .map(|dir| ProtoFolder {
+            files: Vec::new(),
+            path: dir.clone(),
+        })
+        .collect::<Vec<_>>();
+    if include_folders.is_empty() {
+        return Ok(include_folders);
+    }
+    let mut source_paths = folders
+        .iter()
+        .flat_map(|folder| folder.files.iter().map(|file| folder.source_path(file)))
+        .collect::<HashSet<_>>();
+    let mut files = folders
+        .iter()
+        .flat_map(|folder| folder.files.iter().cloned())
+        .collect::<Vec<_>>();
+    while !files.is_empty() {
+        let mut imported_files = Vec::new();
+        for import in read_files_imports(&files)?.into_iter().flatten() {
+            if source_paths.contains(&import) {
+                continue;
+            }
+            let found = include_folders
+                .iter_mut()
+                .map(|folder| (folder.path.join(&import), folder))
+                .find(|(path, _)| path.is_file());
+            if let Some((path, folder)) = found {
+                source_paths.insert(import);
+                folder.files.push(path.clone());
+                imported_files.push(path);
+            }
+        }
+        files = imported_files;
+    }
+    include_folders.retain(|folder| !folder.files.is_empty());
+    Ok(include_folders)
+}
+
+/// Imports of every file, read in parallel.
+/// The results follow the order of `files`, so the first error is the one of the first failing file.
+fn read_files_imports(files: &[PathBuf]) -> io::Result<Vec<Vec<String>>> {
+    parallel_map(files, |file| {
+        fs::read_to_string(file).map(|content| read_imports(&content))
+    })
+    .into_iter()
+    .collect()
+}
+
+/// Paths of the `import` statements of the file.
+/// A file that cannot be read into lexems has no imports here,
+/// the error is reported when it is parsed.
+fn read_imports(content: &str) -> Vec<String> {
+    let lexems = match read_lexems("", content) {
+        Ok(lexems) => lexems,
+        Err(_) => return Vec::new(),
+    };
+    let mut imports = Vec::new();
+    for (index, located) in lexems.iter().enumerate() {
+        if !matches!(&located.lexem, Lexem::Id(id) if &**id == "import") {
+            continue;
+        }
+        let mut next = lexems[index + 1..].iter().map(|located| &located.lexem);
+        let path = match next.next() {
+            Some(Lexem::Id(modifier)) if &**modifier == "public" || &**modifier == "weak" => {
+                next.next()
+            }
+            lexem => lexem,
+        };
+        if let Some(Lexem::StringLiteral(path)) = path {
+            imports.push(path.to_string());
+        }
+    }
+    imports
+}
+
+#[cfg(test)]
+mod test_read_proto_folder {
+    use std::time::SystemTime;
+
+    use super::*;
+
+    #[test]
+    fn it_reads_a_single_file_with_its_imports() {
+        let dir = std::env::temp_dir().join(format!(
+            "protos-ts-single-file-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(
+            dir.join("a.proto"),
+            "syntax = \"proto3\";\nimport \"sub/b.proto\";\nimport \"google/protobuf/timestamp.proto\";\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("sub/b.proto"),
+            "syntax = \"proto3\";\nimport public \"c.proto\";\nimport \"sub/b.proto\";\n",
+        )
+        .unwrap();
+        fs::write(dir.join("c.proto"), "message C { string import = 1; }").unwrap();
+        fs::write(dir.join("d.proto"), "message D {}").unwrap();
+
+        let folder = read_proto_folder(dir.join("a.proto")).unwrap();
+        assert_eq!(folder.path, dir);
+        assert_eq!(
+            folder
+                .files
+                .iter()
+                .map(|file| folder.source_path(file))
+                .collect::<Vec<_>>(),
+            vec!["a.proto", "sub/b.proto", "c.proto"]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_reads_imports_from_include_folders_in_order() {
+        let dir = std::env::temp_dir().join(format!(
+            "protos-ts-include-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        for sub in ["src", "first/shared", "second/shared", "second/other"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        fs::write(
+            dir.join("src/a.proto"),
+            "import \"shared/b.proto\";\nimport \"local.proto\";\nimport \"google/protobuf/empty.proto\";\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src/local.proto"), "message Local {}").unwrap();
+        fs::write(
+            dir.join("first/shared/b.proto"),
+            "import \"other/c.proto\";\nimport \"local.proto\";\n",
+        )
+        .unwrap();
+        fs::write(dir.join("second/shared/b.proto"), "message Shadowed {}").unwrap();
+        fs::write(dir.join("second/other/c.proto"), "message C {}").unwrap();
+        fs::write(dir.join("second/other/unused.proto"), "message Unused {}").unwrap();
+
+        let folders = vec![read_proto_folder(dir.join("src")).unwrap()];
+        let include_folders =
+            read_include_folders(&folders, &[dir.join("first"), dir.join("second")]).unwrap();
+        let source_paths = include_folders
+            .iter()
+            .map(|folder| {
+                let files = folder.files.iter().map(|file| folder.source_path(file));
+                (folder.path.clone(), files.collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            source_paths,
+            vec![
+                (dir.join("first"), vec!["shared/b.proto".to_string()]),
+                (dir.join("second"), vec!["other/c.proto".to_string()]),
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
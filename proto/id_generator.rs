@@ -13,6 +13,18 @@ impl IdGenerator {
     {
         T::create_with_id(self.next().unwrap(), args)
     }
+
+    /// Number of ids created so far.
+    pub fn ids_count(&self) -> usize {
+        self.index
+    }
+
+    /// Skips `count` ids, returns the number of ids created before them.
+    pub fn reserve(&mut self, count: usize) -> usize {
+        let offset = self.index;
+        self.index += count;
+        offset
+    }
 }
 
 impl Iterator for IdGenerator {
@@ -1,20 +1,26 @@
-use std::{fmt::Display, rc::Rc};
+use std::{fmt::Display, sync::Arc};
 
 use super::error::ProtoError;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(super) enum Lexem {
-    Id(Rc<str>),
+    Id(Arc<str>),
     Equal,
-    StringLiteral(Rc<str>),
+    StringLiteral(Arc<str>),
     SemiColon,
     Dot,
     IntLiteral(i64),
+    /// Text of a number with a fraction or an exponent, `inf` or `nan` with a minus,
+    /// like `1.5`, `-2e10` or `-inf`.
+    FloatLiteral(Arc<str>),
     OpenCurly,
     CloseCurly,
     Comma,
     OpenBracket,
     CloseBracket,
+    OpenParen,
+    CloseParen,
+    Colon,
     Less,
     Greater,
     EOF,
@@ -28,10 +34,14 @@ impl Display for Lexem {
             Lexem::SemiColon => write!(f, ";"),
             Lexem::Dot => write!(f, "."),
             Lexem::IntLiteral(i) => write!(f, "{}", i),
+            Lexem::FloatLiteral(s) => write!(f, "{}", s),
             Lexem::OpenCurly => write!(f, "{{"),
             Lexem::Comma => write!(f, ","),
             Lexem::CloseCurly => write!(f, "}}"),
             Lexem::OpenBracket => write!(f, "["),
+            Lexem::OpenParen => write!(f, "("),
+            Lexem::CloseParen => write!(f, ")"),
+            Lexem::Colon => write!(f, ":"),
             Lexem::CloseBracket => write!(f, "]"),
             Lexem::Less => write!(f, "<"),
             Lexem::Greater => write!(f, ">"),
@@ -44,6 +54,8 @@ pub(super) struct Position<'file_path> {
     pub(super) file_path: &'file_path str,
     pub(super) line: usize,
     pub(super) column: usize,
+    /// Offset of the character in bytes from the start of the file.
+    pub(super) byte_offset: usize,
 }
 
 impl Clone for Position<'_> {
@@ -52,6 +64,7 @@ impl Clone for Position<'_> {
             file_path: self.file_path,
             line: self.line,
             column: self.column,
+            byte_offset: self.byte_offset,
         }
     }
 }
@@ -163,6 +176,9 @@ pub(super) fn read_lexems<'file_path>(
             '}' => Some(Lexem::CloseCurly),
             '[' => Some(Lexem::OpenBracket),
             ']' => Some(Lexem::CloseBracket),
+            '(' => Some(Lexem::OpenParen),
+            ')' => Some(Lexem::CloseParen),
+            ':' => Some(Lexem::Colon),
             _ => None,
         };
         if let Some(lexem) = single_char_lexem {
@@ -217,7 +233,7 @@ fn try_read_id<'file_path>(
     if int_str.len() <= 0 {
         unreachable!()
     }
-    let lexem = Lexem::Id(Rc::from(int_str));
+    let lexem = Lexem::Id(Arc::from(int_str));
     let range = SourceRange { start, end };
     let located_lexem: LocatedLexem<'file_path> = LocatedLexem { lexem, range };
     Ok(located_lexem)
@@ -253,6 +269,26 @@ fn try_read_int<'file_path>(
     if digits.len() <= 0 {
         unreachable!()
     }
+    if digits == "-" {
+        // `-inf` and `-nan`
+        let id = try_read_id(located_chars, located_char_index)
+            .ok()
+            .and_then(|located_id| match located_id.lexem {
+                Lexem::Id(id) if &*id == "inf" || &*id == "nan" => Some((id, located_id.range.end)),
+                _ => None,
+            });
+        if let Some((id, end)) = id {
+            digits.push_str(&id);
+            let lexem = Lexem::FloatLiteral(Arc::from(digits));
+            let range = SourceRange { start, end };
+            return Ok(LocatedLexem { lexem, range });
+        }
+    }
+    if read_float_part(located_chars, located_char_index, &mut digits, &mut end) {
+        let lexem = Lexem::FloatLiteral(Arc::from(digits));
+        let range = SourceRange { start, end };
+        return Ok(LocatedLexem { lexem, range });
+    }
     let num = i64::from_str_radix(&digits, 10);
     match num {
         Ok(value) => {
@@ -273,6 +309,48 @@ fn try_read_int<'file_path>(
     }
 }
 
+/// Reads the fraction and the exponent of a number, whose integer part is already in `digits`.
+/// Returns `false` if the number has neither of them.
+fn read_float_part<'file_path>(
+    located_chars: &[LocatedChar<'file_path>],
+    located_char_index: &mut usize,
+    digits: &mut String,
+    end: &mut Position<'file_path>,
+) -> bool {
+    let char_at = |index: usize| located_chars.get(index).map(|located| located.char);
+    let mut index = *located_char_index;
+    let mut found = false;
+    if char_at(index) == Some('.') && char_at(index + 1).is_some_and(|c| c.is_ascii_digit()) {
+        index += 1;
+        while char_at(index).is_some_and(|c| c.is_ascii_digit()) {
+            index += 1;
+        }
+        found = true;
+    }
+    if matches!(char_at(index), Some('e' | 'E')) {
+        let mut exponent = index + 1;
+        if matches!(char_at(exponent), Some('+' | '-')) {
+            exponent += 1;
+        }
+        if char_at(exponent).is_some_and(|c| c.is_ascii_digit()) {
+            index = exponent;
+            while char_at(index).is_some_and(|c| c.is_ascii_digit()) {
+                index += 1;
+            }
+            found = true;
+        }
+    }
+    if !found {
+        return false;
+    }
+    for located in &located_chars[*located_char_index..index] {
+        digits.push(located.char);
+    }
+    *end = located_chars[index - 1].position;
+    *located_char_index = index;
+    true
+}
+
 fn try_read_single_line_comment<'file_path>(
     located_chars: &[LocatedChar<'file_path>],
     located_char_index: &mut usize,
@@ -339,7 +417,7 @@ fn try_read_string_literal<'file_path>(
         string_literal.push(char);
         last_char = char;
     }
-    let lexem = Lexem::StringLiteral(Rc::from(string_literal));
+    let lexem = Lexem::StringLiteral(Arc::from(string_literal));
     let range = SourceRange { start, end };
     let located_lexem: LocatedLexem<'file_path> = LocatedLexem { lexem, range };
     Ok(located_lexem)
@@ -352,7 +430,7 @@ fn read_chars<'file_path>(
     let mut located_chars = Vec::new();
     let mut line = 1;
     let mut column = 1;
-    for char in content.chars() {
+    for (byte_offset, char) in content.char_indices() {
         if char as u32 == 0xfeff {
             continue;
         }
@@ -362,6 +440,7 @@ fn read_chars<'file_path>(
                 file_path: file_path,
                 line,
                 column,
+                byte_offset,
             },
         };
         located_chars.push(located_char);
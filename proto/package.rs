@@ -1,6 +1,7 @@
 use super::{
-    compiler::ts::ast,
+    compiler::ts::{ast, parallel_map::parallel_map},
     error::ProtoError,
+    folder::ProtoFolder,
     id_generator::{IdGenerator, UniqueId},
     lexems,
     proto_scope::{
@@ -8,9 +9,17 @@ use super::{
         root_scope::RootScope,
     },
     syntax,
+    validation::{validate_enum_declaration, validate_message_declaration, MAX_FIELD_NUMBER},
 };
 use lexems::read_lexems;
-use std::{fmt::Display, io::Read, ops::Deref, path::PathBuf, rc::Rc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    io::Read,
+    ops::Deref,
+    path::PathBuf,
+    sync::Arc,
+};
 use syntax::parse_package;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -29,44 +38,100 @@ impl std::fmt::Display for ProtoVersion {
     }
 }
 
+/// Position of a declaration in its proto file,
+/// `line` and `column` start from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub byte_offset: usize,
+}
+
+impl From<lexems::Position<'_>> for Span {
+    fn from(position: lexems::Position<'_>) -> Self {
+        Span {
+            line: position.line,
+            column: position.column,
+            byte_offset: position.byte_offset,
+        }
+    }
+}
+
+/// Whether `option deprecated = true;` or `[deprecated = true]` is among the `options`.
+pub(crate) fn is_deprecated(options: &[(Arc<str>, Arc<str>)]) -> bool {
+    is_enabled(options, "deprecated")
+}
+
+/// Whether `option name = true;` or `[name = true]` is among the `options`.
+pub(crate) fn is_enabled(options: &[(Arc<str>, Arc<str>)], name: &str) -> bool {
+    options
+        .iter()
+        .any(|(key, value)| key.deref() == name && value.deref() == "true")
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct EnumEntry {
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub value: i64,
+    /// Declared with `[deprecated = true]`.
+    pub deprecated: bool,
 }
 
-impl From<(Rc<str>, i64)> for EnumEntry {
-    fn from((name, value): (Rc<str>, i64)) -> Self {
-        Self { name, value }
+impl From<(Arc<str>, i64)> for EnumEntry {
+    fn from((name, value): (Arc<str>, i64)) -> Self {
+        Self {
+            name,
+            value,
+            deprecated: false,
+        }
     }
 }
 
 impl std::fmt::Display for EnumEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{} = {}", self.name, self.value)
+        write!(f, "{} = {}", self.name, self.value)?;
+        if self.deprecated {
+            write!(f, " [deprecated = true]")?;
+        }
+        Ok(())
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct EnumDeclaration {
     pub id: usize,
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub entries: Vec<EnumEntry>,
+    /// Declared with `option deprecated = true;`.
+    pub deprecated: bool,
+    /// Declared with `option allow_alias = true;`, so members may share their values.
+    pub allow_alias: bool,
+    /// Position of the name of the enum.
+    pub span: Span,
 }
 impl UniqueId for EnumDeclaration {
-    type Args = (Rc<str>, Vec<EnumEntry>);
+    type Args = (Arc<str>, Vec<EnumEntry>);
 
     fn create_with_id(id: usize, args: Self::Args) -> Self {
         EnumDeclaration {
             id,
             name: args.0,
             entries: args.1,
+            deprecated: false,
+            allow_alias: false,
+            span: Span::default(),
         }
     }
 }
 impl std::fmt::Display for EnumDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "enum {} {{\n", self.name)?;
+        if self.deprecated {
+            writeln!(f, "  option deprecated = true;")?;
+        }
+        if self.allow_alias {
+            writeln!(f, "  option allow_alias = true;")?;
+        }
         for entry in &self.entries {
             let entry_str = format!("{};", entry);
             let lines = entry_str.lines();
@@ -82,8 +147,8 @@ impl std::fmt::Display for EnumDeclaration {
 pub(crate) enum Type {
     Enum(usize),
     Message(usize),
-    Repeated(Rc<Type>),
-    Map(Rc<Type>, Rc<Type>),
+    Repeated(Arc<Type>),
+    Map(Arc<Type>, Arc<Type>),
     Bool,     // bool
     Bytes,    // bytes
     Double,   // double
@@ -246,8 +311,8 @@ impl Clone for Type {
         match self {
             Self::Enum(enum_id) => Self::Enum(*enum_id),
             Self::Message(message_id) => Self::Message(*message_id),
-            Self::Repeated(rc_type) => Self::Repeated(Rc::clone(rc_type)),
-            Self::Map(rc_key, rc_value) => Self::Map(Rc::clone(rc_key), Rc::clone(rc_value)),
+            Self::Repeated(rc_type) => Self::Repeated(Arc::clone(rc_type)),
+            Self::Map(rc_key, rc_value) => Self::Map(Arc::clone(rc_key), Arc::clone(rc_value)),
             Self::Bool => Self::Bool,
             Self::Bytes => Self::Bytes,
             Self::Double => Self::Double,
@@ -269,7 +334,9 @@ impl Clone for Type {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum FieldTypeReference {
-    IdPath(Vec<Rc<str>>),
+    IdPath(Vec<Arc<str>>),
+    /// `.a.b.Type`, resolved from the root instead of the enclosing scopes
+    AbsoluteIdPath(Vec<Arc<str>>),
     Repeated(Box<FieldTypeReference>),
     Map(Box<FieldTypeReference>, Box<FieldTypeReference>),
     Bool,     // bool
@@ -296,6 +363,7 @@ impl FieldTypeReference {
     pub fn trivial_resolve(&self) -> Option<Type> {
         match self {
             FieldTypeReference::IdPath(_) => None,
+            FieldTypeReference::AbsoluteIdPath(_) => None,
             FieldTypeReference::Repeated(t) => {
                 t.trivial_resolve().map(|t| Type::Repeated(t.into()))
             }
@@ -343,11 +411,11 @@ impl FieldTypeReference {
     }
 }
 
-impl From<Vec<Rc<str>>> for FieldTypeReference {
-    fn from(id_path: Vec<Rc<str>>) -> Self {
+impl From<Vec<Arc<str>>> for FieldTypeReference {
+    fn from(id_path: Vec<Arc<str>>) -> Self {
         assert!(id_path.len() > 0);
         if id_path.len() == 1 {
-            let id = Rc::clone(&id_path[0]);
+            let id = Arc::clone(&id_path[0]);
             if id.deref() == "bool" {
                 return FieldTypeReference::Bool;
             }
@@ -392,6 +460,7 @@ impl std::fmt::Display for FieldTypeReference {
         use FieldTypeReference::*;
         match self {
             IdPath(path) => write!(f, "{}", path.join(".")),
+            AbsoluteIdPath(path) => write!(f, ".{}", path.join(".")),
             Repeated(field_type) => write!(f, "repeated {}", field_type),
             Map(key_type, value_type) => write!(f, "map<{}, {}>", key_type, value_type),
             Bool => write!(f, "bool"),
@@ -415,44 +484,121 @@ impl std::fmt::Display for FieldTypeReference {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct FieldDeclaration {
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub field_type_ref: FieldTypeReference,
     pub tag: i64,
-    pub attributes: Vec<(Rc<str>, Rc<str>)>,
+    pub attributes: Vec<(Arc<str>, Arc<str>)>,
+    /// Options of the `attributes` known to the compiler.
+    pub options: FieldOptions,
+    /// Declared with the `optional` label,
+    /// so the field has explicit presence.
+    pub optional: bool,
+    /// Declared with the proto2 `required` label,
+    /// so decoding fails when the field is missing.
+    pub required: bool,
+    /// Position of the type of the field.
+    pub span: Span,
 }
 
 impl FieldDeclaration {
     pub fn new(name: &str, field_type_ref: FieldTypeReference, tag: i64) -> Self {
         FieldDeclaration {
-            name: Rc::from(name),
+            name: Arc::from(name),
             field_type_ref,
             tag,
             attributes: Vec::new(),
+            options: FieldOptions::default(),
+            optional: false,
+            required: false,
+            span: Span::default(),
+        }
+    }
+}
+
+/// Options of a field the compiler understands, the other options are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct FieldOptions {
+    /// `[packed = true]` or `[packed = false]`,
+    /// `None` leaves it to the default of the proto version.
+    pub packed: Option<bool>,
+    /// `[deprecated = true]`
+    pub deprecated: bool,
+    /// `[default = ...]` as it is written in the proto file,
+    /// without the quotes of string literals.
+    pub default: Option<Arc<str>>,
+}
+
+impl From<&[(Arc<str>, Arc<str>)]> for FieldOptions {
+    fn from(attributes: &[(Arc<str>, Arc<str>)]) -> Self {
+        let packed = attributes
+            .iter()
+            .find(|(key, _)| key.deref() == "packed")
+            .map(|(_, value)| value.deref() == "true");
+        let default = attributes
+            .iter()
+            .find(|(key, _)| key.deref() == "default")
+            .map(|(_, value)| Arc::clone(value));
+        FieldOptions {
+            packed,
+            deprecated: is_deprecated(attributes),
+            default,
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Field {
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub field_type: Type,
     pub tag: i64,
-    pub attributes: Vec<(Rc<str>, Rc<str>)>,
+    pub attributes: Vec<(Arc<str>, Arc<str>)>,
+    pub optional: bool,
+    pub required: bool,
+    /// Repeated scalar encoded as a single length-delimited record,
+    /// resolved from the `packed` option and the default of the proto version.
+    pub packed: bool,
+    pub options: FieldOptions,
 }
 
 impl Field {
-    pub fn json_name(&self) -> Rc<str> {
-        for (key, value) in &self.attributes {
-            if key.deref() == "json_name" {
-                return Rc::clone(value);
-            }
-        }
-        Rc::clone(&self.name)
+    pub fn json_name(&self) -> Arc<str> {
+        self.json_name_option()
+            .unwrap_or_else(|| Arc::clone(&self.name))
+    }
+
+    /// Value of the `json_name` option of the field, if it has one.
+    pub fn json_name_option(&self) -> Option<Arc<str>> {
+        self.attribute("json_name")
+    }
+
+    /// Value of the proto2 `default` option as it is written in the proto file,
+    /// without the quotes of string literals.
+    pub fn default_value(&self) -> Option<Arc<str>> {
+        self.options.default.clone()
+    }
+
+    /// Whether decoded messages may lack the field:
+    /// `optional` fields are left unset unless they have the `default` option.
+    pub fn may_be_unset(&self) -> bool {
+        self.optional && self.options.default.is_none()
+    }
+
+    fn attribute(&self, name: &str) -> Option<Arc<str>> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key.deref() == name)
+            .map(|(_, value)| Arc::clone(value))
     }
 }
 
 impl std::fmt::Display for FieldDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.optional {
+            write!(f, "optional ")?;
+        }
+        if self.required {
+            write!(f, "required ")?;
+        }
         write!(f, "{} {} = {}", self.field_type_ref, self.name, self.tag)?;
         if !self.attributes.is_empty() {
             write!(f, " [")?;
@@ -470,13 +616,13 @@ impl std::fmt::Display for FieldDeclaration {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct OneOfGroup {
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub options: Vec<Field>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct OneOfDeclaration {
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub options: Vec<FieldDeclaration>,
 }
 
@@ -496,11 +642,57 @@ pub(crate) enum MessageEntry {
     #[allow(dead_code)]
     OneOf(OneOfGroup),
 }
+/// `reserved 2, 4 to 6;` or `reserved "foo", "bar";` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ReservedDeclaration {
+    /// Inclusive ranges of field numbers, a single number is a range of one number.
+    /// `to max` ends the range with `MAX_FIELD_NUMBER`.
+    Ranges(Vec<(i64, i64)>),
+    Names(Vec<Arc<str>>),
+}
+
+impl std::fmt::Display for ReservedDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "reserved ")?;
+        match self {
+            ReservedDeclaration::Ranges(ranges) => {
+                for (i, (start, end)) in ranges.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if start == end {
+                        write!(f, "{}", start)?;
+                    } else if *end == MAX_FIELD_NUMBER {
+                        write!(f, "{} to max", start)?;
+                    } else {
+                        write!(f, "{} to {}", start, end)?;
+                    }
+                }
+            }
+            ReservedDeclaration::Names(names) => {
+                for (i, name) in names.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "\"{}\"", name)?;
+                }
+            }
+        }
+        write!(f, ";")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum MessageDeclarationEntry {
     Field(FieldDeclaration),
     Declaration(Declaration),
     OneOf(OneOfDeclaration),
+    /// Only produced by the parser, `MessageDeclaration` keeps
+    /// reserved numbers and names in separate fields.
+    Reserved(ReservedDeclaration),
+    /// `option name = value;`, only produced by the parser,
+    /// `MessageDeclaration` keeps the options it understands in its fields.
+    Option(Arc<str>, Arc<str>),
 }
 impl std::fmt::Display for MessageDeclarationEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -509,6 +701,8 @@ impl std::fmt::Display for MessageDeclarationEntry {
             Field(field) => write!(f, "{};", field),
             Declaration(decl) => write!(f, "\n{}", decl),
             OneOf(one_of_decl) => write!(f, "\n{}", one_of_decl),
+            Reserved(reserved) => write!(f, "{}", reserved),
+            Option(name, value) => write!(f, "option {} = {};", name, value),
         }
     }
 }
@@ -522,18 +716,46 @@ impl From<Declaration> for MessageDeclarationEntry {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct MessageDeclaration {
     pub id: usize,
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub entries: Vec<MessageDeclarationEntry>,
+    /// Inclusive ranges of field numbers that must not be used by fields.
+    pub reserved_ranges: Vec<(i64, i64)>,
+    /// Names that must not be used by fields.
+    pub reserved_names: Vec<Arc<str>>,
+    /// Declared with `option deprecated = true;`.
+    pub deprecated: bool,
+    /// Position of the name of the message.
+    pub span: Span,
 }
 
 impl UniqueId for MessageDeclaration {
-    type Args = (Rc<str>, Vec<MessageDeclarationEntry>);
+    type Args = (Arc<str>, Vec<MessageDeclarationEntry>);
 
     fn create_with_id(id: usize, args: Self::Args) -> Self {
+        let mut entries = Vec::new();
+        let mut reserved_ranges = Vec::new();
+        let mut reserved_names = Vec::new();
+        let mut options = Vec::new();
+        for entry in args.1 {
+            match entry {
+                MessageDeclarationEntry::Reserved(ReservedDeclaration::Ranges(ranges)) => {
+                    reserved_ranges.extend(ranges)
+                }
+                MessageDeclarationEntry::Reserved(ReservedDeclaration::Names(names)) => {
+                    reserved_names.extend(names)
+                }
+                MessageDeclarationEntry::Option(name, value) => options.push((name, value)),
+                entry => entries.push(entry),
+            }
+        }
         MessageDeclaration {
             id,
             name: args.0,
-            entries: args.1,
+            entries,
+            reserved_ranges,
+            reserved_names,
+            deprecated: is_deprecated(&options),
+            span: Span::default(),
         }
     }
 }
@@ -541,6 +763,17 @@ impl UniqueId for MessageDeclaration {
 impl std::fmt::Display for MessageDeclaration {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "message {} {{\n", self.name)?;
+        if self.deprecated {
+            writeln!(f, "  option deprecated = true;")?;
+        }
+        if !self.reserved_ranges.is_empty() {
+            let reserved = ReservedDeclaration::Ranges(self.reserved_ranges.clone());
+            writeln!(f, "  {}", reserved)?;
+        }
+        if !self.reserved_names.is_empty() {
+            let reserved = ReservedDeclaration::Names(self.reserved_names.clone());
+            writeln!(f, "  {}", reserved)?;
+        }
         for entry in &self.entries {
             let entry_str = format!("{}", entry);
             let lines = entry_str.lines();
@@ -552,12 +785,110 @@ impl std::fmt::Display for MessageDeclaration {
     }
 }
 
+/// `rpc Name(Request) returns (stream Reply);` of a service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MethodDeclaration {
+    pub name: Arc<str>,
+    pub input_type_ref: FieldTypeReference,
+    pub output_type_ref: FieldTypeReference,
+    /// Declared with `stream` before the request type.
+    pub client_streaming: bool,
+    /// Declared with `stream` before the response type.
+    pub server_streaming: bool,
+    pub span: Span,
+}
+
+impl std::fmt::Display for MethodDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let stream = |streaming: bool| if streaming { "stream " } else { "" };
+        write!(
+            f,
+            "rpc {}({}{}) returns ({}{});",
+            self.name,
+            stream(self.client_streaming),
+            self.input_type_ref,
+            stream(self.server_streaming),
+            self.output_type_ref
+        )
+    }
+}
+
+/// `service Name { ... }` with its methods, options of the service are skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ServiceDeclaration {
+    pub name: Arc<str>,
+    pub methods: Vec<MethodDeclaration>,
+}
+
+impl std::fmt::Display for ServiceDeclaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "service {} {{", self.name)?;
+        for method in &self.methods {
+            writeln!(f, "  {}", method)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// How many messages each side of a method sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MethodKind {
+    Unary,
+    ServerStreaming,
+    ClientStreaming,
+    BidiStreaming,
+}
+
+/// Method of a service with the resolved ids of its request and response messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Method {
+    pub name: Arc<str>,
+    pub input_id: usize,
+    pub output_id: usize,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
+
+impl Method {
+    pub fn kind(&self) -> MethodKind {
+        match (self.client_streaming, self.server_streaming) {
+            (false, false) => MethodKind::Unary,
+            (false, true) => MethodKind::ServerStreaming,
+            (true, false) => MethodKind::ClientStreaming,
+            (true, true) => MethodKind::BidiStreaming,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Service {
+    pub name: Arc<str>,
+    pub methods: Vec<Method>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum Declaration {
     Enum(EnumDeclaration),
     Message(MessageDeclaration),
 }
 
+impl Declaration {
+    /// Adds `offset` to ids of the declaration and of its nested declarations.
+    fn shift_ids(&mut self, offset: usize) {
+        match self {
+            Declaration::Enum(e) => e.id += offset,
+            Declaration::Message(m) => {
+                m.id += offset;
+                for entry in m.entries.iter_mut() {
+                    if let MessageDeclarationEntry::Declaration(d) = entry {
+                        d.shift_ids(offset);
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl From<EnumDeclaration> for Declaration {
     fn from(decl: EnumDeclaration) -> Self {
         Declaration::Enum(decl)
@@ -581,19 +912,29 @@ impl std::fmt::Display for Declaration {
 
 #[derive(Debug)]
 pub(crate) struct ImportPath {
-    pub file_name: Rc<str>,
-    pub packages: Vec<Rc<str>>,
+    pub file_name: Arc<str>,
+    pub packages: Vec<Arc<str>>,
+    /// `import public`: declarations of the imported file are visible
+    /// to the files importing this one.
+    pub public: bool,
+    /// Position of the path literal in the `import` statement.
+    pub span: Span,
 }
 
 impl Display for ImportPath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}/{}", self.packages.join("/"), self.file_name)
+        for package in &self.packages {
+            write!(f, "{}/", package)?;
+        }
+        write!(f, "{}", self.file_name)
     }
 }
 
 impl PartialEq for ImportPath {
     fn eq(&self, other: &Self) -> bool {
-        self.file_name == other.file_name && self.packages == other.packages
+        self.file_name == other.file_name
+            && self.packages == other.packages
+            && self.public == other.public
     }
 }
 impl Eq for ImportPath {}
@@ -619,9 +960,15 @@ impl Ord for ImportPath {
 pub(crate) struct ProtoFile {
     pub version: ProtoVersion,
     pub declarations: Vec<Declaration>,
+    pub services: Vec<ServiceDeclaration>,
     pub imports: Vec<ImportPath>,
-    pub path: Vec<Rc<str>>,
-    pub name: Rc<str>,
+    pub path: Vec<Arc<str>>,
+    pub name: Arc<str>,
+    /// Path of the file relative to the folder it was read from,
+    /// the same as used in `import` statements.
+    pub source_path: Arc<str>,
+    /// Path the file was read from, used in error messages.
+    pub file_path: Arc<str>,
 }
 
 impl std::fmt::Display for ProtoFile {
@@ -634,7 +981,14 @@ impl std::fmt::Display for ProtoFile {
             for imprt in imports {
                 let ref packages = imprt.packages;
                 let ref file_name = imprt.file_name;
-                write!(f, "import \"{}/{}\";\n", packages.join("/"), file_name)?;
+                let modifier = if imprt.public { "public " } else { "" };
+                writeln!(
+                    f,
+                    "import {}\"{}/{}\";",
+                    modifier,
+                    packages.join("/"),
+                    file_name
+                )?;
             }
         }
 
@@ -647,45 +1001,225 @@ impl std::fmt::Display for ProtoFile {
             writeln!(f, "{}", decl)?;
         }
 
+        for service in &self.services {
+            writeln!(f)?;
+            writeln!(f, "{}", service)?;
+        }
+
         Ok(())
     }
 }
 
-pub(crate) fn read_root_scope(files: &[PathBuf]) -> Result<RootScope, ProtoError> {
+/// Reads the proto files of every folder into a single tree.
+/// Files are placed by their declared package, not by the folder they live in,
+/// so a package may be spread over several folders.
+pub(crate) fn read_root_scope(folders: &[ProtoFolder]) -> Result<RootScope, ProtoError> {
+    let files = folders
+        .iter()
+        .flat_map(|folder| folder.files.iter().map(move |file| (folder, file)))
+        .collect::<Vec<_>>();
+    let parsed_files = parallel_map(&files, |(folder, file)| {
+        let mut id_generator = IdGenerator::new();
+        read_proto_file(&mut id_generator, file, &folder.source_path(file))
+            .map(|proto_file| (proto_file, id_generator.ids_count()))
+    });
+    build_root_scope(parsed_files)
+}
+
+/// Builds the tree of files parsed in parallel.
+///
+/// Every file is parsed with its own `IdGenerator`, so its ids are shifted here,
+/// in the order of the files, to the ones a sequential parsing would give.
+/// The tree itself is not shared between threads.
+///
+/// Validation errors do not stop the resolution of names,
+/// both are reported together. Files importing each other are reported
+/// even when other files failed to parse.
+fn build_root_scope(
+    parsed_files: Vec<Result<(ProtoFile, usize), ProtoError>>,
+) -> Result<RootScope, ProtoError> {
     let builder = ScopeBuilder::new_ref();
     let mut id_generator = IdGenerator::new();
-    for file in files {
-        let proto_file = read_proto_file(&mut id_generator, file)?;
-        for imprt in proto_file
-            .imports
+    let mut errors = Vec::new();
+    let mut all_loaded = true;
+    let mut file_imports = Vec::new();
+    for parsed in parsed_files {
+        let loaded = parsed.and_then(|(mut proto_file, ids_count)| {
+            errors.extend(validate_proto_file(&proto_file));
+            file_imports.push((
+                Arc::clone(&proto_file.source_path),
+                proto_file
+                    .imports
+                    .iter()
+                    .map(|imprt| imprt.to_string())
+                    .collect::<Vec<_>>(),
+            ));
+            let offset = id_generator.reserve(ids_count);
+            for declaration in proto_file.declarations.iter_mut() {
+                declaration.shift_ids(offset);
+            }
+            load_proto_file(&builder, &mut id_generator, proto_file)
+        });
+        if let Err(e) = loaded {
+            errors.push(e);
+            all_loaded = false;
+        }
+    }
+    errors.extend(find_import_cycles(&file_imports).into_iter().map(|cycle| {
+        ProtoError::ImportCycle {
+            files: cycle.iter().map(|path| path.to_string()).collect(),
+        }
+    }));
+    // names are not resolved in files that failed to parse,
+    // so resolution errors would only repeat the parse errors
+    if !all_loaded {
+        return Err(ProtoError::from_errors(errors));
+    }
+    match builder.finish() {
+        Ok(root) if errors.is_empty() => Ok(root),
+        Ok(_) => Err(ProtoError::from_errors(errors)),
+        Err(e) => {
+            errors.push(e);
+            Err(ProtoError::from_errors(errors))
+        }
+    }
+}
+
+/// Chains of files importing each other, found by a depth-first search
+/// over the files in the order of their paths, so they are the same on every run.
+/// A chain is reported once, whichever of its files it is entered from.
+/// Imports of files that were not read, like the well-known ones, are skipped.
+fn find_import_cycles(file_imports: &[(Arc<str>, Vec<String>)]) -> Vec<Vec<Arc<str>>> {
+    let paths = file_imports
+        .iter()
+        .map(|(path, _)| (&**path, Arc::clone(path)))
+        .collect::<HashMap<_, _>>();
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (path, imports) in file_imports {
+        let mut imported = imports
             .iter()
-            .filter(|imp| is_well_known_import(imp))
-        {
-            builder.load_well_known(&mut id_generator, &imprt.file_name);
+            .filter_map(|imprt| paths.get_key_value(imprt.as_str()).map(|(key, _)| *key))
+            .collect::<Vec<_>>();
+        imported.sort_unstable();
+        edges.insert(path, imported);
+    }
+    let mut files = edges.keys().copied().collect::<Vec<_>>();
+    files.sort_unstable();
+
+    let mut search = ImportCycleSearch {
+        edges: &edges,
+        visited: HashSet::new(),
+        stack: Vec::new(),
+        cycles: Vec::new(),
+        reported: HashSet::new(),
+    };
+    for file in files {
+        search.visit(file);
+    }
+    search
+        .cycles
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .into_iter()
+                .map(|path| Arc::clone(&paths[path]))
+                .collect()
+        })
+        .collect()
+}
+
+struct ImportCycleSearch<'a> {
+    edges: &'a HashMap<&'a str, Vec<&'a str>>,
+    visited: HashSet<&'a str>,
+    /// Files of the current chain of imports.
+    stack: Vec<&'a str>,
+    cycles: Vec<Vec<&'a str>>,
+    /// Sorted files of the reported cycles.
+    reported: HashSet<Vec<&'a str>>,
+}
+
+impl<'a> ImportCycleSearch<'a> {
+    fn visit(&mut self, file: &'a str) {
+        if let Some(start) = self.stack.iter().position(|&f| f == file) {
+            let mut cycle = self.stack[start..].to_vec();
+            let mut files = cycle.clone();
+            files.sort_unstable();
+            if self.reported.insert(files) {
+                cycle.push(file);
+                self.cycles.push(cycle);
+            }
+            return;
+        }
+        if !self.visited.insert(file) {
+            return;
+        }
+        self.stack.push(file);
+        for &imported in &self.edges[file] {
+            self.visit(imported);
         }
-        builder.load(proto_file)?;
+        self.stack.pop();
+    }
+}
+
+/// Same as `read_root_scope` but takes `(file_path, content)` pairs
+/// instead of reading files from the disk.
+#[cfg(test)]
+pub(crate) fn read_root_scope_from_sources(
+    sources: &[(&str, &str)],
+) -> Result<RootScope, ProtoError> {
+    let parsed_files = parallel_map(sources, |(file_path, content)| {
+        let mut id_generator = IdGenerator::new();
+        parse_proto_file(&mut id_generator, file_path, file_path, content)
+            .map(|proto_file| (proto_file, id_generator.ids_count()))
+    });
+    build_root_scope(parsed_files)
+}
+
+fn load_proto_file(
+    builder: &impl ScopeBuilderTrait,
+    id_generator: &mut IdGenerator,
+    proto_file: ProtoFile,
+) -> Result<(), ProtoError> {
+    for imprt in proto_file
+        .imports
+        .iter()
+        .filter(|imp| is_well_known_import(imp))
+    {
+        builder.load_well_known(id_generator, &imprt.file_name);
     }
-    builder.finish()
+    builder.load(proto_file)
 }
 
 fn read_proto_file(
     id_generator: &mut IdGenerator,
     file_path: &PathBuf,
+    source_path: &str,
 ) -> Result<ProtoFile, ProtoError> {
     let content = read_file_content(file_path)?;
 
     let relative_file_path = get_relative_path(file_path);
 
-    let lexems = read_lexems(&*relative_file_path, content.as_str())?;
+    parse_proto_file(id_generator, &relative_file_path, source_path, &content)
+}
 
-    let file_name = file_path.file_name().unwrap().to_str().unwrap().to_string();
+fn parse_proto_file(
+    id_generator: &mut IdGenerator,
+    relative_file_path: &str,
+    source_path: &str,
+    content: &str,
+) -> Result<ProtoFile, ProtoError> {
+    let lexems = read_lexems(relative_file_path, content)?;
 
+    let file_name = source_path.rsplit('/').next().unwrap();
     let mut res = ProtoFile {
         version: super::package::ProtoVersion::Proto2,
         declarations: vec![],
+        services: vec![],
         imports: vec![],
         path: vec![],
         name: file_name.into(),
+        source_path: source_path.into(),
+        file_path: relative_file_path.into(),
     };
 
     parse_package(id_generator, &lexems, &mut res)?;
@@ -693,8 +1227,26 @@ fn read_proto_file(
     Ok(res)
 }
 
+/// Errors of the messages and the enums of the parsed file, like duplicate field numbers.
+fn validate_proto_file(proto_file: &ProtoFile) -> Vec<ProtoError> {
+    proto_file
+        .declarations
+        .iter()
+        .filter_map(|declaration| match declaration {
+            Declaration::Message(m) => validate_message_declaration(m, proto_file.version).err(),
+            Declaration::Enum(e) => validate_enum_declaration(e, proto_file.version).err(),
+        })
+        .collect()
+}
+
+/// Path of the file used in error messages: relative to the current directory
+/// when the file is inside of it, absolute otherwise.
 fn get_relative_path(file_path: &PathBuf) -> String {
     let cur_dir = std::env::current_dir().unwrap();
+    let file_path_canonical = file_path.canonicalize().unwrap();
+    if !file_path_canonical.starts_with(cur_dir.canonicalize().unwrap()) {
+        return file_path_canonical.to_string_lossy().to_string();
+    }
     let relative_file_path = relative_file_path(&cur_dir, file_path);
     relative_file_path
 }
@@ -743,3 +1295,163 @@ fn relative_file_path(cur_dir: &PathBuf, file_path: &PathBuf) -> String {
 
     res
 }
+
+#[cfg(test)]
+mod test_read_root_scope {
+    use super::*;
+
+    #[test]
+    fn it_gives_the_same_ids_as_a_sequential_parsing() {
+        let sources = [
+            (
+                "a.proto",
+                r#"
+                syntax = "proto3";
+                package a;
+                import "google/protobuf/timestamp.proto";
+                message A {
+                    message Inner { enum Kind { KIND_UNKNOWN = 0; } }
+                    google.protobuf.Timestamp t = 1;
+                }
+                "#,
+            ),
+            (
+                "b.proto",
+                "syntax = \"proto3\";\npackage b;\nmessage B {}\nenum E { E_UNKNOWN = 0; }\n",
+            ),
+        ];
+
+        let builder = ScopeBuilder::new_ref();
+        let mut id_generator = IdGenerator::new();
+        for (file_path, content) in sources {
+            let proto_file =
+                parse_proto_file(&mut id_generator, file_path, file_path, content).unwrap();
+            load_proto_file(&builder, &mut id_generator, proto_file).unwrap();
+        }
+        let sequential = builder.finish().unwrap();
+
+        let parallel = read_root_scope_from_sources(&sources).unwrap();
+        assert_eq!(parallel.types, sequential.types);
+        let mut ids = parallel.types.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        assert_eq!(ids, (1..=ids.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_reports_validation_and_resolution_errors_together() {
+        let sources = [
+            (
+                "a.proto",
+                "syntax = \"proto3\";\npackage a;\nmessage A { string x = 1; string y = 1; }\n",
+            ),
+            (
+                "b.proto",
+                "syntax = \"proto3\";\npackage b;\nmessage B { Missing m = 1; }\n",
+            ),
+        ];
+        let error = read_root_scope_from_sources(&sources).err().unwrap();
+        let errors = error.errors();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+    }
+
+    /// Messages of the import cycle errors of the sources, in the order they are reported.
+    fn import_cycle_errors(sources: &[(&str, &str)]) -> Vec<String> {
+        match read_root_scope_from_sources(sources) {
+            Ok(_) => Vec::new(),
+            Err(error) => error
+                .errors()
+                .into_iter()
+                .filter(|error| error.code() == "import_cycle")
+                .map(|error| error.to_string())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn it_reports_files_importing_each_other() {
+        let sources = [
+            (
+                "pkg/a.proto",
+                "syntax = \"proto3\";\npackage pkg;\nimport \"pkg/b.proto\";\nmessage A { B b = 1; }\n",
+            ),
+            (
+                "pkg/b.proto",
+                "syntax = \"proto3\";\npackage pkg;\nimport \"pkg/a.proto\";\nmessage B { A a = 1; }\n",
+            ),
+        ];
+        let error = read_root_scope_from_sources(&sources).err().unwrap();
+        assert_eq!(error.code(), "import_cycle");
+        assert_eq!(
+            error.to_string(),
+            "Proto files import each other: pkg/a.proto -> pkg/b.proto -> pkg/a.proto, move the declarations they share to another file"
+        );
+    }
+
+    #[test]
+    fn it_reports_every_chain_of_files_importing_each_other_once() {
+        let file = |imports: &[&str], message: &str| {
+            let imports = imports
+                .iter()
+                .map(|path| format!("import \"{}\";\n", path))
+                .collect::<String>();
+            format!(
+                "syntax = \"proto3\";\npackage p;\n{}message {} {{}}\n",
+                imports, message
+            )
+        };
+        let sources = [
+            ("p/a.proto", file(&["p/b.proto"], "A")),
+            ("p/b.proto", file(&["p/c.proto"], "B")),
+            (
+                "p/c.proto",
+                file(&["p/a.proto", "google/protobuf/empty.proto"], "C"),
+            ),
+            ("p/d.proto", file(&["p/a.proto", "p/e.proto"], "D")),
+            ("p/e.proto", file(&["p/d.proto"], "E")),
+            ("p/f.proto", file(&["p/a.proto"], "F")),
+        ];
+        let sources = sources
+            .iter()
+            .map(|(path, content)| (*path, content.as_str()))
+            .collect::<Vec<_>>();
+        let cycles = import_cycle_errors(&sources);
+        assert_eq!(cycles.len(), 2, "{:?}", cycles);
+        assert!(cycles[0].contains(": p/a.proto -> p/b.proto -> p/c.proto -> p/a.proto,"));
+        assert!(cycles[1].contains(": p/d.proto -> p/e.proto -> p/d.proto,"));
+
+        assert!(read_root_scope_from_sources(&sources[..2]).is_ok());
+    }
+
+    #[test]
+    fn it_reports_files_importing_themselves() {
+        let sources = [(
+            "p/a.proto",
+            "syntax = \"proto3\";\npackage p;\nimport \"p/a.proto\";\nmessage A { A a = 1; }\n",
+        )];
+        let cycles = import_cycle_errors(&sources);
+        assert_eq!(cycles.len(), 1);
+        assert!(cycles[0].contains(": p/a.proto -> p/a.proto,"));
+    }
+
+    #[test]
+    fn it_reports_files_importing_each_other_with_other_errors() {
+        let sources = [
+            (
+                "p/a.proto",
+                "syntax = \"proto3\";\npackage p;\nimport \"p/b.proto\";\nmessage A { Missing m = 1; }\n",
+            ),
+            (
+                "p/b.proto",
+                "syntax = \"proto3\";\npackage p;\nimport \"p/a.proto\";\nmessage B {}\n",
+            ),
+            ("p/c.proto", "syntax = \"proto3\";\npackage p;\nmessage {}\n"),
+        ];
+        let error = read_root_scope_from_sources(&sources).err().unwrap();
+        let codes = error.errors().iter().map(|e| e.code()).collect::<Vec<_>>();
+        assert_eq!(codes, ["syntax_error", "import_cycle"]);
+
+        let error = read_root_scope_from_sources(&sources[..2]).err().unwrap();
+        let codes = error.errors().iter().map(|e| e.code()).collect::<Vec<_>>();
+        assert_eq!(codes, ["import_cycle", "unresolved_name"]);
+    }
+}
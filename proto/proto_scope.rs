@@ -1,4 +1,4 @@
-use std::{rc::Rc, fmt::Formatter};
+use std::{sync::Arc, fmt::Formatter};
 
 use self::{
     enum_scope::EnumScope, file::FileScope, message::MessageScope, package::PackageScope,
@@ -37,10 +37,10 @@ impl ProtoScope {
     pub fn as_path_component(&self) -> PathComponent {
         match self {
             ProtoScope::Root(_) => unreachable!(),
-            ProtoScope::Package(p) => PathComponent::Package(Rc::clone(&p.name)),
-            ProtoScope::File(f) => PathComponent::File(Rc::clone(&f.name)),
-            ProtoScope::Enum(e) => PathComponent::Enum(Rc::clone(&e.name)),
-            ProtoScope::Message(m) => PathComponent::Message(Rc::clone(&m.name)),
+            ProtoScope::Package(p) => PathComponent::Package(Arc::clone(&p.name)),
+            ProtoScope::File(f) => PathComponent::File(Arc::clone(&f.name)),
+            ProtoScope::Enum(e) => PathComponent::Enum(Arc::clone(&e.name)),
+            ProtoScope::Message(m) => PathComponent::Message(Arc::clone(&m.name)),
         }
     }
     pub fn get_message_declaration(&self) -> Option<&MessageScope> {
@@ -52,13 +52,13 @@ impl ProtoScope {
             ProtoScope::Message(m) => Some(m),
         }
     }
-    pub fn name(&self) -> Rc<str> {
+    pub fn name(&self) -> Arc<str> {
         match self {
             ProtoScope::Root(_) => unreachable!(),
-            ProtoScope::Package(p) => Rc::clone(&p.name),
-            ProtoScope::File(f) => Rc::clone(&f.name),
-            ProtoScope::Enum(e) => Rc::clone(&e.name),
-            ProtoScope::Message(m) => Rc::clone(&m.name),
+            ProtoScope::Package(p) => Arc::clone(&p.name),
+            ProtoScope::File(f) => Arc::clone(&f.name),
+            ProtoScope::Enum(e) => Arc::clone(&e.name),
+            ProtoScope::Message(m) => Arc::clone(&m.name),
         }
     }
 }
@@ -94,7 +94,7 @@ impl From<MessageScope> for ProtoScope {
 }
 
 impl ChildrenScopes for ProtoScope {
-    fn children(&self) -> &[Rc<ProtoScope>] {
+    fn children(&self) -> &[Arc<ProtoScope>] {
         match self {
             ProtoScope::Root(r) => r.children(),
             ProtoScope::Package(package) => package.children(),
@@ -2,9 +2,10 @@ pub(crate) mod well_known;
 
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ops::Deref,
     rc::{Rc, Weak},
+    sync::Arc,
 };
 
 use crate::proto::{
@@ -12,8 +13,8 @@ use crate::proto::{
     id_generator::{IdGenerator, UniqueId},
     package::{
         Declaration, EnumDeclaration, Field, FieldDeclaration, FieldTypeReference, ImportPath,
-        MessageDeclaration, MessageDeclarationEntry, MessageEntry, OneOfDeclaration, OneOfGroup,
-        ProtoFile, Type,
+        MessageDeclaration, MessageDeclarationEntry, MessageEntry, Method, OneOfDeclaration,
+        OneOfGroup, ProtoFile, ProtoVersion, Service, ServiceDeclaration, Span, Type,
     },
 };
 
@@ -26,13 +27,20 @@ use super::{
 
 #[derive(Debug)]
 struct PackageData {
-    name: Rc<str>,
+    name: Arc<str>,
 }
 
 #[derive(Debug)]
 struct FileData {
-    name: Rc<str>,
+    name: Arc<str>,
+    /// Path of the file relative to its proto folder.
+    source_path: Arc<str>,
+    /// Path the file was read from, used in error messages.
+    file_path: Arc<str>,
     imports: Vec<ImportPath>,
+    services: Vec<ServiceDeclaration>,
+    /// Decides the defaults of the field options, like `packed`.
+    version: ProtoVersion,
 }
 
 #[derive(Debug)]
@@ -55,18 +63,20 @@ impl From<OneOfDeclaration> for FieldOrOneOf {
 #[derive(Debug)]
 struct MessageData {
     id: usize,
-    name: Rc<str>,
+    name: Arc<str>,
     fields: Vec<FieldOrOneOf>,
+    deprecated: bool,
 }
 
 impl UniqueId for MessageData {
-    type Args = (Rc<str>, Vec<FieldOrOneOf>);
+    type Args = (Arc<str>, Vec<FieldOrOneOf>);
 
     fn create_with_id(id: usize, args: Self::Args) -> Self {
         MessageData {
             id,
             name: args.0,
             fields: args.1,
+            deprecated: false,
         }
     }
 }
@@ -80,13 +90,13 @@ enum ScopeData {
 }
 
 impl ScopeData {
-    fn name(&self) -> Option<Rc<str>> {
+    fn name(&self) -> Option<Arc<str>> {
         match self {
             ScopeData::Root => None,
-            ScopeData::Package(p) => Some(Rc::clone(&p.name)),
-            ScopeData::File(p) => Some(Rc::clone(&p.name)),
-            ScopeData::Enum(p) => Some(Rc::clone(&p.name)),
-            ScopeData::Message(p) => Some(Rc::clone(&p.name)),
+            ScopeData::Package(p) => Some(Arc::clone(&p.name)),
+            ScopeData::File(p) => Some(Arc::clone(&p.name)),
+            ScopeData::Enum(p) => Some(Arc::clone(&p.name)),
+            ScopeData::Message(p) => Some(Arc::clone(&p.name)),
         }
     }
     fn id(&self) -> Option<usize> {
@@ -163,7 +173,7 @@ impl ScopeBuilder {
             None => None,
         }
     }
-    fn get_builder_by_absolute_path(&self, path: &[Rc<str>]) -> Option<Rc<RefCell<ScopeBuilder>>> {
+    fn get_builder_by_absolute_path(&self, path: &[Arc<str>]) -> Option<Rc<RefCell<ScopeBuilder>>> {
         if self.is_root() {
             return self.get_by_path(path);
         }
@@ -171,7 +181,7 @@ impl ScopeBuilder {
             .for_parent(|p| p.get_builder_by_absolute_path(path))
             .flatten();
     }
-    fn matches(&self, full_path: &[Rc<str>]) -> bool {
+    fn matches(&self, full_path: &[Arc<str>]) -> bool {
         if self.is_root() {
             return false;
         }
@@ -187,14 +197,14 @@ impl ScopeBuilder {
         if full_path.len() == 1 {
             return self_name == full_path[0];
         }
-        let last_name = Rc::clone(&full_path[full_path.len() - 1]);
+        let last_name = Arc::clone(&full_path[full_path.len() - 1]);
         last_name == self_name
             && self
                 .for_parent(|parent| parent.matches(&full_path[..full_path.len() - 1]))
                 .unwrap_or(false)
     }
 
-    fn get_by_path(&self, path: &[Rc<str>]) -> Option<Rc<RefCell<ScopeBuilder>>> {
+    fn get_by_path(&self, path: &[Arc<str>]) -> Option<Rc<RefCell<ScopeBuilder>>> {
         if path.is_empty() {
             return None;
         }
@@ -273,10 +283,10 @@ impl ScopeBuilder {
     fn is_message(&self) -> bool {
         self.data.is_message()
     }
-    fn name(&self) -> Option<Rc<str>> {
+    fn name(&self) -> Option<Arc<str>> {
         self.data.name()
     }
-    fn path(&self) -> Vec<Rc<str>> {
+    fn path(&self) -> Vec<Arc<str>> {
         let mut res = self.for_parent(|p| p.path()).unwrap_or(vec![]);
         match self.name() {
             Some(name) => res.push(name),
@@ -284,6 +294,24 @@ impl ScopeBuilder {
         }
         res
     }
+    /// Full protobuf name of the scope, the path without names of files: `pkg.Outer.Inner`
+    fn proto_path(&self) -> Vec<Arc<str>> {
+        let mut res = self.for_parent(|p| p.proto_path()).unwrap_or_default();
+        match &self.data {
+            ScopeData::Root | ScopeData::File(_) => {}
+            data => res.extend(data.name()),
+        }
+        res
+    }
+    fn for_file<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&ScopeBuilder) -> R,
+    {
+        if self.is_file() {
+            return Some(f(self));
+        }
+        self.for_parent(|p| p.for_file(f)).flatten()
+    }
     fn resolve_child_by_name(&self, searched_name: &str) -> Vec<Rc<RefCell<ScopeBuilder>>> {
         let mut res: Vec<Rc<RefCell<ScopeBuilder>>> = Vec::new();
         for child_ref in &self.children {
@@ -317,7 +345,7 @@ impl ScopeBuilder {
         }
     }
 
-    fn new_package(name: Rc<str>, parent: Rc<RefCell<ScopeBuilder>>) -> Self {
+    fn new_package(name: Arc<str>, parent: Rc<RefCell<ScopeBuilder>>) -> Self {
         Self {
             data: ScopeData::Package(PackageData { name }),
             children: Vec::new(),
@@ -325,12 +353,23 @@ impl ScopeBuilder {
         }
     }
     fn new_file(
-        name: Rc<str>,
+        name: Arc<str>,
+        source_path: Arc<str>,
+        file_path: Arc<str>,
         imports: Vec<ImportPath>,
+        services: Vec<ServiceDeclaration>,
+        version: ProtoVersion,
         parent: Rc<RefCell<ScopeBuilder>>,
     ) -> Self {
         Self {
-            data: ScopeData::File(FileData { name, imports }),
+            data: ScopeData::File(FileData {
+                name,
+                source_path,
+                file_path,
+                imports,
+                services,
+                version,
+            }),
             children: Vec::new(),
             parent: Some(Rc::downgrade(&parent)),
         }
@@ -338,12 +377,18 @@ impl ScopeBuilder {
 
     fn new_message(
         id: usize,
-        name: Rc<str>,
+        name: Arc<str>,
         fields: Vec<FieldOrOneOf>,
+        deprecated: bool,
         parent: Rc<RefCell<ScopeBuilder>>,
     ) -> Self {
         Self {
-            data: ScopeData::Message(MessageData { name, fields, id }),
+            data: ScopeData::Message(MessageData {
+                name,
+                fields,
+                id,
+                deprecated,
+            }),
             children: Vec::new(),
             parent: Some(Rc::downgrade(&parent)),
         }
@@ -363,6 +408,40 @@ impl ScopeBuilder {
             _ => false,
         }
     }
+
+    fn source_path(&self) -> Option<Arc<str>> {
+        match &self.data {
+            ScopeData::File(f) => Some(Arc::clone(&f.source_path)),
+            _ => self.for_parent(|p| p.source_path()).flatten(),
+        }
+    }
+
+    fn file_path(&self) -> Option<Arc<str>> {
+        match &self.data {
+            ScopeData::File(f) => Some(Arc::clone(&f.file_path)),
+            _ => self.for_parent(|p| p.file_path()).flatten(),
+        }
+    }
+
+    fn version(&self) -> Option<ProtoVersion> {
+        match &self.data {
+            ScopeData::File(f) => Some(f.version),
+            _ => self.for_parent(|p| p.version()).flatten(),
+        }
+    }
+
+    /// Scopes from this one up to its file, innermost first.
+    fn scope_trace(&self) -> Vec<String> {
+        match &self.data {
+            ScopeData::Root | ScopeData::Package(_) => vec![],
+            ScopeData::File(f) => vec![f.source_path.to_string()],
+            data => {
+                let mut res = vec![data.to_string()];
+                res.extend(self.for_parent(|p| p.scope_trace()).unwrap_or_default());
+                res
+            }
+        }
+    }
 }
 
 pub(crate) trait ScopeBuilderTrait {
@@ -372,7 +451,7 @@ pub(crate) trait ScopeBuilderTrait {
 }
 
 trait ScopeBuilderPrivate {
-    fn load_file(&self, file: ProtoFile, package_path: &[Rc<str>]) -> Result<(), ProtoError>;
+    fn load_file(&self, file: ProtoFile, package_path: &[Arc<str>]) -> Result<(), ProtoError>;
     fn load_declaration(&self, declaration: Declaration) -> Result<(), ProtoError>;
     fn load_enum(&self, enum_declaration: EnumDeclaration) -> Result<(), ProtoError>;
     fn load_message(&self, message_declaration: MessageDeclaration) -> Result<(), ProtoError>;
@@ -416,24 +495,40 @@ impl ScopeBuilderTrait for Rc<RefCell<ScopeBuilder>> {
     fn finish(self) -> Result<RootScope, ProtoError> {
         let root_builder = self.borrow();
         assert!(root_builder.is_root());
-        let mut children: Vec<Rc<ProtoScope>> = Vec::new();
-        let mut types: HashMap<usize, Vec<Rc<str>>> = Default::default();
+        let mut children: Vec<Arc<ProtoScope>> = Vec::new();
+        let mut types: HashMap<usize, Vec<Arc<str>>> = Default::default();
+        let mut errors = Vec::new();
 
         for child_ref in root_builder.children.iter() {
             let ResolveResult {
                 scope,
                 declaration_paths,
-            } = resolve(child_ref)?;
+            } = match resolve(child_ref) {
+                Ok(res) => res,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
             let name = scope.name();
             children.push(scope);
             for (id, mut path) in declaration_paths {
-                path.push(Rc::clone(&name));
+                path.push(Arc::clone(&name));
                 path.reverse();
                 types.insert(id, path);
             }
         }
+        if !errors.is_empty() {
+            return Err(unique_errors(errors));
+        }
 
-        Ok(RootScope { children, types })
+        let mut root = RootScope {
+            children,
+            types,
+            message_cycles: Vec::new(),
+        };
+        root.message_cycles = root.find_message_cycles();
+        Ok(root)
     }
 }
 
@@ -498,42 +593,66 @@ fn ensure_well_known_package(root_ref: &Rc<RefCell<ScopeBuilder>>) -> Rc<RefCell
 }
 
 struct ResolveResult {
-    scope: Rc<ProtoScope>,
-    declaration_paths: Vec<(usize, Vec<Rc<str>>)>,
+    scope: Arc<ProtoScope>,
+    declaration_paths: Vec<(usize, Vec<Arc<str>>)>,
 }
 
 fn resolve(builder_ref: &Rc<RefCell<ScopeBuilder>>) -> Result<ResolveResult, ProtoError> {
     let builder = builder_ref.borrow();
-    let mut children: Vec<Rc<ProtoScope>> = Vec::new();
-    let mut declaration_paths: Vec<(usize, Vec<Rc<str>>)> = Vec::new();
+    let mut children: Vec<Arc<ProtoScope>> = Vec::new();
+    let mut declaration_paths: Vec<(usize, Vec<Arc<str>>)> = Vec::new();
+    let mut errors = Vec::new();
     for child in &builder.children {
         let ResolveResult {
             scope,
             declaration_paths: declaration_scopes,
-        } = resolve(child)?;
+        } = match resolve(child) {
+            Ok(res) => res,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
         let name = scope.name();
         children.push(scope);
         for (id, mut path) in declaration_scopes {
-            path.push(Rc::clone(&name));
+            path.push(Arc::clone(&name));
             declaration_paths.push((id, path))
         }
     }
 
     let scope = match &builder.data {
         ScopeData::Root => unreachable!(),
-        ScopeData::Package(p) => Rc::new(ProtoScope::Package(PackageScope {
+        ScopeData::Package(p) => Arc::new(ProtoScope::Package(PackageScope {
             children,
-            name: Rc::clone(&p.name),
-        })),
-        ScopeData::File(f) => Rc::new(ProtoScope::File(FileScope {
-            children,
-            name: Rc::clone(&f.name),
+            name: Arc::clone(&p.name),
         })),
+        ScopeData::File(f) => {
+            let mut services = Vec::new();
+            for service in &f.services {
+                match resolve_service(&builder, service) {
+                    Ok(service) => services.push(service),
+                    Err(e) => errors.push(e),
+                }
+            }
+            Arc::new(ProtoScope::File(FileScope {
+                children,
+                name: Arc::clone(&f.name),
+                source_path: Arc::clone(&f.source_path),
+                package: builder
+                    .for_parent(|p| p.path())
+                    .unwrap_or_default()
+                    .join(".")
+                    .into(),
+                services,
+            }))
+        }
         ScopeData::Enum(e) => {
-            let enum_scope = Rc::new(ProtoScope::Enum(EnumScope {
+            let enum_scope = Arc::new(ProtoScope::Enum(EnumScope {
                 id: e.id,
-                name: Rc::clone(&e.name),
+                name: Arc::clone(&e.name),
                 entries: e.entries.clone(),
+                deprecated: e.deprecated,
             }));
 
             declaration_paths.push((e.id, vec![]));
@@ -541,31 +660,57 @@ fn resolve(builder_ref: &Rc<RefCell<ScopeBuilder>>) -> Result<ResolveResult, Pro
             enum_scope
         }
         ScopeData::Message(m) => {
+            let version = builder.version().unwrap_or(ProtoVersion::Proto3);
             let mut entries: Vec<MessageEntry> = vec![];
             for field in m.fields.iter() {
                 match field {
                     FieldOrOneOf::Field(f) => {
-                        let field_type = resolve_type(&builder, &f.field_type_ref)?;
+                        let field_type = match resolve_type(&builder, &f.field_type_ref, f.span)
+                        {
+                            Ok(field_type) => field_type,
+                            Err(e) => {
+                                errors.push(e);
+                                continue;
+                            }
+                        };
 
                         let entry = MessageEntry::Field(Field {
-                            name: Rc::clone(&f.name),
+                            name: Arc::clone(&f.name),
+                            packed: is_packed(&field_type, f.options.packed, version),
                             field_type: field_type,
                             tag: f.tag,
                             attributes: f.attributes.clone(),
+                            optional: f.optional,
+                            required: f.required,
+                            options: f.options.clone(),
                         });
 
                         entries.push(entry);
                     }
                     FieldOrOneOf::OneOf(one_of_decl) => {
-                        let name = Rc::clone(&one_of_decl.name);
+                        let name = Arc::clone(&one_of_decl.name);
                         let mut options = Vec::new();
                         for option in &one_of_decl.options {
-                            let field_type = resolve_type(&builder, &option.field_type_ref)?;
+                            let field_type = match resolve_type(
+                                &builder,
+                                &option.field_type_ref,
+                                option.span,
+                            ) {
+                                Ok(field_type) => field_type,
+                                Err(e) => {
+                                    errors.push(e);
+                                    continue;
+                                }
+                            };
                             options.push(Field {
-                                name: Rc::clone(&option.name),
+                                name: Arc::clone(&option.name),
                                 field_type: field_type,
                                 tag: option.tag,
                                 attributes: option.attributes.clone(),
+                                optional: false,
+                                required: false,
+                                packed: false,
+                                options: option.options.clone(),
                             });
                         }
                         let entry = MessageEntry::OneOf(OneOfGroup { name, options });
@@ -573,16 +718,20 @@ fn resolve(builder_ref: &Rc<RefCell<ScopeBuilder>>) -> Result<ResolveResult, Pro
                     }
                 }
             }
-            let message_scope = Rc::new(ProtoScope::Message(MessageScope {
+            let message_scope = Arc::new(ProtoScope::Message(MessageScope {
                 id: m.id,
-                name: Rc::clone(&m.name),
+                name: Arc::clone(&m.name),
                 children,
                 entries,
+                deprecated: m.deprecated,
             }));
             declaration_paths.push((m.id, vec![]));
             message_scope
         }
     };
+    if !errors.is_empty() {
+        return Err(ProtoError::from_errors(errors));
+    }
 
     Ok(ResolveResult {
         scope: scope,
@@ -590,33 +739,117 @@ fn resolve(builder_ref: &Rc<RefCell<ScopeBuilder>>) -> Result<ResolveResult, Pro
     })
 }
 
+/// Resolves the request and response types of every method of the `service`
+/// declared in the file `builder`, all of them have to be messages.
+fn resolve_service(
+    builder: &ScopeBuilder,
+    service: &ServiceDeclaration,
+) -> Result<Service, ProtoError> {
+    let mut methods = Vec::new();
+    let mut errors = Vec::new();
+    for method in &service.methods {
+        let mut resolve_message =
+            |type_ref: &FieldTypeReference| match resolve_type(builder, type_ref, method.span) {
+                Ok(Type::Message(id)) => Some(id),
+                Ok(_) => {
+                    errors.push(ProtoError::InvalidMethodType {
+                        file_path: builder
+                            .file_path()
+                            .map(|p| p.to_string())
+                            .unwrap_or_default(),
+                        line: method.span.line,
+                        column: method.span.column,
+                        method_name: format!("{}.{}", service.name, method.name),
+                        type_name: type_ref.to_string(),
+                    });
+                    None
+                }
+                Err(e) => {
+                    errors.push(e);
+                    None
+                }
+            };
+        let input_id = resolve_message(&method.input_type_ref);
+        let output_id = resolve_message(&method.output_type_ref);
+        if let (Some(input_id), Some(output_id)) = (input_id, output_id) {
+            methods.push(Method {
+                name: Arc::clone(&method.name),
+                input_id,
+                output_id,
+                client_streaming: method.client_streaming,
+                server_streaming: method.server_streaming,
+            });
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ProtoError::from_errors(errors));
+    }
+    Ok(Service {
+        name: Arc::clone(&service.name),
+        methods,
+    })
+}
+
+/// Combines the errors of every scope. The same error is reported once,
+/// as an unresolved import fails every lookup in its file.
+fn unique_errors(errors: Vec<ProtoError>) -> ProtoError {
+    let mut seen = std::collections::HashSet::new();
+    let unique = match ProtoError::from_errors(errors) {
+        ProtoError::Multiple(errors) => errors
+            .into_iter()
+            .filter(|e| seen.insert(e.to_string()))
+            .collect(),
+        error => vec![error],
+    };
+    ProtoError::from_errors(unique)
+}
+
 fn resolve_type(
     builder: &ScopeBuilder,
     field_type_ref: &FieldTypeReference,
+    span: Span,
 ) -> Result<Type, ProtoError> {
     let trivial = field_type_ref.trivial_resolve();
     if trivial.is_some() {
         return Ok(trivial.unwrap());
     }
     match field_type_ref {
-        FieldTypeReference::IdPath(ids) => resolve_full_path(builder, ids),
+        FieldTypeReference::IdPath(ids) => resolve_full_path(builder, ids, span),
+        FieldTypeReference::AbsoluteIdPath(ids) => resolve_absolute_path(builder, ids, span),
         FieldTypeReference::Repeated(v) => {
-            let value_type = resolve_type(builder, v)?;
-            return Ok(Type::Repeated(Rc::new(value_type)));
+            let value_type = resolve_type(builder, v, span)?;
+            return Ok(Type::Repeated(Arc::new(value_type)));
         }
         FieldTypeReference::Map(k, v) => {
-            let key_type = resolve_type(builder, k)?;
-            let value_type = resolve_type(builder, v)?;
-            return Ok(Type::Map(Rc::new(key_type), Rc::new(value_type)));
+            let key_type = resolve_type(builder, k, span)?;
+            let value_type = resolve_type(builder, v, span)?;
+            return Ok(Type::Map(Arc::new(key_type), Arc::new(value_type)));
         }
         _ => unreachable!(),
     }
 }
 
-fn resolve_full_path(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Result<Type, ProtoError> {
+fn resolve_full_path(
+    builder: &ScopeBuilder,
+    full_path: &[Arc<str>],
+    span: Span,
+) -> Result<Type, ProtoError> {
     if full_path.is_empty() {
         return Err(ProtoError::new("Cannot resolve empty full path"));
     }
+    // an unresolved import is reported by the lookup below
+    let imports = get_imports(builder).unwrap_or_default();
+    let declarations = visible_declarations(builder, &imports);
+    let mut scope = builder.proto_path();
+    loop {
+        let candidate = scope.iter().chain(full_path).cloned().collect::<Vec<_>>();
+        if let Some(t) = find_declaration(&declarations, &candidate) {
+            return Ok(t);
+        }
+        if scope.pop().is_none() {
+            break;
+        }
+    }
     let in_file_resolution = resolve_in_file(&builder, &full_path);
     if in_file_resolution.is_some() {
         return Ok(in_file_resolution.unwrap());
@@ -633,17 +866,90 @@ fn resolve_full_path(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Result<Ty
         }
     }
 
-    return Err(ProtoError::new(
-        format!(
-            "Cannot resolve {}\n  in {}",
-            &full_path[0],
-            builder.name().unwrap_or("".into()),
-        )
-        .as_str(),
-    ));
+    return Err(ProtoError::UnresolvedName {
+        file_path: builder
+            .file_path()
+            .map(|p| p.to_string())
+            .unwrap_or_default(),
+        line: span.line,
+        column: span.column,
+        name: full_path.join("."),
+        scopes: builder.scope_trace(),
+    });
+}
+
+/// Resolves `.a.b.Type`, the full name of a declaration of the file or of the imported files.
+fn resolve_absolute_path(
+    builder: &ScopeBuilder,
+    full_path: &[Arc<str>],
+    span: Span,
+) -> Result<Type, ProtoError> {
+    let imports = get_imports(builder)?;
+    let declarations = visible_declarations(builder, &imports);
+    find_declaration(&declarations, full_path).ok_or_else(|| ProtoError::UnresolvedName {
+        file_path: builder
+            .file_path()
+            .map(|p| p.to_string())
+            .unwrap_or_default(),
+        line: span.line,
+        column: span.column,
+        name: format!(".{}", full_path.join(".")),
+        scopes: builder.scope_trace(),
+    })
+}
+
+/// Full names and types of the declarations of the file of the `builder`
+/// and of the files with the absolute paths `imports`.
+fn visible_declarations(
+    builder: &ScopeBuilder,
+    imports: &[Vec<Arc<str>>],
+) -> Vec<(Vec<Arc<str>>, Option<Type>)> {
+    let mut files = builder
+        .for_file(|file| file.get_all_declaration_builders())
+        .into_iter()
+        .collect::<Vec<_>>();
+    for path in imports {
+        if let Some(file_ref) = builder.get_builder_by_absolute_path(path) {
+            files.push(file_ref.borrow().get_all_declaration_builders());
+        }
+    }
+    files
+        .into_iter()
+        .flatten()
+        .map(|declaration_ref| {
+            let declaration = declaration_ref.borrow();
+            (declaration.proto_path(), declaration.get_type())
+        })
+        .collect()
+}
+
+fn find_declaration(
+    declarations: &[(Vec<Arc<str>>, Option<Type>)],
+    full_path: &[Arc<str>],
+) -> Option<Type> {
+    declarations
+        .iter()
+        .find(|(path, _)| path.as_slice() == full_path)
+        .and_then(|(_, t)| t.clone())
+}
+
+/// Whether the repeated field is encoded as a single length-delimited record.
+/// Only repeated scalars of the varint and fixed wire types can be packed,
+/// they are packed by default in proto3 and with `[packed = true]` in proto2.
+fn is_packed(field_type: &Type, packed: Option<bool>, version: ProtoVersion) -> bool {
+    let element_type = match field_type {
+        Type::Repeated(element_type) => element_type,
+        _ => return false,
+    };
+    let packable =
+        matches!(**element_type, Type::Enum(_)) || element_type.packed_wire_type().is_some();
+    if !packable {
+        return false;
+    }
+    packed.unwrap_or(version == ProtoVersion::Proto3)
 }
 
-fn resolve_in_imported_file(file_builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Type> {
+fn resolve_in_imported_file(file_builder: &ScopeBuilder, full_path: &[Arc<str>]) -> Option<Type> {
     for declaration_builder_ref in file_builder.get_all_declaration_builders() {
         let declaration_builder = declaration_builder_ref.borrow();
         if declaration_builder.matches(&full_path) {
@@ -653,7 +959,7 @@ fn resolve_in_imported_file(file_builder: &ScopeBuilder, full_path: &[Rc<str>])
     None
 }
 
-fn get_imports(builder: &ScopeBuilder) -> Result<Vec<Vec<Rc<str>>>, ProtoError> {
+fn get_imports(builder: &ScopeBuilder) -> Result<Vec<Vec<Arc<str>>>, ProtoError> {
     if builder.is_root() {
         return Ok(vec![]);
     }
@@ -663,6 +969,32 @@ fn get_imports(builder: &ScopeBuilder) -> Result<Vec<Vec<Rc<str>>>, ProtoError>
     if !builder.is_file() {
         return builder.for_parent(get_imports).unwrap_or(Ok(vec![]));
     }
+
+    let mut res = resolve_file_imports(builder, false)?;
+
+    // `import public` of an imported file makes its imports visible here as well,
+    // regular imports of it are not
+    let mut visited = res.iter().cloned().collect::<HashSet<_>>();
+    let mut ind = 0;
+    while ind < res.len() {
+        let file_builder_ref = builder.get_builder_by_absolute_path(&res[ind]).unwrap();
+        let public_imports = resolve_file_imports(&file_builder_ref.borrow(), true)?;
+        for public_import in public_imports {
+            if visited.insert(public_import.clone()) {
+                res.push(public_import);
+            }
+        }
+        ind += 1;
+    }
+    Ok(res)
+}
+
+/// Absolute paths of the files imported by the file `builder`,
+/// only of `import public` ones when `public_only` is set.
+fn resolve_file_imports(
+    builder: &ScopeBuilder,
+    public_only: bool,
+) -> Result<Vec<Vec<Arc<str>>>, ProtoError> {
     let data = match &builder.data {
         ScopeData::File(f) => f,
         _ => unreachable!(),
@@ -670,24 +1002,62 @@ fn get_imports(builder: &ScopeBuilder) -> Result<Vec<Vec<Rc<str>>>, ProtoError>
 
     let mut res = Vec::new();
 
-    for import_decl in &data.imports {
-        match resolve_import(&builder, &import_decl.packages, &import_decl.file_name) {
+    for import_decl in data.imports.iter().filter(|i| i.public || !public_only) {
+        let source_path = import_decl.to_string();
+        let resolved = resolve_import_by_source_path(builder, &source_path)
+            .or_else(|| resolve_import(builder, &import_decl.packages, &import_decl.file_name));
+        match resolved {
             Some(imprt) => res.push(imprt),
             None => {
-                return Err(ProtoError::new(
-                    format!("Cannot resolve import {}", import_decl).as_str(),
-                ));
+                return Err(ProtoError::UnresolvedImport {
+                    file_path: data.file_path.to_string(),
+                    line: import_decl.span.line,
+                    column: import_decl.span.column,
+                    import_path: import_decl.to_string(),
+                });
             }
         }
     }
     Ok(res)
 }
 
+/// Finds the file with the `source_path` anywhere in the tree,
+/// since the import path does not have to match the package of the file.
+fn resolve_import_by_source_path(
+    builder: &ScopeBuilder,
+    source_path: &str,
+) -> Option<Vec<Arc<str>>> {
+    if !builder.is_root() {
+        return builder
+            .for_parent(|p| resolve_import_by_source_path(p, source_path))
+            .flatten();
+    }
+    find_file_by_source_path(builder, source_path)
+}
+
+fn find_file_by_source_path(builder: &ScopeBuilder, source_path: &str) -> Option<Vec<Arc<str>>> {
+    for child_ref in &builder.children {
+        let child = child_ref.borrow();
+        match &child.data {
+            ScopeData::File(f) if f.source_path.deref() == source_path => {
+                return Some(child.path())
+            }
+            ScopeData::Root | ScopeData::Package(_) => {
+                if let Some(path) = find_file_by_source_path(&child, source_path) {
+                    return Some(path);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
 fn resolve_import(
     builder: &ScopeBuilder,
-    packages: &[Rc<str>],
+    packages: &[Arc<str>],
     file_name: &str,
-) -> Option<Vec<Rc<str>>> {
+) -> Option<Vec<Arc<str>>> {
     if packages.len() <= 0 {
         let children = builder.resolve_child_by_name(file_name);
         if children.is_empty() {
@@ -717,7 +1087,7 @@ fn resolve_import(
     }
 }
 
-fn resolve_in_file(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Type> {
+fn resolve_in_file(builder: &ScopeBuilder, full_path: &[Arc<str>]) -> Option<Type> {
     let resolved = resolve_in_direct_children(builder, full_path);
     if resolved.is_some() {
         return resolved;
@@ -729,10 +1099,10 @@ fn resolve_in_file(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Type
     resolve_in_parents_until_file(&builder, full_path)
 }
 
-fn resolve_in_direct_children(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Type> {
+fn resolve_in_direct_children(builder: &ScopeBuilder, full_path: &[Arc<str>]) -> Option<Type> {
     assert!(full_path.len() > 0);
     if full_path.len() == 1 {
-        let id = Rc::clone(&full_path[0]);
+        let id = Arc::clone(&full_path[0]);
         let resolved_children = builder.resolve_child_by_name(&id);
         return resolved_children
             .first()
@@ -749,7 +1119,7 @@ fn resolve_in_direct_children(builder: &ScopeBuilder, full_path: &[Rc<str>]) ->
     None
 }
 
-fn resolve_in_itself(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Type> {
+fn resolve_in_itself(builder: &ScopeBuilder, full_path: &[Arc<str>]) -> Option<Type> {
     if full_path.is_empty() {
         return None;
     }
@@ -769,7 +1139,7 @@ fn resolve_in_itself(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Ty
     None
 }
 
-fn resolve_in_parents_until_file(builder: &ScopeBuilder, full_path: &[Rc<str>]) -> Option<Type> {
+fn resolve_in_parents_until_file(builder: &ScopeBuilder, full_path: &[Arc<str>]) -> Option<Type> {
     if builder.is_root() {
         return None;
     }
@@ -799,17 +1169,33 @@ fn resolve_in_parents_until_file(builder: &ScopeBuilder, full_path: &[Rc<str>])
 }
 
 impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
-    fn load_file(&self, file: ProtoFile, path: &[Rc<str>]) -> Result<(), ProtoError> {
+    fn load_file(&self, file: ProtoFile, path: &[Arc<str>]) -> Result<(), ProtoError> {
         if path.is_empty() {
             let present = {
                 let cell = self.borrow();
-                cell.children.iter().any(|child_ref| {
-                    let child = child_ref.borrow();
-                    child.is_file_with_name(&file.name)
-                })
+                cell.children
+                    .iter()
+                    .map(|child_ref| child_ref.borrow())
+                    .find(|child| child.is_file_with_name(&file.name))
+                    .and_then(|child| child.source_path())
             };
-            assert!(!present);
-            let file_builder = ScopeBuilder::new_file(file.name, file.imports, Rc::clone(self));
+            if let Some(present_source_path) = present {
+                return Err(ProtoError::new(&format!(
+                    "Files {} and {} declare the same package {} and have the same name",
+                    present_source_path,
+                    file.source_path,
+                    file.path.join(".")
+                )));
+            }
+            let file_builder = ScopeBuilder::new_file(
+                file.name,
+                file.source_path,
+                file.file_path,
+                file.imports,
+                file.services,
+                file.version,
+                Rc::clone(self),
+            );
             let file_builder_ref = Rc::new(RefCell::new(file_builder));
             for decl in file.declarations {
                 file_builder_ref.load_declaration(decl)?;
@@ -839,7 +1225,7 @@ impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
             }
             None => {
                 let package_builder =
-                    ScopeBuilder::new_package(Rc::clone(&path[0]), Rc::clone(self));
+                    ScopeBuilder::new_package(Arc::clone(&path[0]), Rc::clone(self));
                 let package_ref = Rc::new(RefCell::new(package_builder));
                 package_ref.load_file(file, &path[1..])?;
                 {
@@ -880,6 +1266,7 @@ impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
                     Declaration::Message(m) => sub_messages.push(m),
                 },
                 MessageDeclarationEntry::OneOf(o) => fields.push(FieldOrOneOf::OneOf(o)),
+                MessageDeclarationEntry::Reserved(_) | MessageDeclarationEntry::Option(_, _) => {}
             }
         }
 
@@ -887,6 +1274,7 @@ impl ScopeBuilderPrivate for Rc<RefCell<ScopeBuilder>> {
             message_declaration.id,
             message_declaration.name,
             fields,
+            message_declaration.deprecated,
             Rc::clone(self),
         );
         let message_builder_ref = Rc::new(RefCell::new(message_builder));
@@ -909,3 +1297,314 @@ impl Default for ScopeBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test_resolution_errors {
+    use crate::proto::package::read_root_scope_from_sources;
+
+    fn error(sources: &[(&str, &str)]) -> String {
+        read_root_scope_from_sources(sources)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn it_reports_position_of_unresolved_field_type() {
+        let sources = &[(
+            "schema/user.proto",
+            "syntax = \"proto3\";\npackage app;\n\nmessage User {\n  string name = 1;\n  Address address = 2;\n}\n",
+        )];
+        assert_eq!(
+            error(sources),
+            "schema/user.proto:6:3: Could not resolve name Address\n  in Message User\n  in schema/user.proto"
+        );
+    }
+
+    #[test]
+    fn it_reports_nested_scopes_as_context() {
+        let sources = &[(
+            "user.proto",
+            "package app;\nmessage User {\n  message Inner {\n    repeated common.Tag tags = 1;\n  }\n}\n",
+        )];
+        assert_eq!(
+            error(sources),
+            "user.proto:4:5: Could not resolve name common.Tag\n  in Message Inner\n  in Message User\n  in user.proto"
+        );
+    }
+
+    #[test]
+    fn it_reports_position_of_map_and_oneof_fields() {
+        let sources = &[(
+            "a.proto",
+            "message A {\n  map<string, B> bs = 1;\n}\n",
+        )];
+        assert!(error(sources).starts_with("a.proto:2:3: Could not resolve name B\n"));
+
+        let sources = &[(
+            "a.proto",
+            "message A {\n  oneof kind {\n    int32 x = 1;\n      C c = 2;\n  }\n}\n",
+        )];
+        assert!(error(sources).starts_with("a.proto:4:7: Could not resolve name C\n"));
+    }
+
+    #[test]
+    fn it_reports_position_of_unresolved_import() {
+        let sources = &[(
+            "a.proto",
+            "syntax = \"proto3\";\nimport   \"missing/b.proto\";\nmessage A { B b = 1; }\n",
+        )];
+        assert_eq!(
+            error(sources),
+            "a.proto:2:10: Could not resolve import \"missing/b.proto\""
+        );
+    }
+
+    #[test]
+    fn it_reports_every_unresolved_name_once() {
+        let sources = &[
+            (
+                "a.proto",
+                "import \"missing.proto\";\nmessage A {\n  B b = 1;\n  C c = 2;\n}\n",
+            ),
+            (
+                "b.proto",
+                "message D {\n  int32 x = 1;\n  E e = 2;\n}\n",
+            ),
+        ];
+        let err = read_root_scope_from_sources(sources).unwrap_err();
+        assert_eq!(
+            err.errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "a.proto:1:8: Could not resolve import \"missing.proto\"".to_string(),
+                "b.proto:3:3: Could not resolve name E\n  in Message D\n  in b.proto".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_parse_errors_of_every_file() {
+        let sources = &[
+            ("a.proto", "message A {\n  int32 a = 0;\n}\n"),
+            ("b.proto", "message B {\n  int32 b = 1;\n}\n"),
+            ("c.proto", "message C {\n  int32 c = 1 \n}\n"),
+        ];
+        let err = read_root_scope_from_sources(sources).unwrap_err();
+        let locations = err
+            .errors()
+            .iter()
+            .map(|e| e.location().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(locations, vec!["a.proto:2:13", "c.proto:3:1"]);
+    }
+}
+
+#[cfg(test)]
+mod test_services {
+    use crate::proto::{
+        package::{read_root_scope_from_sources, MethodKind},
+        proto_scope::{traits::ChildrenScopes, ProtoScope},
+    };
+
+    #[test]
+    fn it_resolves_messages_of_methods() {
+        let sources = &[
+            (
+                "chat/chat.proto",
+                "syntax = \"proto3\";\npackage chat;\nimport \"chat/message.proto\";\nservice Chat {\n  rpc Send(Message) returns (Message.Ack);\n  rpc Talk(stream Message) returns (stream Message);\n}\n",
+            ),
+            (
+                "chat/message.proto",
+                "syntax = \"proto3\";\npackage chat;\nmessage Message { message Ack {} string text = 1; }\n",
+            ),
+        ];
+        let root = read_root_scope_from_sources(sources).unwrap();
+        let files = root.children[0].children().to_vec();
+        let chat = match &*files[0] {
+            ProtoScope::File(file) => file,
+            _ => unreachable!(),
+        };
+        assert_eq!(&*chat.name, "chat.proto");
+        assert_eq!(&*chat.package, "chat");
+        let methods = &chat.services[0].methods;
+        assert_eq!(
+            root.get_full_name(methods[0].output_id).as_deref(),
+            Some("chat.Message.Ack")
+        );
+        assert_eq!(methods[0].kind(), MethodKind::Unary);
+        assert_eq!(methods[1].input_id, methods[1].output_id);
+        assert_eq!(methods[1].kind(), MethodKind::BidiStreaming);
+    }
+
+    #[test]
+    fn it_rejects_methods_of_non_message_types() {
+        let sources = &[(
+            "chat.proto",
+            "syntax = \"proto3\";\nenum Kind { KIND_UNKNOWN = 0; }\nmessage M {}\nservice Chat {\n  rpc Send(string) returns (M);\n  rpc Get(M) returns (Kind);\n}\n",
+        )];
+        assert_eq!(
+            read_root_scope_from_sources(sources)
+                .unwrap_err()
+                .to_string(),
+            concat!(
+                "chat.proto:5:3: Type string of method Chat.Send is not a message\n",
+                "chat.proto:6:3: Type Kind of method Chat.Get is not a message",
+            )
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_public_imports {
+    use crate::proto::package::read_root_scope_from_sources;
+
+    const CURRENCY: (&str, &str) = (
+        "currency.proto",
+        "syntax = \"proto3\";\npackage shop;\nenum Currency { CURRENCY_UNKNOWN = 0; }\n",
+    );
+    const INTERNAL: (&str, &str) = (
+        "internal.proto",
+        "syntax = \"proto3\";\npackage shop;\nmessage Internal {}\n",
+    );
+    const MONEY: (&str, &str) = (
+        "money.proto",
+        "syntax = \"proto3\";\npackage shop;\nimport public \"currency.proto\";\nimport \"internal.proto\";\nmessage Money { Currency currency = 1; Internal internal = 2; }\n",
+    );
+    const COMMON: (&str, &str) = (
+        "common.proto",
+        "syntax = \"proto3\";\npackage shop;\nimport public \"money.proto\";\n",
+    );
+
+    #[test]
+    fn it_follows_public_imports_transitively() {
+        let order = (
+            "order.proto",
+            "syntax = \"proto3\";\npackage shop;\nimport \"common.proto\";\nmessage Order { Money total = 1; Currency currency = 2; }\n",
+        );
+        let root =
+            read_root_scope_from_sources(&[CURRENCY, INTERNAL, MONEY, COMMON, order]).unwrap();
+        assert_eq!(root.types.len(), 4);
+    }
+
+    #[test]
+    fn it_does_not_leak_regular_imports() {
+        let order = (
+            "order.proto",
+            "syntax = \"proto3\";\npackage shop;\nimport \"common.proto\";\nmessage Order { Internal internal = 1; }\n",
+        );
+        let error = read_root_scope_from_sources(&[CURRENCY, INTERNAL, MONEY, COMMON, order])
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("order.proto:4:17: Could not resolve name Internal\n"));
+    }
+
+    #[test]
+    fn it_rejects_cyclic_public_imports() {
+        let a = (
+            "a.proto",
+            "syntax = \"proto3\";\npackage p;\nimport public \"b.proto\";\nmessage A {}\n",
+        );
+        let b = (
+            "b.proto",
+            "syntax = \"proto3\";\npackage p;\nimport public \"a.proto\";\nmessage B {}\n",
+        );
+        let c = (
+            "c.proto",
+            "syntax = \"proto3\";\npackage p;\nimport \"a.proto\";\nmessage C { A a = 1; B b = 2; }\n",
+        );
+        let error = read_root_scope_from_sources(&[a, b, c]).unwrap_err();
+        assert_eq!(error.code(), "import_cycle");
+    }
+}
+
+#[cfg(test)]
+mod test_type_references {
+    use crate::proto::{
+        error::ProtoError,
+        package::{read_root_scope_from_sources, Type},
+        proto_scope::{root_scope::RootScope, ProtoScope},
+    };
+
+    /// Full names of the types of the fields of the message named `message_name`.
+    fn field_type_names(root: &RootScope, message_name: &str) -> Vec<String> {
+        let id = root
+            .types
+            .keys()
+            .copied()
+            .find(|id| root.get_full_name(*id).as_deref() == Some(message_name))
+            .unwrap();
+        let declaration = root.get_declaration(id).unwrap();
+        let message = match &*declaration {
+            ProtoScope::Message(message) => message,
+            _ => unreachable!(),
+        };
+        message
+            .get_fields()
+            .into_iter()
+            .map(|field| match field.field_type {
+                Type::Message(id) | Type::Enum(id) => root.get_full_name(id).unwrap().to_string(),
+                ref t => panic!("expected a declaration, got {:?}", t),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_resolves_leading_dot_references_from_the_root() {
+        let sources = &[
+            (
+                "foo/bar.proto",
+                "syntax = \"proto3\";\npackage foo;\nmessage Bar { string x = 1; }\n",
+            ),
+            (
+                "app.proto",
+                "syntax = \"proto3\";\npackage app;\nimport \"foo/bar.proto\";\nmessage Outer {\n  message foo { message Bar { int32 y = 1; } }\n  .foo.Bar absolute = 1;\n  foo.Bar relative = 2;\n}\n",
+            ),
+        ];
+        let root = read_root_scope_from_sources(sources).unwrap();
+        assert_eq!(
+            field_type_names(&root, "app.Outer"),
+            ["foo.Bar", "app.Outer.foo.Bar"]
+        );
+    }
+
+    #[test]
+    fn it_resolves_qualified_references_in_the_innermost_scope() {
+        let sources = &[
+            (
+                "common/v1/money.proto",
+                "syntax = \"proto3\";\npackage common.v1;\nmessage Money { string units = 1; }\n",
+            ),
+            (
+                "mycompany/common/v1/money.proto",
+                "syntax = \"proto3\";\npackage mycompany.common.v1;\nmessage Money { int64 units = 1; }\n",
+            ),
+            (
+                "mycompany/billing/invoice.proto",
+                "syntax = \"proto3\";\npackage mycompany.billing;\nimport \"common/v1/money.proto\";\nimport \"mycompany/common/v1/money.proto\";\nmessage Invoice {\n  common.v1.Money total = 1;\n  .common.v1.Money external = 2;\n  mycompany.common.v1.Money full = 3;\n}\n",
+            ),
+        ];
+        let root = read_root_scope_from_sources(sources).unwrap();
+        assert_eq!(
+            field_type_names(&root, "mycompany.billing.Invoice"),
+            [
+                "mycompany.common.v1.Money",
+                "common.v1.Money",
+                "mycompany.common.v1.Money"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_unresolved_leading_dot_references() {
+        let sources = &[(
+            "app.proto",
+            "syntax = \"proto3\";\npackage app;\nmessage A { message B {} .B b = 1; }\n",
+        )];
+        match read_root_scope_from_sources(sources) {
+            Err(ProtoError::UnresolvedName { name, .. }) => assert_eq!(name, ".B"),
+            res => panic!("expected unresolved name, got {:?}", res.map(|_| ())),
+        }
+    }
+}
@@ -1,7 +1,8 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use crate::proto::{
     id_generator::IdGenerator,
+    package::ProtoVersion,
     proto_scope::builder::{FileData, ScopeBuilder, ScopeData},
 };
 
@@ -10,8 +11,12 @@ use super::time_type::create_time_type;
 pub(in crate::proto) fn create_file(id_gen: &mut IdGenerator) -> Rc<RefCell<ScopeBuilder>> {
     let res = ScopeBuilder {
         data: ScopeData::File(FileData {
-            name: Rc::from("duration.proto"),
+            name: Arc::from("duration.proto"),
+            source_path: Arc::from("google/protobuf/duration.proto"),
+            file_path: Arc::from("google/protobuf/duration.proto"),
             imports: Vec::new(),
+            services: Vec::new(),
+            version: ProtoVersion::Proto3,
         }),
         parent: None,
         children: vec![],
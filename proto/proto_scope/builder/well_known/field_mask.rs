@@ -1,16 +1,20 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, rc::Rc, sync::Arc};
 
 use crate::proto::{
     id_generator::IdGenerator,
-    package::{FieldDeclaration, FieldTypeReference},
+    package::{FieldDeclaration, FieldTypeReference, ProtoVersion},
     proto_scope::builder::{FileData, MessageData, ScopeBuilder, ScopeData},
 };
 
 pub(in crate::proto) fn create_file(id_gen: &mut IdGenerator) -> Rc<RefCell<ScopeBuilder>> {
     let res = ScopeBuilder {
         data: ScopeData::File(FileData {
-            name: Rc::from("field_mask.proto"),
+            name: Arc::from("field_mask.proto"),
+            source_path: Arc::from("google/protobuf/field_mask.proto"),
+            file_path: Arc::from("google/protobuf/field_mask.proto"),
             imports: Vec::new(),
+            services: Vec::new(),
+            version: ProtoVersion::Proto3,
         }),
         parent: None,
         children: vec![],
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::proto::package::EnumEntry;
 
@@ -7,8 +7,10 @@ use super::{traits::ChildrenScopes, ProtoScope};
 #[derive(Debug)]
 pub(crate) struct EnumScope {
     pub id: usize,
-    pub name: Rc<str>,
+    pub name: Arc<str>,
     pub entries: Vec<EnumEntry>,
+    /// Declared with `option deprecated = true;`.
+    pub deprecated: bool,
 }
 
 impl std::fmt::Display for EnumScope {
@@ -39,14 +41,17 @@ mod test_format {
                 EnumEntry {
                     name: "Hello".into(),
                     value: 0,
+                    deprecated: false,
                 }
                 .into(),
                 EnumEntry {
                     name: "World".into(),
                     value: 1,
+                    deprecated: false,
                 }
                 .into(),
             ],
+            deprecated: false,
         };
         let str = format!("{}", enum_scope);
         assert_eq!(
@@ -57,7 +62,7 @@ mod test_format {
 }
 
 impl ChildrenScopes for EnumScope {
-    fn children(&self) -> &[Rc<ProtoScope>] {
+    fn children(&self) -> &[Arc<ProtoScope>] {
         &[]
     }
 }
@@ -1,15 +1,22 @@
-use std::{rc::{Rc}, fmt::Write};
+use std::{fmt::Write, sync::Arc};
+
+use crate::proto::package::Service;
 
 use super::{traits::ChildrenScopes, ProtoScope};
 
 #[derive(Debug)]
 pub(crate) struct FileScope {
-    pub name: Rc<str>,
-    pub children: Vec<Rc<ProtoScope>>,
+    pub name: Arc<str>,
+    /// Path of the proto file relative to its proto folder.
+    pub source_path: Arc<str>,
+    /// Full name of the package the file declares, empty without one.
+    pub package: Arc<str>,
+    pub children: Vec<Arc<ProtoScope>>,
+    pub services: Vec<Service>,
 }
 
 impl ChildrenScopes for FileScope {
-    fn children(&self) -> &[Rc<ProtoScope>] {
+    fn children(&self) -> &[Arc<ProtoScope>] {
         &self.children
     }
 }
@@ -25,6 +32,21 @@ impl std::fmt::Display for FileScope {
                 f.write_char('\n')?;
             }
         }
+        for service in &self.services {
+            writeln!(f, "  service {}", service.name)?;
+            for method in &service.methods {
+                let stream = |streaming: bool| if streaming { "stream " } else { "" };
+                writeln!(
+                    f,
+                    "    rpc {}({}{}) returns ({}{})",
+                    method.name,
+                    stream(method.client_streaming),
+                    method.input_id,
+                    stream(method.server_streaming),
+                    method.output_id
+                )?;
+            }
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
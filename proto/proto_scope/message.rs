@@ -1,4 +1,4 @@
-use std::{fmt::Write, rc::Rc};
+use std::{fmt::Write, sync::Arc};
 
 use crate::proto::package::{MessageEntry, Field};
 
@@ -7,13 +7,15 @@ use super::{traits::ChildrenScopes, ProtoScope};
 #[derive(Debug)]
 pub(crate) struct MessageScope {
     pub id: usize,
-    pub name: Rc<str>,
-    pub children: Vec<Rc<ProtoScope>>,
+    pub name: Arc<str>,
+    pub children: Vec<Arc<ProtoScope>>,
     pub entries: Vec<MessageEntry>,
+    /// Declared with `option deprecated = true;`.
+    pub deprecated: bool,
 }
 
 impl ChildrenScopes for MessageScope {
-    fn children(&self) -> &[Rc<ProtoScope>] {
+    fn children(&self) -> &[Arc<ProtoScope>] {
         &self.children
     }
 }
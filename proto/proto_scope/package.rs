@@ -1,15 +1,15 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use super::{traits::ChildrenScopes, ProtoScope};
 
 #[derive(Debug)]
 pub(crate) struct PackageScope {
-    pub children: Vec<Rc<ProtoScope>>,
-    pub name: Rc<str>,
+    pub children: Vec<Arc<ProtoScope>>,
+    pub name: Arc<str>,
 }
 
 impl ChildrenScopes for PackageScope {
-    fn children(&self) -> &[Rc<ProtoScope>] {
+    fn children(&self) -> &[Arc<ProtoScope>] {
         &self.children
     }
 }
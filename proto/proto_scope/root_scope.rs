@@ -1,6 +1,9 @@
-use std::{collections::HashMap, rc::Rc, fmt::Write};
+use std::{collections::HashMap, fmt::Write, ops::Deref, sync::Arc};
 
-use crate::proto::protopath::ProtoPath;
+use crate::proto::{
+    package::Type,
+    protopath::{PathComponent, ProtoPath},
+};
 
 use super::{
     traits::{ChildrenScopes, ResolveName},
@@ -9,8 +12,11 @@ use super::{
 
 #[derive(Debug)]
 pub(crate) struct RootScope {
-    pub children: Vec<Rc<ProtoScope>>,
-    pub types: HashMap<usize, Vec<Rc<str>>>,
+    pub children: Vec<Arc<ProtoScope>>,
+    pub types: HashMap<usize, Vec<Arc<str>>>,
+    /// Groups of messages that reference each other through their fields,
+    /// directly or through other messages. Ids are sorted in every group.
+    pub message_cycles: Vec<Vec<usize>>,
 }
 
 impl RootScope {
@@ -30,10 +36,135 @@ impl RootScope {
         Some(res)
     }
 
-    pub fn get_declaration_name(&self, decl_id: usize) -> Option<Rc<str>> {
+    pub fn get_declaration(&self, decl_id: usize) -> Option<Arc<ProtoScope>> {
+        let str_path = &self.types.get(&decl_id)?[..];
+        let mut current = self.resolve_name(&str_path[0])?;
+        for name in &str_path[1..] {
+            current = current.resolve_name(name)?;
+        }
+        Some(current)
+    }
+
+    /// Name of the declaration with its package and parent messages: `pkg.Outer.Inner`.
+    pub fn get_full_name(&self, decl_id: usize) -> Option<Arc<str>> {
+        let path = self.get_declaration_path(decl_id)?;
+        let names = path
+            .path
+            .iter()
+            .filter_map(|component| match component {
+                PathComponent::File(_) => None,
+                component => Some(component.as_str()),
+            })
+            .collect::<Vec<_>>();
+        Some(names.join(".").into())
+    }
+
+    pub fn get_declaration_name(&self, decl_id: usize) -> Option<Arc<str>> {
         let str_path = &self.types.get(&decl_id)?;
         let last_name = &str_path[str_path.len() - 1];
-        Some(Rc::clone(last_name))
+        Some(Arc::clone(last_name))
+    }
+
+    /// Whether a field of the message `from` has the type `to`
+    /// and both of them belong to the same cycle of references.
+    pub fn is_cyclic_reference(&self, from: usize, to: usize) -> bool {
+        from != to
+            && self
+                .message_cycles
+                .iter()
+                .any(|cycle| cycle.contains(&from) && cycle.contains(&to))
+    }
+
+    /// Finds strongly connected components of the graph of messages,
+    /// where a field of a message type is an edge. Components with a single message
+    /// are skipped: a message referencing itself does not import anything.
+    pub fn find_message_cycles(&self) -> Vec<Vec<usize>> {
+        let mut ids = self.types.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        let mut edges: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &id in &ids {
+            let scope = self.get_declaration(id).unwrap();
+            if let ProtoScope::Message(message) = scope.deref() {
+                let references = edges.entry(id).or_default();
+                for field in message.get_fields() {
+                    collect_message_ids(&field.field_type, references);
+                }
+            }
+        }
+
+        let mut tarjan = Tarjan {
+            edges: &edges,
+            index: HashMap::new(),
+            low_link: HashMap::new(),
+            stack: Vec::new(),
+            components: Vec::new(),
+        };
+        for &id in &ids {
+            if edges.contains_key(&id) && !tarjan.index.contains_key(&id) {
+                tarjan.visit(id);
+            }
+        }
+        let mut cycles = tarjan
+            .components
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|mut component| {
+                component.sort_unstable();
+                component
+            })
+            .collect::<Vec<_>>();
+        cycles.sort();
+        cycles
+    }
+}
+
+fn collect_message_ids(field_type: &Type, res: &mut Vec<usize>) {
+    match field_type {
+        Type::Message(id) => res.push(*id),
+        Type::Repeated(t) => collect_message_ids(t, res),
+        Type::Map(k, v) => {
+            collect_message_ids(k, res);
+            collect_message_ids(v, res);
+        }
+        _ => {}
+    }
+}
+
+/// State of Tarjan's strongly connected components algorithm.
+struct Tarjan<'edges> {
+    edges: &'edges HashMap<usize, Vec<usize>>,
+    index: HashMap<usize, usize>,
+    low_link: HashMap<usize, usize>,
+    stack: Vec<usize>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, id: usize) {
+        let index = self.index.len();
+        self.index.insert(id, index);
+        self.low_link.insert(id, index);
+        self.stack.push(id);
+        for &next in self.edges.get(&id).into_iter().flatten() {
+            if !self.index.contains_key(&next) {
+                self.visit(next);
+                let low_link = self.low_link[&id].min(self.low_link[&next]);
+                self.low_link.insert(id, low_link);
+            } else if self.stack.contains(&next) {
+                let low_link = self.low_link[&id].min(self.index[&next]);
+                self.low_link.insert(id, low_link);
+            }
+        }
+        if self.low_link[&id] == self.index[&id] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                component.push(member);
+                if member == id {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
     }
 }
 
@@ -42,12 +173,13 @@ impl Default for RootScope {
         Self {
             children: Vec::new(),
             types: Default::default(),
+            message_cycles: Vec::new(),
         }
     }
 }
 
 impl ChildrenScopes for RootScope {
-    fn children(&self) -> &[Rc<ProtoScope>] {
+    fn children(&self) -> &[Arc<ProtoScope>] {
         &self.children
     }
 }
@@ -66,3 +198,50 @@ impl std::fmt::Display for RootScope {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test_message_cycles {
+    use crate::proto::package::read_root_scope_from_sources;
+
+    #[test]
+    fn it_finds_messages_referencing_each_other() {
+        let root = read_root_scope_from_sources(&[(
+            "a.proto",
+            r#"
+            syntax = "proto3";
+            message A { B b = 1; }
+            message B { map<string, C> c = 1; D d = 2; }
+            message C { repeated A a = 1; }
+            message D { D next = 1; }
+            message E { F f = 1; }
+            message F { E e = 1; }
+            "#,
+        )])
+        .unwrap();
+        let names = root
+            .message_cycles
+            .iter()
+            .map(|cycle| {
+                cycle
+                    .iter()
+                    .map(|&id| root.get_full_name(id).unwrap().to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, [vec!["A", "B", "C"], vec!["E", "F"]]);
+
+        let id = |name: &str| {
+            *root
+                .types
+                .iter()
+                .find(|(_, path)| path.last().map(|n| &**n) == Some(name))
+                .unwrap()
+                .0
+        };
+        assert!(root.is_cyclic_reference(id("A"), id("B")));
+        assert!(root.is_cyclic_reference(id("C"), id("A")));
+        assert!(!root.is_cyclic_reference(id("B"), id("D")));
+        assert!(!root.is_cyclic_reference(id("D"), id("D")));
+        assert!(!root.is_cyclic_reference(id("A"), id("E")));
+    }
+}
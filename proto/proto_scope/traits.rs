@@ -1,12 +1,12 @@
 use std::{
     ops::Deref,
-    rc::{Rc, Weak},
+    sync::{Arc, Weak},
 };
 
 use super::ProtoScope;
 
 pub(in crate::proto) trait ChildrenScopes {
-    fn children(&self) -> &[Rc<ProtoScope>];
+    fn children(&self) -> &[Arc<ProtoScope>];
 }
 
 pub(in crate::proto) trait SetParent {
@@ -14,22 +14,22 @@ pub(in crate::proto) trait SetParent {
 }
 
 pub(in crate::proto) trait ParentScope {
-    fn parent(&self) -> Option<Rc<ProtoScope>>;
+    fn parent(&self) -> Option<Arc<ProtoScope>>;
 }
 
 pub(in crate::proto) trait RegisterDeclaration {
-    fn register_declaration(&mut self, scope: Rc<ProtoScope>);
+    fn register_declaration(&mut self, scope: Arc<ProtoScope>);
 }
 
 pub(in crate::proto) trait ResolveName {
-    fn resolve_name(&self, name: &str) -> Option<Rc<ProtoScope>>;
+    fn resolve_name(&self, name: &str) -> Option<Arc<ProtoScope>>;
 }
 
 impl<T: ChildrenScopes> ResolveName for T {
-    fn resolve_name(&self, name: &str) -> Option<Rc<ProtoScope>> {
+    fn resolve_name(&self, name: &str) -> Option<Arc<ProtoScope>> {
         for child in self.children().iter() {
             if child.name().deref() == name {
-                return Some(Rc::clone(child));
+                return Some(Arc::clone(child));
             }
         }
         None
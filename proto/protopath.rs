@@ -1,19 +1,19 @@
-use std::{ops::Index, rc::Rc};
+use std::{ops::Index, sync::Arc};
 
 #[derive(Debug, Clone)]
 pub(crate) enum PathComponent {
-    Package(Rc<str>),
-    File(Rc<str>),
-    Message(Rc<str>),
-    Enum(Rc<str>),
+    Package(Arc<str>),
+    File(Arc<str>),
+    Message(Arc<str>),
+    Enum(Arc<str>),
 }
 impl PathComponent {
-    pub fn as_str(&self) -> Rc<str> {
+    pub fn as_str(&self) -> Arc<str> {
         match self {
-            PathComponent::Package(s) => Rc::clone(&s),
-            PathComponent::File(s) => Rc::clone(&s),
-            PathComponent::Message(s) => Rc::clone(&s),
-            PathComponent::Enum(s) => Rc::clone(&s),
+            PathComponent::Package(s) => Arc::clone(s),
+            PathComponent::File(s) => Arc::clone(s),
+            PathComponent::Message(s) => Arc::clone(s),
+            PathComponent::Enum(s) => Arc::clone(s),
         }
     }
 }
@@ -49,19 +49,69 @@ impl ProtoPath {
     }
 }
 
+impl PathComponent {
+    /// Separator written between this component and the one nested into it.
+    fn separator(&self) -> &'static str {
+        match self {
+            PathComponent::Package(_) => "/",
+            PathComponent::File(_) => "::",
+            PathComponent::Message(_) | PathComponent::Enum(_) => ".",
+        }
+    }
+}
+
 impl std::fmt::Display for ProtoPath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.is_empty() {
-            return Ok(());
-        }
-        for (prev, cur) in self.path.iter().zip(self.path[1..].iter()) {
-            match (prev, cur) {
-                (PathComponent::Package(prev), _) => write!(f, "{}/", prev)?,
-                (PathComponent::File(prev), _) => write!(f, "{}::", prev)?,
-                (PathComponent::Enum(_), _) => unreachable!(),
-                (PathComponent::Message(prev), _) => write!(f, "{}.", prev)?,
+        let mut prev: Option<&PathComponent> = None;
+        for component in self.path.iter() {
+            if let Some(prev) = prev {
+                f.write_str(prev.separator())?;
             }
+            f.write_str(&component.as_str())?;
+            prev = Some(component);
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test_proto_path {
+    use super::*;
+
+    fn path(components: Vec<PathComponent>) -> ProtoPath {
+        ProtoPath { path: components }
+    }
+
+    #[test]
+    fn it_writes_every_component() {
+        let message = path(vec![
+            PathComponent::Package("common".into()),
+            PathComponent::File("types.proto".into()),
+            PathComponent::Message("User".into()),
+            PathComponent::Message("Address".into()),
+        ]);
+        assert_eq!(message.to_string(), "common/types.proto::User.Address");
+
+        let nested_enum = path(vec![
+            PathComponent::Package("common".into()),
+            PathComponent::File("types.proto".into()),
+            PathComponent::Message("User".into()),
+            PathComponent::Enum("Role".into()),
+        ]);
+        assert_eq!(nested_enum.to_string(), "common/types.proto::User.Role");
+
+        let file = path(vec![PathComponent::File("types.proto".into())]);
+        assert_eq!(file.to_string(), "types.proto");
+    }
+
+    #[test]
+    fn it_does_not_panic_on_components_nested_into_enums() {
+        let value = path(vec![
+            PathComponent::File("types.proto".into()),
+            PathComponent::Enum("Role".into()),
+            PathComponent::Message("ADMIN".into()),
+        ]);
+        assert_eq!(value.to_string(), "types.proto::Role.ADMIN");
+        assert_eq!(ProtoPath::new().to_string(), "");
+    }
+}
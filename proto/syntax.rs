@@ -1,4 +1,4 @@
-use std::{ops::Deref, rc::Rc};
+use std::{ops::Deref, sync::Arc};
 
 use crate::proto::package::FieldDeclaration;
 
@@ -7,9 +7,12 @@ use super::{
     id_generator::IdGenerator,
     lexems::{Lexem, LocatedLexem},
     package::{
-        Declaration, EnumDeclaration, EnumEntry, FieldTypeReference, ImportPath,
-        MessageDeclaration, MessageDeclarationEntry, OneOfDeclaration, ProtoFile,
+        is_deprecated, is_enabled, Declaration, EnumDeclaration, EnumEntry, FieldOptions,
+        FieldTypeReference, ImportPath, MessageDeclaration, MessageDeclarationEntry,
+        MethodDeclaration, OneOfDeclaration, ProtoFile, ReservedDeclaration, ServiceDeclaration,
+        Span,
     },
+    validation::{is_valid_field_number, MAX_FIELD_NUMBER},
 };
 
 #[derive(Debug, Clone)]
@@ -21,6 +24,8 @@ enum Task {
     ParsePackageStatement,
     /// Parses enum declaration and pushes to stack
     ParseEnumDeclaration,
+    /// Parses service declaration and pushes it to the services of the file
+    ParseServiceDeclaration,
     ParseEnumEntries,
     ParseEnumEntry,
     WrapRepeated,
@@ -33,14 +38,24 @@ enum Task {
     /// And pushes it to the package declarations
     AppendDeclarationToPackage,
     ParseFieldAttribute,
-    PushFieldDeclaration,
+    /// Takes attributes, tag, name and type from the stack
+    /// and pushes the field declaration to the message entries
+    PushFieldDeclaration {
+        optional: bool,
+        required: bool,
+    },
     PushFieldAttribute,
     ParseMessageEntries,
     ParseMessageEntry,
     ParseOptionalAttributes,
     ParseInt64,
+    /// Checks the field number on top of the stack,
+    /// the name of the field is right below it
+    ValidateFieldNumber,
     ParseFieldType,
-    ParseStringLiteral,
+    /// Parses the value of a field option: a string, a number or an identifier,
+    /// and pushes its text to the stack
+    ParseConstant,
     WrapMessageEntry,
     PushMessageEntry,
     PushMessageStatement,
@@ -49,33 +64,43 @@ enum Task {
     ExpectKeyTypeOnStack,
     /// Id -> Type
     WrapFieldType,
+    /// Id -> Type, for a path starting with a dot
+    WrapAbsoluteFieldType,
     /// [FieldType, FieldType] => Map<FieldType, FieldType>
     WrapMapType,
     /// Input: Vec<MessageEntries> String
     /// Output: OneOfDeclaration
     PushOneOf,
+    /// Parses `reserved` numbers or names
+    /// and pushes them to the message entries
+    ParseReservedStatement,
     /// Parses identifier and places it into stack
     ParseId,
+    /// Pushes the position of the current lexem to the stack
+    PushSpan,
 }
 use Task::*;
 
 #[derive(Debug, Clone)]
 enum StackItem {
-    String(Rc<str>),
-    StringList(Vec<Rc<str>>),
+    String(Arc<str>),
+    StringList(Vec<Arc<str>>),
     EnumEntriesList(Vec<EnumEntry>),
     MessageEntriesList(Vec<MessageDeclarationEntry>),
     MessageEntry(MessageDeclarationEntry),
     FieldType(FieldTypeReference),
     Int64(i64),
     Message(MessageDeclaration),
-    OptionalAttributes(Option<Vec<(Rc<str>, Rc<str>)>>),
+    OptionalAttributes(Option<Vec<(Arc<str>, Arc<str>)>>),
+    /// `option name = value;` statements of an enum
+    Options(Vec<(Arc<str>, Arc<str>)>),
     Enum(EnumDeclaration),
     OneOf(OneOfDeclaration),
+    Span(Span),
 }
 
-impl From<Rc<str>> for StackItem {
-    fn from(s: Rc<str>) -> Self {
+impl From<Arc<str>> for StackItem {
+    fn from(s: Arc<str>) -> Self {
         StackItem::String(s)
     }
 }
@@ -159,6 +184,10 @@ pub(super) fn parse_package(
                         tasks.push(ParseEnumDeclaration);
                         continue;
                     }
+                    Lexem::Id(id) if id.deref() == "service" => {
+                        tasks.push(ParseServiceDeclaration);
+                        continue;
+                    }
                     Lexem::Id(id) => {
                         return Err(syntax_error(
                             format!("Unexpected identifier: {}", id),
@@ -254,20 +283,37 @@ pub(super) fn parse_package(
                     3,
                     "Not enough lexems for import statement",
                 )?;
+                // `import public "path";`
+                let public = matches!(
+                    &located_lexems[ind + 1].lexem,
+                    Lexem::Id(id) if id.deref() == "public"
+                );
+                let path_ind = if public { ind + 2 } else { ind + 1 };
+                assert_enough_length(
+                    located_lexems,
+                    path_ind,
+                    2,
+                    "Not enough lexems for import statement",
+                )?;
                 let import = &located_lexems[ind].lexem;
-                let str = &located_lexems[ind + 1].lexem;
-                let semi_colon = &located_lexems[ind + 2].lexem;
+                let str = &located_lexems[path_ind].lexem;
+                let semi_colon = &located_lexems[path_ind + 1].lexem;
                 match (import, str, semi_colon) {
                     (Lexem::Id(id), Lexem::StringLiteral(s), Lexem::SemiColon)
                         if id.deref().eq("import") =>
                     {
-                        ind += 3;
-                        let imports_components: ImportPath = parse_import_path(s);
+                        let mut imports_components: ImportPath = parse_import_path(s);
+                        imports_components.span = located_lexems[path_ind].range.start.into();
+                        imports_components.public = public;
+                        ind = path_ind + 2;
                         res.imports.push(imports_components);
                         continue;
                     }
                     (Lexem::Id(_), Lexem::StringLiteral(_), _) => {
-                        return Err(syntax_error("expected semicolon", &located_lexems[ind + 2]))
+                        return Err(syntax_error(
+                            "expected semicolon",
+                            &located_lexems[path_ind + 1],
+                        ))
                     }
                     _ => {
                         return Err(syntax_error(
@@ -291,6 +337,27 @@ pub(super) fn parse_package(
                     }
                 }
             }
+            ValidateFieldNumber => {
+                let number = match stack.last() {
+                    Some(StackItem::Int64(number)) => *number,
+                    _ => unreachable!(),
+                };
+                if is_valid_field_number(number) {
+                    continue;
+                }
+                let field_name = match stack.get(stack.len() - 2) {
+                    Some(StackItem::String(name)) => name.to_string(),
+                    _ => unreachable!(),
+                };
+                let start = &located_lexems[ind - 1].range.start;
+                return Err(ProtoError::InvalidFieldNumber {
+                    file_path: start.file_path.to_string(),
+                    line: start.line,
+                    column: start.column,
+                    field_name,
+                    number,
+                });
+            }
             ParseOptionalAttributes => {
                 let located_lexem = &located_lexems[ind];
                 let lexem = &located_lexem.lexem;
@@ -314,6 +381,9 @@ pub(super) fn parse_package(
                     ind += 1;
                     continue;
                 }
+                if let Lexem::Comma = lexem {
+                    ind += 1;
+                }
                 tasks.push(ParseFieldAttributes);
                 tasks.push(ParseFieldAttribute);
 
@@ -321,7 +391,7 @@ pub(super) fn parse_package(
             }
             ParseFieldAttribute => {
                 tasks.push(PushFieldAttribute);
-                tasks.push(ParseStringLiteral);
+                tasks.push(ParseConstant);
                 tasks.push(ExpectLexem(Lexem::Equal));
                 tasks.push(ParseId);
                 continue;
@@ -350,7 +420,7 @@ pub(super) fn parse_package(
                 stack.push(StackItem::OptionalAttributes(Some(optional_list)));
                 continue;
             }
-            PushFieldDeclaration => {
+            PushFieldDeclaration { optional, required } => {
                 let attributes = match stack.pop() {
                     Some(StackItem::OptionalAttributes(optional_attributes)) => optional_attributes,
                     _ => unreachable!(),
@@ -368,11 +438,19 @@ pub(super) fn parse_package(
                     Some(StackItem::FieldType(field_type)) => field_type,
                     _ => unreachable!(),
                 };
+                let span = match stack.pop() {
+                    Some(StackItem::Span(span)) => span,
+                    _ => unreachable!(),
+                };
                 let field_declaration = FieldDeclaration {
                     name,
                     tag,
                     field_type_ref: field_type,
+                    options: FieldOptions::from(attributes.as_slice()),
                     attributes,
+                    optional,
+                    required,
+                    span,
                 };
                 let mut message_entries = match stack.pop() {
                     Some(StackItem::MessageEntriesList(list)) => list,
@@ -407,7 +485,7 @@ pub(super) fn parse_package(
                     let id = &id_loc_lexem.lexem;
                     match id {
                         Lexem::Id(id) => {
-                            res.path.push(Rc::clone(id));
+                            res.path.push(Arc::clone(id));
                         }
                         _ => {
                             return Err(syntax_error("Expected identifier", id_loc_lexem));
@@ -441,8 +519,9 @@ pub(super) fn parse_package(
                 ind += 1;
                 let name_loc_lexem = &located_lexems[ind];
                 let name = &name_loc_lexem.lexem;
+                stack.push(StackItem::Span(name_loc_lexem.range.start.into()));
                 match name {
-                    Lexem::Id(id) => stack.push(Rc::clone(id).into()),
+                    Lexem::Id(id) => stack.push(Arc::clone(id).into()),
                     _ => return Err(syntax_error("Expacted enum name", name_loc_lexem)),
                 }
                 ind += 1;
@@ -455,6 +534,7 @@ pub(super) fn parse_package(
                     }
                 }
                 ind += 1;
+                stack.push(StackItem::Options(Vec::new()));
                 stack.push(StackItem::EnumEntriesList(Vec::new()));
                 tasks.push(ParseEnumEntries);
                 continue;
@@ -466,11 +546,21 @@ pub(super) fn parse_package(
                     Lexem::CloseCurly => {
                         ind += 1;
                         let list_item = stack.pop().unwrap();
+                        let options = match stack.pop() {
+                            Some(StackItem::Options(options)) => options,
+                            _ => unreachable!(),
+                        };
                         let enum_name_item = stack.pop().unwrap();
                         match (list_item, enum_name_item) {
                             (StackItem::EnumEntriesList(entries), StackItem::String(name)) => {
-                                let enum_declaration: EnumDeclaration =
+                                let mut enum_declaration: EnumDeclaration =
                                     id_gen.create((name, entries));
+                                enum_declaration.deprecated = is_deprecated(&options);
+                                enum_declaration.allow_alias = is_enabled(&options, "allow_alias");
+                                enum_declaration.span = match stack.pop() {
+                                    Some(StackItem::Span(span)) => span,
+                                    _ => unreachable!(),
+                                };
                                 stack.push(enum_declaration.into());
                             }
                             (a, b) => {
@@ -480,6 +570,18 @@ pub(super) fn parse_package(
                             }
                         }
                     }
+                    Lexem::Id(id) if id.deref() == "option" => {
+                        let option = parse_option_statement(located_lexems, &mut ind)?;
+                        let entries = stack.pop().unwrap();
+                        if let (Some(option), Some(StackItem::Options(options))) =
+                            (option, stack.last_mut())
+                        {
+                            options.push(option);
+                        }
+                        stack.push(entries);
+                        tasks.push(ParseEnumEntries);
+                        continue;
+                    }
                     Lexem::Id(_) => {
                         tasks.push(ParseEnumEntries);
                         tasks.push(ParseEnumEntry);
@@ -499,6 +601,10 @@ pub(super) fn parse_package(
                 ind += 1;
                 let value_loc = &located_lexems[ind];
                 ind += 1;
+                let options = match located_lexems[ind].lexem {
+                    Lexem::OpenBracket => parse_option_list(located_lexems, &mut ind)?,
+                    _ => Vec::new(),
+                };
                 let semi_loc = &located_lexems[ind];
                 ind += 1;
                 match (
@@ -512,8 +618,9 @@ pub(super) fn parse_package(
                         match entries {
                             StackItem::EnumEntriesList(mut list) => {
                                 list.push(super::package::EnumEntry {
-                                    name: Rc::clone(id),
+                                    name: Arc::clone(id),
                                     value: *value,
+                                    deprecated: is_deprecated(&options),
                                 });
                                 stack.push(StackItem::EnumEntriesList(list));
                             }
@@ -536,6 +643,7 @@ pub(super) fn parse_package(
                 tasks.push(Push(StackItem::MessageEntriesList(Vec::new())));
                 tasks.push(ExpectLexem(Lexem::OpenCurly));
                 tasks.push(ParseId);
+                tasks.push(PushSpan);
                 tasks.push(ExpectLexem(Lexem::Id("message".into())));
                 continue;
             }
@@ -543,6 +651,10 @@ pub(super) fn parse_package(
                 stack.push(stack_item);
                 continue;
             }
+            PushSpan => {
+                stack.push(StackItem::Span(located_lexems[ind].range.start.into()));
+                continue;
+            }
             PushMessageStatement => {
                 let entries = match stack.pop() {
                     Some(StackItem::MessageEntriesList(entries)) => entries,
@@ -552,10 +664,14 @@ pub(super) fn parse_package(
                     Some(StackItem::String(name)) => name,
                     _ => unreachable!(),
                 };
-                let message_declaration: MessageDeclaration = id_gen.create((
+                let mut message_declaration: MessageDeclaration = id_gen.create((
                     message_name,
                     entries
                 ));
+                message_declaration.span = match stack.pop() {
+                    Some(StackItem::Span(span)) => span,
+                    _ => unreachable!(),
+                };
                 stack.push(message_declaration.into());
                 continue;
             }
@@ -594,7 +710,7 @@ pub(super) fn parse_package(
                 let loc_separator = &located_lexems[ind];
                 let separator = &loc_separator.lexem;
                 match separator {
-                    Lexem::Id(_) => {
+                    Lexem::Id(_) | Lexem::Dot => {
                         tasks.push(ParseMessageEntries);
                         tasks.push(ParseMessageEntry);
                         continue;
@@ -624,6 +740,22 @@ pub(super) fn parse_package(
                         tasks.push(ParseEnumDeclaration);
                         continue;
                     }
+                    Lexem::Id(id) if is_reserved_statement(&located_lexems[ind..]) => {
+                        tasks.push(ParseReservedStatement);
+                        continue;
+                    }
+                    Lexem::Id(id) if id.deref() == "option" => {
+                        let option = parse_option_statement(located_lexems, &mut ind)?;
+                        let mut entries = match stack.pop() {
+                            Some(StackItem::MessageEntriesList(entries)) => entries,
+                            _ => unreachable!(),
+                        };
+                        if let Some((name, value)) = option {
+                            entries.push(MessageDeclarationEntry::Option(name, value));
+                        }
+                        stack.push(StackItem::MessageEntriesList(entries));
+                        continue;
+                    }
                     Lexem::Id(id) if id.deref() == "oneof" => {
                         tasks.push(PushMessageEntry);
                         tasks.push(WrapMessageEntry);
@@ -640,7 +772,8 @@ pub(super) fn parse_package(
                         print_state(stack, tasks, task, &located_lexems[ind..]);
                         todo!("Cannot handle start message entry {:?}", start)
                     }
-                    Lexem::Id(_) => {
+                    // `.a.B field = 1;`
+                    Lexem::Id(_) | Lexem::Dot => {
                         tasks.push(ParseFieldDeclaration);
                         continue;
                     }
@@ -686,6 +819,12 @@ pub(super) fn parse_package(
             ParseFieldType => {
                 let start_loc = &located_lexems[ind];
                 let start = &start_loc.lexem;
+                if let Lexem::Dot = start {
+                    ind += 1;
+                    tasks.push(WrapAbsoluteFieldType);
+                    tasks.push(ParseIdPath);
+                    continue;
+                }
                 if let Lexem::Id(id) = start {
                     if id.deref() == "repeated" {
                         tasks.push(WrapRepeated);
@@ -718,6 +857,14 @@ pub(super) fn parse_package(
                 stack.push(field_type.into());
                 continue;
             }
+            WrapAbsoluteFieldType => {
+                let field_type = match stack.pop() {
+                    Some(StackItem::StringList(ids)) => FieldTypeReference::AbsoluteIdPath(ids),
+                    _ => unreachable!(),
+                };
+                stack.push(field_type.into());
+                continue;
+            }
             ParseIdPath => {
                 let mut id_path = Vec::new();
                 loop {
@@ -761,9 +908,41 @@ pub(super) fn parse_package(
                 }
             }
             ParseFieldDeclaration => {
-                tasks.push(PushFieldDeclaration);
+                let optional = is_label(&located_lexems[ind..], "optional");
+                let required = is_label(&located_lexems[ind..], "required");
+                if optional || required {
+                    ind += 1;
+                    if let Lexem::Id(id) = &located_lexems[ind].lexem {
+                        if id.deref() == "repeated" || id.deref() == "map" {
+                            return Err(syntax_error(
+                                format!(
+                                    "Expected singular field type after {}",
+                                    if optional { "optional" } else { "required" }
+                                ),
+                                &located_lexems[ind],
+                            ));
+                        }
+                    }
+                }
+                let group_ind = match &located_lexems[ind].lexem {
+                    Lexem::Id(id) if id.deref() == "repeated" => ind + 1,
+                    _ => ind,
+                };
+                if is_group(&located_lexems[group_ind..]) {
+                    let name = &located_lexems[group_ind + 1];
+                    let start = &name.range.start;
+                    return Err(ProtoError::UnsupportedGroup {
+                        file_path: start.file_path.to_string(),
+                        line: start.line,
+                        column: start.column,
+                        field_name: name.lexem.to_string(),
+                    });
+                }
+                stack.push(StackItem::Span(located_lexems[ind].range.start.into()));
+                tasks.push(PushFieldDeclaration { optional, required });
                 tasks.push(ExpectLexem(Lexem::SemiColon));
                 tasks.push(ParseOptionalAttributes);
+                tasks.push(ValidateFieldNumber);
                 tasks.push(ParseInt64);
                 tasks.push(ExpectLexem(Lexem::Equal));
                 tasks.push(ParseId);
@@ -788,29 +967,104 @@ pub(super) fn parse_package(
                     loc_lexem,
                 ));
             }
-            ParseId => {
-                assert_enough_length(located_lexems, ind, 1, "Expected identifier")?;
-                let loc_lexem = &located_lexems[ind];
-                let lexem = &loc_lexem.lexem;
-                match lexem {
-                    Lexem::Id(found_id) => {
-                        ind += 1;
-                        stack.push(found_id.clone().into());
-                        continue;
+            ParseServiceDeclaration => {
+                let service = parse_service(located_lexems, &mut ind)?;
+                res.services.push(service);
+                continue;
+            }
+            ParseReservedStatement => {
+                // skips `reserved`
+                ind += 1;
+                let reserved = match &located_lexems[ind].lexem {
+                    Lexem::StringLiteral(_) => {
+                        let mut names = Vec::new();
+                        loop {
+                            match &located_lexems[ind].lexem {
+                                Lexem::StringLiteral(name) => names.push(Arc::clone(name)),
+                                _ => {
+                                    return Err(syntax_error(
+                                        "Expected reserved field name",
+                                        &located_lexems[ind],
+                                    ))
+                                }
+                            }
+                            ind += 1;
+                            if located_lexems[ind].lexem != Lexem::Comma {
+                                break;
+                            }
+                            ind += 1;
+                        }
+                        ReservedDeclaration::Names(names)
                     }
                     _ => {
-                        return Err(syntax_error("expected identifier", loc_lexem));
+                        let mut ranges = Vec::new();
+                        loop {
+                            let start = match &located_lexems[ind].lexem {
+                                Lexem::IntLiteral(start) => *start,
+                                _ => {
+                                    return Err(syntax_error(
+                                        "Expected reserved field number",
+                                        &located_lexems[ind],
+                                    ))
+                                }
+                            };
+                            ind += 1;
+                            let end = if located_lexems[ind].lexem == Lexem::Id("to".into()) {
+                                ind += 1;
+                                match &located_lexems[ind].lexem {
+                                    Lexem::IntLiteral(end) => {
+                                        ind += 1;
+                                        *end
+                                    }
+                                    Lexem::Id(id) if id.deref() == "max" => {
+                                        ind += 1;
+                                        MAX_FIELD_NUMBER
+                                    }
+                                    _ => {
+                                        return Err(syntax_error(
+                                            "Expected end of reserved range",
+                                            &located_lexems[ind],
+                                        ))
+                                    }
+                                }
+                            } else {
+                                start
+                            };
+                            if end < start {
+                                return Err(syntax_error(
+                                    format!("Reserved range {} to {} is empty", start, end),
+                                    &located_lexems[ind - 1],
+                                ));
+                            }
+                            ranges.push((start, end));
+                            if located_lexems[ind].lexem != Lexem::Comma {
+                                break;
+                            }
+                            ind += 1;
+                        }
+                        ReservedDeclaration::Ranges(ranges)
                     }
+                };
+                if located_lexems[ind].lexem != Lexem::SemiColon {
+                    return Err(syntax_error("Expected ;", &located_lexems[ind]));
                 }
+                ind += 1;
+                let mut entries = match stack.pop() {
+                    Some(StackItem::MessageEntriesList(entries)) => entries,
+                    _ => unreachable!(),
+                };
+                entries.push(MessageDeclarationEntry::Reserved(reserved));
+                stack.push(StackItem::MessageEntriesList(entries));
+                continue;
             }
-            ParseStringLiteral => {
+            ParseId => {
                 assert_enough_length(located_lexems, ind, 1, "Expected identifier")?;
                 let loc_lexem = &located_lexems[ind];
                 let lexem = &loc_lexem.lexem;
                 match lexem {
-                    Lexem::StringLiteral(found_literal) => {
+                    Lexem::Id(found_id) => {
                         ind += 1;
-                        stack.push(found_literal.clone().into());
+                        stack.push(found_id.clone().into());
                         continue;
                     }
                     _ => {
@@ -818,22 +1072,311 @@ pub(super) fn parse_package(
                     }
                 }
             }
+            ParseConstant => {
+                assert_enough_length(located_lexems, ind, 1, "Expected constant")?;
+                let loc_lexem = &located_lexems[ind];
+                let constant = match constant(&loc_lexem.lexem) {
+                    Some(constant) => constant,
+                    None => return Err(syntax_error("expected constant", loc_lexem)),
+                };
+                ind += 1;
+                stack.push(constant.into());
+                continue;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Text of the constant `lexem`: a string, a number or an identifier.
+fn constant(lexem: &Lexem) -> Option<Arc<str>> {
+    match lexem {
+        Lexem::StringLiteral(text) | Lexem::Id(text) | Lexem::FloatLiteral(text) => {
+            Some(Arc::clone(text))
+        }
+        Lexem::IntLiteral(value) => Some(value.to_string().into()),
+        _ => None,
+    }
+}
+
+/// `name = value` of an option, as written in the proto file.
+type OptionPair = (Arc<str>, Arc<str>);
+
+/// Parses `option name = value;` of a message or an enum.
+/// Custom options, as `option (my.option) = { ... };`, are skipped and `None` is returned.
+fn parse_option_statement(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<Option<OptionPair>, ProtoError> {
+    // skips `option`
+    *ind += 1;
+    let name = match (
+        &located_lexems[*ind].lexem,
+        located_lexems.get(*ind + 1).map(|located| &located.lexem),
+    ) {
+        (Lexem::Id(name), Some(Lexem::Equal)) => Arc::clone(name),
+        _ => {
+            skip_statement(located_lexems, ind)?;
+            return Ok(None);
+        }
+    };
+    *ind += 2;
+    let value = match constant(&located_lexems[*ind].lexem) {
+        Some(value) => value,
+        None => {
+            skip_statement(located_lexems, ind)?;
+            return Ok(None);
+        }
+    };
+    *ind += 1;
+    if located_lexems[*ind].lexem != Lexem::SemiColon {
+        return Err(syntax_error("Expected ;", &located_lexems[*ind]));
+    }
+    *ind += 1;
+    Ok(Some((name, value)))
+}
+
+/// Parses `[name = value, ...]` options of an enum value.
+fn parse_option_list(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<Vec<OptionPair>, ProtoError> {
+    let mut options = Vec::new();
+    // skips `[`
+    *ind += 1;
+    loop {
+        let name = match &located_lexems[*ind].lexem {
+            Lexem::Id(name) => Arc::clone(name),
+            _ => return Err(syntax_error("Expected option name", &located_lexems[*ind])),
+        };
+        *ind += 1;
+        if located_lexems[*ind].lexem != Lexem::Equal {
+            return Err(syntax_error("Expected =", &located_lexems[*ind]));
+        }
+        *ind += 1;
+        let value = match constant(&located_lexems[*ind].lexem) {
+            Some(value) => value,
+            None => return Err(syntax_error("Expected constant", &located_lexems[*ind])),
+        };
+        *ind += 1;
+        options.push((name, value));
+        match &located_lexems[*ind].lexem {
+            Lexem::Comma => *ind += 1,
+            Lexem::CloseBracket => {
+                *ind += 1;
+                return Ok(options);
+            }
+            _ => return Err(syntax_error("Expected , or ]", &located_lexems[*ind])),
+        }
+    }
+}
+
+/// Checks whether the field declaration starts with the `label`: `optional` or `required`.
+/// Labels are also valid type names, as in `optional optional = 1;`
+fn is_label(located_lexems: &[LocatedLexem], label: &str) -> bool {
+    match located_lexems {
+        [first, _, third, ..] => {
+            first.lexem == Lexem::Id(label.into()) && third.lexem != Lexem::Equal
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether the field declaration after its label is a proto2 group,
+/// as in `group Result = 1 { ... }`, which declares its message in place.
+fn is_group(located_lexems: &[LocatedLexem]) -> bool {
+    match located_lexems {
+        [first, LocatedLexem {
+            lexem: Lexem::Id(_),
+            ..
+        }, third, rest @ ..] => {
+            first.lexem == Lexem::Id("group".into())
+                && third.lexem == Lexem::Equal
+                && rest
+                    .iter()
+                    .map(|located| &located.lexem)
+                    .find(|lexem| matches!(lexem, Lexem::SemiColon | Lexem::OpenCurly))
+                    == Some(&Lexem::OpenCurly)
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether the message entry is a `reserved` statement.
+/// `reserved` is also a valid type name, as in `reserved reserved = 1;`
+fn is_reserved_statement(located_lexems: &[LocatedLexem]) -> bool {
+    match located_lexems {
+        [first, second, ..] => {
+            first.lexem == Lexem::Id("reserved".into())
+                && matches!(second.lexem, Lexem::IntLiteral(_) | Lexem::StringLiteral(_))
+        }
+        _ => false,
+    }
+}
+
+/// Parses `service Name { rpc Method(Request) returns (stream Reply); ... }`.
+/// Options of the service and of its methods are skipped.
+fn parse_service(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<ServiceDeclaration, ProtoError> {
+    // skips `service`
+    *ind += 1;
+    let name = expect_id(located_lexems, ind, "Expected service name")?;
+    expect_lexem(located_lexems, ind, Lexem::OpenCurly)?;
+    let mut methods = Vec::new();
+    loop {
+        let located_lexem = &located_lexems[*ind];
+        match &located_lexem.lexem {
+            Lexem::CloseCurly => {
+                *ind += 1;
+                break;
+            }
+            Lexem::SemiColon => *ind += 1,
+            Lexem::Id(id) if id.deref() == "option" => skip_statement(located_lexems, ind)?,
+            Lexem::Id(id) if id.deref() == "rpc" => {
+                methods.push(parse_method(located_lexems, ind)?);
+            }
+            _ => return Err(syntax_error("Expected rpc, option or }", located_lexem)),
+        }
+    }
+    Ok(ServiceDeclaration { name, methods })
+}
+
+fn parse_method(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<MethodDeclaration, ProtoError> {
+    let span = located_lexems[*ind].range.start.into();
+    // skips `rpc`
+    *ind += 1;
+    let name = expect_id(located_lexems, ind, "Expected method name")?;
+    let (client_streaming, input_type_ref) = parse_method_type(located_lexems, ind)?;
+    if located_lexems[*ind].lexem != Lexem::Id("returns".into()) {
+        return Err(syntax_error("Expected returns", &located_lexems[*ind]));
+    }
+    *ind += 1;
+    let (server_streaming, output_type_ref) = parse_method_type(located_lexems, ind)?;
+    match &located_lexems[*ind].lexem {
+        Lexem::SemiColon => *ind += 1,
+        Lexem::OpenCurly => skip_block(located_lexems, ind)?,
+        _ => return Err(syntax_error("Expected ; or {", &located_lexems[*ind])),
+    }
+    Ok(MethodDeclaration {
+        name,
+        input_type_ref,
+        output_type_ref,
+        client_streaming,
+        server_streaming,
+        span,
+    })
+}
+
+/// Parses `([stream] a.b.Type)` of a method, returns whether it is a stream and the type.
+fn parse_method_type(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+) -> Result<(bool, FieldTypeReference), ProtoError> {
+    expect_lexem(located_lexems, ind, Lexem::OpenParen)?;
+    // `stream` is also a valid type name, as in `rpc Get(stream) returns (Reply);`
+    let streaming = located_lexems[*ind].lexem == Lexem::Id("stream".into())
+        && located_lexems[*ind + 1].lexem != Lexem::CloseParen;
+    if streaming {
+        *ind += 1;
+    }
+    let mut path = Vec::new();
+    let absolute = located_lexems[*ind].lexem == Lexem::Dot;
+    if absolute {
+        *ind += 1;
+    }
+    loop {
+        path.push(expect_id(located_lexems, ind, "Expected type name")?);
+        if located_lexems[*ind].lexem != Lexem::Dot {
+            break;
+        }
+        *ind += 1;
+    }
+    expect_lexem(located_lexems, ind, Lexem::CloseParen)?;
+    if absolute {
+        return Ok((streaming, FieldTypeReference::AbsoluteIdPath(path)));
+    }
+    Ok((streaming, path.into()))
+}
+
+fn expect_id(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+    message: &str,
+) -> Result<Arc<str>, ProtoError> {
+    match &located_lexems[*ind].lexem {
+        Lexem::Id(id) => {
+            *ind += 1;
+            Ok(Arc::clone(id))
         }
+        _ => Err(syntax_error(message, &located_lexems[*ind])),
+    }
+}
+
+fn expect_lexem(
+    located_lexems: &[LocatedLexem],
+    ind: &mut usize,
+    expected: Lexem,
+) -> Result<(), ProtoError> {
+    if located_lexems[*ind].lexem != expected {
+        return Err(syntax_error(
+            format!("Expected lexem: {:?}", expected),
+            &located_lexems[*ind],
+        ));
     }
+    *ind += 1;
     Ok(())
 }
 
+/// Skips lexems up to and including the `;` that ends the statement.
+fn skip_statement(located_lexems: &[LocatedLexem], ind: &mut usize) -> Result<(), ProtoError> {
+    loop {
+        match &located_lexems[*ind].lexem {
+            Lexem::SemiColon => {
+                *ind += 1;
+                return Ok(());
+            }
+            Lexem::OpenCurly => skip_block(located_lexems, ind)?,
+            Lexem::EOF => return Err(syntax_error("Expected ;", &located_lexems[*ind])),
+            _ => *ind += 1,
+        }
+    }
+}
+
+/// Skips the `{ ... }` block that starts at `ind` with all nested blocks.
+fn skip_block(located_lexems: &[LocatedLexem], ind: &mut usize) -> Result<(), ProtoError> {
+    let mut depth = 0;
+    loop {
+        match &located_lexems[*ind].lexem {
+            Lexem::OpenCurly => depth += 1,
+            Lexem::CloseCurly => depth -= 1,
+            Lexem::EOF => return Err(syntax_error("Expected }", &located_lexems[*ind])),
+            _ => {}
+        }
+        *ind += 1;
+        if depth == 0 {
+            return Ok(());
+        }
+    }
+}
+
 fn parse_import_path(s: &str) -> ImportPath {
     let parts = s.split("/").collect::<Vec<&str>>();
     let packages = parts
         .iter()
         .take(parts.len() - 1)
-        .map(|&s| Rc::from(s))
+        .map(|&s| Arc::from(s))
         .collect::<Vec<_>>();
-    let file_name = Rc::from(parts.last().unwrap().deref());
+    let file_name = Arc::from(*parts.last().unwrap());
     return ImportPath {
         packages,
         file_name,
+        public: false,
+        span: Span::default(),
     };
 }
 
@@ -847,12 +1390,318 @@ mod test {
             res,
             super::ImportPath {
                 packages: vec!["google".into(), "protobuf".into()],
-                file_name: "timestamp.proto".into()
+                file_name: "timestamp.proto".into(),
+                public: false,
+                span: super::Span::default(),
             }
         );
     }
 }
 
+#[cfg(test)]
+mod test_optional_label {
+    use super::*;
+    use crate::proto::{lexems::read_lexems, package::ProtoVersion};
+
+    fn parse(content: &str) -> Result<ProtoFile, ProtoError> {
+        let lexems = read_lexems("test.proto", content)?;
+        let mut res = ProtoFile {
+            version: ProtoVersion::Proto3,
+            declarations: vec![],
+            services: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            source_path: "test.proto".into(),
+            file_path: "test.proto".into(),
+        };
+        parse_package(&mut IdGenerator::new(), &lexems, &mut res)?;
+        Ok(res)
+    }
+
+    #[test]
+    fn it_parses_optional_fields() {
+        let file = parse(
+            "message A { optional int32 a = 1; optional pkg.B b = 2; optional optional = 3; }",
+        )
+        .unwrap();
+        let message = match &file.declarations[0] {
+            Declaration::Message(m) => m,
+            _ => unreachable!(),
+        };
+        let optional_flags = message
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                MessageDeclarationEntry::Field(f) => (f.name.to_string(), f.optional),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            optional_flags,
+            vec![
+                ("a".to_string(), true),
+                ("b".to_string(), true),
+                ("optional".to_string(), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rejects_optional_repeated_fields() {
+        assert!(parse("message A { optional repeated int32 a = 1; }").is_err());
+    }
+}
+
+#[cfg(test)]
+mod test_proto2 {
+    use super::*;
+    use crate::proto::{lexems::read_lexems, package::ProtoVersion};
+
+    fn parse(content: &str) -> Result<ProtoFile, ProtoError> {
+        let lexems = read_lexems("test.proto", content)?;
+        let mut res = ProtoFile {
+            version: ProtoVersion::Proto2,
+            declarations: vec![],
+            services: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            source_path: "test.proto".into(),
+            file_path: "test.proto".into(),
+        };
+        parse_package(&mut IdGenerator::new(), &lexems, &mut res)?;
+        Ok(res)
+    }
+
+    fn fields(file: &ProtoFile) -> Vec<&FieldDeclaration> {
+        let message = match &file.declarations[0] {
+            Declaration::Message(m) => m,
+            _ => unreachable!(),
+        };
+        message
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                MessageDeclarationEntry::Field(f) => f,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_parses_labels() {
+        let file = parse(
+            "syntax = \"proto2\";\nmessage A { required int32 a = 1; optional string b = 2; repeated int32 c = 3; required required = 4; }",
+        )
+        .unwrap();
+        let labels = fields(&file)
+            .iter()
+            .map(|f| (f.name.to_string(), f.optional, f.required))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec![
+                ("a".to_string(), false, true),
+                ("b".to_string(), true, false),
+                ("c".to_string(), false, false),
+                ("required".to_string(), false, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_default_values() {
+        let file = parse(
+            r#"message A {
+                optional int32 a = 1 [default = -5];
+                optional double b = 2 [default = 1.05];
+                optional float c = 3 [default = -inf];
+                optional bool d = 4 [default = true];
+                optional string e = 5 [default = "text", json_name = "eee"];
+                optional double f = 6 [default = 2e-3];
+            }"#,
+        )
+        .unwrap();
+        let defaults = fields(&file)
+            .iter()
+            .map(|f| f.attributes[0].1.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(defaults, vec!["-5", "1.05", "-inf", "true", "text", "2e-3"]);
+        assert_eq!(
+            fields(&file)[4].attributes[1],
+            ("json_name".into(), "eee".into())
+        );
+    }
+
+    #[test]
+    fn it_rejects_groups() {
+        let res = parse(
+            "message A {\n  repeated group Result = 1 {\n    required string url = 2;\n  }\n}",
+        );
+        match res {
+            Err(ProtoError::UnsupportedGroup {
+                field_name,
+                line,
+                column,
+                ..
+            }) => {
+                assert_eq!(field_name, "Result");
+                assert_eq!((line, column), (2, 18));
+            }
+            res => panic!("expected unsupported group, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn it_accepts_fields_of_type_named_group() {
+        let file = parse("message A { optional group group = 1; }").unwrap();
+        assert_eq!(fields(&file)[0].name.deref(), "group");
+    }
+}
+
+#[cfg(test)]
+mod test_field_numbers {
+    use super::*;
+    use crate::proto::{lexems::read_lexems, package::ProtoVersion};
+
+    fn parse(content: &str) -> Result<ProtoFile, ProtoError> {
+        let lexems = read_lexems("test.proto", content)?;
+        let mut res = ProtoFile {
+            version: ProtoVersion::Proto3,
+            declarations: vec![],
+            services: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            source_path: "test.proto".into(),
+            file_path: "test.proto".into(),
+        };
+        parse_package(&mut IdGenerator::new(), &lexems, &mut res)?;
+        Ok(res)
+    }
+
+    fn parse_field_number(number: i64) -> Result<ProtoFile, ProtoError> {
+        parse(&format!("message A {{ int32 a = {}; }}", number))
+    }
+
+    fn assert_invalid(content: &str, expected_name: &str, expected_number: i64) {
+        match parse(content) {
+            Err(ProtoError::InvalidFieldNumber {
+                field_name, number, ..
+            }) => {
+                assert_eq!(field_name, expected_name);
+                assert_eq!(number, expected_number);
+            }
+            res => panic!("expected invalid field number, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn it_accepts_boundaries_of_the_legal_range() {
+        for number in [1, 18_999, 20_000, 536_870_911] {
+            assert!(parse_field_number(number).is_ok(), "{}", number);
+        }
+    }
+
+    #[test]
+    fn it_rejects_numbers_outside_of_the_legal_range() {
+        for number in [0, -1, 19_000, 19_500, 19_999, 536_870_912] {
+            assert_invalid(
+                &format!("message A {{ int32 a = {}; }}", number),
+                "a",
+                number,
+            );
+        }
+    }
+
+    #[test]
+    fn it_validates_every_kind_of_field() {
+        assert_invalid("message A { repeated int32 list = 0; }", "list", 0);
+        assert_invalid("message A { map<string, int32> m = 19000; }", "m", 19_000);
+        assert_invalid(
+            "message A { oneof kind { int32 x = 1; string y = 536870912; } }",
+            "y",
+            536_870_912,
+        );
+        assert_invalid("message A { optional int32 o = -5; }", "o", -5);
+    }
+
+    #[test]
+    fn it_reports_the_location_of_the_number() {
+        let err = parse("message A {\n  int32 a = 19999;\n}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "test.proto:2:13: Invalid field number 19999 of \"a\": field numbers must be in range 1..=536870911 excluding the reserved range 19000..=19999"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_reserved {
+    use super::*;
+    use crate::proto::{lexems::read_lexems, package::ProtoVersion};
+
+    fn parse(content: &str) -> Result<MessageDeclaration, ProtoError> {
+        let lexems = read_lexems("test.proto", content)?;
+        let mut res = ProtoFile {
+            version: ProtoVersion::Proto3,
+            declarations: vec![],
+            services: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            source_path: "test.proto".into(),
+            file_path: "test.proto".into(),
+        };
+        parse_package(&mut IdGenerator::new(), &lexems, &mut res)?;
+        match res.declarations.pop() {
+            Some(Declaration::Message(m)) => Ok(m),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn it_parses_reserved_numbers_and_names() {
+        let m = parse(
+            "message A { reserved 2, 4 to 6; int32 a = 1; reserved \"foo\", \"bar\"; reserved 9; }",
+        )
+        .unwrap();
+        assert_eq!(m.reserved_ranges, vec![(2, 2), (4, 6), (9, 9)]);
+        assert_eq!(
+            m.reserved_names,
+            vec![Arc::<str>::from("foo"), Arc::<str>::from("bar")]
+        );
+        assert_eq!(m.entries.len(), 1);
+        assert_eq!(
+            m.to_string(),
+            "message A {\n  reserved 2, 4 to 6, 9;\n  reserved \"foo\", \"bar\";\n  int32 a = 1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn it_parses_fields_of_reserved_type() {
+        let m = parse("message A { reserved reserved = 1; }").unwrap();
+        assert!(m.reserved_ranges.is_empty());
+        assert_eq!(m.entries.len(), 1);
+    }
+
+    #[test]
+    fn it_rejects_malformed_reserved_statements() {
+        assert!(parse("message A { reserved 6 to 4; }").is_err());
+        assert!(parse("message A { reserved 1, \"a\"; }").is_err());
+        assert!(parse("message A { reserved 1 to; }").is_err());
+        assert!(parse("message A { reserved 1 2; }").is_err());
+    }
+
+    #[test]
+    fn it_parses_ranges_up_to_max() {
+        let m = parse("message A { reserved 1000 to max; }").unwrap();
+        assert_eq!(m.reserved_ranges, vec![(1000, MAX_FIELD_NUMBER)]);
+        assert_eq!(m.to_string(), "message A {\n  reserved 1000 to max;\n}\n");
+    }
+}
+
 fn print_stack(stack: &[StackItem]) {
     println!("\nStack: ");
     println!(
@@ -870,8 +1719,10 @@ fn print_stack(stack: &[StackItem]) {
                 StackItem::Int64(_) => "i64",
                 StackItem::Message(_) => "message",
                 StackItem::OptionalAttributes(_) => "attributes[]?",
+                StackItem::Options(_) => "options[]",
                 StackItem::Enum(_) => "enum",
                 StackItem::OneOf(_) => "oneof",
+                StackItem::Span(_) => "span",
             })
             .collect::<Vec<_>>()
             .join("\n")
@@ -947,3 +1798,284 @@ where
     }
     return Ok(());
 }
+
+#[cfg(test)]
+mod test_options {
+    use super::*;
+    use crate::proto::{lexems::read_lexems, package::ProtoVersion};
+
+    fn parse(content: &str) -> Result<ProtoFile, ProtoError> {
+        let lexems = read_lexems("test.proto", content)?;
+        let mut res = ProtoFile {
+            version: ProtoVersion::Proto3,
+            declarations: vec![],
+            services: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            source_path: "test.proto".into(),
+            file_path: "test.proto".into(),
+        };
+        parse_package(&mut IdGenerator::new(), &lexems, &mut res)?;
+        Ok(res)
+    }
+
+    #[test]
+    fn it_parses_options_of_fields() {
+        let file = parse(
+            "message A { repeated int32 a = 1 [packed = false, deprecated = true]; int32 b = 2 [json_name = \"bb\"]; optional int32 c = 3 [default = 5]; }",
+        )
+        .unwrap();
+        let m = match &file.declarations[0] {
+            Declaration::Message(m) => m,
+            _ => unreachable!(),
+        };
+        let options = m
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                MessageDeclarationEntry::Field(f) => f.options.clone(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            options,
+            vec![
+                FieldOptions {
+                    packed: Some(false),
+                    deprecated: true,
+                    default: None,
+                },
+                FieldOptions::default(),
+                FieldOptions {
+                    default: Some("5".into()),
+                    ..FieldOptions::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_parses_deprecated_messages_and_enums() {
+        let file = parse(
+            r#"message A {
+                option deprecated = true;
+                option (my.option) = { value: 1 };
+                option map_entry = false;
+                int32 a = 1;
+            }
+            enum E {
+                option deprecated = true;
+                option allow_alias = true;
+                X = 0;
+                Y = 1 [deprecated = true];
+            }
+            message B { int32 b = 1; }"#,
+        )
+        .unwrap();
+        match &file.declarations[..] {
+            [Declaration::Message(a), Declaration::Enum(e), Declaration::Message(b)] => {
+                assert!(a.deprecated);
+                assert_eq!(a.entries.len(), 1);
+                assert!(e.deprecated);
+                assert!(e.allow_alias);
+                assert_eq!(
+                    e.entries
+                        .iter()
+                        .map(|entry| entry.deprecated)
+                        .collect::<Vec<_>>(),
+                    vec![false, true]
+                );
+                assert!(!b.deprecated);
+            }
+            declarations => panic!("Unexpected declarations: {:?}", declarations),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_services {
+    use super::*;
+    use crate::proto::{lexems::read_lexems, package::ProtoVersion};
+
+    fn parse(content: &str) -> Result<ProtoFile, ProtoError> {
+        let lexems = read_lexems("test.proto", content)?;
+        let mut res = ProtoFile {
+            version: ProtoVersion::Proto3,
+            declarations: vec![],
+            services: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            source_path: "test.proto".into(),
+            file_path: "test.proto".into(),
+        };
+        parse_package(&mut IdGenerator::new(), &lexems, &mut res)?;
+        Ok(res)
+    }
+
+    #[test]
+    fn it_parses_methods_of_every_kind() {
+        let file = parse(concat!(
+            "service Chat {\n",
+            "  option deprecated = true;\n",
+            "  rpc Send(Message) returns (Ack);\n",
+            "  rpc Watch(Filter) returns (stream chat.Message) { option (http) = { get: \"/m\" }; }\n",
+            "  rpc Upload(stream Message) returns (Ack) {}\n",
+            "  rpc Talk(stream Message) returns (stream Message);\n",
+            "  rpc Stream(stream) returns (stream);\n",
+            "}\n",
+            "message Ack {}\n",
+        ))
+        .unwrap();
+        assert_eq!(file.declarations.len(), 1);
+        assert_eq!(
+            file.services[0].to_string(),
+            concat!(
+                "service Chat {\n",
+                "  rpc Send(Message) returns (Ack);\n",
+                "  rpc Watch(Filter) returns (stream chat.Message);\n",
+                "  rpc Upload(stream Message) returns (Ack);\n",
+                "  rpc Talk(stream Message) returns (stream Message);\n",
+                "  rpc Stream(stream) returns (stream);\n",
+                "}",
+            )
+        );
+        assert_eq!(
+            file.services[0].methods[1].span,
+            Span {
+                line: 4,
+                column: 3,
+                byte_offset: 80,
+            }
+        );
+    }
+
+    #[test]
+    fn it_exposes_the_types_and_the_streaming_of_methods() {
+        let file = parse(concat!(
+            "package chat;\n",
+            "service Chat {\n",
+            "  rpc Send(Message) returns (.chat.Ack);\n",
+            "  rpc Watch(stream chat.Filter) returns (stream Message);\n",
+            "}\n",
+        ))
+        .unwrap();
+        let chat = &file.services[0];
+        assert_eq!(chat.name.deref(), "Chat");
+        let send = &chat.methods[0];
+        assert_eq!(send.name.deref(), "Send");
+        assert_eq!(
+            send.input_type_ref,
+            FieldTypeReference::IdPath(vec!["Message".into()])
+        );
+        assert_eq!(
+            send.output_type_ref,
+            FieldTypeReference::AbsoluteIdPath(vec!["chat".into(), "Ack".into()])
+        );
+        assert!(!send.client_streaming && !send.server_streaming);
+        let watch = &chat.methods[1];
+        assert_eq!(
+            watch.input_type_ref,
+            FieldTypeReference::IdPath(vec!["chat".into(), "Filter".into()])
+        );
+        assert!(watch.client_streaming && watch.server_streaming);
+    }
+
+    #[test]
+    fn it_rejects_malformed_methods() {
+        for (content, message) in [
+            (
+                "service S { rpc M(A) (B); }",
+                "test.proto:1:22: SyntaxError: Expected returns, but ( occurred",
+            ),
+            (
+                "service S { rpc M(A) returns (B) }",
+                "test.proto:1:34: SyntaxError: Expected ; or {, but } occurred",
+            ),
+            (
+                "service S { message A {} }",
+                "test.proto:1:13: SyntaxError: Expected rpc, option or }, but message occurred",
+            ),
+            (
+                "service S { rpc M(A) returns (B) { option a = 1; }",
+                "test.proto:1:50: SyntaxError: Expected rpc, option or }, but EOF occurred",
+            ),
+        ] {
+            assert_eq!(parse(content).unwrap_err().to_string(), message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_spans {
+    use super::*;
+    use crate::proto::{lexems::read_lexems, package::ProtoVersion};
+
+    fn parse(content: &str) -> ProtoFile {
+        let lexems = read_lexems("test.proto", content).unwrap();
+        let mut res = ProtoFile {
+            version: ProtoVersion::Proto3,
+            declarations: vec![],
+            services: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            source_path: "test.proto".into(),
+            file_path: "test.proto".into(),
+        };
+        parse_package(&mut IdGenerator::new(), &lexems, &mut res).unwrap();
+        res
+    }
+
+    fn span(line: usize, column: usize, byte_offset: usize) -> Span {
+        Span {
+            line,
+            column,
+            byte_offset,
+        }
+    }
+
+    #[test]
+    fn it_parses_public_imports() {
+        let file = parse("import \"a.proto\";\nimport public \"b/c.proto\";\n");
+        assert!(!file.imports[0].public);
+        assert!(file.imports[1].public);
+        assert_eq!(file.imports[1].to_string(), "b/c.proto");
+        assert_eq!(file.imports[1].span, span(2, 15, 32));
+    }
+
+    #[test]
+    fn it_records_positions_of_declarations() {
+        let file = parse(concat!(
+            "// ünïcode\n",
+            "import \"a/b.proto\";\n",
+            "message User {\n",
+            "  optional Address address = 1;\n",
+            "  enum Kind { A = 0; }\n",
+            "  oneof id { string email = 2; }\n",
+            "}\n",
+        ));
+        assert_eq!(file.imports[0].span, span(2, 8, 20));
+        let user = match &file.declarations[0] {
+            Declaration::Message(m) => m,
+            _ => unreachable!(),
+        };
+        assert_eq!(user.span, span(3, 9, 41));
+        let field = match &user.entries[0] {
+            MessageDeclarationEntry::Field(f) => f,
+            _ => unreachable!(),
+        };
+        assert_eq!(field.span, span(4, 12, 59));
+        match &user.entries[1] {
+            MessageDeclarationEntry::Declaration(Declaration::Enum(e)) => {
+                assert_eq!(e.span, span(5, 8, 87))
+            }
+            _ => unreachable!(),
+        };
+        match &user.entries[2] {
+            MessageDeclarationEntry::OneOf(o) => assert_eq!(o.options[0].span, span(6, 14, 116)),
+            _ => unreachable!(),
+        };
+    }
+}
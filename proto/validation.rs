@@ -0,0 +1,397 @@
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use super::{
+    error::ProtoError,
+    package::{
+        Declaration, EnumDeclaration, FieldDeclaration, MessageDeclaration,
+        MessageDeclarationEntry, ProtoVersion,
+    },
+};
+
+/// Smallest field number allowed by the protobuf specification.
+pub(crate) const MIN_FIELD_NUMBER: i64 = 1;
+/// Largest field number allowed by the protobuf specification, `2^29 - 1`.
+pub(crate) const MAX_FIELD_NUMBER: i64 = 536_870_911;
+/// Field numbers reserved for the protobuf implementation.
+pub(crate) const RESERVED_FIELD_NUMBERS: RangeInclusive<i64> = 19_000..=19_999;
+
+/// Checks whether `number` can be used as a field number.
+pub(crate) fn is_valid_field_number(number: i64) -> bool {
+    (MIN_FIELD_NUMBER..=MAX_FIELD_NUMBER).contains(&number)
+        && !RESERVED_FIELD_NUMBERS.contains(&number)
+}
+
+/// Checks that values of the enum are unique, unless it is declared with `option allow_alias = true;`,
+/// and that the first value is zero in proto3 files.
+pub(crate) fn validate_enum_declaration(
+    e: &EnumDeclaration,
+    version: ProtoVersion,
+) -> Result<(), ProtoError> {
+    let mut errors = Vec::new();
+    validate_enum(&e.name, e, version, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProtoError::from_errors(errors))
+    }
+}
+
+fn validate_enum(
+    enum_name: &str,
+    e: &EnumDeclaration,
+    version: ProtoVersion,
+    errors: &mut Vec<ProtoError>,
+) {
+    if version == ProtoVersion::Proto3 {
+        match e.entries.first() {
+            Some(first) if first.value == 0 => {}
+            first => errors.push(ProtoError::MissingZeroEnumValue {
+                enum_name: enum_name.to_string(),
+                first_value: first.map(|entry| (entry.name.to_string(), entry.value)),
+            }),
+        }
+    }
+    if e.allow_alias {
+        return;
+    }
+    let mut names_by_value: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+    for entry in &e.entries {
+        names_by_value
+            .entry(entry.value)
+            .or_default()
+            .push(entry.name.to_string());
+    }
+    let duplicates = names_by_value
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .collect::<Vec<_>>();
+    if !duplicates.is_empty() {
+        errors.push(ProtoError::DuplicateEnumValues {
+            enum_name: enum_name.to_string(),
+            duplicates,
+        });
+    }
+}
+
+/// Checks that field numbers of the message and of its nested messages
+/// are unique, legal and not reserved, that field names are not reserved
+/// and that nested enums are valid for the proto `version`, see [validate_enum_declaration].
+/// All found problems are reported together.
+pub(crate) fn validate_message_declaration(
+    m: &MessageDeclaration,
+    version: ProtoVersion,
+) -> Result<(), ProtoError> {
+    let mut errors = Vec::new();
+    validate_message(&m.name, m, version, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ProtoError::from_errors(errors))
+    }
+}
+
+fn validate_message(
+    message_name: &str,
+    m: &MessageDeclaration,
+    version: ProtoVersion,
+    errors: &mut Vec<ProtoError>,
+) {
+    let mut fields_by_number: BTreeMap<i64, Vec<&FieldDeclaration>> = BTreeMap::new();
+    let mut nested_messages = Vec::new();
+    for entry in &m.entries {
+        match entry {
+            MessageDeclarationEntry::Field(field) => {
+                fields_by_number.entry(field.tag).or_default().push(field)
+            }
+            MessageDeclarationEntry::OneOf(one_of) => {
+                for field in &one_of.options {
+                    fields_by_number.entry(field.tag).or_default().push(field)
+                }
+            }
+            MessageDeclarationEntry::Declaration(Declaration::Message(nested)) => {
+                nested_messages.push(nested)
+            }
+            MessageDeclarationEntry::Declaration(Declaration::Enum(nested)) => {
+                let enum_name = format!("{}.{}", message_name, nested.name);
+                validate_enum(&enum_name, nested, version, errors)
+            }
+            MessageDeclarationEntry::Reserved(_) | MessageDeclarationEntry::Option(_, _) => {}
+        }
+    }
+
+    for (&number, fields) in &fields_by_number {
+        if !is_valid_field_number(number) {
+            for field in fields {
+                errors.push(ProtoError::FieldNumberOutOfRange {
+                    message_name: message_name.to_string(),
+                    field_name: field.name.to_string(),
+                    number,
+                });
+            }
+        }
+        let is_reserved = m
+            .reserved_ranges
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&number));
+        if is_reserved {
+            for field in fields {
+                errors.push(ProtoError::ReservedFieldNumber {
+                    message_name: message_name.to_string(),
+                    field_name: field.name.to_string(),
+                    number,
+                });
+            }
+        }
+    }
+
+    for fields in fields_by_number.values() {
+        for field in fields {
+            if m.reserved_names.contains(&field.name) {
+                errors.push(ProtoError::ReservedFieldName {
+                    message_name: message_name.to_string(),
+                    field_name: field.name.to_string(),
+                });
+            }
+        }
+    }
+
+    let duplicates = fields_by_number
+        .into_iter()
+        .filter(|(_, fields)| fields.len() > 1)
+        .map(|(number, fields)| {
+            let field_names = fields.iter().map(|f| f.name.to_string()).collect();
+            (number, field_names)
+        })
+        .collect::<Vec<_>>();
+    if !duplicates.is_empty() {
+        errors.push(ProtoError::DuplicateFieldNumbers {
+            message_name: message_name.to_string(),
+            duplicates,
+        });
+    }
+
+    for nested in nested_messages {
+        let nested_name = format!("{}.{}", message_name, nested.name);
+        validate_message(&nested_name, nested, version, errors);
+    }
+}
+
+#[cfg(test)]
+mod test_validate_message_declaration {
+    use super::*;
+    use crate::proto::{
+        id_generator::IdGenerator,
+        lexems::read_lexems,
+        package::{FieldTypeReference, OneOfDeclaration, ProtoFile, ProtoVersion},
+        syntax::parse_package,
+    };
+
+    fn message(content: &str) -> MessageDeclaration {
+        let lexems = read_lexems("test.proto", content).unwrap();
+        let mut file = ProtoFile {
+            version: ProtoVersion::Proto3,
+            declarations: vec![],
+            services: vec![],
+            imports: vec![],
+            path: vec![],
+            name: "test.proto".into(),
+            source_path: "test.proto".into(),
+            file_path: "test.proto".into(),
+        };
+        parse_package(&mut IdGenerator::new(), &lexems, &mut file).unwrap();
+        match file.declarations.pop() {
+            Some(Declaration::Message(m)) => m,
+            _ => unreachable!(),
+        }
+    }
+
+    fn field(name: &str, tag: i64) -> MessageDeclarationEntry {
+        MessageDeclarationEntry::Field(FieldDeclaration::new(name, FieldTypeReference::Int32, tag))
+    }
+
+    fn message_with_fields(entries: Vec<MessageDeclarationEntry>) -> MessageDeclaration {
+        IdGenerator::new().create(("A".into(), entries))
+    }
+
+    fn error_messages(m: &MessageDeclaration) -> Vec<String> {
+        match validate_message_declaration(m, ProtoVersion::Proto3) {
+            Ok(()) => vec![],
+            Err(e) => e.errors().iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn it_accepts_unique_field_numbers() {
+        let m = message(
+            "message A { int32 a = 1; oneof kind { string b = 2; int32 c = 3; } message B { int32 a = 1; } }",
+        );
+        assert!(validate_message_declaration(&m, ProtoVersion::Proto3).is_ok());
+    }
+
+    #[test]
+    fn it_lists_duplicate_numbers_with_field_names() {
+        let m = message(
+            "message A { int32 a = 1; int32 b = 2; oneof kind { string c = 1; int32 d = 2; } int32 e = 1; }",
+        );
+        assert_eq!(
+            error_messages(&m),
+            vec!["Duplicate field numbers in message \"A\": 1 (a, c, e), 2 (b, d)"]
+        );
+    }
+
+    #[test]
+    fn it_validates_nested_messages() {
+        let m = message(
+            "message A { message B { int32 x = 5; int32 y = 5; } int32 a = 1; int32 b = 1; }",
+        );
+        assert_eq!(
+            error_messages(&m),
+            vec![
+                "Duplicate field numbers in message \"A\": 1 (a, b)",
+                "Duplicate field numbers in message \"A.B\": 5 (x, y)"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_rejects_aliased_enum_values_unless_they_are_allowed() {
+        let m = message(
+            "message A { enum Kind { NONE = 0; ONE = 1; FIRST = 1; } enum Alias { option allow_alias = true; X = 0; Y = 0; } }",
+        );
+        assert_eq!(
+            error_messages(&m),
+            vec!["Duplicate values in enum \"A.Kind\" without option allow_alias = true: 1 (ONE, FIRST)"]
+        );
+    }
+
+    #[test]
+    fn it_requires_the_first_value_of_proto3_enums_to_be_zero() {
+        let m = message(
+            "message A { enum Kind { ONE = 1; NONE = 0; } enum Sign { NEGATIVE = -1; ZERO = 0; } enum Zero { ZERO = 0; NEGATIVE = -1; } }",
+        );
+        assert_eq!(
+            error_messages(&m),
+            vec![
+                "The first value of enum \"A.Kind\" must be zero in proto3, found ONE = 1",
+                "The first value of enum \"A.Sign\" must be zero in proto3, found NEGATIVE = -1",
+            ]
+        );
+        assert!(validate_message_declaration(&m, ProtoVersion::Proto2).is_ok());
+
+        let m = message("message A { enum Empty {} }");
+        assert_eq!(
+            validate_message_declaration(&m, ProtoVersion::Proto3)
+                .unwrap_err()
+                .to_string(),
+            "Enum \"A.Empty\" must have a zero value in proto3, it has no values"
+        );
+    }
+
+    #[test]
+    fn it_checks_the_boundaries_of_field_numbers() {
+        let valid = [1, 18_999, 20_000, 536_870_911];
+        let invalid = [0, -1, 19_000, 19_999, 536_870_912];
+        for number in valid {
+            assert!(is_valid_field_number(number), "{}", number);
+        }
+        for number in invalid {
+            assert!(!is_valid_field_number(number), "{}", number);
+        }
+
+        let m = message_with_fields(vec![
+            field("a", 0),
+            field("b", 536_870_911),
+            MessageDeclarationEntry::OneOf(OneOfDeclaration {
+                name: "kind".into(),
+                options: vec![FieldDeclaration::new(
+                    "c",
+                    FieldTypeReference::Int32,
+                    19_000,
+                )],
+            }),
+        ]);
+        assert_eq!(
+            error_messages(&m),
+            vec![
+                "Invalid field number 0 of \"A.a\": field numbers must be in range 1..=536870911 excluding the reserved range 19000..=19999",
+                "Invalid field number 19000 of \"A.c\": field numbers must be in range 1..=536870911 excluding the reserved range 19000..=19999",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_every_problem() {
+        let m = message_with_fields(vec![field("a", 19_999), field("b", 19_999)]);
+        assert_eq!(
+            validate_message_declaration(&m, ProtoVersion::Proto3)
+                .unwrap_err()
+                .code(),
+            "multiple"
+        );
+        assert_eq!(error_messages(&m).len(), 3);
+    }
+
+    #[test]
+    fn it_rejects_fields_using_reserved_numbers_and_names() {
+        let m = message(
+            "message A { reserved 2, 4 to 6; reserved \"foo\"; int32 a = 1; int32 b = 4; oneof kind { string foo = 7; } message B { reserved 1; int32 c = 1; } }",
+        );
+        assert_eq!(
+            error_messages(&m),
+            vec![
+                "Field \"A.b\" uses the reserved field number 4",
+                "Field \"A.foo\" uses the reserved field name",
+                "Field \"A.B.c\" uses the reserved field number 1",
+            ]
+        );
+        assert_eq!(
+            validate_message_declaration(&m, ProtoVersion::Proto3)
+                .unwrap_err()
+                .errors()
+                .iter()
+                .map(|e| e.code())
+                .collect::<Vec<_>>(),
+            [
+                "reserved_field_number",
+                "reserved_field_name",
+                "reserved_field_number"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_treats_reserved_ranges_as_inclusive() {
+        let m = message(
+            "message A { reserved 4 to 6, 10, 20 to max; int32 a = 3; int32 b = 4; int32 c = 6; int32 d = 7; int32 e = 9; int32 f = 10; int32 g = 11; int32 h = 19; int32 i = 20; int32 j = 536870911; }",
+        );
+        assert_eq!(
+            error_messages(&m),
+            vec![
+                "Field \"A.b\" uses the reserved field number 4",
+                "Field \"A.c\" uses the reserved field number 6",
+                "Field \"A.f\" uses the reserved field number 10",
+                "Field \"A.i\" uses the reserved field number 20",
+                "Field \"A.j\" uses the reserved field number 536870911",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_matches_reserved_names_exactly_and_per_message() {
+        let m = message(
+            "message A { reserved \"foo\", \"bar_id\"; int32 Foo = 1; int32 bar = 2; int32 foo_bar = 3; message B { int32 foo = 1; } }",
+        );
+        assert!(validate_message_declaration(&m, ProtoVersion::Proto3).is_ok());
+
+        let m = message(
+            "message A { reserved \"foo\"; reserved \"bar\"; int32 bar = 1; int32 foo = 2; }",
+        );
+        assert_eq!(
+            error_messages(&m),
+            vec![
+                "Field \"A.bar\" uses the reserved field name",
+                "Field \"A.foo\" uses the reserved field name",
+            ]
+        );
+    }
+}
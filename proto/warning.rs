@@ -0,0 +1,41 @@
+use std::fmt::{Display, Formatter};
+
+/// Problem found during the compilation that does not stop it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// Messages reference each other through their fields, directly or through other messages.
+    /// Imports between them are generated as `import type` and namespace imports.
+    CircularReferences { messages: Vec<String> },
+    /// Proto files import each other, `files` is the chain of imports from a file back to itself.
+    #[deprecated(
+        note = "proto files importing each other are rejected with `ProtoError::ImportCycle`, this warning is not reported anymore"
+    )]
+    CircularImports { files: Vec<String> },
+}
+
+impl Warning {
+    /// Stable identifier of the kind of the warning.
+    #[allow(deprecated)]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Warning::CircularReferences { .. } => "circular_references",
+            Warning::CircularImports { .. } => "circular_imports",
+        }
+    }
+}
+
+impl Display for Warning {
+    #[allow(deprecated)]
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Warning::CircularReferences { messages } => write!(
+                f,
+                "Messages {} reference each other, imports between them are lazy",
+                messages.join(", ")
+            ),
+            Warning::CircularImports { files } => {
+                write!(f, "Proto files import each other: {}", files.join(" -> "))
+            }
+        }
+    }
+}
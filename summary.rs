@@ -0,0 +1,123 @@
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use protos_ts::CompileReport;
+use serde::Serialize;
+
+/// Version of the `--summary-json` structure.
+/// It must be bumped on every incompatible change of the structure.
+pub(super) const SUMMARY_VERSION: u32 = 1;
+
+/// Machine-readable result of a single run,
+/// printed to stdout when `--summary-json` is passed.
+#[derive(Debug, Serialize)]
+pub(super) struct Summary {
+    pub version: u32,
+    pub success: bool,
+    pub out_folder_path: String,
+    pub files: FileCounts,
+    pub warnings: BTreeMap<&'static str, usize>,
+    pub errors: BTreeMap<&'static str, usize>,
+    pub durations_ms: StageDurations,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(super) struct FileCounts {
+    pub read: usize,
+    pub parsed: usize,
+    pub generated: usize,
+    pub written: usize,
+    pub unchanged: usize,
+    pub pruned: usize,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(super) struct StageDurations {
+    pub read: f64,
+    pub parse: f64,
+    pub compile: f64,
+    pub write: f64,
+}
+
+impl Summary {
+    pub fn new(out_folder_path: &Path) -> Self {
+        Self {
+            version: SUMMARY_VERSION,
+            success: false,
+            out_folder_path: out_folder_path.to_string_lossy().into(),
+            files: FileCounts::default(),
+            warnings: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            durations_ms: StageDurations::default(),
+        }
+    }
+
+    /// Copies file counts and durations of the stages that finished.
+    pub fn add_report(&mut self, report: &CompileReport) {
+        self.files = FileCounts {
+            read: report.files_read,
+            parsed: report.files_parsed,
+            generated: report.files_generated,
+            written: report.files_written,
+            unchanged: report.files_unchanged,
+            pruned: report.files_pruned,
+        };
+        self.durations_ms = StageDurations {
+            read: duration_ms(report.durations.read),
+            parse: duration_ms(report.durations.parse),
+            compile: duration_ms(report.durations.compile),
+            write: duration_ms(report.durations.write),
+        };
+    }
+
+    pub fn add_warning(&mut self, code: &'static str) {
+        *self.warnings.entry(code).or_insert(0) += 1;
+    }
+
+    pub fn add_error(&mut self, code: &'static str) {
+        *self.errors.entry(code).or_insert(0) += 1;
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.errors.values().sum()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+pub(super) fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod test_summary {
+    use super::*;
+
+    #[test]
+    fn it_serializes_empty_summary() {
+        let summary = Summary::new(Path::new("out"));
+        assert_eq!(
+            summary.to_json(),
+            concat!(
+                "{\"version\":1,\"success\":false,\"out_folder_path\":\"out\",",
+                "\"files\":{\"read\":0,\"parsed\":0,\"generated\":0,\"written\":0,\"unchanged\":0,\"pruned\":0},",
+                "\"warnings\":{},\"errors\":{},",
+                "\"durations_ms\":{\"read\":0.0,\"parse\":0.0,\"compile\":0.0,\"write\":0.0}}"
+            )
+        );
+    }
+
+    #[test]
+    fn it_counts_errors_by_code() {
+        let mut summary = Summary::new(Path::new("out"));
+        summary.add_error("syntax_error");
+        summary.add_error("syntax_error");
+        summary.add_error("io_error");
+        let json: serde_json::Value = serde_json::from_str(&summary.to_json()).unwrap();
+        assert_eq!(json["errors"]["syntax_error"], 2);
+        assert_eq!(json["errors"]["io_error"], 1);
+        assert_eq!(json["success"], false);
+        assert_eq!(summary.error_count(), 3);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{Event, RecursiveMode, Watcher};
+
+/// Time without new events after which a change is considered finished.
+/// Editors often save a file in several steps (write to a temporary file, rename, ...),
+/// so a single save is recompiled once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Calls `on_change` every time a `.proto` file inside any of `proto_paths` is created, modified or removed.
+/// Changes inside `out_path` are ignored, so the output folder can be placed
+/// inside of a proto folder. Returns only when the watcher cannot be started.
+pub(super) fn watch(
+    proto_paths: &[PathBuf],
+    out_path: &Path,
+    mut on_change: impl FnMut(),
+) -> notify::Result<()> {
+    let watched_paths = watched_folders(proto_paths)?;
+    let out_path = canonicalize_missing(out_path)?;
+
+    let (sender, receiver) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for watched_path in &watched_paths {
+        watcher.watch(watched_path, RecursiveMode::Recursive)?;
+    }
+
+    while let Ok(event) = receiver.recv() {
+        let mut changed = is_change(event, &out_path);
+        while let Ok(event) = receiver.recv_timeout(DEBOUNCE) {
+            changed |= is_change(event, &out_path);
+        }
+        if changed {
+            on_change();
+        }
+    }
+    Ok(())
+}
+
+/// Absolute paths of the folders to watch for the proto files and folders, without duplicates.
+/// A single file is replaced, not modified, by many editors,
+/// so the folder it and its imports are in is watched instead.
+/// Folders are watched recursively, so the ones inside of another watched folder are left out.
+fn watched_folders(proto_paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut folders = Vec::new();
+    for proto_path in proto_paths {
+        let proto_path = proto_path.canonicalize()?;
+        let folder = if proto_path.is_file() {
+            proto_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default()
+        } else {
+            proto_path
+        };
+        folders.push(folder);
+    }
+    let mut res: Vec<PathBuf> = Vec::new();
+    for folder in &folders {
+        let is_nested = folders
+            .iter()
+            .any(|other| other != folder && folder.starts_with(other));
+        if !is_nested && !res.contains(folder) {
+            res.push(folder.clone());
+        }
+    }
+    Ok(res)
+}
+
+fn is_change(event: notify::Result<Event>, out_path: &Path) -> bool {
+    match event {
+        Ok(event) => {
+            !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| is_proto_file(path) && !path.starts_with(out_path))
+        }
+        Err(e) => {
+            eprintln!("watch error: {}", e);
+            false
+        }
+    }
+}
+
+fn is_proto_file(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "proto")
+}
+
+/// Absolute path of a file that may not exist yet.
+fn canonicalize_missing(path: &Path) -> io::Result<PathBuf> {
+    match path.canonicalize() {
+        Ok(path) => Ok(path),
+        Err(_) => Ok(std::env::current_dir()?.join(path)),
+    }
+}
+
+#[cfg(test)]
+mod test_watch {
+    use notify::{
+        event::{AccessKind, CreateKind, ModifyKind, RemoveKind},
+        EventKind,
+    };
+
+    use super::*;
+
+    fn event(kind: EventKind, path: &str) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn it_watches_folders_of_every_proto_path() {
+        let dir = std::env::temp_dir().join(format!("protos-ts-watch-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("protos/shop")).unwrap();
+        std::fs::create_dir_all(dir.join("include")).unwrap();
+        std::fs::write(dir.join("protos/shop/order.proto"), "").unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        let folders = watched_folders(&[
+            dir.join("protos"),
+            dir.join("include"),
+            dir.join("protos/shop/order.proto"),
+            dir.join("include"),
+        ])
+        .unwrap();
+        assert_eq!(folders, vec![dir.join("protos"), dir.join("include")]);
+        assert_eq!(
+            watched_folders(&[dir.join("protos/shop/order.proto"), dir.join("protos")]).unwrap(),
+            vec![dir.join("protos")]
+        );
+        assert!(watched_folders(&[dir.join("missing")]).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_reacts_to_changes_of_proto_files() {
+        let out = Path::new("/protos/out");
+        for kind in [
+            EventKind::Create(CreateKind::File),
+            EventKind::Modify(ModifyKind::Any),
+            EventKind::Remove(RemoveKind::File),
+        ] {
+            assert!(is_change(event(kind, "/protos/shop/order.proto"), out));
+        }
+        assert!(!is_change(
+            event(
+                EventKind::Access(AccessKind::Any),
+                "/protos/shop/order.proto"
+            ),
+            out
+        ));
+    }
+
+    #[test]
+    fn it_ignores_other_files_and_the_output() {
+        let out = Path::new("/protos/out");
+        let modify = EventKind::Modify(ModifyKind::Any);
+        assert!(!is_change(event(modify, "/protos/shop/order.proto~"), out));
+        assert!(!is_change(event(modify, "/protos/README.md"), out));
+        assert!(!is_change(
+            event(modify, "/protos/out/shop/order.proto"),
+            out
+        ));
+    }
+}